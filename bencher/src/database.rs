@@ -1,10 +1,17 @@
+use std::{env, fs, process};
+use std::path::{Path, PathBuf};
 use chain::IndexedBlock;
 use storage::{BlockProvider, BlockRef, BlockOrigin, ForkChain};
 use db::BlockChainDatabase;
+use db::BlockFiles;
+use db::kv::{CacheDatabase, AutoFlushingOverlayDatabase, DatabaseBackend, DatabaseEngine, DatabaseConfig, COL_COUNT,
+	DEFAULT_COIN_CACHE_SIZE};
 use test_data;
 
 use super::Benchmark;
 
+type DiskBlockChainDatabase = BlockChainDatabase<CacheDatabase<AutoFlushingOverlayDatabase<Box<DatabaseBackend>>>>;
+
 pub fn fetch(benchmark: &mut Benchmark) {
 	// params
 	const BLOCKS: usize = 1000;
@@ -250,3 +257,119 @@ pub fn write_heavy(benchmark: &mut Benchmark) {
 	}
 	benchmark.stop();
 }
+
+// 1. write 2000 blocks with 50 transactions each to the database on disk
+// 2. report total size of the database directory
+fn import_to_disk(benchmark: &mut Benchmark, store: DiskBlockChainDatabase, path: &Path) {
+	// params
+	const BLOCKS: usize = 2000;
+	const TRANSACTIONS: usize = 50;
+
+	benchmark.samples(BLOCKS);
+
+	// test setup
+	let genesis: IndexedBlock = test_data::genesis().into();
+	let mut rolling_hash = genesis.hash().clone();
+	let mut previous_coinbase = genesis.transactions[0].hash.clone();
+	let mut blocks: Vec<IndexedBlock> = Vec::new();
+
+	for x in 0..BLOCKS {
+		let mut coinbase = test_data::block_builder()
+			.transaction()
+				.coinbase()
+				.lock_time(x as u32);
+		for _ in 0..TRANSACTIONS {
+			coinbase = coinbase.output().value(1000).script_pubkey_with_sigops(1).build();
+		}
+		let mut builder = coinbase.build();
+
+		// every transaction spends single output of the previous block coinbase
+		for t in 0..TRANSACTIONS {
+			builder = builder.transaction()
+				.input().hash(previous_coinbase.clone()).index(t as u32).signature_with_size(107).build()
+				.output().value(900).script_pubkey_with_sigops(1).build()
+				.build();
+		}
+
+		let next_block = builder.merkled_header().parent(rolling_hash.clone()).nonce(x as u32).build().build();
+		rolling_hash = next_block.hash();
+		previous_coinbase = next_block.transactions()[0].hash();
+		blocks.push(next_block.into());
+	}
+
+	store.insert(genesis.clone()).unwrap();
+	store.canonize(genesis.hash()).unwrap();
+
+	// bench
+	benchmark.start();
+	for block in blocks {
+		let hash = block.hash().clone();
+		store.insert(block).expect("cannot insert bench block");
+		store.canonize(&hash).unwrap();
+	}
+	// pending changes are written to the disk when database is closed
+	drop(store);
+	benchmark.stop();
+
+	benchmark.disk_usage(directory_size(path));
+	fs::remove_dir_all(path).unwrap();
+}
+
+/// Import with transactions, stored in the database column
+pub fn import_transactions_column(benchmark: &mut Benchmark) {
+	let path = bench_path("transactions-column");
+	let db = DatabaseEngine::RocksDb.open(DatabaseConfig::with_columns(Some(COL_COUNT)), &path).unwrap();
+	import_to_disk(benchmark, BlockChainDatabase::open_with_cache(db).unwrap(), &path);
+}
+
+/// Import with blocks, stored in the flat block files
+pub fn import_block_files(benchmark: &mut Benchmark) {
+	let path = bench_path("block-files");
+	import_to_disk(benchmark, BlockChainDatabase::open_at_path(&path, 64, DatabaseEngine::RocksDb).unwrap(), &path);
+}
+
+/// Import with blocks, stored in the flat block files, and embedded log-structured database
+pub fn import_block_files_log(benchmark: &mut Benchmark) {
+	let path = bench_path("block-files-log");
+	import_to_disk(benchmark, BlockChainDatabase::open_at_path(&path, 64, DatabaseEngine::Log).unwrap(), &path);
+}
+
+/// Import with blocks, stored in the flat block files, and coins cache
+pub fn import_coin_cache(benchmark: &mut Benchmark) {
+	import_with_coin_cache_size(benchmark, "coin-cache", DEFAULT_COIN_CACHE_SIZE);
+}
+
+/// Import with blocks, stored in the flat block files, and without coins cache.
+/// Difference with `import_coin_cache` is the gain of the coins cache
+pub fn import_no_coin_cache(benchmark: &mut Benchmark) {
+	import_with_coin_cache_size(benchmark, "no-coin-cache", 0);
+}
+
+fn import_with_coin_cache_size(benchmark: &mut Benchmark, name: &str, coin_cache_size: usize) {
+	let path = bench_path(name);
+	let db = DatabaseEngine::RocksDb.open(DatabaseConfig::with_columns(Some(COL_COUNT)), &path).unwrap();
+	let db = CacheDatabase::with_coin_cache_size(AutoFlushingOverlayDatabase::new(db, 50), coin_cache_size);
+	let block_files = BlockFiles::open(path.join("blocks")).unwrap();
+	import_to_disk(benchmark, BlockChainDatabase::open_with_block_files(db, block_files).unwrap(), &path);
+}
+
+fn bench_path(name: &str) -> PathBuf {
+	let path = env::temp_dir().join(format!("bencher-{}-{}", name, process::id()));
+	let _ = fs::remove_dir_all(&path);
+	fs::create_dir_all(&path).unwrap();
+	path
+}
+
+fn directory_size(path: &Path) -> u64 {
+	fs::read_dir(path).unwrap()
+		.map(|entry| entry.unwrap())
+		.map(|entry| {
+			let metadata = entry.metadata().unwrap();
+			if metadata.is_dir() {
+				directory_size(&entry.path())
+			} else {
+				metadata.len()
+			}
+		})
+		.sum()
+}
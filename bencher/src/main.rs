@@ -20,6 +20,7 @@ pub struct Benchmark {
 	start: Option<PreciseTime>,
 	end: Option<PreciseTime>,
 	samples: Option<usize>,
+	disk_usage: Option<u64>,
 }
 
 impl Benchmark {
@@ -38,6 +39,10 @@ impl Benchmark {
 	pub fn samples(&mut self, samples: usize) {
 		self.samples = Some(samples);
 	}
+
+	pub fn disk_usage(&mut self, disk_usage: u64) {
+		self.disk_usage = Some(disk_usage);
+	}
 }
 
 fn decimal_mark(s: String) -> String {
@@ -62,6 +67,9 @@ fn run_benchmark<F>(name: &str, f: F) where F: FnOnce(&mut Benchmark) {
 	else {
 		println!("{} ns", decimal_mark(format!("{}", benchmark.evaluate().num_nanoseconds().unwrap())));
 	}
+	if let Some(disk_usage) = benchmark.disk_usage {
+		println!("{}: {} bytes on disk", name, decimal_mark(format!("{}", disk_usage)));
+	}
 }
 
 macro_rules! benchmark {
@@ -75,5 +83,10 @@ fn main() {
 	benchmark!(database::write);
 	benchmark!(database::reorg_short);
 	benchmark!(database::write_heavy);
+	benchmark!(database::import_transactions_column);
+	benchmark!(database::import_block_files);
+	benchmark!(database::import_block_files_log);
+	benchmark!(database::import_coin_cache);
+	benchmark!(database::import_no_coin_cache);
 	benchmark!(verifier::main);
 }
@@ -20,7 +20,7 @@ const WITNESS_MARKER: u8 = 0;
 const WITNESS_FLAG: u8 = 1;
 
 #[cfg_attr(feature = "std", derive(Debug))]
-#[derive(PartialEq, Eq, Clone, Default)]
+#[derive(PartialEq, Eq, Clone, Default, Hash)]
 pub struct OutPoint {
     pub hash: H256,
     pub index: u32,
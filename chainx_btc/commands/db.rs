@@ -0,0 +1,28 @@
+use clap::ArgMatches;
+use db;
+use db::kv::DatabaseEngine;
+use util::db_path;
+
+pub fn db(matches: &ArgMatches, db_matches: &ArgMatches) -> Result<(), String> {
+    match db_matches.subcommand() {
+        ("migrate", Some(migrate_matches)) => migrate(matches, migrate_matches),
+        _ => Err("Database command is required. See --help".into()),
+    }
+}
+
+fn migrate(matches: &ArgMatches, migrate_matches: &ArgMatches) -> Result<(), String> {
+    let engine: DatabaseEngine = migrate_matches
+        .value_of("BACKEND")
+        .expect("BACKEND is required in cli.yml; qed")
+        .parse()?;
+    let data_dir = matches.value_of("data-dir").map(str::to_owned);
+
+    let backup_path = db::migrate_database(db_path(&data_dir), engine)?;
+    info!(
+        "Database has been migrated to {} backend. Start the node with --db-backend {}. Backup of the original database is kept at {}",
+        engine,
+        engine,
+        backup_path.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use std::path::Path;
+use clap::ArgMatches;
+use storage::SnapshotBase;
+use config::Config;
+use util::init_db;
+
+pub fn loadtxoutset(cfg: Config, matches: &ArgMatches) -> Result<(), String> {
+    try!(init_db(&cfg));
+
+    let path = matches.value_of("PATH").expect(
+        "PATH is required in cli.yml; qed",
+    );
+
+    // only snapshots, committed to by the chain parameters, are accepted
+    let trusted: Vec<_> = cfg.consensus
+        .assume_utxo
+        .iter()
+        .map(|assume_utxo| SnapshotBase {
+            block_hash: assume_utxo.block_hash.clone(),
+            block_number: assume_utxo.block_number,
+            content_hash: assume_utxo.content_hash.clone(),
+        })
+        .collect();
+
+    let info = cfg.db
+        .load_snapshot(Path::new(path), &trusted)
+        .map_err(|e| format!("Failed to load UTXO set snapshot: {}", e))?;
+    info!(
+        "Loaded {} coins from UTXO set snapshot at block#{} {}. Blocks before the snapshot will be validated in background",
+        info.coins_count,
+        info.base.block_number,
+        info.base.block_hash.to_reversed_str()
+    );
+    Ok(())
+}
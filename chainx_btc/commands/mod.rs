@@ -2,8 +2,14 @@
 mod start;
 mod dev;
 mod rollback;
+mod loadtxoutset;
+mod db;
+mod verify_db;
 
 //pub use self::import::import;
 pub use self::start::start;
 pub use self::rollback::rollback;
+pub use self::loadtxoutset::loadtxoutset;
+pub use self::db::db;
+pub use self::verify_db::verify_db;
 pub use self::dev::dev;
@@ -1,19 +1,23 @@
 // Copyright 2018 Chainpool
 
+use std::fs;
 use std::net::SocketAddr;
 use std::thread;
 use std::sync::Arc;
 use std::sync::mpsc::{channel, Sender, Receiver};
 use std::sync::atomic::{AtomicBool, Ordering};
-use sync::{create_sync_peers, create_local_sync_node, create_sync_connection_factory, SyncListener};
+use sync::{create_sync_peers, create_local_sync_node, create_sync_connection_factory, SyncListener, ZmqPublisher};
 use primitives::hash::H256;
-use util::{init_db, node_table_path};
+use storage::SnapshotValidation;
+use util::{init_db, db_path, background_db_path, open_background_db, node_table_path, fee_estimates_path};
 use {config, p2p, PROTOCOL_VERSION, PROTOCOL_MINIMUM};
 use super::super::rpc;
 use tokio::timer::Interval;
 use tokio::prelude::Stream;
 use std::time::{Duration, Instant};
-use node::build_block;
+use node::{build_block, build_signet_block};
+use miner::CpuMiner;
+use stratum::{self, StratumCore};
 
 const TIMER_INTERVAL_MS: u64 = 60 * 1000;
 
@@ -101,6 +105,7 @@ pub fn start(cfg: config::Config) -> Result<(), String> {
     init_db(&cfg)?;
 
     let nodes_path = node_table_path(&cfg);
+    let fee_estimates_path = fee_estimates_path(&cfg);
 
     let p2p_cfg = p2p::Config {
         threads: cfg.p2p_threads,
@@ -119,17 +124,37 @@ pub fn start(cfg: config::Config) -> Result<(), String> {
         peers: cfg.connect.map_or_else(|| vec![], |x| vec![x]),
         seeds: cfg.seednodes,
         node_table_path: nodes_path,
-        preferable_services: cfg.services,
+        // even pruned node prefers to sync from full nodes
+        preferable_services: cfg.services.with_network(true).with_network_limited(false),
         internet_protocol: cfg.internet_protocol,
     };
 
     let sync_peers = create_sync_peers();
     let local_sync_node = create_local_sync_node(
-        cfg.consensus,
+        cfg.consensus.clone(),
         cfg.db.clone(),
         sync_peers.clone(),
-        cfg.verification_params,
+        cfg.verification_params.clone(),
+        Some(fee_estimates_path),
     );
+
+    // chainstate, seeded from UTXO set snapshot, is validated by the background chainstate
+    let background_db = match cfg.db.snapshot_validation() {
+        Some(SnapshotValidation::Pending) => {
+            let background_db = open_background_db(&cfg)?;
+            local_sync_node.start_background_validation(background_db.clone(), cfg.verification_params)?;
+            Some(background_db)
+        }
+        Some(SnapshotValidation::Invalid) => {
+            return Err("UTXO set snapshot has failed background validation. Database must be removed and synchronized again".into());
+        }
+        Some(SnapshotValidation::Valid) => {
+            // background chainstate is not needed anymore
+            let _ = fs::remove_dir_all(background_db_path(&cfg.data_dir));
+            None
+        }
+        None => None,
+    };
     let sync_connection_factory =
         create_sync_connection_factory(sync_peers.clone(), local_sync_node.clone());
 
@@ -137,18 +162,33 @@ pub fn start(cfg: config::Config) -> Result<(), String> {
         local_sync_node.install_sync_listener(Box::new(BlockNotifier::new(block_notify_command)));
     }
 
+    if !cfg.zmq.is_empty() {
+        let zmq_publisher = try!(ZmqPublisher::new(&cfg.zmq, cfg.db.clone()));
+        local_sync_node.install_sync_listener(Box::new(zmq_publisher.clone()));
+        local_sync_node.install_memory_pool_listener(Box::new(zmq_publisher));
+    }
+
     let p2p = try!(
         p2p::P2P::new(p2p_cfg, sync_connection_factory, el.handle())
             .map_err(|x| x.to_string())
     );
+    let cpu_miner = CpuMiner::new(cfg.miner_threads);
     let rpc_deps = rpc::Dependencies {
         network: cfg.network,
+        consensus: cfg.consensus.clone(),
+        database_path: db_path(&cfg.data_dir),
         storage: cfg.db,
+        background_storage: background_db,
         local_sync_node: local_sync_node.clone(),
         p2p_context: p2p.context().clone(),
         remote: el.remote(),
+        hash_rate_meter: cpu_miner.hash_rate_meter(),
     };
-    let _rpc_server = try!(rpc::new_http(cfg.rpc_config, rpc_deps));
+    let _rpc_servers = try!(rpc::new_servers(cfg.rpc_config, rpc_deps));
+
+    if let Some(stratum_config) = cfg.stratum {
+        try!(stratum::start(stratum_config, StratumCore::new(local_sync_node.clone())));
+    }
 
     let interval = Interval::new(
         Instant::now() + Duration::from_millis(TIMER_INTERVAL_MS),
@@ -163,21 +203,48 @@ pub fn start(cfg: config::Config) -> Result<(), String> {
             r.store(false, Ordering::SeqCst);
             Ok(())
         });
-    let child = thread::spawn(move || loop {
-        if let Some(block) = build_block(local_sync_node.get_block_template(), running.clone()) {
-            local_sync_node.spawn_block(block);
-        } else {
-            info!("build block failed")
-        }
-        running.store(true, Ordering::SeqCst);
-        trace!("store true");
-    });
+    // blocks are only mined when asked: on regtest to the --gen-address and on signet with the --signet-miner-key
+    let signet_challenge = cfg.consensus.signet_challenge.clone();
+    let signet_miner_key = cfg.signet_miner_key;
+    let gen_script_pubkey = cfg.gen_script_pubkey;
+    let child = if signet_miner_key.is_none() && gen_script_pubkey.is_none() {
+        None
+    } else {
+        Some(thread::spawn(move || loop {
+            let block_template = local_sync_node.get_block_template();
+            let block = match (signet_challenge.as_ref(), signet_miner_key.as_ref(), gen_script_pubkey.as_ref()) {
+                (Some(challenge), Some(private_key), _) => build_signet_block(
+                    &cpu_miner,
+                    block_template,
+                    challenge.clone(),
+                    private_key.clone(),
+                    running.clone(),
+                ),
+                (_, _, Some(script_pubkey)) => {
+                    build_block(&cpu_miner, block_template, script_pubkey.clone(), running.clone())
+                }
+                _ => unreachable!("mining thread is only started when there's signet key or gen address"),
+            };
+            if let Some(block) = block {
+                let block_hash = block.hash().clone();
+                if let Err(err) = local_sync_node.spawn_block(block) {
+                    warn!("mined block {} is rejected: {}", block_hash.to_reversed_str(), err);
+                }
+            } else {
+                info!("build block failed")
+            }
+            running.store(true, Ordering::SeqCst);
+            trace!("store true");
+        }))
+    };
     el.handle().spawn(work);
 
     try!(p2p.run().map_err(|_| "Failed to start p2p module"));
     el.run(p2p::forever()).unwrap();
-    child.join().expect(
-        "Couldn't join on the associated thread",
-    );
+    if let Some(child) = child {
+        child.join().expect(
+            "Couldn't join on the associated thread",
+        );
+    }
     Ok(())
 }
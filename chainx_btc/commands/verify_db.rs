@@ -0,0 +1,65 @@
+use clap::ArgMatches;
+use config::Config;
+use util::init_db;
+use verification::{DatabaseVerifier, DEFAULT_CHECK_LEVEL, DEFAULT_CHECK_BLOCKS};
+
+pub fn verify_db(cfg: Config, matches: &ArgMatches) -> Result<(), String> {
+    try!(init_db(&cfg));
+
+    let check_level = match matches.value_of("check-level") {
+        Some(check_level) => check_level.parse().map_err(
+            |e| format!("Invalid check level: {}", e),
+        )?,
+        None => DEFAULT_CHECK_LEVEL,
+    };
+    let blocks = match matches.value_of("blocks") {
+        Some(blocks) => blocks.parse().map_err(
+            |e| format!("Invalid number of blocks: {}", e),
+        )?,
+        None => DEFAULT_CHECK_BLOCKS,
+    };
+
+    let verifier = DatabaseVerifier::new(cfg.db.clone(), cfg.consensus.clone());
+    let report = verifier.verify(check_level, blocks);
+    for issue in &report.issues {
+        warn!("Block {}: {}", issue.block_number, issue.error);
+    }
+
+    let first_invalid_block = match report.first_invalid_block() {
+        Some(first_invalid_block) => first_invalid_block,
+        None => {
+            info!(
+                "Database is consistent. Blocks {}..{} have passed checks of level {}",
+                report.first_block,
+                report.last_block,
+                report.check_level
+            );
+            return Ok(());
+        }
+    };
+
+    if first_invalid_block == 0 {
+        return Err(format!(
+            "Found {} inconsistencies, including the genesis block. The database can't be repaired and should be removed",
+            report.issues.len()
+        ));
+    }
+
+    if !matches.is_present("repair") {
+        return Err(format!(
+            "Found {} inconsistencies, starting at block {}. Use --repair to roll the database back to block {}",
+            report.issues.len(),
+            first_invalid_block,
+            first_invalid_block - 1
+        ));
+    }
+
+    while cfg.db.best_block().number >= first_invalid_block {
+        cfg.db.rollback_best().map_err(|e| {
+            format!("Failed to roll back block {}: {}", cfg.db.best_block().number, e)
+        })?;
+    }
+
+    info!("Database has been rolled back to block {}", first_invalid_block - 1);
+    Ok(())
+}
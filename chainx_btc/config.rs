@@ -1,18 +1,26 @@
 use std::net;
+use std::path::Path;
 use clap;
 use storage;
+use db;
 use message::Services;
-use network::{Network, ConsensusParams, ConsensusFork, BitcoinCashConsensusParams};
+use network::{Network, ConsensusParams, ConsensusFork, BitcoinCashConsensusParams, ChainSpec, AddressPrefixes,
+              default_signet_challenge};
 use p2p::InternetProtocol;
-use seednodes::{mainnet_seednodes, testnet_seednodes, bitcoin_cash_seednodes,
+use seednodes::{mainnet_seednodes, testnet_seednodes, signet_seednodes, bitcoin_cash_seednodes,
                 bitcoin_cash_testnet_seednodes};
 use rpc_apis::ApiSet;
 use {USER_AGENT, REGTEST_USER_AGENT};
+use primitives::bytes::Bytes;
 use primitives::hash::H256;
 use rpc::HttpConfiguration as RpcHttpConfig;
+use core_rpc::RpcAuthUser;
 use verification::VerificationLevel;
-use sync::VerificationParameters;
-use util::open_db;
+use sync::{VerificationParameters, ZmqConfig, ZmqTopic};
+use util::{open_db, cookie_path};
+use keys::{self, Address};
+use script::Builder as ScriptBuilder;
+use stratum::{StratumConfig, VarDiffConfig};
 
 pub struct Config {
     pub network: Network,
@@ -27,16 +35,25 @@ pub struct Config {
     pub outbound_connections: u32,
     pub p2p_threads: usize,
     pub db_cache: usize,
+    pub db_engine: db::kv::DatabaseEngine,
     pub data_dir: Option<String>,
     pub user_agent: String,
     pub internet_protocol: InternetProtocol,
     pub rpc_config: RpcHttpConfig,
     pub block_notify_command: Option<String>,
+    pub zmq: ZmqConfig,
+    pub miner_threads: usize,
+    pub signet_miner_key: Option<keys::Private>,
+    pub gen_script_pubkey: Option<Bytes>,
+    pub stratum: Option<StratumConfig>,
     pub verification_params: VerificationParameters,
     pub db: storage::SharedStore,
 }
 
 pub const DEFAULT_DB_CACHE: usize = 512;
+pub const DEFAULT_MINER_THREADS: usize = 1;
+/// Minimal size (in MiB) of blocks, kept by pruned node
+pub const MIN_PRUNE_TARGET: u32 = 550;
 
 pub fn parse(matches: &clap::ArgMatches) -> Result<Config, String> {
     let db_cache = match matches.value_of("db-cache") {
@@ -53,21 +70,64 @@ pub fn parse(matches: &clap::ArgMatches) -> Result<Config, String> {
         None => None,
     };
 
-    let db = open_db(&data_dir, db_cache);
+    let db_engine = match matches.value_of("db-backend") {
+        Some(s) => s.parse()?,
+        None => db::kv::DatabaseEngine::default(),
+    };
+
+    let prune_mode = parse_prune_mode(matches)?;
+    let spent_index = matches.is_present("spentindex");
+    let db = open_db(&data_dir, db_cache, db_engine, prune_mode, spent_index)?;
+    if !prune_mode.is_enabled() && db.pruned_height().is_some() {
+        return Err("Database has been pruned. Remove the database to switch back to unpruned mode, or use --prune".into());
+    }
 
     let quiet = matches.is_present("quiet");
-    let network = match (matches.is_present("testnet"), matches.is_present("regtest")) {
-        (true, false) => Network::Testnet,
-        (false, true) => Network::Regtest,
-        (false, false) => Network::Mainnet,
-        (true, true) => return Err("Only one testnet option can be used".into()),
+    let chain_spec = match matches.value_of("chain-spec") {
+        Some(path) => Some(ChainSpec::load(Path::new(path))?),
+        None => None,
+    };
+    let network = match (matches.is_present("testnet"), matches.is_present("regtest"), matches.is_present("signet"), &chain_spec) {
+        (false, false, false, &Some(ref chain_spec)) => chain_spec.network(),
+        (_, _, _, &Some(_)) => return Err("--chain-spec can not be combined with other network options".into()),
+        (true, false, false, &None) => Network::Testnet,
+        (false, true, false, &None) => Network::Regtest,
+        (false, false, true, &None) => Network::Signet,
+        (false, false, false, &None) => Network::Mainnet,
+        _ => return Err("Only one testnet option can be used".into()),
     };
 
     let consensus_fork = parse_consensus_fork(network, &db, &matches)?;
-    let consensus = ConsensusParams::new(network, consensus_fork);
+    let consensus = match chain_spec {
+        Some(ref chain_spec) => chain_spec.consensus_params(consensus_fork)?,
+        None => ConsensusParams::new(network, consensus_fork),
+    };
+    let consensus = match matches.value_of("signetchallenge") {
+        Some(_) if network != Network::Signet => return Err("--signetchallenge requires --signet".into()),
+        Some(s) => {
+            let challenge: Bytes = s.parse().map_err(|_| "Invalid signet challenge".to_owned())?;
+            consensus.with_signet_challenge(challenge)
+        }
+        None => consensus,
+    };
+    let is_default_signet = consensus.signet_challenge == Some(default_signet_challenge());
+
+    let signet_miner_key = match matches.value_of("signet-miner-key") {
+        Some(_) if network != Network::Signet => return Err("--signet-miner-key requires --signet".into()),
+        Some(s) => Some(s.parse().map_err(|_| "Invalid signet miner key".to_owned())?),
+        None => None,
+    };
+
+    // there's no real proof of work on regtest => continuous mining is only allowed there
+    let gen_script_pubkey = match matches.value_of("gen-address") {
+        Some(_) if network != Network::Regtest => return Err("--gen-address requires --regtest".into()),
+        Some(s) => Some(parse_address_script_pubkey(consensus.address_prefixes, s, "gen")?),
+        None => None,
+    };
 
     let (in_connections, out_connections) = match network {
         Network::Testnet |
+        Network::Signet |
         Network::Mainnet |
         Network::Other(_) => (10, 10),
         Network::Regtest | Network::Unitest => (1, 0),
@@ -75,6 +135,7 @@ pub fn parse(matches: &clap::ArgMatches) -> Result<Config, String> {
 
     let p2p_threads = match network {
         Network::Testnet |
+        Network::Signet |
         Network::Mainnet |
         Network::Other(_) => 4,
         Network::Regtest | Network::Unitest => 1,
@@ -87,22 +148,28 @@ pub fn parse(matches: &clap::ArgMatches) -> Result<Config, String> {
     };
     let user_agent = match network {
         Network::Testnet |
+        Network::Signet |
         Network::Mainnet |
         Network::Unitest |
         Network::Other(_) => format!("{}{}", USER_AGENT, user_agent_suffix),
         Network::Regtest => REGTEST_USER_AGENT.into(),
     };
 
+    let (default_port, default_rpc_port) = match chain_spec {
+        Some(ref chain_spec) => (chain_spec.port, chain_spec.rpc_port),
+        None => (network.port(), network.rpc_port()),
+    };
+
     let port = match matches.value_of("port") {
         Some(port) => port.parse().map_err(|_| "Invalid port".to_owned())?,
-        None => network.port(),
+        None => default_port,
     };
 
     let connect = match matches.value_of("connect") {
         Some(s) => Some(match s.parse::<net::SocketAddr>() {
             Err(_) => {
                 s.parse::<net::IpAddr>()
-                    .map(|ip| net::SocketAddr::new(ip, network.port()))
+                    .map(|ip| net::SocketAddr::new(ip, default_port))
                     .map_err(|_| "Invalid connect".to_owned())
             }
             Ok(a) => Ok(a),
@@ -128,7 +195,11 @@ pub fn parse(matches: &clap::ArgMatches) -> Result<Config, String> {
                 }
                 (Network::Mainnet, _) => mainnet_seednodes().into_iter().map(Into::into).collect(),
                 (Network::Testnet, _) => testnet_seednodes().into_iter().map(Into::into).collect(),
-                (Network::Other(_), _) |
+                (Network::Signet, _) if is_default_signet => signet_seednodes().into_iter().map(Into::into).collect(),
+                (Network::Other(_), _) => {
+                    chain_spec.as_ref().map(|chain_spec| chain_spec.seeds.clone()).unwrap_or_default()
+                }
+                (Network::Signet, _) |
                 (Network::Regtest, _) |
                 (Network::Unitest, _) => Vec::new(),
             }
@@ -154,7 +225,7 @@ pub fn parse(matches: &clap::ArgMatches) -> Result<Config, String> {
         }
     };
 
-    let rpc_config = parse_rpc_config(network, matches)?;
+    let rpc_config = parse_rpc_config(default_rpc_port, &data_dir, matches)?;
 
     let block_notify_command = match matches.value_of("blocknotify") {
         Some(s) => Some(s.parse().map_err(
@@ -163,7 +234,29 @@ pub fn parse(matches: &clap::ArgMatches) -> Result<Config, String> {
         None => None,
     };
 
-    let services = Services::default().with_network(true);
+    let mut zmq = ZmqConfig::default();
+    for topic in ZmqTopic::all().iter() {
+        if let Some(endpoints) = matches.values_of(&*format!("zmqpub{}", topic.name())) {
+            zmq.endpoints.extend(endpoints.map(|endpoint| (*topic, endpoint.to_owned())));
+        }
+    }
+
+    let miner_threads = match matches.value_of("miner-threads") {
+        Some(s) => {
+            match s.parse() {
+                Ok(threads) if threads > 0 => threads,
+                _ => return Err("Invalid miner threads - should be positive number".to_owned()),
+            }
+        }
+        None => DEFAULT_MINER_THREADS,
+    };
+
+    let stratum = parse_stratum_config(consensus.address_prefixes, host, matches)?;
+
+    // pruned nodes are only able to serve recent blocks (BIP159)
+    let services = Services::default()
+        .with_network(!prune_mode.is_enabled())
+        .with_network_limited(prune_mode.is_enabled());
     let services = match &consensus.fork {
         &ConsensusFork::BitcoinCash(_) => services.with_bitcoin_cash(true),
         &ConsensusFork::BitcoinCore => services.with_witness(true),
@@ -184,6 +277,7 @@ pub fn parse(matches: &clap::ArgMatches) -> Result<Config, String> {
             )?;
             edge.reversed()
         }
+        _ if chain_spec.is_some() => consensus.genesis_block.hash(),
         _ => network.default_verification_edge(),
     };
 
@@ -200,11 +294,17 @@ pub fn parse(matches: &clap::ArgMatches) -> Result<Config, String> {
         outbound_connections: out_connections,
         p2p_threads: p2p_threads,
         db_cache: db_cache,
+        db_engine: db_engine,
         data_dir: data_dir,
         user_agent: user_agent,
         internet_protocol: only_net,
         rpc_config: rpc_config,
         block_notify_command: block_notify_command,
+        zmq: zmq,
+        miner_threads: miner_threads,
+        signet_miner_key: signet_miner_key,
+        gen_script_pubkey: gen_script_pubkey,
+        stratum: stratum,
         verification_params: VerificationParameters {
             verification_level: verification_level,
             verification_edge: verification_edge,
@@ -215,6 +315,70 @@ pub fn parse(matches: &clap::ArgMatches) -> Result<Config, String> {
     Ok(config)
 }
 
+fn parse_prune_mode(matches: &clap::ArgMatches) -> Result<storage::PruneMode, String> {
+    let target: u32 = match matches.value_of("prune") {
+        Some(s) => s.parse().map_err(|_| "Invalid prune target - should be number in MiB".to_owned())?,
+        None => return Ok(storage::PruneMode::Disabled),
+    };
+
+    match target {
+        0 => Ok(storage::PruneMode::Disabled),
+        1 => Ok(storage::PruneMode::Manual),
+        target if target < MIN_PRUNE_TARGET => {
+            Err(format!("Prune target is too low - should be at least {} MiB", MIN_PRUNE_TARGET))
+        }
+        target => Ok(storage::PruneMode::Automatic {
+            target_size: target as u64 * 1024 * 1024,
+        }),
+    }
+}
+
+fn parse_stratum_config(
+    address_prefixes: AddressPrefixes,
+    host: net::IpAddr,
+    matches: &clap::ArgMatches,
+) -> Result<Option<StratumConfig>, String> {
+    let port: u16 = match matches.value_of("stratum-port") {
+        Some(s) => s.parse().map_err(|_| "Invalid stratum port".to_owned())?,
+        None => return Ok(None),
+    };
+
+    let coinbase_script_pubkey = match matches.value_of("stratum-address") {
+        Some(s) => parse_address_script_pubkey(address_prefixes, s, "stratum")?,
+        None => return Err("--stratum-address is required by --stratum-port".into()),
+    };
+
+    let mut vardiff = VarDiffConfig::default();
+    if let Some(s) = matches.value_of("stratum-difficulty") {
+        vardiff.initial_difficulty = match s.parse() {
+            Ok(difficulty) if difficulty > 0 => difficulty,
+            _ => return Err("Invalid stratum difficulty - should be positive number".to_owned()),
+        };
+    }
+
+    Ok(Some(StratumConfig {
+        address: net::SocketAddr::new(host, port),
+        coinbase_script_pubkey: coinbase_script_pubkey,
+        vardiff: vardiff,
+    }))
+}
+
+fn parse_address_script_pubkey(address_prefixes: AddressPrefixes, s: &str, name: &str) -> Result<Bytes, String> {
+    let address: Address = s.parse().map_err(|_| format!("Invalid --{}-address", name))?;
+    let address_network = match address_prefixes {
+        AddressPrefixes::Mainnet => keys::Network::Mainnet,
+        AddressPrefixes::Testnet => keys::Network::Testnet,
+    };
+    if address.network != address_network {
+        return Err(format!("--{}-address is not valid for current network", name));
+    }
+    let script_pubkey = match address.kind {
+        keys::Type::P2PKH => ScriptBuilder::build_p2pkh(&address.hash),
+        keys::Type::P2SH => ScriptBuilder::build_p2sh(&address.hash),
+    };
+    Ok(script_pubkey.into())
+}
+
 fn parse_consensus_fork(
     network: Network,
     db: &storage::SharedStore,
@@ -256,8 +420,8 @@ fn parse_consensus_fork(
     })
 }
 
-fn parse_rpc_config(network: Network, matches: &clap::ArgMatches) -> Result<RpcHttpConfig, String> {
-    let mut config = RpcHttpConfig::with_port(network.rpc_port());
+fn parse_rpc_config(default_port: u16, data_dir: &Option<String>, matches: &clap::ArgMatches) -> Result<RpcHttpConfig, String> {
+    let mut config = RpcHttpConfig::with_port(default_port);
     config.enabled = !matches.is_present("no-jsonrpc");
     if !config.enabled {
         return Ok(config);
@@ -276,6 +440,9 @@ fn parse_rpc_config(network: Network, matches: &clap::ArgMatches) -> Result<RpcH
     if let Some(interface) = matches.value_of("jsonrpc-interface") {
         config.interface = interface.to_owned();
     }
+    if let Some(ws_port) = matches.value_of("jsonrpc-ws-port") {
+        config.ws_port = Some(ws_port.parse().map_err(|_| "Invalid JSON RPC WebSocket port".to_owned())?);
+    }
     if let Some(cors) = matches.value_of("jsonrpc-cors") {
         config.cors = Some(vec![
             cors.parse().map_err(
@@ -291,5 +458,23 @@ fn parse_rpc_config(network: Network, matches: &clap::ArgMatches) -> Result<RpcH
         ]);
     }
 
+    config.auth.cookie_path = Some(cookie_path(data_dir));
+    if let Some(users) = matches.values_of("jsonrpc-auth") {
+        config.auth.users = users.map(|user| user.parse::<RpcAuthUser>()).collect::<Result<_, _>>()?;
+    }
+    if let Some(whitelists) = matches.values_of("jsonrpc-whitelist") {
+        for whitelist in whitelists {
+            let mut parts = whitelist.splitn(2, ':');
+            let (user, methods) = match (parts.next(), parts.next()) {
+                (Some(user), Some(methods)) if !user.is_empty() => (user, methods),
+                _ => return Err(format!("Invalid JSON RPC whitelist: {}", whitelist)),
+            };
+            config.auth.whitelists
+                .entry(user.to_owned())
+                .or_insert_with(Default::default)
+                .extend(methods.split(',').map(str::trim).filter(|method| !method.is_empty()).map(str::to_owned));
+        }
+    }
+
     Ok(config)
 }
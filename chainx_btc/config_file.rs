@@ -0,0 +1,296 @@
+//! bitcoin.conf-style configuration file.
+//!
+//! Every line is either `option=value`, `[section]` or a `#` comment. Option names are
+//! the long names of command line options; flags accept `1`/`0` (or `true`/`false`).
+//! Options from `[main]`, `[test]`, `[regtest]` and `[signet]` sections are only applied
+//! to the corresponding network. Options, passed on the command line, override the file.
+//! Options, which may be passed multiple times, may also be repeated in the file.
+//!
+//! This module is shared by the chainx_btc and pbtc binaries: options and the default file
+//! name (`<APP_INFO.name>.conf`) are taken from the binary, which includes it.
+
+use std::env;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use app_dirs::{get_app_root, AppDataType};
+use clap;
+use yaml_rust::Yaml;
+use APP_INFO;
+
+/// Sections with network-specific options
+const NETWORK_SECTIONS: [&'static str; 4] = ["main", "test", "regtest", "signet"];
+
+/// Command line option
+struct CliOption {
+    takes_value: bool,
+    multiple: bool,
+}
+
+/// Single `option=value` line of the configuration file
+struct Entry {
+    line: usize,
+    section: Option<String>,
+    key: String,
+    value: String,
+}
+
+/// Returns command line matches, merged with options from the configuration file.
+/// Configuration file is read from `--conf` path, or from the data directory (if exists).
+pub fn merge<'a>(yaml: &'a Yaml, matches: clap::ArgMatches<'a>) -> Result<clap::ArgMatches<'a>, String> {
+    let path = match matches.value_of("conf") {
+        Some(path) => PathBuf::from(path),
+        None => match default_path(matches.value_of("data-dir")) {
+            Some(ref path) if path.exists() => path.clone(),
+            _ => return Ok(matches),
+        },
+    };
+
+    let entries = read(&path)?;
+    merge_entries(yaml, &matches, &path, entries, env::args_os().collect())
+}
+
+/// Returns matches of the `cli_args` command line, merged with the configuration file entries
+fn merge_entries<'a>(
+    yaml: &'a Yaml,
+    matches: &clap::ArgMatches,
+    path: &Path,
+    entries: Vec<Entry>,
+    cli_args: Vec<OsString>,
+) -> Result<clap::ArgMatches<'a>, String> {
+    let options = options(yaml);
+    let section = network_section(matches, &entries);
+
+    let mut args: Vec<OsString> = cli_args.iter().take(1).cloned().collect();
+    let mut file_options = HashMap::new();
+    for entry in entries {
+        match entry.section {
+            Some(ref entry_section) if !NETWORK_SECTIONS.contains(&entry_section.as_str()) => {
+                return Err(format!("{}:{}: unknown section [{}]", path.display(), entry.line, entry_section));
+            }
+            _ => (),
+        }
+
+        let option = match options.get(&entry.key) {
+            Some(_) if entry.key == "conf" || entry.key == "data-dir" => {
+                return Err(format!("{}:{}: option '{}' can only be set on command line", path.display(), entry.line, entry.key));
+            }
+            Some(option) => option,
+            None => return Err(format!("{}:{}: unknown option '{}'", path.display(), entry.line, entry.key)),
+        };
+
+        // options of other networks are ignored; network options override top-level options
+        let is_network_entry = entry.section.is_some();
+        if is_network_entry && entry.section.as_ref().map(String::as_str) != section {
+            continue;
+        }
+        if !option.takes_value {
+            parse_flag(&entry.value).ok_or_else(|| format!("{}:{}: invalid value of flag '{}'", path.display(), entry.line, entry.key))?;
+        }
+        let values = file_options.entry(entry.key.clone()).or_insert_with(|| (is_network_entry, Vec::new()));
+        match (values.0, is_network_entry) {
+            (true, false) => continue,
+            (false, true) => *values = (true, Vec::new()),
+            _ if !option.multiple => values.1.clear(),
+            _ => (),
+        }
+        values.1.push(entry.value);
+    }
+
+    for (key, (_, values)) in file_options {
+        // command line overrides configuration file
+        if matches.occurrences_of(key.as_str()) != 0 {
+            continue;
+        }
+
+        for value in values {
+            if options[&key].takes_value {
+                args.push(format!("--{}", key).into());
+                args.push(value.into());
+            } else if parse_flag(&value) == Some(true) {
+                args.push(format!("--{}", key).into());
+            }
+        }
+    }
+
+    args.extend(cli_args.into_iter().skip(1));
+    clap::App::from_yaml(yaml).get_matches_from_safe(args).map_err(|err| err.to_string())
+}
+
+/// Default path of the configuration file
+fn default_path(data_dir: Option<&str>) -> Option<PathBuf> {
+    let mut path = match data_dir {
+        Some(data_dir) => PathBuf::from(data_dir),
+        None => get_app_root(AppDataType::UserData, &APP_INFO).ok()?,
+    };
+    path.push(format!("{}.conf", APP_INFO.name));
+    Some(path)
+}
+
+/// Returns all command line options by their long names
+fn options(yaml: &Yaml) -> HashMap<String, CliOption> {
+    yaml["args"].as_vec()
+        .map(|args| args.iter()
+            .filter_map(Yaml::as_hash)
+            .flat_map(|arg| arg.values())
+            .filter_map(|settings| settings["long"].as_str().map(|long| (long.to_owned(), CliOption {
+                takes_value: settings["takes_value"].as_bool().unwrap_or(false),
+                multiple: settings["multiple"].as_bool().unwrap_or(false),
+            })))
+            .collect())
+        .unwrap_or_default()
+}
+
+/// Section with options of the network, selected either on command line or at the top of the file
+fn network_section(matches: &clap::ArgMatches, entries: &[Entry]) -> Option<&'static str> {
+    let is_selected = |key: &str| matches.is_present(key) || entries.iter()
+        .any(|entry| entry.section.is_none() && entry.key == key && parse_flag(&entry.value) == Some(true));
+    if is_selected("chain-spec") {
+        return None;
+    }
+
+    match (is_selected("testnet"), is_selected("regtest"), is_selected("signet")) {
+        (true, false, false) => Some("test"),
+        (false, true, false) => Some("regtest"),
+        (false, false, true) => Some("signet"),
+        _ => Some("main"),
+    }
+}
+
+fn parse_flag(value: &str) -> Option<bool> {
+    match value {
+        "1" | "true" => Some(true),
+        "0" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<Entry>, String> {
+    let mut contents = String::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut contents))
+        .map_err(|err| format!("Failed to read configuration file {}: {}", path.display(), err))?;
+    parse(path, &contents)
+}
+
+fn parse(path: &Path, contents: &str) -> Result<Vec<Entry>, String> {
+    let mut section = None;
+    let mut entries = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = match line.find('#') {
+            Some(comment) => &line[..comment],
+            None => line,
+        }.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('[') && line.ends_with(']') {
+            section = Some(line[1..line.len() - 1].trim().to_owned());
+            continue;
+        }
+
+        let mut parts = line.splitn(2, '=');
+        let key = parts.next().expect("splitn always yields at least one item; qed").trim();
+        let value = parts.next().map(str::trim).unwrap_or("1");
+        if key.is_empty() {
+            return Err(format!("{}:{}: option name is missing", path.display(), index + 1));
+        }
+
+        entries.push(Entry {
+            line: index + 1,
+            section: section.clone(),
+            key: key.to_owned(),
+            value: value.to_owned(),
+        });
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+    use std::path::Path;
+    use clap;
+    use super::{merge_entries, parse};
+
+    fn merge_with(contents: &str, cli_args: &[&str]) -> Result<(Option<String>, Vec<String>), String> {
+        let yaml = load_yaml!("cli.yml");
+        let cli_args: Vec<OsString> = Some(&"chainx_btc").into_iter().chain(cli_args).map(Into::into).collect();
+        let matches = clap::App::from_yaml(yaml).get_matches_from_safe(cli_args.clone()).unwrap();
+        let path = Path::new("chainx_btc.conf");
+        let entries = parse(path, contents)?;
+        let matches = merge_entries(yaml, &matches, path, entries, cli_args)?;
+        let port = matches.value_of("port").map(ToOwned::to_owned);
+        let zmq_addresses = matches.values_of("zmqpubhashblock")
+            .map(|values| values.map(ToOwned::to_owned).collect())
+            .unwrap_or_default();
+        Ok((port, zmq_addresses))
+    }
+
+    fn port(contents: &str, cli_args: &[&str]) -> Option<String> {
+        merge_with(contents, cli_args).unwrap().0
+    }
+
+    #[test]
+    fn command_line_overrides_configuration_file() {
+        let contents = "port=1\n[test]\nport=2\n";
+        assert_eq!(port(contents, &["--port", "3"]), Some("3".to_owned()));
+        assert_eq!(port(contents, &["--testnet", "--port", "3"]), Some("3".to_owned()));
+    }
+
+    #[test]
+    fn network_section_overrides_global_section() {
+        let contents = "port=1\n[test]\nport=2\n[main]\nport=4\n";
+        assert_eq!(port(contents, &["--testnet"]), Some("2".to_owned()));
+        assert_eq!(port(contents, &[]), Some("4".to_owned()));
+        assert_eq!(port(contents, &["--regtest"]), Some("1".to_owned()));
+    }
+
+    #[test]
+    fn defaults_are_used_when_option_is_not_set() {
+        assert_eq!(port("", &[]), None);
+        assert_eq!(port("[test]\nport=2\n", &[]), None);
+        assert_eq!(port("[regtest]\nport=2\n", &["--testnet"]), None);
+    }
+
+    #[test]
+    fn network_is_selected_by_configuration_file() {
+        assert_eq!(port("testnet=1\n[test]\nport=2\n", &[]), Some("2".to_owned()));
+        assert_eq!(port("regtest=1\n[test]\nport=2\n[regtest]\nport=3\n", &[]), Some("3".to_owned()));
+        assert_eq!(port("testnet=0\n[test]\nport=2\n", &[]), None);
+    }
+
+    #[test]
+    fn repeated_options_are_merged() {
+        let contents = "zmqpubhashblock=tcp://127.0.0.1:1\nzmqpubhashblock=tcp://127.0.0.1:2\n[test]\nzmqpubhashblock=tcp://127.0.0.1:3\n";
+        assert_eq!(
+            merge_with(contents, &[]).unwrap().1,
+            vec!["tcp://127.0.0.1:1".to_owned(), "tcp://127.0.0.1:2".to_owned()]
+        );
+        assert_eq!(merge_with(contents, &["--testnet"]).unwrap().1, vec!["tcp://127.0.0.1:3".to_owned()]);
+    }
+
+    #[test]
+    fn unknown_options_and_sections_are_rejected() {
+        assert_eq!(
+            merge_with("unknown=1\n", &[]),
+            Err("chainx_btc.conf:1: unknown option 'unknown'".to_owned())
+        );
+        assert_eq!(
+            merge_with("port=1\n[unknown]\nport=2\n", &[]),
+            Err("chainx_btc.conf:3: unknown section [unknown]".to_owned())
+        );
+        assert_eq!(
+            merge_with("data-dir=/tmp\n", &[]),
+            Err("chainx_btc.conf:1: option 'data-dir' can only be set on command line".to_owned())
+        );
+        assert_eq!(
+            merge_with("[test]\nquiet=maybe\n", &["--testnet"]),
+            Err("chainx_btc.conf:2: invalid value of flag 'quiet'".to_owned())
+        );
+    }
+}
@@ -26,11 +26,14 @@ extern crate rpc as core_rpc;
 extern crate primitives;
 extern crate verification;
 extern crate node;
+extern crate stratum;
 extern crate tokio;
 extern crate jsonrpc_http_server;
+extern crate yaml_rust;
 
 mod commands;
 mod config;
+mod config_file;
 mod seednodes;
 mod util;
 mod rpc;
@@ -60,9 +63,9 @@ fn main() {
 fn run() -> Result<(), String> {
     let yaml = load_yaml!("cli.yml");
     let matches = clap::App::from_yaml(yaml).get_matches();
-    let cfg = try!(config::parse(&matches));
+    let matches = try!(config_file::merge(yaml, matches));
 
-    if !cfg.quiet {
+    if !matches.is_present("quiet") {
         if cfg!(windows) {
             logs::init(LOG_INFO, logs::DateLogFormatter);
         } else {
@@ -72,9 +75,18 @@ fn run() -> Result<(), String> {
         env_logger::init();
     }
 
+    // database maintenance commands must run before the database is opened
+    if let ("db", Some(db_matches)) = matches.subcommand() {
+        return commands::db(&matches, db_matches);
+    }
+
+    let cfg = try!(config::parse(&matches));
+
     match matches.subcommand() {
         //("import", Some(import_matches)) => commands::import(cfg, import_matches),
         ("rollback", Some(rollback_matches)) => commands::rollback(cfg, rollback_matches),
+        ("loadtxoutset", Some(loadtxoutset_matches)) => commands::loadtxoutset(cfg, loadtxoutset_matches),
+        ("verify-db", Some(verify_db_matches)) => commands::verify_db(cfg, verify_db_matches),
         ("dev", _) => commands::dev(cfg),
         _ => commands::start(cfg),
     }
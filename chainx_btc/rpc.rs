@@ -1,19 +1,29 @@
+use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use rpc_apis::{self, ApiSet};
-use core_rpc::{Server, start_http, MetaIoHandler, Compatibility, Remote};
-use network::Network;
+use core_rpc::{Server, WsServer, start_http, start_ws, Remote, RpcAuth, WsAuth, RpcAuthConfig, MetaIoHandler, PubSubHandler,
+    RemoteProcedure, MethodWhitelist, Session};
+use core_rpc::v1::{PubSub, PubSubClient, PubSubNotifier, Subscriptions};
+use std::path::PathBuf;
+use network::{Network, ConsensusParams};
 use std::io;
 use sync;
 use storage;
 use p2p;
+use miner;
 
 pub struct Dependencies {
     pub network: Network,
+    pub consensus: ConsensusParams,
+    pub database_path: PathBuf,
     pub local_sync_node: sync::LocalNodeRef,
     pub storage: storage::SharedStore,
+    /// Background chainstate, which validates UTXO set snapshot of the `storage`
+    pub background_storage: Option<storage::SharedStore>,
     pub p2p_context: Arc<p2p::Context>,
     pub remote: Remote,
+    pub hash_rate_meter: Arc<miner::HashRateMeter>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -24,6 +34,15 @@ pub struct HttpConfiguration {
     pub apis: ApiSet,
     pub cors: Option<Vec<String>>,
     pub hosts: Option<Vec<String>>,
+    pub auth: RpcAuthConfig,
+    /// Port of the WebSocket (pub/sub) server. WebSocket server is disabled if None
+    pub ws_port: Option<u16>,
+}
+
+/// Running JSON-RPC servers
+pub struct Servers {
+    pub http: Server,
+    pub ws: Option<WsServer>,
 }
 
 impl HttpConfiguration {
@@ -35,11 +54,13 @@ impl HttpConfiguration {
             apis: ApiSet::default(),
             cors: None,
             hosts: Some(Vec::new()),
+            auth: RpcAuthConfig::default(),
+            ws_port: None,
         }
     }
 }
 
-pub fn new_http(conf: HttpConfiguration, deps: Dependencies) -> Result<Option<Server>, String> {
+pub fn new_servers(conf: HttpConfiguration, deps: Dependencies) -> Result<Option<Servers>, String> {
     if !conf.enabled {
         return Ok(None);
     }
@@ -48,13 +69,28 @@ pub fn new_http(conf: HttpConfiguration, deps: Dependencies) -> Result<Option<Se
     let addr = try!(url.parse().map_err(|_| {
         format!("Invalid JSONRPC listen host/port given: {}", url)
     }));
-    Ok(Some(try!(setup_http_rpc_server(
+    // both servers share the cookie
+    let auth = try!(RpcAuth::new(&conf.auth));
+    let ws = match conf.ws_port {
+        Some(ws_port) => {
+            let ws_url = format!("{}:{}", conf.interface, ws_port);
+            let ws_addr = try!(ws_url.parse().map_err(|_| {
+                format!("Invalid WebSocket JSONRPC listen host/port given: {}", ws_url)
+            }));
+            Some(try!(setup_ws_rpc_server(&ws_addr, conf.hosts.clone(), auth.clone(), &conf.auth.whitelists, &deps)))
+        }
+        None => None,
+    };
+    let http = try!(setup_http_rpc_server(
         &addr,
         conf.cors,
         conf.hosts,
         conf.apis,
+        auth,
+        &conf.auth.whitelists,
         deps,
-    ))))
+    ));
+    Ok(Some(Servers { http: http, ws: ws }))
 }
 
 pub fn setup_http_rpc_server(
@@ -62,10 +98,12 @@ pub fn setup_http_rpc_server(
     cors_domains: Option<Vec<String>>,
     allowed_hosts: Option<Vec<String>>,
     apis: ApiSet,
+    auth: RpcAuth,
+    whitelists: &HashMap<String, HashSet<String>>,
     deps: Dependencies,
 ) -> Result<Server, String> {
-    let server = setup_rpc_server(apis, deps);
-    let start_result = start_http(url, cors_domains, allowed_hosts, server);
+    let server = rpc_apis::setup_rpc(apis, deps, whitelists);
+    let start_result = start_http(url, cors_domains, allowed_hosts, auth, server);
     match start_result {
         Err(ref err) if err.kind() == io::ErrorKind::AddrInUse => {
             Err(format!(
@@ -78,10 +116,78 @@ pub fn setup_http_rpc_server(
     }
 }
 
-fn setup_rpc_server(apis: ApiSet, deps: Dependencies) -> MetaIoHandler<()> {
-    rpc_apis::setup_rpc(
-        MetaIoHandler::with_compatibility(Compatibility::Both),
-        apis,
-        deps,
-    )
+pub fn setup_ws_rpc_server(
+    url: &SocketAddr,
+    allowed_hosts: Option<Vec<String>>,
+    auth: RpcAuth,
+    whitelists: &HashMap<String, HashSet<String>>,
+    deps: &Dependencies,
+) -> Result<WsServer, String> {
+    let subscriptions = Arc::new(Subscriptions::default());
+    deps.local_sync_node.install_sync_listener(Box::new(
+        PubSubNotifier::new(deps.storage.clone(), subscriptions.clone()),
+    ));
+
+    let (handler, auth) = setup_ws_rpc(PubSubClient::new(subscriptions).to_delegate().into(), auth, whitelists);
+    start_ws(url, allowed_hosts, auth, handler).map_err(|e| {
+        format!(
+            "WebSocket RPC error: {:?}. Make sure that {} is not in use or change the address using the --jsonrpc-ws-port and --jsonrpc-interface options.",
+            e,
+            url
+        )
+    })
+}
+
+/// Build WebSocket handler and the handshake middleware, which rejects users that may not call `methods`.
+/// Session metadata has no user => whitelists are checked once, on handshake.
+/// Whitelists may refer to all WebSocket methods by the `pubsub` api name.
+fn setup_ws_rpc(
+    methods: HashMap<String, RemoteProcedure<Arc<Session>>>,
+    auth: RpcAuth,
+    whitelists: &HashMap<String, HashSet<String>>,
+) -> (PubSubHandler<Arc<Session>>, WsAuth) {
+    let method_names: Vec<String> = methods.keys().cloned().collect();
+    let mut api_methods = HashMap::new();
+    api_methods.insert("pubsub".to_owned(), method_names.clone());
+    let auth = WsAuth::new(auth, MethodWhitelist::new(whitelists, &api_methods), method_names);
+
+    let mut handler = PubSubHandler::new(MetaIoHandler::default());
+    handler.extend_with(methods);
+    (handler, auth)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use std::sync::Arc;
+    use core_rpc::{RpcAuth, RpcAuthConfig, AuthError};
+    use core_rpc::v1::{PubSub, PubSubClient, Subscriptions};
+    use super::setup_ws_rpc;
+
+    #[test]
+    fn ws_rpc_rejects_users_not_whitelisted_for_pubsub() {
+        // password of every user is `secret`
+        let hash = "cb77f0957de88ff388cf817ddbc7273$c9ce7cb2de2ad5aadae1449ad1e62baa38d98fced30a7cd2eae656cab574b678";
+        let whitelist = |entries: &[&str]| entries.iter().map(|entry| entry.to_string()).collect::<HashSet<_>>();
+        let mut whitelists = HashMap::new();
+        whitelists.insert("alice".to_owned(), whitelist(&["blockchain"]));
+        whitelists.insert("bob".to_owned(), whitelist(&["pubsub"]));
+        whitelists.insert("carol".to_owned(), whitelist(&["subscribe"]));
+        let auth = RpcAuth::new(&RpcAuthConfig {
+            users: ["alice", "bob", "carol", "dave"].iter()
+                .map(|user| format!("{}:{}", user, hash).parse().unwrap())
+                .collect(),
+            whitelists: whitelists.clone(),
+            ..Default::default()
+        }).unwrap();
+
+        let methods = PubSubClient::new(Arc::new(Subscriptions::default())).to_delegate().into();
+        let (_, auth) = setup_ws_rpc(methods, auth, &whitelists);
+
+        // alice:secret, bob:secret, carol:secret, dave:secret
+        assert_eq!(auth.authorize(Some("Basic YWxpY2U6c2VjcmV0")), Err(AuthError::Forbidden));
+        assert_eq!(auth.authorize(Some("Basic Ym9iOnNlY3JldA==")), Ok("bob".into()));
+        assert_eq!(auth.authorize(Some("Basic Y2Fyb2w6c2VjcmV0")), Err(AuthError::Forbidden));
+        assert_eq!(auth.authorize(Some("Basic ZGF2ZTpzZWNyZXQ=")), Ok("dave".into()));
+    }
 }
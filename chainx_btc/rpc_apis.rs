@@ -1,7 +1,7 @@
 use std::str::FromStr;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use rpc::Dependencies;
-use core_rpc::MetaIoHandler;
+use core_rpc::{MetaIoHandler, Compatibility, RemoteProcedure, RpcMetadata, MethodWhitelist};
 
 #[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum Api {
@@ -13,6 +13,10 @@ pub enum Api {
     BlockChain,
     /// Network
     Network,
+    /// Memory pool-related methods
+    MemoryPool,
+    /// Regtest blocks generation methods
+    Generate,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -23,7 +27,7 @@ pub enum ApiSet {
 impl Default for ApiSet {
     fn default() -> Self {
         ApiSet::List(
-            vec![Api::Raw, Api::Miner, Api::BlockChain, Api::Network]
+            vec![Api::Raw, Api::Miner, Api::BlockChain, Api::Network, Api::MemoryPool, Api::Generate]
                 .into_iter()
                 .collect(),
         )
@@ -39,11 +43,27 @@ impl FromStr for Api {
             "miner" => Ok(Api::Miner),
             "blockchain" => Ok(Api::BlockChain),
             "network" => Ok(Api::Network),
+            "mempool" => Ok(Api::MemoryPool),
+            "generate" => Ok(Api::Generate),
             api => Err(format!("Unknown api: {}", api)),
         }
     }
 }
 
+impl Api {
+    /// Name of the api, as accepted by `FromStr`
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Api::Raw => "raw",
+            Api::Miner => "miner",
+            Api::BlockChain => "blockchain",
+            Api::Network => "network",
+            Api::MemoryPool => "mempool",
+            Api::Generate => "generate",
+        }
+    }
+}
+
 impl ApiSet {
     pub fn list_apis(&self) -> HashSet<Api> {
         match *self {
@@ -53,42 +73,65 @@ impl ApiSet {
 }
 
 pub fn setup_rpc(
-    mut handler: MetaIoHandler<()>,
     apis: ApiSet,
     deps: Dependencies,
-) -> MetaIoHandler<()> {
+    whitelists: &HashMap<String, HashSet<String>>,
+) -> MetaIoHandler<RpcMetadata, MethodWhitelist> {
     use core_rpc::v1::*;
 
+    let mut methods = HashMap::new();
+    let mut api_methods = HashMap::new();
     for api in apis.list_apis() {
-        match api {
+        let delegate: HashMap<String, RemoteProcedure<RpcMetadata>> = match api {
             Api::Raw => {
-                handler.extend_with(
-                    RawClient::new(RawClientCore::new(deps.local_sync_node.clone()))
-                        .to_delegate(),
-                )
+                RawClient::new(RawClientCore::new(deps.local_sync_node.clone()))
+                    .to_delegate()
+                    .into()
             }
             Api::Miner => {
-                handler.extend_with(
-                    MinerClient::new(MinerClientCore::new(deps.local_sync_node.clone()))
-                        .to_delegate(),
-                )
+                MinerClient::new(MinerClientCore::new(
+                    deps.local_sync_node.clone(),
+                    deps.hash_rate_meter.clone(),
+                )).to_delegate()
+                    .into()
             }
             Api::BlockChain => {
-                handler.extend_with(
-                    BlockChainClient::new(BlockChainClientCore::new(
-                        deps.network,
-                        deps.storage.clone(),
-                    )).to_delegate(),
-                )
+                let core = BlockChainClientCore::new(
+                    deps.consensus.clone(),
+                    deps.storage.clone(),
+                    Some(deps.database_path.clone()),
+                ).with_local_sync_node(deps.local_sync_node.clone());
+                let core = match deps.background_storage {
+                    Some(ref background_storage) => core.with_background_storage(background_storage.clone()),
+                    None => core,
+                };
+                BlockChainClient::new(core).to_delegate().into()
             }
             Api::Network => {
-                handler.extend_with(
-                    NetworkClient::new(NetworkClientCore::new(deps.p2p_context.clone()))
-                        .to_delegate(),
-                )
+                NetworkClient::new(NetworkClientCore::new(deps.p2p_context.clone()))
+                    .to_delegate()
+                    .into()
             }
-        }
+            Api::MemoryPool => {
+                MemoryPoolClient::new(MemoryPoolClientCore::new(deps.local_sync_node.clone()))
+                    .to_delegate()
+                    .into()
+            }
+            Api::Generate => {
+                GenerateClient::new(GenerateClientCore::new(
+                    &deps.consensus,
+                    deps.local_sync_node.clone(),
+                )).to_delegate()
+                    .into()
+            }
+        };
+
+        // whitelists may refer to all methods of the api by its name
+        api_methods.insert(api.name().to_owned(), delegate.keys().cloned().collect());
+        methods.extend(delegate);
     }
 
+    let mut handler = MetaIoHandler::new(Compatibility::Both, MethodWhitelist::new(whitelists, &api_methods));
+    handler.extend_with(methods);
     handler
 }
@@ -47,3 +47,9 @@ pub fn bitcoin_cash_testnet_seednodes() -> Vec<&'static str> {
         "testnet-seeder.criptolayer.net:18333",
     ]
 }
+
+pub fn signet_seednodes() -> Vec<&'static str> {
+    vec![
+        "seed.signet.bitcoin.sprovoost.nl:38333",
+    ]
+}
@@ -1,20 +1,49 @@
 use std::sync::Arc;
 use std::path::PathBuf;
 use std::fs::create_dir_all;
-use app_dirs::{app_dir, AppDataType};
+use app_dirs::{app_dir, app_root, AppDataType};
 use {storage, APP_INFO};
 use db;
 use config::Config;
 use chain::IndexedBlock;
+use network::ConsensusParams;
 
-pub fn open_db(data_dir: &Option<String>, db_cache: usize) -> storage::SharedStore {
-    let db_path = match *data_dir {
+pub fn db_path(data_dir: &Option<String>) -> PathBuf {
+    match *data_dir {
         Some(ref data_dir) => custom_path(&data_dir, "db"),
         None => app_dir(AppDataType::UserData, &APP_INFO, "db").expect("Failed to get app dir"),
-    };
-    Arc::new(
-        db::BlockChainDatabase::open_at_path(db_path, db_cache).expect("Failed to open database"),
-    )
+    }
+}
+
+pub fn open_db(
+    data_dir: &Option<String>,
+    db_cache: usize,
+    db_engine: db::kv::DatabaseEngine,
+    prune_mode: storage::PruneMode,
+    spent_index: bool,
+) -> Result<storage::SharedStore, String> {
+    let db = db::BlockChainDatabase::open_at_path(db_path(data_dir), db_cache, db_engine)
+        .map_err(|e| format!("Failed to open database: {}", e))?;
+    Ok(Arc::new(
+        db.with_prune_mode(prune_mode).with_spent_index(spent_index),
+    ))
+}
+
+/// Path of the background chainstate database, which validates UTXO set snapshot
+pub fn background_db_path(data_dir: &Option<String>) -> PathBuf {
+    match *data_dir {
+        Some(ref data_dir) => custom_path(&data_dir, "background_db"),
+        None => app_dir(AppDataType::UserData, &APP_INFO, "background_db").expect("Failed to get app dir"),
+    }
+}
+
+pub fn open_background_db(cfg: &Config) -> Result<storage::SharedStore, String> {
+    let db: storage::SharedStore = Arc::new(
+        db::BlockChainDatabase::open_at_path(background_db_path(&cfg.data_dir), cfg.db_cache, cfg.db_engine)
+            .map_err(|e| format!("Failed to open background database: {}", e))?,
+    );
+    init_genesis(&db, &cfg.consensus)?;
+    Ok(db)
 }
 
 pub fn node_table_path(cfg: &Config) -> PathBuf {
@@ -26,20 +55,46 @@ pub fn node_table_path(cfg: &Config) -> PathBuf {
     node_table
 }
 
+pub fn fee_estimates_path(cfg: &Config) -> PathBuf {
+    let mut fee_estimates = match cfg.data_dir {
+        Some(ref data_dir) => custom_path(&data_dir, "mempool"),
+        None => app_dir(AppDataType::UserData, &APP_INFO, "mempool").expect("Failed to get app dir"),
+    };
+    fee_estimates.push("fee_estimates.dat");
+    fee_estimates
+}
+
+pub fn cookie_path(data_dir: &Option<String>) -> PathBuf {
+    let mut cookie = match *data_dir {
+        Some(ref data_dir) => {
+            let path = PathBuf::from(data_dir);
+            create_dir_all(&path).expect("Failed to get app dir");
+            path
+        }
+        None => app_root(AppDataType::UserData, &APP_INFO).expect("Failed to get app dir"),
+    };
+    cookie.push(".cookie");
+    cookie
+}
+
 pub fn init_db(cfg: &Config) -> Result<(), String> {
+    init_genesis(&cfg.db, &cfg.consensus)
+}
+
+fn init_genesis(db: &storage::SharedStore, consensus: &ConsensusParams) -> Result<(), String> {
     // insert genesis block if db is empty
-    let genesis_block: IndexedBlock = cfg.network.genesis_block().into();
-    match cfg.db.block_hash(0) {
+    let genesis_block: IndexedBlock = consensus.genesis_block.clone().into();
+    match db.block_hash(0) {
         Some(ref db_genesis_block_hash) if db_genesis_block_hash != genesis_block.hash() => Err(
             "Trying to open database with incompatible genesis block".into(),
         ),
         Some(_) => Ok(()),
         None => {
             let hash = genesis_block.hash().clone();
-            cfg.db.insert(genesis_block).expect(
+            db.insert(genesis_block).expect(
                 "Failed to insert genesis block to the database",
             );
-            cfg.db.canonize(&hash).expect(
+            db.canonize(&hash).expect(
                 "Failed to canonize genesis block",
             );
             Ok(())
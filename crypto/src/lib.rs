@@ -126,6 +126,33 @@ impl Digest for DHash256 {
 	}
 }
 
+/// Double SHA-256 of the data with fixed prefix, which is hashed only once.
+/// Prefix length must be a multiple of SHA-256 block size (64 bytes).
+#[derive(Clone, Copy)]
+pub struct DHash256Midstate {
+	hasher: Sha256,
+}
+
+impl DHash256Midstate {
+	pub fn new(prefix: &[u8]) -> Self {
+		assert_eq!(prefix.len() % 64, 0);
+		let mut hasher = Sha256::new();
+		hasher.input(prefix);
+		DHash256Midstate {
+			hasher: hasher,
+		}
+	}
+
+	/// Double SHA-256 of prefix, followed by given suffix
+	pub fn dhash256(&self, suffix: &[u8]) -> H256 {
+		let mut hasher = DHash256 {
+			hasher: self.hasher,
+		};
+		hasher.input(suffix);
+		hasher.finish()
+	}
+}
+
 /// RIPEMD160
 #[inline]
 pub fn ripemd160(input: &[u8]) -> H160 {
@@ -197,7 +224,7 @@ pub fn checksum(data: &[u8]) -> H32 {
 #[cfg(test)]
 mod tests {
 	use primitives::bytes::Bytes;
-	use super::{ripemd160, sha1, sha256, dhash160, dhash256, siphash24,checksum};
+	use super::{ripemd160, sha1, sha256, dhash160, dhash256, siphash24,checksum, DHash256Midstate};
 
 	#[test]
 	fn test_ripemd160() {
@@ -239,6 +266,15 @@ mod tests {
 		assert_eq!(result, expected);
 	}
 
+	#[test]
+	fn test_dhash256_midstate() {
+		let data: Vec<u8> = (0..80u8).collect();
+		let midstate = DHash256Midstate::new(&data[..64]);
+		assert_eq!(midstate.dhash256(&data[64..]), dhash256(&data));
+		// midstate is reusable
+		assert_eq!(midstate.dhash256(&data[64..]), dhash256(&data));
+	}
+
 	#[test]
 	fn test_siphash24() {
 		let expected = 0x74f839c593dc67fd_u64;
@@ -1,7 +1,12 @@
-use std::collections::HashMap;
+use std::cmp;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io;
+use std::mem;
 use std::path::Path;
-use parking_lot::RwLock;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use parking_lot::{Mutex, RwLock};
 use hash::H256;
 use bytes::Bytes;
 use chain::{
@@ -9,29 +14,77 @@ use chain::{
 	OutPoint, TransactionOutput
 };
 use ser::{
-	deserialize, serialize, List
+	Deserializable, deserialize, serialize, List
 };
 use kv::{
-	KeyValueDatabase, OverlayDatabase, Transaction as DBTransaction, Value, DiskDatabase,
-	DatabaseConfig, MemoryDatabase, AutoFlushingOverlayDatabase, KeyValue, Key, KeyState, CacheDatabase
+	KeyValueDatabase, OverlayDatabase, Transaction as DBTransaction, Value, DatabaseBackend,
+	DatabaseEngine, DatabaseConfig, MemoryDatabase, AutoFlushingOverlayDatabase, KeyValue, Key, KeyState, CacheDatabase
 };
 use kv::{
 	COL_COUNT, COL_BLOCK_HASHES, COL_BLOCK_HEADERS, COL_BLOCK_TRANSACTIONS, COL_TRANSACTIONS,
-	COL_TRANSACTIONS_META, COL_BLOCK_NUMBERS
+	COL_TRANSACTIONS_META, COL_BLOCK_NUMBERS, COL_COINS, COL_BLOCK_UNDO, COL_BLOCK_POSITIONS
 };
 use storage::{
 	BlockRef, Error, BlockHeaderProvider, BlockProvider, BlockOrigin, TransactionMeta, IndexedBlockProvider,
 	TransactionMetaProvider, TransactionProvider, TransactionOutputProvider, BlockChain, Store,
-	SideChainOrigin, ForkChain, Forkable, CanonStore, ConfigStore, BestBlock
+	SideChainOrigin, ForkChain, Forkable, CanonStore, ConfigStore, BestBlock, Coin, SpentCoin, BlockUndo,
+	PruneMode, PruneStore, MIN_BLOCKS_TO_KEEP, SnapshotBase, SnapshotInfo, SnapshotValidation, SnapshotStore,
+	SpentInfo, SpentInfoProvider, TransactionLocation, TransactionLocationProvider, ChainTip, ChainTipState,
+	ChainTipStatus, ChainTipStore
 };
+use snapshot::{SnapshotWriter, SnapshotReader};
+use block_files::{BlockFiles, BlockPosition};
+use migration::complete_interrupted_migration;
 
 const KEY_BEST_BLOCK_NUMBER: &'static str = "best_block_number";
 const KEY_BEST_BLOCK_HASH: &'static str = "best_block_hash";
+const KEY_PRUNED_BLOCK_NUMBER: &'static str = "pruned_block_number";
+const KEY_SNAPSHOT_BASE: &'static str = "snapshot_base";
+const KEY_SNAPSHOT_VALIDATION: &'static str = "snapshot_validation";
+const KEY_CHAIN_TIPS: &'static str = "chain_tips";
+const KEY_INVALID_BLOCKS: &'static str = "invalid_blocks";
+const KEY_SCHEMA_VERSION: &'static str = "schema_version";
+const KEY_MIGRATION_PROGRESS: &'static str = "migration_progress";
+const KEY_FIRST_BLOCK_FILE: &'static str = "first_block_file";
+
+/// Schema version of the databases, created by this version of the node.
+/// Older databases are upgraded by migrations when opened
+pub const SCHEMA_VERSION: u32 = 3;
+
+/// Name of the block files directory, relative to the database path
+pub const BLOCK_FILES_DIR: &'static str = "blocks";
 
 const MAX_FORK_ROUTE_PRESET: usize = 2048;
+/// Max number of blocks, pruned when single block is canonized. Limits canonization
+/// time when pruning is enabled on the database with many unpruned blocks
+const MAX_PRUNED_BLOCKS_PER_CANONIZE: u32 = 64;
+/// Max number of database operations, written at once when UTXO snapshot is loaded
+const MAX_SNAPSHOT_OPERATIONS_PER_WRITE: usize = 16 * 1024;
+/// Max number of blocks, processed by the single write of schema migration
+const MAX_MIGRATED_BLOCKS_PER_WRITE: u32 = 1000;
+/// Schema migration progress is logged every time it passes multiple of this value
+const MIGRATION_PROGRESS_LOG_INTERVAL: u32 = 10_000;
+
+/// Step of the database schema upgrade
+struct SchemaMigration<T> where T: KeyValueDatabase {
+	/// Schema version after the step is completed
+	version: u32,
+	description: &'static str,
+	/// Adds next part of the migration to the update, starting at given progress.
+	/// Returns progress to continue from or None if the step is completed
+	apply: fn(&BlockChainDatabase<T>, &mut DBTransaction, u32) -> Result<Option<u32>, Error>,
+}
 
 pub struct BlockChainDatabase<T> where T: KeyValueDatabase {
 	best_block: RwLock<BestBlock>,
+	prune_mode: PruneMode,
+	spent_index: bool,
+	block_files: Option<Arc<BlockFiles>>,
+	/// Number of the first block file, which isn't removed by pruning. Block positions
+	/// in the older files are unavailable. AtomicU32 is unstable => using AtomicUsize here
+	first_block_file: AtomicUsize,
+	/// Serializes read-modify-write updates of the chain tips list
+	chain_tips_lock: Mutex<()>,
 	db: T,
 }
 
@@ -49,23 +102,35 @@ impl<'a, T> ForkChain for ForkChainDatabase<'a, T> where T: KeyValueDatabase {
 	}
 }
 
-impl BlockChainDatabase<CacheDatabase<AutoFlushingOverlayDatabase<DiskDatabase>>> {
-	pub fn open_at_path<P>(path: P, total_cache: usize) -> Result<Self, Error> where P: AsRef<Path> {
+impl BlockChainDatabase<CacheDatabase<AutoFlushingOverlayDatabase<Box<DatabaseBackend>>>> {
+	pub fn open_at_path<P>(path: P, total_cache: usize, engine: DatabaseEngine) -> Result<Self, Error> where P: AsRef<Path> {
+		// otherwise the empty database would be created in place of the database, moved to the backup
+		complete_interrupted_migration(path.as_ref()).map_err(Error::DatabaseError)?;
 		fs::create_dir_all(path.as_ref()).map_err(|err| Error::DatabaseError(err.to_string()))?;
 		let mut cfg = DatabaseConfig::with_columns(Some(COL_COUNT));
 
-		cfg.set_cache(Some(COL_TRANSACTIONS), total_cache / 4);
-		cfg.set_cache(Some(COL_TRANSACTIONS_META), total_cache / 4);
-		cfg.set_cache(Some(COL_BLOCK_HEADERS), total_cache / 4);
+		// coins cache of the `CacheDatabase` is a part of the total cache (MiB)
+		let coin_cache = total_cache / 4;
+		cfg.set_cache(Some(COL_COINS), total_cache / 8);
+		cfg.set_cache(Some(COL_TRANSACTIONS), total_cache / 8);
+		cfg.set_cache(Some(COL_TRANSACTIONS_META), total_cache / 8);
+		cfg.set_cache(Some(COL_BLOCK_HEADERS), total_cache / 8);
 
-		cfg.set_cache(Some(COL_BLOCK_HASHES), total_cache / 12);
-		cfg.set_cache(Some(COL_BLOCK_TRANSACTIONS), total_cache / 12);
-		cfg.set_cache(Some(COL_BLOCK_NUMBERS), total_cache / 12);
+		cfg.set_cache(Some(COL_BLOCK_HASHES), total_cache / 32);
+		cfg.set_cache(Some(COL_BLOCK_TRANSACTIONS), total_cache / 32);
+		cfg.set_cache(Some(COL_BLOCK_NUMBERS), total_cache / 32);
+		cfg.set_cache(Some(COL_BLOCK_UNDO), total_cache / 32);
+		cfg.set_cache(Some(COL_BLOCK_POSITIONS), total_cache / 32);
 
 		cfg.bloom_filters.insert(Some(COL_TRANSACTIONS_META), 32);
-
-		match DiskDatabase::open(cfg, path) {
-			Ok(db) => Ok(Self::open_with_cache(db)),
+		cfg.bloom_filters.insert(Some(COL_COINS), 32);
+
+		let block_files = BlockFiles::open(path.as_ref().join(BLOCK_FILES_DIR))?;
+		match engine.open(cfg, path) {
+			Ok(db) => {
+				let db = CacheDatabase::with_coin_cache_size(AutoFlushingOverlayDatabase::new(db, 50), coin_cache * 1024 * 1024);
+				Self::open_with_block_files(db, block_files)
+			},
 			Err(err) => Err(Error::DatabaseError(err))
 		}
 	}
@@ -73,7 +138,7 @@ impl BlockChainDatabase<CacheDatabase<AutoFlushingOverlayDatabase<DiskDatabase>>
 
 impl BlockChainDatabase<MemoryDatabase> {
 	pub fn init_test_chain(blocks: Vec<IndexedBlock>) -> Self {
-		let store = BlockChainDatabase::open(MemoryDatabase::default());
+		let store = BlockChainDatabase::open(MemoryDatabase::default()).expect("memory database is always empty");
 
 		for block in blocks {
 			let hash = block.hash().clone();
@@ -85,13 +150,9 @@ impl BlockChainDatabase<MemoryDatabase> {
 }
 
 impl<T> BlockChainDatabase<CacheDatabase<AutoFlushingOverlayDatabase<T>>> where T: KeyValueDatabase {
-	pub fn open_with_cache(db: T) -> Self {
+	pub fn open_with_cache(db: T) -> Result<Self, Error> {
 		let db = CacheDatabase::new(AutoFlushingOverlayDatabase::new(db, 50));
-		let best_block = Self::read_best_block(&db).unwrap_or_default();
-		BlockChainDatabase {
-			best_block: RwLock::new(best_block),
-			db: db,
-		}
+		BlockChainDatabase::open(db)
 	}
 }
 
@@ -110,20 +171,276 @@ impl<T> BlockChainDatabase<T> where T: KeyValueDatabase {
 		}
 	}
 
-	pub fn open(db: T) -> Self {
+	fn read_first_block_file(db: &T) -> u32 {
+		db.get(&Key::Meta(KEY_FIRST_BLOCK_FILE))
+			.map(KeyState::into_option)
+			.map(|x| x.and_then(Value::as_meta))
+			.expect("Inconsistent DB. Unable to read first block file.")
+			.map(|file| deserialize(&*file).expect("Inconsistent DB. Invalid first block file."))
+			.unwrap_or_default()
+	}
+
+	fn read_schema_version(db: &T) -> Option<u32> {
+		db.get(&Key::Meta(KEY_SCHEMA_VERSION))
+			.map(KeyState::into_option)
+			.map(|x| x.and_then(Value::as_meta))
+			.expect("Inconsistent DB. Unable to read schema version.")
+			.map(|version| deserialize(&*version).expect("Inconsistent DB. Invalid schema version."))
+	}
+
+	fn read_migration_progress(db: &T) -> Option<u32> {
+		db.get(&Key::Meta(KEY_MIGRATION_PROGRESS))
+			.map(KeyState::into_option)
+			.map(|x| x.and_then(Value::as_meta))
+			.expect("Inconsistent DB. Unable to read migration progress.")
+			.map(|progress| deserialize(&*progress).expect("Inconsistent DB. Invalid migration progress."))
+	}
+
+	/// Opens the database, upgrading its schema to `SCHEMA_VERSION` if required.
+	/// Fails if database has been created with newer schema
+	pub fn open(db: T) -> Result<Self, Error> {
+		let store = Self::open_unchecked(db);
+		store.upgrade_schema()?;
+		Ok(store)
+	}
+
+	/// Opens the database, which stores new blocks in given block files. Canon chain blocks,
+	/// stored in the database by the older versions, are appended to the block files when
+	/// schema is upgraded
+	pub fn open_with_block_files(db: T, block_files: BlockFiles) -> Result<Self, Error> {
+		let store = Self::open_unchecked(db).with_block_files(block_files);
+		store.upgrade_schema()?;
+		Ok(store)
+	}
+
+	fn open_unchecked(db: T) -> Self {
 		let best_block = Self::read_best_block(&db).unwrap_or_default();
+		let first_block_file = Self::read_first_block_file(&db);
 		BlockChainDatabase {
 			best_block: RwLock::new(best_block),
+			prune_mode: PruneMode::Disabled,
+			spent_index: false,
+			block_files: None,
+			first_block_file: AtomicUsize::new(first_block_file as usize),
+			chain_tips_lock: Mutex::new(()),
 			db: db,
 		}
 	}
 
+	/// Returns schema version of the database
+	pub fn schema_version(&self) -> u32 {
+		Self::read_schema_version(&self.db).unwrap_or(0)
+	}
+
+	/// Ordered migration steps. Every step upgrades database from `version - 1` to `version`
+	fn schema_migrations() -> Vec<SchemaMigration<T>> {
+		vec![
+			SchemaMigration {
+				version: 1,
+				description: "index locations of canon chain transactions",
+				apply: Self::migrate_transaction_locations,
+			},
+			SchemaMigration {
+				version: 2,
+				description: "build unspent outputs set and undo data of canon chain blocks",
+				apply: Self::migrate_coins,
+			},
+			SchemaMigration {
+				version: 3,
+				description: "store canon chain blocks in the block files",
+				apply: Self::migrate_block_files,
+			},
+		]
+	}
+
+	fn upgrade_schema(&self) -> Result<(), Error> {
+		let version = match Self::read_schema_version(&self.db) {
+			Some(version) => version,
+			// new database is created with the latest schema
+			None if Self::read_best_block(&self.db).is_none() => {
+				let mut update = DBTransaction::new();
+				update.insert(KeyValue::Meta(KEY_SCHEMA_VERSION, serialize(&SCHEMA_VERSION)));
+				return self.db.write(update).map_err(Error::DatabaseError);
+			},
+			// database has been created before schema versioning
+			None => 0,
+		};
+
+		if version > SCHEMA_VERSION {
+			return Err(Error::UnsupportedSchemaVersion(version));
+		}
+
+		for migration in Self::schema_migrations().into_iter().filter(|migration| migration.version > version) {
+			// interrupted migration is resumed from the last written progress
+			let mut progress = Self::read_migration_progress(&self.db).unwrap_or_default();
+			info!(target: "db", "Upgrading database to schema version {}: {}", migration.version, migration.description);
+			if progress != 0 {
+				info!(target: "db", "Resuming migration from {}", progress);
+			}
+
+			loop {
+				let mut update = DBTransaction::new();
+				match (migration.apply)(self, &mut update, progress)? {
+					Some(next) => {
+						update.insert(KeyValue::Meta(KEY_MIGRATION_PROGRESS, serialize(&next)));
+						self.db.write(update).map_err(Error::DatabaseError)?;
+						if next / MIGRATION_PROGRESS_LOG_INTERVAL != progress / MIGRATION_PROGRESS_LOG_INTERVAL {
+							info!(target: "db", "Schema version {} migration progress: {}", migration.version, next);
+						}
+						progress = next;
+					},
+					None => {
+						update.delete(Key::Meta(KEY_MIGRATION_PROGRESS));
+						update.insert(KeyValue::Meta(KEY_SCHEMA_VERSION, serialize(&migration.version)));
+						self.db.write(update).map_err(Error::DatabaseError)?;
+						info!(target: "db", "Database has been upgraded to schema version {}", migration.version);
+						break;
+					},
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Schema version 1: every canon chain transaction has its location indexed.
+	/// Progress is the number of the next block to index
+	fn migrate_transaction_locations(&self, update: &mut DBTransaction, from: u32) -> Result<Option<u32>, Error> {
+		let best_number = self.best_block.read().number;
+		if from > best_number {
+			return Ok(None);
+		}
+
+		let to = cmp::min(from.saturating_add(MAX_MIGRATED_BLOCKS_PER_WRITE - 1), best_number);
+		for number in from..to + 1 {
+			// blocks below UTXO snapshot base and pruned blocks are missing
+			let block_hash = match self.block_hash(number) {
+				Some(block_hash) => block_hash,
+				None => continue,
+			};
+
+			for (index, tx_hash) in self.block_transaction_hashes(block_hash.clone().into()).into_iter().enumerate() {
+				if self.get(Key::TransactionLocation(tx_hash.clone())).is_none() {
+					update.insert(KeyValue::TransactionLocation(tx_hash, TransactionLocation {
+						block_hash: block_hash.clone(),
+						index: index as u32,
+					}));
+				}
+			}
+		}
+
+		Ok(Some(to + 1))
+	}
+
+	/// Schema version 2: unspent outputs of the canon chain are stored in the coins column and
+	/// every canon block has undo data. Both are built from the transactions meta.
+	/// Progress is the number of the next block to process
+	fn migrate_coins(&self, update: &mut DBTransaction, from: u32) -> Result<Option<u32>, Error> {
+		let best_number = self.best_block.read().number;
+		if from > best_number {
+			return Ok(None);
+		}
+
+		let to = cmp::min(from.saturating_add(MAX_MIGRATED_BLOCKS_PER_WRITE - 1), best_number);
+		for number in from..to + 1 {
+			// blocks below UTXO snapshot base and pruned blocks are missing
+			let block = match self.block_hash(number).and_then(|hash| self.indexed_block(hash.into())) {
+				Some(block) => block,
+				None => continue,
+			};
+
+			for (tx_index, tx) in block.transactions.iter().enumerate() {
+				// meta of the duplicate transaction (BIP30) belongs to the latest one
+				let meta = match self.transaction_meta(&tx.hash) {
+					Some(ref meta) if meta.height() == number => meta.clone(),
+					_ => continue,
+				};
+
+				for (output_index, output) in tx.raw.outputs.iter().enumerate() {
+					if meta.is_spent(output_index) == Some(false) {
+						update.insert(KeyValue::Coin(OutPoint {
+							hash: tx.hash.clone(),
+							index: output_index as u32,
+						}, Coin::new(output, number, tx_index == 0)));
+					}
+				}
+			}
+
+			if self.get(Key::BlockUndo(block.header.hash.clone())).is_none() {
+				match self.legacy_block_undo(&block) {
+					Some(undo) => update.insert(KeyValue::BlockUndo(block.header.hash.clone(), undo)),
+					None => warn!(target: "db", "Undo data of block {} is not built: spent transactions are missing", number),
+				}
+			}
+		}
+
+		Ok(Some(to + 1))
+	}
+
+	/// Schema version 3: canon chain blocks are stored in the block files. Database, opened
+	/// without block files, keeps its blocks in the transactions column. Transactions aren't
+	/// removed from this column, because side chain blocks, stored there, may include them.
+	/// Progress is the number of the next block to process
+	fn migrate_block_files(&self, update: &mut DBTransaction, from: u32) -> Result<Option<u32>, Error> {
+		let block_files = match self.block_files {
+			Some(ref block_files) => block_files,
+			None => return Ok(None),
+		};
+
+		let best_number = self.best_block.read().number;
+		if from > best_number {
+			return Ok(None);
+		}
+
+		let to = cmp::min(from.saturating_add(MAX_MIGRATED_BLOCKS_PER_WRITE - 1), best_number);
+		for number in from..to + 1 {
+			let block_hash = match self.block_hash(number) {
+				Some(block_hash) => block_hash,
+				None => continue,
+			};
+
+			// blocks, inserted after the block files have been introduced, are already there.
+			// Blocks, appended before the migration has been interrupted, are appended again
+			if self.block_position(&block_hash).is_some() {
+				continue;
+			}
+
+			// blocks below UTXO snapshot base and pruned blocks are missing
+			if let Some(block) = self.indexed_block(block_hash.clone().into()) {
+				let position = block_files.append(&block)?;
+				update.insert(KeyValue::BlockPosition(block_hash, position));
+			}
+		}
+
+		Ok(Some(to + 1))
+	}
+
+	/// Sets blocks pruning mode
+	pub fn with_prune_mode(mut self, prune_mode: PruneMode) -> Self {
+		self.prune_mode = prune_mode;
+		self
+	}
+
+	/// Enables or disables spent outputs index
+	pub fn with_spent_index(mut self, spent_index: bool) -> Self {
+		self.spent_index = spent_index;
+		self
+	}
+
+	/// Stores new blocks in the flat block files instead of the transactions column
+	pub fn with_block_files(mut self, block_files: BlockFiles) -> Self {
+		self.block_files = Some(Arc::new(block_files));
+		self
+	}
+
 	pub fn best_block(&self) -> BestBlock {
 		self.best_block.read().clone()
 	}
 
 	pub fn fork(&self, side_chain: SideChainOrigin) -> Result<ForkChainDatabase<T>, Error> {
-		let overlay = BlockChainDatabase::open(OverlayDatabase::new(&self.db));
+		let mut overlay = BlockChainDatabase::open_unchecked(OverlayDatabase::new(&self.db))
+			.with_spent_index(self.spent_index);
+		overlay.block_files = self.block_files.clone();
 
 		for hash in side_chain.decanonized_route.into_iter().rev() {
 			let decanonized_hash = overlay.decanonize()?;
@@ -180,7 +497,8 @@ impl<T> BlockChainDatabase<T> where T: KeyValueDatabase {
 							.collect(),
 						block_number: block_number,
 					};
-					if block_number > best_block.number {
+					// branches with invalid blocks never become canon
+					if block_number > best_block.number && !self.is_invalid_block(&header.raw.previous_header_hash) {
 						return Ok(BlockOrigin::SideChainBecomingCanonChain(origin))
 					} else {
 						return Ok(BlockOrigin::SideChain(origin))
@@ -209,12 +527,46 @@ impl<T> BlockChainDatabase<T> where T: KeyValueDatabase {
 		}
 
 		let mut update = DBTransaction::new();
-		update.insert(KeyValue::BlockHeader(block.hash().clone(), block.header.raw));
+		let is_block_file_stored = match self.block_files {
+			Some(ref block_files) => {
+				// block is written to the file before the database is updated, so position always
+				// points to the existing data. Data of the failed insertions is never referenced
+				let position = block_files.append(&block)?;
+				update.insert(KeyValue::BlockPosition(block.header.hash.clone(), position));
+				true
+			},
+			None => false,
+		};
+		// transaction hashes are kept in the database, so that they're read without the block
 		let tx_hashes = block.transactions.iter().map(|tx| tx.hash.clone()).collect::<Vec<_>>();
 		update.insert(KeyValue::BlockTransactions(block.header.hash.clone(), List::from(tx_hashes)));
+		update.insert(KeyValue::BlockHeader(block.hash().clone(), block.header.raw));
 
-		for tx in block.transactions.into_iter() {
-			update.insert(KeyValue::Transaction(tx.hash, tx.raw));
+		// new block replaces its parent in the list of chain tips
+		let number = if parent_hash.is_zero() {
+			Some(0)
+		} else {
+			self.fork_point(&parent_hash).map(|(fork_number, distance)| fork_number + distance + 1)
+		};
+		// chain tips are updated under the lock => concurrent updates are not lost
+		let _chain_tips_lock = self.chain_tips_lock.lock();
+		if let Some(number) = number {
+			let block_hash = block.header.hash.clone();
+			let mut tips = self.read_chain_tips();
+			tips.retain(|tip| tip.hash != parent_hash && tip.hash != block_hash);
+			tips.push(ChainTipState {
+				hash: block_hash.clone(),
+				number: number,
+				base: block_hash,
+				status: if self.is_invalid_block(&parent_hash) { ChainTipStatus::Invalid } else { ChainTipStatus::ValidFork },
+			});
+			update.insert(KeyValue::Meta(KEY_CHAIN_TIPS, serialize(&List::from(tips))));
+		}
+
+		if !is_block_file_stored {
+			for tx in block.transactions.into_iter() {
+				update.insert(KeyValue::Transaction(tx.hash, tx.raw));
+			}
 		}
 
 		self.db.write(update).map_err(Error::DatabaseError)
@@ -235,10 +587,24 @@ impl<T> BlockChainDatabase<T> where T: KeyValueDatabase {
 		let mut update = DBTransaction::new();
 		update.delete(Key::BlockHeader(decanonized_hash.clone()));
 		update.delete(Key::BlockTransactions(decanonized_hash.clone()));
+		update.delete(Key::BlockPosition(decanonized_hash.clone()));
 		for tx in decanonized.transactions.into_iter() {
 			update.delete(Key::Transaction(tx.hash()));
 		}
 
+		// new best block replaces removed block in the list of chain tips
+		let best_block = self.best_block();
+		let _chain_tips_lock = self.chain_tips_lock.lock();
+		let mut tips = self.read_chain_tips();
+		tips.retain(|tip| tip.hash != decanonized_hash && tip.hash != best_block.hash);
+		tips.push(ChainTipState {
+			hash: best_block.hash.clone(),
+			number: best_block.number,
+			base: best_block.hash.clone(),
+			status: ChainTipStatus::ValidFork,
+		});
+		update.insert(KeyValue::Meta(KEY_CHAIN_TIPS, serialize(&List::from(tips))));
+
 		self.db.write(update).map_err(Error::DatabaseError)?;
 
 		Ok(self.best_block().hash)
@@ -283,6 +649,61 @@ impl<T> BlockChainDatabase<T> where T: KeyValueDatabase {
 			modified_meta.insert(tx.hash.clone(), meta);
 		}
 
+		// coins, created by this block and still unspent
+		let mut created_coins: HashMap<OutPoint, Coin> = HashMap::new();
+		// coins of the canon chain, spent by this block
+		let mut spent_coins: HashSet<OutPoint> = HashSet::new();
+		let mut undo = BlockUndo::default();
+
+		for (tx_index, tx) in block.transactions.iter().enumerate() {
+			update.insert(KeyValue::TransactionLocation(tx.hash.clone(), TransactionLocation {
+				block_hash: new_best_block.hash.clone(),
+				index: tx_index as u32,
+			}));
+
+			let is_coinbase = tx_index == 0;
+			if !is_coinbase {
+				for (input_index, input) in tx.raw.inputs.iter().enumerate() {
+					let outpoint = &input.previous_output;
+					if self.spent_index {
+						update.insert(KeyValue::SpentInfo(outpoint.clone(), SpentInfo {
+							txid: tx.hash.clone(),
+							index: input_index as u32,
+							height: new_best_block.number,
+						}));
+					}
+
+					if created_coins.remove(outpoint).is_some() {
+						continue;
+					}
+
+					if !spent_coins.insert(outpoint.clone()) {
+						return Err(Error::CannotCanonize);
+					}
+
+					let coin = self.coin(outpoint).ok_or(Error::CannotCanonize)?;
+					update.delete(Key::Coin(outpoint.clone()));
+					undo.spent.push(SpentCoin {
+						outpoint: outpoint.clone(),
+						coin: coin,
+					});
+				}
+			}
+
+			for (output_index, output) in tx.raw.outputs.iter().enumerate() {
+				let outpoint = OutPoint {
+					hash: tx.hash.clone(),
+					index: output_index as u32,
+				};
+				created_coins.insert(outpoint, Coin::new(output, new_best_block.number, is_coinbase));
+			}
+		}
+
+		for (outpoint, coin) in created_coins {
+			update.insert(KeyValue::Coin(outpoint, coin));
+		}
+		update.insert(KeyValue::BlockUndo(new_best_block.hash.clone(), undo));
+
 		for tx in block.transactions.iter().skip(1) {
 			modified_meta.insert(tx.hash.clone(), TransactionMeta::new(new_best_block.number, tx.raw.outputs.len()));
 
@@ -308,11 +729,119 @@ impl<T> BlockChainDatabase<T> where T: KeyValueDatabase {
 			update.insert(KeyValue::TransactionMeta(hash, meta));
 		}
 
+		let mut first_used_file = None;
+		if let PruneMode::Automatic { target_size } = self.prune_mode {
+			if let Some((prune_from, prune_to)) = self.automatic_prune_range(new_best_block.number, target_size) {
+				first_used_file = self.prune_blocks(&mut update, prune_from, prune_to)?;
+			}
+		}
+
 		self.db.write(update).map_err(Error::DatabaseError)?;
 		*best_block = new_best_block;
+		self.remove_unused_block_files(first_used_file);
 		Ok(())
 	}
 
+	/// Returns range of canon blocks, which are pruned to remove the oldest block file, if total size of
+	/// block files exceeds the target. Blocks in the file are pruned by at most `MAX_PRUNED_BLOCKS_PER_CANONIZE`
+	/// per call. The file is only pruned if all its canon blocks are deeper than `MIN_BLOCKS_TO_KEEP`.
+	fn automatic_prune_range(&self, best_block_number: u32, target_size: u64) -> Option<(u32, u32)> {
+		let block_files = self.block_files.as_ref()?;
+		if block_files.size() <= target_size || best_block_number < MIN_BLOCKS_TO_KEEP {
+			return None;
+		}
+
+		let block_file = |number: u32| self.block_hash(number)
+			.and_then(|hash| self.block_position(&hash))
+			.map(|position| position.file);
+		let last_prunable = best_block_number - MIN_BLOCKS_TO_KEEP;
+		let prune_from = self.pruned_height().map(|number| number + 1).unwrap_or(0);
+		// blocks are appended to the descendants of the canon block => files of canon blocks never decrease.
+		// Blocks, stored in the database (before block files have been introduced), are pruned first
+		let oldest_file = block_file(prune_from);
+		// the file, blocks are appended to, is never removed
+		if oldest_file == Some(block_files.current_file()) {
+			return None;
+		}
+
+		let mut number = prune_from;
+		loop {
+			if number != prune_from && block_file(number) != oldest_file {
+				return Some((prune_from, number - 1));
+			}
+			if number > last_prunable {
+				return None;
+			}
+			if number - prune_from == MAX_PRUNED_BLOCKS_PER_CANONIZE {
+				return Some((prune_from, number - 1));
+			}
+			number += 1;
+		}
+	}
+
+	/// Removes transactions and undo data of canon blocks in range [from; to].
+	/// Transactions with unspent outputs are kept, so that they're still available via rpc.
+	/// Headers and UTXO set are never pruned.
+	fn prune_blocks(&self, update: &mut DBTransaction, from: u32, to: u32) -> Result<Option<u32>, Error> {
+		trace!(target: "db", "prune blocks {}..{}", from, to);
+
+		for number in from..to + 1 {
+			let hash = self.block_hash(number)
+				.ok_or_else(|| Error::DatabaseError(format!("Missing canon block {}", number)))?;
+			let is_block_file_stored = self.block_position(&hash).is_some();
+			for tx in self.indexed_block_transactions(hash.clone().into()) {
+				let is_fully_spent = self.transaction_meta(&tx.hash)
+					.map(|meta| meta.is_fully_spent())
+					.unwrap_or(true);
+				if is_fully_spent {
+					update.delete(Key::TransactionLocation(tx.hash.clone()));
+					update.delete(Key::Transaction(tx.hash));
+				} else if is_block_file_stored {
+					// block files are removed after pruning, so unspent transactions are kept in the database
+					update.insert(KeyValue::Transaction(tx.hash, tx.raw));
+				}
+			}
+			update.delete(Key::BlockTransactions(hash.clone()));
+			update.delete(Key::BlockPosition(hash.clone()));
+			update.delete(Key::BlockUndo(hash));
+		}
+
+		update.insert(KeyValue::Meta(KEY_PRUNED_BLOCK_NUMBER, serialize(&to)));
+
+		// every canon block above the pruned range descends from the block `to + 1`, so it has
+		// been appended after this block. Older files are left with pruned blocks and forks of
+		// the pruned part of the chain only. Positions of the side chain blocks in these files
+		// are still stored, so files are marked as removed in the same update
+		let first_used_file = self.block_hash(to + 1)
+			.and_then(|hash| self.block_position(&hash))
+			.map(|position| position.file);
+		if let Some(first_used_file) = first_used_file {
+			if first_used_file > self.first_block_file() {
+				update.insert(KeyValue::Meta(KEY_FIRST_BLOCK_FILE, serialize(&first_used_file)));
+			}
+		}
+		Ok(first_used_file)
+	}
+
+	/// Returns number of the first block file, which isn't removed by pruning
+	fn first_block_file(&self) -> u32 {
+		self.first_block_file.load(Ordering::Acquire) as u32
+	}
+
+	/// Removes block files, which are not used after pruning. Must be called after pruning
+	/// changes are written to the database, under the best block write lock
+	fn remove_unused_block_files(&self, first_used_file: Option<u32>) {
+		if let (Some(block_files), Some(first_used_file)) = (self.block_files.as_ref(), first_used_file) {
+			// positions in the older files are not read anymore
+			if first_used_file > self.first_block_file() {
+				self.first_block_file.store(first_used_file as usize, Ordering::Release);
+			}
+			if let Err(err) = block_files.remove_files_before(first_used_file) {
+				warn!(target: "db", "Failed to remove pruned block files: {}", err);
+			}
+		}
+	}
+
 	pub fn decanonize(&self) -> Result<H256, Error> {
 		let mut best_block = self.best_block.write();
 		let block = match self.indexed_block(best_block.hash.clone().into()) {
@@ -321,6 +850,10 @@ impl<T> BlockChainDatabase<T> where T: KeyValueDatabase {
 		};
 		let block_number = best_block.number;
 		let block_hash = best_block.hash.clone();
+		let undo = match self.get(Key::BlockUndo(block_hash.clone())).and_then(Value::as_block_undo) {
+			Some(undo) => undo,
+			None => self.legacy_block_undo(&block).ok_or(Error::CannotDecanonize)?,
+		};
 
 		let new_best_block = BestBlock {
 			hash: block.header.raw.previous_header_hash.clone(),
@@ -357,6 +890,10 @@ impl<T> BlockChainDatabase<T> where T: KeyValueDatabase {
 						entry.insert(meta);
 					}
 				}
+
+				if self.spent_index {
+					update.delete(Key::SpentInfo(input.previous_output.clone()));
+				}
 			}
 		}
 
@@ -364,7 +901,24 @@ impl<T> BlockChainDatabase<T> where T: KeyValueDatabase {
 			update.insert(KeyValue::TransactionMeta(hash, meta));
 		}
 
+		for tx in &block.transactions {
+			for output_index in 0..tx.raw.outputs.len() {
+				update.delete(Key::Coin(OutPoint {
+					hash: tx.hash.clone(),
+					index: output_index as u32,
+				}));
+			}
+		}
+
+		// restore coins, spent by the block. must go after deletions above, since
+		// the block could spend (and recreate, bip30) the same outpoint
+		for spent in undo.spent {
+			update.insert(KeyValue::Coin(spent.outpoint, spent.coin));
+		}
+		update.delete(Key::BlockUndo(block_hash.clone()));
+
 		for tx in block.transactions {
+			update.delete(Key::TransactionLocation(tx.hash.clone()));
 			update.delete(Key::TransactionMeta(tx.hash));
 		}
 
@@ -373,6 +927,306 @@ impl<T> BlockChainDatabase<T> where T: KeyValueDatabase {
 		Ok(block_hash)
 	}
 
+	/// Reads snapshot from the file, checking that it is made at one of `trusted` blocks.
+	/// Chainstate is only modified if `write` is true
+	fn read_snapshot(&self, path: &Path, trusted: &[SnapshotBase], genesis_hash: &H256, write: bool) -> Result<SnapshotInfo, Error> {
+		let file = fs::File::open(path).map_err(|err| Error::InvalidSnapshot(err.to_string()))?;
+		let mut snapshot = SnapshotReader::new(io::BufReader::new(file))?;
+		let base = trusted.iter()
+			.find(|base| base.block_hash == *snapshot.base_block_hash() && base.block_number == snapshot.base_block_number())
+			.cloned()
+			.ok_or_else(|| Error::InvalidSnapshot(format!(
+				"snapshot of block {} is not known to the network",
+				snapshot.base_block_hash().to_reversed_str()
+			)))?;
+
+		let mut update = DBTransaction::new();
+		let mut previous_hash = H256::default();
+		for number in 0..base.block_number + 1 {
+			let header = snapshot.read_header()?;
+			let hash = header.hash();
+			if header.previous_header_hash != previous_hash {
+				return Err(Error::InvalidSnapshot(format!("header {} is not connected to its parent", number)));
+			}
+			if number == 0 && hash != *genesis_hash {
+				return Err(Error::InvalidSnapshot("snapshot is made for other genesis block".into()));
+			}
+
+			if write && number != 0 {
+				update.insert(KeyValue::BlockHeader(hash.clone(), header));
+				update.insert(KeyValue::BlockHash(number, hash.clone()));
+				update.insert(KeyValue::BlockNumber(hash.clone(), number));
+				self.write_snapshot_batch(&mut update, false)?;
+			}
+			previous_hash = hash;
+		}
+
+		if previous_hash != base.block_hash {
+			return Err(Error::InvalidSnapshot("headers are not leading to the base block".into()));
+		}
+
+		// coins of the same transaction are stored together, since they're ordered by outpoint
+		let mut transaction: Option<(H256, Coin, Vec<u32>)> = None;
+		while let Some((outpoint, coin)) = snapshot.read_coin()? {
+			if !write {
+				continue;
+			}
+
+			transaction = match transaction.take() {
+				Some((hash, first_coin, mut unspent)) if hash == outpoint.hash => {
+					unspent.push(outpoint.index);
+					Some((hash, first_coin, unspent))
+				},
+				previous => {
+					if let Some((hash, first_coin, unspent)) = previous {
+						update.insert(KeyValue::TransactionMeta(hash, snapshot_transaction_meta(&first_coin, &unspent)));
+					}
+					Some((outpoint.hash.clone(), coin.clone(), vec![outpoint.index]))
+				},
+			};
+
+			update.insert(KeyValue::Coin(outpoint, coin));
+			self.write_snapshot_batch(&mut update, false)?;
+		}
+
+		let (coins_count, content_hash) = snapshot.finish()?;
+		if content_hash != base.content_hash {
+			return Err(Error::InvalidSnapshot(format!(
+				"content hash {} differs from the expected {}",
+				content_hash.to_reversed_str(),
+				base.content_hash.to_reversed_str()
+			)));
+		}
+
+		if write {
+			if let Some((hash, first_coin, unspent)) = transaction.take() {
+				update.insert(KeyValue::TransactionMeta(hash, snapshot_transaction_meta(&first_coin, &unspent)));
+			}
+			update.insert(KeyValue::Meta(KEY_BEST_BLOCK_HASH, serialize(&base.block_hash)));
+			update.insert(KeyValue::Meta(KEY_BEST_BLOCK_NUMBER, serialize(&base.block_number)));
+			update.insert(KeyValue::Meta(KEY_SNAPSHOT_BASE, serialize(&base)));
+			update.insert(KeyValue::Meta(KEY_SNAPSHOT_VALIDATION, serialize(&SnapshotValidation::Pending)));
+			self.write_snapshot_batch(&mut update, true)?;
+		}
+
+		Ok(SnapshotInfo {
+			base: base,
+			coins_count: coins_count,
+		})
+	}
+
+	/// Writes pending snapshot operations to the database, if there are enough of them or if `force` is true
+	fn write_snapshot_batch(&self, update: &mut DBTransaction, force: bool) -> Result<(), Error> {
+		if !force && update.operations.len() < MAX_SNAPSHOT_OPERATIONS_PER_WRITE {
+			return Ok(());
+		}
+
+		let update = mem::replace(update, DBTransaction::new());
+		self.db.write(update).map_err(Error::DatabaseError)
+	}
+
+	fn read_chain_tips(&self) -> Vec<ChainTipState> {
+		self.get(Key::Meta(KEY_CHAIN_TIPS))
+			.and_then(Value::as_meta)
+			.map(|tips| deserialize::<_, List<ChainTipState>>(&*tips).expect("Inconsistent DB. Invalid chain tips.").into())
+			.unwrap_or_default()
+	}
+
+	fn read_invalid_blocks(&self) -> Vec<H256> {
+		self.get(Key::Meta(KEY_INVALID_BLOCKS))
+			.and_then(Value::as_meta)
+			.map(|hashes| deserialize::<_, List<H256>>(&*hashes).expect("Inconsistent DB. Invalid invalid blocks list.").into())
+			.unwrap_or_default()
+	}
+
+	/// Returns number of the canon chain block, which given block descends from,
+	/// and number of side chain blocks between these blocks
+	fn fork_point(&self, hash: &H256) -> Option<(u32, u32)> {
+		let mut next_hash = hash.clone();
+		for distance in 0..MAX_FORK_ROUTE_PRESET as u32 {
+			if let Some(number) = self.block_number(&next_hash) {
+				return Some((number, distance));
+			}
+
+			next_hash = match self.block_header(next_hash.into()) {
+				Some(header) => header.previous_header_hash,
+				None => return None,
+			};
+		}
+
+		None
+	}
+
+	/// Returns true if block with given hash is the ancestor block or its descendant
+	fn is_descendant_of(&self, hash: &H256, ancestor: &H256) -> bool {
+		let ancestor_number = self.block_number(ancestor);
+		let mut next_hash = hash.clone();
+		for _ in 0..MAX_FORK_ROUTE_PRESET {
+			if next_hash == *ancestor {
+				return true;
+			}
+
+			if let Some(number) = self.block_number(&next_hash) {
+				return ancestor_number.map_or(false, |ancestor_number| ancestor_number <= number);
+			}
+
+			next_hash = match self.block_header(next_hash.into()) {
+				Some(header) => header.previous_header_hash,
+				None => return false,
+			};
+		}
+
+		false
+	}
+
+	/// Selects the longest branch, which has no invalid blocks, and makes it canon
+	fn switch_to_best_valid_branch(&self) -> Result<SideChainOrigin, Error> {
+		let best_block = self.best_block();
+
+		// canon chain itself is valid up to the first invalid block
+		let mut best_valid = self.read_invalid_blocks().iter()
+			.filter_map(|hash| self.block_number(hash))
+			.min()
+			.map(|number| number - 1)
+			.map(|number| (number, self.block_hash(number).expect("canon block number is known; qed")))
+			.unwrap_or_else(|| (best_block.number, best_block.hash.clone()));
+
+		for tip in self.read_chain_tips() {
+			if tip.status == ChainTipStatus::HeadersOnly || tip.base != tip.hash {
+				continue;
+			}
+
+			// branch forks from the invalid part of canon chain, or is the canon chain itself
+			let (fork_number, distance) = match self.fork_point(&tip.hash) {
+				Some(fork_point) => fork_point,
+				None => continue,
+			};
+			if distance == 0 || fork_number > best_valid.0 || fork_number + distance <= best_valid.0 {
+				continue;
+			}
+
+			if !self.is_invalid_block(&tip.hash) {
+				best_valid = (fork_number + distance, tip.hash);
+			}
+		}
+
+		let (block_number, block_hash) = best_valid;
+		let mut canonized_route = Vec::new();
+		let mut next_hash = block_hash;
+		let ancestor = loop {
+			if let Some(number) = self.block_number(&next_hash) {
+				break number;
+			}
+
+			let header = self.block_header(next_hash.clone().into())
+				.expect("not to find orphaned side chain in database; qed");
+			canonized_route.push(next_hash);
+			next_hash = header.previous_header_hash;
+		};
+
+		let origin = SideChainOrigin {
+			ancestor: ancestor,
+			canonized_route: canonized_route.into_iter().rev().collect(),
+			decanonized_route: (ancestor + 1..best_block.number + 1).into_iter()
+				.filter_map(|decanonized_bn| self.block_hash(decanonized_bn))
+				.collect(),
+			block_number: block_number,
+		};
+
+		if !origin.canonized_route.is_empty() || !origin.decanonized_route.is_empty() {
+			let fork = self.fork(origin.clone())?;
+			self.switch_to_fork(fork)?;
+		}
+
+		Ok(origin)
+	}
+
+	/// Returns unspent output of the canon chain
+	pub fn coin(&self, outpoint: &OutPoint) -> Option<Coin> {
+		self.get(Key::Coin(outpoint.clone()))
+			.and_then(Value::as_coin)
+			.or_else(|| self.legacy_coin(outpoint))
+	}
+
+	/// Returns unspent output, created before the coins column has been introduced.
+	/// Such outputs are only marked as unspent in the transaction meta
+	fn legacy_coin(&self, outpoint: &OutPoint) -> Option<Coin> {
+		let meta = self.transaction_meta(&outpoint.hash)?;
+		if meta.is_spent(outpoint.index as usize) != Some(false) {
+			return None;
+		}
+
+		self.transaction(&outpoint.hash)
+			.and_then(|tx| tx.outputs.into_iter().nth(outpoint.index as usize))
+			.map(|output| Coin::new(&output, meta.height(), meta.is_coinbase()))
+	}
+
+	/// Builds undo data of the canon block, canonized before the undo data has been introduced,
+	/// from the transactions it spends. Returns None if any of these transactions is missing
+	fn legacy_block_undo(&self, block: &IndexedBlock) -> Option<BlockUndo> {
+		let created: HashSet<&H256> = block.transactions.iter().map(|tx| &tx.hash).collect();
+		let mut undo = BlockUndo::default();
+		for tx in block.transactions.iter().skip(1) {
+			for input in &tx.raw.inputs {
+				let outpoint = &input.previous_output;
+				// outputs, created and spent by the same block, are never restored
+				if created.contains(&outpoint.hash) {
+					continue;
+				}
+
+				let meta = self.transaction_meta(&outpoint.hash)?;
+				let output = self.transaction(&outpoint.hash)
+					.and_then(|tx| tx.outputs.into_iter().nth(outpoint.index as usize))?;
+				undo.spent.push(SpentCoin {
+					outpoint: outpoint.clone(),
+					coin: Coin::new(&output, meta.height(), meta.is_coinbase()),
+				});
+			}
+		}
+		Some(undo)
+	}
+
+	/// Returns position of the block in the block files. Blocks from the files, removed by
+	/// pruning, have no position
+	fn block_position(&self, hash: &H256) -> Option<BlockPosition> {
+		self.get(Key::BlockPosition(hash.clone()))
+			.and_then(Value::as_block_position)
+			.filter(|position| position.file >= self.first_block_file())
+	}
+
+	/// Reads block from the block files. Blocks, inserted before the block files have been
+	/// introduced, are stored in the transactions column and aren't read here
+	fn read_block_file(&self, hash: &H256) -> Option<IndexedBlock> {
+		let position = self.block_position(hash)?;
+		self.read_block_file_at(hash, &position)
+	}
+
+	/// Reads single transaction of the block from the block files. Falls back to reading the
+	/// whole block, if transaction offsets aren't stored for this block
+	fn read_block_file_transaction(&self, location: &TransactionLocation) -> Option<Transaction> {
+		let position = self.block_position(&location.block_hash)?;
+		match position.transaction_position(location.index) {
+			Some(tx_position) => self.read_block_file_at(&location.block_hash, &tx_position),
+			None if position.tx_offsets.is_empty() => self.read_block_file_at::<IndexedBlock>(&location.block_hash, &position)
+				.and_then(|block| block.transactions.into_iter().nth(location.index as usize))
+				.map(|tx| tx.raw),
+			None => None,
+		}
+	}
+
+	fn read_block_file_at<D>(&self, hash: &H256, position: &BlockPosition) -> Option<D> where D: Deserializable {
+		let block_files = self.block_files.as_ref()?;
+		let data = block_files.read(position)
+			.and_then(|bytes| deserialize(&*bytes).map_err(|err| Error::DatabaseError(format!("{:?}", err))));
+		match data {
+			Ok(data) => Some(data),
+			Err(err) => {
+				warn!(target: "db", "Failed to read block {} from block files: {}", hash.reversed(), err);
+				None
+			},
+		}
+	}
+
 	fn get(&self, key: Key) -> Option<Value> {
 		self.db.get(&key).expect("db value to be fine").into_option()
 	}
@@ -385,6 +1239,27 @@ impl<T> BlockChainDatabase<T> where T: KeyValueDatabase {
 	}
 }
 
+/// Meta of the snapshot transaction with given unspent outputs. All other outputs are marked as spent
+fn snapshot_transaction_meta(coin: &Coin, unspent: &[u32]) -> TransactionMeta {
+	let outputs = unspent.iter().max().map_or(0, |index| *index as usize + 1);
+	let mut meta = if coin.is_coinbase {
+		TransactionMeta::new_coinbase(coin.height, outputs)
+	} else {
+		TransactionMeta::new(coin.height, outputs)
+	};
+
+	let mut is_spent = vec![true; outputs];
+	for index in unspent {
+		is_spent[*index as usize] = false;
+	}
+	for (index, is_spent) in is_spent.into_iter().enumerate() {
+		if is_spent {
+			meta.denote_used(index);
+		}
+	}
+	meta
+}
+
 impl<T> BlockHeaderProvider for BlockChainDatabase<T> where T: KeyValueDatabase {
 	fn block_header_bytes(&self, block_ref: BlockRef) -> Option<Bytes> {
 		self.block_header(block_ref).map(|header| serialize(&header))
@@ -409,14 +1284,7 @@ impl<T> BlockProvider for BlockChainDatabase<T> where T: KeyValueDatabase {
 	}
 
 	fn block(&self, block_ref: BlockRef) -> Option<Block> {
-		self.resolve_hash(block_ref)
-			.and_then(|block_hash| {
-				self.block_header(block_hash.clone().into())
-					.map(|header| {
-						let transactions = self.block_transactions(block_hash.into());
-						Block::new(header, transactions)
-					})
-			})
+		self.indexed_block(block_ref).map(IndexedBlock::to_raw_block)
 	}
 
 	fn contains_block(&self, block_ref: BlockRef) -> bool {
@@ -426,18 +1294,26 @@ impl<T> BlockProvider for BlockChainDatabase<T> where T: KeyValueDatabase {
 	}
 
 	fn block_transaction_hashes(&self, block_ref: BlockRef) -> Vec<H256> {
-		self.resolve_hash(block_ref)
-			.and_then(|hash| self.get(Key::BlockTransactions(hash)))
-			.and_then(Value::as_block_transactions)
-			.map(List::into)
+		let hash = match self.resolve_hash(block_ref) {
+			Some(hash) => hash,
+			None => return Vec::new(),
+		};
+
+		if let Some(hashes) = self.get(Key::BlockTransactions(hash.clone())).and_then(Value::as_block_transactions) {
+			return hashes.into();
+		}
+
+		// blocks, inserted to the block files before transaction hashes have been kept
+		// in the database
+		self.read_block_file(&hash)
+			.map(|block| block.transactions.into_iter().map(|tx| tx.hash).collect())
 			.unwrap_or_default()
 	}
 
 	fn block_transactions(&self, block_ref: BlockRef) -> Vec<Transaction> {
-		self.block_transaction_hashes(block_ref)
+		self.indexed_block_transactions(block_ref)
 			.into_iter()
-			.filter_map(|hash| self.get(Key::Transaction(hash)))
-			.filter_map(Value::as_transaction)
+			.map(|tx| tx.raw)
 			.collect()
 	}
 }
@@ -453,18 +1329,38 @@ impl<T> IndexedBlockProvider for BlockChainDatabase<T> where T: KeyValueDatabase
 	}
 
 	fn indexed_block(&self, block_ref: BlockRef) -> Option<IndexedBlock> {
-		self.resolve_hash(block_ref)
-			.and_then(|block_hash| {
-				self.indexed_block_header(block_hash.clone().into())
-					.map(|header| {
-						let transactions = self.indexed_block_transactions(block_hash.into());
-						IndexedBlock::new(header, transactions)
-					})
+		let block_hash = self.resolve_hash(block_ref)?;
+		if let Some(block) = self.read_block_file(&block_hash) {
+			return Some(block);
+		}
+
+		// block is read from the database only if all of its transactions are there. Blocks,
+		// which are pruned or removed with their block files, are missing
+		let header = self.indexed_block_header(block_hash.clone().into())?;
+		let tx_hashes: Vec<H256> = self.get(Key::BlockTransactions(block_hash))
+			.and_then(Value::as_block_transactions)
+			.map(List::into)?;
+		let transactions = tx_hashes.into_iter()
+			.map(|hash| {
+				self.get(Key::Transaction(hash.clone()))
+					.and_then(Value::as_transaction)
+					.map(|tx| IndexedTransaction::new(hash, tx))
 			})
+			.collect::<Option<Vec<_>>>()?;
+		Some(IndexedBlock::new(header, transactions))
 	}
 
 	fn indexed_block_transactions(&self, block_ref: BlockRef) -> Vec<IndexedTransaction> {
-		self.block_transaction_hashes(block_ref)
+		let hash = match self.resolve_hash(block_ref) {
+			Some(hash) => hash,
+			None => return Vec::new(),
+		};
+
+		if let Some(block) = self.read_block_file(&hash) {
+			return block.transactions;
+		}
+
+		self.block_transaction_hashes(hash.into())
 			.into_iter()
 			.filter_map(|hash| {
 				self.get(Key::Transaction(hash.clone()))
@@ -482,26 +1378,53 @@ impl<T> TransactionMetaProvider for BlockChainDatabase<T> where T: KeyValueDatab
 	}
 }
 
+impl<T> TransactionLocationProvider for BlockChainDatabase<T> where T: KeyValueDatabase {
+	fn transaction_location(&self, hash: &H256) -> Option<TransactionLocation> {
+		if let Some(location) = self.get(Key::TransactionLocation(hash.clone())).and_then(Value::as_transaction_location) {
+			return Some(location);
+		}
+
+		// transactions, canonized before the location index has been introduced, are searched
+		// in the block at the height from transaction meta
+		let block_hash = self.transaction_meta(hash)
+			.and_then(|meta| self.block_hash(meta.height()))?;
+		self.block_transaction_hashes(block_hash.clone().into())
+			.into_iter()
+			.position(|tx_hash| tx_hash == *hash)
+			.map(|index| TransactionLocation {
+				block_hash: block_hash,
+				index: index as u32,
+			})
+	}
+}
+
 impl<T> TransactionProvider for BlockChainDatabase<T> where T: KeyValueDatabase {
 	fn transaction_bytes(&self, hash: &H256) -> Option<Bytes> {
 		self.transaction(hash).map(|tx| serialize(&tx))
 	}
 
 	fn transaction(&self, hash: &H256) -> Option<Transaction> {
-		self.get(Key::Transaction(hash.clone()))
-			.and_then(Value::as_transaction)
+		if let Some(tx) = self.get(Key::Transaction(hash.clone())).and_then(Value::as_transaction) {
+			return Some(tx);
+		}
+
+		// transactions of the blocks, stored in the block files, are found with location index
+		let location = self.transaction_location(hash)?;
+		self.read_block_file_transaction(&location)
 	}
 }
 
 impl<T> TransactionOutputProvider for BlockChainDatabase<T> where T: KeyValueDatabase {
 	fn transaction_output(&self, prevout: &OutPoint, _transaction_index: usize) -> Option<TransactionOutput> {
 		// return previous transaction outputs only for canon chain transactions
-		self.transaction_meta(&prevout.hash)
-			.and_then(|_| self.transaction(&prevout.hash))
-			.and_then(|tx| tx.outputs.into_iter().nth(prevout.index as usize))
+		self.coin(prevout).map(Into::into)
 	}
 
 	fn is_spent(&self, prevout: &OutPoint) -> bool {
+		if self.coin(prevout).is_some() {
+			return false;
+		}
+
 		self.transaction_meta(&prevout.hash)
 			.and_then(|meta| meta.is_spent(prevout.index as usize))
 			.unwrap_or(false)
@@ -568,6 +1491,255 @@ impl<T> Store for BlockChainDatabase<T> where T: KeyValueDatabase {
 	}
 }
 
+impl<T> SpentInfoProvider for BlockChainDatabase<T> where T: KeyValueDatabase {
+	fn is_spent_index_enabled(&self) -> bool {
+		self.spent_index
+	}
+
+	fn spent_info(&self, outpoint: &OutPoint) -> Option<SpentInfo> {
+		if !self.spent_index {
+			return None;
+		}
+
+		self.get(Key::SpentInfo(outpoint.clone()))
+			.and_then(Value::as_spent_info)
+	}
+}
+
+impl<T> PruneStore for BlockChainDatabase<T> where T: KeyValueDatabase {
+	fn prune_mode(&self) -> PruneMode {
+		self.prune_mode
+	}
+
+	fn pruned_height(&self) -> Option<u32> {
+		self.get(Key::Meta(KEY_PRUNED_BLOCK_NUMBER))
+			.and_then(Value::as_meta)
+			.map(|number| deserialize(&*number).expect("Inconsistent DB. Invalid pruned block number."))
+	}
+
+	fn prune(&self, height: u32) -> Result<Option<u32>, Error> {
+		if !self.prune_mode.is_enabled() {
+			return Err(Error::PruningDisabled);
+		}
+
+		// hold the lock, so that best block isn't changed while we're pruning
+		let best_block = self.best_block.write();
+		let pruned_height = self.pruned_height();
+		if best_block.number < MIN_BLOCKS_TO_KEEP {
+			return Ok(pruned_height);
+		}
+
+		let prune_from = pruned_height.map(|number| number + 1).unwrap_or(0);
+		let prune_to = cmp::min(height, best_block.number - MIN_BLOCKS_TO_KEEP);
+		if prune_from > prune_to {
+			return Ok(pruned_height);
+		}
+
+		let mut update = DBTransaction::new();
+		let first_used_file = self.prune_blocks(&mut update, prune_from, prune_to)?;
+		self.db.write(update).map_err(Error::DatabaseError)?;
+		self.remove_unused_block_files(first_used_file);
+		Ok(Some(prune_to))
+	}
+}
+
+impl<T> SnapshotStore for BlockChainDatabase<T> where T: KeyValueDatabase {
+	fn dump_snapshot(&self, writer: &mut io::Write) -> Result<SnapshotInfo, Error> {
+		// hold the lock, so that UTXO set isn't changed while we're writing it
+		let best_block = self.best_block.read();
+		let mut snapshot = SnapshotWriter::new(writer, &best_block.hash, best_block.number)?;
+		for number in 0..best_block.number + 1 {
+			let header = self.block_header(number.into())
+				.ok_or_else(|| Error::DatabaseError(format!("Missing canon block {}", number)))?;
+			snapshot.write_header(&header)?;
+		}
+
+		self.db.for_each_coin(&mut |outpoint, coin| snapshot.write_coin(&outpoint, &coin).map_err(String::from))
+			.map_err(Error::DatabaseError)?;
+		let (coins_count, content_hash) = snapshot.finish()?;
+
+		Ok(SnapshotInfo {
+			base: SnapshotBase {
+				block_hash: best_block.hash.clone(),
+				block_number: best_block.number,
+				content_hash: content_hash,
+			},
+			coins_count: coins_count,
+		})
+	}
+
+	fn load_snapshot(&self, path: &Path, trusted: &[SnapshotBase]) -> Result<SnapshotInfo, Error> {
+		let mut best_block = self.best_block.write();
+		if best_block.number != 0 || best_block.hash.is_zero() {
+			return Err(Error::InvalidSnapshot("snapshot can only be loaded into chainstate with genesis block only".into()));
+		}
+
+		// the whole snapshot is verified before anything is written to the database
+		let genesis_hash = best_block.hash.clone();
+		self.read_snapshot(path, trusted, &genesis_hash, false)?;
+		let info = self.read_snapshot(path, trusted, &genesis_hash, true)?;
+
+		*best_block = BestBlock {
+			number: info.base.block_number,
+			hash: info.base.block_hash.clone(),
+		};
+		Ok(info)
+	}
+
+	fn snapshot_base(&self) -> Option<SnapshotBase> {
+		self.get(Key::Meta(KEY_SNAPSHOT_BASE))
+			.and_then(Value::as_meta)
+			.map(|base| deserialize(&*base).expect("Inconsistent DB. Invalid snapshot base."))
+	}
+
+	fn snapshot_validation(&self) -> Option<SnapshotValidation> {
+		self.get(Key::Meta(KEY_SNAPSHOT_VALIDATION))
+			.and_then(Value::as_meta)
+			.map(|validation| deserialize(&*validation).expect("Inconsistent DB. Invalid snapshot validation state."))
+	}
+
+	fn set_snapshot_validation(&self, validation: SnapshotValidation) -> Result<(), Error> {
+		let mut update = DBTransaction::new();
+		update.insert(KeyValue::Meta(KEY_SNAPSHOT_VALIDATION, serialize(&validation)));
+		self.db.write(update).map_err(Error::DatabaseError)
+	}
+}
+
+impl<T> ChainTipStore for BlockChainDatabase<T> where T: KeyValueDatabase {
+	fn chain_tips(&self) -> Vec<ChainTip> {
+		let best_block = self.best_block();
+		let mut result = vec![ChainTip {
+			hash: best_block.hash.clone(),
+			number: best_block.number,
+			branch_len: 0,
+			status: ChainTipStatus::Active,
+		}];
+
+		for tip in self.read_chain_tips() {
+			if tip.hash == best_block.hash {
+				continue;
+			}
+
+			let (fork_number, distance) = match self.fork_point(&tip.base) {
+				Some(fork_point) => fork_point,
+				None => continue,
+			};
+			// stored blocks of the canon chain are not tips
+			if tip.base == tip.hash && distance == 0 {
+				continue;
+			}
+
+			let status = match tip.status {
+				ChainTipStatus::Active | ChainTipStatus::ValidFork if self.is_invalid_block(&tip.base) => ChainTipStatus::Invalid,
+				ChainTipStatus::Active => ChainTipStatus::ValidFork,
+				ChainTipStatus::HeadersOnly if self.is_invalid_block(&tip.base) => ChainTipStatus::Invalid,
+				status => status,
+			};
+
+			result.push(ChainTip {
+				hash: tip.hash,
+				number: tip.number,
+				branch_len: tip.number.saturating_sub(fork_number),
+				status: status,
+			});
+		}
+
+		result.sort_by(|a, b| b.number.cmp(&a.number));
+		result
+	}
+
+	fn insert_chain_tip(&self, hash: &H256, base: &H256, distance: u32, status: ChainTipStatus) -> Result<(), Error> {
+		let number = match self.fork_point(base) {
+			Some((fork_number, base_distance)) => fork_number + base_distance + distance,
+			None => return Err(Error::UnknownParent),
+		};
+
+		let _chain_tips_lock = self.chain_tips_lock.lock();
+		let mut tips = self.read_chain_tips();
+		tips.retain(|tip| tip.hash != *hash);
+		tips.push(ChainTipState {
+			hash: hash.clone(),
+			number: number,
+			base: base.clone(),
+			status: status,
+		});
+
+		let mut update = DBTransaction::new();
+		update.insert(KeyValue::Meta(KEY_CHAIN_TIPS, serialize(&List::from(tips))));
+		self.db.write(update).map_err(Error::DatabaseError)
+	}
+
+	fn is_invalid_block(&self, hash: &H256) -> bool {
+		let invalid_blocks = self.read_invalid_blocks();
+		if invalid_blocks.is_empty() {
+			return false;
+		}
+
+		// invalid blocks are normally decanonized, but this isn't true while invalidation is in progress
+		let first_invalid_number = invalid_blocks.iter().filter_map(|hash| self.block_number(hash)).min();
+		let mut next_hash = hash.clone();
+		for _ in 0..MAX_FORK_ROUTE_PRESET {
+			if invalid_blocks.contains(&next_hash) {
+				return true;
+			}
+
+			if let Some(number) = self.block_number(&next_hash) {
+				return first_invalid_number.map_or(false, |first_invalid_number| first_invalid_number <= number);
+			}
+
+			next_hash = match self.block_header(next_hash.into()) {
+				Some(header) => header.previous_header_hash,
+				None => return false,
+			};
+		}
+
+		false
+	}
+
+	fn invalidate_block(&self, hash: &H256) -> Result<SideChainOrigin, Error> {
+		if !self.contains_block(hash.clone().into()) {
+			return Err(Error::UnknownBlock);
+		}
+		if self.block_number(hash) == Some(0) {
+			return Err(Error::CannotInvalidateGenesis);
+		}
+
+		let mut invalid_blocks = self.read_invalid_blocks();
+		if !invalid_blocks.contains(hash) {
+			invalid_blocks.push(hash.clone());
+			let mut update = DBTransaction::new();
+			update.insert(KeyValue::Meta(KEY_INVALID_BLOCKS, serialize(&List::from(invalid_blocks))));
+			self.db.write(update).map_err(Error::DatabaseError)?;
+		}
+
+		self.switch_to_best_valid_branch()
+	}
+
+	fn reconsider_block(&self, hash: &H256) -> Result<SideChainOrigin, Error> {
+		{
+			let _chain_tips_lock = self.chain_tips_lock.lock();
+			let mut tips = self.read_chain_tips();
+			if !self.contains_block(hash.clone().into()) && !tips.iter().any(|tip| tip.hash == *hash) {
+				return Err(Error::UnknownBlock);
+			}
+
+			// forget about verification failures in this branch
+			tips.retain(|tip| tip.status != ChainTipStatus::Invalid || (tip.hash != *hash && !self.is_descendant_of(&tip.base, hash)));
+
+			// and about invalidated ancestors and descendants
+			let mut invalid_blocks = self.read_invalid_blocks();
+			invalid_blocks.retain(|invalid| !self.is_descendant_of(invalid, hash) && !self.is_descendant_of(hash, invalid));
+
+			let mut update = DBTransaction::new();
+			update.insert(KeyValue::Meta(KEY_CHAIN_TIPS, serialize(&List::from(tips))));
+			update.insert(KeyValue::Meta(KEY_INVALID_BLOCKS, serialize(&List::from(invalid_blocks))));
+			self.db.write(update).map_err(Error::DatabaseError)?;
+		}
+
+		self.switch_to_best_valid_branch()
+	}
+}
+
 impl<T> ConfigStore for BlockChainDatabase<T> where T: KeyValueDatabase {
 	fn consensus_fork(&self) -> Result<Option<String>, Error> {
 		match self.db.get(&Key::Configuration("consensus_fork"))
@@ -0,0 +1,324 @@
+//! Flat files with raw blocks.
+//!
+//! Blocks are appended to the sequential `blkNNNNN.dat` files, serialized with transaction
+//! witnesses. When the next block doesn't fit into the current file, the new file is started.
+//! Files have no index of their own: position of every block is stored in the database.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use parking_lot::Mutex;
+use primitives::io as ser_io;
+use bytes::Bytes;
+use chain::IndexedBlock;
+use ser::{
+	Serializable, Deserializable, Stream, Reader, CompactInteger, serialize, serialize_with_flags,
+	SERIALIZE_TRANSACTION_WITNESS
+};
+use storage::Error;
+
+/// Max size of the single block file
+pub const MAX_BLOCK_FILE_SIZE: u32 = 128 * 1024 * 1024;
+
+const BLOCK_FILE_PREFIX: &'static str = "blk";
+const BLOCK_FILE_SUFFIX: &'static str = ".dat";
+
+fn io_error(err: io::Error) -> Error {
+	Error::DatabaseError(format!("Block files error: {}", err))
+}
+
+/// Position of the serialized block in the block files
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockPosition {
+	/// Number of the block file
+	pub file: u32,
+	/// Offset of the block in the file
+	pub offset: u32,
+	/// Length of the serialized block
+	pub length: u32,
+	/// Offsets of the block transactions, relative to the block offset.
+	/// Empty for blocks, appended before the offsets have been introduced
+	pub tx_offsets: Vec<u32>,
+}
+
+impl BlockPosition {
+	/// Position of the block transaction with given index
+	pub fn transaction_position(&self, index: u32) -> Option<BlockPosition> {
+		let start = *self.tx_offsets.get(index as usize)?;
+		let end = self.tx_offsets.get(index as usize + 1).cloned().unwrap_or(self.length);
+		Some(BlockPosition {
+			file: self.file,
+			offset: self.offset + start,
+			length: end - start,
+			tx_offsets: Vec::new(),
+		})
+	}
+}
+
+impl Serializable for BlockPosition {
+	fn serialize(&self, stream: &mut Stream) {
+		stream
+			.append(&self.file)
+			.append(&self.offset)
+			.append(&self.length)
+			.append_list(&self.tx_offsets);
+	}
+}
+
+impl Deserializable for BlockPosition {
+	fn deserialize<T>(reader: &mut Reader<T>) -> Result<Self, ser_io::Error> where T: ser_io::Read {
+		let file = reader.read()?;
+		let offset = reader.read()?;
+		let length = reader.read()?;
+		let tx_offsets = if reader.is_finished() { Vec::new() } else { reader.read_list()? };
+
+		let position = BlockPosition {
+			file: file,
+			offset: offset,
+			length: length,
+			tx_offsets: tx_offsets,
+		};
+
+		Ok(position)
+	}
+}
+
+/// File, blocks are currently appended to
+struct CurrentFile {
+	number: u32,
+	size: u32,
+	file: Option<File>,
+	/// Total size of all block files
+	total_size: u64,
+}
+
+/// Sequential flat files with raw blocks
+pub struct BlockFiles {
+	path: PathBuf,
+	max_file_size: u32,
+	current: Mutex<CurrentFile>,
+}
+
+impl BlockFiles {
+	/// Opens block files in the given directory. Appending continues at the end of the last file
+	pub fn open<P>(path: P) -> Result<Self, Error> where P: AsRef<Path> {
+		fs::create_dir_all(path.as_ref()).map_err(io_error)?;
+
+		let mut last_file = None;
+		let mut total_size = 0;
+		for entry in fs::read_dir(path.as_ref()).map_err(io_error)? {
+			let entry = entry.map_err(io_error)?;
+			if let Some(number) = entry.file_name().to_str().and_then(parse_file_name) {
+				total_size += entry.metadata().map_err(io_error)?.len();
+				if last_file.map_or(true, |last| number > last) {
+					last_file = Some(number);
+				}
+			}
+		}
+
+		let number = last_file.unwrap_or_default();
+		let size = match fs::metadata(path.as_ref().join(file_name(number))) {
+			Ok(metadata) => metadata.len() as u32,
+			Err(ref err) if err.kind() == io::ErrorKind::NotFound => 0,
+			Err(err) => return Err(io_error(err)),
+		};
+
+		Ok(BlockFiles {
+			path: path.as_ref().to_owned(),
+			max_file_size: MAX_BLOCK_FILE_SIZE,
+			current: Mutex::new(CurrentFile {
+				number: number,
+				size: size,
+				file: None,
+				total_size: total_size,
+			}),
+		})
+	}
+
+	/// Sets max size of the single block file
+	pub fn with_max_file_size(mut self, max_file_size: u32) -> Self {
+		self.max_file_size = max_file_size;
+		self
+	}
+
+	/// Appends block to the last file and returns its position. Block is synced to the disk
+	/// before position is returned
+	pub fn append(&self, block: &IndexedBlock) -> Result<BlockPosition, Error> {
+		let mut bytes = Vec::new();
+		bytes.extend_from_slice(&serialize(&block.header.raw));
+		bytes.extend_from_slice(&serialize(&CompactInteger::from(block.transactions.len())));
+		let mut tx_offsets = Vec::with_capacity(block.transactions.len());
+		for tx in &block.transactions {
+			tx_offsets.push(bytes.len() as u32);
+			bytes.extend_from_slice(&serialize_with_flags(&tx.raw, SERIALIZE_TRANSACTION_WITNESS));
+		}
+		let length = bytes.len() as u32;
+
+		let mut current = self.current.lock();
+		// block, larger than max file size, is written to the empty file
+		if current.size != 0 && current.size.saturating_add(length) > self.max_file_size {
+			current.number += 1;
+			current.size = 0;
+			current.file = None;
+		}
+
+		if current.file.is_none() {
+			let file = OpenOptions::new()
+				.create(true)
+				.append(true)
+				.open(self.path.join(file_name(current.number)))
+				.map_err(io_error)?;
+			current.file = Some(file);
+		}
+
+		let position = BlockPosition {
+			file: current.number,
+			offset: current.size,
+			length: length,
+			tx_offsets: tx_offsets,
+		};
+
+		{
+			let file = current.file.as_mut().expect("file is opened above; qed");
+			file.write_all(&bytes).map_err(io_error)?;
+			file.flush().map_err(io_error)?;
+			file.sync_data().map_err(io_error)?;
+		}
+		current.size += length;
+		current.total_size += length as u64;
+
+		Ok(position)
+	}
+
+	/// Reads serialized block at given position
+	pub fn read(&self, position: &BlockPosition) -> Result<Bytes, Error> {
+		let mut file = File::open(self.path.join(file_name(position.file))).map_err(io_error)?;
+		file.seek(SeekFrom::Start(position.offset as u64)).map_err(io_error)?;
+		let mut bytes = vec![0u8; position.length as usize];
+		file.read_exact(&mut bytes).map_err(io_error)?;
+		Ok(bytes.into())
+	}
+
+	/// Removes all block files with numbers less than given. Positions of the blocks in these
+	/// files must be marked as unavailable in the database before
+	pub fn remove_files_before(&self, number: u32) -> Result<(), Error> {
+		let mut current = self.current.lock();
+		for entry in fs::read_dir(&self.path).map_err(io_error)? {
+			let entry = entry.map_err(io_error)?;
+			let file_number = match entry.file_name().to_str().and_then(parse_file_name) {
+				Some(file_number) => file_number,
+				None => continue,
+			};
+
+			if file_number < number && file_number != current.number {
+				trace!(target: "db", "removing block file {}", file_number);
+				let file_size = entry.metadata().map_err(io_error)?.len();
+				fs::remove_file(entry.path()).map_err(io_error)?;
+				current.total_size -= file_size;
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Returns total size of all block files
+	pub fn size(&self) -> u64 {
+		self.current.lock().total_size
+	}
+
+	/// Returns number of the file, blocks are currently appended to
+	pub fn current_file(&self) -> u32 {
+		self.current.lock().number
+	}
+}
+
+fn file_name(number: u32) -> String {
+	format!("{}{:05}{}", BLOCK_FILE_PREFIX, number, BLOCK_FILE_SUFFIX)
+}
+
+fn parse_file_name(name: &str) -> Option<u32> {
+	if !name.starts_with(BLOCK_FILE_PREFIX) || !name.ends_with(BLOCK_FILE_SUFFIX) {
+		return None;
+	}
+
+	name[BLOCK_FILE_PREFIX.len()..name.len() - BLOCK_FILE_SUFFIX.len()].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+	extern crate tempdir;
+	extern crate test_data;
+
+	use self::tempdir::TempDir;
+	use chain::{IndexedBlock, Transaction};
+	use ser::{serialize, deserialize};
+	use super::{BlockFiles, BlockPosition, file_name, parse_file_name};
+
+	#[test]
+	fn test_block_position_serialization() {
+		let position = BlockPosition {
+			file: 1,
+			offset: 2,
+			length: 3,
+			tx_offsets: vec![4, 5],
+		};
+
+		assert_eq!(deserialize::<_, BlockPosition>(serialize(&position).as_ref()).unwrap(), position);
+		// positions without transaction offsets
+		assert_eq!(deserialize::<_, BlockPosition>(&[1u8, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0] as &[u8]).unwrap(), BlockPosition {
+			tx_offsets: Vec::new(),
+			..position
+		});
+	}
+
+	#[test]
+	fn test_block_file_name() {
+		assert_eq!(file_name(7), "blk00007.dat");
+		assert_eq!(parse_file_name("blk00007.dat"), Some(7));
+		assert_eq!(parse_file_name("rev00007.dat"), None);
+		assert_eq!(parse_file_name("blk00007.tmp"), None);
+	}
+
+	#[test]
+	fn test_block_files_append_and_read() {
+		let dir = TempDir::new("blocks").unwrap();
+		let b0: IndexedBlock = test_data::block_h0().into();
+		let b1: IndexedBlock = test_data::block_h1().into();
+		let b2: IndexedBlock = test_data::block_h2().into();
+
+		let (p0, p1) = {
+			let files = BlockFiles::open(dir.path()).unwrap().with_max_file_size(400);
+			let p0 = files.append(&b0).unwrap();
+			let p1 = files.append(&b1).unwrap();
+			(p0, p1)
+		};
+
+		assert_eq!(p0.file, 0);
+		assert_eq!(p0.offset, 0);
+		// second block doesn't fit into the first file
+		assert_eq!(p1.file, 1);
+		assert_eq!(p1.offset, 0);
+
+		// appending continues at the end of the last file after reopening
+		let files = BlockFiles::open(dir.path()).unwrap();
+		let p2 = files.append(&b2).unwrap();
+		assert_eq!(p2.file, 1);
+		assert_eq!(p2.offset, p1.length);
+
+		assert_eq!(deserialize::<_, IndexedBlock>(files.read(&p0).unwrap().as_ref()).unwrap(), b0);
+		assert_eq!(deserialize::<_, IndexedBlock>(files.read(&p1).unwrap().as_ref()).unwrap(), b1);
+		assert_eq!(deserialize::<_, IndexedBlock>(files.read(&p2).unwrap().as_ref()).unwrap(), b2);
+
+		let tx_position = p2.transaction_position(0).unwrap();
+		assert_eq!(deserialize::<_, Transaction>(files.read(&tx_position).unwrap().as_ref()).unwrap(), b2.transactions[0].raw);
+		assert_eq!(p2.transaction_position(1), None);
+
+		assert_eq!(files.size(), (p0.length + p1.length + p2.length) as u64);
+		assert_eq!(files.current_file(), 1);
+
+		files.remove_files_before(1).unwrap();
+		assert!(files.read(&p0).is_err());
+		assert!(files.read(&p1).is_ok());
+		assert_eq!(files.size(), (p1.length + p2.length) as u64);
+	}
+}
@@ -0,0 +1,171 @@
+//! Storage engines, `KeyValueDatabase` can be opened with.
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use bytes::Bytes;
+use ser::deserialize;
+use chain::OutPoint;
+use storage::Coin;
+use kv::{
+	Transaction, RawTransaction, RawKeyValue, RawOperation, RawKey, Location, Key, KeyState, Value,
+	KeyValueDatabase, DiskDatabase, DatabaseConfig, LogDatabase, LOG_FILE_NAME, COL_COINS
+};
+
+/// Max number of operations, copied at once by `migrate`
+const MAX_MIGRATION_OPERATIONS_PER_WRITE: usize = 16 * 1024;
+
+/// Raw key-value storage engine
+pub trait DatabaseBackend: Send + Sync {
+	/// Commit transaction to database
+	fn write_raw(&self, tx: RawTransaction) -> Result<(), String>;
+
+	/// Get value by key
+	fn get_raw(&self, key: &RawKey) -> Result<Option<Bytes>, String>;
+
+	/// Iterate over all key-value pairs of the location, in order of keys. Iteration should be
+	/// stopped after the first read error
+	fn iter_raw<'a>(&'a self, location: Location) -> Box<Iterator<Item = Result<(Box<[u8]>, Box<[u8]>), String>> + 'a>;
+}
+
+impl DatabaseBackend for DiskDatabase {
+	fn write_raw(&self, tx: RawTransaction) -> Result<(), String> {
+		DiskDatabase::write(self, tx)
+	}
+
+	fn get_raw(&self, key: &RawKey) -> Result<Option<Bytes>, String> {
+		DiskDatabase::get(self, key)
+	}
+
+	fn iter_raw<'a>(&'a self, location: Location) -> Box<Iterator<Item = Result<(Box<[u8]>, Box<[u8]>), String>> + 'a> {
+		Box::new(DiskDatabase::iter(self, location).map(Ok))
+	}
+}
+
+impl DatabaseBackend for LogDatabase {
+	fn write_raw(&self, tx: RawTransaction) -> Result<(), String> {
+		LogDatabase::write(self, tx)
+	}
+
+	fn get_raw(&self, key: &RawKey) -> Result<Option<Bytes>, String> {
+		LogDatabase::get(self, key)
+	}
+
+	fn iter_raw<'a>(&'a self, location: Location) -> Box<Iterator<Item = Result<(Box<[u8]>, Box<[u8]>), String>> + 'a> {
+		Box::new(LogDatabase::iter(self, location))
+	}
+}
+
+impl KeyValueDatabase for Box<DatabaseBackend> {
+	fn write(&self, tx: Transaction) -> Result<(), String> {
+		self.write_raw((&tx).into())
+	}
+
+	fn get(&self, key: &Key) -> Result<KeyState<Value>, String> {
+		match self.get_raw(&key.into())? {
+			Some(value) => Ok(KeyState::Insert(Value::for_key(key, &value)?)),
+			None => Ok(KeyState::Unknown)
+		}
+	}
+
+	fn for_each_coin(&self, f: &mut FnMut(OutPoint, Coin) -> Result<(), String>) -> Result<(), String> {
+		for pair in self.iter_raw(Location::Column(COL_COINS)) {
+			let (key, value) = pair?;
+			let outpoint = deserialize(&*key).map_err(|e| format!("Invalid coin key: {:?}", e))?;
+			let coin = deserialize(&*value).map_err(|e| format!("Invalid coin: {:?}", e))?;
+			f(outpoint, coin)?;
+		}
+		Ok(())
+	}
+}
+
+/// Storage engine of the database
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DatabaseEngine {
+	/// RocksDB
+	RocksDb,
+	/// Embedded log-structured store
+	Log,
+}
+
+impl Default for DatabaseEngine {
+	fn default() -> Self {
+		DatabaseEngine::RocksDb
+	}
+}
+
+impl fmt::Display for DatabaseEngine {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			DatabaseEngine::RocksDb => f.write_str("rocksdb"),
+			DatabaseEngine::Log => f.write_str("log"),
+		}
+	}
+}
+
+impl FromStr for DatabaseEngine {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"rocksdb" => Ok(DatabaseEngine::RocksDb),
+			"log" => Ok(DatabaseEngine::Log),
+			_ => Err(format!("Unknown database backend: {}. Should be one of: rocksdb, log", s)),
+		}
+	}
+}
+
+impl DatabaseEngine {
+	/// Detects engine of the existing database. Returns None if there's no database at given path
+	pub fn detect<P>(path: P) -> Option<Self> where P: AsRef<Path> {
+		if path.as_ref().join("CURRENT").exists() {
+			Some(DatabaseEngine::RocksDb)
+		} else if path.as_ref().join(LOG_FILE_NAME).exists() {
+			Some(DatabaseEngine::Log)
+		} else {
+			None
+		}
+	}
+
+	/// Opens database at given path. Fails if database, created with other engine, exists there
+	pub fn open<P>(&self, config: DatabaseConfig, path: P) -> Result<Box<DatabaseBackend>, String> where P: AsRef<Path> {
+		match DatabaseEngine::detect(path.as_ref()) {
+			Some(engine) if engine != *self => return Err(format!(
+				"Database at {} has been created with {} backend. Use `db migrate {}` to convert it",
+				path.as_ref().display(), engine, self
+			)),
+			_ => (),
+		}
+
+		match *self {
+			DatabaseEngine::RocksDb => Ok(Box::new(DiskDatabase::open(config, path)?)),
+			DatabaseEngine::Log => Ok(Box::new(LogDatabase::open(path)?)),
+		}
+	}
+}
+
+/// Copies all key-value pairs of given columns from one database to another.
+/// Returns number of copied pairs
+pub fn migrate(from: &DatabaseBackend, to: &DatabaseBackend, columns: u32) -> Result<u64, String> {
+	let mut copied = 0;
+	for column in 0..columns {
+		let mut tx = RawTransaction::default();
+		for pair in from.iter_raw(Location::Column(column)) {
+			let (key, value) = pair?;
+			tx.operations.push(RawOperation::Insert(RawKeyValue {
+				location: Location::Column(column),
+				key: key.to_vec().into(),
+				value: value.to_vec().into(),
+			}));
+			copied += 1;
+
+			if tx.operations.len() == MAX_MIGRATION_OPERATIONS_PER_WRITE {
+				to.write_raw(tx)?;
+				tx = RawTransaction::default();
+			}
+		}
+		to.write_raw(tx)?;
+	}
+
+	Ok(copied)
+}
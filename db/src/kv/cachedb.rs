@@ -1,20 +1,35 @@
 use lru_cache::LruCache;
 use parking_lot::Mutex;
 use hash::H256;
-use chain::BlockHeader;
+use chain::{BlockHeader, OutPoint};
+use storage::Coin;
 use kv::{KeyValueDatabase, KeyState, Operation, KeyValue, Key, Value, Transaction};
 
+/// Approximate memory, occupied by the single cached coin (outpoint, coin with the script and cache links)
+const COIN_CACHE_ENTRY_SIZE: usize = 160;
+/// Default memory limit of the coins cache
+pub const DEFAULT_COIN_CACHE_SIZE: usize = 128 * 1024 * 1024;
+
 pub struct CacheDatabase<T> where T: KeyValueDatabase {
 	db: T,
 	header: Mutex<LruCache<H256, KeyState<BlockHeader>>>,
+	/// Recently created, spent and read coins. Writes are still passed to the
+	/// underlying database, which is responsible for batching them to disk.
+	coin: Mutex<LruCache<OutPoint, KeyState<Coin>>>,
 }
 
 impl<T> CacheDatabase<T> where T: KeyValueDatabase {
 	pub fn new(db: T) -> Self {
+		Self::with_coin_cache_size(db, DEFAULT_COIN_CACHE_SIZE)
+	}
+
+	/// Creates cache, where coins occupy approximately `coin_cache_size` bytes
+	pub fn with_coin_cache_size(db: T, coin_cache_size: usize) -> Self {
 		CacheDatabase {
 			db: db,
 			// 144 (blocks per day) * 14 (days) + 100 (arbitrary number)
 			header: Mutex::new(LruCache::new(2116)),
+			coin: Mutex::new(LruCache::new(coin_cache_size / COIN_CACHE_ENTRY_SIZE)),
 		}
 	}
 }
@@ -29,6 +44,12 @@ impl<T> KeyValueDatabase for CacheDatabase<T> where T: KeyValueDatabase {
 				Operation::Delete(Key::BlockHeader(ref hash)) => {
 					self.header.lock().insert(hash.clone(), KeyState::Delete);
 				},
+				Operation::Insert(KeyValue::Coin(ref outpoint, ref coin)) => {
+					self.coin.lock().insert(outpoint.clone(), KeyState::Insert(coin.clone()));
+				},
+				Operation::Delete(Key::Coin(ref outpoint)) => {
+					self.coin.lock().insert(outpoint.clone(), KeyState::Delete);
+				},
 				_ => (),
 			}
 		}
@@ -36,12 +57,36 @@ impl<T> KeyValueDatabase for CacheDatabase<T> where T: KeyValueDatabase {
 	}
 
 	fn get(&self, key: &Key) -> Result<KeyState<Value>, String> {
-		if let Key::BlockHeader(ref hash) = *key {
-			let mut header = self.header.lock();
-			if let Some(state) = header.get_mut(hash) {
-				return Ok(state.clone().map(Value::BlockHeader))
-			}
+		match *key {
+			Key::BlockHeader(ref hash) => {
+				let mut header = self.header.lock();
+				if let Some(state) = header.get_mut(hash) {
+					return Ok(state.clone().map(Value::BlockHeader))
+				}
+			},
+			Key::Coin(ref outpoint) => {
+				let mut coin = self.coin.lock();
+				if let Some(state) = coin.get_mut(outpoint) {
+					return Ok(state.clone().map(Value::Coin))
+				}
+
+				// remember coins that we have read, since they're going to be spent soon.
+				// lock is held, so that concurrent write can't be overwritten by the stale value
+				let state = self.db.get(key)?;
+				let cached = match state.clone() {
+					KeyState::Insert(Value::Coin(value)) => KeyState::Insert(value),
+					_ => KeyState::Delete,
+				};
+				coin.insert(outpoint.clone(), cached);
+				return Ok(state)
+			},
+			_ => (),
 		}
 		self.db.get(key)
 	}
+
+	fn for_each_coin(&self, f: &mut FnMut(OutPoint, Coin) -> Result<(), String>) -> Result<(), String> {
+		// all writes are passed to the underlying database => there are no cache-only coins
+		self.db.for_each_coin(f)
+	}
 }
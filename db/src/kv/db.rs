@@ -1,7 +1,12 @@
+use chain::OutPoint;
+use storage::Coin;
 use kv::{Transaction, KeyState, Key, Value};
 
 pub trait KeyValueDatabase: Send + Sync {
 	fn write(&self, tx: Transaction) -> Result<(), String>;
 
 	fn get(&self, key: &Key) -> Result<KeyState<Value>, String>;
+
+	/// Calls `f` for every coin in the database, in order of serialized outpoints
+	fn for_each_coin(&self, f: &mut FnMut(OutPoint, Coin) -> Result<(), String>) -> Result<(), String>;
 }
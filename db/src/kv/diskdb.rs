@@ -7,8 +7,11 @@ use rocksdb::{
 	Options, DBCompactionStyle, BlockBasedOptions, Cache, Column, ReadOptions
 };
 use bytes::Bytes;
+use ser::deserialize;
+use chain::OutPoint;
+use storage::Coin;
 use kv::{Transaction, RawTransaction, RawOperation, Location, Value, KeyValueDatabase, Key, KeyState, RawKeyValue,
-RawKey};
+RawKey, COL_COINS};
 
 const DB_BACKGROUND_FLUSHES: i32 = 2;
 const DB_BACKGROUND_COMPACTIONS: i32 = 2;
@@ -132,6 +135,15 @@ impl KeyValueDatabase for Database {
 			None => Ok(KeyState::Unknown)
 		}
 	}
+
+	fn for_each_coin(&self, f: &mut FnMut(OutPoint, Coin) -> Result<(), String>) -> Result<(), String> {
+		for (key, value) in self.iter(Location::Column(COL_COINS)) {
+			let outpoint = deserialize(&*key).map_err(|e| format!("Invalid coin key: {:?}", e))?;
+			let coin = deserialize(&*value).map_err(|e| format!("Invalid coin: {:?}", e))?;
+			f(outpoint, coin)?;
+		}
+		Ok(())
+	}
 }
 
 impl Database {
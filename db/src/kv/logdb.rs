@@ -0,0 +1,717 @@
+//! Key-Value store abstraction with embedded log-structured backend.
+//!
+//! All changes are appended to the single `data.log` file. Every written transaction is a
+//! checksummed record, so partially written transaction is detected and dropped on open.
+//! Keys of all columns are kept in memory, together with positions of their latest values
+//! in the log. Log is compacted when it contains much more outdated data than live data.
+//!
+//! Writes are serialized and synced to the disk before they're applied to the index. Values
+//! are read with positional reads, so reads only wait for the index updates.
+//!
+//! Compaction runs in the background thread. It copies live values of the index snapshot to the
+//! new log without locks, so reads and writes continue. Only the records, written while copying,
+//! are appended to the new log under the writer lock, before the new log replaces the old one.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::vec;
+use parking_lot::{Mutex, RwLock};
+use bytes::Bytes;
+use crypto::checksum;
+use kv::{RawTransaction, RawOperation, RawKeyValue, RawKey, Location};
+
+/// Name of the log file in the database directory
+pub const LOG_FILE_NAME: &'static str = "data.log";
+const COMPACTION_FILE_NAME: &'static str = "data.log.compact";
+/// Log is compacted when outdated data occupies more than this number of bytes...
+const MIN_COMPACTION_GARBAGE: u64 = 256 * 1024 * 1024;
+/// ...and more than `COMPACTION_GARBAGE_RATIO` times the size of live data
+const COMPACTION_GARBAGE_RATIO: u64 = 2;
+
+const OP_INSERT: u8 = 0;
+const OP_DELETE: u8 = 1;
+const LOCATION_DB: u32 = u32::max_value();
+/// Size of the record header: payload length and checksum
+const RECORD_HEADER_SIZE: u64 = 8;
+
+fn io_error(err: io::Error) -> String {
+	format!("Log database error: {}", err)
+}
+
+/// Position of the value in the log
+#[derive(Debug, Clone, Copy)]
+struct ValuePosition {
+	offset: u64,
+	length: u32,
+}
+
+/// Operation, read from the log
+enum LogOperation {
+	Insert(Location, Vec<u8>, ValuePosition),
+	Delete(Location, Vec<u8>),
+}
+
+/// Appending side of the log
+struct Writer {
+	file: File,
+	size: u64,
+}
+
+/// Positions of the live values
+struct Index {
+	/// Log file, which positions point to. Old log file is still readable after compaction
+	/// by readers, which have taken positions from the old index
+	file: Arc<File>,
+	columns: HashMap<Location, BTreeMap<Vec<u8>, ValuePosition>>,
+	/// Size of the live keys and values
+	live: u64,
+	/// Size of the outdated keys and values
+	garbage: u64,
+}
+
+impl Index {
+	/// Removes key from the index. Returns true if key has been live
+	fn remove(&mut self, location: Location, key: &[u8]) -> bool {
+		let removed = self.columns.get_mut(&location).and_then(|column| column.remove(key));
+		match removed {
+			Some(position) => {
+				let size = key.len() as u64 + position.length as u64;
+				self.live -= size;
+				self.garbage += size;
+				true
+			},
+			None => false,
+		}
+	}
+
+	fn insert(&mut self, location: Location, key: Vec<u8>, position: ValuePosition) {
+		self.remove(location, &key);
+		self.live += key.len() as u64 + position.length as u64;
+		self.columns.entry(location).or_insert_with(BTreeMap::new).insert(key, position);
+	}
+
+	fn apply(&mut self, operation: LogOperation) {
+		match operation {
+			LogOperation::Insert(location, key, position) => self.insert(location, key, position),
+			LogOperation::Delete(location, key) => {
+				// deletion of the missing key doesn't make anything outdated
+				if self.remove(location, &key) {
+					self.garbage += key.len() as u64;
+				}
+			},
+		}
+	}
+}
+
+/// Log-structured key-value database
+pub struct Database {
+	path: PathBuf,
+	writer: Arc<Mutex<Writer>>,
+	index: Arc<RwLock<Index>>,
+	/// True while the log is compacted by the background thread
+	compacting: Arc<AtomicBool>,
+	compaction: Mutex<Option<thread::JoinHandle<()>>>,
+}
+
+/// Log with live values of the index snapshot, which replaces the database log when compaction is finished
+struct CompactedLog {
+	path: PathBuf,
+	index: Index,
+	/// Log, the snapshot has been taken of
+	snapshot_file: Arc<File>,
+	/// Size of the log, when the snapshot has been taken
+	snapshot_size: u64,
+	size: u64,
+}
+
+impl Database {
+	/// Open database at given directory. Creates if it does not exist.
+	pub fn open<P>(path: P) -> Result<Database, String> where P: AsRef<Path> {
+		fs::create_dir_all(path.as_ref()).map_err(io_error)?;
+		let file_path = path.as_ref().join(LOG_FILE_NAME);
+		let mut file = OpenOptions::new()
+			.read(true)
+			.write(true)
+			.create(true)
+			.open(&file_path)
+			.map_err(io_error)?;
+
+		let mut writer = Writer {
+			file: file.try_clone().map_err(io_error)?,
+			size: 0,
+		};
+
+		let mut index = Index {
+			// reader has its own handle, so that it doesn't share the cursor with the writer
+			file: Arc::new(File::open(&file_path).map_err(io_error)?),
+			columns: HashMap::new(),
+			live: 0,
+			garbage: 0,
+		};
+
+		let file_size = file.metadata().map_err(io_error)?.len();
+		{
+			let mut reader = BufReader::new(&mut file);
+			while let Some((record_size, operations)) = read_record(&mut reader, writer.size)? {
+				for operation in operations {
+					index.apply(operation);
+				}
+				writer.size += record_size;
+			}
+		}
+
+		if writer.size != file_size {
+			warn!(target: "db", "Dropping {} bytes of incomplete transaction from {}", file_size - writer.size, file_path.display());
+			writer.file.set_len(writer.size).map_err(io_error)?;
+			writer.file.sync_all().map_err(io_error)?;
+		}
+
+		let compaction_required = is_compaction_required(&index);
+		let db = Database {
+			path: path.as_ref().to_owned(),
+			writer: Arc::new(Mutex::new(writer)),
+			index: Arc::new(RwLock::new(index)),
+			compacting: Arc::new(AtomicBool::new(false)),
+			compaction: Mutex::new(None),
+		};
+
+		// nothing else accesses the database yet => compaction is not moved to the background
+		if compaction_required {
+			compact(&db.path, &db.writer, &db.index)?;
+		}
+
+		Ok(db)
+	}
+
+	/// Commit transaction to database.
+	pub fn write(&self, tx: RawTransaction) -> Result<(), String> {
+		if tx.operations.is_empty() {
+			return Ok(());
+		}
+
+		let mut writer = self.writer.lock();
+		let record_offset = writer.size;
+		let (record, operations) = write_record(tx, record_offset);
+
+		// transaction is applied to the index only after it is durable
+		writer.file.seek(SeekFrom::Start(record_offset)).map_err(io_error)?;
+		writer.file.write_all(&record).map_err(io_error)?;
+		writer.file.flush().map_err(io_error)?;
+		writer.file.sync_data().map_err(io_error)?;
+		writer.size += record.len() as u64;
+
+		let compaction_required = {
+			let mut index = self.index.write();
+			for operation in operations {
+				index.apply(operation);
+			}
+			is_compaction_required(&index)
+		};
+
+		if compaction_required {
+			self.compact_in_background();
+		}
+
+		Ok(())
+	}
+
+	/// Get value by key.
+	pub fn get(&self, key: &RawKey) -> Result<Option<Bytes>, String> {
+		let (file, position) = {
+			let index = self.index.read();
+			match index.columns.get(&key.location).and_then(|column| column.get(&*key.key)) {
+				Some(position) => (index.file.clone(), *position),
+				None => return Ok(None),
+			}
+		};
+
+		read_value(&file, position).map(Some)
+	}
+
+	/// Iterate over all keys of the location, in order of keys. Changes, made while iterating,
+	/// are only visible to the iterator if they have changed values of the not yet visited keys
+	pub fn iter(&self, location: Location) -> DatabaseIterator {
+		let keys: Vec<_> = self.index.read().columns.get(&location)
+			.map(|column| column.keys().cloned().collect())
+			.unwrap_or_default();
+
+		DatabaseIterator {
+			db: self,
+			location: location,
+			keys: keys.into_iter(),
+		}
+	}
+
+	/// Starts compaction of the log in the background thread, unless it is already compacted
+	fn compact_in_background(&self) {
+		if self.compacting.swap(true, Ordering::AcqRel) {
+			return;
+		}
+
+		let path = self.path.clone();
+		let writer = self.writer.clone();
+		let index = self.index.clone();
+		let compacting = self.compacting.clone();
+		let handle = thread::Builder::new()
+			.name("log-db-compaction".into())
+			.spawn(move || {
+				if let Err(err) = compact(&path, &writer, &index) {
+					// compaction is retried on the next write
+					warn!(target: "db", "Failed to compact log database at {}: {}", path.display(), err);
+				}
+				compacting.store(false, Ordering::Release);
+			});
+
+		match handle {
+			Ok(handle) => *self.compaction.lock() = Some(handle),
+			Err(err) => {
+				warn!(target: "db", "Failed to start compaction of the log database: {}", err);
+				self.compacting.store(false, Ordering::Release);
+			},
+		}
+	}
+}
+
+impl Drop for Database {
+	fn drop(&mut self) {
+		// log is not replaced after the database is closed
+		if let Some(handle) = self.compaction.lock().take() {
+			let _ = handle.join();
+		}
+	}
+}
+
+/// Rewrites log, leaving live values only
+fn compact(path: &Path, writer: &Mutex<Writer>, index: &RwLock<Index>) -> Result<(), String> {
+	let compacted = copy_live_values(path, writer, index)?;
+	finish_compaction(path, writer, index, compacted)
+}
+
+/// Writes live values of the index snapshot to the new log. Database is not locked while copying
+fn copy_live_values(path: &Path, writer: &Mutex<Writer>, index: &RwLock<Index>) -> Result<CompactedLog, String> {
+	let (snapshot_file, snapshot_size, entries) = {
+		// index matches the log, when the writer is locked
+		let writer = writer.lock();
+		let index = index.read();
+		info!(target: "db", "Compacting log database at {}: {} bytes of live data, {} bytes of outdated data", path.display(), index.live, index.garbage);
+		let entries: Vec<_> = index.columns.iter()
+			.flat_map(|(location, column)| column.iter().map(move |(key, position)| (*location, key.clone(), *position)))
+			.collect();
+		(index.file.clone(), writer.size, entries)
+	};
+
+	let compaction_path = path.join(COMPACTION_FILE_NAME);
+	let file = OpenOptions::new()
+		.read(true)
+		.write(true)
+		.create(true)
+		.truncate(true)
+		.open(&compaction_path)
+		.map_err(io_error)?;
+	let mut compacted = CompactedLog {
+		path: compaction_path,
+		index: Index {
+			file: Arc::new(file.try_clone().map_err(io_error)?),
+			columns: HashMap::new(),
+			live: 0,
+			garbage: 0,
+		},
+		snapshot_file: snapshot_file,
+		snapshot_size: snapshot_size,
+		size: 0,
+	};
+
+	let mut compaction_writer = BufWriter::new(file);
+	for (location, key, position) in entries {
+		let value = read_value(&compacted.snapshot_file, position)?;
+		let mut tx = RawTransaction::default();
+		tx.operations.push(RawOperation::Insert(RawKeyValue {
+			location: location,
+			key: key.into(),
+			value: value,
+		}));
+
+		let (record, operations) = write_record(tx, compacted.size);
+		compaction_writer.write_all(&record).map_err(io_error)?;
+		compacted.size += record.len() as u64;
+		for operation in operations {
+			compacted.index.apply(operation);
+		}
+	}
+	compaction_writer.flush().map_err(io_error)?;
+
+	Ok(compacted)
+}
+
+/// Appends records, written after the snapshot has been taken, to the compacted log and replaces
+/// the database log with it. Writer is locked only while the records are appended
+fn finish_compaction(path: &Path, writer: &Mutex<Writer>, index: &RwLock<Index>, mut compacted: CompactedLog) -> Result<(), String> {
+	let mut writer = writer.lock();
+	let mut tail = vec![0u8; (writer.size - compacted.snapshot_size) as usize];
+	read_exact_at(&compacted.snapshot_file, &mut tail, compacted.snapshot_size).map_err(io_error)?;
+	{
+		let mut reader = &tail[..];
+		let mut record_offset = compacted.size;
+		while let Some((record_size, operations)) = read_record(&mut reader, record_offset)? {
+			for operation in operations {
+				compacted.index.apply(operation);
+			}
+			record_offset += record_size;
+		}
+	}
+
+	let mut file = compacted.index.file.try_clone().map_err(io_error)?;
+	file.seek(SeekFrom::Start(compacted.size)).map_err(io_error)?;
+	file.write_all(&tail).map_err(io_error)?;
+	file.sync_all().map_err(io_error)?;
+	compacted.size += tail.len() as u64;
+
+	let file_path = path.join(LOG_FILE_NAME);
+	fs::rename(&compacted.path, &file_path).map_err(io_error)?;
+	writer.file = OpenOptions::new().read(true).write(true).open(&file_path).map_err(io_error)?;
+	writer.size = compacted.size;
+
+	// outdated values of the compacted log are deleted values of the appended records only
+	*index.write() = compacted.index;
+	Ok(())
+}
+
+/// Database iterator
+pub struct DatabaseIterator<'a> {
+	db: &'a Database,
+	location: Location,
+	keys: vec::IntoIter<Vec<u8>>,
+}
+
+impl<'a> Iterator for DatabaseIterator<'a> {
+	type Item = Result<(Box<[u8]>, Box<[u8]>), String>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		while let Some(key) = self.keys.next() {
+			match self.db.get(&RawKey::new(self.location, key.clone())) {
+				Ok(Some(value)) => return Some(Ok((key.into_boxed_slice(), value.to_vec().into_boxed_slice()))),
+				Ok(None) => continue,
+				Err(err) => return Some(Err(err)),
+			}
+		}
+
+		None
+	}
+}
+
+fn is_compaction_required(index: &Index) -> bool {
+	index.garbage > MIN_COMPACTION_GARBAGE && index.garbage > index.live * COMPACTION_GARBAGE_RATIO
+}
+
+fn read_value(file: &File, position: ValuePosition) -> Result<Bytes, String> {
+	let mut value = vec![0u8; position.length as usize];
+	read_exact_at(file, &mut value, position.offset).map_err(io_error)?;
+	Ok(value.into())
+}
+
+/// Fills the buffer with data at given offset. Positional reads don't use the file cursor,
+/// so the file is read concurrently
+fn read_exact_at(file: &File, mut buffer: &mut [u8], mut offset: u64) -> io::Result<()> {
+	while !buffer.is_empty() {
+		match read_at(file, buffer, offset) {
+			Ok(0) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "failed to fill whole buffer")),
+			Ok(n) => {
+				let rest = buffer;
+				buffer = &mut rest[n..];
+				offset += n as u64;
+			},
+			Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+			Err(err) => return Err(err),
+		}
+	}
+	Ok(())
+}
+
+#[cfg(unix)]
+fn read_at(file: &File, buffer: &mut [u8], offset: u64) -> io::Result<usize> {
+	use std::os::unix::fs::FileExt;
+	file.read_at(buffer, offset)
+}
+
+#[cfg(windows)]
+fn read_at(file: &File, buffer: &mut [u8], offset: u64) -> io::Result<usize> {
+	use std::os::windows::fs::FileExt;
+	file.seek_read(buffer, offset)
+}
+
+fn location_to_u32(location: Location) -> u32 {
+	match location {
+		Location::DB => LOCATION_DB,
+		Location::Column(column) => column,
+	}
+}
+
+fn location_from_u32(location: u32) -> Location {
+	match location {
+		LOCATION_DB => Location::DB,
+		column => Location::Column(column),
+	}
+}
+
+fn push_u32(buffer: &mut Vec<u8>, value: u32) {
+	buffer.push(value as u8);
+	buffer.push((value >> 8) as u8);
+	buffer.push((value >> 16) as u8);
+	buffer.push((value >> 24) as u8);
+}
+
+fn read_u32(buffer: &[u8], offset: usize) -> Option<u32> {
+	if buffer.len() < offset + 4 {
+		return None;
+	}
+
+	Some(buffer[offset] as u32
+		| (buffer[offset + 1] as u32) << 8
+		| (buffer[offset + 2] as u32) << 16
+		| (buffer[offset + 3] as u32) << 24)
+}
+
+/// Serializes transaction into the log record, written at given offset.
+/// Record consists of payload length, payload checksum and operations:
+/// `kind, location, key length, key[, value length, value]`
+fn write_record(tx: RawTransaction, record_offset: u64) -> (Vec<u8>, Vec<LogOperation>) {
+	let mut payload = Vec::new();
+	let mut operations = Vec::with_capacity(tx.operations.len());
+	for operation in tx.operations {
+		match operation {
+			RawOperation::Insert(RawKeyValue { location, key, value }) => {
+				payload.push(OP_INSERT);
+				push_u32(&mut payload, location_to_u32(location));
+				push_u32(&mut payload, key.len() as u32);
+				payload.extend_from_slice(&key);
+				push_u32(&mut payload, value.len() as u32);
+				let position = ValuePosition {
+					offset: record_offset + RECORD_HEADER_SIZE + payload.len() as u64,
+					length: value.len() as u32,
+				};
+				payload.extend_from_slice(&value);
+				operations.push(LogOperation::Insert(location, key.to_vec(), position));
+			},
+			RawOperation::Delete(RawKey { location, key }) => {
+				payload.push(OP_DELETE);
+				push_u32(&mut payload, location_to_u32(location));
+				push_u32(&mut payload, key.len() as u32);
+				payload.extend_from_slice(&key);
+				operations.push(LogOperation::Delete(location, key.to_vec()));
+			},
+		}
+	}
+
+	let mut record = Vec::with_capacity(RECORD_HEADER_SIZE as usize + payload.len());
+	push_u32(&mut record, payload.len() as u32);
+	record.extend_from_slice(&*checksum(&payload));
+	record.extend_from_slice(&payload);
+	(record, operations)
+}
+
+/// Reads next record of the log, located at given offset. Returns None if there are no more
+/// complete records
+fn read_record<R>(reader: &mut R, record_offset: u64) -> Result<Option<(u64, Vec<LogOperation>)>, String> where R: Read {
+	let mut header = [0u8; RECORD_HEADER_SIZE as usize];
+	if !read_exact_or_eof(reader, &mut header)? {
+		return Ok(None);
+	}
+
+	let payload_len = read_u32(&header, 0).expect("header has 8 bytes; qed") as usize;
+	let mut payload = vec![0u8; payload_len];
+	if !read_exact_or_eof(reader, &mut payload)? || *checksum(&payload) != header[4..] {
+		return Ok(None);
+	}
+
+	let mut operations = Vec::new();
+	let mut offset = 0;
+	while offset < payload.len() {
+		let kind = payload[offset];
+		let location = match read_u32(&payload, offset + 1) {
+			Some(location) => location_from_u32(location),
+			None => return Ok(None),
+		};
+		let key_len = match read_u32(&payload, offset + 5) {
+			Some(key_len) => key_len as usize,
+			None => return Ok(None),
+		};
+		offset += 9;
+		if payload.len() < offset + key_len {
+			return Ok(None);
+		}
+		let key = payload[offset..offset + key_len].to_vec();
+		offset += key_len;
+
+		match kind {
+			OP_INSERT => {
+				let value_len = match read_u32(&payload, offset) {
+					Some(value_len) => value_len,
+					None => return Ok(None),
+				};
+				offset += 4;
+				if payload.len() < offset + value_len as usize {
+					return Ok(None);
+				}
+				let position = ValuePosition {
+					offset: record_offset + RECORD_HEADER_SIZE + offset as u64,
+					length: value_len,
+				};
+				offset += value_len as usize;
+				operations.push(LogOperation::Insert(location, key, position));
+			},
+			OP_DELETE => operations.push(LogOperation::Delete(location, key)),
+			_ => return Ok(None),
+		}
+	}
+
+	Ok(Some((RECORD_HEADER_SIZE + payload_len as u64, operations)))
+}
+
+/// Fills the buffer. Returns false if reader has ended before the buffer is filled
+fn read_exact_or_eof<R>(reader: &mut R, buffer: &mut [u8]) -> Result<bool, String> where R: Read {
+	let mut read = 0;
+	while read < buffer.len() {
+		match reader.read(&mut buffer[read..]) {
+			Ok(0) => return Ok(false),
+			Ok(n) => read += n,
+			Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+			Err(err) => return Err(io_error(err)),
+		}
+	}
+	Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+	extern crate tempdir;
+
+	use std::fs::OpenOptions;
+	use std::io::Write;
+	use self::tempdir::TempDir;
+	use kv::{RawTransaction, RawKey, Location};
+	use super::{Database, LOG_FILE_NAME, compact, copy_live_values, finish_compaction};
+
+	#[test]
+	fn incomplete_transaction_is_dropped_on_open() {
+		let tempdir = TempDir::new("").unwrap();
+		{
+			let db = Database::open(tempdir.path()).unwrap();
+			let mut batch = RawTransaction::default();
+			batch.insert_raw(Location::Column(1), b"key1", b"cat");
+			db.write(batch).unwrap();
+		}
+
+		// simulate crash in the middle of the transaction
+		OpenOptions::new().append(true).open(tempdir.path().join(LOG_FILE_NAME)).unwrap()
+			.write_all(&[10, 0, 0, 0, 1, 2]).unwrap();
+
+		let db = Database::open(tempdir.path()).unwrap();
+		assert_eq!(&*db.get(&RawKey::new(Location::Column(1), b"key1" as &[u8])).unwrap().unwrap(), b"cat");
+
+		let mut batch = RawTransaction::default();
+		batch.insert_raw(Location::Column(1), b"key2", b"dog");
+		db.write(batch).unwrap();
+		drop(db);
+
+		let db = Database::open(tempdir.path()).unwrap();
+		assert_eq!(&*db.get(&RawKey::new(Location::Column(1), b"key2" as &[u8])).unwrap().unwrap(), b"dog");
+	}
+
+	#[test]
+	fn compaction_keeps_live_values() {
+		let tempdir = TempDir::new("").unwrap();
+		let db = Database::open(tempdir.path()).unwrap();
+		for value in 0..10u8 {
+			let mut batch = RawTransaction::default();
+			batch.insert_raw(Location::DB, b"key1", &[value]);
+			batch.insert_raw(Location::Column(2), &[value], b"value");
+			batch.delete_raw(Location::Column(2), &[value / 2]);
+			db.write(batch).unwrap();
+		}
+
+		compact(&db.path, &db.writer, &db.index).unwrap();
+		assert_eq!(db.index.read().garbage, 0);
+
+		assert_eq!(&*db.get(&RawKey::new(Location::DB, b"key1" as &[u8])).unwrap().unwrap(), &[9]);
+		let keys: Vec<_> = db.iter(Location::Column(2)).map(|pair| pair.unwrap().0.to_vec()).collect();
+		assert_eq!(keys, vec![vec![5], vec![6], vec![7], vec![8], vec![9]]);
+		drop(db);
+
+		let db = Database::open(tempdir.path()).unwrap();
+		assert_eq!(&*db.get(&RawKey::new(Location::DB, b"key1" as &[u8])).unwrap().unwrap(), &[9]);
+	}
+
+	#[test]
+	fn compaction_keeps_values_written_while_copying() {
+		let tempdir = TempDir::new("").unwrap();
+		let db = Database::open(tempdir.path()).unwrap();
+		fn write(db: &Database, key: &[u8], value: Option<&[u8]>) {
+			let mut batch = RawTransaction::default();
+			match value {
+				Some(value) => batch.insert_raw(Location::Column(1), key, value),
+				None => batch.delete_raw(Location::Column(1), key),
+			}
+			db.write(batch).unwrap();
+		}
+
+		fn get(db: &Database, key: &[u8]) -> Option<Vec<u8>> {
+			db.get(&RawKey::new(Location::Column(1), key)).unwrap().map(|value| value.to_vec())
+		}
+
+		write(&db, b"key1", Some(b"cat"));
+		write(&db, b"key2", Some(b"dog"));
+		write(&db, b"key3", Some(b"cow"));
+		write(&db, b"key1", Some(b"owl"));
+
+		let compacted = copy_live_values(&db.path, &db.writer, &db.index).unwrap();
+		// database is not locked while live values are copied
+		write(&db, b"key2", Some(b"fox"));
+		write(&db, b"key3", None);
+		write(&db, b"key4", Some(b"elk"));
+		assert_eq!(get(&db, b"key2"), Some(b"fox".to_vec()));
+		finish_compaction(&db.path, &db.writer, &db.index, compacted).unwrap();
+
+		for db in vec![db, Database::open(tempdir.path()).unwrap()] {
+			assert_eq!(get(&db, b"key1"), Some(b"owl".to_vec()));
+			assert_eq!(get(&db, b"key2"), Some(b"fox".to_vec()));
+			assert_eq!(get(&db, b"key3"), None);
+			assert_eq!(get(&db, b"key4"), Some(b"elk".to_vec()));
+			let index = db.index.read();
+			assert_eq!(index.live, 3 * (4 + 3));
+			// `dog` and `cow` of the appended records, and the deletion of `key3`
+			assert_eq!(index.garbage, 2 * (4 + 3) + 4);
+		}
+	}
+
+	#[test]
+	fn deleting_missing_key_is_not_garbage() {
+		let tempdir = TempDir::new("").unwrap();
+		{
+			let db = Database::open(tempdir.path()).unwrap();
+			let mut batch = RawTransaction::default();
+			batch.insert_raw(Location::Column(1), b"key1", b"cat");
+			db.write(batch).unwrap();
+
+			let mut batch = RawTransaction::default();
+			batch.delete_raw(Location::Column(1), b"key1");
+			batch.delete_raw(Location::Column(1), b"key1");
+			batch.delete_raw(Location::Column(1), b"key2");
+			db.write(batch).unwrap();
+
+			let index = db.index.read();
+			assert_eq!(index.live, 0);
+			// outdated value and the deletion of the live key
+			assert_eq!(index.garbage, 7 + 4);
+		}
+
+		let db = Database::open(tempdir.path()).unwrap();
+		let index = db.index.read();
+		assert_eq!(index.live, 0);
+		assert_eq!(index.garbage, 7 + 4);
+	}
+}
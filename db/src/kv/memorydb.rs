@@ -4,10 +4,11 @@ use std::mem::replace;
 use parking_lot::RwLock;
 use hash::H256;
 use bytes::Bytes;
-use ser::List;
-use chain::{Transaction as ChainTransaction, BlockHeader};
+use ser::{List, serialize};
+use chain::{Transaction as ChainTransaction, BlockHeader, OutPoint};
 use kv::{Transaction, Key, KeyState, Operation, Value, KeyValueDatabase, KeyValue};
-use storage::{TransactionMeta};
+use storage::{TransactionMeta, Coin, BlockUndo, SpentInfo, TransactionLocation};
+use block_files::BlockPosition;
 
 #[derive(Default, Debug)]
 struct InnerDatabase {
@@ -19,6 +20,11 @@ struct InnerDatabase {
 	transaction_meta: HashMap<H256, KeyState<TransactionMeta>>,
 	block_number: HashMap<H256, KeyState<u32>>,
 	configuration: HashMap<&'static str, KeyState<Bytes>>,
+	coin: HashMap<OutPoint, KeyState<Coin>>,
+	block_undo: HashMap<H256, KeyState<BlockUndo>>,
+	spent_info: HashMap<OutPoint, KeyState<SpentInfo>>,
+	transaction_location: HashMap<H256, KeyState<TransactionLocation>>,
+	block_position: HashMap<H256, KeyState<BlockPosition>>,
 }
 
 #[derive(Default, Debug)]
@@ -53,6 +59,21 @@ impl MemoryDatabase {
 		let configuration = replace(&mut db.configuration, HashMap::default()).into_iter()
 			.flat_map(|(key, state)| state.into_operation(key, KeyValue::Configuration, Key::Configuration));
 
+		let coin = replace(&mut db.coin, HashMap::default()).into_iter()
+			.flat_map(|(key, state)| state.into_operation(key, KeyValue::Coin, Key::Coin));
+
+		let block_undo = replace(&mut db.block_undo, HashMap::default()).into_iter()
+			.flat_map(|(key, state)| state.into_operation(key, KeyValue::BlockUndo, Key::BlockUndo));
+
+		let spent_info = replace(&mut db.spent_info, HashMap::default()).into_iter()
+			.flat_map(|(key, state)| state.into_operation(key, KeyValue::SpentInfo, Key::SpentInfo));
+
+		let transaction_location = replace(&mut db.transaction_location, HashMap::default()).into_iter()
+			.flat_map(|(key, state)| state.into_operation(key, KeyValue::TransactionLocation, Key::TransactionLocation));
+
+		let block_position = replace(&mut db.block_position, HashMap::default()).into_iter()
+			.flat_map(|(key, state)| state.into_operation(key, KeyValue::BlockPosition, Key::BlockPosition));
+
 		Transaction {
 			operations: meta
 				.chain(block_hash)
@@ -62,9 +83,24 @@ impl MemoryDatabase {
 				.chain(transaction_meta)
 				.chain(block_number)
 				.chain(configuration)
+				.chain(coin)
+				.chain(block_undo)
+				.chain(spent_info)
+				.chain(transaction_location)
+				.chain(block_position)
 				.collect()
 		}
 	}
+
+	/// Returns states of all coins, ordered by serialized outpoints
+	pub fn coin_states(&self) -> Vec<(Bytes, OutPoint, KeyState<Coin>)> {
+		let db = self.db.read();
+		let mut result: Vec<_> = db.coin.iter()
+			.map(|(outpoint, state)| (serialize(outpoint), outpoint.clone(), state.clone()))
+			.collect();
+		result.sort_by(|a, b| (*a.0).cmp(&*b.0));
+		result
+	}
 }
 
 impl KeyValueDatabase for MemoryDatabase {
@@ -81,6 +117,11 @@ impl KeyValueDatabase for MemoryDatabase {
 					KeyValue::TransactionMeta(key, value) => { db.transaction_meta.insert(key, KeyState::Insert(value)); },
 					KeyValue::BlockNumber(key, value) => { db.block_number.insert(key, KeyState::Insert(value)); },
 					KeyValue::Configuration(key, value) => { db.configuration.insert(key, KeyState::Insert(value)); },
+					KeyValue::Coin(key, value) => { db.coin.insert(key, KeyState::Insert(value)); },
+					KeyValue::BlockUndo(key, value) => { db.block_undo.insert(key, KeyState::Insert(value)); },
+					KeyValue::SpentInfo(key, value) => { db.spent_info.insert(key, KeyState::Insert(value)); },
+					KeyValue::TransactionLocation(key, value) => { db.transaction_location.insert(key, KeyState::Insert(value)); },
+					KeyValue::BlockPosition(key, value) => { db.block_position.insert(key, KeyState::Insert(value)); },
 				},
 				Operation::Delete(delete) => match delete {
 					Key::Meta(key) => { db.meta.insert(key, KeyState::Delete); }
@@ -91,6 +132,11 @@ impl KeyValueDatabase for MemoryDatabase {
 					Key::TransactionMeta(key) => { db.transaction_meta.insert(key, KeyState::Delete); }
 					Key::BlockNumber(key) => { db.block_number.insert(key, KeyState::Delete); }
 					Key::Configuration(key) => { db.configuration.insert(key, KeyState::Delete); }
+					Key::Coin(key) => { db.coin.insert(key, KeyState::Delete); }
+					Key::BlockUndo(key) => { db.block_undo.insert(key, KeyState::Delete); }
+					Key::SpentInfo(key) => { db.spent_info.insert(key, KeyState::Delete); }
+					Key::TransactionLocation(key) => { db.transaction_location.insert(key, KeyState::Delete); }
+					Key::BlockPosition(key) => { db.block_position.insert(key, KeyState::Delete); }
 				}
 			}
 		}
@@ -108,10 +154,24 @@ impl KeyValueDatabase for MemoryDatabase {
 			Key::TransactionMeta(ref key) => db.transaction_meta.get(key).cloned().unwrap_or_default().map(Value::TransactionMeta),
 			Key::BlockNumber(ref key) => db.block_number.get(key).cloned().unwrap_or_default().map(Value::BlockNumber),
 			Key::Configuration(ref key) => db.configuration.get(key).cloned().unwrap_or_default().map(Value::Configuration),
+			Key::Coin(ref key) => db.coin.get(key).cloned().unwrap_or_default().map(Value::Coin),
+			Key::BlockUndo(ref key) => db.block_undo.get(key).cloned().unwrap_or_default().map(Value::BlockUndo),
+			Key::SpentInfo(ref key) => db.spent_info.get(key).cloned().unwrap_or_default().map(Value::SpentInfo),
+			Key::TransactionLocation(ref key) => db.transaction_location.get(key).cloned().unwrap_or_default().map(Value::TransactionLocation),
+			Key::BlockPosition(ref key) => db.block_position.get(key).cloned().unwrap_or_default().map(Value::BlockPosition),
 		};
 
 		Ok(result)
 	}
+
+	fn for_each_coin(&self, f: &mut FnMut(OutPoint, Coin) -> Result<(), String>) -> Result<(), String> {
+		for (_, outpoint, state) in self.coin_states() {
+			if let KeyState::Insert(coin) = state {
+				f(outpoint, coin)?;
+			}
+		}
+		Ok(())
+	}
 }
 
 #[derive(Debug)]
@@ -143,4 +203,8 @@ impl KeyValueDatabase for SharedMemoryDatabase {
 	fn get(&self, key: &Key) -> Result<KeyState<Value>, String> {
 		self.db.get(key)
 	}
+
+	fn for_each_coin(&self, f: &mut FnMut(OutPoint, Coin) -> Result<(), String>) -> Result<(), String> {
+		self.db.for_each_coin(f)
+	}
 }
@@ -1,18 +1,23 @@
+mod backend;
 mod cachedb;
 mod db;
 mod diskdb;
+mod logdb;
 mod memorydb;
 mod overlaydb;
 mod transaction;
 
-pub use self::cachedb::CacheDatabase;
+pub use self::backend::{DatabaseBackend, DatabaseEngine, migrate};
+pub use self::cachedb::{CacheDatabase, DEFAULT_COIN_CACHE_SIZE};
 pub use self::db::KeyValueDatabase;
 pub use self::diskdb::{Database as DiskDatabase, DatabaseConfig, CompactionProfile};
+pub use self::logdb::{Database as LogDatabase, LOG_FILE_NAME};
 pub use self::memorydb::{MemoryDatabase, SharedMemoryDatabase};
 pub use self::overlaydb::{OverlayDatabase, AutoFlushingOverlayDatabase};
 pub use self::transaction::{
 	RawTransaction, Transaction, RawOperation, Operation, Location, KeyState,
 	Key, Value, KeyValue, RawKeyValue, RawKey,
 	COL_COUNT, COL_META, COL_BLOCK_HASHES, COL_BLOCK_HEADERS, COL_BLOCK_TRANSACTIONS,
-	COL_TRANSACTIONS, COL_TRANSACTIONS_META, COL_BLOCK_NUMBERS
+	COL_TRANSACTIONS, COL_TRANSACTIONS_META, COL_BLOCK_NUMBERS, COL_COINS, COL_BLOCK_UNDO, COL_SPENT_INFO,
+	COL_TRANSACTION_LOCATIONS, COL_BLOCK_POSITIONS
 };
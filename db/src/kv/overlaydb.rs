@@ -1,6 +1,44 @@
 use parking_lot::Mutex;
+use ser::serialize;
+use chain::OutPoint;
+use storage::Coin;
 use kv::{Transaction, Value, KeyValueDatabase, MemoryDatabase, KeyState, Key};
 
+/// Calls `f` for every coin of the `db` with `overlay` applied, in order of serialized outpoints
+fn for_each_overlaid_coin<T>(
+	overlay: &MemoryDatabase,
+	db: &T,
+	f: &mut FnMut(OutPoint, Coin) -> Result<(), String>
+) -> Result<(), String> where T: KeyValueDatabase {
+	let mut overlay_coins = overlay.coin_states().into_iter().peekable();
+	db.for_each_coin(&mut |outpoint, coin| {
+		let key = serialize(&outpoint);
+		// overlay coins, preceding this coin
+		while overlay_coins.peek().map_or(false, |&(ref overlay_key, _, _)| **overlay_key < *key) {
+			if let Some((_, overlay_outpoint, KeyState::Insert(overlay_coin))) = overlay_coins.next() {
+				f(overlay_outpoint, overlay_coin)?;
+			}
+		}
+
+		// coin is either modified or deleted by overlay
+		if overlay_coins.peek().map_or(false, |&(ref overlay_key, _, _)| **overlay_key == *key) {
+			if let Some((_, overlay_outpoint, KeyState::Insert(overlay_coin))) = overlay_coins.next() {
+				f(overlay_outpoint, overlay_coin)?;
+			}
+			return Ok(());
+		}
+
+		f(outpoint, coin)
+	})?;
+
+	for (_, overlay_outpoint, state) in overlay_coins {
+		if let KeyState::Insert(overlay_coin) = state {
+			f(overlay_outpoint, overlay_coin)?;
+		}
+	}
+	Ok(())
+}
+
 pub struct OverlayDatabase<'a, T> where T: 'a + KeyValueDatabase {
 	db: &'a T,
 	overlay: MemoryDatabase,
@@ -30,6 +68,10 @@ impl<'a, T> KeyValueDatabase for OverlayDatabase<'a, T> where T: 'a + KeyValueDa
 			exists => Ok(exists)
 		}
 	}
+
+	fn for_each_coin(&self, f: &mut FnMut(OutPoint, Coin) -> Result<(), String>) -> Result<(), String> {
+		for_each_overlaid_coin(&self.overlay, self.db, f)
+	}
 }
 
 pub struct AutoFlushingOverlayDatabase<T> where T: KeyValueDatabase {
@@ -72,6 +114,12 @@ impl<T> KeyValueDatabase for AutoFlushingOverlayDatabase<T> where T: KeyValueDat
 			exists => Ok(exists)
 		}
 	}
+
+	fn for_each_coin(&self, f: &mut FnMut(OutPoint, Coin) -> Result<(), String>) -> Result<(), String> {
+		// hold the lock, so that overlay isn't flushed while we're iterating
+		let _operations = self.operations.lock();
+		for_each_overlaid_coin(&self.overlay, &self.db, f)
+	}
 }
 
 impl<T> Drop for AutoFlushingOverlayDatabase<T> where T: KeyValueDatabase {
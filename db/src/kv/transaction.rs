@@ -1,10 +1,11 @@
 use bytes::Bytes;
 use hash::H256;
 use ser::{serialize, List, deserialize};
-use chain::{Transaction as ChainTransaction, BlockHeader};
-use storage::{TransactionMeta};
+use chain::{Transaction as ChainTransaction, BlockHeader, OutPoint};
+use storage::{TransactionMeta, Coin, BlockUndo, SpentInfo, TransactionLocation};
+use block_files::BlockPosition;
 
-pub const COL_COUNT: u32 = 10;
+pub const COL_COUNT: u32 = 13;
 pub const COL_META: u32 = 0;
 pub const COL_BLOCK_HASHES: u32 = 1;
 pub const COL_BLOCK_HEADERS: u32 = 2;
@@ -13,6 +14,11 @@ pub const COL_TRANSACTIONS: u32 = 4;
 pub const COL_TRANSACTIONS_META: u32 = 5;
 pub const COL_BLOCK_NUMBERS: u32 = 6;
 pub const COL_CONFIGURATION: u32 = 7;
+pub const COL_COINS: u32 = 8;
+pub const COL_BLOCK_UNDO: u32 = 9;
+pub const COL_SPENT_INFO: u32 = 10;
+pub const COL_TRANSACTION_LOCATIONS: u32 = 11;
+pub const COL_BLOCK_POSITIONS: u32 = 12;
 
 #[derive(Debug)]
 pub enum Operation {
@@ -30,6 +36,11 @@ pub enum KeyValue {
 	TransactionMeta(H256, TransactionMeta),
 	BlockNumber(H256, u32),
 	Configuration(&'static str, Bytes),
+	Coin(OutPoint, Coin),
+	BlockUndo(H256, BlockUndo),
+	SpentInfo(OutPoint, SpentInfo),
+	TransactionLocation(H256, TransactionLocation),
+	BlockPosition(H256, BlockPosition),
 }
 
 #[derive(Debug)]
@@ -42,6 +53,11 @@ pub enum Key {
 	TransactionMeta(H256),
 	BlockNumber(H256),
 	Configuration(&'static str),
+	Coin(OutPoint),
+	BlockUndo(H256),
+	SpentInfo(OutPoint),
+	TransactionLocation(H256),
+	BlockPosition(H256),
 }
 
 #[derive(Debug, Clone)]
@@ -54,6 +70,11 @@ pub enum Value {
 	TransactionMeta(TransactionMeta),
 	BlockNumber(u32),
 	Configuration(Bytes),
+	Coin(Coin),
+	BlockUndo(BlockUndo),
+	SpentInfo(SpentInfo),
+	TransactionLocation(TransactionLocation),
+	BlockPosition(BlockPosition),
 }
 
 impl Value {
@@ -67,6 +88,11 @@ impl Value {
 			Key::TransactionMeta(_) => deserialize(bytes).map(Value::TransactionMeta),
 			Key::BlockNumber(_) => deserialize(bytes).map(Value::BlockNumber),
 			Key::Configuration(_) => deserialize(bytes).map(Value::Configuration),
+			Key::Coin(_) => deserialize(bytes).map(Value::Coin),
+			Key::BlockUndo(_) => deserialize(bytes).map(Value::BlockUndo),
+			Key::SpentInfo(_) => deserialize(bytes).map(Value::SpentInfo),
+			Key::TransactionLocation(_) => deserialize(bytes).map(Value::TransactionLocation),
+			Key::BlockPosition(_) => deserialize(bytes).map(Value::BlockPosition),
 		}.map_err(|e| format!("{:?}", e))
 	}
 
@@ -125,6 +151,41 @@ impl Value {
 			_ => None,
 		}
 	}
+
+	pub fn as_coin(self) -> Option<Coin> {
+		match self {
+			Value::Coin(coin) => Some(coin),
+			_ => None,
+		}
+	}
+
+	pub fn as_block_undo(self) -> Option<BlockUndo> {
+		match self {
+			Value::BlockUndo(undo) => Some(undo),
+			_ => None,
+		}
+	}
+
+	pub fn as_spent_info(self) -> Option<SpentInfo> {
+		match self {
+			Value::SpentInfo(info) => Some(info),
+			_ => None,
+		}
+	}
+
+	pub fn as_transaction_location(self) -> Option<TransactionLocation> {
+		match self {
+			Value::TransactionLocation(location) => Some(location),
+			_ => None,
+		}
+	}
+
+	pub fn as_block_position(self) -> Option<BlockPosition> {
+		match self {
+			Value::BlockPosition(position) => Some(position),
+			_ => None,
+		}
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -228,6 +289,11 @@ impl<'a> From<&'a KeyValue> for RawKeyValue {
 			KeyValue::TransactionMeta(ref key, ref value) => (COL_TRANSACTIONS_META, serialize(key), serialize(value)),
 			KeyValue::BlockNumber(ref key, ref value) => (COL_BLOCK_NUMBERS, serialize(key), serialize(value)),
 			KeyValue::Configuration(ref key, ref value) => (COL_CONFIGURATION, serialize(key), serialize(value)),
+			KeyValue::Coin(ref key, ref value) => (COL_COINS, serialize(key), serialize(value)),
+			KeyValue::BlockUndo(ref key, ref value) => (COL_BLOCK_UNDO, serialize(key), serialize(value)),
+			KeyValue::SpentInfo(ref key, ref value) => (COL_SPENT_INFO, serialize(key), serialize(value)),
+			KeyValue::TransactionLocation(ref key, ref value) => (COL_TRANSACTION_LOCATIONS, serialize(key), serialize(value)),
+			KeyValue::BlockPosition(ref key, ref value) => (COL_BLOCK_POSITIONS, serialize(key), serialize(value)),
 		};
 
 		RawKeyValue {
@@ -263,6 +329,11 @@ impl<'a> From<&'a Key> for RawKey {
 			Key::TransactionMeta(ref key) => (COL_TRANSACTIONS_META, serialize(key)),
 			Key::BlockNumber(ref key) => (COL_BLOCK_NUMBERS, serialize(key)),
 			Key::Configuration(ref key) => (COL_CONFIGURATION, serialize(key)),
+			Key::Coin(ref key) => (COL_COINS, serialize(key)),
+			Key::BlockUndo(ref key) => (COL_BLOCK_UNDO, serialize(key)),
+			Key::SpentInfo(ref key) => (COL_SPENT_INFO, serialize(key)),
+			Key::TransactionLocation(ref key) => (COL_TRANSACTION_LOCATIONS, serialize(key)),
+			Key::BlockPosition(ref key) => (COL_BLOCK_POSITIONS, serialize(key)),
 		};
 
 		RawKey {
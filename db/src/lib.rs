@@ -9,12 +9,18 @@ extern crate bit_vec;
 extern crate lru_cache;
 
 extern crate primitives;
+extern crate bitcrypto as crypto;
 extern crate serialization as ser;
 extern crate chain;
 extern crate storage;
 
 pub mod kv;
 mod block_chain_db;
+mod block_files;
+mod migration;
+mod snapshot;
 
-pub use block_chain_db::{BlockChainDatabase, ForkChainDatabase};
+pub use block_chain_db::{BlockChainDatabase, ForkChainDatabase, SCHEMA_VERSION};
+pub use block_files::{BlockFiles, BlockPosition, MAX_BLOCK_FILE_SIZE};
+pub use migration::{migrate_database, complete_interrupted_migration};
 pub use primitives::{hash, bytes};
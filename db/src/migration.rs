@@ -0,0 +1,209 @@
+//! Conversion of the database to other storage engine.
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use kv::{DatabaseEngine, DatabaseConfig, migrate, COL_COUNT};
+use block_chain_db::BLOCK_FILES_DIR;
+
+/// File, which marks the completely copied database
+const MIGRATION_COMPLETE_FILE_NAME: &'static str = "MIGRATION_COMPLETE";
+
+fn io_error(err: ::std::io::Error) -> String {
+	format!("Database migration error: {}", err)
+}
+
+/// Path in the same directory, with given suffix appended to the file name
+fn sibling_path(path: &Path, suffix: &str) -> Result<PathBuf, String> {
+	let mut name = path.file_name()
+		.ok_or_else(|| format!("Invalid database path: {}", path.display()))?
+		.to_os_string();
+	name.push(suffix);
+	Ok(path.with_file_name(name))
+}
+
+/// Converts database at given path to the other storage engine. All columns are copied to the
+/// temporary database first, and block files are linked into it. Then the original database is
+/// kept as backup, and the copy takes its place. Returns path of the backup.
+///
+/// Original database is never modified, so the interrupted migration is either restarted (if the
+/// copy hasn't been completed) or completed by `complete_interrupted_migration`
+pub fn migrate_database<P>(path: P, to: DatabaseEngine) -> Result<PathBuf, String> where P: AsRef<Path> {
+	let path = path.as_ref();
+	complete_interrupted_migration(path)?;
+
+	let from = DatabaseEngine::detect(path)
+		.ok_or_else(|| format!("Database is not found at {}", path.display()))?;
+	if from == to {
+		return Err(format!("Database at {} already uses {} backend", path.display(), to));
+	}
+
+	let migration_path = sibling_path(path, ".migrating")?;
+	let backup_path = sibling_path(path, &format!(".{}-backup", from))?;
+	if backup_path.exists() {
+		return Err(format!("Backup of the database already exists at {}. Remove it first", backup_path.display()));
+	}
+
+	// leftovers of the interrupted migration only have links to the block files of the original database
+	if migration_path.exists() {
+		fs::remove_dir_all(&migration_path).map_err(io_error)?;
+	}
+
+	copy_database(path, &migration_path, from, to)?;
+
+	fs::rename(path, &backup_path).map_err(io_error)?;
+	fs::rename(&migration_path, path).map_err(io_error)?;
+	fs::remove_file(path.join(MIGRATION_COMPLETE_FILE_NAME)).map_err(io_error)?;
+
+	Ok(backup_path)
+}
+
+/// Completes migration, which has been interrupted after the original database has been moved to
+/// the backup. Must be called before the database at given path is opened
+pub fn complete_interrupted_migration<P>(path: P) -> Result<(), String> where P: AsRef<Path> {
+	let path = path.as_ref();
+	let migration_path = sibling_path(path, ".migrating")?;
+	if !path.exists() && migration_path.join(MIGRATION_COMPLETE_FILE_NAME).exists() {
+		info!(target: "db", "Completing interrupted migration of the database at {}", path.display());
+		fs::rename(&migration_path, path).map_err(io_error)?;
+	}
+
+	let marker_path = path.join(MIGRATION_COMPLETE_FILE_NAME);
+	if marker_path.exists() {
+		fs::remove_file(marker_path).map_err(io_error)?;
+	}
+
+	Ok(())
+}
+
+/// Copies database to the other engine and links its block files. Copy is marked as complete
+fn copy_database(path: &Path, migration_path: &Path, from: DatabaseEngine, to: DatabaseEngine) -> Result<(), String> {
+	{
+		let source = from.open(DatabaseConfig::with_columns(Some(COL_COUNT)), path)?;
+		let target = to.open(DatabaseConfig::with_columns(Some(COL_COUNT)), migration_path)?;
+		info!(target: "db", "Copying database from {} to {} backend", from, to);
+		let copied = migrate(&*source, &*target, COL_COUNT)?;
+		info!(target: "db", "Copied {} database entries", copied);
+	}
+
+	let block_files_path = path.join(BLOCK_FILES_DIR);
+	if block_files_path.exists() {
+		link_block_files(&block_files_path, &migration_path.join(BLOCK_FILES_DIR))?;
+	}
+
+	let marker = File::create(migration_path.join(MIGRATION_COMPLETE_FILE_NAME)).map_err(io_error)?;
+	marker.sync_all().map_err(io_error)
+}
+
+/// Hard links block files to the other directory. The last file is copied instead, because new
+/// blocks are appended to it. Files are copied if links are not supported
+fn link_block_files(from: &Path, to: &Path) -> Result<(), String> {
+	fs::create_dir_all(to).map_err(io_error)?;
+	let mut names = Vec::new();
+	for entry in fs::read_dir(from).map_err(io_error)? {
+		names.push(entry.map_err(io_error)?.file_name());
+	}
+	// names of the block files are zero-padded => the last name is the name of the last file
+	names.sort();
+
+	let last_name = names.last().cloned();
+	for name in names {
+		let (source, target) = (from.join(&name), to.join(&name));
+		if Some(&name) != last_name.as_ref() && fs::hard_link(&source, &target).is_ok() {
+			continue;
+		}
+		fs::copy(&source, &target).map_err(io_error)?;
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	extern crate tempdir;
+
+	use std::fs::{self, OpenOptions};
+	use std::io::Write;
+	use std::path::Path;
+	use self::tempdir::TempDir;
+	use kv::{DatabaseEngine, DatabaseConfig, RawTransaction, RawKey, Location, COL_COUNT, COL_META, COL_COINS};
+	use block_chain_db::BLOCK_FILES_DIR;
+	use super::{migrate_database, complete_interrupted_migration, copy_database, sibling_path, MIGRATION_COMPLETE_FILE_NAME};
+
+	fn create_database(path: &Path) {
+		let db = DatabaseEngine::RocksDb.open(DatabaseConfig::with_columns(Some(COL_COUNT)), path).unwrap();
+		let mut batch = RawTransaction::default();
+		batch.insert_raw(Location::Column(COL_META), b"key1", b"cat");
+		db.write_raw(batch).unwrap();
+
+		fs::create_dir_all(path.join(BLOCK_FILES_DIR)).unwrap();
+		fs::write(path.join(BLOCK_FILES_DIR).join("blk00000.dat"), b"block0").unwrap();
+		fs::write(path.join(BLOCK_FILES_DIR).join("blk00001.dat"), b"block1").unwrap();
+	}
+
+	fn assert_block_files(path: &Path) {
+		assert_eq!(fs::read(path.join(BLOCK_FILES_DIR).join("blk00000.dat")).unwrap(), b"block0");
+		assert_eq!(fs::read(path.join(BLOCK_FILES_DIR).join("blk00001.dat")).unwrap(), b"block1");
+	}
+
+	#[test]
+	fn database_is_migrated_to_other_engine() {
+		let dir = TempDir::new("").unwrap();
+		let path = dir.path().join("db");
+		{
+			let db = DatabaseEngine::RocksDb.open(DatabaseConfig::with_columns(Some(COL_COUNT)), &path).unwrap();
+			let mut batch = RawTransaction::default();
+			batch.insert_raw(Location::Column(COL_META), b"key1", b"cat");
+			batch.insert_raw(Location::Column(COL_COINS), b"key2", b"dog");
+			db.write_raw(batch).unwrap();
+		}
+
+		assert!(migrate_database(&path, DatabaseEngine::RocksDb).is_err());
+		let backup_path = migrate_database(&path, DatabaseEngine::Log).unwrap();
+		assert_eq!(DatabaseEngine::detect(&path), Some(DatabaseEngine::Log));
+		assert_eq!(DatabaseEngine::detect(&backup_path), Some(DatabaseEngine::RocksDb));
+
+		let db = DatabaseEngine::Log.open(DatabaseConfig::default(), &path).unwrap();
+		assert_eq!(&*db.get_raw(&RawKey::new(Location::Column(COL_META), b"key1" as &[u8])).unwrap().unwrap(), b"cat");
+		assert_eq!(&*db.get_raw(&RawKey::new(Location::Column(COL_COINS), b"key2" as &[u8])).unwrap().unwrap(), b"dog");
+		assert!(DatabaseEngine::RocksDb.open(DatabaseConfig::with_columns(Some(COL_COUNT)), &path).is_err());
+	}
+
+	#[test]
+	fn interrupted_migration_is_restarted_or_completed() {
+		let dir = TempDir::new("").unwrap();
+		let path = dir.path().join("db");
+		let migration_path = sibling_path(&path, ".migrating").unwrap();
+		let backup_path = sibling_path(&path, ".rocksdb-backup").unwrap();
+		create_database(&path);
+
+		// interrupted before the copy is completed => copy is removed and migration is restarted
+		copy_database(&path, &migration_path, DatabaseEngine::RocksDb, DatabaseEngine::Log).unwrap();
+		fs::remove_file(migration_path.join(MIGRATION_COMPLETE_FILE_NAME)).unwrap();
+		assert_eq!(migrate_database(&path, DatabaseEngine::Log).unwrap(), backup_path);
+		assert!(!migration_path.exists());
+		assert_block_files(&path);
+		assert_block_files(&backup_path);
+
+		// blocks, appended by the migrated database, are not appended to the backup
+		OpenOptions::new().append(true).open(path.join(BLOCK_FILES_DIR).join("blk00001.dat")).unwrap()
+			.write_all(b"block2").unwrap();
+		assert_block_files(&backup_path);
+
+		// interrupted after the original database is moved to the backup => migration is completed
+		// before the database is opened
+		fs::remove_dir_all(&path).unwrap();
+		fs::remove_dir_all(&backup_path).unwrap();
+		create_database(&path);
+		copy_database(&path, &migration_path, DatabaseEngine::RocksDb, DatabaseEngine::Log).unwrap();
+		fs::rename(&path, &backup_path).unwrap();
+		complete_interrupted_migration(&path).unwrap();
+		assert!(!migration_path.exists());
+		assert!(!path.join(MIGRATION_COMPLETE_FILE_NAME).exists());
+		assert_eq!(DatabaseEngine::detect(&path), Some(DatabaseEngine::Log));
+		assert_block_files(&path);
+		assert_block_files(&backup_path);
+
+		let db = DatabaseEngine::Log.open(DatabaseConfig::default(), &path).unwrap();
+		assert_eq!(&*db.get_raw(&RawKey::new(Location::Column(COL_META), b"key1" as &[u8])).unwrap().unwrap(), b"cat");
+	}
+}
@@ -0,0 +1,225 @@
+//! UTXO set snapshot file.
+//!
+//! Snapshot consists of:
+//! - magic bytes and format version;
+//! - hash and number of the base block;
+//! - headers of all canon blocks from genesis to the base block;
+//! - unspent outputs, ordered by serialized outpoints. Every coin is prefixed with `true` and
+//!   the list is terminated with `false`;
+//! - number of coins and content hash: dhash256 of all serialized (outpoint, coin) pairs.
+
+use std::io;
+use primitives::io as ser_io;
+use crypto::{DHash256, Digest};
+use hash::H256;
+use chain::{BlockHeader, OutPoint};
+use ser::{Stream, Reader, serialize};
+use storage::{Coin, Error};
+
+const SNAPSHOT_MAGIC: [u8; 5] = [b'u', b't', b'x', b'o', 0xff];
+const SNAPSHOT_VERSION: u16 = 1;
+
+fn write_error(err: io::Error) -> Error {
+	Error::DatabaseError(format!("Failed to write snapshot: {}", err))
+}
+
+fn read_error(err: ser_io::Error) -> Error {
+	Error::InvalidSnapshot(format!("{:?}", err))
+}
+
+/// Serialized (outpoint, coin) pair, as it is written to the snapshot and hashed
+fn serialize_coin(outpoint: &OutPoint, coin: &Coin) -> Stream {
+	let mut stream = Stream::new();
+	stream
+		.append(outpoint)
+		.append(coin);
+	stream
+}
+
+/// Writes snapshot to the underlying writer
+pub struct SnapshotWriter<'a> {
+	writer: &'a mut io::Write,
+	hasher: DHash256,
+	coins_count: u64,
+}
+
+impl<'a> SnapshotWriter<'a> {
+	/// Writes snapshot header
+	pub fn new(writer: &'a mut io::Write, base_block_hash: &H256, base_block_number: u32) -> Result<Self, Error> {
+		let mut stream = Stream::new();
+		stream
+			.append_slice(&SNAPSHOT_MAGIC)
+			.append(&SNAPSHOT_VERSION)
+			.append(base_block_hash)
+			.append(&base_block_number);
+		writer.write_all(&stream.out()).map_err(write_error)?;
+
+		Ok(SnapshotWriter {
+			writer: writer,
+			hasher: DHash256::new(),
+			coins_count: 0,
+		})
+	}
+
+	/// Writes next block header. Headers must be written in order, starting from genesis
+	pub fn write_header(&mut self, header: &BlockHeader) -> Result<(), Error> {
+		self.writer.write_all(&serialize(header)).map_err(write_error)
+	}
+
+	/// Writes next coin. Coins must be written after all headers, in order of serialized outpoints
+	pub fn write_coin(&mut self, outpoint: &OutPoint, coin: &Coin) -> Result<(), Error> {
+		let coin = serialize_coin(outpoint, coin).out();
+		self.hasher.input(&coin);
+		self.coins_count += 1;
+		self.writer.write_all(&serialize(&true)).map_err(write_error)?;
+		self.writer.write_all(&coin).map_err(write_error)
+	}
+
+	/// Writes snapshot trailer. Returns number of coins and content hash
+	pub fn finish(self) -> Result<(u64, H256), Error> {
+		let content_hash = self.hasher.finish();
+		let mut stream = Stream::new();
+		stream
+			.append(&false)
+			.append(&self.coins_count)
+			.append(&content_hash);
+		self.writer.write_all(&stream.out()).map_err(write_error)?;
+		self.writer.flush().map_err(write_error)?;
+		Ok((self.coins_count, content_hash))
+	}
+}
+
+/// Adapts `std::io::Read` to the serialization reader
+struct ReadAdapter<R>(R);
+
+impl<R> ser_io::Read for ReadAdapter<R> where R: io::Read {
+	fn read(&mut self, buf: &mut [u8]) -> Result<usize, ser_io::Error> {
+		self.0.read(buf).map_err(|_| ser_io::ErrorKind::UnexpectedEof)
+	}
+}
+
+/// Reads snapshot from the underlying reader, computing its content hash
+pub struct SnapshotReader<R> {
+	reader: Reader<ReadAdapter<R>>,
+	hasher: DHash256,
+	coins_count: u64,
+	base_block_hash: H256,
+	base_block_number: u32,
+}
+
+impl<R> SnapshotReader<R> where R: io::Read {
+	/// Reads and checks snapshot header
+	pub fn new(reader: R) -> Result<Self, Error> {
+		let mut reader = Reader::from_read(ReadAdapter(reader));
+		let mut magic = [0u8; 5];
+		reader.read_slice(&mut magic).map_err(read_error)?;
+		if magic != SNAPSHOT_MAGIC {
+			return Err(Error::InvalidSnapshot("not a snapshot file".into()));
+		}
+
+		let version: u16 = reader.read().map_err(read_error)?;
+		if version != SNAPSHOT_VERSION {
+			return Err(Error::InvalidSnapshot(format!("unsupported snapshot version {}", version)));
+		}
+
+		Ok(SnapshotReader {
+			base_block_hash: reader.read().map_err(read_error)?,
+			base_block_number: reader.read().map_err(read_error)?,
+			reader: reader,
+			hasher: DHash256::new(),
+			coins_count: 0,
+		})
+	}
+
+	/// Hash of the block, at which snapshot has been made
+	pub fn base_block_hash(&self) -> &H256 {
+		&self.base_block_hash
+	}
+
+	/// Number of the block, at which snapshot has been made
+	pub fn base_block_number(&self) -> u32 {
+		self.base_block_number
+	}
+
+	/// Reads next block header
+	pub fn read_header(&mut self) -> Result<BlockHeader, Error> {
+		self.reader.read().map_err(read_error)
+	}
+
+	/// Reads next coin. Returns None after the last coin is read
+	pub fn read_coin(&mut self) -> Result<Option<(OutPoint, Coin)>, Error> {
+		let has_coin: bool = self.reader.read().map_err(read_error)?;
+		if !has_coin {
+			return Ok(None);
+		}
+
+		let outpoint: OutPoint = self.reader.read().map_err(read_error)?;
+		let coin: Coin = self.reader.read().map_err(read_error)?;
+		self.hasher.input(&serialize_coin(&outpoint, &coin).out());
+		self.coins_count += 1;
+		Ok(Some((outpoint, coin)))
+	}
+
+	/// Reads and checks snapshot trailer. Must be called after all coins are read.
+	/// Returns number of coins and content hash
+	pub fn finish(mut self) -> Result<(u64, H256), Error> {
+		let coins_count: u64 = self.reader.read().map_err(read_error)?;
+		let content_hash: H256 = self.reader.read().map_err(read_error)?;
+		if !self.reader.is_finished() {
+			return Err(Error::InvalidSnapshot("unexpected data after the snapshot end".into()));
+		}
+
+		let computed_hash = self.hasher.finish();
+		if coins_count != self.coins_count || content_hash != computed_hash {
+			return Err(Error::InvalidSnapshot("snapshot is corrupted".into()));
+		}
+
+		Ok((coins_count, content_hash))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	extern crate test_data;
+
+	use chain::OutPoint;
+	use storage::Coin;
+	use super::{SnapshotWriter, SnapshotReader};
+
+	#[test]
+	fn snapshot_file_roundtrip() {
+		let header = test_data::genesis().block_header;
+		let outpoint = OutPoint { hash: 1.into(), index: 0 };
+		let coin = Coin {
+			value: 100,
+			script_pubkey: vec![0x51].into(),
+			height: 0,
+			is_coinbase: true,
+		};
+
+		let mut data = Vec::new();
+		let written = {
+			let mut writer = SnapshotWriter::new(&mut data, &header.hash(), 0).unwrap();
+			writer.write_header(&header).unwrap();
+			writer.write_coin(&outpoint, &coin).unwrap();
+			writer.finish().unwrap()
+		};
+
+		let mut reader = SnapshotReader::new(&data as &[u8]).unwrap();
+		assert_eq!(reader.base_block_hash(), &header.hash());
+		assert_eq!(reader.base_block_number(), 0);
+		assert_eq!(reader.read_header().unwrap(), header);
+		assert_eq!(reader.read_coin().unwrap(), Some((outpoint, coin)));
+		assert_eq!(reader.read_coin().unwrap(), None);
+		assert_eq!(reader.finish().unwrap(), written);
+		assert_eq!(written.0, 1);
+
+		// corrupted coin value
+		data[5 + 2 + 32 + 4 + 80 + 1 + 36 + 4 + 1] ^= 1;
+		let mut reader = SnapshotReader::new(&data as &[u8]).unwrap();
+		reader.read_header().unwrap();
+		reader.read_coin().unwrap();
+		reader.read_coin().unwrap();
+		assert!(reader.finish().is_err());
+	}
+}
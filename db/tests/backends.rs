@@ -0,0 +1,141 @@
+//! Conformance tests, run against every `KeyValueDatabase` backend.
+
+extern crate chain;
+extern crate storage;
+extern crate db;
+extern crate tempdir;
+
+use tempdir::TempDir;
+use chain::OutPoint;
+use storage::Coin;
+use db::hash::H256;
+use db::kv::{
+	KeyValueDatabase, MemoryDatabase, DiskDatabase, DatabaseEngine, DatabaseConfig, Transaction, KeyValue, Key,
+	Value, COL_COUNT
+};
+
+fn coin(value: u64) -> Coin {
+	Coin {
+		value: value,
+		script_pubkey: vec![value as u8].into(),
+		height: 1,
+		is_coinbase: false,
+	}
+}
+
+fn outpoint(hash: u8, index: u32) -> OutPoint {
+	OutPoint {
+		hash: H256::from(hash),
+		index: index,
+	}
+}
+
+fn block_hash(db: &KeyValueDatabase, number: u32) -> Option<H256> {
+	db.get(&Key::BlockHash(number)).unwrap().into_option().and_then(Value::as_block_hash)
+}
+
+fn coins(db: &KeyValueDatabase) -> Vec<(OutPoint, Coin)> {
+	let mut coins = Vec::new();
+	db.for_each_coin(&mut |outpoint, coin| {
+		coins.push((outpoint, coin));
+		Ok(())
+	}).unwrap();
+	coins
+}
+
+fn check_conformance(db: &KeyValueDatabase) {
+	// missing keys
+	assert_eq!(block_hash(db, 1), None);
+	assert!(coins(db).is_empty());
+
+	// insertion
+	let mut tx = Transaction::new();
+	tx.insert(KeyValue::BlockHash(1, H256::from(1)));
+	tx.insert(KeyValue::BlockHash(2, H256::from(2)));
+	tx.insert(KeyValue::Meta("key", vec![1, 2, 3].into()));
+	db.write(tx).unwrap();
+	assert_eq!(block_hash(db, 1), Some(H256::from(1)));
+	assert_eq!(block_hash(db, 2), Some(H256::from(2)));
+	assert_eq!(db.get(&Key::Meta("key")).unwrap().into_option().and_then(Value::as_meta), Some(vec![1, 2, 3].into()));
+
+	// overwrite and deletion
+	let mut tx = Transaction::new();
+	tx.insert(KeyValue::BlockHash(1, H256::from(3)));
+	tx.delete(Key::BlockHash(2));
+	db.write(tx).unwrap();
+	assert_eq!(block_hash(db, 1), Some(H256::from(3)));
+	assert_eq!(block_hash(db, 2), None);
+
+	// operations of the single transaction are applied in order
+	let mut tx = Transaction::new();
+	tx.insert(KeyValue::BlockHash(3, H256::from(3)));
+	tx.delete(Key::BlockHash(3));
+	tx.delete(Key::BlockHash(4));
+	tx.insert(KeyValue::BlockHash(4, H256::from(4)));
+	db.write(tx).unwrap();
+	assert_eq!(block_hash(db, 3), None);
+	assert_eq!(block_hash(db, 4), Some(H256::from(4)));
+
+	// columns are independent
+	assert_eq!(db.get(&Key::BlockNumber(H256::from(4))).unwrap().into_option(), None);
+
+	// coins are iterated in order of serialized outpoints
+	let mut tx = Transaction::new();
+	tx.insert(KeyValue::Coin(outpoint(2, 0), coin(20)));
+	tx.insert(KeyValue::Coin(outpoint(1, 1), coin(11)));
+	tx.insert(KeyValue::Coin(outpoint(1, 0), coin(10)));
+	tx.insert(KeyValue::Coin(outpoint(3, 0), coin(30)));
+	db.write(tx).unwrap();
+	let mut tx = Transaction::new();
+	tx.delete(Key::Coin(outpoint(3, 0)));
+	db.write(tx).unwrap();
+	assert_eq!(coins(db), vec![
+		(outpoint(1, 0), coin(10)),
+		(outpoint(1, 1), coin(11)),
+		(outpoint(2, 0), coin(20)),
+	]);
+}
+
+fn check_persistence(engine: DatabaseEngine) {
+	let dir = TempDir::new("").unwrap();
+	{
+		let db = engine.open(DatabaseConfig::with_columns(Some(COL_COUNT)), dir.path()).unwrap();
+		check_conformance(&db);
+	}
+
+	let db = engine.open(DatabaseConfig::with_columns(Some(COL_COUNT)), dir.path()).unwrap();
+	assert_eq!(block_hash(&db, 1), Some(H256::from(3)));
+	assert_eq!(block_hash(&db, 2), None);
+	assert_eq!(coins(&db).len(), 3);
+}
+
+#[test]
+fn memory_database_conformance() {
+	check_conformance(&MemoryDatabase::default());
+}
+
+#[test]
+fn rocksdb_database_conformance() {
+	let dir = TempDir::new("").unwrap();
+	check_conformance(&DiskDatabase::open(DatabaseConfig::with_columns(Some(COL_COUNT)), dir.path()).unwrap());
+	check_persistence(DatabaseEngine::RocksDb);
+}
+
+#[test]
+fn log_database_conformance() {
+	check_persistence(DatabaseEngine::Log);
+}
+
+#[test]
+fn database_engine_is_detected() {
+	let dir = TempDir::new("").unwrap();
+	assert_eq!(DatabaseEngine::detect(dir.path()), None);
+	{
+		let _db = DatabaseEngine::Log.open(DatabaseConfig::with_columns(Some(COL_COUNT)), dir.path()).unwrap();
+	}
+	assert_eq!(DatabaseEngine::detect(dir.path()), Some(DatabaseEngine::Log));
+	assert!(DatabaseEngine::RocksDb.open(DatabaseConfig::with_columns(Some(COL_COUNT)), dir.path()).is_err());
+	assert_eq!("log".parse::<DatabaseEngine>(), Ok(DatabaseEngine::Log));
+	assert_eq!("rocksdb".parse::<DatabaseEngine>(), Ok(DatabaseEngine::RocksDb));
+	assert!("leveldb".parse::<DatabaseEngine>().is_err());
+}
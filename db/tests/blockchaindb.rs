@@ -1,16 +1,30 @@
 extern crate chain;
 extern crate storage;
 extern crate db;
+extern crate serialization as ser;
 extern crate test_data;
+extern crate tempdir;
 
-use chain::IndexedBlock;
-use storage::{ForkChain, BlockProvider, SideChainOrigin};
-use db::BlockChainDatabase;
-use db::kv::{MemoryDatabase, SharedMemoryDatabase};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::sync::Arc;
+use std::thread;
+use tempdir::TempDir;
+use chain::{IndexedBlock, OutPoint};
+use ser::{serialize, List};
+use storage::{
+	ForkChain, BlockProvider, BlockHeaderProvider, SideChainOrigin, TransactionOutputProvider, TransactionProvider,
+	PruneMode, PruneStore, Error, MIN_BLOCKS_TO_KEEP, SnapshotStore, SnapshotValidation,
+	SpentInfo, SpentInfoProvider, TransactionLocation, TransactionLocationProvider, ChainTip, ChainTipStatus,
+	ChainTipStore, TransactionMeta
+};
+use db::{BlockChainDatabase, BlockFiles, SCHEMA_VERSION};
+use db::kv::{MemoryDatabase, SharedMemoryDatabase, KeyValueDatabase, Transaction as DBTransaction, Key, KeyValue, Value};
 
 #[test]
 fn insert_block() {
-	let store = BlockChainDatabase::open(MemoryDatabase::default());
+	let store = BlockChainDatabase::open(MemoryDatabase::default()).unwrap();
 	let b0: IndexedBlock = test_data::block_h0().into();
 	let b1: IndexedBlock = test_data::block_h1().into();
 	let b2: IndexedBlock = test_data::block_h2().into();
@@ -56,7 +70,7 @@ fn reopen_db() {
 	let b2: IndexedBlock = test_data::block_h2().into();
 
 	{
-		let store = BlockChainDatabase::open(shared_database.clone());
+		let store = BlockChainDatabase::open(shared_database.clone()).unwrap();
 		store.insert(b0.clone()).unwrap();
 		store.insert(b1.clone()).unwrap();
 		store.insert(b2.clone()).unwrap();
@@ -68,7 +82,7 @@ fn reopen_db() {
 		store.decanonize().unwrap();
 	}
 	{
-		let store = BlockChainDatabase::open(shared_database);
+		let store = BlockChainDatabase::open(shared_database).unwrap();
 		assert_eq!(b0.hash(), &store.block_hash(0).unwrap());
 		assert_eq!(1, store.best_block().number);
 		assert_eq!(b1.hash(), &store.best_block().hash);
@@ -77,7 +91,7 @@ fn reopen_db() {
 
 #[test]
 fn switch_to_simple_fork() {
-	let store = BlockChainDatabase::open(MemoryDatabase::default());
+	let store = BlockChainDatabase::open(MemoryDatabase::default()).unwrap();
 	let b0: IndexedBlock = test_data::block_h0().into();
 	let b1: IndexedBlock = test_data::block_h1().into();
 	let b2: IndexedBlock = test_data::block_h2().into();
@@ -128,3 +142,691 @@ fn switch_to_simple_fork() {
 	assert_eq!(store.best_block().hash, store.block_hash(2).unwrap());
 
 }
+
+#[test]
+fn coins_are_restored_from_undo_data() {
+	let b0: IndexedBlock = test_data::block_builder()
+		.transaction()
+			.coinbase()
+			.output().value(50).build()
+			.build()
+		.merkled_header().build()
+		.build()
+		.into();
+	let genesis_coinbase = OutPoint { hash: b0.transactions[0].hash.clone(), index: 0 };
+
+	let b1: IndexedBlock = test_data::block_builder()
+		.transaction()
+			.coinbase()
+			.output().value(10).build()
+			.build()
+		.transaction()
+			.input().hash(genesis_coinbase.hash.clone()).build()
+			.output().value(40).build()
+			.build()
+		.derived_transaction(1, 0)
+			.output().value(30).build()
+			.build()
+		.merkled_header().parent(b0.hash().clone()).build()
+		.build()
+		.into();
+	let spent_in_block = OutPoint { hash: b1.transactions[1].hash.clone(), index: 0 };
+	let unspent = OutPoint { hash: b1.transactions[2].hash.clone(), index: 0 };
+
+	let store = BlockChainDatabase::init_test_chain(vec![b0.clone()]);
+	assert_eq!(store.coin(&genesis_coinbase).unwrap().value, 50);
+	assert!(store.coin(&genesis_coinbase).unwrap().is_coinbase);
+
+	store.insert(b1.clone()).unwrap();
+	store.canonize(b1.hash()).unwrap();
+
+	assert!(store.coin(&genesis_coinbase).is_none());
+	assert!(store.is_spent(&genesis_coinbase));
+	assert!(store.coin(&spent_in_block).is_none());
+	assert_eq!(store.transaction_output(&unspent, 0).unwrap().value, 30);
+	assert_eq!(store.coin(&unspent).unwrap().height, 1);
+	assert!(!store.coin(&unspent).unwrap().is_coinbase);
+
+	assert_eq!(b1.hash(), &store.decanonize().unwrap());
+
+	assert_eq!(store.transaction_output(&genesis_coinbase, 0).unwrap().value, 50);
+	assert!(!store.is_spent(&genesis_coinbase));
+	assert!(store.coin(&spent_in_block).is_none());
+	assert!(store.coin(&unspent).is_none());
+}
+
+#[test]
+fn spent_index_is_maintained_on_canonize_and_decanonize() {
+	let b0: IndexedBlock = test_data::block_builder()
+		.transaction()
+			.coinbase()
+			.output().value(50).build()
+			.build()
+		.merkled_header().build()
+		.build()
+		.into();
+	let genesis_coinbase = OutPoint { hash: b0.transactions[0].hash.clone(), index: 0 };
+
+	let b1: IndexedBlock = test_data::block_builder()
+		.transaction()
+			.coinbase()
+			.output().value(10).build()
+			.build()
+		.transaction()
+			.input().hash(genesis_coinbase.hash.clone()).build()
+			.output().value(40).build()
+			.build()
+		.merkled_header().parent(b0.hash().clone()).build()
+		.build()
+		.into();
+
+	let store = BlockChainDatabase::open(MemoryDatabase::default()).unwrap();
+	assert!(!store.is_spent_index_enabled());
+	let store = store.with_spent_index(true);
+	assert!(store.is_spent_index_enabled());
+
+	for block in vec![b0.clone(), b1.clone()] {
+		let hash = block.hash().clone();
+		store.insert(block).unwrap();
+		store.canonize(&hash).unwrap();
+	}
+
+	assert_eq!(store.spent_info(&genesis_coinbase), Some(SpentInfo {
+		txid: b1.transactions[1].hash.clone(),
+		index: 0,
+		height: 1,
+	}));
+	assert_eq!(store.spent_info(&OutPoint { hash: b1.transactions[1].hash.clone(), index: 0 }), None);
+
+	assert_eq!(b1.hash(), &store.decanonize().unwrap());
+	assert_eq!(store.spent_info(&genesis_coinbase), None);
+}
+
+#[test]
+fn transaction_location_is_maintained_on_canonize_and_decanonize() {
+	let b0: IndexedBlock = test_data::genesis().into();
+	let b1: IndexedBlock = test_data::block_h1().into();
+	let b1_coinbase = b1.transactions[0].hash.clone();
+
+	let store = BlockChainDatabase::init_test_chain(vec![b0.clone(), b1.clone()]);
+	assert_eq!(store.transaction_location(&b0.transactions[0].hash), Some(TransactionLocation {
+		block_hash: b0.hash().clone(),
+		index: 0,
+	}));
+	assert_eq!(store.transaction_location(&b1_coinbase), Some(TransactionLocation {
+		block_hash: b1.hash().clone(),
+		index: 0,
+	}));
+
+	assert_eq!(b1.hash(), &store.decanonize().unwrap());
+	assert_eq!(store.transaction_location(&b1_coinbase), None);
+	// side chain transactions have no location
+	assert!(store.transaction(&b1_coinbase).is_some());
+}
+
+#[test]
+fn chain_tips_are_tracked_and_invalid_branches_are_switched_off() {
+	let block = |parent: &IndexedBlock, value: u64| -> IndexedBlock {
+		test_data::block_builder()
+			.transaction()
+				.coinbase()
+				.output().value(value).build()
+				.build()
+			.merkled_header().parent(parent.hash().clone()).build()
+			.build()
+			.into()
+	};
+	let b0: IndexedBlock = test_data::block_builder()
+		.transaction().coinbase().build()
+		.merkled_header().build()
+		.build()
+		.into();
+	let a1 = block(&b0, 1);
+	let a2 = block(&a1, 2);
+	let b1 = block(&b0, 3);
+
+	let store = BlockChainDatabase::init_test_chain(vec![b0.clone(), a1.clone(), a2.clone()]);
+	store.insert(b1.clone()).unwrap();
+	assert_eq!(store.chain_tips(), vec![
+		ChainTip { hash: a2.hash().clone(), number: 2, branch_len: 0, status: ChainTipStatus::Active },
+		ChainTip { hash: b1.hash().clone(), number: 1, branch_len: 1, status: ChainTipStatus::ValidFork },
+	]);
+
+	// invalidation of canon block switches to the best valid branch
+	assert_eq!(store.invalidate_block(b0.hash()).unwrap_err(), Error::CannotInvalidateGenesis);
+	let origin = store.invalidate_block(a1.hash()).unwrap();
+	assert_eq!(origin.canonized_route, vec![b1.hash().clone()]);
+	assert_eq!(origin.decanonized_route, vec![a1.hash().clone(), a2.hash().clone()]);
+	assert_eq!(b1.hash(), &store.best_block().hash);
+	assert!(store.is_invalid_block(a2.hash()));
+	assert!(!store.is_invalid_block(b1.hash()));
+	assert_eq!(store.chain_tips(), vec![
+		ChainTip { hash: a2.hash().clone(), number: 2, branch_len: 2, status: ChainTipStatus::Invalid },
+		ChainTip { hash: b1.hash().clone(), number: 1, branch_len: 0, status: ChainTipStatus::Active },
+	]);
+
+	// reconsideration of descendant also reconsiders its ancestors
+	store.reconsider_block(a2.hash()).unwrap();
+	assert_eq!(a2.hash(), &store.best_block().hash);
+	assert!(!store.is_invalid_block(a2.hash()));
+
+	// headers-only branch
+	store.insert_chain_tip(&1.into(), a2.hash(), 3, ChainTipStatus::HeadersOnly).unwrap();
+	assert_eq!(store.chain_tips(), vec![
+		ChainTip { hash: 1.into(), number: 5, branch_len: 3, status: ChainTipStatus::HeadersOnly },
+		ChainTip { hash: a2.hash().clone(), number: 2, branch_len: 0, status: ChainTipStatus::Active },
+		ChainTip { hash: b1.hash().clone(), number: 1, branch_len: 1, status: ChainTipStatus::ValidFork },
+	]);
+}
+
+#[test]
+fn concurrently_inserted_side_blocks_are_chain_tips() {
+	let b0: IndexedBlock = test_data::genesis().into();
+	let store = Arc::new(BlockChainDatabase::init_test_chain(vec![b0.clone()]));
+
+	let handles: Vec<_> = (0..8)
+		.map(|nonce| {
+			let store = store.clone();
+			let block: IndexedBlock = test_data::block_builder()
+				.header().parent(b0.hash().clone()).nonce(nonce).build()
+				.build()
+				.into();
+			thread::spawn(move || store.insert(block).unwrap())
+		})
+		.collect();
+	for handle in handles {
+		handle.join().unwrap();
+	}
+
+	// no update of the chain tips list is lost
+	let tips = store.chain_tips();
+	assert_eq!(tips.len(), 9);
+	assert!(tips[1..].iter().all(|tip| tip.status == ChainTipStatus::ValidFork && tip.branch_len == 1));
+}
+
+#[test]
+fn automatic_pruning_keeps_headers_and_recent_blocks() {
+	let dir = TempDir::new("blocks").unwrap();
+	let b0: IndexedBlock = test_data::genesis().into();
+	let genesis_coinbase = OutPoint { hash: b0.transactions[0].hash.clone(), index: 0 };
+	let blocks: Vec<IndexedBlock> = test_data::build_n_empty_blocks_from_genesis(MIN_BLOCKS_TO_KEEP + 10, 0)
+		.into_iter()
+		.map(Into::into)
+		.collect();
+
+	// genesis (285 bytes) and blocks 1..8 (81 bytes each) are stored in the first file
+	let store = BlockChainDatabase::open(MemoryDatabase::default()).unwrap()
+		.with_block_files(BlockFiles::open(dir.path()).unwrap().with_max_file_size(1000))
+		.with_prune_mode(PruneMode::Automatic { target_size: 0 });
+	assert_eq!(store.prune(100), Ok(None));
+
+	for block in ::std::iter::once(b0.clone()).chain(blocks.iter().cloned()) {
+		let hash = block.hash().clone();
+		store.insert(block).unwrap();
+		store.canonize(&hash).unwrap();
+	}
+
+	// the second file has blocks within MIN_BLOCKS_TO_KEEP from the best block => only blocks 0..8 are pruned
+	assert_eq!(store.pruned_height(), Some(8));
+	assert!(!dir.path().join("blk00000.dat").exists());
+	assert!(store.block(0.into()).is_none());
+	assert!(store.block(8.into()).is_none());
+	assert!(store.block(9.into()).is_some());
+	assert!(store.block_header(0.into()).is_some());
+	assert!(store.contains_block(b0.hash().clone().into()));
+	// unspent outputs are still available
+	assert_eq!(store.transaction_output(&genesis_coinbase, 0).unwrap().value, 5000000000);
+	assert!(store.transaction(&genesis_coinbase.hash).is_some());
+
+	// recent blocks can still be decanonized
+	let best_hash = store.best_block().hash;
+	assert_eq!(store.decanonize().unwrap(), best_hash);
+}
+
+#[test]
+fn automatic_pruning_keeps_block_files_within_target() {
+	let dir = TempDir::new("blocks").unwrap();
+	let blocks: Vec<IndexedBlock> = test_data::build_n_empty_blocks_from_genesis(MIN_BLOCKS_TO_KEEP + 100, 0)
+		.into_iter()
+		.map(Into::into)
+		.collect();
+
+	// 31713 bytes of blocks are stored in files of 9 (genesis file) or 12 blocks
+	let store = BlockChainDatabase::open(MemoryDatabase::default()).unwrap()
+		.with_block_files(BlockFiles::open(dir.path()).unwrap().with_max_file_size(1000))
+		.with_prune_mode(PruneMode::Automatic { target_size: 30000 });
+
+	for block in ::std::iter::once(test_data::genesis().into()).chain(blocks.into_iter()) {
+		let hash = block.hash().clone();
+		store.insert(block).unwrap();
+		store.canonize(&hash).unwrap();
+	}
+
+	// only the two oldest files are removed
+	let size: u64 = fs::read_dir(dir.path()).unwrap().map(|entry| entry.unwrap().metadata().unwrap().len()).sum();
+	assert_eq!(size, 29808);
+	assert_eq!(store.pruned_height(), Some(20));
+	assert!(!dir.path().join("blk00001.dat").exists());
+	assert!(dir.path().join("blk00002.dat").exists());
+	assert!(store.block(20.into()).is_none());
+	assert!(store.block(21.into()).is_some());
+}
+
+#[test]
+fn blocks_are_read_from_block_files() {
+	let dir = TempDir::new("blocks").unwrap();
+	let shared_database = SharedMemoryDatabase::default();
+	let b0: IndexedBlock = test_data::block_h0().into();
+	let b1: IndexedBlock = test_data::block_h1().into();
+	let b2: IndexedBlock = test_data::block_h2().into();
+
+	{
+		let store = BlockChainDatabase::open(shared_database.clone()).unwrap()
+			.with_block_files(BlockFiles::open(dir.path()).unwrap());
+		for block in vec![b0.clone(), b1.clone(), b2.clone()] {
+			let hash = block.hash().clone();
+			store.insert(block).unwrap();
+			store.canonize(&hash).unwrap();
+		}
+	}
+
+	let store = BlockChainDatabase::open(shared_database).unwrap()
+		.with_block_files(BlockFiles::open(dir.path()).unwrap());
+	assert_eq!(store.best_block().number, 2);
+	assert_eq!(store.block(1.into()), Some(b1.clone().to_raw_block()));
+	assert_eq!(store.block_transaction_hashes(2.into()), vec![b2.transactions[0].hash.clone()]);
+	assert_eq!(store.transaction(&b1.transactions[0].hash), Some(b1.transactions[0].raw.clone()));
+	assert_eq!(store.transaction(&b2.transactions[0].hash), Some(b2.transactions[0].raw.clone()));
+
+	// decanonized block is still readable until it is removed
+	assert_eq!(store.decanonize().unwrap(), *b2.hash());
+	assert_eq!(store.block(b2.hash().clone().into()), Some(b2.to_raw_block()));
+}
+
+#[test]
+fn pruned_block_files_are_removed() {
+	let dir = TempDir::new("blocks").unwrap();
+	let b0: IndexedBlock = test_data::genesis().into();
+	let genesis_coinbase = OutPoint { hash: b0.transactions[0].hash.clone(), index: 0 };
+	let blocks: Vec<IndexedBlock> = test_data::build_n_empty_blocks_from_genesis(MIN_BLOCKS_TO_KEEP + 10, 0)
+		.into_iter()
+		.map(Into::into)
+		.collect();
+	let side_block: IndexedBlock = test_data::block_builder()
+		.transaction().coinbase().output().value(1).build().build()
+		.merkled_header().parent(b0.hash().clone()).nonce(7).build()
+		.build()
+		.into();
+
+	// every block file keeps only a few blocks
+	let store = BlockChainDatabase::open(MemoryDatabase::default()).unwrap()
+		.with_block_files(BlockFiles::open(dir.path()).unwrap().with_max_file_size(1000))
+		.with_prune_mode(PruneMode::Automatic { target_size: 0 });
+
+	store.insert(b0.clone()).unwrap();
+	store.canonize(b0.hash()).unwrap();
+	store.insert(side_block.clone()).unwrap();
+	assert!(store.block(side_block.hash().clone().into()).is_some());
+	for block in blocks.iter().cloned() {
+		let hash = block.hash().clone();
+		store.insert(block).unwrap();
+		store.canonize(&hash).unwrap();
+	}
+
+	// genesis, side block and blocks 1..7 are stored in the first file
+	assert_eq!(store.pruned_height(), Some(7));
+	assert!(!dir.path().join("blk00000.dat").exists());
+	assert!(store.block(7.into()).is_none());
+	assert!(store.block(8.into()).is_some());
+	// side chain blocks from the removed files are not found
+	assert!(store.contains_block(side_block.hash().clone().into()));
+	assert!(store.block(side_block.hash().clone().into()).is_none());
+	assert_eq!(store.block_transaction_hashes(side_block.hash().clone().into()), vec![side_block.transactions[0].hash.clone()]);
+	// unspent transactions of pruned blocks are still available
+	assert!(store.transaction(&genesis_coinbase.hash).is_some());
+}
+
+#[test]
+fn manual_pruning() {
+	let store = BlockChainDatabase::init_test_chain(vec![test_data::genesis().into()]);
+	assert_eq!(store.prune(0), Err(Error::PruningDisabled));
+
+	let mut blocks: Vec<IndexedBlock> = vec![test_data::genesis().into()];
+	blocks.extend(test_data::build_n_empty_blocks_from_genesis(MIN_BLOCKS_TO_KEEP + 10, 0).into_iter().map(Into::into));
+	let store = BlockChainDatabase::init_test_chain(blocks).with_prune_mode(PruneMode::Manual);
+	assert_eq!(store.pruned_height(), None);
+	assert!(store.block(0.into()).is_some());
+
+	assert_eq!(store.prune(5), Ok(Some(5)));
+	assert_eq!(store.prune(3), Ok(Some(5)));
+	assert_eq!(store.prune(1000), Ok(Some(10)));
+	assert!(store.block(10.into()).is_none());
+	assert!(store.block(11.into()).is_some());
+}
+
+#[test]
+fn utxo_snapshot_is_loaded_into_chainstate_with_genesis_only() {
+	let b0: IndexedBlock = test_data::block_builder()
+		.transaction()
+			.coinbase()
+			.output().value(50).build()
+			.output().value(25).build()
+			.build()
+		.merkled_header().build()
+		.build()
+		.into();
+	let spent_before_snapshot = OutPoint { hash: b0.transactions[0].hash.clone(), index: 0 };
+	let unspent_at_snapshot = OutPoint { hash: b0.transactions[0].hash.clone(), index: 1 };
+
+	let b1: IndexedBlock = test_data::block_builder()
+		.transaction()
+			.coinbase()
+			.output().value(10).build()
+			.build()
+		.transaction()
+			.input().hash(spent_before_snapshot.hash.clone()).build()
+			.output().value(40).build()
+			.build()
+		.merkled_header().parent(b0.hash().clone()).build()
+		.build()
+		.into();
+
+	let b2: IndexedBlock = test_data::block_builder()
+		.transaction()
+			.coinbase()
+			.output().value(10).build()
+			.build()
+		.transaction()
+			.input().hash(unspent_at_snapshot.hash.clone()).index(1).build()
+			.output().value(20).build()
+			.build()
+		.merkled_header().parent(b1.hash().clone()).build()
+		.build()
+		.into();
+
+	let source = BlockChainDatabase::init_test_chain(vec![b0.clone(), b1.clone()]);
+	let mut data = Vec::new();
+	let info = source.dump_snapshot(&mut data).unwrap();
+	assert_eq!(&info.base.block_hash, b1.hash());
+	assert_eq!(info.base.block_number, 1);
+	assert_eq!(info.coins_count, 3);
+
+	let dir = TempDir::new("snapshot").unwrap();
+	let path = dir.path().join("utxo.dat");
+	fs::File::create(&path).unwrap().write_all(&data).unwrap();
+
+	let store = BlockChainDatabase::init_test_chain(vec![b0.clone()]);
+	// snapshot must be made at the block, known to the network
+	assert!(store.load_snapshot(&path, &[]).is_err());
+	let mut wrong_base = info.base.clone();
+	wrong_base.content_hash = 1.into();
+	assert!(store.load_snapshot(&path, &[wrong_base]).is_err());
+	assert_eq!(store.best_block().number, 0);
+	assert_eq!(store.snapshot_base(), None);
+
+	assert_eq!(store.load_snapshot(&path, &[info.base.clone()]), Ok(info.clone()));
+	assert_eq!(&store.best_block().hash, b1.hash());
+	assert_eq!(store.snapshot_base(), Some(info.base.clone()));
+	assert_eq!(store.snapshot_validation(), Some(SnapshotValidation::Pending));
+	assert!(store.block_header(1.into()).is_some());
+	assert!(store.block(1.into()).is_none());
+	assert!(store.coin(&spent_before_snapshot).is_none());
+	assert!(store.is_spent(&spent_before_snapshot));
+	assert_eq!(store.coin(&unspent_at_snapshot), source.coin(&unspent_at_snapshot));
+
+	// snapshot of loaded chainstate is the same
+	let mut loaded_data = Vec::new();
+	assert_eq!(store.dump_snapshot(&mut loaded_data).unwrap(), info);
+	assert_eq!(loaded_data, data);
+
+	// chainstate can be continued from the snapshot
+	store.insert(b2.clone()).unwrap();
+	store.canonize(b2.hash()).unwrap();
+	assert!(store.coin(&unspent_at_snapshot).is_none());
+	assert!(store.is_spent(&unspent_at_snapshot));
+
+	store.set_snapshot_validation(SnapshotValidation::Valid).unwrap();
+	assert_eq!(store.snapshot_validation(), Some(SnapshotValidation::Valid));
+}
+
+fn meta(db: &KeyValueDatabase, key: &'static str) -> Option<Vec<u8>> {
+	db.get(&Key::Meta(key)).unwrap().into_option().and_then(Value::as_meta).map(|value| value.to_vec())
+}
+
+fn has_transaction_location(db: &KeyValueDatabase, block: &IndexedBlock) -> bool {
+	db.get(&Key::TransactionLocation(block.transactions[0].hash.clone())).unwrap().into_option().is_some()
+}
+
+/// Database with the layout of the node before schema versioning: canon chain blocks, stored in
+/// the transactions column, and transactions meta. There are no coins, undo data, transaction
+/// locations, block positions, chain tips and schema version
+fn baseline_database(blocks: &[IndexedBlock]) -> SharedMemoryDatabase {
+	let mut update = DBTransaction::new();
+	let mut metas = HashMap::new();
+	for (number, block) in blocks.iter().enumerate() {
+		let number = number as u32;
+		let tx_hashes = block.transactions.iter().map(|tx| tx.hash.clone()).collect::<Vec<_>>();
+		update.insert(KeyValue::BlockHeader(block.hash().clone(), block.header.raw.clone()));
+		update.insert(KeyValue::BlockTransactions(block.hash().clone(), List::from(tx_hashes)));
+		update.insert(KeyValue::BlockHash(number, block.hash().clone()));
+		update.insert(KeyValue::BlockNumber(block.hash().clone(), number));
+
+		for (index, tx) in block.transactions.iter().enumerate() {
+			update.insert(KeyValue::Transaction(tx.hash.clone(), tx.raw.clone()));
+			if index == 0 {
+				metas.insert(tx.hash.clone(), TransactionMeta::new_coinbase(number, tx.raw.outputs.len()));
+				continue;
+			}
+
+			metas.insert(tx.hash.clone(), TransactionMeta::new(number, tx.raw.outputs.len()));
+			for input in &tx.raw.inputs {
+				metas.get_mut(&input.previous_output.hash).unwrap().denote_used(input.previous_output.index as usize);
+			}
+		}
+	}
+
+	for (hash, meta) in metas {
+		update.insert(KeyValue::TransactionMeta(hash, meta));
+	}
+	let best_block = blocks.last().unwrap();
+	update.insert(KeyValue::Meta("best_block_number", serialize(&(blocks.len() as u32 - 1))));
+	update.insert(KeyValue::Meta("best_block_hash", serialize(best_block.hash())));
+
+	let shared_database = SharedMemoryDatabase::default();
+	shared_database.write(update).unwrap();
+	shared_database
+}
+
+#[test]
+fn coins_and_undo_data_are_built_from_transactions_meta() {
+	let b0: IndexedBlock = test_data::block_builder()
+		.transaction()
+			.coinbase()
+			.output().value(50).build()
+			.output().value(5).build()
+			.build()
+		.merkled_header().build()
+		.build()
+		.into();
+	let spent = OutPoint { hash: b0.transactions[0].hash.clone(), index: 0 };
+	let unspent_coinbase = OutPoint { hash: b0.transactions[0].hash.clone(), index: 1 };
+
+	let b1: IndexedBlock = test_data::block_builder()
+		.transaction()
+			.coinbase()
+			.output().value(10).build()
+			.build()
+		.transaction()
+			.input().hash(spent.hash.clone()).index(spent.index).build()
+			.output().value(40).build()
+			.build()
+		.merkled_header().parent(b0.hash().clone()).build()
+		.build()
+		.into();
+	let unspent = OutPoint { hash: b1.transactions[1].hash.clone(), index: 0 };
+
+	let shared_database = baseline_database(&[b0.clone(), b1.clone()]);
+	let store = BlockChainDatabase::open(shared_database.clone()).unwrap();
+	let coin = |outpoint: &OutPoint| shared_database.get(&Key::Coin(outpoint.clone())).unwrap().into_option();
+	assert!(coin(&spent).is_none());
+	assert!(coin(&unspent_coinbase).and_then(Value::as_coin).unwrap().is_coinbase);
+	assert_eq!(coin(&unspent).and_then(Value::as_coin).unwrap().value, 40);
+	assert!(shared_database.get(&Key::BlockUndo(b1.hash().clone())).unwrap().into_option().is_some());
+
+	assert_eq!(b1.hash(), &store.decanonize().unwrap());
+	assert_eq!(store.coin(&spent).unwrap().value, 50);
+	assert!(store.coin(&unspent).is_none());
+}
+
+#[test]
+fn missing_coins_and_undo_data_are_read_from_transactions() {
+	let b0: IndexedBlock = test_data::block_builder()
+		.transaction()
+			.coinbase()
+			.output().value(50).build()
+			.build()
+		.merkled_header().build()
+		.build()
+		.into();
+	let genesis_coinbase = OutPoint { hash: b0.transactions[0].hash.clone(), index: 0 };
+	let b1: IndexedBlock = test_data::block_builder()
+		.transaction()
+			.coinbase()
+			.output().value(10).build()
+			.build()
+		.transaction()
+			.input().hash(genesis_coinbase.hash.clone()).build()
+			.output().value(40).build()
+			.build()
+		.merkled_header().parent(b0.hash().clone()).build()
+		.build()
+		.into();
+	let b1_output = OutPoint { hash: b1.transactions[1].hash.clone(), index: 0 };
+
+	let shared_database = SharedMemoryDatabase::default();
+	let store = BlockChainDatabase::open(shared_database.clone()).unwrap();
+	store.insert(b0.clone()).unwrap();
+	store.canonize(b0.hash()).unwrap();
+	store.insert(b1.clone()).unwrap();
+
+	// genesis coinbase is only marked as unspent in the transaction meta
+	let mut update = DBTransaction::new();
+	update.delete(Key::Coin(genesis_coinbase.clone()));
+	shared_database.write(update).unwrap();
+	let store = BlockChainDatabase::open(shared_database.clone()).unwrap();
+	assert_eq!(store.transaction_output(&genesis_coinbase, 0).unwrap().value, 50);
+	store.canonize(b1.hash()).unwrap();
+	assert!(store.is_spent(&genesis_coinbase));
+
+	// block has no undo data
+	let mut update = DBTransaction::new();
+	update.delete(Key::BlockUndo(b1.hash().clone()));
+	shared_database.write(update).unwrap();
+	let store = BlockChainDatabase::open(shared_database.clone()).unwrap();
+	assert_eq!(b1.hash(), &store.decanonize().unwrap());
+	assert_eq!(store.coin(&genesis_coinbase).unwrap().value, 50);
+	assert!(store.coin(&b1_output).is_none());
+}
+
+#[test]
+fn new_database_has_latest_schema_version() {
+	let shared_database = SharedMemoryDatabase::default();
+	let store = BlockChainDatabase::open(shared_database.clone()).unwrap();
+	assert_eq!(store.schema_version(), SCHEMA_VERSION);
+	assert_eq!(meta(&shared_database, "schema_version"), Some(vec![SCHEMA_VERSION as u8, 0, 0, 0]));
+}
+
+#[test]
+fn unversioned_database_is_migrated() {
+	let b0: IndexedBlock = test_data::block_h0().into();
+	let b1: IndexedBlock = test_data::block_h1().into();
+	let b2: IndexedBlock = test_data::block_h2().into();
+	let b0_coinbase = OutPoint { hash: b0.transactions[0].hash.clone(), index: 0 };
+	let b3: IndexedBlock = test_data::block_builder()
+		.transaction().coinbase().output().value(10).build().build()
+		.transaction()
+			.input().hash(b0_coinbase.hash.clone()).build()
+			.output().value(40).build()
+			.build()
+		.merkled_header().parent(b2.hash().clone()).build()
+		.build()
+		.into();
+	let dir = TempDir::new("blocks").unwrap();
+	let shared_database = baseline_database(&[b0.clone(), b1.clone(), b2.clone()]);
+	assert!(!has_transaction_location(&shared_database, &b1));
+
+	let store = BlockChainDatabase::open_with_block_files(shared_database.clone(), BlockFiles::open(dir.path()).unwrap()).unwrap();
+	assert_eq!(store.schema_version(), SCHEMA_VERSION);
+	assert_eq!(store.best_block().number, 2);
+	assert!(has_transaction_location(&shared_database, &b0));
+	assert!(has_transaction_location(&shared_database, &b1));
+	assert!(has_transaction_location(&shared_database, &b2));
+	assert_eq!(store.transaction_location(&b1.transactions[0].hash), Some(TransactionLocation {
+		block_hash: b1.hash().clone(),
+		index: 0,
+	}));
+	assert_eq!(meta(&shared_database, "migration_progress"), None);
+
+	// coins and blocks are moved to the new layout
+	assert!(shared_database.get(&Key::Coin(b0_coinbase.clone())).unwrap().into_option().is_some());
+	for block in &[&b0, &b1, &b2] {
+		assert!(shared_database.get(&Key::BlockPosition(block.hash().clone())).unwrap().into_option().is_some());
+	}
+	drop(store);
+	let mut update = DBTransaction::new();
+	for block in &[&b0, &b1, &b2] {
+		update.delete(Key::Transaction(block.transactions[0].hash.clone()));
+	}
+	shared_database.write(update).unwrap();
+
+	let store = BlockChainDatabase::open_with_block_files(shared_database.clone(), BlockFiles::open(dir.path()).unwrap()).unwrap();
+	assert_eq!(store.block(1.into()), Some(b1.to_raw_block()));
+	assert_eq!(store.transaction(&b2.transactions[0].hash), Some(b2.transactions[0].raw.clone()));
+
+	// migrated chain is canonized and decanonized
+	store.insert(b3.clone()).unwrap();
+	store.canonize(b3.hash()).unwrap();
+	assert!(store.is_spent(&b0_coinbase));
+	assert_eq!(store.decanonize().unwrap(), *b3.hash());
+	assert_eq!(store.coin(&b0_coinbase).unwrap().value, 5000000000);
+	assert_eq!(store.decanonize().unwrap(), *b2.hash());
+	assert_eq!(store.best_block().number, 1);
+	assert!(store.coin(&OutPoint { hash: b2.transactions[0].hash.clone(), index: 0 }).is_none());
+	store.canonize(b2.hash()).unwrap();
+	assert_eq!(store.best_block().number, 2);
+}
+
+#[test]
+fn interrupted_migration_is_resumed() {
+	let b0: IndexedBlock = test_data::block_h0().into();
+	let b1: IndexedBlock = test_data::block_h1().into();
+	let b2: IndexedBlock = test_data::block_h2().into();
+	let shared_database = baseline_database(&[b0.clone(), b1.clone(), b2.clone()]);
+
+	// blocks 0 and 1 have been processed before migration has been interrupted
+	let mut update = DBTransaction::new();
+	update.insert(KeyValue::Meta("migration_progress", vec![2, 0, 0, 0].into()));
+	shared_database.write(update).unwrap();
+
+	let store = BlockChainDatabase::open(shared_database.clone()).unwrap();
+	assert_eq!(store.schema_version(), SCHEMA_VERSION);
+	assert!(!has_transaction_location(&shared_database, &b0));
+	assert!(!has_transaction_location(&shared_database, &b1));
+	assert!(has_transaction_location(&shared_database, &b2));
+	assert_eq!(meta(&shared_database, "migration_progress"), None);
+}
+
+#[test]
+fn newer_database_is_not_opened() {
+	let shared_database = SharedMemoryDatabase::default();
+	{
+		BlockChainDatabase::open(shared_database.clone()).unwrap();
+	}
+
+	let newer_version = SCHEMA_VERSION + 1;
+	let mut update = DBTransaction::new();
+	update.insert(KeyValue::Meta("schema_version", vec![newer_version as u8, 0, 0, 0].into()));
+	shared_database.write(update).unwrap();
+
+	assert_eq!(BlockChainDatabase::open(shared_database).err(), Some(Error::UnsupportedSchemaVersion(newer_version)));
+}
@@ -16,7 +16,7 @@ use {Secret, DisplayLayout, Error, Message, Signature, CompactSignature};
 use rstd::prelude::Vec;
 
 /// Secret with additional network identifier and format type
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone)]
 pub struct Private {
 	/// The network on which this key should be used.
 	pub network: Network,
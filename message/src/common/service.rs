@@ -68,6 +68,16 @@ impl Services {
 		self
 	}
 
+	/// NODE_NETWORK_LIMITED (BIP159): node is able to serve the last 288 blocks only
+	pub fn network_limited(&self) -> bool {
+		self.bit_at(10)
+	}
+
+	pub fn with_network_limited(mut self, v: bool) -> Self {
+		self.set_bit(10, v);
+		self
+	}
+
 	pub fn includes(&self, other: &Self) -> bool {
 		self.0 & other.0 == other.0
 	}
@@ -104,4 +114,15 @@ mod test {
 		assert!(s1.includes(&s2));
 		assert!(!s2.includes(&s1));
 	}
+
+	#[test]
+	fn test_services_network_limited() {
+		let services = Services::default()
+			.with_network(false)
+			.with_network_limited(true);
+
+		assert!(!services.network());
+		assert!(services.network_limited());
+		assert_eq!(u64::from(services), 1 << 10);
+	}
 }
@@ -0,0 +1,44 @@
+use primitives::io;
+use primitives::hash::H256;
+use ser::{Stream, Reader};
+use {Payload, MessageResult};
+
+/// Maximal number of transactions in single package.
+pub const GETPKGTXNS_MAX_TRANSACTIONS: usize = 25;
+
+/// Request for package transactions, which are required to accept some other transaction.
+#[derive(Debug, PartialEq)]
+pub struct GetPkgTxns {
+	pub hashes: Vec<H256>,
+}
+
+impl GetPkgTxns {
+	pub fn with_hashes(hashes: Vec<H256>) -> Self {
+		GetPkgTxns {
+			hashes: hashes,
+		}
+	}
+}
+
+impl Payload for GetPkgTxns {
+	fn version() -> u32 {
+		70014
+	}
+
+	fn command() -> &'static str {
+		"getpkgtxns"
+	}
+
+	fn deserialize_payload<T>(reader: &mut Reader<T>, _version: u32) -> MessageResult<Self> where T: io::Read {
+		let get_pkg_txns = GetPkgTxns {
+			hashes: try!(reader.read_list_max(GETPKGTXNS_MAX_TRANSACTIONS)),
+		};
+
+		Ok(get_pkg_txns)
+	}
+
+	fn serialize_payload(&self, stream: &mut Stream, _version: u32) -> MessageResult<()> {
+		stream.append_list(&self.hashes);
+		Ok(())
+	}
+}
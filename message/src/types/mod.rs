@@ -11,12 +11,14 @@ mod getblocks;
 mod getblocktxn;
 mod getdata;
 mod getheaders;
+mod getpkgtxns;
 mod headers;
 mod inv;
 mod mempool;
 mod merkle_block;
 mod notfound;
 mod ping;
+mod pkgtxns;
 mod pong;
 pub mod reject;
 mod sendcompact;
@@ -39,12 +41,14 @@ pub use self::getblocks::{GetBlocks, GETBLOCKS_MAX_RESPONSE_HASHES};
 pub use self::getblocktxn::GetBlockTxn;
 pub use self::getdata::{GetData, GETDATA_MAX_INVENTORY_LEN};
 pub use self::getheaders::{GetHeaders, GETHEADERS_MAX_RESPONSE_HEADERS};
+pub use self::getpkgtxns::{GetPkgTxns, GETPKGTXNS_MAX_TRANSACTIONS};
 pub use self::headers::{Headers, HEADERS_MAX_HEADERS_LEN};
 pub use self::inv::{Inv, INV_MAX_INVENTORY_LEN};
 pub use self::mempool::MemPool;
 pub use self::merkle_block::MerkleBlock;
 pub use self::notfound::NotFound;
 pub use self::ping::Ping;
+pub use self::pkgtxns::PkgTxns;
 pub use self::pong::Pong;
 pub use self::reject::Reject;
 pub use self::sendcompact::SendCompact;
@@ -0,0 +1,57 @@
+use primitives::io;
+use ser::{Stream, Reader};
+use chain::Transaction;
+use types::GETPKGTXNS_MAX_TRANSACTIONS;
+use {Payload, MessageResult};
+
+/// Package transactions, sent in response to `getpkgtxns` message.
+#[derive(Debug, PartialEq)]
+pub struct PkgTxns {
+	pub transactions: Vec<Transaction>,
+}
+
+impl PkgTxns {
+	pub fn with_transactions(transactions: Vec<Transaction>) -> Self {
+		PkgTxns {
+			transactions: transactions,
+		}
+	}
+}
+
+impl Payload for PkgTxns {
+	fn version() -> u32 {
+		70014
+	}
+
+	fn command() -> &'static str {
+		"pkgtxns"
+	}
+
+	fn deserialize_payload<T>(reader: &mut Reader<T>, _version: u32) -> MessageResult<Self> where T: io::Read {
+		let pkg_txns = PkgTxns {
+			transactions: try!(reader.read_list_max(GETPKGTXNS_MAX_TRANSACTIONS)),
+		};
+
+		Ok(pkg_txns)
+	}
+
+	fn serialize_payload(&self, stream: &mut Stream, _version: u32) -> MessageResult<()> {
+		stream.append_list(&self.transactions);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serialization::{serialize_payload, deserialize_payload};
+	use chain::Transaction;
+	use super::PkgTxns;
+
+	#[test]
+	fn test_pkgtxns_roundtrip() {
+		let transaction: Transaction = "0100000001ad9d38823d95f31dc6c0cb0724c11a3cf5a466ca4147254a10cd94aade6eb5b3230000006b483045022100b7683165c3ecd57b0c44bf6a0fb258dc08c328458321c8fadc2b9348d4e66bd502204fd164c58d1a949a4d39bb380f8f05c9f6b3e9417f06bf72e5c068428ca3578601210391c35ac5ee7cf82c5015229dcff89507f83f9b8c952b8fecfa469066c1cb44ccffffffff0170f30500000000001976a914801da3cb2ed9e44540f4b982bde07cd3fbae264288ac00000000".into();
+		let message = PkgTxns::with_transactions(vec![transaction]);
+		let serialized = serialize_payload(&message, 70014).unwrap();
+		assert_eq!(deserialize_payload::<PkgTxns>(&serialized, 70014).unwrap(), message);
+	}
+}
@@ -5,12 +5,12 @@ use chain::{OutPoint, TransactionOutput, IndexedTransaction};
 use storage::{SharedStore, TransactionOutputProvider};
 use network::ConsensusParams;
 use memory_pool::{MemoryPool, OrderingStrategy, Entry};
-use verification::{work_required, block_reward_satoshi, transaction_sigops};
+use verification::{work_required, block_reward_satoshi, transaction_sigops, Deployments};
 
-const BLOCK_VERSION: u32 = 0x20000000;
 const BLOCK_HEADER_SIZE: u32 = 4 + 32 + 32 + 4 + 4 + 4;
 
 /// Block template as described in [BIP0022](https://github.com/bitcoin/bips/blob/master/bip-0022.mediawiki#block-template-request)
+#[derive(Clone)]
 pub struct BlockTemplate {
 	/// Version
 	pub version: u32,
@@ -233,14 +233,15 @@ impl<'a, T> Iterator for FittingTransactionsIterator<'a, T> where T: Iterator<It
 }
 
 impl BlockAssembler {
-	pub fn create_new_block(&self, store: &SharedStore, mempool: &MemoryPool, time: u32, consensus: &ConsensusParams) -> BlockTemplate {
+	/// Create template of the next block. Block version signals for all started BIP9 deployments.
+	pub fn create_new_block(&self, store: &SharedStore, mempool: &MemoryPool, time: u32, consensus: &ConsensusParams, deployments: &Deployments) -> BlockTemplate {
 		// get best block
 		// take it's hash && height
 		let best_block = store.best_block();
 		let previous_header_hash = best_block.hash;
 		let height = best_block.number + 1;
 		let bits = work_required(previous_header_hash.clone(), time, height, store.as_block_header_provider(), consensus);
-		let version = BLOCK_VERSION;
+		let version = deployments.block_version(height, store.as_block_header_provider(), consensus);
 
 		let mut coinbase_value = block_reward_satoshi(height);
 		let mut transactions = Vec::new();
@@ -3,15 +3,27 @@ use primitives::bytes::Bytes;
 use primitives::hash::H256;
 use primitives::U256;
 use primitives::compact::Compact;
-use chain::{merkle_root, Transaction};
-use crypto::dhash256;
+use chain::{merkle_root, Transaction, TransactionInput, TransactionOutput, BlockHeader, IndexedBlock,
+	IndexedBlockHeader, IndexedTransaction};
+use crypto::DHash256Midstate;
+use script::Builder;
 use ser::Stream;
 use verification::is_valid_proof_of_work_hash;
 use block_assembler::BlockTemplate;
+use hash_rate_meter::HashRateMeter;
+use std::cmp;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{mpsc, Arc};
+use std::thread;
 use rand;
 
+/// Number of header bytes, hashed once per merkle root (= SHA-256 block size)
+const HEADER_MIDSTATE_LEN: usize = 64;
+/// Number of header bytes, hashed for every nonce: end of merkle root, time, bits and nonce
+const HEADER_TAIL_LEN: usize = 16;
+/// Number of hashes, after which every miner thread reports to the hash rate meter
+const HASH_RATE_REPORT_INTERVAL: usize = 0x10000;
+
 /// Instead of serializing `BlockHeader` from scratch over and over again,
 /// let's keep it serialized in memory and replace needed bytes
 struct BlockHeaderBytes {
@@ -51,18 +63,25 @@ impl BlockHeaderBytes {
 		time_bytes.write_u32::<LittleEndian>(time).unwrap();
 	}
 
-	/// Set block header nonce
-	fn set_nonce(&mut self, nonce: u32) {
-		let mut nonce_bytes: &mut [u8] = &mut self.data[4 + 32 + 32 + 4 + 4..];
-		nonce_bytes.write_u32::<LittleEndian>(nonce).unwrap();
+	/// Returns hasher state after the first header chunk, which is the same for all nonces
+	fn midstate(&self) -> DHash256Midstate {
+		DHash256Midstate::new(&self.data[..HEADER_MIDSTATE_LEN])
 	}
 
-	/// Returns block header hash
-	fn hash(&self) -> H256 {
-		dhash256(&self.data)
+	/// Returns header bytes after the first chunk
+	fn tail(&self) -> [u8; HEADER_TAIL_LEN] {
+		let mut tail = [0u8; HEADER_TAIL_LEN];
+		tail.copy_from_slice(&self.data[HEADER_MIDSTATE_LEN..]);
+		tail
 	}
 }
 
+/// Set nonce of the block header tail
+fn set_tail_nonce(tail: &mut [u8; HEADER_TAIL_LEN], nonce: u32) {
+	let mut nonce_bytes: &mut [u8] = &mut tail[HEADER_TAIL_LEN - 4..];
+	nonce_bytes.write_u32::<LittleEndian>(nonce).unwrap();
+}
+
 /// This trait should be implemented by coinbase transaction.
 pub trait CoinbaseTransactionBuilder {
 	/// Should be used to increase number of hash possibities for miner
@@ -73,6 +92,50 @@ pub trait CoinbaseTransactionBuilder {
 	fn finish(self) -> Transaction;
 }
 
+/// Coinbase transaction builder, paying whole coinbase value to given script.
+/// BIP34 block height is pushed to the coinbase script before the extranonce.
+#[derive(Clone)]
+pub struct ScriptCoinbaseTransactionBuilder {
+	height: u32,
+	transaction: Transaction,
+}
+
+impl ScriptCoinbaseTransactionBuilder {
+	pub fn new(height: u32, script_pubkey: Bytes, value: u64) -> Self {
+		let transaction = Transaction {
+			version: 1,
+			inputs: vec![TransactionInput::coinbase(Bytes::default())],
+			outputs: vec![TransactionOutput {
+				value: value,
+				script_pubkey: script_pubkey,
+			}],
+			lock_time: 0,
+		};
+
+		ScriptCoinbaseTransactionBuilder {
+			height: height,
+			transaction: transaction,
+		}
+	}
+}
+
+impl CoinbaseTransactionBuilder for ScriptCoinbaseTransactionBuilder {
+	fn set_extranonce(&mut self, extranonce: &[u8]) {
+		self.transaction.inputs[0].script_sig = Builder::default()
+			.push_num(self.height.into())
+			.push_data(extranonce)
+			.into_bytes();
+	}
+
+	fn hash(&self) -> H256 {
+		self.transaction.hash()
+	}
+
+	fn finish(self) -> Transaction {
+		self.transaction
+	}
+}
+
 /// Cpu miner solution.
 pub struct Solution {
 	/// Block header nonce.
@@ -85,27 +148,137 @@ pub struct Solution {
 	pub coinbase_transaction: Transaction,
 }
 
-/// Simple bitcoin cpu miner.
+/// Multi-threaded bitcoin cpu miner.
 ///
-/// First it tries to find solution by changing block header nonce.
-/// Once all nonce values have been tried, it increases extranonce.
+/// Every thread tries to find solution by changing block header nonce.
+/// Once all nonce values have been tried, it moves to the next extranonce.
+/// Extranonce space is split between threads: thread `i` of `n` tries extranonces
+/// `start + i`, `start + i + n`, ... => threads never check the same header.
 /// Once all of them have been tried (quite unlikely on cpu ;),
 /// and solution still hasn't been found it returns None.
 /// It's possible to also experiment with time, but I find it pointless
 /// to implement on CPU.
-pub fn find_solution<T>(block: &BlockTemplate, mut coinbase_transaction_builder: T, max_extranonce: U256, running: Arc<AtomicBool>) -> Option<Solution> where T: CoinbaseTransactionBuilder {
+pub struct CpuMiner {
+	/// Number of mining threads
+	threads: usize,
+	/// Hash rate of all mining threads
+	hash_rate_meter: Arc<HashRateMeter>,
+}
+
+/// Shared state of the single solution search
+struct SearchState {
+	/// Is external caller still interested in the solution?
+	running: Arc<AtomicBool>,
+	/// Has solution already been found by some thread?
+	solved: AtomicBool,
+	/// Hash rate meter of the miner
+	hash_rate_meter: Arc<HashRateMeter>,
+}
+
+impl CpuMiner {
+	pub fn new(threads: usize) -> Self {
+		CpuMiner {
+			threads: cmp::max(threads, 1),
+			hash_rate_meter: Arc::new(HashRateMeter::default()),
+		}
+	}
+
+	/// Returns hash rate meter of this miner
+	pub fn hash_rate_meter(&self) -> Arc<HashRateMeter> {
+		self.hash_rate_meter.clone()
+	}
+
+	/// Find solution of the block template, checking at most `max_tries` header hashes.
+	/// Returns None if solution hasn't been found or if `running` is reset.
+	pub fn find_solution<T>(&self, block: &BlockTemplate, coinbase_transaction_builder: T, max_extranonce: U256, max_tries: u64, running: Arc<AtomicBool>) -> Option<Solution>
+		where T: CoinbaseTransactionBuilder + Clone + Send + 'static {
+		let mut extranonce_bytes = [0u8; 32];
+		for x in extranonce_bytes.iter_mut() {
+			*x = rand::random();
+		}
+		let start_extranonce = U256::from(&extranonce_bytes as &[u8]);
+
+		let state = Arc::new(SearchState {
+			running: running,
+			solved: AtomicBool::new(false),
+			hash_rate_meter: self.hash_rate_meter.clone(),
+		});
+
+		// no need to spawn threads for single worker
+		if self.threads == 1 {
+			return find_worker_solution(block, coinbase_transaction_builder, start_extranonce, U256::from(1), max_extranonce, max_tries, &state);
+		}
+
+		let block = Arc::new(block.clone());
+		let (solution_sender, solution_receiver) = mpsc::channel();
+		let workers: Vec<_> = (0..self.threads)
+			.filter_map(|index| {
+				let worker_start_extranonce = start_extranonce.overflowing_add(U256::from(index as u64)).0;
+				let worker_max_tries = max_tries / self.threads as u64 + if (index as u64) < max_tries % self.threads as u64 { 1 } else { 0 };
+				let block = block.clone();
+				let coinbase_transaction_builder = coinbase_transaction_builder.clone();
+				let extranonce_step = U256::from(self.threads as u64);
+				let state = state.clone();
+				let solution_sender = solution_sender.clone();
+				thread::Builder::new()
+					.name(format!("Cpu miner thread #{}", index))
+					.spawn(move || {
+						let solution = find_worker_solution(&block, coinbase_transaction_builder, worker_start_extranonce,
+							extranonce_step, max_extranonce, worker_max_tries, &state);
+						if let Some(solution) = solution {
+							let _ = solution_sender.send(solution);
+						}
+					})
+					.map_err(|err| warn!("Error creating cpu miner thread: {}", err))
+					.ok()
+			})
+			.collect();
+		drop(solution_sender);
+
+		// channel is closed when all workers are done => recv returns error if solution isn't found
+		let solution = solution_receiver.recv().ok();
+		state.solved.store(true, Ordering::SeqCst);
+		for worker in workers {
+			let _ = worker.join();
+		}
+		solution
+	}
+
+	/// Mine block from the template, paying coinbase value to given script.
+	/// Returns None if solution hasn't been found after `max_tries` header hashes have been checked.
+	pub fn mine_block(&self, block: &BlockTemplate, coinbase_script_pubkey: Bytes, max_tries: u64) -> Option<IndexedBlock> {
+		let coinbase_transaction_builder = ScriptCoinbaseTransactionBuilder::new(block.height, coinbase_script_pubkey, block.coinbase_value);
+		let running = Arc::new(AtomicBool::new(true));
+		self.find_solution(block, coinbase_transaction_builder, U256::max_value(), max_tries, running)
+			.map(|solution| build_block(block, solution))
+	}
+}
+
+/// Single-threaded version of `CpuMiner::find_solution`, which never gives up until `running` is reset.
+pub fn find_solution<T>(block: &BlockTemplate, coinbase_transaction_builder: T, max_extranonce: U256, running: Arc<AtomicBool>) -> Option<Solution>
+	where T: CoinbaseTransactionBuilder + Clone + Send + 'static {
+	CpuMiner::new(1).find_solution(block, coinbase_transaction_builder, max_extranonce, u64::max_value(), running)
+}
+
+/// Try extranonces `start_extranonce`, `start_extranonce + extranonce_step`, ..., until solution is found,
+/// `max_tries` header hashes are checked, or the search is stopped.
+fn find_worker_solution<T>(block: &BlockTemplate, mut coinbase_transaction_builder: T, start_extranonce: U256, extranonce_step: U256, max_extranonce: U256, max_tries: u64, state: &SearchState) -> Option<Solution>
+	where T: CoinbaseTransactionBuilder {
+	let mut tries = 0u64;
+	let mut unreported_hashes = 0usize;
+	let mut extranonce = start_extranonce;
 	let mut extranonce_bytes = [0u8; 32];
-    for x in extranonce_bytes.iter_mut() {
-        *x = rand::random();
-    }
-	let mut extranonce = U256::from(&extranonce_bytes as &[u8]);
 
 	let mut header_bytes = BlockHeaderBytes::new(block.version, block.previous_header_hash.clone(), block.bits);
 	// update header with time
 	header_bytes.set_time(block.time);
 
-	while extranonce < max_extranonce {
-        trace!("extranonce: {:?}", extranonce);
+	let solution = 'extranonce: loop {
+		if extranonce >= max_extranonce {
+			break None;
+		}
+
+		trace!("extranonce: {:?}", extranonce);
 		extranonce.to_little_endian(&mut extranonce_bytes);
 		// update coinbase transaction with new extranonce
 		coinbase_transaction_builder.set_extranonce(&extranonce_bytes);
@@ -116,34 +289,74 @@ pub fn find_solution<T>(block: &BlockTemplate, mut coinbase_transaction_builder:
 		merkle_tree.extend(block.transactions.iter().map(|tx| &tx.hash));
 		let merkle_root_hash = merkle_root(&merkle_tree);
 
-		// update header with new merkle root hash
+		// update header with new merkle root hash => first header chunk is hashed once per extranonce
 		header_bytes.set_merkle_root_hash(&merkle_root_hash);
+		let midstate = header_bytes.midstate();
+		let mut tail = header_bytes.tail();
 
 		for nonce in 0..(u32::max_value() as u64 + 1) {
-            if running.load(Ordering::SeqCst) == false {
-                return None;
-            }
-			header_bytes.set_nonce(nonce as u32);
-			let hash = header_bytes.hash();
+			if tries == max_tries || !state.running.load(Ordering::SeqCst) || state.solved.load(Ordering::SeqCst) {
+				break 'extranonce None;
+			}
+			tries += 1;
+			unreported_hashes += 1;
+			if unreported_hashes == HASH_RATE_REPORT_INTERVAL {
+				state.hash_rate_meter.add_hashes(unreported_hashes);
+				unreported_hashes = 0;
+			}
+
+			set_tail_nonce(&mut tail, nonce as u32);
+			let hash = midstate.dhash256(&tail);
 			if is_valid_proof_of_work_hash(block.bits, &hash) {
-				let solution = Solution {
-					nonce: nonce as u32,
-					extranonce: extranonce,
-					time: block.time,
-					coinbase_transaction: coinbase_transaction_builder.finish(),
-				};
-
-				return Some(solution);
+				break 'extranonce Some((nonce as u32, extranonce));
 			}
 		}
-		extranonce = extranonce + U256::from(1);
-	}
 
-	None
+		if max_extranonce - extranonce <= extranonce_step {
+			break None;
+		}
+		extranonce = extranonce + extranonce_step;
+	};
+
+	state.hash_rate_meter.add_hashes(unreported_hashes);
+	solution.map(|(nonce, extranonce)| {
+		state.solved.store(true, Ordering::SeqCst);
+		Solution {
+			nonce: nonce,
+			extranonce: extranonce,
+			time: block.time,
+			coinbase_transaction: coinbase_transaction_builder.finish(),
+		}
+	})
+}
+
+/// Build block from the template and the solution, found by the miner.
+pub fn build_block(block: &BlockTemplate, solution: Solution) -> IndexedBlock {
+	let mut transactions = vec![IndexedTransaction::from(solution.coinbase_transaction)];
+	transactions.extend(block.transactions.iter().cloned());
+	let merkle_root_hash = merkle_root(&transactions.iter().map(|tx| &tx.hash).collect::<Vec<_>>());
+	let header = BlockHeader {
+		version: block.version,
+		previous_header_hash: block.previous_header_hash.clone(),
+		merkle_root_hash: merkle_root_hash,
+		time: solution.time,
+		bits: block.bits,
+		nonce: solution.nonce,
+	};
+
+	IndexedBlock::new(IndexedBlockHeader::from(header), transactions)
+}
+
+/// Mine block from the template on the current thread, paying coinbase value to given script.
+/// Returns None if solution hasn't been found after `max_tries` header hashes have been checked.
+pub fn mine_block(block: &BlockTemplate, coinbase_script_pubkey: Bytes, max_tries: u64) -> Option<IndexedBlock> {
+	CpuMiner::new(1).mine_block(block, coinbase_script_pubkey, max_tries)
 }
 
 #[cfg(test)]
 mod tests {
+	extern crate test_data;
+
 	//use primitives::bigint::{U256, Uint};
 	use std::sync::Arc;
 	use primitives::U256;
@@ -153,9 +366,10 @@ mod tests {
 	use chain::{Transaction, TransactionInput, TransactionOutput};
 	use keys::AddressHash;
 	use script::Builder;
-	use super::{find_solution, CoinbaseTransactionBuilder};
+	use super::{find_solution, mine_block, set_tail_nonce, BlockHeaderBytes, CoinbaseTransactionBuilder, CpuMiner};
     use std::sync::atomic::AtomicBool;
 
+	#[derive(Clone)]
 	pub struct P2shCoinbaseTransactionBuilder {
 		transaction: Transaction,
 	}
@@ -214,4 +428,72 @@ mod tests {
 		let solution = find_solution(&block_template, coinbase_builder, U256::max_value(), running);
 		assert!(solution.is_some());
 	}
+
+	#[test]
+	fn test_cpu_miner_mines_block() {
+		let block_template = BlockTemplate {
+			version: 0,
+			previous_header_hash: 0.into(),
+			time: 0,
+			bits: U256::max_value().into(),
+			height: 1,
+			transactions: Vec::new(),
+			coinbase_value: 10,
+			size_limit: 1000,
+			sigop_limit: 100
+		};
+
+		let script_pubkey: Bytes = Builder::build_p2sh(&Default::default()).into();
+		let block = mine_block(&block_template, script_pubkey.clone(), 1).unwrap();
+		assert_eq!(block.transactions.len(), 1);
+		assert_eq!(block.transactions[0].raw.outputs[0].value, 10);
+		assert_eq!(block.transactions[0].raw.outputs[0].script_pubkey, script_pubkey);
+		assert_eq!(block.header.raw.merkle_root_hash, block.transactions[0].hash);
+		assert_eq!(block.header.hash, block.header.raw.hash());
+
+		let block_template = BlockTemplate {
+			bits: U256::from(1).into(),
+			.. block_template
+		};
+		assert!(mine_block(&block_template, script_pubkey, 1).is_none());
+	}
+
+	#[test]
+	fn test_block_header_bytes_midstate() {
+		let header = test_data::genesis().block_header;
+		let mut header_bytes = BlockHeaderBytes::new(header.version, header.previous_header_hash.clone(), header.bits);
+		header_bytes.set_merkle_root_hash(&header.merkle_root_hash);
+		header_bytes.set_time(header.time);
+		let mut tail = header_bytes.tail();
+		set_tail_nonce(&mut tail, header.nonce);
+		assert_eq!(header_bytes.midstate().dhash256(&tail), header.hash());
+	}
+
+	#[test]
+	fn test_multi_threaded_cpu_miner() {
+		let block_template = BlockTemplate {
+			version: 0,
+			previous_header_hash: 0.into(),
+			time: 0,
+			bits: U256::max_value().into(),
+			height: 0,
+			transactions: Vec::new(),
+			coinbase_value: 10,
+			size_limit: 1000,
+			sigop_limit: 100
+		};
+
+		let miner = CpuMiner::new(4);
+		let coinbase_builder = P2shCoinbaseTransactionBuilder::new(&Default::default(), 10);
+		let running = Arc::new(AtomicBool::new(true));
+		let solution = miner.find_solution(&block_template, coinbase_builder.clone(), U256::max_value(), u64::max_value(), running.clone());
+		assert!(solution.is_some());
+
+		// every thread gives up after checking its share of hashes
+		let block_template = BlockTemplate {
+			bits: U256::from(1).into(),
+			.. block_template
+		};
+		assert!(miner.find_solution(&block_template, coinbase_builder, U256::max_value(), 100, running).is_none());
+	}
 }
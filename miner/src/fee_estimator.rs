@@ -0,0 +1,845 @@
+//! Transaction fee estimator
+//!
+//! `FeeEstimator` records the fee rate of every transaction entering the memory pool together with
+//! the height at which it has entered, and the number of blocks it took to get it confirmed. Statistics
+//! are kept for exponentially spaced fee rate buckets and for three time horizons (short, medium and long),
+//! each with its own exponential decay. Estimation looks for the lowest fee rate bucket range in which
+//! enough transactions were confirmed within requested number of blocks (as in Bitcoin Core).
+use std::cmp::{min, max};
+use std::collections::HashMap;
+use primitives::io;
+use primitives::hash::H256;
+use ser::{Serializable, Deserializable, Stream, Reader};
+
+/// Lowest tracked fee rate (satoshis per 1000 bytes)
+const MIN_BUCKET_FEE_RATE: f64 = 1000.0;
+/// Highest tracked fee rate (satoshis per 1000 bytes)
+const MAX_BUCKET_FEE_RATE: f64 = 1e7;
+/// Spacing between neighbouring fee rate buckets
+const FEE_SPACING: f64 = 1.05;
+/// Fee rates above this value are tracked in the overflow bucket
+const INF_FEE_RATE: f64 = 1e99;
+
+/// Short horizon: track confirmations up to 12 blocks, with high decay
+const SHORT_BLOCK_PERIODS: usize = 12;
+const SHORT_SCALE: u32 = 1;
+const SHORT_DECAY: f64 = 0.962;
+/// Medium horizon: track confirmations up to 48 blocks
+const MEDIUM_BLOCK_PERIODS: usize = 24;
+const MEDIUM_SCALE: u32 = 2;
+const MEDIUM_DECAY: f64 = 0.9952;
+/// Long horizon: track confirmations up to 1008 blocks, with low decay
+const LONG_BLOCK_PERIODS: usize = 42;
+const LONG_SCALE: u32 = 24;
+const LONG_DECAY: f64 = 0.99931;
+
+/// Required success rate for estimates for half of target
+const HALF_SUCCESS_PCT: f64 = 0.6;
+/// Required success rate for estimates for target
+const SUCCESS_PCT: f64 = 0.85;
+/// Required success rate for estimates for double target
+const DOUBLE_SUCCESS_PCT: f64 = 0.95;
+/// Require an average of 1 transaction in the combined fee rate bucket per block to have statistical significance
+const SUFFICIENT_FEE_TXS: f64 = 0.1;
+/// Require an average of 0.5 transactions when using short decay
+const SUFFICIENT_TXS_SHORT: f64 = 0.5;
+
+/// Version of serialized estimator state
+const FEE_ESTIMATOR_VERSION: u32 = 2;
+/// Historical estimates, older than this number of blocks, are not used
+const OLDEST_ESTIMATE_HISTORY: u32 = 6 * 1008;
+
+/// Estimation horizon
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeeEstimateHorizon {
+	/// Short horizon (up to 12 blocks)
+	Short,
+	/// Medium horizon (up to 48 blocks)
+	Medium,
+	/// Long horizon (up to 1008 blocks)
+	Long,
+}
+
+/// Estimation mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeeEstimateMode {
+	/// Prefer responsiveness to short-term fee drops
+	Economical,
+	/// Prefer estimates that are less likely to be too low over longer history
+	Conservative,
+}
+
+/// Statistics on the range of buckets that has been used to estimate fee rate
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct EstimatorBucket {
+	/// Lower bound of fee rate range (satoshis per 1000 bytes)
+	pub start: f64,
+	/// Upper bound of fee rate range (satoshis per 1000 bytes)
+	pub end: f64,
+	/// Number of transactions that were confirmed within target
+	pub within_target: f64,
+	/// Number of transactions that have been tracked in this range
+	pub total_confirmed: f64,
+	/// Number of transactions that are still in memory pool for at least target blocks
+	pub in_mempool: f64,
+	/// Number of transactions that have left memory pool unconfirmed after at least target blocks
+	pub left_mempool: f64,
+}
+
+/// Raw estimation result
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct EstimationResult {
+	/// Fee rate estimate (satoshis per 1000 bytes), if any
+	pub fee_rate: Option<u64>,
+	/// Bucket range which has passed the threshold
+	pub pass: EstimatorBucket,
+	/// Highest bucket range which has failed the threshold
+	pub fail: EstimatorBucket,
+	/// Exponential decay (per block) of the horizon
+	pub decay: f64,
+	/// Resolution of confirmation targets of the horizon
+	pub scale: u32,
+}
+
+/// Smart estimation result
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeEstimate {
+	/// Fee rate estimate (satoshis per 1000 bytes), if any
+	pub fee_rate: Option<u64>,
+	/// Number of blocks for which the estimate is valid
+	pub blocks: u32,
+}
+
+/// Transaction tracked by the estimator
+#[derive(Debug, Clone, PartialEq)]
+struct TrackedTransaction {
+	/// Height of the best block at the moment transaction has entered the memory pool
+	height: u32,
+	/// Index of fee rate bucket
+	bucket: usize,
+	/// Fee rate (satoshis per 1000 bytes)
+	fee_rate: f64,
+}
+
+/// Confirmation statistics for single time horizon
+#[derive(Debug, Clone, PartialEq)]
+struct ConfirmationStats {
+	/// Decay applied to moving averages on every block
+	decay: f64,
+	/// Number of blocks in every period
+	scale: u32,
+	/// For every period and every bucket: moving average of number of transactions confirmed within period
+	confirmed: Vec<Vec<f64>>,
+	/// For every period and every bucket: moving average of number of transactions failed to confirm within period
+	failed: Vec<Vec<f64>>,
+	/// For every bucket: moving average of number of transactions
+	transactions: Vec<f64>,
+	/// For every bucket: moving average of fee rates sum
+	fee_rates: Vec<f64>,
+	/// For every block (modulo max confirmations) and every bucket: number of unconfirmed transactions
+	unconfirmed: Vec<Vec<u32>>,
+	/// For every bucket: number of transactions, unconfirmed for more than max confirmations blocks
+	old_unconfirmed: Vec<u32>,
+}
+
+/// Transaction fee estimator
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeEstimator {
+	/// Upper bounds of fee rate buckets
+	buckets: Vec<f64>,
+	/// Height of the best block we have seen
+	best_seen_height: u32,
+	/// Height of the first block with confirmed tracked transactions, since estimator has been created (0 if none)
+	first_recorded_height: u32,
+	/// First recorded height of the persisted statistics (0 if none)
+	historical_first_height: u32,
+	/// Best seen height of the persisted statistics
+	historical_best_height: u32,
+	/// Transactions we are currently tracking
+	tracked: HashMap<H256, TrackedTransaction>,
+	/// Short horizon statistics
+	short_stats: ConfirmationStats,
+	/// Medium horizon statistics
+	medium_stats: ConfirmationStats,
+	/// Long horizon statistics
+	long_stats: ConfirmationStats,
+}
+
+impl ConfirmationStats {
+	fn new(buckets_count: usize, periods: usize, decay: f64, scale: u32) -> Self {
+		let max_confirms = periods * scale as usize;
+		ConfirmationStats {
+			decay: decay,
+			scale: scale,
+			confirmed: vec![vec![0f64; buckets_count]; periods],
+			failed: vec![vec![0f64; buckets_count]; periods],
+			transactions: vec![0f64; buckets_count],
+			fee_rates: vec![0f64; buckets_count],
+			unconfirmed: vec![vec![0u32; buckets_count]; max_confirms],
+			old_unconfirmed: vec![0u32; buckets_count],
+		}
+	}
+
+	/// Max number of confirmations, tracked by this horizon
+	fn max_confirms(&self) -> u32 {
+		self.scale * self.confirmed.len() as u32
+	}
+
+	/// Move transactions that are about to become too old to be tracked individually
+	fn clear_current(&mut self, height: u32) {
+		let index = height as usize % self.unconfirmed.len();
+		for (bucket, unconfirmed) in self.unconfirmed[index].iter_mut().enumerate() {
+			self.old_unconfirmed[bucket] += *unconfirmed;
+			*unconfirmed = 0;
+		}
+	}
+
+	/// Remember that transaction has been confirmed after `blocks_to_confirm` blocks
+	fn record(&mut self, blocks_to_confirm: u32, bucket: usize, fee_rate: f64) {
+		// blocks_to_confirm is 1-based
+		if blocks_to_confirm < 1 {
+			return;
+		}
+
+		let periods_to_confirm = ((blocks_to_confirm + self.scale - 1) / self.scale) as usize;
+		for period in (periods_to_confirm - 1)..self.confirmed.len() {
+			self.confirmed[period][bucket] += 1f64;
+		}
+		self.transactions[bucket] += 1f64;
+		self.fee_rates[bucket] += fee_rate;
+	}
+
+	/// Apply decay to all moving averages
+	fn update_moving_averages(&mut self) {
+		for bucket in 0..self.transactions.len() {
+			for period in 0..self.confirmed.len() {
+				self.confirmed[period][bucket] *= self.decay;
+				self.failed[period][bucket] *= self.decay;
+			}
+			self.transactions[bucket] *= self.decay;
+			self.fee_rates[bucket] *= self.decay;
+		}
+	}
+
+	/// Remember that new transaction has entered memory pool
+	fn new_transaction(&mut self, height: u32, bucket: usize) {
+		let index = height as usize % self.unconfirmed.len();
+		self.unconfirmed[index][bucket] += 1;
+	}
+
+	/// Forget unconfirmed transaction (because it is either confirmed, or removed from memory pool)
+	fn remove_transaction(&mut self, entry_height: u32, best_seen_height: u32, bucket: usize, in_block: bool) {
+		let blocks_ago = best_seen_height.saturating_sub(entry_height);
+		if blocks_ago as usize >= self.unconfirmed.len() {
+			self.old_unconfirmed[bucket] = self.old_unconfirmed[bucket].saturating_sub(1);
+		} else {
+			let index = entry_height as usize % self.unconfirmed.len();
+			self.unconfirmed[index][bucket] = self.unconfirmed[index][bucket].saturating_sub(1);
+		}
+
+		// transaction has failed to confirm => count it as failure for all periods it has been waiting for
+		if !in_block && blocks_ago >= self.scale {
+			let periods_ago = min((blocks_ago / self.scale) as usize, self.failed.len());
+			for period in 0..periods_ago {
+				self.failed[period][bucket] += 1f64;
+			}
+		}
+	}
+
+	/// Estimate the lowest fee rate such that `success_threshold` part of transactions with this (or greater)
+	/// fee rate were confirmed within `confirmation_target` blocks
+	fn estimate_median(&self, buckets: &[f64], confirmation_target: u32, sufficient_transactions: f64, success_threshold: f64, best_seen_height: u32) -> EstimationResult {
+		let mut result = EstimationResult {
+			decay: self.decay,
+			scale: self.scale,
+			..Default::default()
+		};
+
+		if confirmation_target < 1 || confirmation_target > self.max_confirms() {
+			return result;
+		}
+
+		let period_target = ((confirmation_target + self.scale - 1) / self.scale) as usize;
+		let max_bucket = buckets.len() - 1;
+
+		// counters of currently processed bucket range
+		let mut confirmed = 0f64;
+		let mut total = 0f64;
+		let mut failed = 0f64;
+		let mut extra = 0f64;
+		let mut cur_near_bucket = max_bucket;
+		let mut cur_far_bucket = max_bucket;
+		let mut best_near_bucket = max_bucket;
+		let mut best_far_bucket = max_bucket;
+		let mut found_answer = false;
+		let mut new_bucket_range = true;
+		let mut passing = true;
+
+		// start from the highest fee rate and go down until success rate drops below threshold
+		for bucket in (0..buckets.len()).rev() {
+			if new_bucket_range {
+				cur_near_bucket = bucket;
+				new_bucket_range = false;
+			}
+			cur_far_bucket = bucket;
+			confirmed += self.confirmed[period_target - 1][bucket];
+			total += self.transactions[bucket];
+			failed += self.failed[period_target - 1][bucket];
+			for confirmations in confirmation_target..self.max_confirms() {
+				// there are no transactions, that have entered memory pool before the first block
+				if confirmations > best_seen_height {
+					break;
+				}
+				let index = (best_seen_height - confirmations) as usize % self.unconfirmed.len();
+				extra += self.unconfirmed[index][bucket] as f64;
+			}
+			extra += self.old_unconfirmed[bucket] as f64;
+
+			// only check if we have enough data points in the current range
+			if total < sufficient_transactions / (1f64 - self.decay) {
+				continue;
+			}
+
+			let success_rate = confirmed / (total + failed + extra);
+			if success_rate < success_threshold {
+				// this range is failing => remember it and stop
+				if passing {
+					let fail_min = min(cur_near_bucket, cur_far_bucket);
+					let fail_max = max(cur_near_bucket, cur_far_bucket);
+					result.fail = EstimatorBucket {
+						start: if fail_min == 0 { 0f64 } else { buckets[fail_min - 1] },
+						end: buckets[fail_max],
+						within_target: confirmed,
+						total_confirmed: total,
+						in_mempool: extra,
+						left_mempool: failed,
+					};
+					passing = false;
+				}
+				continue;
+			}
+
+			// this range is passing => remember it and start new range
+			result.fail = EstimatorBucket::default();
+			found_answer = true;
+			passing = true;
+			result.pass = EstimatorBucket {
+				within_target: confirmed,
+				total_confirmed: total,
+				in_mempool: extra,
+				left_mempool: failed,
+				..Default::default()
+			};
+			confirmed = 0f64;
+			total = 0f64;
+			failed = 0f64;
+			extra = 0f64;
+			best_near_bucket = cur_near_bucket;
+			best_far_bucket = cur_far_bucket;
+			new_bucket_range = true;
+		}
+
+		if !found_answer {
+			return result;
+		}
+
+		let min_bucket = min(best_near_bucket, best_far_bucket);
+		let max_bucket = max(best_near_bucket, best_far_bucket);
+
+		// find median bucket of the passing range && use its average fee rate
+		let transactions_sum: f64 = (min_bucket..max_bucket + 1).map(|bucket| self.transactions[bucket]).sum();
+		let mut fee_rate = 0f64;
+		if transactions_sum != 0f64 {
+			let mut half_sum = transactions_sum / 2f64;
+			for bucket in min_bucket..max_bucket + 1 {
+				if self.transactions[bucket] < half_sum {
+					half_sum -= self.transactions[bucket];
+				} else {
+					fee_rate = self.fee_rates[bucket] / self.transactions[bucket];
+					break;
+				}
+			}
+		}
+
+		result.pass.start = if min_bucket == 0 { 0f64 } else { buckets[min_bucket - 1] };
+		result.pass.end = buckets[max_bucket];
+		if fee_rate > 0f64 {
+			result.fee_rate = Some(fee_rate as u64);
+		}
+		result
+	}
+}
+
+impl Default for FeeEstimator {
+	fn default() -> Self {
+		FeeEstimator::new()
+	}
+}
+
+impl FeeEstimator {
+	/// Creates new empty estimator
+	pub fn new() -> Self {
+		let mut buckets = Vec::new();
+		let mut bucket_boundary = MIN_BUCKET_FEE_RATE;
+		while bucket_boundary <= MAX_BUCKET_FEE_RATE {
+			buckets.push(bucket_boundary);
+			bucket_boundary *= FEE_SPACING;
+		}
+		buckets.push(INF_FEE_RATE);
+
+		let buckets_count = buckets.len();
+		FeeEstimator {
+			buckets: buckets,
+			best_seen_height: 0,
+			first_recorded_height: 0,
+			historical_first_height: 0,
+			historical_best_height: 0,
+			tracked: HashMap::new(),
+			short_stats: ConfirmationStats::new(buckets_count, SHORT_BLOCK_PERIODS, SHORT_DECAY, SHORT_SCALE),
+			medium_stats: ConfirmationStats::new(buckets_count, MEDIUM_BLOCK_PERIODS, MEDIUM_DECAY, MEDIUM_SCALE),
+			long_stats: ConfirmationStats::new(buckets_count, LONG_BLOCK_PERIODS, LONG_DECAY, LONG_SCALE),
+		}
+	}
+
+	/// Height of the best block, processed by estimator
+	pub fn best_seen_height(&self) -> u32 {
+		self.best_seen_height
+	}
+
+	/// Number of transactions, currently tracked by estimator
+	pub fn tracked_transactions_count(&self) -> usize {
+		self.tracked.len()
+	}
+
+	/// Max confirmation target that could be estimated for given horizon
+	pub fn max_target(&self, horizon: FeeEstimateHorizon) -> u32 {
+		self.stats(horizon).max_confirms()
+	}
+
+	/// Remember transaction that has entered memory pool when best block was at given `height`
+	pub fn process_transaction(&mut self, hash: &H256, height: u32, fee: u64, size: usize) {
+		if self.tracked.contains_key(hash) {
+			return;
+		}
+
+		// we only track transactions which are entering memory pool on top of the best block
+		// => we can't say for how many blocks transactions received during sync were waiting
+		if height != self.best_seen_height || size == 0 {
+			return;
+		}
+
+		let fee_rate = fee as f64 * 1000f64 / size as f64;
+		let bucket = self.bucket_index(fee_rate);
+		self.short_stats.new_transaction(height, bucket);
+		self.medium_stats.new_transaction(height, bucket);
+		self.long_stats.new_transaction(height, bucket);
+		self.tracked.insert(hash.clone(), TrackedTransaction {
+			height: height,
+			bucket: bucket,
+			fee_rate: fee_rate,
+		});
+	}
+
+	/// Forget transaction which has left memory pool without being confirmed
+	pub fn remove_transaction(&mut self, hash: &H256) -> bool {
+		self.remove_tracked(hash, false)
+	}
+
+	/// Process block, inserted at given height, with given transactions
+	pub fn process_block(&mut self, height: u32, transactions: &[H256]) {
+		// reorganizations are ignored: confirmation times of transactions from the
+		// new blocks are not known
+		if height <= self.best_seen_height {
+			return;
+		}
+		self.best_seen_height = height;
+
+		// transactions, that are waiting for too long, are not tracked individually anymore
+		self.short_stats.clear_current(height);
+		self.medium_stats.clear_current(height);
+		self.long_stats.clear_current(height);
+
+		// decay old data
+		self.short_stats.update_moving_averages();
+		self.medium_stats.update_moving_averages();
+		self.long_stats.update_moving_averages();
+
+		for hash in transactions {
+			if let Some(tracked) = self.tracked.get(hash).cloned() {
+				if self.first_recorded_height == 0 {
+					self.first_recorded_height = height;
+				}
+				let blocks_to_confirm = height - tracked.height;
+				self.remove_tracked(hash, true);
+				self.short_stats.record(blocks_to_confirm, tracked.bucket, tracked.fee_rate);
+				self.medium_stats.record(blocks_to_confirm, tracked.bucket, tracked.fee_rate);
+				self.long_stats.record(blocks_to_confirm, tracked.bucket, tracked.fee_rate);
+			}
+		}
+
+		// forget about transactions that are waiting longer than we could ever estimate
+		let max_confirms = self.long_stats.max_confirms();
+		let expired: Vec<H256> = self.tracked.iter()
+			.filter(|&(_, tracked)| height - tracked.height > max_confirms)
+			.map(|(hash, _)| hash.clone())
+			.collect();
+		for hash in expired {
+			self.remove_tracked(&hash, false);
+		}
+	}
+
+	/// Estimate fee rate, required for transaction to be confirmed within given `confirmation_target`
+	/// blocks with at least `success_threshold` probability, using statistics of given horizon
+	pub fn estimate_raw_fee(&self, confirmation_target: u32, success_threshold: f64, horizon: FeeEstimateHorizon) -> EstimationResult {
+		let sufficient_transactions = match horizon {
+			FeeEstimateHorizon::Short => SUFFICIENT_TXS_SHORT,
+			FeeEstimateHorizon::Medium | FeeEstimateHorizon::Long => SUFFICIENT_FEE_TXS,
+		};
+		self.stats(horizon).estimate_median(&self.buckets, confirmation_target, sufficient_transactions, success_threshold, self.best_seen_height)
+	}
+
+	/// Estimate fee rate, required for transaction to be confirmed within given `confirmation_target` blocks.
+	/// If there's not enough data for given target, the closest target with available estimate is used.
+	pub fn estimate_smart_fee(&self, confirmation_target: u32, mode: FeeEstimateMode) -> FeeEstimate {
+		let max_target = self.max_usable_estimate();
+		let mut confirmation_target = min(max(confirmation_target, 1), max_target);
+		// it is not possible to get reasonable estimate for confirmation_target of 1
+		if confirmation_target <= 1 {
+			confirmation_target = 2;
+		}
+
+		while confirmation_target <= max_target {
+			let mut estimate = self.estimate_combined(confirmation_target / 2, HALF_SUCCESS_PCT, true);
+			estimate = max(estimate, self.estimate_combined(confirmation_target, SUCCESS_PCT, true));
+			estimate = max(estimate, self.estimate_combined(confirmation_target * 2, DOUBLE_SUCCESS_PCT, true));
+			if mode == FeeEstimateMode::Conservative {
+				estimate = max(estimate, self.estimate_conservative(confirmation_target * 2));
+			}
+
+			if estimate.is_some() {
+				return FeeEstimate {
+					fee_rate: estimate,
+					blocks: confirmation_target,
+				};
+			}
+
+			confirmation_target += 1;
+		}
+
+		FeeEstimate {
+			fee_rate: None,
+			blocks: max_target,
+		}
+	}
+
+	/// Number of blocks, for which statistics has been collected in this session
+	fn block_span(&self) -> u32 {
+		match self.first_recorded_height {
+			0 => 0,
+			first_recorded_height => self.best_seen_height.saturating_sub(first_recorded_height),
+		}
+	}
+
+	/// Number of blocks, for which persisted statistics has been collected
+	fn historical_block_span(&self) -> u32 {
+		if self.historical_first_height == 0 || self.historical_best_height < self.historical_first_height {
+			return 0;
+		}
+		if self.best_seen_height.saturating_sub(self.historical_best_height) > OLDEST_ESTIMATE_HISTORY {
+			return 0;
+		}
+		self.historical_best_height - self.historical_first_height
+	}
+
+	/// Max confirmation target, which could be estimated with the collected statistics
+	fn max_usable_estimate(&self) -> u32 {
+		min(self.long_stats.max_confirms(), max(self.block_span(), self.historical_block_span()) / 2)
+	}
+
+	/// Estimate using the shortest horizon, which tracks given target. Optionally check shorter horizons
+	/// and return the highest estimate.
+	fn estimate_combined(&self, confirmation_target: u32, success_threshold: f64, check_shorter_horizons: bool) -> Option<u64> {
+		if confirmation_target < 1 || confirmation_target > self.long_stats.max_confirms() {
+			return None;
+		}
+
+		let estimate = if confirmation_target <= self.short_stats.max_confirms() {
+			self.estimate_raw_fee(confirmation_target, success_threshold, FeeEstimateHorizon::Short).fee_rate
+		} else if confirmation_target <= self.medium_stats.max_confirms() {
+			self.estimate_raw_fee(confirmation_target, success_threshold, FeeEstimateHorizon::Medium).fee_rate
+		} else {
+			self.estimate_raw_fee(confirmation_target, success_threshold, FeeEstimateHorizon::Long).fee_rate
+		};
+
+		if !check_shorter_horizons {
+			return estimate;
+		}
+
+		// if shorter horizon (with shorter target) requires higher fee rate, use it
+		let mut estimate = estimate;
+		if confirmation_target > self.medium_stats.max_confirms() {
+			let medium_max = self.medium_stats.max_confirms();
+			let medium_estimate = self.estimate_raw_fee(medium_max, success_threshold, FeeEstimateHorizon::Medium).fee_rate;
+			if medium_estimate.is_some() && (estimate.is_none() || medium_estimate > estimate) {
+				estimate = medium_estimate;
+			}
+		}
+		if confirmation_target > self.short_stats.max_confirms() {
+			let short_max = self.short_stats.max_confirms();
+			let short_estimate = self.estimate_raw_fee(short_max, success_threshold, FeeEstimateHorizon::Short).fee_rate;
+			if short_estimate.is_some() && (estimate.is_none() || short_estimate > estimate) {
+				estimate = short_estimate;
+			}
+		}
+		estimate
+	}
+
+	/// Conservative estimate: use the maximal estimate of all horizons, which are able to answer for given target
+	fn estimate_conservative(&self, confirmation_target: u32) -> Option<u64> {
+		let mut estimate = None;
+		if confirmation_target <= self.medium_stats.max_confirms() {
+			estimate = self.estimate_raw_fee(confirmation_target, DOUBLE_SUCCESS_PCT, FeeEstimateHorizon::Medium).fee_rate;
+		}
+		if confirmation_target <= self.long_stats.max_confirms() {
+			estimate = max(estimate, self.estimate_raw_fee(confirmation_target, DOUBLE_SUCCESS_PCT, FeeEstimateHorizon::Long).fee_rate);
+		}
+		estimate
+	}
+
+	fn stats(&self, horizon: FeeEstimateHorizon) -> &ConfirmationStats {
+		match horizon {
+			FeeEstimateHorizon::Short => &self.short_stats,
+			FeeEstimateHorizon::Medium => &self.medium_stats,
+			FeeEstimateHorizon::Long => &self.long_stats,
+		}
+	}
+
+	fn remove_tracked(&mut self, hash: &H256, in_block: bool) -> bool {
+		match self.tracked.remove(hash) {
+			Some(tracked) => {
+				self.short_stats.remove_transaction(tracked.height, self.best_seen_height, tracked.bucket, in_block);
+				self.medium_stats.remove_transaction(tracked.height, self.best_seen_height, tracked.bucket, in_block);
+				self.long_stats.remove_transaction(tracked.height, self.best_seen_height, tracked.bucket, in_block);
+				true
+			},
+			None => false,
+		}
+	}
+
+	fn bucket_index(&self, fee_rate: f64) -> usize {
+		self.buckets.iter()
+			.position(|bound| fee_rate <= *bound)
+			.unwrap_or(self.buckets.len() - 1)
+	}
+}
+
+fn append_f64(stream: &mut Stream, value: f64) {
+	stream.append(&value.to_bits());
+}
+
+fn append_f64_list(stream: &mut Stream, values: &[f64]) {
+	stream.append(&(values.len() as u32));
+	for value in values {
+		append_f64(stream, *value);
+	}
+}
+
+fn read_f64<T>(reader: &mut Reader<T>) -> Result<f64, io::Error> where T: io::Read {
+	Ok(f64::from_bits(reader.read()?))
+}
+
+fn read_f64_list<T>(reader: &mut Reader<T>, expected_len: usize) -> Result<Vec<f64>, io::Error> where T: io::Read {
+	let len: u32 = reader.read()?;
+	if len as usize != expected_len {
+		return Err(io::ErrorKind::MalformedData);
+	}
+	(0..len).map(|_| read_f64(reader)).collect()
+}
+
+impl ConfirmationStats {
+	fn serialize(&self, stream: &mut Stream) {
+		append_f64(stream, self.decay);
+		stream.append(&self.scale);
+		append_f64_list(stream, &self.transactions);
+		append_f64_list(stream, &self.fee_rates);
+		stream.append(&(self.confirmed.len() as u32));
+		for period in 0..self.confirmed.len() {
+			append_f64_list(stream, &self.confirmed[period]);
+			append_f64_list(stream, &self.failed[period]);
+		}
+	}
+
+	fn deserialize<T>(&mut self, reader: &mut Reader<T>) -> Result<(), io::Error> where T: io::Read {
+		let decay = read_f64(reader)?;
+		let scale: u32 = reader.read()?;
+		if decay != self.decay || scale != self.scale {
+			return Err(io::ErrorKind::MalformedData);
+		}
+
+		let buckets_count = self.transactions.len();
+		self.transactions = read_f64_list(reader, buckets_count)?;
+		self.fee_rates = read_f64_list(reader, buckets_count)?;
+		let periods: u32 = reader.read()?;
+		if periods as usize != self.confirmed.len() {
+			return Err(io::ErrorKind::MalformedData);
+		}
+		for period in 0..self.confirmed.len() {
+			self.confirmed[period] = read_f64_list(reader, buckets_count)?;
+			self.failed[period] = read_f64_list(reader, buckets_count)?;
+		}
+		Ok(())
+	}
+}
+
+/// Only confirmation statistics are persisted. Transactions that are currently tracked
+/// are forgotten, because memory pool is not persisted.
+impl Serializable for FeeEstimator {
+	fn serialize(&self, stream: &mut Stream) {
+		// span of the persisted statistics: either this session, or the loaded one, if it is longer
+		let (first_height, best_height) = if self.block_span() > self.historical_block_span() / 2 {
+			(self.first_recorded_height, self.best_seen_height)
+		} else {
+			(self.historical_first_height, self.historical_best_height)
+		};
+		stream
+			.append(&FEE_ESTIMATOR_VERSION)
+			.append(&self.best_seen_height)
+			.append(&first_height)
+			.append(&best_height);
+		append_f64_list(stream, &self.buckets);
+		self.short_stats.serialize(stream);
+		self.medium_stats.serialize(stream);
+		self.long_stats.serialize(stream);
+	}
+}
+
+impl Deserializable for FeeEstimator {
+	fn deserialize<T>(reader: &mut Reader<T>) -> Result<Self, io::Error> where T: io::Read {
+		let version: u32 = reader.read()?;
+		if version != 1 && version != FEE_ESTIMATOR_VERSION {
+			return Err(io::ErrorKind::InvalidVersion);
+		}
+
+		let mut estimator = FeeEstimator::new();
+		estimator.best_seen_height = reader.read()?;
+		// span of version 1 statistics is unknown => it is only used after enough new blocks are seen
+		if version == FEE_ESTIMATOR_VERSION {
+			estimator.historical_first_height = reader.read()?;
+			estimator.historical_best_height = reader.read()?;
+		}
+		let buckets_count = estimator.buckets.len();
+		if read_f64_list(reader, buckets_count)? != estimator.buckets {
+			return Err(io::ErrorKind::MalformedData);
+		}
+		estimator.short_stats.deserialize(reader)?;
+		estimator.medium_stats.deserialize(reader)?;
+		estimator.long_stats.deserialize(reader)?;
+		Ok(estimator)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use primitives::hash::H256;
+	use ser::{serialize, deserialize};
+	use super::{FeeEstimator, FeeEstimateMode, FeeEstimateHorizon};
+
+	fn fill_estimator(estimator: &mut FeeEstimator, blocks: u32, fee_rates: &[u64]) {
+		let mut nonce = 0u32;
+		for height in 1..blocks + 1 {
+			// every transaction is confirmed in the next block
+			let mut hashes = Vec::new();
+			for fee_rate in fee_rates {
+				nonce += 1;
+				let mut hash = [0u8; 32];
+				hash[0..4].copy_from_slice(&[(nonce >> 24) as u8, (nonce >> 16) as u8, (nonce >> 8) as u8, nonce as u8]);
+				let hash = H256::from(hash);
+				estimator.process_transaction(&hash, height - 1, *fee_rate, 1000);
+				hashes.push(hash);
+			}
+			estimator.process_block(height, &hashes);
+		}
+	}
+
+	#[test]
+	fn fee_estimator_has_no_estimate_without_data() {
+		let estimator = FeeEstimator::new();
+		assert_eq!(estimator.estimate_smart_fee(6, FeeEstimateMode::Economical).fee_rate, None);
+		assert_eq!(estimator.estimate_raw_fee(6, 0.95, FeeEstimateHorizon::Medium).fee_rate, None);
+	}
+
+	#[test]
+	fn fee_estimator_ignores_transactions_accepted_during_sync() {
+		let mut estimator = FeeEstimator::new();
+		estimator.process_block(10, &[]);
+		estimator.process_transaction(&H256::from(1), 5, 10_000, 250);
+		assert_eq!(estimator.tracked_transactions_count(), 0);
+		estimator.process_transaction(&H256::from(1), 10, 10_000, 250);
+		assert_eq!(estimator.tracked_transactions_count(), 1);
+		estimator.process_block(11, &[H256::from(1)]);
+		assert_eq!(estimator.tracked_transactions_count(), 0);
+	}
+
+	#[test]
+	fn fee_estimator_estimates_fee_of_quickly_confirmed_transactions() {
+		let mut estimator = FeeEstimator::new();
+		fill_estimator(&mut estimator, 200, &[20_000, 20_000, 20_000]);
+
+		let estimate = estimator.estimate_smart_fee(2, FeeEstimateMode::Economical);
+		assert_eq!(estimate.blocks, 2);
+		let fee_rate = estimate.fee_rate.unwrap();
+		assert!(fee_rate > 19_000 && fee_rate < 21_000);
+
+		let raw = estimator.estimate_raw_fee(1, 0.95, FeeEstimateHorizon::Short);
+		assert!(raw.fee_rate.is_some());
+		assert!(raw.pass.within_target > 0f64);
+	}
+
+	#[test]
+	fn fee_estimator_target_is_limited_by_collected_statistics() {
+		let mut estimator = FeeEstimator::new();
+		fill_estimator(&mut estimator, 21, &[20_000, 20_000, 20_000]);
+
+		// statistics has been collected for 20 blocks => targets up to 10 blocks could be estimated
+		let estimate = estimator.estimate_smart_fee(100, FeeEstimateMode::Economical);
+		assert_eq!(estimate.blocks, 10);
+		assert!(estimate.fee_rate.is_some());
+	}
+
+	#[test]
+	fn fee_estimator_removed_transactions_are_failures() {
+		let mut estimator = FeeEstimator::new();
+		fill_estimator(&mut estimator, 200, &[20_000]);
+
+		// transactions with the same fee rate are waiting for 2 blocks and leave memory pool
+		let hashes: Vec<H256> = (0..5u8).map(|n| H256::from([n + 1; 32])).collect();
+		for hash in &hashes {
+			estimator.process_transaction(hash, 200, 20_000, 1000);
+		}
+		estimator.process_block(201, &[]);
+		estimator.process_block(202, &[]);
+		for hash in &hashes {
+			assert!(estimator.remove_transaction(hash));
+			assert!(!estimator.remove_transaction(hash));
+		}
+		assert_eq!(estimator.tracked_transactions_count(), 0);
+
+		// failures are accounted in the bucket of removed transactions
+		let result = estimator.estimate_raw_fee(1, 0.95, FeeEstimateHorizon::Short);
+		assert_eq!(result.fee_rate, None);
+		assert_eq!(result.fail.left_mempool, 5f64);
+		assert!(estimator.estimate_raw_fee(1, 0.5, FeeEstimateHorizon::Short).fee_rate.is_some());
+	}
+
+	#[test]
+	fn fee_estimator_serialization_roundtrip() {
+		let mut estimator = FeeEstimator::new();
+		fill_estimator(&mut estimator, 20, &[5_000, 50_000]);
+		let serialized = serialize(&estimator);
+		let deserialized: FeeEstimator = deserialize(&serialized as &[u8]).unwrap();
+		assert_eq!(deserialized.best_seen_height(), 20);
+		assert_eq!(deserialized.historical_block_span(), 19);
+		assert_eq!(deserialized.estimate_smart_fee(2, FeeEstimateMode::Conservative),
+			estimator.estimate_smart_fee(2, FeeEstimateMode::Conservative));
+	}
+}
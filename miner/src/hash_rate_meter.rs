@@ -0,0 +1,73 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Minimal duration of the window, over which hash rate is computed
+const HASH_RATE_WINDOW_MS: u64 = 5_000;
+
+/// Measures hash rate of the miner: number of block header hashes, checked per second
+pub struct HashRateMeter {
+	/// Number of hashes, checked since the current window has started
+	hashes: AtomicUsize,
+	/// Current window
+	window: Mutex<HashRateWindow>,
+}
+
+struct HashRateWindow {
+	/// When the current window has started
+	start: Instant,
+	/// Hash rate, computed over the previous window
+	hash_rate: f64,
+}
+
+impl Default for HashRateMeter {
+	fn default() -> Self {
+		HashRateMeter {
+			hashes: AtomicUsize::new(0),
+			window: Mutex::new(HashRateWindow {
+				start: Instant::now(),
+				hash_rate: 0f64,
+			}),
+		}
+	}
+}
+
+impl HashRateMeter {
+	/// Report that given number of hashes has been checked
+	pub fn add_hashes(&self, hashes: usize) {
+		self.hashes.fetch_add(hashes, Ordering::Relaxed);
+	}
+
+	/// Get hash rate (hashes per second), computed over the last complete window
+	pub fn hash_rate(&self) -> f64 {
+		let mut window = self.window.lock().expect("hash rate meter lock is never poisoned; qed");
+		let elapsed = window.start.elapsed();
+		if elapsed >= Duration::from_millis(HASH_RATE_WINDOW_MS) {
+			let elapsed_secs = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1_000_000_000f64;
+			window.hash_rate = self.hashes.swap(0, Ordering::Relaxed) as f64 / elapsed_secs;
+			window.start = Instant::now();
+		}
+		window.hash_rate
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::{Duration, Instant};
+	use super::HashRateMeter;
+
+	#[test]
+	fn hash_rate_is_computed_over_complete_window() {
+		let meter = HashRateMeter::default();
+		meter.add_hashes(1_000);
+		assert_eq!(meter.hash_rate(), 0f64);
+
+		meter.window.lock().unwrap().start = Instant::now() - Duration::from_secs(10);
+		let hash_rate = meter.hash_rate();
+		assert!(hash_rate > 99f64 && hash_rate <= 100f64);
+
+		// rate is kept until the next window is complete
+		meter.add_hashes(1_000);
+		assert_eq!(meter.hash_rate(), hash_rate);
+	}
+}
@@ -18,10 +18,20 @@ extern crate log;
 mod block_assembler;
 mod cpu_miner;
 mod fee;
+mod fee_estimator;
+mod hash_rate_meter;
 mod memory_pool;
+mod signet;
 
 pub use block_assembler::{BlockAssembler, BlockTemplate};
-pub use cpu_miner::{find_solution, CoinbaseTransactionBuilder};
-pub use memory_pool::{MemoryPool, HashedOutPoint, Information as MemoryPoolInformation,
+pub use cpu_miner::{find_solution, build_block, mine_block, CoinbaseTransactionBuilder, CpuMiner,
+	ScriptCoinbaseTransactionBuilder, Solution};
+pub use hash_rate_meter::HashRateMeter;
+pub use signet::SignetCoinbaseTransactionBuilder;
+pub use memory_pool::{MemoryPool, MemoryPoolListener, RemovalReason as MemoryPoolRemovalReason, HashedOutPoint,
+	Information as MemoryPoolInformation,
+	EntryInformation as MemoryPoolEntryInformation,
 	OrderingStrategy as MemoryPoolOrderingStrategy, DoubleSpendCheckResult, NonFinalDoubleSpendSet};
 pub use fee::{transaction_fee, transaction_fee_rate};
+pub use fee_estimator::{FeeEstimator, FeeEstimate, FeeEstimateMode, FeeEstimateHorizon, EstimationResult,
+	EstimatorBucket};
@@ -10,11 +10,13 @@ use primitives::bytes::Bytes;
 use primitives::hash::H256;
 use chain::{IndexedTransaction, Transaction, OutPoint, TransactionOutput};
 use std::cmp::Ordering;
+use std::fmt;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::BTreeSet;
 use std::collections::VecDeque;
 use std::hash::{Hash, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
 use ser::{Serializable, serialize};
 use heapsize::HeapSizeOf;
 
@@ -37,13 +39,69 @@ pub struct Information {
 	pub transactions_count: usize,
 	/// Total number of bytes occupied by transactions from the `MemoryPool`
 	pub transactions_size_in_bytes: usize,
+	/// Total memory usage of the `MemoryPool`
+	pub memory_usage_in_bytes: usize,
+}
+
+/// Information on single `MemoryPool` entry (as in GetMemPoolEntry RPC)
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryInformation {
+	/// Transaction hash
+	pub hash: H256,
+	/// Transaction size
+	pub size: usize,
+	/// Transaction fee
+	pub fee: i64,
+	/// Transaction fee, including virtual fee
+	pub modified_fee: i64,
+	/// Time (in seconds since epoch) when transaction has entered the memory pool
+	pub time: u32,
+	/// Number of in-pool descendants (including this transaction)
+	pub descendant_count: usize,
+	/// Size of in-pool descendants (including this transaction)
+	pub descendant_size: usize,
+	/// Modified fees of in-pool descendants (including this transaction)
+	pub descendant_fees: i64,
+	/// Number of in-pool ancestors (including this transaction)
+	pub ancestor_count: usize,
+	/// Size of in-pool ancestors (including this transaction)
+	pub ancestor_size: usize,
+	/// Modified fees of in-pool ancestors (including this transaction)
+	pub ancestor_fees: i64,
+	/// In-pool transactions, this transaction directly depends on
+	pub depends: Vec<H256>,
+	/// In-pool transactions, directly spending outputs of this transaction
+	pub spent_by: Vec<H256>,
+}
+
+/// Reason of transaction removal from the `MemoryPool`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RemovalReason {
+	/// Transaction has been included in the best chain block
+	Block,
+	/// Transaction (or its in-pool ancestor) has spent the same output as other transaction
+	Conflict,
+	/// Transaction has been removed for other reason (e.g. for reverification after reorganization)
+	Other,
+}
+
+/// `MemoryPool` events listener
+pub trait MemoryPoolListener: Send + Sync {
+	/// Called when transaction is inserted to the memory pool.
+	/// `sequence` is the memory pool sequence number, incremented on every insertion and removal
+	fn transaction_added(&self, transaction: &IndexedTransaction, sequence: u64);
+	/// Called when transaction is removed from the memory pool
+	fn transaction_removed(&self, hash: &H256, reason: RemovalReason, sequence: u64);
 }
 
 /// Transactions memory pool
-#[derive(Debug)]
 pub struct MemoryPool {
 	/// Transactions storage
 	storage: Storage,
+	/// Memory pool sequence number
+	sequence: u64,
+	/// Memory pool events listeners
+	listeners: Vec<Box<MemoryPoolListener>>,
 }
 
 /// Single entry
@@ -69,6 +127,8 @@ pub struct Entry {
 	pub package_miner_fee: i64,
 	/// miner_virtual_fee + Sum(miner_virtual_fee) for all in-pool descendants
 	pub package_miner_virtual_fee: i64,
+	/// Time (in seconds since epoch) when transaction has entered the memory pool
+	pub time: u32,
 }
 
 /// Multi-index transactions storage
@@ -350,6 +410,10 @@ impl Storage {
 		self.by_previous_output.contains_key(&prevout.clone().into())
 	}
 
+	pub fn output_spent_by(&self, prevout: &OutPoint) -> Option<&H256> {
+		self.by_previous_output.get(&prevout.clone().into())
+	}
+
 	pub fn set_virtual_fee(&mut self, h: &H256, virtual_fee: i64) {
 		// for updating ancestors
 		let mut miner_virtual_fee_change = 0i64;
@@ -391,6 +455,48 @@ impl Storage {
 		}
 	}
 
+	pub fn set_fee(&mut self, h: &H256, fee: i64) {
+		// for updating ancestors
+		let mut miner_fee_change = 0i64;
+		let mut ancestors: Option<Vec<H256>> = None;
+
+		// modify the entry itself
+		if let Some(entry) = self.by_hash.get_mut(h) {
+			let insert_to_package_score = self.references.ordered.by_package_score.remove(&(entry as &Entry).into());
+			let insert_to_transaction_score = self.references.ordered.by_transaction_score.remove(&(entry as &Entry).into());
+
+			miner_fee_change = fee - entry.miner_fee;
+			if !entry.ancestors.is_empty() {
+				ancestors = Some(entry.ancestors.iter().cloned().collect());
+			}
+
+			entry.miner_fee = fee;
+			entry.package_miner_fee += miner_fee_change;
+
+			if insert_to_transaction_score {
+				self.references.ordered.by_transaction_score.insert((entry as &Entry).into());
+			}
+			if insert_to_package_score {
+				self.references.ordered.by_package_score.insert((entry as &Entry).into());
+			}
+		}
+
+		// now modify all ancestor entries
+		if miner_fee_change != 0 {
+			if let Some(ancestors) = ancestors {
+				for ancestor_hash in ancestors {
+					if let Some(ancestor_entry) = self.by_hash.get_mut(&ancestor_hash) {
+						let insert_to_package_score = self.references.ordered.by_package_score.remove(&(ancestor_entry as &Entry).into());
+						ancestor_entry.package_miner_fee += miner_fee_change;
+						if insert_to_package_score {
+							self.references.ordered.by_package_score.insert((ancestor_entry as &Entry).into());
+						}
+					}
+				}
+			}
+		}
+	}
+
 	pub fn read_by_hash(&self, h: &H256) -> Option<&Transaction> {
 		self.by_hash.get(h).map(|e| &e.transaction)
 	}
@@ -560,6 +666,67 @@ impl Storage {
 	pub fn get_transactions_ids(&self) -> Vec<H256> {
 		self.by_hash.keys().cloned().collect()
 	}
+
+	pub fn get_descendants(&self, h: &H256) -> HashSet<H256> {
+		let mut all_descendants: HashSet<H256> = HashSet::new();
+		let mut descendants: Vec<H256> = match self.references.by_input.get(h) {
+			Some(descendants) => descendants.iter().cloned().collect(),
+			None => return all_descendants,
+		};
+		while let Some(descendant) = descendants.pop() {
+			if !all_descendants.insert(descendant.clone()) {
+				continue
+			}
+
+			if let Some(grand_descendants) = self.references.by_input.get(&descendant) {
+				descendants.extend(grand_descendants.iter().cloned());
+			}
+		}
+		all_descendants
+	}
+
+	pub fn get_entry_information(&self, h: &H256) -> Option<EntryInformation> {
+		let entry = match self.by_hash.get(h) {
+			Some(entry) => entry,
+			None => return None,
+		};
+
+		let modified_fee = |entry: &Entry| entry.miner_fee + entry.miner_virtual_fee;
+		let descendants = self.get_descendants(h);
+		let (descendant_size, descendant_fees) = descendants.iter()
+			.filter_map(|hash| self.by_hash.get(hash))
+			.fold((entry.size, modified_fee(entry)), |(size, fees), descendant| (size + descendant.size, fees + modified_fee(descendant)));
+		let (ancestor_size, ancestor_fees) = entry.ancestors.iter()
+			.filter_map(|hash| self.by_hash.get(hash))
+			.fold((entry.size, modified_fee(entry)), |(size, fees), ancestor| (size + ancestor.size, fees + modified_fee(ancestor)));
+
+		let mut depends: Vec<H256> = entry.transaction.inputs.iter()
+			.map(|input| input.previous_output.hash.clone())
+			.filter(|hash| self.by_hash.contains_key(hash))
+			.collect();
+		depends.sort();
+		depends.dedup();
+		let mut spent_by: Vec<H256> = self.references.by_input.get(h)
+			.map(|spent_by| spent_by.iter().cloned().collect())
+			.unwrap_or_default();
+		spent_by.sort();
+
+		Some(EntryInformation {
+			hash: entry.hash.clone(),
+			size: entry.size,
+			fee: entry.miner_fee,
+			modified_fee: modified_fee(entry),
+			time: entry.time,
+			descendant_count: descendants.len() + 1,
+			descendant_size: descendant_size,
+			descendant_fees: descendant_fees,
+			ancestor_count: entry.ancestors.len() + 1,
+			ancestor_size: ancestor_size,
+			ancestor_fees: ancestor_fees,
+			depends: depends,
+			spent_by: spent_by,
+		})
+	}
 }
 
 impl ReferenceStorage {
@@ -638,18 +805,47 @@ impl Default for MemoryPool {
 	fn default() -> Self {
 		MemoryPool {
 			storage: Storage::new(),
+			sequence: 0,
+			listeners: Vec::new(),
 		}
 	}
 }
 
+impl fmt::Debug for MemoryPool {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("MemoryPool")
+			.field("storage", &self.storage)
+			.field("sequence", &self.sequence)
+			.field("listeners", &self.listeners.len())
+			.finish()
+	}
+}
+
 impl MemoryPool {
 	/// Creates new memory pool
 	pub fn new() -> Self {
 		MemoryPool::default()
 	}
 
+	/// Install memory pool events listener
+	pub fn install_listener(&mut self, listener: Box<MemoryPoolListener>) {
+		self.listeners.push(listener);
+	}
+
+	/// Returns memory pool sequence number
+	pub fn sequence(&self) -> u64 {
+		self.sequence
+	}
+
 	/// Insert verified transaction to the `MemoryPool`
 	pub fn insert_verified(&mut self, t: IndexedTransaction) {
+		if !self.storage.contains(&t.hash) {
+			self.sequence += 1;
+			for listener in &self.listeners {
+				listener.transaction_added(&t, self.sequence);
+			}
+		}
+
 		let entry = self.make_entry(t);
 		let descendants = self.storage.remove_by_parent_hash(&entry.hash);
 		self.storage.insert(entry);
@@ -669,7 +865,13 @@ impl MemoryPool {
 	/// Removes single transaction by its hash.
 	/// All descedants remain in the pool.
 	pub fn remove_by_hash(&mut self, h: &H256) -> Option<Transaction> {
-		self.storage.remove_by_hash(h).map(|entry| entry.transaction)
+		self.remove_by_hash_with_reason(h, RemovalReason::Other)
+	}
+
+	/// Removes single transaction, included in the best chain block, by its hash.
+	/// All descedants remain in the pool.
+	pub fn remove_included(&mut self, h: &H256) -> Option<Transaction> {
+		self.remove_by_hash_with_reason(h, RemovalReason::Block)
 	}
 
 	/// Checks if `transaction` spends some outputs, already spent by inpool transactions.
@@ -679,7 +881,13 @@ impl MemoryPool {
 
 	/// Removes transaction (and all its descendants) which has spent given output
 	pub fn remove_by_prevout(&mut self, prevout: &OutPoint) -> Option<Vec<IndexedTransaction>> {
-		self.storage.remove_by_prevout(prevout)
+		let removed = self.storage.remove_by_prevout(prevout);
+		if let Some(ref removed) = removed {
+			for transaction in removed {
+				self.notify_removed(&transaction.hash, RemovalReason::Conflict);
+			}
+		}
+		removed
 	}
 
 	/// Reads single transaction by its hash.
@@ -703,13 +911,21 @@ impl MemoryPool {
 	/// Removes the 'top' transaction from the `MemoryPool` using selected strategy.
 	/// Ancestors are always removed before descendant transactions.
 	pub fn remove_with_strategy(&mut self, strategy: OrderingStrategy) -> Option<IndexedTransaction> {
-		self.storage.remove_with_strategy(strategy)
+		let removed = self.storage.remove_with_strategy(strategy);
+		if let Some(ref transaction) = removed {
+			self.notify_removed(&transaction.hash, RemovalReason::Other);
+		}
+		removed
 	}
 
 	/// Removes up to n transactions from the `MemoryPool`, using selected strategy.
 	/// Ancestors are always removed before descendant transactions.
 	pub fn remove_n_with_strategy(&mut self, n: usize, strategy: OrderingStrategy) -> Vec<IndexedTransaction> {
-		self.storage.remove_n_with_strategy(n, strategy)
+		let removed = self.storage.remove_n_with_strategy(n, strategy);
+		for transaction in &removed {
+			self.notify_removed(&transaction.hash, RemovalReason::Other);
+		}
+		removed
 	}
 
 	/// Set miner virtual fee for transaction
@@ -717,6 +933,16 @@ impl MemoryPool {
 		self.storage.set_virtual_fee(h, virtual_fee)
 	}
 
+	/// Set miner fee for transaction
+	pub fn set_fee(&mut self, h: &H256, fee: i64) {
+		self.storage.set_fee(h, fee)
+	}
+
+	/// Get virtual fee of transaction
+	pub fn virtual_fee(&self, h: &H256) -> Option<i64> {
+		self.storage.get_by_hash(h).map(|entry| entry.miner_virtual_fee)
+	}
+
 	/// Get transaction by hash
 	pub fn get(&self, hash: &H256) -> Option<&Transaction> {
 		self.storage.get_by_hash(hash).map(|entry| &entry.transaction)
@@ -733,6 +959,7 @@ impl MemoryPool {
 		Information {
 			transactions_count: self.storage.by_hash.len(),
 			transactions_size_in_bytes: self.storage.transactions_size_in_bytes,
+			memory_usage_in_bytes: self.heap_size_of_children(),
 		}
 	}
 
@@ -742,16 +969,64 @@ impl MemoryPool {
 		self.storage.get_transactions_ids()
 	}
 
+	/// Returns information on single `MemoryPool` entry (as in GetMemPoolEntry RPC)
+	/// https://bitcoin.org/en/developer-reference#getmempoolentry
+	pub fn entry_information(&self, hash: &H256) -> Option<EntryInformation> {
+		self.storage.get_entry_information(hash)
+	}
+
+	/// Returns hashes of all in-pool ancestors of given transaction (as in GetMemPoolAncestors RPC)
+	/// https://bitcoin.org/en/developer-reference#getmempoolancestors
+	pub fn get_ancestors_ids(&self, hash: &H256) -> Option<Vec<H256>> {
+		self.storage.get_by_hash(hash).map(|entry| entry.ancestors.iter().cloned().collect())
+	}
+
+	/// Returns hashes of all in-pool descendants of given transaction (as in GetMemPoolDescendants RPC)
+	/// https://bitcoin.org/en/developer-reference#getmempooldescendants
+	pub fn get_descendants_ids(&self, hash: &H256) -> Option<Vec<H256>> {
+		if !self.storage.contains(hash) {
+			return None;
+		}
+		Some(self.storage.get_descendants(hash).into_iter().collect())
+	}
+
 	/// Returns true if output was spent
 	pub fn is_spent(&self, prevout: &OutPoint) -> bool {
 		self.storage.is_output_spent(prevout)
 	}
 
+	/// Returns hash of in-pool transaction and index of its input, which spends given output
+	pub fn spending_input(&self, prevout: &OutPoint) -> Option<(H256, u32)> {
+		let hash = match self.storage.output_spent_by(prevout) {
+			Some(hash) => hash,
+			None => return None,
+		};
+		self.storage.get_by_hash(hash)
+			.and_then(|entry| entry.transaction.inputs.iter().position(|input| input.previous_output == *prevout))
+			.map(|index| (hash.clone(), index as u32))
+	}
+
+	fn remove_by_hash_with_reason(&mut self, h: &H256, reason: RemovalReason) -> Option<Transaction> {
+		let removed = self.storage.remove_by_hash(h);
+		if removed.is_some() {
+			self.notify_removed(h, reason);
+		}
+		removed.map(|entry| entry.transaction)
+	}
+
+	fn notify_removed(&mut self, hash: &H256, reason: RemovalReason) {
+		self.sequence += 1;
+		for listener in &self.listeners {
+			listener.transaction_removed(hash, reason, self.sequence);
+		}
+	}
+
 	fn make_entry(&mut self, t: IndexedTransaction) -> Entry {
 		let ancestors = self.get_ancestors(&t.raw);
 		let size = self.get_transaction_size(&t.raw);
 		let storage_index = self.get_storage_index();
 		let miner_fee = self.get_transaction_miner_fee(&t.raw);
+		let time = self.get_time();
 		Entry {
 			transaction: t.raw,
 			hash: t.hash,
@@ -764,6 +1039,7 @@ impl MemoryPool {
 			package_size: size,
 			package_miner_fee: miner_fee,
 			package_miner_virtual_fee: 0,
+			time: time,
 		}
 	}
 
@@ -790,6 +1066,12 @@ impl MemoryPool {
 		(output_value - input_value) as i64
 	}
 
+	fn get_time(&self) -> u32 {
+		SystemTime::now().duration_since(UNIX_EPOCH)
+			.map(|duration| duration.as_secs() as u32)
+			.unwrap_or_default()
+	}
+
 	#[cfg(not(test))]
 	fn get_storage_index(&mut self) -> u64 {
 		self.storage.counter += 1;
@@ -871,9 +1153,11 @@ impl<'a> Iterator for MemoryPoolIterator<'a> {
 mod tests {
 	extern crate test_data;
 
-	use chain::{Transaction, OutPoint};
+	use chain::{Transaction, OutPoint, IndexedTransaction};
 	use heapsize::HeapSizeOf;
-	use super::{MemoryPool, OrderingStrategy, DoubleSpendCheckResult};
+	use primitives::hash::H256;
+	use std::sync::{Arc, Mutex};
+	use super::{MemoryPool, MemoryPoolListener, RemovalReason, OrderingStrategy, DoubleSpendCheckResult};
 	use self::test_data::{ChainBuilder, TransactionBuilder};
 
 	fn to_memory_pool(chain: &mut ChainBuilder) -> MemoryPool {
@@ -1396,4 +1680,119 @@ mod tests {
 		assert!(memory_pool.is_spent(&out1));
 		assert!(!memory_pool.is_spent(&out2));
 	}
+
+	#[test]
+	fn test_memory_pool_spending_input() {
+		let tx1: Transaction = TransactionBuilder::with_default_input(0).add_default_input(1).into();
+		let tx1_hash = tx1.hash();
+		let out1 = tx1.inputs[1].previous_output.clone();
+		let out2 = OutPoint { hash: H256::from(2), index: 0 };
+		let mut memory_pool = MemoryPool::new();
+		memory_pool.insert_verified(tx1.into());
+		assert_eq!(memory_pool.spending_input(&out1), Some((tx1_hash, 1)));
+		assert_eq!(memory_pool.spending_input(&out2), None);
+	}
+
+	#[test]
+	fn test_memory_pool_entry_information() {
+		let chain = &mut ChainBuilder::new();
+		TransactionBuilder::with_output(100).store(chain)
+			.into_input(0).add_output(100).store(chain)
+			.into_input(0).add_output(100).store(chain);
+		let mut pool = to_memory_pool(chain);
+
+		pool.set_fee(&chain.hash(0), 10);
+		pool.set_fee(&chain.hash(1), 20);
+		pool.set_fee(&chain.hash(2), 30);
+		pool.set_virtual_fee(&chain.hash(1), 5);
+		assert_eq!(pool.storage.get_by_hash(&chain.hash(0)).unwrap().package_miner_fee, 60);
+
+		let info = pool.entry_information(&chain.hash(1)).unwrap();
+		assert_eq!(info.hash, chain.hash(1));
+		assert_eq!(info.size, chain.size(1));
+		assert_eq!(info.fee, 20);
+		assert_eq!(info.modified_fee, 25);
+		assert_eq!(info.descendant_count, 2);
+		assert_eq!(info.descendant_size, chain.size(1) + chain.size(2));
+		assert_eq!(info.descendant_fees, 55);
+		assert_eq!(info.ancestor_count, 2);
+		assert_eq!(info.ancestor_size, chain.size(0) + chain.size(1));
+		assert_eq!(info.ancestor_fees, 35);
+		assert_eq!(info.depends, vec![chain.hash(0)]);
+		assert_eq!(info.spent_by, vec![chain.hash(2)]);
+		assert!(pool.entry_information(&H256::default()).is_none());
+	}
+
+	#[test]
+	fn test_memory_pool_ancestors_and_descendants() {
+		let chain = &mut ChainBuilder::new();
+		TransactionBuilder::with_output(100).store(chain)
+			.into_input(0).add_output(100).store(chain)
+			.into_input(0).add_output(100).store(chain);
+		let pool = to_memory_pool(chain);
+
+		let mut ancestors = pool.get_ancestors_ids(&chain.hash(2)).unwrap();
+		ancestors.sort();
+		let mut expected = vec![chain.hash(0), chain.hash(1)];
+		expected.sort();
+		assert_eq!(ancestors, expected);
+
+		let mut descendants = pool.get_descendants_ids(&chain.hash(0)).unwrap();
+		descendants.sort();
+		let mut expected = vec![chain.hash(1), chain.hash(2)];
+		expected.sort();
+		assert_eq!(descendants, expected);
+
+		assert_eq!(pool.get_ancestors_ids(&chain.hash(0)), Some(vec![]));
+		assert_eq!(pool.get_descendants_ids(&chain.hash(2)), Some(vec![]));
+		assert_eq!(pool.get_descendants_ids(&H256::default()), None);
+	}
+
+	#[derive(Default)]
+	struct DummyListener {
+		events: Arc<Mutex<Vec<(H256, Option<RemovalReason>, u64)>>>,
+	}
+
+	impl MemoryPoolListener for DummyListener {
+		fn transaction_added(&self, transaction: &IndexedTransaction, sequence: u64) {
+			self.events.lock().unwrap().push((transaction.hash.clone(), None, sequence));
+		}
+
+		fn transaction_removed(&self, hash: &H256, reason: RemovalReason, sequence: u64) {
+			self.events.lock().unwrap().push((hash.clone(), Some(reason), sequence));
+		}
+	}
+
+	#[test]
+	fn test_memory_pool_listener() {
+		let chain = &mut ChainBuilder::new();
+		TransactionBuilder::with_output(10).store(chain)		// transaction0
+			.into_input(0).add_output(20).store(chain)			// transaction0 -> transaction1
+			.set_output(30).store(chain)						// transaction0 -> transaction2 (conflicts with transaction1)
+			.reset().add_output(40).store(chain);				// transaction3
+
+		let listener = DummyListener::default();
+		let events = listener.events.clone();
+		let mut pool = MemoryPool::new();
+		pool.install_listener(Box::new(listener));
+
+		pool.insert_verified(chain.at(0).into());
+		pool.insert_verified(chain.at(0).into());
+		pool.insert_verified(chain.at(1).into());
+		pool.insert_verified(chain.at(3).into());
+		pool.remove_by_prevout(&chain.at(2).inputs[0].previous_output);
+		pool.remove_included(&chain.hash(0));
+		pool.remove_by_hash(&chain.hash(3));
+		pool.remove_by_hash(&chain.hash(3));
+
+		assert_eq!(pool.sequence(), 6);
+		assert_eq!(*events.lock().unwrap(), vec![
+			(chain.hash(0), None, 1),
+			(chain.hash(1), None, 2),
+			(chain.hash(3), None, 3),
+			(chain.hash(1), Some(RemovalReason::Conflict), 4),
+			(chain.hash(0), Some(RemovalReason::Block), 5),
+			(chain.hash(3), Some(RemovalReason::Other), 6),
+		]);
+	}
 }
@@ -0,0 +1,280 @@
+use chain::{merkle_root, BlockHeader, IndexedTransaction, Transaction, TransactionInput, TransactionOutput};
+use crypto::dhash256;
+use keys::{KeyPair, Private};
+use primitives::bytes::Bytes;
+use primitives::hash::H256;
+use script::{Builder, Opcode, Script, ScriptType, SignatureVersion, TransactionInputSigner};
+use ser::Stream;
+use verification::{SignetTxs, SIGNET_HEADER, signet_solution};
+use block_assembler::BlockTemplate;
+use cpu_miner::CoinbaseTransactionBuilder;
+
+/// Prefix of the witness commitment (BIP141)
+const WITNESS_COMMITMENT_HEADER: [u8; 4] = [0xaa, 0x21, 0xa9, 0xed];
+/// SIGHASH_ALL signature hash type
+const SIGHASH_ALL: u32 = 1;
+
+/// Coinbase transaction builder of the signet miner, paying whole coinbase value to given script.
+/// Signet solution commits to the coinbase transaction => block is signed again every time
+/// the extranonce is changed. Supported challenges are: pay-to-pubkey, pay-to-pubkey-hash,
+/// pay-to-witness-pubkey-hash and 1-of-n multisig.
+#[derive(Clone)]
+pub struct SignetCoinbaseTransactionBuilder {
+	height: u32,
+	/// Header fields, committed to by the signet solution
+	header: BlockHeader,
+	/// Block transactions. First transaction is the coinbase transaction.
+	transactions: Vec<IndexedTransaction>,
+	/// Witness commitment of the block
+	witness_commitment: H256,
+	challenge: Bytes,
+	private: Private,
+}
+
+impl SignetCoinbaseTransactionBuilder {
+	/// Returns error if `private` key is unable to produce solution for the `challenge` script.
+	pub fn new(template: &BlockTemplate, script_pubkey: Bytes, challenge: Bytes, private: Private) -> Result<Self, String> {
+		KeyPair::from_private(private.clone()).map_err(|err| format!("Invalid signet private key: {}", err))?;
+
+		let coinbase = Transaction {
+			version: 1,
+			inputs: vec![TransactionInput {
+				script_witness: vec![H256::default().to_vec().into()],
+				..TransactionInput::coinbase(Bytes::default())
+			}],
+			outputs: vec![
+				TransactionOutput {
+					value: template.coinbase_value,
+					script_pubkey: script_pubkey,
+				},
+				TransactionOutput {
+					value: 0,
+					script_pubkey: Bytes::default(),
+				},
+			],
+			lock_time: 0,
+		};
+
+		let mut transactions = vec![IndexedTransaction::from(coinbase)];
+		transactions.extend(template.transactions.iter().cloned());
+
+		let mut builder = SignetCoinbaseTransactionBuilder {
+			height: template.height,
+			header: BlockHeader {
+				version: template.version,
+				previous_header_hash: template.previous_header_hash.clone(),
+				merkle_root_hash: H256::default(),
+				time: template.time,
+				bits: template.bits,
+				nonce: 0,
+			},
+			witness_commitment: witness_commitment(&template.transactions),
+			transactions: transactions,
+			challenge: challenge,
+			private: private,
+		};
+
+		// make sure that blocks, produced by this builder, are valid
+		builder.set_extranonce(&[]);
+		let signet_txs = SignetTxs::new(&builder.header, &builder.transactions, &builder.challenge)
+			.expect("coinbase transaction has witness commitment; qed");
+		signet_txs.verify().map_err(|err| format!("Signet private key is unable to sign the challenge: {}", err))?;
+
+		Ok(builder)
+	}
+
+	/// Witness commitment script with given signet solution
+	fn commitment_script(&self, solution: &[u8]) -> Bytes {
+		let mut commitment = WITNESS_COMMITMENT_HEADER.to_vec();
+		commitment.extend_from_slice(&*self.witness_commitment);
+		let mut signet_commitment = SIGNET_HEADER.to_vec();
+		signet_commitment.extend_from_slice(solution);
+
+		Builder::default()
+			.push_opcode(Opcode::OP_RETURN)
+			.push_data(&commitment)
+			.push_data(&signet_commitment)
+			.into_bytes()
+	}
+
+	fn set_coinbase(&mut self, coinbase: Transaction) {
+		self.transactions[0] = coinbase.into();
+	}
+
+	/// Sign the block with current coinbase transaction
+	fn sign(&self) -> Bytes {
+		let signet_txs = SignetTxs::new(&self.header, &self.transactions, &self.challenge)
+			.expect("coinbase transaction has witness commitment; qed");
+		let key_pair = KeyPair::from_private(self.private.clone())
+			.expect("private key is checked in constructor; qed");
+		let signer: TransactionInputSigner = signet_txs.to_sign.into();
+		let challenge: Script = self.challenge.clone().into();
+
+		let (script_sig, script_witness) = match challenge.script_type() {
+			ScriptType::WitnessKey => {
+				let script_code = Builder::build_p2pkh(&key_pair.public().address_hash());
+				let signature = signature(&key_pair, &signer, &script_code, SignatureVersion::WitnessV0);
+				(Bytes::default(), vec![signature, key_pair.public().to_vec().into()])
+			},
+			script_type => {
+				let signature = signature(&key_pair, &signer, &challenge, SignatureVersion::Base);
+				let script_sig = match script_type {
+					ScriptType::Multisig => Builder::default()
+						.push_opcode(Opcode::OP_0)
+						.push_data(&signature),
+					ScriptType::PubKeyHash => Builder::default()
+						.push_data(&signature)
+						.push_data(key_pair.public()),
+					_ => Builder::default()
+						.push_data(&signature),
+				};
+				(script_sig.into_bytes(), Vec::new())
+			},
+		};
+
+		signet_solution(&script_sig, &script_witness)
+	}
+}
+
+impl CoinbaseTransactionBuilder for SignetCoinbaseTransactionBuilder {
+	fn set_extranonce(&mut self, extranonce: &[u8]) {
+		let mut coinbase = self.transactions[0].raw.clone();
+		coinbase.inputs[0].script_sig = Builder::default()
+			.push_num(self.height.into())
+			.push_data(extranonce)
+			.into_bytes();
+
+		// solution is replaced with the signet header before signing
+		coinbase.outputs[1].script_pubkey = self.commitment_script(&[]);
+		self.set_coinbase(coinbase.clone());
+
+		coinbase.outputs[1].script_pubkey = self.commitment_script(&self.sign());
+		self.set_coinbase(coinbase);
+	}
+
+	fn hash(&self) -> H256 {
+		self.transactions[0].hash.clone()
+	}
+
+	fn finish(self) -> Transaction {
+		self.transactions.into_iter().next().expect("coinbase transaction is always present; qed").raw
+	}
+}
+
+/// Witness commitment of the block with given non-coinbase transactions and zero witness reserved value
+fn witness_commitment(transactions: &[IndexedTransaction]) -> H256 {
+	let mut hashes = vec![H256::default()];
+	hashes.extend(transactions.iter().map(|tx| tx.raw.witness_hash()));
+
+	let mut stream = Stream::default();
+	stream.append(&merkle_root(&hashes));
+	stream.append_slice(&*H256::default());
+	dhash256(&stream.out())
+}
+
+/// Sign the only input of the signet `to_sign` transaction
+fn signature(key_pair: &KeyPair, signer: &TransactionInputSigner, script_code: &Script, sigversion: SignatureVersion) -> Bytes {
+	let hash = signer.signature_hash(0, 0, script_code, sigversion, SIGHASH_ALL);
+	let mut signature: Vec<u8> = match key_pair.private().sign(&hash) {
+		Ok(signature) => signature.into(),
+		Err(_) => Vec::new(),
+	};
+	signature.push(SIGHASH_ALL as u8);
+	signature.into()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use std::sync::atomic::AtomicBool;
+	use chain::IndexedBlock;
+	use keys::{KeyPair, Network, Private};
+	use primitives::bytes::Bytes;
+	use primitives::U256;
+	use script::{Builder, Opcode};
+	use verification::SignetTxs;
+	use block_assembler::BlockTemplate;
+	use cpu_miner::{find_solution, build_block};
+	use super::SignetCoinbaseTransactionBuilder;
+
+	fn private(secret: u8) -> Private {
+		Private {
+			network: Network::Testnet,
+			secret: secret.into(),
+			compressed: true,
+		}
+	}
+
+	fn template() -> BlockTemplate {
+		BlockTemplate {
+			version: 0x20000000,
+			previous_header_hash: 1u8.into(),
+			time: 1598918400,
+			bits: U256::max_value().into(),
+			height: 1,
+			transactions: Vec::new(),
+			coinbase_value: 50_0000_0000,
+			size_limit: 1_000_000,
+			sigop_limit: 20_000,
+		}
+	}
+
+	fn mine(challenge: Bytes, private: Private) -> IndexedBlock {
+		let template = template();
+		let builder = SignetCoinbaseTransactionBuilder::new(&template, "51".into(), challenge, private).unwrap();
+		let solution = find_solution(&template, builder, U256::max_value(), Arc::new(AtomicBool::new(true))).unwrap();
+		build_block(&template, solution)
+	}
+
+	fn verify(block: &IndexedBlock, challenge: &Bytes) -> bool {
+		SignetTxs::new(&block.header.raw, &block.transactions, challenge).unwrap().verify().is_ok()
+	}
+
+	#[test]
+	fn test_signet_block_p2pk_challenge() {
+		let key_pair = KeyPair::from_private(private(1)).unwrap();
+		let challenge = Builder::default()
+			.push_data(key_pair.public())
+			.push_opcode(Opcode::OP_CHECKSIG)
+			.into_bytes();
+		let block = mine(challenge.clone(), private(1));
+		assert!(verify(&block, &challenge));
+		assert_eq!(block.header.raw.merkle_root_hash, block.merkle_root());
+	}
+
+	#[test]
+	fn test_signet_block_p2wpkh_challenge() {
+		let key_pair = KeyPair::from_private(private(1)).unwrap();
+		let challenge = Builder::default()
+			.push_opcode(Opcode::OP_0)
+			.push_data(&*key_pair.public().address_hash())
+			.into_bytes();
+		let block = mine(challenge.clone(), private(1));
+		assert!(verify(&block, &challenge));
+	}
+
+	#[test]
+	fn test_signet_block_multisig_challenge() {
+		let first = KeyPair::from_private(private(1)).unwrap();
+		let second = KeyPair::from_private(private(2)).unwrap();
+		let challenge = Builder::default()
+			.push_opcode(Opcode::OP_1)
+			.push_data(first.public())
+			.push_data(second.public())
+			.push_opcode(Opcode::OP_2)
+			.push_opcode(Opcode::OP_CHECKMULTISIG)
+			.into_bytes();
+		let block = mine(challenge.clone(), private(2));
+		assert!(verify(&block, &challenge));
+	}
+
+	#[test]
+	fn test_signet_builder_rejects_foreign_challenge() {
+		let key_pair = KeyPair::from_private(private(1)).unwrap();
+		let challenge = Builder::default()
+			.push_data(key_pair.public())
+			.push_opcode(Opcode::OP_CHECKSIG)
+			.into_bytes();
+		assert!(SignetCoinbaseTransactionBuilder::new(&template(), "51".into(), challenge, private(2)).is_err());
+	}
+}
@@ -0,0 +1,372 @@
+//! Description of the custom chain, loaded from JSON or TOML file.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use serde_json;
+use toml;
+use chain::Block;
+use primitives::bytes::Bytes;
+use primitives::U256;
+use ser::deserialize;
+use consensus::{DEFAULT_TARGET_SPACING_SECONDS, DEFAULT_TARGET_TIMESPAN_SECONDS};
+use hash::H256;
+use {ConsensusParams, ConsensusFork, Deployment, Network, AddressPrefixes, AssumeUtxo};
+
+/// Custom chain parameters
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChainSpec {
+	/// Chain name
+	pub name: String,
+	/// Network magic
+	pub magic: u32,
+	/// Default p2p port
+	pub port: u16,
+	/// Default JSON-RPC port
+	pub rpc_port: u16,
+	/// Hex-encoded serialized genesis block
+	pub genesis: String,
+	/// Hex-encoded maximal proof of work target
+	pub pow_limit: String,
+	/// Difficulty adjustment rules
+	#[serde(default)]
+	pub retarget: RetargetSpec,
+	/// Time when BIP16 becomes active
+	#[serde(default)]
+	pub bip16_time: u32,
+	/// Block height at which BIP34 becomes active
+	#[serde(default)]
+	pub bip34_height: u32,
+	/// Block height at which BIP65 becomes active
+	#[serde(default)]
+	pub bip65_height: u32,
+	/// Block height at which BIP66 becomes active
+	#[serde(default)]
+	pub bip66_height: u32,
+	/// Number of signalling blocks, required to lock in the versionbits deployment
+	#[serde(default = "default_activation_threshold")]
+	pub rule_change_activation_threshold: u32,
+	/// Number of blocks in the versionbits signalling period
+	#[serde(default = "default_confirmation_window")]
+	pub miner_confirmation_window: u32,
+	/// Versionbits deployments schedule
+	#[serde(default)]
+	pub deployments: DeploymentsSpec,
+	/// Seed nodes
+	#[serde(default)]
+	pub seeds: Vec<String>,
+	/// Base58 prefixes of addresses and private keys
+	#[serde(default = "default_address_prefixes")]
+	pub address_prefixes: AddressPrefixes,
+	/// UTXO set snapshots, which may be loaded instead of synchronizing from the genesis block
+	#[serde(default)]
+	pub assume_utxo: Vec<AssumeUtxoSpec>,
+}
+
+/// Difficulty adjustment rules
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetargetSpec {
+	/// Desired number of seconds between blocks
+	pub target_spacing: u32,
+	/// Desired number of seconds between difficulty adjustments
+	pub target_timespan: u32,
+	/// Allow blocks with minimal difficulty, if there were no blocks for a long time
+	pub allow_min_difficulty_blocks: bool,
+	/// Never adjust difficulty
+	pub no_retargeting: bool,
+}
+
+impl Default for RetargetSpec {
+	fn default() -> Self {
+		RetargetSpec {
+			target_spacing: DEFAULT_TARGET_SPACING_SECONDS,
+			target_timespan: DEFAULT_TARGET_TIMESPAN_SECONDS,
+			allow_min_difficulty_blocks: false,
+			no_retargeting: false,
+		}
+	}
+}
+
+/// Versionbits deployments schedule. Missing deployments are active since genesis block.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DeploymentsSpec {
+	/// BIP68, BIP112, BIP113 deployment
+	pub csv: Option<DeploymentSpec>,
+	/// BIP141, BIP143, BIP147 deployment
+	pub segwit: Option<DeploymentSpec>,
+}
+
+/// Versionbits deployment schedule
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeploymentSpec {
+	/// Bit
+	pub bit: u8,
+	/// Start time
+	#[serde(default)]
+	pub start_time: u32,
+	/// Timeout
+	#[serde(default)]
+	pub timeout: u32,
+	/// Block height at which the deployment is active, regardless of signalling
+	#[serde(default)]
+	pub activation: Option<u32>,
+}
+
+/// UTXO set snapshot, committed to by the chain
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AssumeUtxoSpec {
+	/// Number of the block, at which snapshot is made
+	pub block_number: u32,
+	/// Hex-encoded hash of the block, at which snapshot is made (as displayed by rpc)
+	pub block_hash: String,
+	/// Hex-encoded hash of the snapshot coins (as displayed by rpc)
+	pub content_hash: String,
+}
+
+fn default_activation_threshold() -> u32 {
+	108
+}
+
+fn default_confirmation_window() -> u32 {
+	144
+}
+
+fn default_address_prefixes() -> AddressPrefixes {
+	AddressPrefixes::Testnet
+}
+
+impl DeploymentSpec {
+	fn to_deployment(&self, name: &'static str) -> Deployment {
+		Deployment {
+			name: name,
+			bit: self.bit,
+			start_time: self.start_time,
+			timeout: self.timeout,
+			activation: self.activation,
+		}
+	}
+}
+
+impl AssumeUtxoSpec {
+	fn to_assume_utxo(&self) -> Result<AssumeUtxo, String> {
+		let block_hash: H256 = self.block_hash.parse().map_err(|_| "assume_utxo.block_hash is not a valid hash".to_owned())?;
+		let content_hash: H256 = self.content_hash.parse().map_err(|_| "assume_utxo.content_hash is not a valid hash".to_owned())?;
+		Ok(AssumeUtxo {
+			block_number: self.block_number,
+			block_hash: block_hash.reversed(),
+			content_hash: content_hash.reversed(),
+		})
+	}
+}
+
+/// Deployment, which is active since genesis block
+fn buried_deployment(name: &'static str, bit: u8) -> Deployment {
+	Deployment {
+		name: name,
+		bit: bit,
+		start_time: 0,
+		timeout: 0,
+		activation: Some(0),
+	}
+}
+
+impl ChainSpec {
+	/// Load chain spec from file. Files with `.toml` extension are parsed as TOML, all other as JSON.
+	pub fn load(path: &Path) -> Result<Self, String> {
+		let mut contents = String::new();
+		File::open(path)
+			.and_then(|mut file| file.read_to_string(&mut contents))
+			.map_err(|err| format!("Failed to read chain spec {}: {}", path.display(), err))?;
+
+		let is_toml = path.extension().map(|extension| extension == "toml").unwrap_or(false);
+		let spec = if is_toml {
+			ChainSpec::from_toml(&contents)
+		} else {
+			ChainSpec::from_json(&contents)
+		};
+		spec.map_err(|err| format!("Invalid chain spec {}: {}", path.display(), err))
+	}
+
+	pub fn from_json(contents: &str) -> Result<Self, String> {
+		let spec: ChainSpec = serde_json::from_str(contents).map_err(|err| err.to_string())?;
+		spec.validate()?;
+		Ok(spec)
+	}
+
+	pub fn from_toml(contents: &str) -> Result<Self, String> {
+		let spec: ChainSpec = toml::from_str(contents).map_err(|err| err.to_string())?;
+		spec.validate()?;
+		Ok(spec)
+	}
+
+	/// Network of the chain
+	pub fn network(&self) -> Network {
+		Network::Other(self.magic)
+	}
+
+	pub fn genesis_block(&self) -> Result<Block, String> {
+		let genesis: Bytes = self.genesis.parse().map_err(|_| "genesis is not a valid hex".to_owned())?;
+		deserialize(&genesis as &[u8]).map_err(|_| "genesis is not a valid block".to_owned())
+	}
+
+	pub fn pow_limit(&self) -> Result<U256, String> {
+		self.pow_limit.parse().map_err(|_| "pow_limit is not a valid hex".to_owned())
+	}
+
+	/// Consensus parameters of the chain
+	pub fn consensus_params(&self, fork: ConsensusFork) -> Result<ConsensusParams, String> {
+		let csv_deployment = match self.deployments.csv {
+			Some(ref csv) => csv.to_deployment("csv"),
+			None => buried_deployment("csv", 0),
+		};
+		let segwit_deployment = match fork {
+			ConsensusFork::BitcoinCore => Some(match self.deployments.segwit {
+				Some(ref segwit) => segwit.to_deployment("segwit"),
+				None => buried_deployment("segwit", 1),
+			}),
+			ConsensusFork::BitcoinCash(_) => None,
+		};
+
+		Ok(ConsensusParams {
+			network: self.network(),
+			genesis_block: self.genesis_block()?,
+			pow_limit: self.pow_limit()?,
+			pow_target_spacing: self.retarget.target_spacing,
+			pow_target_timespan: self.retarget.target_timespan,
+			pow_allow_min_difficulty_blocks: self.retarget.allow_min_difficulty_blocks,
+			pow_no_retargeting: self.retarget.no_retargeting,
+			address_prefixes: self.address_prefixes,
+			bip16_time: self.bip16_time,
+			bip34_height: self.bip34_height,
+			bip65_height: self.bip65_height,
+			bip66_height: self.bip66_height,
+			segwit_deployment: segwit_deployment,
+			fork: fork,
+			rule_change_activation_threshold: self.rule_change_activation_threshold,
+			miner_confirmation_window: self.miner_confirmation_window,
+			csv_deployment: Some(csv_deployment),
+			signet_challenge: None,
+			assume_utxo: self.assume_utxo.iter().map(AssumeUtxoSpec::to_assume_utxo).collect::<Result<_, _>>()?,
+		})
+	}
+
+	fn validate(&self) -> Result<(), String> {
+		let genesis_block = self.genesis_block()?;
+		let pow_limit = self.pow_limit()?;
+		if pow_limit.is_zero() {
+			return Err("pow_limit must be positive".into());
+		}
+		match genesis_block.block_header.bits.to_u256() {
+			Ok(target) if target <= pow_limit => (),
+			_ => return Err("genesis block target is above pow_limit".into()),
+		}
+		if self.retarget.target_spacing == 0 || self.retarget.target_timespan < self.retarget.target_spacing {
+			return Err("retarget.target_timespan must be at least retarget.target_spacing, which must be positive".into());
+		}
+		if self.miner_confirmation_window == 0 || self.rule_change_activation_threshold > self.miner_confirmation_window {
+			return Err("rule_change_activation_threshold must not exceed positive miner_confirmation_window".into());
+		}
+		for assume_utxo in &self.assume_utxo {
+			assume_utxo.to_assume_utxo()?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use {ConsensusFork, Network, AddressPrefixes};
+	use super::ChainSpec;
+
+	const GENESIS: &'static str = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4adae5494dffff7f20020000000101000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000";
+	const POW_LIMIT: &'static str = "7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff";
+	const ASSUME_UTXO_BLOCK: &'static str = "00000000000000000000000000000000000000000000000000000000000000aa";
+	const ASSUME_UTXO_CONTENT: &'static str = "00000000000000000000000000000000000000000000000000000000000000bb";
+
+	fn json_spec() -> String {
+		format!(r#"{{
+			"name": "private",
+			"magic": 3735928559,
+			"port": 28444,
+			"rpc_port": 28443,
+			"genesis": "{}",
+			"pow_limit": "{}",
+			"retarget": {{ "target_spacing": 60, "target_timespan": 3600, "no_retargeting": true }},
+			"bip34_height": 10,
+			"bip65_height": 20,
+			"bip66_height": 30,
+			"deployments": {{
+				"segwit": {{ "bit": 1, "start_time": 0, "timeout": 4294967295 }}
+			}},
+			"seeds": ["127.0.0.1:28444"],
+			"assume_utxo": [{{
+				"block_number": 110,
+				"block_hash": "{}",
+				"content_hash": "{}"
+			}}]
+		}}"#, GENESIS, POW_LIMIT, ASSUME_UTXO_BLOCK, ASSUME_UTXO_CONTENT)
+	}
+
+	#[test]
+	fn test_chain_spec_from_json() {
+		let spec = ChainSpec::from_json(&json_spec()).unwrap();
+		assert_eq!(spec.network(), Network::Other(0xdeadbeef));
+		assert_eq!(spec.seeds, vec!["127.0.0.1:28444".to_owned()]);
+		assert_eq!(spec.address_prefixes, AddressPrefixes::Testnet);
+
+		let consensus = spec.consensus_params(ConsensusFork::BitcoinCore).unwrap();
+		assert_eq!(consensus.magic(), 0xdeadbeef);
+		assert_eq!(consensus.genesis_block.hash(), Network::Regtest.genesis_block().hash());
+		assert_eq!(consensus.pow_limit, Network::Regtest.max_bits());
+		assert_eq!(consensus.retargeting_interval(), 60);
+		assert!(consensus.pow_no_retargeting);
+		assert_eq!(consensus.bip34_height, 10);
+		assert_eq!(consensus.bip65_height, 20);
+		assert_eq!(consensus.bip66_height, 30);
+		assert_eq!(consensus.csv_deployment.unwrap().activation, Some(0));
+		let segwit = consensus.segwit_deployment.unwrap();
+		assert_eq!(segwit.activation, None);
+		assert_eq!(segwit.timeout, 4294967295);
+		assert_eq!(consensus.assume_utxo.len(), 1);
+		assert_eq!(consensus.assume_utxo[0].block_number, 110);
+		assert_eq!(consensus.assume_utxo[0].block_hash.to_reversed_str(), ASSUME_UTXO_BLOCK);
+		assert_eq!(consensus.assume_utxo[0].content_hash.to_reversed_str(), ASSUME_UTXO_CONTENT);
+	}
+
+	#[test]
+	fn test_chain_spec_from_toml() {
+		let spec = ChainSpec::from_toml(&format!(r#"
+			name = "private"
+			magic = 3735928559
+			port = 28444
+			rpc_port = 28443
+			genesis = "{}"
+			pow_limit = "{}"
+			address_prefixes = "mainnet"
+
+			[deployments.csv]
+			bit = 0
+			activation = 100
+		"#, GENESIS, POW_LIMIT)).unwrap();
+		assert_eq!(spec.address_prefixes, AddressPrefixes::Mainnet);
+
+		let consensus = spec.consensus_params(ConsensusFork::BitcoinCore).unwrap();
+		assert_eq!(consensus.retargeting_interval(), 2016);
+		assert_eq!(consensus.csv_deployment.unwrap().activation, Some(100));
+		assert_eq!(consensus.segwit_deployment.unwrap().activation, Some(0));
+	}
+
+	#[test]
+	fn test_chain_spec_validation() {
+		assert!(ChainSpec::from_json(&json_spec().replace(POW_LIMIT, "00ff")).is_err());
+		assert!(ChainSpec::from_json(&json_spec().replace(GENESIS, "00")).is_err());
+		assert!(ChainSpec::from_json(&json_spec().replace("\"target_spacing\": 60", "\"target_spacing\": 0")).is_err());
+		assert!(ChainSpec::from_json(&json_spec().replace("\"name\"", "\"unknown\": 1, \"name\"")).is_err());
+		assert!(ChainSpec::from_json(&json_spec().replace(ASSUME_UTXO_CONTENT, "xyz")).is_err());
+	}
+}
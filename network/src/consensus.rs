@@ -1,11 +1,33 @@
 use hash::H256;
-use {Network, Magic, Deployment};
+use chain::Block;
+use primitives::bytes::Bytes;
+use primitives::U256;
+use {Network, Magic, Deployment, AddressPrefixes, default_signet_challenge, signet_magic};
+
+/// Default number of seconds between blocks.
+pub const DEFAULT_TARGET_SPACING_SECONDS: u32 = 10 * 60;
+/// Default number of seconds between difficulty adjustments.
+pub const DEFAULT_TARGET_TIMESPAN_SECONDS: u32 = 2 * 7 * 24 * 60 * 60;
 
 #[derive(Debug, Clone)]
 /// Parameters that influence chain consensus.
 pub struct ConsensusParams {
 	/// Network.
 	pub network: Network,
+	/// Genesis block of the network.
+	pub genesis_block: Block,
+	/// Maximal proof of work target.
+	pub pow_limit: U256,
+	/// Desired number of seconds between blocks.
+	pub pow_target_spacing: u32,
+	/// Desired number of seconds between difficulty adjustments.
+	pub pow_target_timespan: u32,
+	/// Allow blocks with minimal difficulty, if there were no blocks for a long time (testnet rule).
+	pub pow_allow_min_difficulty_blocks: bool,
+	/// Never adjust difficulty.
+	pub pow_no_retargeting: bool,
+	/// Base58 prefixes of addresses and private keys.
+	pub address_prefixes: AddressPrefixes,
 	/// Time when BIP16 becomes active.
 	/// See https://github.com/bitcoin/bips/blob/master/bip-0016.mediawiki
 	pub bip16_time: u32,
@@ -28,6 +50,22 @@ pub struct ConsensusParams {
 	pub csv_deployment: Option<Deployment>,
 	/// BIP141, BIP143, BIP147 deployment
 	pub segwit_deployment: Option<Deployment>,
+	/// Script, which must be satisfied by the signet block solution (signet only).
+	/// See https://github.com/bitcoin/bips/blob/master/bip-0325.mediawiki
+	pub signet_challenge: Option<Bytes>,
+	/// UTXO set snapshots, which may be loaded instead of synchronizing from the genesis block.
+	pub assume_utxo: Vec<AssumeUtxo>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// UTXO set snapshot, committed to by the chain parameters (assumeutxo).
+pub struct AssumeUtxo {
+	/// Number of the block, at which snapshot is made.
+	pub block_number: u32,
+	/// Hash of the block, at which snapshot is made.
+	pub block_hash: H256,
+	/// Hash of the snapshot coins.
+	pub content_hash: H256,
 }
 
 #[derive(Debug, Clone)]
@@ -62,6 +100,13 @@ impl ConsensusParams {
 		match network {
 			Network::Mainnet | Network::Other(_) => ConsensusParams {
 				network: network,
+				genesis_block: network.genesis_block(),
+				pow_limit: network.max_bits(),
+				pow_target_spacing: DEFAULT_TARGET_SPACING_SECONDS,
+				pow_target_timespan: DEFAULT_TARGET_TIMESPAN_SECONDS,
+				pow_allow_min_difficulty_blocks: false,
+				pow_no_retargeting: false,
+				address_prefixes: network.address_prefixes(),
 				bip16_time: 1333238400,	// Apr 1 2012
 				bip34_height: 227931,	// 000000000000024b89b42a942fe0d9fea3bb44ab7bd1b19115dd6a759c0808b8
 				bip65_height: 388381,	// 000000000000000004c2b624ed5d7756c508d90fd0da2c7c679febfa6c4735f0
@@ -86,9 +131,18 @@ impl ConsensusParams {
 					timeout: 1493596800,
 					activation: Some(419328),
 				}),
+				signet_challenge: None,
+				assume_utxo: network.assume_utxo(),
 			},
 			Network::Testnet => ConsensusParams {
 				network: network,
+				genesis_block: network.genesis_block(),
+				pow_limit: network.max_bits(),
+				pow_target_spacing: DEFAULT_TARGET_SPACING_SECONDS,
+				pow_target_timespan: DEFAULT_TARGET_TIMESPAN_SECONDS,
+				pow_allow_min_difficulty_blocks: true,
+				pow_no_retargeting: false,
+				address_prefixes: network.address_prefixes(),
 				bip16_time: 1333238400,	// Apr 1 2012
 				bip34_height: 21111,	// 0000000023b3a96d3484e5abb3755c413e7d41500f8e2a5c3f0dd01299cd8ef8
 				bip65_height: 581885,	// 00000000007f6655f22f98e72ed80d8b06dc761d5da09df0fa1dc4be4f861eb6
@@ -113,9 +167,18 @@ impl ConsensusParams {
 					timeout: 1493596800,
 					activation: Some(770112),
 				}),
+				signet_challenge: None,
+				assume_utxo: network.assume_utxo(),
 			},
 			Network::Regtest | Network::Unitest => ConsensusParams {
 				network: network,
+				genesis_block: network.genesis_block(),
+				pow_limit: network.max_bits(),
+				pow_target_spacing: DEFAULT_TARGET_SPACING_SECONDS,
+				pow_target_timespan: DEFAULT_TARGET_TIMESPAN_SECONDS,
+				pow_allow_min_difficulty_blocks: false,
+				pow_no_retargeting: false,
+				address_prefixes: network.address_prefixes(),
 				bip16_time: 1333238400,	// Apr 1 2012
 				bip34_height: 100000000,	// not activated on regtest
 				bip65_height: 1351,
@@ -140,12 +203,73 @@ impl ConsensusParams {
 					timeout: 0,
 					activation: Some(0),
 				}),
+				signet_challenge: None,
+				assume_utxo: network.assume_utxo(),
+			},
+			Network::Signet => ConsensusParams {
+				network: network,
+				genesis_block: network.genesis_block(),
+				pow_limit: network.max_bits(),
+				pow_target_spacing: DEFAULT_TARGET_SPACING_SECONDS,
+				pow_target_timespan: DEFAULT_TARGET_TIMESPAN_SECONDS,
+				pow_allow_min_difficulty_blocks: false,
+				pow_no_retargeting: false,
+				address_prefixes: network.address_prefixes(),
+				bip16_time: 1333238400,	// Apr 1 2012
+				bip34_height: 1,
+				bip65_height: 1,
+				bip66_height: 1,
+				segwit_deployment: match fork {
+					ConsensusFork::BitcoinCore => Some(Deployment {
+						name: "segwit",
+						bit: 1,
+						start_time: 0,
+						timeout: 0,
+						activation: Some(1),
+					}),
+					ConsensusFork::BitcoinCash(_) => None,
+				},
+				fork: fork,
+				rule_change_activation_threshold: 1815, // 90%
+				miner_confirmation_window: 2016,
+				csv_deployment: Some(Deployment {
+					name: "csv",
+					bit: 0,
+					start_time: 0,
+					timeout: 0,
+					activation: Some(1),
+				}),
+				signet_challenge: Some(default_signet_challenge()),
+				assume_utxo: network.assume_utxo(),
 			},
 		}
 	}
 
+	/// Replace challenge of the signet network. Custom challenge also changes network magic.
+	pub fn with_signet_challenge(mut self, challenge: Bytes) -> Self {
+		self.signet_challenge = Some(challenge);
+		self
+	}
+
+	/// Number of blocks between difficulty adjustments.
+	pub fn retargeting_interval(&self) -> u32 {
+		self.pow_target_timespan / self.pow_target_spacing
+	}
+
+	pub fn is_retarget_height(&self, height: u32) -> bool {
+		height % self.retargeting_interval() == 0
+	}
+
 	pub fn magic(&self) -> Magic {
-		self.network.magic(&self.fork)
+		match (self.network, self.signet_challenge.as_ref()) {
+			(Network::Signet, Some(challenge)) => signet_magic(challenge),
+			_ => self.network.magic(&self.fork),
+		}
+	}
+
+	/// All versionbits deployments, known for this network
+	pub fn deployments(&self) -> Vec<Deployment> {
+		self.csv_deployment.iter().chain(self.segwit_deployment.iter()).cloned().collect()
 	}
 
 	pub fn is_bip30_exception(&self, hash: &H256, height: u32) -> bool {
@@ -240,7 +364,7 @@ impl BitcoinCashConsensusParams {
 				difficulty_adjustion_height: 1188697,
 				monolith_time: 1526400000,
 			},
-			Network::Regtest | Network::Unitest => BitcoinCashConsensusParams {
+			Network::Regtest | Network::Unitest | Network::Signet => BitcoinCashConsensusParams {
 				height: 0,
 				difficulty_adjustion_height: 0,
 				monolith_time: 1526400000,
@@ -289,6 +413,23 @@ mod tests {
 		assert_eq!(ConsensusParams::new(Network::Regtest, ConsensusFork::BitcoinCore).miner_confirmation_window, 144);
 	}
 
+	#[test]
+	fn test_consensus_retargeting_interval() {
+		assert_eq!(ConsensusParams::new(Network::Mainnet, ConsensusFork::BitcoinCore).retargeting_interval(), 2016);
+		assert_eq!(ConsensusParams::new(Network::Regtest, ConsensusFork::BitcoinCore).retargeting_interval(), 2016);
+		assert!(ConsensusParams::new(Network::Testnet, ConsensusFork::BitcoinCore).pow_allow_min_difficulty_blocks);
+		assert!(!ConsensusParams::new(Network::Mainnet, ConsensusFork::BitcoinCore).pow_allow_min_difficulty_blocks);
+	}
+
+	#[test]
+	fn test_consensus_signet_magic() {
+		let consensus = ConsensusParams::new(Network::Signet, ConsensusFork::BitcoinCore);
+		assert_eq!(consensus.magic(), Network::Signet.magic(&ConsensusFork::BitcoinCore));
+		let consensus = consensus.with_signet_challenge("51".into());
+		assert!(consensus.magic() != Network::Signet.magic(&ConsensusFork::BitcoinCore));
+		assert_eq!(ConsensusParams::new(Network::Mainnet, ConsensusFork::BitcoinCore).signet_challenge, None);
+	}
+
 	#[test]
 	fn test_consensus_fork_min_block_size() {
 		assert_eq!(ConsensusFork::BitcoinCore.min_block_size(0), 0);
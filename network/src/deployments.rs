@@ -1,5 +1,7 @@
+/// What bits to set in version for versionbits blocks
+pub const VERSIONBITS_TOP_BITS: u32 = 0x20000000;
+/// What bitmask determines whether versionbits is in use
 const VERSIONBITS_TOP_MASK: u32 = 0xe0000000;
-const VERSIONBITS_TOP_BITS: u32 = 0x20000000;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Deployment {
@@ -16,8 +18,13 @@ pub struct Deployment {
 }
 
 impl Deployment {
+	/// Version bit, used to signal for this deployment
+	pub fn mask(&self) -> u32 {
+		1 << self.bit
+	}
+
 	pub fn matches(&self, version: u32) -> bool {
-		(version & VERSIONBITS_TOP_MASK) == VERSIONBITS_TOP_BITS && (version & (1 << self.bit)) != 0
+		(version & VERSIONBITS_TOP_MASK) == VERSIONBITS_TOP_BITS && (version & self.mask()) != 0
 	}
 }
 
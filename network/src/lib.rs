@@ -1,15 +1,24 @@
 #[macro_use]
 extern crate lazy_static;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate toml;
 
+extern crate bitcrypto as crypto;
 extern crate chain;
 extern crate primitives;
+extern crate serialization as ser;
 
+mod chain_spec;
 mod consensus;
 mod deployments;
 mod network;
 
 pub use primitives::{hash, compact};
 
-pub use consensus::{ConsensusParams, ConsensusFork, BitcoinCashConsensusParams};
-pub use deployments::Deployment;
-pub use network::{Magic, Network};
+pub use chain_spec::{ChainSpec, RetargetSpec, DeploymentsSpec, DeploymentSpec, AssumeUtxoSpec};
+pub use consensus::{ConsensusParams, ConsensusFork, BitcoinCashConsensusParams, AssumeUtxo};
+pub use deployments::{Deployment, VERSIONBITS_TOP_BITS};
+pub use network::{Magic, Network, AddressPrefixes, default_signet_challenge, signet_magic};
@@ -3,14 +3,18 @@
 
 use compact::Compact;
 use chain::Block;
+use crypto::dhash256;
+use primitives::bytes::Bytes;
 use primitives::hash::H256;
 use primitives::U256;
-use {ConsensusFork};
+use ser::serialize;
+use {ConsensusFork, AssumeUtxo};
 
 const MAGIC_MAINNET: u32 = 0xD9B4BEF9;
 const MAGIC_TESTNET: u32 = 0x0709110B;
 const MAGIC_REGTEST: u32 = 0xDAB5BFFA;
 const MAGIC_UNITEST: u32 = 0x00000000;
+const MAGIC_SIGNET: u32 = 0x40CF030A;
 
 const BITCOIN_CASH_MAGIC_MAINNET: u32 = 0xE8F3E1E3;
 const BITCOIN_CASH_MAGIC_TESTNET: u32 = 0xF4F3E5F4;
@@ -23,8 +27,13 @@ lazy_static! {
 		.expect("hardcoded value should parse without errors");
 	static ref MAX_BITS_REGTEST: U256 = "7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff".parse()
 		.expect("hardcoded value should parse without errors");
+	static ref MAX_BITS_SIGNET: U256 = "00000377ae000000000000000000000000000000000000000000000000000000".parse()
+		.expect("hardcoded value should parse without errors");
 }
 
+/// Challenge of the default (global) signet: 1-of-2 multisig
+const DEFAULT_SIGNET_CHALLENGE: &'static str = "512103ad5e0edad18cb1f0fc0d28a3d4f1f3e445640337489abb10404f2d1e086be430210359ef5021964fe22d6f8e05b2463c9540ce96883fe3b278760f048f5189f2e6c452ae";
+
 /// Network magic type.
 pub type Magic = u32;
 
@@ -39,10 +48,22 @@ pub enum Network {
 	Regtest,
 	/// Testnet for unittests, proof of work difficulty is almost 0
 	Unitest,
+	/// Signet network (BIP325), where blocks must be signed by the challenge script.
+	Signet,
 	/// Any other network. By default behaves like bitcoin mainnet.
 	Other(u32),
 }
 
+/// Base58 prefixes of addresses and private keys
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressPrefixes {
+	/// Mainnet prefixes: 0 (p2pkh), 5 (p2sh), 128 (private key)
+	Mainnet,
+	/// Testnet prefixes: 111 (p2pkh), 196 (p2sh), 239 (private key)
+	Testnet,
+}
+
 impl Network {
 	pub fn magic(&self, fork: &ConsensusFork) -> Magic {
 		match (fork, *self) {
@@ -53,6 +74,7 @@ impl Network {
 			(_, Network::Testnet) => MAGIC_TESTNET,
 			(_, Network::Regtest) => MAGIC_REGTEST,
 			(_, Network::Unitest) => MAGIC_UNITEST,
+			(_, Network::Signet) => MAGIC_SIGNET,
 			(_, Network::Other(value)) => value,
 		}
 	}
@@ -63,6 +85,7 @@ impl Network {
 			Network::Testnet => MAX_BITS_TESTNET.clone(),
 			Network::Regtest => MAX_BITS_REGTEST.clone(),
 			Network::Unitest => Compact::max_value().into(),
+			Network::Signet => MAX_BITS_SIGNET.clone(),
 		}
 	}
 
@@ -71,6 +94,7 @@ impl Network {
 			Network::Mainnet | Network::Other(_)  => 8333,
 			Network::Testnet => 18333,
 			Network::Regtest | Network::Unitest => 18444,
+			Network::Signet => 38333,
 		}
 	}
 
@@ -79,6 +103,7 @@ impl Network {
 			Network::Mainnet | Network::Other(_) => 8332,
 			Network::Testnet => 18332,
 			Network::Regtest | Network::Unitest => 18443,
+			Network::Signet => 38332,
 		}
 	}
 
@@ -87,6 +112,15 @@ impl Network {
 			Network::Mainnet | Network::Other(_) => "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c0101000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000".into(),
 			Network::Testnet => "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4adae5494dffff001d1aa4ae180101000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000".into(),
 			Network::Regtest | Network::Unitest => "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4adae5494dffff7f20020000000101000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000".into(),
+			Network::Signet => "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a008f4d5fae77031e8ad222030101000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000".into(),
+		}
+	}
+
+	/// There are only mainnet and testnet address prefixes => all other networks are using testnet prefixes
+	pub fn address_prefixes(&self) -> AddressPrefixes {
+		match *self {
+			Network::Mainnet => AddressPrefixes::Mainnet,
+			Network::Testnet | Network::Regtest | Network::Unitest | Network::Signet | Network::Other(_) => AddressPrefixes::Testnet,
 		}
 	}
 
@@ -97,15 +131,34 @@ impl Network {
 			_ => self.genesis_block().hash(),
 		}
 	}
+
+	/// UTXO set snapshots, committed to by the network. Content hash is specific to the snapshot
+	/// format of this implementation, so there are no snapshots for public networks until one is published.
+	pub fn assume_utxo(&self) -> Vec<AssumeUtxo> {
+		Vec::new()
+	}
+}
+
+/// Challenge script of the default signet
+pub fn default_signet_challenge() -> Bytes {
+	DEFAULT_SIGNET_CHALLENGE.into()
+}
+
+/// Signet magic is derived from the challenge script: first 4 bytes of the hash of the serialized challenge
+pub fn signet_magic(challenge: &Bytes) -> Magic {
+	let hash = dhash256(&serialize(challenge));
+	(hash[0] as u32) | (hash[1] as u32) << 8 | (hash[2] as u32) << 16 | (hash[3] as u32) << 24
 }
 
 #[cfg(test)]
 mod tests {
 	use compact::Compact;
-	use {ConsensusFork};
+	use {ConsensusFork, AssumeUtxo};
+	use primitives::hash::H256;
 	use super::{
-		Network, MAGIC_MAINNET, MAGIC_TESTNET, MAGIC_REGTEST, MAGIC_UNITEST,
-		MAX_BITS_MAINNET, MAX_BITS_TESTNET, MAX_BITS_REGTEST,
+		Network, MAGIC_MAINNET, MAGIC_TESTNET, MAGIC_REGTEST, MAGIC_UNITEST, MAGIC_SIGNET,
+		MAX_BITS_MAINNET, MAX_BITS_TESTNET, MAX_BITS_REGTEST, MAX_BITS_SIGNET,
+		default_signet_challenge, signet_magic,
 	};
 
 	#[test]
@@ -114,6 +167,7 @@ mod tests {
 		assert_eq!(MAGIC_TESTNET, Network::Testnet.magic(&ConsensusFork::BitcoinCore));
 		assert_eq!(MAGIC_REGTEST, Network::Regtest.magic(&ConsensusFork::BitcoinCore));
 		assert_eq!(MAGIC_UNITEST, Network::Unitest.magic(&ConsensusFork::BitcoinCore));
+		assert_eq!(MAGIC_SIGNET, Network::Signet.magic(&ConsensusFork::BitcoinCore));
 	}
 
 	#[test]
@@ -122,6 +176,7 @@ mod tests {
 		assert_eq!(Network::Testnet.max_bits(), *MAX_BITS_TESTNET);
 		assert_eq!(Network::Regtest.max_bits(), *MAX_BITS_REGTEST);
 		assert_eq!(Network::Unitest.max_bits(), Compact::max_value().into());
+		assert_eq!(Network::Signet.max_bits(), *MAX_BITS_SIGNET);
 	}
 
 	#[test]
@@ -130,6 +185,7 @@ mod tests {
 		assert_eq!(Network::Testnet.port(), 18333);
 		assert_eq!(Network::Regtest.port(), 18444);
 		assert_eq!(Network::Unitest.port(), 18444);
+		assert_eq!(Network::Signet.port(), 38333);
 	}
 
 	#[test]
@@ -138,5 +194,17 @@ mod tests {
 		assert_eq!(Network::Testnet.rpc_port(), 18332);
 		assert_eq!(Network::Regtest.rpc_port(), 18443);
 		assert_eq!(Network::Unitest.rpc_port(), 18443);
+		assert_eq!(Network::Signet.rpc_port(), 38332);
+	}
+
+	#[test]
+	fn test_signet_magic() {
+		assert_eq!(signet_magic(&default_signet_challenge()), MAGIC_SIGNET);
+		assert!(signet_magic(&"51".into()) != MAGIC_SIGNET);
+	}
+
+	#[test]
+	fn test_signet_genesis_block() {
+		assert_eq!(Network::Signet.genesis_block().hash(), H256::from_reversed_str("00000008819873e925422c1ff0f99f7cc9bbb232af63a077a480a3633bee1ef6"));
 	}
 }
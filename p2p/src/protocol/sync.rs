@@ -35,6 +35,8 @@ pub trait InboundSyncConnection : Send + Sync {
 	fn on_get_block_txn(&self, message: types::GetBlockTxn);
 	fn on_block_txn(&self, message: types::BlockTxn);
 	fn on_notfound(&self, message: types::NotFound);
+	fn on_get_pkg_txns(&self, message: types::GetPkgTxns);
+	fn on_pkg_txns(&self, message: types::PkgTxns);
 }
 
 pub trait OutboundSyncConnection : Send + Sync {
@@ -60,6 +62,8 @@ pub trait OutboundSyncConnection : Send + Sync {
 	fn send_get_block_txn(&self, message: &types::GetBlockTxn);
 	fn send_block_txn(&self, message: &types::BlockTxn);
 	fn send_notfound(&self, message: &types::NotFound);
+	fn send_get_pkg_txns(&self, message: &types::GetPkgTxns);
+	fn send_pkg_txns(&self, message: &types::PkgTxns);
 	fn ignored(&self, id: u32);
 	fn close(&self);
 }
@@ -165,6 +169,14 @@ impl OutboundSyncConnection for OutboundSync {
 		self.context.send_request(message);
 	}
 
+	fn send_get_pkg_txns(&self, message: &types::GetPkgTxns) {
+		self.context.send_request(message);
+	}
+
+	fn send_pkg_txns(&self, message: &types::PkgTxns) {
+		self.context.send_request(message);
+	}
+
 	fn ignored(&self, id: u32) {
 		self.context.ignore_response(id);
 	}
@@ -280,6 +292,14 @@ impl Protocol for SyncProtocol {
 			let message: types::NotFound = try!(deserialize_payload(payload, version));
 			self.inbound_connection.on_notfound(message);
 		}
+		else if command == &types::GetPkgTxns::command() {
+			let message: types::GetPkgTxns = try!(deserialize_payload(payload, version));
+			self.inbound_connection.on_get_pkg_txns(message);
+		}
+		else if command == &types::PkgTxns::command() {
+			let message: types::PkgTxns = try!(deserialize_payload(payload, version));
+			self.inbound_connection.on_pkg_txns(message);
+		}
 		Ok(())
 	}
 
@@ -22,9 +22,13 @@ extern crate import;
 extern crate rpc as ethcore_rpc;
 extern crate primitives;
 extern crate verification;
+extern crate yaml_rust;
 
 mod commands;
 mod config;
+// configuration file format is shared with chainx_btc
+#[path = "../chainx_btc/config_file.rs"]
+mod config_file;
 mod seednodes;
 mod util;
 mod rpc;
@@ -51,6 +55,7 @@ fn main() {
 fn run() -> Result<(), String> {
 	let yaml = load_yaml!("cli.yml");
 	let matches = clap::App::from_yaml(yaml).get_matches();
+	let matches = try!(config_file::merge(yaml, matches));
 	let cfg = try!(config::parse(&matches));
 
 	if !cfg.quiet {
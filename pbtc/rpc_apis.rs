@@ -13,6 +13,8 @@ pub enum Api {
 	BlockChain,
 	/// Network
 	Network,
+	/// Memory pool-related methods
+	MemoryPool,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -22,7 +24,7 @@ pub enum ApiSet {
 
 impl Default for ApiSet {
 	fn default() -> Self {
-		ApiSet::List(vec![Api::Raw, Api::Miner, Api::BlockChain, Api::Network].into_iter().collect())
+		ApiSet::List(vec![Api::Raw, Api::Miner, Api::BlockChain, Api::Network, Api::MemoryPool].into_iter().collect())
 	}
 }
 
@@ -35,6 +37,7 @@ impl FromStr for Api {
 			"miner" => Ok(Api::Miner),
 			"blockchain" => Ok(Api::BlockChain),
 			"network" => Ok(Api::Network),
+			"mempool" => Ok(Api::MemoryPool),
 			api => Err(format!("Unknown api: {}", api)),
 		}
 	}
@@ -57,6 +60,7 @@ pub fn setup_rpc(mut handler: MetaIoHandler<()>, apis: ApiSet, deps: Dependencie
 			Api::Miner => handler.extend_with(MinerClient::new(MinerClientCore::new(deps.local_sync_node.clone())).to_delegate()),
 			Api::BlockChain => handler.extend_with(BlockChainClient::new(BlockChainClientCore::new(deps.network, deps.storage.clone())).to_delegate()),
 			Api::Network => handler.extend_with(NetworkClient::new(NetworkClientCore::new(deps.p2p_context.clone())).to_delegate()),
+			Api::MemoryPool => handler.extend_with(MemoryPoolClient::new(MemoryPoolClientCore::new(deps.local_sync_node.clone())).to_delegate()),
 		}
 	}
 
@@ -0,0 +1,638 @@
+//! JSON-RPC authentication.
+//!
+//! Requests are authenticated with HTTP Basic auth, using either credentials from the cookie
+//! file, or credentials of `rpcauth` users (salted HMAC-SHA256 of the password).
+//! Users may be restricted to whitelisted methods.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::str;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use rand::{OsRng, Rng};
+use hex::{ToHex, FromHex};
+use jsonrpc_core::{Metadata, Middleware, FutureResponse, Request, Response, Call, Output, Failure, Version, Error};
+use jsonrpc_core::futures::{future, Future};
+use jsonrpc_core::futures::future::Either;
+use jsonrpc_http_server::{hyper, RequestMiddleware, RequestMiddlewareAction, MetaExtractor};
+use jsonrpc_ws_server::{self, ws, MiddlewareAction};
+use tokio::timer::Delay;
+use crypto::sha256;
+use v1::helpers::errors::method_not_allowed;
+
+/// Name of the user, authenticated with the cookie file
+pub const COOKIE_USER: &'static str = "__cookie__";
+/// Delay of the HTTP response to the request with invalid credentials (deters brute-forcing, as in Bitcoin Core).
+/// Response is delayed by the timer => event loop threads are never blocked, and users with
+/// valid credentials are never locked out by failures of other clients.
+const AUTH_FAILURE_DELAY_MS: u64 = 250;
+/// Number of consecutive failed WebSocket handshakes of the `rpcauth` user, after which the user is locked out.
+/// WebSocket handshakes are processed synchronously, so they can't be delayed without blocking the event loop.
+const WS_MAX_AUTH_FAILURES: u32 = 5;
+/// Duration of the WebSocket lockout of the user
+const WS_AUTH_LOCKOUT_SECS: u64 = 60;
+/// Block size of SHA256
+const SHA256_BLOCK_SIZE: usize = 64;
+
+/// Metadata of the JSON-RPC request
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RpcMetadata {
+	/// Name of the authenticated user
+	pub user: Option<String>,
+}
+
+impl Metadata for RpcMetadata {}
+
+/// User, authenticated with salted HMAC-SHA256 of the password (`USER:SALT$HASH`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcAuthUser {
+	pub name: String,
+	pub salt: String,
+	pub hash: Vec<u8>,
+}
+
+impl RpcAuthUser {
+	pub fn verify(&self, password: &str) -> bool {
+		constant_time_eq(&hmac_sha256(self.salt.as_bytes(), password.as_bytes()), &self.hash)
+	}
+}
+
+impl FromStr for RpcAuthUser {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut name_and_rest = s.splitn(2, ':');
+		let name = name_and_rest.next().unwrap_or_default();
+		let mut salt_and_hash = name_and_rest.next().unwrap_or_default().splitn(2, '$');
+		let salt = salt_and_hash.next().unwrap_or_default();
+		let hash = salt_and_hash.next().unwrap_or_default();
+		if name.is_empty() || salt.is_empty() || name == COOKIE_USER {
+			return Err(format!("Invalid rpcauth user: {}", s));
+		}
+
+		let hash: Vec<u8> = hash.from_hex().map_err(|_| format!("Invalid rpcauth hash: {}", s))?;
+		if hash.len() != 32 {
+			return Err(format!("Invalid rpcauth hash: {}", s));
+		}
+
+		Ok(RpcAuthUser {
+			name: name.to_owned(),
+			salt: salt.to_owned(),
+			hash: hash,
+		})
+	}
+}
+
+/// JSON-RPC authentication settings
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RpcAuthConfig {
+	/// Path of the cookie file. New cookie is generated on every start.
+	pub cookie_path: Option<PathBuf>,
+	/// `rpcauth` users
+	pub users: Vec<RpcAuthUser>,
+	/// Methods (or API names), allowed for the user. Users without whitelist may call any method.
+	pub whitelists: HashMap<String, HashSet<String>>,
+}
+
+/// Generate new random cookie and write it to the file.
+/// Returns password of the cookie user.
+pub fn write_cookie(path: &Path) -> Result<String, String> {
+	let mut rng = OsRng::new().map_err(|err| format!("Failed to generate cookie: {}", err))?;
+	let mut password = [0u8; 32];
+	rng.fill_bytes(&mut password);
+	let password: String = password.to_hex();
+
+	// write to temporary file first => cookie file is never left half-written
+	let tmp_path = path.with_extension("tmp");
+	create_private_file(&tmp_path)
+		.and_then(|mut file| {
+			write!(file, "{}:{}", COOKIE_USER, password)?;
+			file.sync_all()
+		})
+		.and_then(|_| fs::rename(&tmp_path, path))
+		.map_err(|err| format!("Failed to write cookie file {}: {}", path.display(), err))?;
+
+	Ok(password)
+}
+
+/// Create (or truncate) file, which is only readable by the owner
+fn create_private_file(path: &Path) -> ::std::io::Result<fs::File> {
+	let mut options = OpenOptions::new();
+	options.write(true).create(true).truncate(true);
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::OpenOptionsExt;
+		options.mode(0o600);
+	}
+	let file = options.open(path)?;
+	// mode is only applied to new files
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::PermissionsExt;
+		file.set_permissions(fs::Permissions::from_mode(0o600))?;
+	}
+	Ok(file)
+}
+
+/// Reason of authentication failure
+#[derive(Debug, PartialEq)]
+pub enum AuthError {
+	/// Credentials are missing or invalid
+	Unauthorized,
+	/// User is not allowed to call methods of the server
+	Forbidden,
+}
+
+/// HTTP request (and WebSocket handshake) middleware, rejecting requests with invalid credentials.
+#[derive(Clone)]
+pub struct RpcAuth {
+	cookie_password: Option<String>,
+	users: Vec<RpcAuthUser>,
+	failure_delay: Duration,
+}
+
+impl RpcAuth {
+	/// Creates authenticator. Writes new cookie file if `cookie_path` is set.
+	pub fn new(config: &RpcAuthConfig) -> Result<Self, String> {
+		let cookie_password = match config.cookie_path {
+			Some(ref cookie_path) => Some(write_cookie(cookie_path)?),
+			None => None,
+		};
+
+		Ok(RpcAuth {
+			cookie_password: cookie_password,
+			users: config.users.clone(),
+			failure_delay: Duration::from_millis(AUTH_FAILURE_DELAY_MS),
+		})
+	}
+
+	/// Authenticate request with given `Authorization` header value.
+	/// Returns name of the authenticated user.
+	pub fn authenticate(&self, authorization: Option<&str>) -> Result<String, AuthError> {
+		match authorization.and_then(basic_credentials) {
+			Some((ref user, ref password)) if self.verify(user, password) => Ok(user.clone()),
+			_ => Err(AuthError::Unauthorized),
+		}
+	}
+
+	fn is_rpcauth_user(&self, user: &str) -> bool {
+		self.users.iter().any(|rpc_user| rpc_user.name == user)
+	}
+
+	fn verify(&self, user: &str, password: &str) -> bool {
+		if user == COOKIE_USER {
+			return self.cookie_password.as_ref()
+				.map(|cookie_password| constant_time_eq(cookie_password.as_bytes(), password.as_bytes()))
+				.unwrap_or(false);
+		}
+
+		self.users.iter().any(|rpc_user| rpc_user.name == user && rpc_user.verify(password))
+	}
+}
+
+impl RequestMiddleware for RpcAuth {
+	fn on_request(&self, request: hyper::Request<hyper::Body>) -> RequestMiddlewareAction {
+		let result = {
+			let authorization = request.headers().get(hyper::header::AUTHORIZATION)
+				.and_then(|value| value.to_str().ok());
+			self.authenticate(authorization)
+		};
+
+		let status = match result {
+			Ok(_) => return request.into(),
+			Err(AuthError::Unauthorized) => hyper::StatusCode::UNAUTHORIZED,
+			Err(AuthError::Forbidden) => hyper::StatusCode::FORBIDDEN,
+		};
+
+		let response = hyper::Response::builder()
+			.status(status)
+			.header(hyper::header::WWW_AUTHENTICATE, "Basic realm=\"jsonrpc\"")
+			.body(hyper::Body::empty())
+			.expect("status and header are valid; qed");
+
+		// the rejection is sent when the timer fires => the event loop is free to serve other requests
+		let delayed_response = Delay::new(Instant::now() + self.failure_delay)
+			.then(move |result| {
+				if let Err(err) = result {
+					warn!(target: "rpc", "Failed to delay rejection of unauthorized request: {}", err);
+				}
+				Ok::<_, hyper::Error>(response)
+			});
+
+		RequestMiddlewareAction::Respond {
+			should_validate_hosts: true,
+			response: Box::new(delayed_response),
+		}
+	}
+}
+
+/// Consecutive failed authentications of the user
+struct AuthFailures {
+	count: u32,
+	last: Instant,
+}
+
+/// WebSocket handshake middleware, rejecting connections with invalid credentials and connections
+/// of users, which are not allowed to call methods of the WebSocket server.
+///
+/// After `WS_MAX_AUTH_FAILURES` consecutive failures, `rpcauth` user is locked out for `WS_AUTH_LOCKOUT_SECS`.
+/// Only configured `rpcauth` users are tracked: the cookie password is random, and unknown users can't
+/// be authenticated anyway.
+pub struct WsAuth {
+	auth: RpcAuth,
+	whitelist: MethodWhitelist,
+	methods: Vec<String>,
+	failures: Mutex<HashMap<String, AuthFailures>>,
+	lockout: Duration,
+}
+
+impl WsAuth {
+	/// Creates middleware. Authenticated user must be allowed (by the `whitelist`) to call all `methods`.
+	pub fn new(auth: RpcAuth, whitelist: MethodWhitelist, methods: Vec<String>) -> Self {
+		WsAuth {
+			auth: auth,
+			whitelist: whitelist,
+			methods: methods,
+			failures: Mutex::new(HashMap::new()),
+			lockout: Duration::from_secs(WS_AUTH_LOCKOUT_SECS),
+		}
+	}
+
+	/// Authenticate handshake with given `Authorization` header value and check that the user may call
+	/// methods of the server. Returns name of the authenticated user.
+	pub fn authorize(&self, authorization: Option<&str>) -> Result<String, AuthError> {
+		let user = self.authenticate(authorization)?;
+		if self.methods.iter().all(|method| self.whitelist.is_method_allowed(&user, method)) {
+			Ok(user)
+		} else {
+			Err(AuthError::Forbidden)
+		}
+	}
+
+	fn authenticate(&self, authorization: Option<&str>) -> Result<String, AuthError> {
+		let user = match authorization.and_then(basic_credentials) {
+			Some((ref user, _)) if self.auth.is_rpcauth_user(user) => user.clone(),
+			_ => return self.auth.authenticate(authorization),
+		};
+
+		let mut failures = self.failures.lock().expect("auth failures lock is never poisoned; qed");
+		let now = Instant::now();
+		let is_locked_out = failures.get(&user)
+			.map(|failures| failures.count >= WS_MAX_AUTH_FAILURES && now < failures.last + self.lockout)
+			.unwrap_or(false);
+		if is_locked_out {
+			return Err(AuthError::Unauthorized);
+		}
+
+		match self.auth.authenticate(authorization) {
+			Ok(user) => {
+				failures.remove(&user);
+				Ok(user)
+			},
+			Err(err) => {
+				let lockout = self.lockout;
+				let user_failures = failures.entry(user).or_insert(AuthFailures { count: 0, last: now });
+				// lockout is over => start counting again
+				if now >= user_failures.last + lockout {
+					user_failures.count = 0;
+				}
+				user_failures.count += 1;
+				user_failures.last = now;
+				Err(err)
+			},
+		}
+	}
+}
+
+impl jsonrpc_ws_server::RequestMiddleware for WsAuth {
+	fn process(&self, request: &ws::Request) -> MiddlewareAction {
+		let authorization = request.header("authorization")
+			.and_then(|value| str::from_utf8(value).ok());
+
+		let (status, reason) = match self.authorize(authorization) {
+			Ok(_) => return MiddlewareAction::Proceed,
+			Err(AuthError::Unauthorized) => (401, "Unauthorized"),
+			Err(AuthError::Forbidden) => (403, "Forbidden"),
+		};
+
+		let mut response = ws::Response::new(status, reason, Vec::new());
+		response.headers_mut().push(("WWW-Authenticate".into(), b"Basic realm=\"jsonrpc\"".to_vec()));
+		MiddlewareAction::Respond {
+			response: response,
+			validate_origin: false,
+			validate_hosts: true,
+		}
+	}
+}
+
+/// Reads name of the user, authenticated by `RpcAuth`
+#[derive(Default)]
+pub struct RpcMetaExtractor;
+
+impl MetaExtractor<RpcMetadata> for RpcMetaExtractor {
+	fn read_metadata(&self, request: &hyper::Request<hyper::Body>) -> RpcMetadata {
+		RpcMetadata {
+			user: request.headers().get(hyper::header::AUTHORIZATION)
+				.and_then(|value| value.to_str().ok())
+				.and_then(basic_credentials)
+				.map(|(user, _)| user),
+		}
+	}
+}
+
+/// JSON-RPC middleware, rejecting calls of methods, which are not whitelisted for the user.
+/// If any call of the batch is rejected, the whole batch is rejected.
+#[derive(Default)]
+pub struct MethodWhitelist {
+	whitelists: HashMap<String, HashSet<String>>,
+}
+
+impl MethodWhitelist {
+	/// Creates whitelist middleware. API names in whitelists are replaced with methods of the API.
+	pub fn new(whitelists: &HashMap<String, HashSet<String>>, api_methods: &HashMap<String, Vec<String>>) -> Self {
+		let whitelists = whitelists.iter()
+			.map(|(user, whitelist)| {
+				let methods = whitelist.iter()
+					.flat_map(|entry| match api_methods.get(entry) {
+						Some(methods) => methods.clone(),
+						None => vec![entry.clone()],
+					})
+					.collect();
+				(user.clone(), methods)
+			})
+			.collect();
+
+		MethodWhitelist {
+			whitelists: whitelists,
+		}
+	}
+
+	/// Returns true if the user may call the method
+	pub fn is_method_allowed(&self, user: &str, method: &str) -> bool {
+		match self.whitelists.get(user) {
+			Some(whitelist) => whitelist.contains(method),
+			None => true,
+		}
+	}
+
+	fn is_allowed(&self, meta: &RpcMetadata, call: &Call) -> bool {
+		let method = match *call {
+			Call::MethodCall(ref call) => &call.method,
+			Call::Notification(ref notification) => &notification.method,
+			Call::Invalid(_) => return true,
+		};
+
+		meta.user.as_ref().map(|user| self.is_method_allowed(user, method)).unwrap_or(true)
+	}
+}
+
+impl Middleware<RpcMetadata> for MethodWhitelist {
+	type Future = FutureResponse;
+
+	fn on_request<F, X>(&self, request: Request, meta: RpcMetadata, next: F) -> Either<Self::Future, X> where
+		F: FnOnce(Request, RpcMetadata) -> X + Send,
+		X: Future<Item = Option<Response>, Error = ()> + Send + 'static,
+	{
+		let is_allowed = match request {
+			Request::Single(ref call) => self.is_allowed(&meta, call),
+			Request::Batch(ref calls) => calls.iter().all(|call| self.is_allowed(&meta, call)),
+		};
+		if is_allowed {
+			return Either::B(next(request, meta));
+		}
+
+		let response = match request {
+			Request::Single(call) => rejected_call_output(call).map(Response::Single),
+			Request::Batch(calls) => Some(Response::Batch(calls.into_iter().filter_map(rejected_call_output).collect())),
+		};
+		Either::A(Box::new(future::ok(response)))
+	}
+}
+
+fn rejected_call_output(call: Call) -> Option<Output> {
+	match call {
+		Call::MethodCall(call) => Some(Output::Failure(Failure {
+			jsonrpc: call.jsonrpc,
+			error: method_not_allowed(&call.method),
+			id: call.id,
+		})),
+		Call::Notification(_) => None,
+		Call::Invalid(id) => Some(Output::Failure(Failure {
+			jsonrpc: Some(Version::V2),
+			error: Error::invalid_request(),
+			id: id,
+		})),
+	}
+}
+
+/// Parse `Basic` authorization header value into user name and password
+fn basic_credentials(authorization: &str) -> Option<(String, String)> {
+	if !authorization.starts_with("Basic ") {
+		return None;
+	}
+
+	let credentials = base64_decode(authorization["Basic ".len()..].trim())?;
+	let credentials = String::from_utf8(credentials).ok()?;
+	let mut parts = credentials.splitn(2, ':');
+	match (parts.next(), parts.next()) {
+		(Some(user), Some(password)) => Some((user.to_owned(), password.to_owned())),
+		_ => None,
+	}
+}
+
+fn base64_decode(input: &str) -> Option<Vec<u8>> {
+	let input = input.trim_right_matches('=').as_bytes();
+	let mut output = Vec::with_capacity(input.len() * 3 / 4);
+	let mut buffer = 0u32;
+	let mut bits = 0;
+	for c in input {
+		let value = match *c {
+			b'A'...b'Z' => c - b'A',
+			b'a'...b'z' => c - b'a' + 26,
+			b'0'...b'9' => c - b'0' + 52,
+			b'+' => 62,
+			b'/' => 63,
+			_ => return None,
+		};
+		buffer = (buffer << 6) | value as u32;
+		bits += 6;
+		if bits >= 8 {
+			bits -= 8;
+			output.push((buffer >> bits) as u8);
+		}
+	}
+	Some(output)
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+	let mut block_key = [0u8; SHA256_BLOCK_SIZE];
+	if key.len() > SHA256_BLOCK_SIZE {
+		block_key[..32].copy_from_slice(&*sha256(key));
+	} else {
+		block_key[..key.len()].copy_from_slice(key);
+	}
+
+	let mut inner: Vec<u8> = block_key.iter().map(|b| b ^ 0x36).collect();
+	inner.extend_from_slice(message);
+	let mut outer: Vec<u8> = block_key.iter().map(|b| b ^ 0x5c).collect();
+	outer.extend_from_slice(&*sha256(&inner));
+	sha256(&outer).to_vec()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+	a.len() == b.len() && a.iter().zip(b.iter()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::{HashMap, HashSet};
+	use std::{env, fs, process, thread};
+	use std::time::{Duration, Instant};
+	use hex::ToHex;
+	use jsonrpc_core::{MetaIoHandler, Compatibility, Value};
+	use jsonrpc_http_server::{hyper, RequestMiddleware, RequestMiddlewareAction};
+	use tokio::runtime::current_thread::Runtime;
+	use super::{RpcAuth, WsAuth, RpcAuthConfig, RpcAuthUser, RpcMetadata, MethodWhitelist, AuthError, COOKIE_USER,
+		AUTH_FAILURE_DELAY_MS, WS_MAX_AUTH_FAILURES, write_cookie, hmac_sha256, base64_decode};
+
+	const ALICE: &'static str = "alice:cb77f0957de88ff388cf817ddbc7273$c9ce7cb2de2ad5aadae1449ad1e62baa38d98fced30a7cd2eae656cab574b678";
+
+	fn auth() -> RpcAuth {
+		RpcAuth::new(&RpcAuthConfig {
+			users: vec![ALICE.parse().unwrap()],
+			..Default::default()
+		}).unwrap()
+	}
+
+	#[test]
+	fn test_hmac_sha256() {
+		// RFC 4231 test cases 2 and 6
+		assert_eq!(hmac_sha256(b"Jefe", b"what do ya want for nothing?").to_hex::<String>(),
+			"5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+		assert_eq!(hmac_sha256(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First").to_hex::<String>(),
+			"60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54");
+	}
+
+	#[test]
+	fn test_base64_decode() {
+		assert_eq!(base64_decode("YWxpY2U6c2VjcmV0"), Some(b"alice:secret".to_vec()));
+		assert_eq!(base64_decode("YWI="), Some(b"ab".to_vec()));
+		assert_eq!(base64_decode("YW!="), None);
+	}
+
+	#[test]
+	fn test_rpc_auth_user() {
+		let user: RpcAuthUser = ALICE.parse().unwrap();
+		assert_eq!(user.name, "alice");
+		assert!(user.verify("secret"));
+		assert!(!user.verify("wrong"));
+		assert!("alice:salt$00".parse::<RpcAuthUser>().is_err());
+		assert!("alice".parse::<RpcAuthUser>().is_err());
+	}
+
+	#[test]
+	fn test_rpc_auth_authenticate() {
+		let auth = auth();
+		assert_eq!(auth.authenticate(Some("Basic YWxpY2U6c2VjcmV0")), Ok("alice".into()));
+		assert_eq!(auth.authenticate(Some("Basic YWxpY2U6d3Jvbmc=")), Err(AuthError::Unauthorized));
+		assert_eq!(auth.authenticate(None), Err(AuthError::Unauthorized));
+		// cookie is not configured
+		assert_eq!(auth.authenticate(Some("Basic X19jb29raWVfXzo=")), Err(AuthError::Unauthorized));
+	}
+
+	#[test]
+	fn test_rpc_auth_failure_delay() {
+		let auth = auth();
+		let request = |authorization: &str| hyper::Request::builder()
+			.header(hyper::header::AUTHORIZATION, authorization)
+			.body(hyper::Body::empty())
+			.unwrap();
+
+		let start = Instant::now();
+		let response = match auth.on_request(request("Basic YWxpY2U6d3Jvbmc=")) {
+			RequestMiddlewareAction::Respond { response, .. } => response,
+			RequestMiddlewareAction::Proceed { .. } => panic!("request with invalid credentials must be rejected"),
+		};
+		// the rejection is delayed by the timer, not by blocking the caller
+		assert!(start.elapsed() < Duration::from_millis(AUTH_FAILURE_DELAY_MS));
+		let response = Runtime::new().unwrap().block_on(response).unwrap();
+		assert!(start.elapsed() >= Duration::from_millis(AUTH_FAILURE_DELAY_MS));
+		assert_eq!(response.status(), hyper::StatusCode::UNAUTHORIZED);
+
+		// failures of other clients do not lock out users with valid credentials
+		for _ in 0..4 {
+			assert_eq!(auth.authenticate(None), Err(AuthError::Unauthorized));
+		}
+		match auth.on_request(request("Basic YWxpY2U6c2VjcmV0")) {
+			RequestMiddlewareAction::Proceed { .. } => (),
+			RequestMiddlewareAction::Respond { .. } => panic!("request with valid credentials must proceed"),
+		}
+	}
+
+	#[test]
+	fn test_ws_auth_lockout() {
+		let mut ws_auth = WsAuth::new(auth(), MethodWhitelist::default(), vec!["subscribe".into()]);
+		ws_auth.lockout = Duration::from_millis(100);
+
+		for _ in 0..WS_MAX_AUTH_FAILURES {
+			assert_eq!(ws_auth.authorize(Some("Basic YWxpY2U6d3Jvbmc=")), Err(AuthError::Unauthorized));
+		}
+		// alice is locked out, even with valid credentials
+		assert_eq!(ws_auth.authorize(Some("Basic YWxpY2U6c2VjcmV0")), Err(AuthError::Unauthorized));
+
+		thread::sleep(Duration::from_millis(150));
+		assert_eq!(ws_auth.authorize(Some("Basic YWxpY2U6c2VjcmV0")), Ok("alice".into()));
+
+		// successful authentication resets the failures counter
+		for _ in 0..WS_MAX_AUTH_FAILURES - 1 {
+			assert_eq!(ws_auth.authorize(Some("Basic YWxpY2U6d3Jvbmc=")), Err(AuthError::Unauthorized));
+		}
+		assert_eq!(ws_auth.authorize(Some("Basic YWxpY2U6c2VjcmV0")), Ok("alice".into()));
+	}
+
+	#[test]
+	fn test_write_cookie() {
+		let dir = env::temp_dir().join(format!("rpc-cookie-{}", process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		let path = dir.join(".cookie");
+		let first = write_cookie(&path).unwrap();
+		let second = write_cookie(&path).unwrap();
+		assert!(first != second);
+		assert_eq!(fs::read_to_string(&path).unwrap(), format!("{}:{}", COOKIE_USER, second));
+		assert!(!path.with_extension("tmp").exists());
+
+		#[cfg(unix)]
+		{
+			use std::os::unix::fs::PermissionsExt;
+			assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+		}
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn test_method_whitelist() {
+		let mut whitelists = HashMap::new();
+		whitelists.insert("alice".to_owned(), vec!["blockchain".to_owned()].into_iter().collect::<HashSet<_>>());
+		let mut api_methods = HashMap::new();
+		api_methods.insert("blockchain".to_owned(), vec!["getbestblockhash".to_owned()]);
+
+		let mut handler = MetaIoHandler::new(Compatibility::Both, MethodWhitelist::new(&whitelists, &api_methods));
+		handler.add_method("getbestblockhash", |_| Ok(Value::Bool(true)));
+		handler.add_method("addnode", |_| Ok(Value::Bool(true)));
+
+		let alice = RpcMetadata { user: Some("alice".into()) };
+		let bob = RpcMetadata { user: Some("bob".into()) };
+		let request = |method: &str| format!(r#"{{"jsonrpc": "2.0", "method": "{}", "params": [], "id": 1}}"#, method);
+
+		assert_eq!(handler.handle_request_sync(&request("getbestblockhash"), alice.clone()),
+			Some(r#"{"jsonrpc":"2.0","result":true,"id":1}"#.to_owned()));
+		assert_eq!(handler.handle_request_sync(&request("addnode"), alice),
+			Some(r#"{"jsonrpc":"2.0","error":{"code":-32160,"message":"Method is not allowed","data":"addnode"},"id":1}"#.to_owned()));
+		assert_eq!(handler.handle_request_sync(&request("addnode"), bob),
+			Some(r#"{"jsonrpc":"2.0","result":true,"id":1}"#.to_owned()));
+	}
+}
@@ -11,10 +11,15 @@ extern crate jsonrpc_core;
 #[macro_use]
 extern crate jsonrpc_macros;
 extern crate jsonrpc_http_server;
+extern crate jsonrpc_pubsub;
+extern crate jsonrpc_ws_server;
+extern crate tokio;
 extern crate tokio_core;
+extern crate rand;
 extern crate sync;
 extern crate chain;
 extern crate serialization as ser;
+extern crate bitcrypto as crypto;
 extern crate primitives;
 extern crate p2p;
 extern crate network;
@@ -28,9 +33,13 @@ extern crate db;
 
 pub mod v1;
 pub mod rpc_server;
+pub mod auth;
 
-pub use jsonrpc_core::{MetaIoHandler, Compatibility, Error};
+pub use jsonrpc_core::{MetaIoHandler, Compatibility, Error, RemoteProcedure};
 pub use tokio_core::reactor::{Remote};
 
 pub use jsonrpc_http_server::Server;
-pub use rpc_server::start_http;
+pub use jsonrpc_ws_server::Server as WsServer;
+pub use jsonrpc_pubsub::{PubSubHandler, Session};
+pub use rpc_server::{start_http, start_ws};
+pub use auth::{RpcAuth, WsAuth, RpcAuthConfig, RpcAuthUser, RpcMetadata, MethodWhitelist, AuthError, COOKIE_USER};
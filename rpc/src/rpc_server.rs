@@ -1,15 +1,21 @@
 // TODO: panic handler
 use std::io;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use jsonrpc_core;
 use jsonrpc_http_server::{self, ServerBuilder, Server, Host};
+use jsonrpc_pubsub::{PubSubHandler, Session};
+use jsonrpc_ws_server::{self, RequestContext};
+use auth::{RpcAuth, WsAuth, RpcMetadata, RpcMetaExtractor};
 
 /// Start http server asynchronously and returns result with `Server` handle on success or an error.
-pub fn start_http<M: jsonrpc_core::Metadata + Default, S: jsonrpc_core::Middleware<M>>(
+/// Requests, which are not authenticated by `auth`, are rejected.
+pub fn start_http<S: jsonrpc_core::Middleware<RpcMetadata>>(
 	addr: &SocketAddr,
 	cors_domains: Option<Vec<String>>,
 	allowed_hosts: Option<Vec<String>>,
-	handler: jsonrpc_core::MetaIoHandler<M, S>,
+	auth: RpcAuth,
+	handler: jsonrpc_core::MetaIoHandler<RpcMetadata, S>,
 	) -> Result<Server, io::Error> {
 
 	let cors_domains = cors_domains.map(|domains| {
@@ -22,8 +28,24 @@ pub fn start_http<M: jsonrpc_core::Metadata + Default, S: jsonrpc_core::Middlewa
 			.collect()
 	});
 
-	ServerBuilder::new(handler)
+	ServerBuilder::with_meta_extractor(handler, RpcMetaExtractor)
+		.request_middleware(auth)
 		.cors(cors_domains.into())
 		.allowed_hosts(allowed_hosts.map(|hosts| hosts.into_iter().map(Host::from).collect()).into())
 		.start_http(addr)
 }
+
+/// Start WebSocket server asynchronously and returns result with `Server` handle on success or an error.
+/// Connections, which are not authorized by `auth`, are rejected.
+pub fn start_ws<S: jsonrpc_core::Middleware<Arc<Session>>>(
+	addr: &SocketAddr,
+	allowed_hosts: Option<Vec<String>>,
+	auth: WsAuth,
+	handler: PubSubHandler<Arc<Session>, S>,
+	) -> Result<jsonrpc_ws_server::Server, jsonrpc_ws_server::Error> {
+
+	jsonrpc_ws_server::ServerBuilder::with_meta_extractor(handler, |context: &RequestContext| Arc::new(Session::new(context.sender())))
+		.request_middleware(auth)
+		.allowed_hosts(allowed_hosts.map(|hosts| hosts.into_iter().map(jsonrpc_ws_server::Host::from).collect()).into())
+		.start(addr)
+}
@@ -0,0 +1,142 @@
+///! Minimal output script descriptors (BIP380) support
+
+use hex::FromHex;
+use global_script::{Builder as ScriptBuilder, Opcode};
+use keys::{self, Address, Public};
+use primitives::bytes::Bytes;
+
+/// Characters, allowed in descriptors, ordered by checksum symbol groups
+const INPUT_CHARSET: &'static str = "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+/// Characters of the descriptor checksum
+const CHECKSUM_CHARSET: &'static [u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+/// Length of the descriptor checksum
+const CHECKSUM_LEN: usize = 8;
+
+/// Compute output script of given address
+pub fn address_script_pubkey(address: &Address) -> Bytes {
+	match address.kind {
+		keys::Type::P2PKH => ScriptBuilder::build_p2pkh(&address.hash).into(),
+		keys::Type::P2SH => ScriptBuilder::build_p2sh(&address.hash).into(),
+	}
+}
+
+/// Compute output script of given descriptor.
+/// Supported descriptors are `addr(ADDRESS)`, `raw(HEX)`, `pk(KEY)` and `pkh(KEY)`,
+/// optionally followed by the `#CHECKSUM`, which is verified if present.
+pub fn descriptor_script_pubkey(descriptor: &str, network: keys::Network) -> Result<Bytes, String> {
+	let descriptor = match descriptor.find('#') {
+		Some(checksum_position) => {
+			let (descriptor, checksum) = (&descriptor[..checksum_position], &descriptor[checksum_position + 1..]);
+			let expected_checksum = try!(descriptor_checksum(descriptor));
+			if checksum != expected_checksum {
+				return Err(format!("Provided checksum '{}' does not match computed checksum '{}'", checksum, expected_checksum));
+			}
+			descriptor
+		},
+		None => descriptor,
+	};
+
+	let (function, argument) = match (descriptor.find('('), descriptor.ends_with(')')) {
+		(Some(open_position), true) => (&descriptor[..open_position], &descriptor[open_position + 1..descriptor.len() - 1]),
+		_ => return Err(format!("'{}' is not a valid descriptor", descriptor)),
+	};
+
+	match function {
+		"addr" => {
+			let address: Address = try!(argument.parse().map_err(|_| format!("Address '{}' is not valid", argument)));
+			if address.network != network {
+				return Err(format!("Address '{}' is not valid for current network", argument));
+			}
+			Ok(address_script_pubkey(&address))
+		},
+		"raw" => argument.from_hex::<Vec<u8>>()
+			.map(Into::into)
+			.map_err(|_| format!("Raw script '{}' is not hex", argument)),
+		"pk" => parse_public_key(argument)
+			.map(|public| ScriptBuilder::default()
+				.push_data(&*public)
+				.push_opcode(Opcode::OP_CHECKSIG)
+				.into_bytes()),
+		"pkh" => parse_public_key(argument)
+			.map(|public| ScriptBuilder::build_p2pkh(&public.address_hash()).into()),
+		_ => Err(format!("'{}' descriptor is not supported", function)),
+	}
+}
+
+/// Parse hex-encoded public key
+fn parse_public_key(key: &str) -> Result<Public, String> {
+	key.from_hex::<Vec<u8>>()
+		.ok()
+		.and_then(|key_data| Public::from_slice(&key_data).ok())
+		.ok_or_else(|| format!("Public key '{}' is not valid", key))
+}
+
+/// Compute BIP380 descriptor checksum
+fn descriptor_checksum(descriptor: &str) -> Result<String, String> {
+	fn polymod(c: u64, value: u64) -> u64 {
+		let c0 = c >> 35;
+		let mut c = ((c & 0x7ffffffff) << 5) ^ value;
+		if c0 & 1 != 0 { c ^= 0xf5dee51989; }
+		if c0 & 2 != 0 { c ^= 0xa9fdca3312; }
+		if c0 & 4 != 0 { c ^= 0x1bab10e32d; }
+		if c0 & 8 != 0 { c ^= 0x3706b1677a; }
+		if c0 & 16 != 0 { c ^= 0x644d626ffd; }
+		c
+	}
+
+	let mut c = 1u64;
+	let mut class = 0u64;
+	let mut class_count = 0;
+	for ch in descriptor.chars() {
+		let position = try!(INPUT_CHARSET.find(ch).ok_or_else(|| format!("Invalid character '{}' in descriptor", ch))) as u64;
+		c = polymod(c, position & 31);
+		class = class * 3 + (position >> 5);
+		class_count += 1;
+		if class_count == 3 {
+			c = polymod(c, class);
+			class = 0;
+			class_count = 0;
+		}
+	}
+	if class_count > 0 {
+		c = polymod(c, class);
+	}
+	for _ in 0..CHECKSUM_LEN {
+		c = polymod(c, 0);
+	}
+	c ^= 1;
+
+	Ok((0..CHECKSUM_LEN)
+		.map(|i| CHECKSUM_CHARSET[((c >> (5 * (CHECKSUM_LEN - 1 - i))) & 31) as usize] as char)
+		.collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use keys::Network;
+	use super::{descriptor_script_pubkey, descriptor_checksum};
+
+	#[test]
+	fn descriptor_checksum_matches_bip380() {
+		assert_eq!(descriptor_checksum("raw(deadbeef)").unwrap(), "89f8spxm");
+		assert_eq!(descriptor_checksum("addr(mkmZxiEcEd8ZqjQWVZuC6so5dFMKEFpN2j)").unwrap(), "02wpgw69");
+	}
+
+	#[test]
+	fn descriptor_script_pubkey_parsed() {
+		assert_eq!(descriptor_script_pubkey("raw(deadbeef)", Network::Testnet).unwrap(), "deadbeef".into());
+		assert_eq!(descriptor_script_pubkey("raw(deadbeef)#89f8spxm", Network::Testnet).unwrap(), "deadbeef".into());
+		assert_eq!(descriptor_script_pubkey("addr(mkmZxiEcEd8ZqjQWVZuC6so5dFMKEFpN2j)", Network::Testnet).unwrap(),
+			"76a914399c39ac90dac26965fb55fdb2035e6715fdac4e88ac".into());
+		assert_eq!(descriptor_script_pubkey("pk(0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798)", Network::Testnet).unwrap(),
+			"210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ac".into());
+	}
+
+	#[test]
+	fn descriptor_script_pubkey_rejected() {
+		assert!(descriptor_script_pubkey("raw(deadbeef)#00000000", Network::Testnet).is_err());
+		assert!(descriptor_script_pubkey("raw(deadbeef", Network::Testnet).is_err());
+		assert!(descriptor_script_pubkey("wpkh(0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798)", Network::Testnet).is_err());
+		assert!(descriptor_script_pubkey("addr(mkmZxiEcEd8ZqjQWVZuC6so5dFMKEFpN2j)", Network::Mainnet).is_err());
+	}
+}
@@ -4,12 +4,14 @@ mod codes {
 	// NOTE [ToDr] Codes from [-32099, -32000]
 	pub const UNKNOWN: i64 = -32000;
 	pub const EXECUTION_ERROR: i64 = -32015;
+	pub const SPENT_INFO_NOT_FOUND: i64 = -32095;
 	pub const TRANSACTION_NOT_FOUND: i64 = -32096;
 	pub const TRANSACTION_OUTPUT_NOT_FOUND: i64 = -32097;
 	pub const TRANSACTION_OF_SIDE_BRANCH: i64 = -32098;
 	pub const BLOCK_NOT_FOUND: i64 = -32099;
 	pub const NODE_ALREADY_ADDED: i64 = -32150;
 	pub const NODE_NOT_ADDED: i64 = -32151;
+	pub const METHOD_NOT_ALLOWED: i64 = -32160;
 }
 
 use std::fmt;
@@ -75,6 +77,14 @@ pub fn transaction_output_not_found<T: fmt::Debug>(data: T) -> Error {
 	}
 }
 
+pub fn spent_info_not_found<T: fmt::Debug>(data: T) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::SPENT_INFO_NOT_FOUND),
+		message: "Unable to get spent info".into(),
+		data: Some(Value::String(format!("{:?}", data))),
+	}
+}
+
 pub fn transaction_of_side_branch<T: fmt::Debug>(data: T) -> Error {
 	Error {
 		code: ErrorCode::ServerError(codes::TRANSACTION_OF_SIDE_BRANCH),
@@ -99,6 +109,14 @@ pub fn node_not_added() -> Error {
 	}
 }
 
+pub fn method_not_allowed(method: &str) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::METHOD_NOT_ALLOWED),
+		message: "Method is not allowed".into(),
+		data: Some(Value::String(method.into())),
+	}
+}
+
 pub fn unknown() -> Error {
 	Error {
 		code: ErrorCode::ServerError(codes::UNKNOWN),
@@ -2,16 +2,25 @@ use v1::traits::BlockChain;
 use v1::types::{GetBlockResponse, VerboseBlock, RawBlock};
 use v1::types::{GetTxOutResponse, TransactionOutputScript};
 use v1::types::GetTxOutSetInfoResponse;
+use v1::types::{BlockchainInfo, Softfork, SoftforkType, Bip9Softfork};
+use v1::types::{DumpTxOutSetResponse, ChainStates, ChainState};
+use v1::types::{ChainTip, ChainTipStatus};
+use v1::types::{VerifyChainResponse, VerifyChainIssue};
 use v1::types::H256;
 use v1::types::U256;
-use v1::helpers::errors::{block_not_found, block_at_height_not_found, transaction_not_found,
+use v1::helpers::errors::{block_not_found, block_at_height_not_found, transaction_not_found, execution,
                           invalid_params, transaction_output_not_found, transaction_of_side_branch};
 use jsonrpc_macros::Trailing;
 use jsonrpc_core::Error;
-use {storage, chain};
+use {storage, chain, sync};
 use chain::{ OutPoint, TransactionOutput };
-use verification;
-use network::Network;
+use std::fs;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use verification::{self, Deployments, ThresholdState, deployment_statistics, DatabaseVerifier,
+                   DEFAULT_CHECK_LEVEL, DEFAULT_CHECK_BLOCKS};
+use network::{Network, ConsensusParams, Deployment, AddressPrefixes};
 use primitives::hash::H256 as GlobalH256;
 use chain::Transaction as GlobalTransaction;
 use keys::{self, Address, KeyPair, Private};
@@ -32,21 +41,128 @@ pub trait BlockChainClientCoreApi: Send + Sync + 'static {
     fn verbose_block(&self, hash: GlobalH256) -> Option<VerboseBlock>;
     fn verbose_transaction_out(&self, prev_out: OutPoint) -> Result<GetTxOutResponse, Error>;
     fn transaction_out(&self, prev_out: OutPoint) -> Option<TransactionOutput>;
+    fn blockchain_info(&self) -> BlockchainInfo;
+    fn prune_blockchain(&self, height: u32) -> Result<Option<u32>, Error>;
+    fn dump_tx_out_set(&self, path: PathBuf) -> Result<DumpTxOutSetResponse, Error>;
+    fn chain_states(&self) -> ChainStates;
+    fn chain_tips(&self) -> Vec<ChainTip>;
+    fn invalidate_block(&self, hash: GlobalH256) -> Result<(), Error>;
+    fn reconsider_block(&self, hash: GlobalH256) -> Result<(), Error>;
+    fn verify_chain(&self, check_level: u32, blocks: u32) -> VerifyChainResponse;
 }
 
 pub struct BlockChainClientCore {
     network: Network,
+    consensus: ConsensusParams,
     storage: storage::SharedStore,
+    deployments: Deployments,
+    database_path: Option<PathBuf>,
+    background_storage: Option<storage::SharedStore>,
+    local_sync_node: Option<sync::LocalNodeRef>,
 }
 
 impl BlockChainClientCore {
-    pub fn new(network: Network, storage: storage::SharedStore) -> Self {
+    pub fn new(consensus: ConsensusParams, storage: storage::SharedStore, database_path: Option<PathBuf>) -> Self {
 
         BlockChainClientCore {
-            network: network,
+            network: consensus.network,
+            consensus: consensus,
             storage: storage,
+            deployments: Deployments::new(),
+            database_path: database_path,
+            background_storage: None,
+            local_sync_node: None,
         }
     }
+
+    /// Report state of the background chainstate, which validates UTXO set snapshot of the `storage`
+    pub fn with_background_storage(mut self, background_storage: storage::SharedStore) -> Self {
+        self.background_storage = Some(background_storage);
+        self
+    }
+
+    /// Switch best branch through the synchronization node, so that its in-memory state is updated.
+    /// Without the node, best branch is switched directly in the `storage`
+    pub fn with_local_sync_node(mut self, local_sync_node: sync::LocalNodeRef) -> Self {
+        self.local_sync_node = Some(local_sync_node);
+        self
+    }
+
+    /// Resolve relative path against the data directory (parent of the database directory)
+    fn data_dir_path(&self, path: PathBuf) -> PathBuf {
+        match self.database_path.as_ref().and_then(|database_path| database_path.parent()) {
+            Some(data_dir) if path.is_relative() => data_dir.join(path),
+            _ => path,
+        }
+    }
+
+    /// Status of the deployment for the block with given number
+    fn softfork(&self, deployment: Deployment, number: u32) -> Softfork {
+        if let Some(height) = deployment.activation {
+            return Softfork {
+                softfork_type: SoftforkType::Buried,
+                bip9: None,
+                height: Some(height),
+                active: height <= number,
+            };
+        }
+
+        let headers = self.storage.as_block_header_provider();
+        let state = self.deployments.threshold_state(deployment, number, headers, &self.consensus);
+        let statistics = match state {
+            ThresholdState::Started => Some(deployment_statistics(deployment, number, headers, &self.consensus).into()),
+            _ => None,
+        };
+
+        Softfork {
+            softfork_type: SoftforkType::Bip9,
+            bip9: Some(Bip9Softfork {
+                status: state.into(),
+                bit: deployment.bit,
+                start_time: deployment.start_time,
+                timeout: deployment.timeout,
+                statistics: statistics,
+            }),
+            height: None,
+            active: state.is_active(),
+        }
+    }
+}
+
+/// Network name, as reported by getblockchaininfo
+fn chain_name(network: Network) -> &'static str {
+    match network {
+        Network::Mainnet => "main",
+        Network::Testnet => "test",
+        Network::Regtest => "regtest",
+        Network::Unitest => "unitest",
+        Network::Signet => "signet",
+        Network::Other(_) => "other",
+    }
+}
+
+/// Estimate verification progress by comparing time of the best block with the current time
+fn verification_progress(genesis_time: u32, best_block_time: u32, now: u32) -> f64 {
+    if best_block_time >= now || now <= genesis_time {
+        return 1.0;
+    }
+
+    let progress = best_block_time.saturating_sub(genesis_time) as f64 / (now - genesis_time) as f64;
+    progress.min(1.0)
+}
+
+/// Total size of all files in the directory
+fn directory_size(path: &Path) -> u64 {
+    fs::read_dir(path)
+        .map(|entries| entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| match entry.metadata() {
+                Ok(ref metadata) if metadata.is_dir() => directory_size(&entry.path()),
+                Ok(metadata) => metadata.len(),
+                Err(_) => 0,
+            })
+            .sum())
+        .unwrap_or_default()
 }
 
 impl BlockChainClientCoreApi for BlockChainClientCore {
@@ -70,6 +186,163 @@ impl BlockChainClientCoreApi for BlockChainClientCore {
         self.storage.transaction_output(&prev_out, usize::max_value())
     }
 
+    fn blockchain_info(&self) -> BlockchainInfo {
+        let best_block = self.storage.best_block();
+        let best_block_header = self.storage
+            .block_header(best_block.number.into())
+            .expect("best block is in db; qed");
+        let headers = self.storage.as_block_header_provider();
+        let genesis_time = self.consensus.genesis_block.block_header.time;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as u32).unwrap_or_default();
+
+        // softforks status is reported for the next block
+        let softforks = self.consensus.deployments().into_iter()
+            .map(|deployment| (deployment.name.to_owned(), self.softfork(deployment, best_block.number + 1)))
+            .collect();
+
+        BlockchainInfo {
+            chain: chain_name(self.network).into(),
+            blocks: best_block.number,
+            headers: best_block.number,
+            bestblockhash: best_block.hash.clone().into(),
+            difficulty: self.storage.difficulty(),
+            mediantime: verification::median_timestamp_inclusive(best_block.hash, headers),
+            verificationprogress: verification_progress(genesis_time, best_block_header.time, now),
+            size_on_disk: self.database_path.as_ref().map(|path| directory_size(path)).unwrap_or_default(),
+            pruned: self.storage.prune_mode().is_enabled(),
+            pruneheight: if self.storage.prune_mode().is_enabled() {
+                Some(self.storage.pruned_height().map(|height| height + 1).unwrap_or(0))
+            } else {
+                None
+            },
+            softforks: softforks,
+        }
+    }
+
+    fn prune_blockchain(&self, height: u32) -> Result<Option<u32>, Error> {
+        self.storage.prune(height).map_err(execution)
+    }
+
+    fn dump_tx_out_set(&self, path: PathBuf) -> Result<DumpTxOutSetResponse, Error> {
+        let path = self.data_dir_path(path);
+        if path.exists() {
+            return Err(invalid_params("path", "File already exists"));
+        }
+
+        // snapshot is written to the temporary file, so that incomplete snapshot is never left at the path
+        let tmp_path = path.with_extension("incomplete");
+        let info = fs::File::create(&tmp_path)
+            .map_err(|err| storage::Error::DatabaseError(err.to_string()))
+            .and_then(|file| self.storage.dump_snapshot(&mut BufWriter::new(file)))
+            .and_then(|info| fs::rename(&tmp_path, &path)
+                .map(|_| info)
+                .map_err(|err| storage::Error::DatabaseError(err.to_string())));
+        let info = match info {
+            Ok(info) => info,
+            Err(err) => {
+                let _ = fs::remove_file(&tmp_path);
+                return Err(execution(err));
+            },
+        };
+
+        Ok(DumpTxOutSetResponse {
+            coins_written: info.coins_count,
+            base_hash: info.base.block_hash.into(),
+            base_height: info.base.block_number,
+            path: path.display().to_string(),
+            txoutset_hash: info.base.content_hash.into(),
+        })
+    }
+
+    fn chain_states(&self) -> ChainStates {
+        let validation = self.storage.snapshot_validation();
+        let mut chainstates = Vec::new();
+        if let (Some(background), Some(storage::SnapshotValidation::Pending)) = (self.background_storage.as_ref(), validation) {
+            let best_block = background.best_block();
+            chainstates.push(ChainState {
+                blocks: best_block.number,
+                bestblockhash: best_block.hash.into(),
+                validated: true,
+                snapshot_blockhash: None,
+            });
+        }
+
+        let best_block = self.storage.best_block();
+        chainstates.push(ChainState {
+            blocks: best_block.number,
+            bestblockhash: best_block.hash.into(),
+            validated: match validation {
+                None | Some(storage::SnapshotValidation::Valid) => true,
+                Some(storage::SnapshotValidation::Pending) | Some(storage::SnapshotValidation::Invalid) => false,
+            },
+            snapshot_blockhash: self.storage.snapshot_base().map(|base| base.block_hash.into()),
+        });
+
+        ChainStates {
+            headers: best_block.number,
+            chainstates: chainstates,
+        }
+    }
+
+    fn chain_tips(&self) -> Vec<ChainTip> {
+        self.storage.chain_tips()
+            .into_iter()
+            .map(|tip| ChainTip {
+                height: tip.number,
+                hash: tip.hash.into(),
+                branchlen: tip.branch_len,
+                status: match tip.status {
+                    storage::ChainTipStatus::Active => ChainTipStatus::Active,
+                    storage::ChainTipStatus::ValidFork => ChainTipStatus::ValidFork,
+                    storage::ChainTipStatus::HeadersOnly => ChainTipStatus::HeadersOnly,
+                    storage::ChainTipStatus::Invalid => ChainTipStatus::Invalid,
+                },
+            })
+            .collect()
+    }
+
+    fn invalidate_block(&self, hash: GlobalH256) -> Result<(), Error> {
+        if !self.storage.contains_block(hash.clone().into()) {
+            return Err(block_not_found(hash.reversed()));
+        }
+
+        match self.local_sync_node {
+            Some(ref local_sync_node) => local_sync_node.invalidate_block(&hash).map_err(execution),
+            None => self.storage.invalidate_block(&hash).map(|_| ()).map_err(execution),
+        }
+    }
+
+    fn reconsider_block(&self, hash: GlobalH256) -> Result<(), Error> {
+        // invalid tips of blocks, which have failed verification, are not stored
+        if !self.storage.contains_block(hash.clone().into()) && !self.storage.chain_tips().iter().any(|tip| tip.hash == hash) {
+            return Err(block_not_found(hash.reversed()));
+        }
+
+        match self.local_sync_node {
+            Some(ref local_sync_node) => local_sync_node.reconsider_block(&hash).map_err(execution),
+            None => self.storage.reconsider_block(&hash).map(|_| ()).map_err(execution),
+        }
+    }
+
+    fn verify_chain(&self, check_level: u32, blocks: u32) -> VerifyChainResponse {
+        let verifier = DatabaseVerifier::new(self.storage.clone(), self.consensus.clone());
+        let report = verifier.verify(check_level, blocks);
+        VerifyChainResponse {
+            valid: report.is_ok(),
+            checklevel: report.check_level,
+            firstblock: report.first_block,
+            lastblock: report.last_block,
+            errors: report.issues
+                .into_iter()
+                .map(|issue| VerifyChainIssue {
+                    height: issue.block_number,
+                    hash: self.storage.block_hash(issue.block_number).map(Into::into),
+                    error: issue.error.to_string(),
+                })
+                .collect(),
+        }
+    }
+
     fn raw_block(&self, hash: GlobalH256) -> Option<RawBlock> {
         self.storage.block(hash.into()).map(|block| {
             serialize(&block).into()
@@ -166,11 +439,9 @@ impl BlockChainClientCoreApi for BlockChainClientCore {
                     .into_iter()
                     .map(|a| {
                         Address {
-                            network: match self.network {
-                                Network::Mainnet => keys::Network::Mainnet,
-                                // there's no correct choices for Regtests && Other networks
-                                // => let's just make Testnet key
-                                _ => keys::Network::Testnet,
+                            network: match self.consensus.address_prefixes {
+                                AddressPrefixes::Mainnet => keys::Network::Mainnet,
+                                AddressPrefixes::Testnet => keys::Network::Testnet,
                             },
                             hash: a.hash,
                             kind: a.kind,
@@ -229,6 +500,12 @@ where
         Ok(transaction.into()) 
     }
 
+    fn blockchain_info(&self) -> Result<BlockchainInfo, Error> {
+        let mut info = self.core.blockchain_info();
+        info.bestblockhash = info.bestblockhash.reversed();
+        Ok(info)
+    }
+
     fn best_block_hash(&self) -> Result<H256, Error> {
         Ok(self.core.best_block_hash().reversed().into())
     }
@@ -292,6 +569,59 @@ where
     fn transaction_out_set_info(&self) -> Result<GetTxOutSetInfoResponse, Error> {
         rpc_unimplemented!()
     }
+
+    fn prune_blockchain(&self, height: u32) -> Result<Option<u32>, Error> {
+        self.core.prune_blockchain(height)
+    }
+
+    fn dump_tx_out_set(&self, path: String) -> Result<DumpTxOutSetResponse, Error> {
+        self.core.dump_tx_out_set(path.into())
+            .map(|mut response| {
+                response.base_hash = response.base_hash.reversed();
+                response.txoutset_hash = response.txoutset_hash.reversed();
+                response
+            })
+    }
+
+    fn chain_states(&self) -> Result<ChainStates, Error> {
+        let mut states = self.core.chain_states();
+        for state in &mut states.chainstates {
+            state.bestblockhash = state.bestblockhash.reversed();
+            state.snapshot_blockhash = state.snapshot_blockhash.take().map(|hash| hash.reversed());
+        }
+        Ok(states)
+    }
+
+    fn chain_tips(&self) -> Result<Vec<ChainTip>, Error> {
+        Ok(self.core.chain_tips()
+            .into_iter()
+            .map(|mut tip| {
+                tip.hash = tip.hash.reversed();
+                tip
+            })
+            .collect())
+    }
+
+    fn invalidate_block(&self, hash: H256) -> Result<(), Error> {
+        let global_hash: GlobalH256 = hash.into();
+        self.core.invalidate_block(global_hash.reversed())
+    }
+
+    fn reconsider_block(&self, hash: H256) -> Result<(), Error> {
+        let global_hash: GlobalH256 = hash.into();
+        self.core.reconsider_block(global_hash.reversed())
+    }
+
+    fn verify_chain(&self, check_level: Trailing<u32>, blocks: Trailing<u32>) -> Result<VerifyChainResponse, Error> {
+        let mut response = self.core.verify_chain(
+            check_level.unwrap_or(DEFAULT_CHECK_LEVEL),
+            blocks.unwrap_or(DEFAULT_CHECK_BLOCKS),
+        );
+        for issue in &mut response.errors {
+            issue.hash = issue.hash.take().map(|hash| hash.reversed());
+        }
+        Ok(response)
+    }
 }
 
 #[cfg(test)]
@@ -299,6 +629,7 @@ pub mod tests {
     extern crate test_data;
 
     use std::sync::Arc;
+    use std::collections::BTreeMap;
     use jsonrpc_core::IoHandler;
     use jsonrpc_core::Error;
     use db::BlockChainDatabase;
@@ -312,7 +643,8 @@ pub mod tests {
     use v1::types::H256;
     use v1::types::ScriptType;
     use chain::OutPoint;
-    use network::Network;
+    use network::{Network, ConsensusParams, ConsensusFork};
+    use v1::types::Bip9SoftforkStatus;
     use super::*;
 
     #[derive(Default)]
@@ -400,6 +732,87 @@ pub mod tests {
                 script_pubkey:"000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f".into(),
             })
         }
+
+        fn blockchain_info(&self) -> BlockchainInfo {
+            let mut softforks = BTreeMap::new();
+            softforks.insert("csv".to_owned(), Softfork {
+                softfork_type: SoftforkType::Buried,
+                bip9: None,
+                height: Some(419328),
+                active: true,
+            });
+            BlockchainInfo {
+                chain: "main".into(),
+                blocks: 1,
+                headers: 1,
+                bestblockhash: test_data::genesis().hash().into(),
+                difficulty: 1.0,
+                mediantime: 1231006505,
+                verificationprogress: 0.5,
+                size_on_disk: 1024,
+                pruned: false,
+                pruneheight: None,
+                softforks: softforks,
+            }
+        }
+
+        fn prune_blockchain(&self, height: u32) -> Result<Option<u32>, Error> {
+            Ok(Some(height))
+        }
+
+        fn dump_tx_out_set(&self, path: PathBuf) -> Result<DumpTxOutSetResponse, Error> {
+            Ok(DumpTxOutSetResponse {
+                coins_written: 1,
+                base_hash: test_data::genesis().hash().into(),
+                base_height: 0,
+                path: path.display().to_string(),
+                txoutset_hash: GlobalH256::from(1).into(),
+            })
+        }
+
+        fn chain_states(&self) -> ChainStates {
+            ChainStates {
+                headers: 0,
+                chainstates: vec![ChainState {
+                    blocks: 0,
+                    bestblockhash: test_data::genesis().hash().into(),
+                    validated: true,
+                    snapshot_blockhash: None,
+                }],
+            }
+        }
+
+        fn chain_tips(&self) -> Vec<ChainTip> {
+            vec![ChainTip {
+                height: 0,
+                hash: test_data::genesis().hash().into(),
+                branchlen: 0,
+                status: ChainTipStatus::Active,
+            }, ChainTip {
+                height: 2,
+                hash: GlobalH256::from(1).into(),
+                branchlen: 2,
+                status: ChainTipStatus::HeadersOnly,
+            }]
+        }
+
+        fn invalidate_block(&self, _hash: GlobalH256) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn reconsider_block(&self, _hash: GlobalH256) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn verify_chain(&self, check_level: u32, _blocks: u32) -> VerifyChainResponse {
+            VerifyChainResponse {
+                valid: true,
+                checklevel: check_level,
+                firstblock: 0,
+                lastblock: 0,
+                errors: vec![],
+            }
+        }
     }
 
     impl BlockChainClientCoreApi for ErrorBlockChainClientCore {
@@ -432,6 +845,48 @@ pub mod tests {
         }
 
         fn transaction_out(&self, prev_out: OutPoint) -> Option<TransactionOutput> { None }
+
+        fn blockchain_info(&self) -> BlockchainInfo {
+            SuccessBlockChainClientCore.blockchain_info()
+        }
+
+        fn prune_blockchain(&self, _height: u32) -> Result<Option<u32>, Error> {
+            Err(execution(storage::Error::PruningDisabled))
+        }
+
+        fn dump_tx_out_set(&self, _path: PathBuf) -> Result<DumpTxOutSetResponse, Error> {
+            Err(invalid_params("path", "File already exists"))
+        }
+
+        fn chain_states(&self) -> ChainStates {
+            SuccessBlockChainClientCore.chain_states()
+        }
+
+        fn chain_tips(&self) -> Vec<ChainTip> {
+            SuccessBlockChainClientCore.chain_tips()
+        }
+
+        fn invalidate_block(&self, hash: GlobalH256) -> Result<(), Error> {
+            Err(block_not_found(hash.reversed()))
+        }
+
+        fn reconsider_block(&self, hash: GlobalH256) -> Result<(), Error> {
+            Err(block_not_found(hash.reversed()))
+        }
+
+        fn verify_chain(&self, check_level: u32, _blocks: u32) -> VerifyChainResponse {
+            VerifyChainResponse {
+                valid: false,
+                checklevel: check_level,
+                firstblock: 0,
+                lastblock: 0,
+                errors: vec![VerifyChainIssue {
+                    height: 0,
+                    hash: Some(test_data::genesis().hash().into()),
+                    error: "block transactions don't match merkle root".into(),
+                }],
+            }
+        }
     }
 
     #[test]
@@ -551,7 +1006,7 @@ pub mod tests {
             test_data::block_h2().into(),
         ]));
 
-        let core = BlockChainClientCore::new(Network::Mainnet, storage);
+        let core = BlockChainClientCore::new(ConsensusParams::new(Network::Mainnet, ConsensusFork::BitcoinCore), storage, None);
 
         // get info on block #1:
         // https://blockexplorer.com/block/00000000839a8e6886ab5951d76f411475428afc90947ee320161bbf18eb6048
@@ -737,7 +1192,7 @@ pub mod tests {
         let storage = Arc::new(BlockChainDatabase::init_test_chain(
             vec![test_data::genesis().into()],
         ));
-        let core = BlockChainClientCore::new(Network::Mainnet, storage);
+        let core = BlockChainClientCore::new(ConsensusParams::new(Network::Mainnet, ConsensusFork::BitcoinCore), storage, None);
 
         // get info on tx from genesis block:
         // https://blockchain.info/ru/tx/4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b
@@ -802,4 +1257,365 @@ pub mod tests {
 
         assert_eq!(&sample, r#"{"jsonrpc":"2.0","error":{"code":-32099,"message":"Block with given hash is not found","data":"3ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a"},"id":1}"#);
     }
+
+    #[test]
+    fn blockchain_info_success() {
+        let client = BlockChainClient::new(SuccessBlockChainClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "getblockchaininfo",
+				"params": [],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":{"bestblockhash":"000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f","blocks":1,"chain":"main","difficulty":1.0,"headers":1,"mediantime":1231006505,"pruned":false,"size_on_disk":1024,"softforks":{"csv":{"active":true,"height":419328,"type":"buried"}},"verificationprogress":0.5},"id":1}"#);
+    }
+
+    #[test]
+    fn blockchain_info_contents() {
+        let storage = Arc::new(BlockChainDatabase::init_test_chain(
+            vec![test_data::genesis().into()],
+        ));
+        let consensus = ConsensusParams::new(Network::Regtest, ConsensusFork::BitcoinCore);
+        let core = BlockChainClientCore::new(consensus, storage, None);
+
+        let info = core.blockchain_info();
+        assert_eq!(info.chain, "regtest");
+        assert_eq!(info.blocks, 0);
+        assert_eq!(info.bestblockhash, test_data::genesis().hash().into());
+        assert_eq!(info.pruned, false);
+        assert_eq!(info.softforks["csv"], Softfork {
+            softfork_type: SoftforkType::Buried,
+            bip9: None,
+            height: Some(0),
+            active: true,
+        });
+        assert_eq!(info.softforks["segwit"], Softfork {
+            softfork_type: SoftforkType::Bip9,
+            bip9: Some(Bip9Softfork {
+                status: Bip9SoftforkStatus::Defined,
+                bit: 1,
+                start_time: 0,
+                timeout: ::std::u32::MAX,
+                statistics: None,
+            }),
+            height: None,
+            active: false,
+        });
+    }
+
+    #[test]
+    fn prune_blockchain_success() {
+        let client = BlockChainClient::new(SuccessBlockChainClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "pruneblockchain",
+				"params": [1000],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":1000,"id":1}"#);
+    }
+
+    #[test]
+    fn prune_blockchain_error() {
+        let client = BlockChainClient::new(ErrorBlockChainClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "pruneblockchain",
+				"params": [1000],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","error":{"code":-32015,"message":"Execution error.","data":"PruningDisabled"},"id":1}"#);
+    }
+
+    #[test]
+    fn prune_blockchain_contents() {
+        let mut blocks: Vec<chain::IndexedBlock> = vec![test_data::genesis().into()];
+        blocks.extend(test_data::build_n_empty_blocks_from_genesis(storage::MIN_BLOCKS_TO_KEEP + 10, 0).into_iter().map(Into::into));
+        let storage = Arc::new(BlockChainDatabase::init_test_chain(blocks).with_prune_mode(storage::PruneMode::Manual));
+        let consensus = ConsensusParams::new(Network::Regtest, ConsensusFork::BitcoinCore);
+        let core = BlockChainClientCore::new(consensus, storage, None);
+
+        assert_eq!(core.blockchain_info().pruneheight, Some(0));
+        // blocks within MIN_BLOCKS_TO_KEEP from the best block are never pruned
+        assert_eq!(core.prune_blockchain(1000).unwrap(), Some(10));
+        assert_eq!(core.blockchain_info().pruneheight, Some(11));
+        assert!(core.raw_block(test_data::genesis().hash()).is_none());
+        assert!(core.block_hash(0).is_some());
+    }
+
+    #[test]
+    fn dump_tx_out_set_success() {
+        let client = BlockChainClient::new(SuccessBlockChainClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "dumptxoutset",
+				"params": ["utxo.dat"],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":{"base_hash":"000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f","base_height":0,"coins_written":1,"path":"utxo.dat","txoutset_hash":"0000000000000000000000000000000000000000000000000000000000000001"},"id":1}"#);
+    }
+
+    #[test]
+    fn dump_tx_out_set_error() {
+        let client = BlockChainClient::new(ErrorBlockChainClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "dumptxoutset",
+				"params": ["utxo.dat"],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: path","data":"\"File already exists\""},"id":1}"#);
+    }
+
+    #[test]
+    fn chain_states_success() {
+        let client = BlockChainClient::new(SuccessBlockChainClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "getchainstates",
+				"params": [],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":{"chainstates":[{"bestblockhash":"000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f","blocks":0,"validated":true}],"headers":0},"id":1}"#);
+    }
+
+    #[test]
+    fn chain_tips_success() {
+        let client = BlockChainClient::new(SuccessBlockChainClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "getchaintips",
+				"params": [],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":[{"branchlen":0,"hash":"000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f","height":0,"status":"active"},{"branchlen":2,"hash":"0000000000000000000000000000000000000000000000000000000000000001","height":2,"status":"headers-only"}],"id":1}"#);
+    }
+
+    #[test]
+    fn invalidate_block_success() {
+        let client = BlockChainClient::new(SuccessBlockChainClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "invalidateblock",
+				"params": ["000000006a625f06636b8bb6ac7b960a8d03705d1ace08b1a19da3fdcc99ddbd"],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":null,"id":1}"#);
+    }
+
+    #[test]
+    fn reconsider_block_error() {
+        let client = BlockChainClient::new(ErrorBlockChainClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "reconsiderblock",
+				"params": ["000000006a625f06636b8bb6ac7b960a8d03705d1ace08b1a19da3fdcc99ddbd"],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","error":{"code":-32099,"message":"Block with given hash is not found","data":"000000006a625f06636b8bb6ac7b960a8d03705d1ace08b1a19da3fdcc99ddbd"},"id":1}"#);
+    }
+
+    #[test]
+    fn invalidate_and_reconsider_block_contents() {
+        let storage = Arc::new(BlockChainDatabase::init_test_chain(vec![
+            test_data::genesis().into(),
+            test_data::block_h1().into(),
+            test_data::block_h2().into(),
+        ]));
+        let consensus = ConsensusParams::new(Network::Mainnet, ConsensusFork::BitcoinCore);
+        let core = BlockChainClientCore::new(consensus, storage, None);
+
+        assert!(core.invalidate_block(GlobalH256::from(1)).is_err());
+        core.invalidate_block(test_data::block_h1().hash()).unwrap();
+        assert_eq!(core.best_block_hash(), test_data::genesis().hash());
+        assert_eq!(core.chain_tips(), vec![ChainTip {
+            height: 2,
+            hash: test_data::block_h2().hash().into(),
+            branchlen: 2,
+            status: ChainTipStatus::Invalid,
+        }, ChainTip {
+            height: 0,
+            hash: test_data::genesis().hash().into(),
+            branchlen: 0,
+            status: ChainTipStatus::Active,
+        }]);
+
+        core.reconsider_block(test_data::block_h1().hash()).unwrap();
+        assert_eq!(core.best_block_hash(), test_data::block_h2().hash());
+        assert_eq!(core.chain_tips(), vec![ChainTip {
+            height: 2,
+            hash: test_data::block_h2().hash().into(),
+            branchlen: 0,
+            status: ChainTipStatus::Active,
+        }]);
+    }
+
+    #[test]
+    fn verify_chain_success() {
+        let client = BlockChainClient::new(SuccessBlockChainClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "verifychain",
+				"params": [],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":{"checklevel":3,"errors":[],"firstblock":0,"lastblock":0,"valid":true},"id":1}"#);
+    }
+
+    #[test]
+    fn verify_chain_error() {
+        let client = BlockChainClient::new(ErrorBlockChainClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "verifychain",
+				"params": [4, 1],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":{"checklevel":4,"errors":[{"error":"block transactions don't match merkle root","hash":"000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f","height":0}],"firstblock":0,"lastblock":0,"valid":false},"id":1}"#);
+    }
+
+    #[test]
+    fn verify_chain_contents() {
+        let storage = Arc::new(BlockChainDatabase::init_test_chain(vec![
+            test_data::block_h0().into(),
+            test_data::block_h1().into(),
+            test_data::block_h2().into(),
+        ]));
+        let core = BlockChainClientCore::new(ConsensusParams::new(Network::Unitest, ConsensusFork::BitcoinCore), storage, None);
+
+        let response = core.verify_chain(4, 2);
+        assert!(response.valid);
+        assert_eq!(response.checklevel, 4);
+        assert_eq!(response.firstblock, 1);
+        assert_eq!(response.lastblock, 2);
+        assert!(response.errors.is_empty());
+    }
+
+    #[test]
+    fn dump_tx_out_set_contents() {
+        let storage = Arc::new(BlockChainDatabase::init_test_chain(vec![test_data::genesis().into()]));
+        let consensus = ConsensusParams::new(Network::Regtest, ConsensusFork::BitcoinCore);
+        let data_dir = ::std::env::temp_dir().join(format!("dumptxoutset-{}", ::std::process::id()));
+        fs::create_dir_all(data_dir.join("db")).unwrap();
+        let core = BlockChainClientCore::new(consensus, storage, Some(data_dir.join("db")));
+
+        // relative path is resolved against the data directory
+        let response = core.dump_tx_out_set("utxo.dat".into()).unwrap();
+        assert_eq!(response.base_height, 0);
+        assert_eq!(response.base_hash, test_data::genesis().hash().into());
+        assert_eq!(response.path, data_dir.join("utxo.dat").display().to_string());
+        assert!(data_dir.join("utxo.dat").exists());
+        assert!(!data_dir.join("utxo.incomplete").exists());
+
+        // existing file is never overwritten
+        assert!(core.dump_tx_out_set("utxo.dat".into()).is_err());
+
+        // chainstate, which is not seeded from snapshot, is validated
+        let states = core.chain_states();
+        assert_eq!(states.headers, 0);
+        assert_eq!(states.chainstates.len(), 1);
+        assert!(states.chainstates[0].validated);
+        assert_eq!(states.chainstates[0].snapshot_blockhash, None);
+
+        fs::remove_dir_all(&data_dir).unwrap();
+    }
 }
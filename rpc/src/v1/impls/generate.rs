@@ -0,0 +1,500 @@
+use std::sync::Arc;
+use v1::traits::Generate;
+use v1::types::{H256, GenerateBlockResponse};
+use v1::helpers::errors::{invalid_params, execution};
+use v1::helpers::descriptor::{address_script_pubkey, descriptor_script_pubkey};
+use jsonrpc_macros::Trailing;
+use jsonrpc_core::Error;
+use chain::{IndexedBlock, Transaction as GlobalTransaction};
+use hex::FromHex;
+use keys::{self, Address};
+use network::{Network, ConsensusParams, AddressPrefixes};
+use primitives::bytes::Bytes;
+use primitives::hash::H256 as GlobalH256;
+use ser::{Reader, deserialize};
+use sync;
+use miner;
+
+/// Default maximal number of header hashes, checked when mining single block
+const DEFAULT_MAX_TRIES: u64 = 1_000_000;
+
+pub struct GenerateClient<T: GenerateClientCoreApi> {
+    core: T,
+}
+
+pub trait GenerateClientCoreApi: Send + Sync + 'static {
+    fn network(&self) -> Network;
+    fn address_prefixes(&self) -> AddressPrefixes;
+    fn get_block_template(&self) -> miner::BlockTemplate;
+    fn get_block_template_with_transactions(
+        &self,
+        transactions: Vec<GlobalTransaction>,
+    ) -> Result<miner::BlockTemplate, String>;
+    fn memory_pool_transaction(&self, hash: &GlobalH256) -> Option<GlobalTransaction>;
+    fn spawn_block(&self, block: IndexedBlock) -> Result<(), String>;
+}
+
+pub struct GenerateClientCore {
+    network: Network,
+    address_prefixes: AddressPrefixes,
+    local_sync_node: sync::LocalNodeRef,
+}
+
+pub struct SimpleGenerateClientCore {
+    network: Network,
+    address_prefixes: AddressPrefixes,
+    simple_node: Arc<sync::SimpleNode>,
+}
+
+impl GenerateClientCore {
+    pub fn new(consensus: &ConsensusParams, local_sync_node: sync::LocalNodeRef) -> Self {
+        GenerateClientCore {
+            network: consensus.network,
+            address_prefixes: consensus.address_prefixes,
+            local_sync_node: local_sync_node,
+        }
+    }
+}
+
+impl SimpleGenerateClientCore {
+    pub fn new(consensus: &ConsensusParams, node: Arc<sync::SimpleNode>) -> Self {
+        SimpleGenerateClientCore {
+            network: consensus.network,
+            address_prefixes: consensus.address_prefixes,
+            simple_node: node,
+        }
+    }
+}
+
+impl GenerateClientCoreApi for GenerateClientCore {
+    fn network(&self) -> Network {
+        self.network
+    }
+
+    fn address_prefixes(&self) -> AddressPrefixes {
+        self.address_prefixes
+    }
+
+    fn get_block_template(&self) -> miner::BlockTemplate {
+        self.local_sync_node.get_block_template()
+    }
+
+    fn get_block_template_with_transactions(
+        &self,
+        transactions: Vec<GlobalTransaction>,
+    ) -> Result<miner::BlockTemplate, String> {
+        self.local_sync_node.get_block_template_with_transactions(transactions)
+    }
+
+    fn memory_pool_transaction(&self, hash: &GlobalH256) -> Option<GlobalTransaction> {
+        self.local_sync_node.memory_pool_transaction(hash)
+    }
+
+    fn spawn_block(&self, block: IndexedBlock) -> Result<(), String> {
+        self.local_sync_node.spawn_block(block)
+    }
+}
+
+impl GenerateClientCoreApi for SimpleGenerateClientCore {
+    fn network(&self) -> Network {
+        self.network
+    }
+
+    fn address_prefixes(&self) -> AddressPrefixes {
+        self.address_prefixes
+    }
+
+    fn get_block_template(&self) -> miner::BlockTemplate {
+        self.simple_node.get_block_template()
+    }
+
+    fn get_block_template_with_transactions(
+        &self,
+        transactions: Vec<GlobalTransaction>,
+    ) -> Result<miner::BlockTemplate, String> {
+        self.simple_node.get_block_template_with_transactions(transactions)
+    }
+
+    fn memory_pool_transaction(&self, hash: &GlobalH256) -> Option<GlobalTransaction> {
+        self.simple_node.memory_pool_transaction(hash)
+    }
+
+    fn spawn_block(&self, block: IndexedBlock) -> Result<(), String> {
+        self.simple_node.spawn_block(block)
+    }
+}
+
+impl<T> GenerateClient<T>
+where
+    T: GenerateClientCoreApi,
+{
+    pub fn new(core: T) -> Self {
+        GenerateClient { core: core }
+    }
+
+    /// Network of addresses, which are valid for current chain
+    fn address_network(&self) -> keys::Network {
+        match self.core.address_prefixes() {
+            AddressPrefixes::Mainnet => keys::Network::Mainnet,
+            AddressPrefixes::Testnet => keys::Network::Testnet,
+        }
+    }
+
+    /// Only allow blocks generation where there's no real proof of work
+    fn ensure_generation_allowed(&self) -> Result<(), Error> {
+        match self.core.network() {
+            Network::Regtest | Network::Unitest => Ok(()),
+            _ => Err(execution("Blocks generation is only available on regtest")),
+        }
+    }
+
+    /// Parse coinbase output, given either as address or as output descriptor
+    fn parse_output(&self, param: &str, output: &str) -> Result<Bytes, Error> {
+        if let Ok(address) = output.parse::<Address>() {
+            if address.network != self.address_network() {
+                return Err(invalid_params(param, "Address is not valid for current network"));
+            }
+            return Ok(address_script_pubkey(&address));
+        }
+
+        descriptor_script_pubkey(output, self.address_network()).map_err(|e| invalid_params(param, e))
+    }
+
+    /// Mine and insert single block, built using given template
+    fn mine_block(
+        &self,
+        block_template: miner::BlockTemplate,
+        script_pubkey: Bytes,
+        max_tries: u64,
+    ) -> Result<Option<H256>, Error> {
+        let block = match miner::mine_block(&block_template, script_pubkey, max_tries) {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+
+        let block_hash = block.hash().clone();
+        try!(self.core.spawn_block(block).map_err(execution));
+        Ok(Some(block_hash.reversed().into()))
+    }
+
+    /// Mine and insert given number of blocks, built using memory pool transactions
+    fn generate(&self, blocks: u32, script_pubkey: Bytes, max_tries: u64) -> Result<Vec<H256>, Error> {
+        try!(self.ensure_generation_allowed());
+
+        let mut hashes = Vec::new();
+        for _ in 0..blocks {
+            match try!(self.mine_block(self.core.get_block_template(), script_pubkey.clone(), max_tries)) {
+                Some(hash) => hashes.push(hash),
+                None => break,
+            }
+        }
+        Ok(hashes)
+    }
+
+    /// Parse transaction, given either as memory pool transaction id, or as raw transaction
+    fn parse_transaction(&self, transaction: &str) -> Result<GlobalTransaction, Error> {
+        if let Ok(hash) = transaction.parse::<H256>() {
+            let global_hash: GlobalH256 = hash.reversed().into();
+            return self.core.memory_pool_transaction(&global_hash).ok_or_else(|| {
+                invalid_params("transactions", format!("Transaction {} is not in the memory pool", transaction))
+            });
+        }
+
+        let transaction_data: Vec<u8> = try!(transaction.from_hex().map_err(|e| invalid_params("transactions", e)));
+        deserialize(Reader::new(&transaction_data)).map_err(|e| invalid_params("transactions", e))
+    }
+}
+
+impl<T> Generate for GenerateClient<T>
+where
+    T: GenerateClientCoreApi,
+{
+    fn generate_to_address(&self, blocks: u32, address: String, max_tries: Trailing<u64>) -> Result<Vec<H256>, Error> {
+        let address: Address = try!(address.parse().map_err(|_| invalid_params("address", "Invalid address")));
+        if address.network != self.address_network() {
+            return Err(invalid_params("address", "Address is not valid for current network"));
+        }
+
+        let max_tries: Option<u64> = max_tries.into();
+        self.generate(blocks, address_script_pubkey(&address), max_tries.unwrap_or(DEFAULT_MAX_TRIES))
+    }
+
+    fn generate_to_descriptor(&self, blocks: u32, descriptor: String, max_tries: Trailing<u64>) -> Result<Vec<H256>, Error> {
+        let script_pubkey = try!(
+            descriptor_script_pubkey(&descriptor, self.address_network())
+                .map_err(|e| invalid_params("descriptor", e))
+        );
+
+        let max_tries: Option<u64> = max_tries.into();
+        self.generate(blocks, script_pubkey, max_tries.unwrap_or(DEFAULT_MAX_TRIES))
+    }
+
+    fn generate_block(&self, output: String, transactions: Vec<String>) -> Result<GenerateBlockResponse, Error> {
+        try!(self.ensure_generation_allowed());
+
+        let script_pubkey = try!(self.parse_output("output", &output));
+        let transactions = try!(
+            transactions
+                .iter()
+                .map(|transaction| self.parse_transaction(transaction))
+                .collect::<Result<Vec<_>, _>>()
+        );
+        let block_template = try!(
+            self.core
+                .get_block_template_with_transactions(transactions)
+                .map_err(execution)
+        );
+
+        match try!(self.mine_block(block_template, script_pubkey, DEFAULT_MAX_TRIES)) {
+            Some(hash) => Ok(GenerateBlockResponse { hash: hash }),
+            None => Err(execution("Failed to make block")),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::sync::{Arc, Mutex};
+    use jsonrpc_core::IoHandler;
+    use v1::traits::Generate;
+    use primitives::U256;
+    use chain;
+    use miner;
+    use super::*;
+
+    const TRANSACTION: &'static str = "00000000013ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a0000000000000000000101000000000000000000000000";
+
+    #[derive(Default)]
+    struct SuccessGenerateClientCore {
+        network: Option<Network>,
+        blocks: Arc<Mutex<Vec<IndexedBlock>>>,
+    }
+
+    impl SuccessGenerateClientCore {
+        fn new(network: Network) -> Self {
+            SuccessGenerateClientCore {
+                network: Some(network),
+                blocks: Default::default(),
+            }
+        }
+    }
+
+    impl GenerateClientCoreApi for SuccessGenerateClientCore {
+        fn network(&self) -> Network {
+            self.network.unwrap_or(Network::Regtest)
+        }
+
+        fn address_prefixes(&self) -> AddressPrefixes {
+            self.network().address_prefixes()
+        }
+
+        fn get_block_template(&self) -> miner::BlockTemplate {
+            miner::BlockTemplate {
+                version: 4,
+                previous_header_hash: GlobalH256::from(1),
+                time: 33,
+                bits: U256::max_value().into(),
+                height: self.blocks.lock().unwrap().len() as u32 + 1,
+                transactions: Vec::new(),
+                coinbase_value: 5_000_000_000,
+                size_limit: 1_000_000,
+                sigop_limit: 20_000,
+            }
+        }
+
+        fn get_block_template_with_transactions(
+            &self,
+            transactions: Vec<GlobalTransaction>,
+        ) -> Result<miner::BlockTemplate, String> {
+            let mut block_template = self.get_block_template();
+            block_template.transactions = transactions.into_iter().map(Into::into).collect();
+            Ok(block_template)
+        }
+
+        fn memory_pool_transaction(&self, hash: &GlobalH256) -> Option<GlobalTransaction> {
+            let transaction: GlobalTransaction = TRANSACTION.into();
+            if *hash == transaction.hash() {
+                Some(transaction)
+            } else {
+                None
+            }
+        }
+
+        fn spawn_block(&self, block: IndexedBlock) -> Result<(), String> {
+            self.blocks.lock().unwrap().push(block);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn generatetoaddress_accepted() {
+        let core = SuccessGenerateClientCore::default();
+        let blocks = core.blocks.clone();
+        let client = GenerateClient::new(core);
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "generatetoaddress",
+				"params": [2, "mkmZxiEcEd8ZqjQWVZuC6so5dFMKEFpN2j"],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        let blocks = blocks.lock().unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(
+            sample,
+            format!(
+                r#"{{"jsonrpc":"2.0","result":["{}","{}"],"id":1}}"#,
+                blocks[0].hash().to_reversed_str(),
+                blocks[1].hash().to_reversed_str()
+            )
+        );
+        assert_eq!(blocks[1].transactions[0].raw.outputs[0].value, 5_000_000_000);
+        assert_eq!(
+            blocks[1].transactions[0].raw.outputs[0].script_pubkey,
+            "76a914399c39ac90dac26965fb55fdb2035e6715fdac4e88ac".into()
+        );
+    }
+
+    #[test]
+    fn generatetoaddress_rejects_other_network_address() {
+        let client = GenerateClient::new(SuccessGenerateClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "generatetoaddress",
+				"params": [1, "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa"],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: address","data":"\"Address is not valid for current network\""},"id":1}"#);
+    }
+
+    #[test]
+    fn generatetoaddress_rejected_on_mainnet() {
+        let client = GenerateClient::new(SuccessGenerateClientCore::new(Network::Mainnet));
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "generatetoaddress",
+				"params": [1, "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa"],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","error":{"code":-32015,"message":"Execution error.","data":"\"Blocks generation is only available on regtest\""},"id":1}"#);
+    }
+
+    #[test]
+    fn generatetodescriptor_accepted() {
+        let core = SuccessGenerateClientCore::default();
+        let blocks = core.blocks.clone();
+        let client = GenerateClient::new(core);
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "generatetodescriptor",
+				"params": [1, "raw(51)", 10],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        let blocks = blocks.lock().unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(sample, format!(r#"{{"jsonrpc":"2.0","result":["{}"],"id":1}}"#, blocks[0].hash().to_reversed_str()));
+        assert_eq!(blocks[0].transactions[0].raw.outputs[0].script_pubkey, "51".into());
+    }
+
+    #[test]
+    fn generatetodescriptor_rejects_invalid_checksum() {
+        let client = GenerateClient::new(SuccessGenerateClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "generatetodescriptor",
+				"params": [1, "raw(deadbeef)#00000000"],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: descriptor","data":"\"Provided checksum '00000000' does not match computed checksum '89f8spxm'\""},"id":1}"#);
+    }
+
+    #[test]
+    fn generateblock_accepted() {
+        let core = SuccessGenerateClientCore::default();
+        let blocks = core.blocks.clone();
+        let client = GenerateClient::new(core);
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let transaction: chain::Transaction = TRANSACTION.into();
+        let sample = handler
+            .handle_request_sync(&format!(
+                r#"{{"jsonrpc": "2.0", "method": "generateblock", "params": ["raw(51)", ["{}", "{}"]], "id": 1}}"#,
+                transaction.hash().to_reversed_str(),
+                TRANSACTION
+            ))
+            .unwrap();
+
+        let blocks = blocks.lock().unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(sample, format!(r#"{{"jsonrpc":"2.0","result":{{"hash":"{}"}},"id":1}}"#, blocks[0].hash().to_reversed_str()));
+        assert_eq!(blocks[0].transactions.len(), 3);
+        assert_eq!(blocks[0].transactions[1].hash, transaction.hash());
+        assert_eq!(blocks[0].transactions[2].hash, transaction.hash());
+    }
+
+    #[test]
+    fn generateblock_rejects_unknown_transaction() {
+        let client = GenerateClient::new(SuccessGenerateClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "generateblock",
+				"params": ["raw(51)", ["0000000000000000000000000000000000000000000000000000000000000001"]],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: transactions","data":"\"Transaction 0000000000000000000000000000000000000000000000000000000000000001 is not in the memory pool\""},"id":1}"#);
+    }
+}
@@ -0,0 +1,384 @@
+use v1::traits::MemoryPool;
+use v1::types::{H256, MemoryPoolInfo, MemoryPoolEntry, GetRawMemoryPoolResponse};
+use v1::helpers::errors::transaction_not_found;
+use jsonrpc_macros::Trailing;
+use jsonrpc_core::Error;
+use primitives::hash::H256 as GlobalH256;
+use sync;
+use miner;
+
+pub struct MemoryPoolClient<T: MemoryPoolClientCoreApi> {
+    core: T,
+}
+
+pub trait MemoryPoolClientCoreApi: Send + Sync + 'static {
+    fn information(&self) -> miner::MemoryPoolInformation;
+    fn transactions(&self) -> Vec<GlobalH256>;
+    fn entries(&self) -> Vec<miner::MemoryPoolEntryInformation>;
+    fn entry(&self, hash: &GlobalH256) -> Option<miner::MemoryPoolEntryInformation>;
+    fn ancestors(&self, hash: &GlobalH256) -> Option<Vec<miner::MemoryPoolEntryInformation>>;
+    fn descendants(&self, hash: &GlobalH256) -> Option<Vec<miner::MemoryPoolEntryInformation>>;
+}
+
+pub struct MemoryPoolClientCore {
+    local_sync_node: sync::LocalNodeRef,
+}
+
+impl MemoryPoolClientCore {
+    pub fn new(local_sync_node: sync::LocalNodeRef) -> Self {
+        MemoryPoolClientCore { local_sync_node: local_sync_node }
+    }
+}
+
+impl MemoryPoolClientCoreApi for MemoryPoolClientCore {
+    fn information(&self) -> miner::MemoryPoolInformation {
+        self.local_sync_node.memory_pool_information()
+    }
+
+    fn transactions(&self) -> Vec<GlobalH256> {
+        self.local_sync_node.memory_pool_transactions()
+    }
+
+    fn entries(&self) -> Vec<miner::MemoryPoolEntryInformation> {
+        self.local_sync_node.memory_pool_entries()
+    }
+
+    fn entry(&self, hash: &GlobalH256) -> Option<miner::MemoryPoolEntryInformation> {
+        self.local_sync_node.memory_pool_entry(hash)
+    }
+
+    fn ancestors(&self, hash: &GlobalH256) -> Option<Vec<miner::MemoryPoolEntryInformation>> {
+        self.local_sync_node.memory_pool_ancestors(hash)
+    }
+
+    fn descendants(&self, hash: &GlobalH256) -> Option<Vec<miner::MemoryPoolEntryInformation>> {
+        self.local_sync_node.memory_pool_descendants(hash)
+    }
+}
+
+impl<T> MemoryPoolClient<T>
+where
+    T: MemoryPoolClientCoreApi,
+{
+    pub fn new(core: T) -> Self {
+        MemoryPoolClient { core: core }
+    }
+}
+
+fn to_raw_memory_pool_response(
+    entries: Vec<miner::MemoryPoolEntryInformation>,
+    verbose: bool,
+) -> GetRawMemoryPoolResponse {
+    if verbose {
+        GetRawMemoryPoolResponse::Verbose(
+            entries
+                .into_iter()
+                .map(|entry| (entry.hash.reversed().into(), entry.into()))
+                .collect(),
+        )
+    } else {
+        GetRawMemoryPoolResponse::Hashes(
+            entries
+                .into_iter()
+                .map(|entry| entry.hash.reversed().into())
+                .collect(),
+        )
+    }
+}
+
+impl<T> MemoryPool for MemoryPoolClient<T>
+where
+    T: MemoryPoolClientCoreApi,
+{
+    fn memory_pool_info(&self) -> Result<MemoryPoolInfo, Error> {
+        Ok(self.core.information().into())
+    }
+
+    fn raw_memory_pool(&self, verbose: Trailing<bool>) -> Result<GetRawMemoryPoolResponse, Error> {
+        if verbose.unwrap_or_default() {
+            Ok(to_raw_memory_pool_response(self.core.entries(), true))
+        } else {
+            Ok(GetRawMemoryPoolResponse::Hashes(
+                self.core
+                    .transactions()
+                    .into_iter()
+                    .map(|hash| hash.reversed().into())
+                    .collect(),
+            ))
+        }
+    }
+
+    fn memory_pool_entry(&self, hash: H256) -> Result<MemoryPoolEntry, Error> {
+        let global_hash: GlobalH256 = hash.clone().reversed().into();
+        self.core
+            .entry(&global_hash)
+            .map(Into::into)
+            .ok_or_else(|| transaction_not_found(hash))
+    }
+
+    fn memory_pool_ancestors(
+        &self,
+        hash: H256,
+        verbose: Trailing<bool>,
+    ) -> Result<GetRawMemoryPoolResponse, Error> {
+        let global_hash: GlobalH256 = hash.clone().reversed().into();
+        self.core
+            .ancestors(&global_hash)
+            .map(|ancestors| to_raw_memory_pool_response(ancestors, verbose.unwrap_or_default()))
+            .ok_or_else(|| transaction_not_found(hash))
+    }
+
+    fn memory_pool_descendants(
+        &self,
+        hash: H256,
+        verbose: Trailing<bool>,
+    ) -> Result<GetRawMemoryPoolResponse, Error> {
+        let global_hash: GlobalH256 = hash.clone().reversed().into();
+        self.core
+            .descendants(&global_hash)
+            .map(|descendants| to_raw_memory_pool_response(descendants, verbose.unwrap_or_default()))
+            .ok_or_else(|| transaction_not_found(hash))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use jsonrpc_core::IoHandler;
+    use v1::traits::MemoryPool;
+    use primitives::hash::H256 as GlobalH256;
+    use miner;
+    use super::*;
+
+    #[derive(Default)]
+    struct SuccessMemoryPoolClientCore;
+
+    fn entry_information(hash: GlobalH256) -> miner::MemoryPoolEntryInformation {
+        miner::MemoryPoolEntryInformation {
+            hash: hash,
+            size: 100,
+            fee: 10_000,
+            modified_fee: 15_000,
+            time: 1_000,
+            descendant_count: 1,
+            descendant_size: 100,
+            descendant_fees: 15_000,
+            ancestor_count: 2,
+            ancestor_size: 200,
+            ancestor_fees: 20_000,
+            depends: vec![GlobalH256::from(2)],
+            spent_by: vec![],
+        }
+    }
+
+    impl MemoryPoolClientCoreApi for SuccessMemoryPoolClientCore {
+        fn information(&self) -> miner::MemoryPoolInformation {
+            miner::MemoryPoolInformation {
+                transactions_count: 2,
+                transactions_size_in_bytes: 200,
+                memory_usage_in_bytes: 1000,
+            }
+        }
+
+        fn transactions(&self) -> Vec<GlobalH256> {
+            vec![GlobalH256::from(1)]
+        }
+
+        fn entries(&self) -> Vec<miner::MemoryPoolEntryInformation> {
+            vec![entry_information(GlobalH256::from(1))]
+        }
+
+        fn entry(&self, hash: &GlobalH256) -> Option<miner::MemoryPoolEntryInformation> {
+            Some(entry_information(hash.clone()))
+        }
+
+        fn ancestors(&self, _hash: &GlobalH256) -> Option<Vec<miner::MemoryPoolEntryInformation>> {
+            Some(vec![entry_information(GlobalH256::from(2))])
+        }
+
+        fn descendants(&self, _hash: &GlobalH256) -> Option<Vec<miner::MemoryPoolEntryInformation>> {
+            Some(vec![])
+        }
+    }
+
+    #[derive(Default)]
+    struct ErrorMemoryPoolClientCore;
+
+    impl MemoryPoolClientCoreApi for ErrorMemoryPoolClientCore {
+        fn information(&self) -> miner::MemoryPoolInformation {
+            miner::MemoryPoolInformation {
+                transactions_count: 0,
+                transactions_size_in_bytes: 0,
+                memory_usage_in_bytes: 0,
+            }
+        }
+
+        fn transactions(&self) -> Vec<GlobalH256> {
+            vec![]
+        }
+
+        fn entries(&self) -> Vec<miner::MemoryPoolEntryInformation> {
+            vec![]
+        }
+
+        fn entry(&self, _hash: &GlobalH256) -> Option<miner::MemoryPoolEntryInformation> {
+            None
+        }
+
+        fn ancestors(&self, _hash: &GlobalH256) -> Option<Vec<miner::MemoryPoolEntryInformation>> {
+            None
+        }
+
+        fn descendants(&self, _hash: &GlobalH256) -> Option<Vec<miner::MemoryPoolEntryInformation>> {
+            None
+        }
+    }
+
+    #[test]
+    fn getmempoolinfo_accepted() {
+        let client = MemoryPoolClient::new(SuccessMemoryPoolClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "getmempoolinfo",
+				"params": [],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":{"bytes":200,"size":2,"usage":1000},"id":1}"#);
+    }
+
+    #[test]
+    fn getrawmempool_accepted() {
+        let client = MemoryPoolClient::new(SuccessMemoryPoolClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "getrawmempool",
+				"params": [],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        // direct hash is 0100000000000000000000000000000000000000000000000000000000000000
+        // but client expects reverse hash
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":["0000000000000000000000000000000000000000000000000000000000000001"],"id":1}"#);
+    }
+
+    #[test]
+    fn getrawmempool_verbose_accepted() {
+        let client = MemoryPoolClient::new(SuccessMemoryPoolClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "getrawmempool",
+				"params": [true],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":{"0000000000000000000000000000000000000000000000000000000000000001":{"ancestorcount":2,"ancestorfees":20000,"ancestorsize":200,"depends":["0000000000000000000000000000000000000000000000000000000000000002"],"descendantcount":1,"descendantfees":15000,"descendantsize":100,"fee":0.0001,"modifiedfee":0.00015,"size":100,"spentby":[],"time":1000}},"id":1}"#);
+    }
+
+    #[test]
+    fn getmempoolentry_accepted() {
+        let client = MemoryPoolClient::new(SuccessMemoryPoolClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "getmempoolentry",
+				"params": ["0000000000000000000000000000000000000000000000000000000000000001"],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":{"ancestorcount":2,"ancestorfees":20000,"ancestorsize":200,"depends":["0000000000000000000000000000000000000000000000000000000000000002"],"descendantcount":1,"descendantfees":15000,"descendantsize":100,"fee":0.0001,"modifiedfee":0.00015,"size":100,"spentby":[],"time":1000},"id":1}"#);
+    }
+
+    #[test]
+    fn getmempoolentry_rejected() {
+        let client = MemoryPoolClient::new(ErrorMemoryPoolClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "getmempoolentry",
+				"params": ["0000000000000000000000000000000000000000000000000000000000000001"],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","error":{"code":-32096,"message":"Transaction with given hash is not found","data":"0000000000000000000000000000000000000000000000000000000000000001"},"id":1}"#);
+    }
+
+    #[test]
+    fn getmempoolancestors_accepted() {
+        let client = MemoryPoolClient::new(SuccessMemoryPoolClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "getmempoolancestors",
+				"params": ["0000000000000000000000000000000000000000000000000000000000000001"],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":["0000000000000000000000000000000000000000000000000000000000000002"],"id":1}"#);
+    }
+
+    #[test]
+    fn getmempooldescendants_rejected() {
+        let client = MemoryPoolClient::new(ErrorMemoryPoolClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "getmempooldescendants",
+				"params": ["0000000000000000000000000000000000000000000000000000000000000001", true],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","error":{"code":-32096,"message":"Transaction with given hash is not found","data":"0000000000000000000000000000000000000000000000000000000000000001"},"id":1}"#);
+    }
+}
@@ -1,6 +1,13 @@
 use v1::traits::Miner;
-use v1::types::{BlockTemplate, BlockTemplateRequest};
+use std::sync::Arc;
+use v1::types::{H256, Bytes, RawBlock, BlockTemplate, BlockTemplateRequest, EstimateMode,
+    EstimateSmartFeeResponse, EstimateRawFeeResponse, MiningInfo};
+use v1::helpers::errors::{invalid_params, execution, transaction_not_found};
+use jsonrpc_macros::Trailing;
 use jsonrpc_core::Error;
+use chain::{Block as GlobalBlock, BlockHeader as GlobalBlockHeader, IndexedBlock, IndexedBlockHeader};
+use primitives::hash::H256 as GlobalH256;
+use ser::{Reader, deserialize};
 use sync;
 use miner;
 
@@ -8,17 +15,40 @@ pub struct MinerClient<T: MinerClientCoreApi> {
     core: T,
 }
 
+/// Default success threshold for estimaterawfee
+const DEFAULT_RAW_FEE_THRESHOLD: f64 = 0.95;
+/// Max absolute value of prioritisetransaction fee delta: total supply of money (in satoshis)
+const MAX_FEE_DELTA: i64 = 21_000_000 * 100_000_000;
+
 pub trait MinerClientCoreApi: Send + Sync + 'static {
     fn get_block_template(&self) -> miner::BlockTemplate;
+    fn block_template_long_poll_id(&self) -> String;
+    fn wait_block_template_change(&self, long_poll_id: &str);
+    fn submit_block(&self, block: IndexedBlock) -> Result<(), String>;
+    fn submit_header(&self, header: IndexedBlockHeader) -> Result<(), String>;
+    fn mining_info(&self) -> MiningInfo;
+    fn estimate_smart_fee(&self, confirmation_target: u32, mode: miner::FeeEstimateMode) -> miner::FeeEstimate;
+    fn estimate_raw_fee(
+        &self,
+        confirmation_target: u32,
+        success_threshold: f64,
+        horizon: miner::FeeEstimateHorizon,
+    ) -> miner::EstimationResult;
+    fn max_fee_estimation_target(&self, horizon: miner::FeeEstimateHorizon) -> u32;
+    fn prioritise_transaction(&self, hash: &GlobalH256, fee_delta: i64) -> bool;
 }
 
 pub struct MinerClientCore {
     local_sync_node: sync::LocalNodeRef,
+    hash_rate_meter: Arc<miner::HashRateMeter>,
 }
 
 impl MinerClientCore {
-    pub fn new(local_sync_node: sync::LocalNodeRef) -> Self {
-        MinerClientCore { local_sync_node: local_sync_node }
+    pub fn new(local_sync_node: sync::LocalNodeRef, hash_rate_meter: Arc<miner::HashRateMeter>) -> Self {
+        MinerClientCore {
+            local_sync_node: local_sync_node,
+            hash_rate_meter: hash_rate_meter,
+        }
     }
 }
 
@@ -26,6 +56,52 @@ impl MinerClientCoreApi for MinerClientCore {
     fn get_block_template(&self) -> miner::BlockTemplate {
         self.local_sync_node.get_block_template()
     }
+
+    fn block_template_long_poll_id(&self) -> String {
+        self.local_sync_node.block_template_long_poll_id()
+    }
+
+    fn wait_block_template_change(&self, long_poll_id: &str) {
+        self.local_sync_node.wait_block_template_change(long_poll_id)
+    }
+
+    fn submit_block(&self, block: IndexedBlock) -> Result<(), String> {
+        self.local_sync_node.spawn_block(block)
+    }
+
+    fn submit_header(&self, header: IndexedBlockHeader) -> Result<(), String> {
+        self.local_sync_node.submit_header(header)
+    }
+
+    fn mining_info(&self) -> MiningInfo {
+        MiningInfo {
+            blocks: self.local_sync_node.storage.best_block().number,
+            difficulty: self.local_sync_node.storage.difficulty(),
+            pooledtx: self.local_sync_node.memory_pool_information().transactions_count,
+            hashespersec: self.hash_rate_meter.hash_rate(),
+        }
+    }
+
+    fn estimate_smart_fee(&self, confirmation_target: u32, mode: miner::FeeEstimateMode) -> miner::FeeEstimate {
+        self.local_sync_node.estimate_smart_fee(confirmation_target, mode)
+    }
+
+    fn estimate_raw_fee(
+        &self,
+        confirmation_target: u32,
+        success_threshold: f64,
+        horizon: miner::FeeEstimateHorizon,
+    ) -> miner::EstimationResult {
+        self.local_sync_node.estimate_raw_fee(confirmation_target, success_threshold, horizon)
+    }
+
+    fn max_fee_estimation_target(&self, horizon: miner::FeeEstimateHorizon) -> u32 {
+        self.local_sync_node.max_fee_estimation_target(horizon)
+    }
+
+    fn prioritise_transaction(&self, hash: &GlobalH256, fee_delta: i64) -> bool {
+        self.local_sync_node.prioritise_transaction(hash, fee_delta)
+    }
 }
 
 impl<T> MinerClient<T>
@@ -41,18 +117,119 @@ impl<T> Miner for MinerClient<T>
 where
     T: MinerClientCoreApi,
 {
-    fn get_block_template(&self, _request: BlockTemplateRequest) -> Result<BlockTemplate, Error> {
-        Ok(self.core.get_block_template().into())
+    fn get_block_template(&self, request: BlockTemplateRequest) -> Result<BlockTemplate, Error> {
+        if let Some(ref long_poll_id) = request.longpollid {
+            self.core.wait_block_template_change(long_poll_id);
+        }
+
+        // read id before building template => template could only be newer than its id
+        let long_poll_id = self.core.block_template_long_poll_id();
+        let mut block_template: BlockTemplate = self.core.get_block_template().into();
+        block_template.longpollid = Some(long_poll_id);
+        Ok(block_template)
+    }
+
+    fn submit_block(&self, raw_block: RawBlock) -> Result<Option<String>, Error> {
+        let raw_block_data: Vec<u8> = raw_block.into();
+        let block: GlobalBlock = try!(deserialize(Reader::new(&raw_block_data)).map_err(
+            |e| invalid_params("block", e),
+        ));
+        Ok(self.core.submit_block(block.into()).err())
+    }
+
+    fn submit_header(&self, raw_header: Bytes) -> Result<(), Error> {
+        let raw_header_data: Vec<u8> = raw_header.into();
+        let header: GlobalBlockHeader = try!(deserialize(Reader::new(&raw_header_data)).map_err(
+            |e| invalid_params("hexdata", e),
+        ));
+        self.core.submit_header(header.into()).map_err(execution)
+    }
+
+    fn mining_info(&self) -> Result<MiningInfo, Error> {
+        Ok(self.core.mining_info())
+    }
+
+    fn estimate_smart_fee(
+        &self,
+        confirmation_target: u32,
+        mode: Trailing<EstimateMode>,
+    ) -> Result<EstimateSmartFeeResponse, Error> {
+        let max_target = self.core.max_fee_estimation_target(miner::FeeEstimateHorizon::Long);
+        if confirmation_target == 0 || confirmation_target > max_target {
+            return Err(invalid_params(
+                "conf_target",
+                format!("Invalid conf_target, must be between 1 - {}", max_target),
+            ));
+        }
+
+        let mode = mode.unwrap_or_default();
+        Ok(self.core.estimate_smart_fee(confirmation_target, mode.into()).into())
+    }
+
+    fn estimate_raw_fee(
+        &self,
+        confirmation_target: u32,
+        threshold: Trailing<f64>,
+    ) -> Result<EstimateRawFeeResponse, Error> {
+        let max_target = self.core.max_fee_estimation_target(miner::FeeEstimateHorizon::Long);
+        if confirmation_target == 0 || confirmation_target > max_target {
+            return Err(invalid_params(
+                "conf_target",
+                format!("Invalid conf_target, must be between 1 - {}", max_target),
+            ));
+        }
+
+        let threshold: Option<f64> = threshold.into();
+        let threshold = threshold.unwrap_or(DEFAULT_RAW_FEE_THRESHOLD);
+        if threshold < 0f64 || threshold > 1f64 {
+            return Err(invalid_params("threshold", "Invalid threshold"));
+        }
+
+        let estimate = |horizon| {
+            if confirmation_target > self.core.max_fee_estimation_target(horizon) {
+                return None;
+            }
+            Some(self.core.estimate_raw_fee(confirmation_target, threshold, horizon).into())
+        };
+
+        Ok(EstimateRawFeeResponse {
+            short: estimate(miner::FeeEstimateHorizon::Short),
+            medium: estimate(miner::FeeEstimateHorizon::Medium),
+            long: estimate(miner::FeeEstimateHorizon::Long),
+        })
+    }
+
+    fn prioritise_transaction(&self, hash: H256, dummy: Option<f64>, fee_delta: i64) -> Result<bool, Error> {
+        if dummy.map(|dummy| dummy != 0f64).unwrap_or(false) {
+            return Err(invalid_params(
+                "dummy",
+                "Priority is no longer supported, dummy argument must be zero or null",
+            ));
+        }
+        if fee_delta > MAX_FEE_DELTA || fee_delta < -MAX_FEE_DELTA {
+            return Err(invalid_params("fee_delta", "Fee delta is out of range"));
+        }
+
+        let global_hash: GlobalH256 = hash.clone().reversed().into();
+        if self.core.prioritise_transaction(&global_hash, fee_delta) {
+            Ok(true)
+        } else {
+            Err(transaction_not_found(hash))
+        }
     }
 }
 
 #[cfg(test)]
 pub mod tests {
+    extern crate test_data;
+
     use jsonrpc_core::IoHandler;
     use v1::traits::Miner;
     use primitives::hash::H256;
     use chain;
     use miner;
+    use hex::ToHex;
+    use ser::serialize;
     use super::*;
 
     #[derive(Default)]
@@ -73,6 +250,71 @@ pub mod tests {
                 sigop_limit: 88,
             }
         }
+
+        fn block_template_long_poll_id(&self) -> String {
+            "00000000000000000000000000000000000000000000000000000000000000015".to_owned()
+        }
+
+        fn wait_block_template_change(&self, _long_poll_id: &str) {}
+
+        fn submit_block(&self, block: chain::IndexedBlock) -> Result<(), String> {
+            if *block.hash() == test_data::genesis().hash() {
+                Err("duplicate".to_owned())
+            } else {
+                Ok(())
+            }
+        }
+
+        fn submit_header(&self, header: chain::IndexedBlockHeader) -> Result<(), String> {
+            if header.hash == test_data::genesis().hash() {
+                Ok(())
+            } else {
+                Err("prev-blk-not-found".to_owned())
+            }
+        }
+
+        fn mining_info(&self) -> MiningInfo {
+            MiningInfo {
+                blocks: 100,
+                difficulty: 1.5,
+                pooledtx: 3,
+                hashespersec: 1000.0,
+            }
+        }
+
+        fn estimate_smart_fee(&self, confirmation_target: u32, _mode: miner::FeeEstimateMode) -> miner::FeeEstimate {
+            miner::FeeEstimate {
+                fee_rate: Some(20_000),
+                blocks: confirmation_target,
+            }
+        }
+
+        fn estimate_raw_fee(
+            &self,
+            _confirmation_target: u32,
+            _success_threshold: f64,
+            _horizon: miner::FeeEstimateHorizon,
+        ) -> miner::EstimationResult {
+            miner::EstimationResult {
+                fee_rate: None,
+                pass: Default::default(),
+                fail: Default::default(),
+                decay: 0.5,
+                scale: 1,
+            }
+        }
+
+        fn max_fee_estimation_target(&self, horizon: miner::FeeEstimateHorizon) -> u32 {
+            match horizon {
+                miner::FeeEstimateHorizon::Short => 12,
+                miner::FeeEstimateHorizon::Medium => 48,
+                miner::FeeEstimateHorizon::Long => 1008,
+            }
+        }
+
+        fn prioritise_transaction(&self, hash: &GlobalH256, _fee_delta: i64) -> bool {
+            *hash == GlobalH256::from(1)
+        }
     }
 
     #[test]
@@ -95,6 +337,242 @@ pub mod tests {
 
         // direct hash is 0100000000000000000000000000000000000000000000000000000000000000
         // but client expects reverse hash
-        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":{"bits":44,"coinbaseaux":null,"coinbasetxn":null,"coinbasevalue":66,"curtime":33,"height":55,"mintime":null,"mutable":null,"noncerange":null,"previousblockhash":"0000000000000000000000000000000000000000000000000000000000000001","rules":null,"sigoplimit":88,"sizelimit":77,"target":"0000000000000000000000000000000000000000000000000000000000000000","transactions":[{"data":"00000000013ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a0000000000000000000101000000000000000000000000","depends":null,"fee":null,"hash":null,"required":false,"sigops":null,"txid":null,"weight":null}],"vbavailable":null,"vbrequired":null,"version":777,"weightlimit":null},"id":1}"#);
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":{"bits":44,"coinbaseaux":null,"coinbasetxn":null,"coinbasevalue":66,"curtime":33,"height":55,"longpollid":"00000000000000000000000000000000000000000000000000000000000000015","mintime":null,"mutable":null,"noncerange":null,"previousblockhash":"0000000000000000000000000000000000000000000000000000000000000001","rules":null,"sigoplimit":88,"sizelimit":77,"target":"0000000000000000000000000000000000000000000000000000000000000000","transactions":[{"data":"00000000013ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a0000000000000000000101000000000000000000000000","depends":null,"fee":null,"hash":null,"required":false,"sigops":null,"txid":null,"weight":null}],"vbavailable":null,"vbrequired":null,"version":777,"weightlimit":null},"id":1}"#);
+    }
+
+    #[test]
+    fn getblocktemplate_long_poll_accepted() {
+        let client = MinerClient::new(SuccessMinerClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "getblocktemplate",
+				"params": [{"longpollid": "00000000000000000000000000000000000000000000000000000000000000014"}],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert!(sample.contains(r#""longpollid":"00000000000000000000000000000000000000000000000000000000000000015""#));
+    }
+
+    #[test]
+    fn submitblock_accepted() {
+        let client = MinerClient::new(SuccessMinerClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let raw_block: String = serialize(&test_data::block_h1()).to_hex();
+        let sample = handler
+            .handle_request_sync(&format!(
+                r#"{{"jsonrpc": "2.0", "method": "submitblock", "params": ["{}"], "id": 1}}"#,
+                raw_block
+            ))
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":null,"id":1}"#);
+    }
+
+    #[test]
+    fn submitblock_rejected() {
+        let client = MinerClient::new(SuccessMinerClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let raw_block: String = serialize(&test_data::genesis()).to_hex();
+        let sample = handler
+            .handle_request_sync(&format!(
+                r#"{{"jsonrpc": "2.0", "method": "submitblock", "params": ["{}"], "id": 1}}"#,
+                raw_block
+            ))
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":"duplicate","id":1}"#);
+    }
+
+    #[test]
+    fn submitheader_accepted() {
+        let client = MinerClient::new(SuccessMinerClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let raw_header: String = serialize(&test_data::genesis().block_header).to_hex();
+        let sample = handler
+            .handle_request_sync(&format!(
+                r#"{{"jsonrpc": "2.0", "method": "submitheader", "params": ["{}"], "id": 1}}"#,
+                raw_header
+            ))
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":null,"id":1}"#);
+    }
+
+    #[test]
+    fn submitheader_rejected() {
+        let client = MinerClient::new(SuccessMinerClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let raw_header: String = serialize(&test_data::block_h1().block_header).to_hex();
+        let sample = handler
+            .handle_request_sync(&format!(
+                r#"{{"jsonrpc": "2.0", "method": "submitheader", "params": ["{}"], "id": 1}}"#,
+                raw_header
+            ))
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","error":{"code":-32015,"message":"Execution error.","data":"\"prev-blk-not-found\""},"id":1}"#);
+    }
+
+    #[test]
+    fn getmininginfo_accepted() {
+        let client = MinerClient::new(SuccessMinerClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "getmininginfo",
+				"params": [],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":{"blocks":100,"difficulty":1.5,"hashespersec":1000.0,"pooledtx":3},"id":1}"#);
+    }
+
+    #[test]
+    fn estimatesmartfee_accepted() {
+        let client = MinerClient::new(SuccessMinerClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "estimatesmartfee",
+				"params": [6, "ECONOMICAL"],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":{"blocks":6,"feerate":0.0002},"id":1}"#);
+    }
+
+    #[test]
+    fn estimatesmartfee_rejects_invalid_target() {
+        let client = MinerClient::new(SuccessMinerClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "estimatesmartfee",
+				"params": [0],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: conf_target","data":"\"Invalid conf_target, must be between 1 - 1008\""},"id":1}"#);
+    }
+
+    #[test]
+    fn estimaterawfee_accepted() {
+        let client = MinerClient::new(SuccessMinerClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "estimaterawfee",
+				"params": [24],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":{"long":{"decay":0.5,"errors":["Insufficient data or no feerate found which meets threshold"],"scale":1},"medium":{"decay":0.5,"errors":["Insufficient data or no feerate found which meets threshold"],"scale":1}},"id":1}"#);
+    }
+
+    #[test]
+    fn prioritisetransaction_accepted() {
+        let client = MinerClient::new(SuccessMinerClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "prioritisetransaction",
+				"params": ["0000000000000000000000000000000000000000000000000000000000000001", null, 10000],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","result":true,"id":1}"#);
+    }
+
+    #[test]
+    fn prioritisetransaction_rejected() {
+        let client = MinerClient::new(SuccessMinerClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "prioritisetransaction",
+				"params": ["0000000000000000000000000000000000000000000000000000000000000002", 0, 10000],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","error":{"code":-32096,"message":"Transaction with given hash is not found","data":"0000000000000000000000000000000000000000000000000000000000000002"},"id":1}"#);
+    }
+
+    #[test]
+    fn prioritisetransaction_rejects_out_of_range_fee_delta() {
+        let client = MinerClient::new(SuccessMinerClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler
+            .handle_request_sync(
+                &(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "prioritisetransaction",
+				"params": ["0000000000000000000000000000000000000000000000000000000000000001", null, -9223372036854775808],
+				"id": 1
+			}"#),
+            )
+            .unwrap();
+
+        assert_eq!(&sample, r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: fee_delta","data":"\"Fee delta is out of range\""},"id":1}"#);
     }
 }
@@ -2,8 +2,14 @@ mod blockchain;
 mod miner;
 mod raw;
 mod network;
+mod memory_pool;
+mod generate;
+mod pubsub;
 
 pub use self::blockchain::{BlockChainClient, BlockChainClientCore};
 pub use self::miner::{MinerClient, MinerClientCore};
 pub use self::raw::{RawClient, RawClientCore, SimpleClientCore};
 pub use self::network::{NetworkClient, NetworkClientCore};
+pub use self::memory_pool::{MemoryPoolClient, MemoryPoolClientCore};
+pub use self::generate::{GenerateClient, GenerateClientCore, SimpleGenerateClientCore};
+pub use self::pubsub::{PubSubClient, PubSubNotifier, Subscriptions};
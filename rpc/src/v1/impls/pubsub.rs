@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Sender, Receiver};
+use std::thread;
+use jsonrpc_core::Error;
+use jsonrpc_core::futures::Future;
+use jsonrpc_macros::{pubsub, Trailing};
+use jsonrpc_pubsub::{Session, SubscriptionId};
+use v1::traits::PubSub;
+use v1::types::{SubscriptionKind, SubscriptionParams, PubSubNotification, HeadNotification,
+	TransactionNotification, ReorgNotification, Bytes};
+use v1::helpers::errors;
+use chain::{Transaction, IndexedTransaction};
+use global_script::Script;
+use keys::Address;
+use primitives::hash::H256 as GlobalH256;
+use ser::serialize;
+use storage::SharedStore;
+use sync::SyncListener;
+
+/// Active subscription
+struct Subscription {
+    kind: SubscriptionKind,
+    addresses: Vec<Address>,
+    sink: pubsub::Sink<PubSubNotification>,
+}
+
+/// Active subscriptions, shared by `PubSubClient` and `PubSubNotifier`
+#[derive(Default)]
+pub struct Subscriptions {
+    next_id: AtomicUsize,
+    subscriptions: Mutex<HashMap<SubscriptionId, Subscription>>,
+}
+
+impl Subscriptions {
+    fn add(
+        &self,
+        subscriber: pubsub::Subscriber<PubSubNotification>,
+        kind: SubscriptionKind,
+        addresses: Vec<Address>,
+    ) {
+        let id = SubscriptionId::Number(self.next_id.fetch_add(1, Ordering::SeqCst) as u64 + 1);
+        if let Ok(sink) = subscriber.assign_id(id.clone()) {
+            self.subscriptions.lock().expect("lock is never poisoned; qed").insert(id, Subscription {
+                kind: kind,
+                addresses: addresses,
+                sink: sink,
+            });
+        }
+    }
+
+    fn remove(&self, id: &SubscriptionId) -> bool {
+        self.subscriptions.lock().expect("lock is never poisoned; qed").remove(id).is_some()
+    }
+
+    fn has_subscribers(&self, kind: SubscriptionKind) -> bool {
+        self.subscriptions.lock().expect("lock is never poisoned; qed")
+            .values()
+            .any(|subscription| subscription.kind == kind)
+    }
+
+    /// Send notifications to all subscribers of given kind. `notification` may skip the subscriber by returning None.
+    /// Subscriptions of disconnected subscribers are removed.
+    fn notify<F>(&self, kind: SubscriptionKind, notification: F)
+    where
+        F: Fn(&[Address]) -> Option<PubSubNotification>,
+    {
+        // do not hold the lock while sending notifications
+        let notifications: Vec<_> = self.subscriptions.lock().expect("lock is never poisoned; qed")
+            .iter()
+            .filter(|&(_, subscription)| subscription.kind == kind)
+            .filter_map(|(id, subscription)| notification(&subscription.addresses)
+                .map(|notification| (id.clone(), subscription.sink.clone(), notification)))
+            .collect();
+
+        for (id, sink, notification) in notifications {
+            if sink.notify(Ok(notification)).wait().is_err() {
+                trace!(target: "rpc", "Removing subscription {:?} of disconnected subscriber", id);
+                self.remove(&id);
+            }
+        }
+    }
+}
+
+pub struct PubSubClient {
+    subscriptions: Arc<Subscriptions>,
+}
+
+impl PubSubClient {
+    pub fn new(subscriptions: Arc<Subscriptions>) -> Self {
+        PubSubClient {
+            subscriptions: subscriptions,
+        }
+    }
+}
+
+impl PubSub for PubSubClient {
+    type Metadata = Arc<Session>;
+
+    fn subscribe(
+        &self,
+        _meta: Self::Metadata,
+        subscriber: pubsub::Subscriber<PubSubNotification>,
+        kind: SubscriptionKind,
+        params: Trailing<SubscriptionParams>,
+    ) {
+        let params: Option<SubscriptionParams> = params.into();
+        let addresses = params.map(|params| params.addresses).unwrap_or_default();
+        match (kind, addresses.is_empty()) {
+            (SubscriptionKind::AddressTransactions, true) => {
+                let _ = subscriber.reject(errors::invalid_params(
+                    "addresses",
+                    "addressTransactions subscription requires at least one address",
+                ));
+            }
+            (SubscriptionKind::AddressTransactions, false) | (_, true) => {
+                self.subscriptions.add(subscriber, kind, addresses)
+            }
+            (_, false) => {
+                let _ = subscriber.reject(errors::invalid_params(
+                    "addresses",
+                    "addresses are only supported by addressTransactions subscription",
+                ));
+            }
+        }
+    }
+
+    fn unsubscribe(&self, id: SubscriptionId) -> Result<bool, Error> {
+        Ok(self.subscriptions.remove(&id))
+    }
+}
+
+enum PubSubTask {
+    NewBlock(GlobalH256),
+    Reorganization(Vec<GlobalH256>, Vec<GlobalH256>),
+    NewTransaction(IndexedTransaction),
+    Stop,
+}
+
+/// Synchronization listener, sending notifications to subscribers.
+/// Notifications are sent from the separate thread, so slow subscribers do not block synchronization.
+/// New blocks are not notified while the node is synchronizing.
+pub struct PubSubNotifier {
+    tx: Sender<PubSubTask>,
+    subscriptions: Arc<Subscriptions>,
+    is_synchronizing: Arc<AtomicBool>,
+    worker_thread: Option<thread::JoinHandle<()>>,
+}
+
+impl PubSubNotifier {
+    pub fn new(storage: SharedStore, subscriptions: Arc<Subscriptions>) -> Self {
+        let (tx, rx) = channel();
+        let worker_subscriptions = subscriptions.clone();
+        PubSubNotifier {
+            tx: tx,
+            subscriptions: subscriptions,
+            is_synchronizing: Arc::new(AtomicBool::default()),
+            worker_thread: Some(
+                thread::Builder::new()
+                    .name("PubSub notification thread".to_owned())
+                    .spawn(move || PubSubNotifier::worker(rx, storage, worker_subscriptions))
+                    .expect("Error creating pubsub notification thread"),
+            ),
+        }
+    }
+
+    fn worker(rx: Receiver<PubSubTask>, storage: SharedStore, subscriptions: Arc<Subscriptions>) {
+        for task in rx {
+            match task {
+                PubSubTask::NewBlock(block_hash) => {
+                    notify_new_head(&storage, &subscriptions, &block_hash);
+                    notify_block_transactions(&storage, &subscriptions, &block_hash);
+                }
+                PubSubTask::Reorganization(decanonized, canonized) => {
+                    subscriptions.notify(SubscriptionKind::Reorgs, |_| {
+                        Some(PubSubNotification::Reorg(ReorgNotification {
+                            disconnected: decanonized.iter().map(|hash| hash.reversed().into()).collect(),
+                            connected: canonized.iter().map(|hash| hash.reversed().into()).collect(),
+                        }))
+                    });
+                    // transactions of the new best block are notified with `NewBlock` task
+                    let connected_len = canonized.len();
+                    for block_hash in canonized.iter().take(connected_len.saturating_sub(1)) {
+                        notify_block_transactions(&storage, &subscriptions, block_hash);
+                    }
+                }
+                PubSubTask::NewTransaction(transaction) => {
+                    subscriptions.notify(SubscriptionKind::NewTransactions, |_| {
+                        Some(PubSubNotification::Transaction(transaction_notification(&transaction, None, Vec::new())))
+                    });
+                    notify_address_transaction(&subscriptions, &transaction, None);
+                }
+                PubSubTask::Stop => break,
+            }
+        }
+        trace!(target: "rpc", "PubSub notification thread stopped");
+    }
+}
+
+impl SyncListener for PubSubNotifier {
+    fn synchronization_state_switched(&self, is_synchronizing: bool) {
+        self.is_synchronizing.store(is_synchronizing, Ordering::SeqCst);
+    }
+
+    fn best_storage_block_inserted(&self, block_hash: &GlobalH256) {
+        if !self.is_synchronizing.load(Ordering::SeqCst) {
+            self.send(PubSubTask::NewBlock(block_hash.clone()));
+        }
+    }
+
+    fn best_chain_reorganized(&self, decanonized: &[GlobalH256], canonized: &[GlobalH256]) {
+        self.send(PubSubTask::Reorganization(decanonized.to_vec(), canonized.to_vec()));
+    }
+
+    fn transaction_accepted(&self, transaction: &IndexedTransaction, _fee: u64) {
+        if self.subscriptions.has_subscribers(SubscriptionKind::NewTransactions) ||
+            self.subscriptions.has_subscribers(SubscriptionKind::AddressTransactions)
+        {
+            self.send(PubSubTask::NewTransaction(transaction.clone()));
+        }
+    }
+}
+
+impl PubSubNotifier {
+    fn send(&self, task: PubSubTask) {
+        self.tx.send(task).expect(
+            "PubSub notification thread have the same lifetime as `PubSubNotifier`",
+        )
+    }
+}
+
+impl Drop for PubSubNotifier {
+    fn drop(&mut self) {
+        if let Some(join_handle) = self.worker_thread.take() {
+            let _ = self.tx.send(PubSubTask::Stop);
+            join_handle.join().expect("Clean shutdown.");
+        }
+    }
+}
+
+fn notify_new_head(storage: &SharedStore, subscriptions: &Subscriptions, block_hash: &GlobalH256) {
+    if !subscriptions.has_subscribers(SubscriptionKind::NewHeads) {
+        return;
+    }
+
+    let (header, height) = match (storage.block_header(block_hash.clone().into()), storage.block_number(block_hash)) {
+        (Some(header), Some(height)) => (header, height),
+        // block has been already decanonized
+        _ => return,
+    };
+
+    subscriptions.notify(SubscriptionKind::NewHeads, |_| {
+        Some(PubSubNotification::Head(HeadNotification {
+            hash: block_hash.reversed().into(),
+            height: height,
+            version: header.version,
+            merkleroot: header.merkle_root_hash.reversed().into(),
+            time: header.time,
+            nonce: header.nonce,
+            bits: header.bits.into(),
+            previousblockhash: header.previous_header_hash.reversed().into(),
+        }))
+    });
+}
+
+fn notify_block_transactions(storage: &SharedStore, subscriptions: &Subscriptions, block_hash: &GlobalH256) {
+    if !subscriptions.has_subscribers(SubscriptionKind::AddressTransactions) {
+        return;
+    }
+
+    for transaction in storage.indexed_block_transactions(block_hash.clone().into()) {
+        notify_address_transaction(subscriptions, &transaction, Some(block_hash));
+    }
+}
+
+fn notify_address_transaction(subscriptions: &Subscriptions, transaction: &IndexedTransaction, block_hash: Option<&GlobalH256>) {
+    subscriptions.notify(SubscriptionKind::AddressTransactions, |addresses| {
+        let paid_addresses = paid_addresses(&transaction.raw, addresses);
+        if paid_addresses.is_empty() {
+            return None;
+        }
+
+        Some(PubSubNotification::Transaction(transaction_notification(transaction, block_hash, paid_addresses)))
+    });
+}
+
+fn transaction_notification(transaction: &IndexedTransaction, block_hash: Option<&GlobalH256>, addresses: Vec<Address>) -> TransactionNotification {
+    TransactionNotification {
+        txid: transaction.hash.reversed().into(),
+        hex: Bytes::new(serialize(&transaction.raw).take()),
+        blockhash: block_hash.map(|hash| hash.reversed().into()),
+        addresses: addresses,
+    }
+}
+
+/// Returns addresses (of given), which are paid by the transaction outputs
+fn paid_addresses(transaction: &Transaction, addresses: &[Address]) -> Vec<Address> {
+    let destinations: Vec<_> = transaction.outputs.iter()
+        .flat_map(|output| Script::new(output.script_pubkey.clone()).extract_destinations().unwrap_or_default())
+        .collect();
+
+    addresses.iter()
+        .filter(|address| destinations.iter().any(|destination| destination.kind == address.kind && destination.hash == address.hash))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chain::{Transaction, TransactionOutput};
+    use keys::{Address, Network, Type};
+    use global_script::Builder;
+    use super::paid_addresses;
+
+    #[test]
+    fn paid_addresses_match_outputs_only() {
+        let paid: Address = "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa".into();
+        let other = Address {
+            kind: Type::P2SH,
+            network: Network::Mainnet,
+            hash: paid.hash.clone(),
+        };
+        let transaction = Transaction {
+            outputs: vec![
+                TransactionOutput {
+                    value: 10,
+                    script_pubkey: Builder::build_p2pkh(&paid.hash).into(),
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(paid_addresses(&transaction, &[paid.clone(), other]), vec![paid]);
+        assert_eq!(paid_addresses(&transaction, &[]), Vec::<Address>::new());
+    }
+}
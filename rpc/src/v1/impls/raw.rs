@@ -1,19 +1,23 @@
 use jsonrpc_core::Error;
 use jsonrpc_macros::Trailing;
-use ser::{Reader, serialize, deserialize};
+use ser::{Reader, Serializable, serialize, serialize_with_flags, deserialize, SERIALIZE_TRANSACTION_WITNESS};
 use v1::traits::Raw;
 use v1::types::{SignedTransactionOutput, TransactionInputScript, TransactionOutputScript,
                 SignedTransactionInput, Bytes, RawTransaction, TransactionInput,
-                TransactionOutput, TransactionOutputs, Transaction, GetRawTransactionResponse};
+                TransactionOutput, TransactionOutputs, Transaction, GetRawTransactionResponse,
+                TestMemoryPoolAcceptResult, SubmitPackageResponse, SpentInfoRequest, SpentInfoResponse,
+                SignedTransactionInputPrevout};
 use v1::types::H256;
-use v1::helpers::errors::{execution, invalid_params, transaction_not_found};
-use chain::Transaction as GlobalTransaction;
+use v1::helpers::errors::{execution, invalid_params, block_not_found, transaction_not_found, spent_info_not_found};
+use chain::{Transaction as GlobalTransaction, TransactionOutput as GlobalTransactionOutput, BlockHeader, OutPoint};
 use primitives::bytes::Bytes as GlobalBytes;
 use primitives::hash::H256 as GlobalH256;
 use std::sync::Arc;
 use keys::{self, Address};
 use global_script::{Opcode, Script};
 use sync;
+use storage;
+use network::AddressPrefixes;
 use other_hex;
 
 pub struct RawClient<T: RawClientCoreApi> {
@@ -30,9 +34,13 @@ pub trait RawClientCoreApi: Send + Sync + 'static {
     ) -> Result<GlobalTransaction, String>;
     fn get_raw_transaction(
         &self,
-        hash: H256,
-        verbose: Trailing<bool>,
+        hash: GlobalH256,
+        verbose: bool,
+        block_hash: Option<GlobalH256>,
     ) -> Result<GetRawTransactionResponse, Error>;
+    fn test_accept_transactions(&self, transactions: Vec<GlobalTransaction>) -> Vec<sync::MemoryPoolAcceptResult>;
+    fn submit_package(&self, transactions: Vec<GlobalTransaction>) -> Result<sync::PackageAcceptResult, String>;
+    fn spent_info(&self, prevout: OutPoint) -> Result<SpentInfoResponse, Error>;
 }
 
 pub struct RawClientCore {
@@ -127,6 +135,220 @@ pub fn do_create_raw_transaction(
     Ok(transaction)
 }
 
+/// Height of outputs of memory pool transactions, reported in verbose transaction inputs
+const MEMORY_POOL_HEIGHT: u32 = 0x7FFFFFFF;
+
+/// Block, which contains the transaction
+struct TransactionBlock {
+    hash: GlobalH256,
+    header: BlockHeader,
+    /// True if block hash has been passed to the method
+    is_requested: bool,
+}
+
+/// Output, spent by the transaction
+struct PreviousOutput {
+    output: GlobalTransactionOutput,
+    height: u32,
+    is_coinbase: bool,
+}
+
+/// Finds transaction in the given block, in the canon chain or in the memory pool and
+/// returns it in the form of `getrawtransaction` response
+pub fn do_get_raw_transaction<F>(
+    storage: &storage::CanonStore,
+    memory_pool_transaction: F,
+    address_prefixes: AddressPrefixes,
+    hash: GlobalH256,
+    verbose: bool,
+    block_hash: Option<GlobalH256>,
+) -> Result<GetRawTransactionResponse, Error>
+where
+    F: Fn(&GlobalH256) -> Option<GlobalTransaction>,
+{
+    let (transaction, block) = match block_hash {
+        Some(block_hash) => {
+            let header = try!(storage.block_header(block_hash.clone().into())
+                .ok_or_else(|| block_not_found(block_hash.reversed())));
+            if !storage.block_transaction_hashes(block_hash.clone().into()).contains(&hash) {
+                return Err(transaction_not_found(hash.reversed()));
+            }
+            let transaction = try!(storage.transaction(&hash)
+                .ok_or_else(|| transaction_not_found(hash.reversed())));
+            (transaction, Some(TransactionBlock {
+                hash: block_hash,
+                header: header,
+                is_requested: true,
+            }))
+        },
+        None => match storage.transaction_location(&hash) {
+            Some(location) => {
+                let transaction = try!(storage.transaction(&hash)
+                    .ok_or_else(|| transaction_not_found(hash.reversed())));
+                let header = try!(storage.block_header(location.block_hash.clone().into())
+                    .ok_or_else(|| transaction_not_found(hash.reversed())));
+                (transaction, Some(TransactionBlock {
+                    hash: location.block_hash,
+                    header: header,
+                    is_requested: false,
+                }))
+            },
+            // memory pool transaction or side chain transaction with unknown block
+            None => match memory_pool_transaction(&hash).or_else(|| storage.transaction(&hash)) {
+                Some(transaction) => (transaction, None),
+                None => return Err(transaction_not_found(hash.reversed())),
+            },
+        },
+    };
+
+    let raw_transaction = Bytes::new(serialize_with_flags(&transaction, SERIALIZE_TRANSACTION_WITNESS).take());
+    if !verbose {
+        return Ok(GetRawTransactionResponse::Raw(raw_transaction));
+    }
+
+    let network = match address_prefixes {
+        AddressPrefixes::Mainnet => keys::Network::Mainnet,
+        AddressPrefixes::Testnet => keys::Network::Testnet,
+    };
+
+    let is_coinbase = transaction.is_coinbase();
+    let mut inputs_value = Some(0u64);
+    let vin = transaction.inputs.iter().map(|input| {
+        let witness = input.script_witness.iter().map(|bytes| bytes.clone().into()).collect();
+        if is_coinbase {
+            return SignedTransactionInput {
+                txid: None,
+                vout: None,
+                script_sig: None,
+                coinbase: Some(input.script_sig.clone().into()),
+                txinwitness: witness,
+                prevout: None,
+                sequence: input.sequence,
+            };
+        }
+
+        let previous_output = previous_output(storage, &memory_pool_transaction, &input.previous_output);
+        inputs_value = match (inputs_value, previous_output.as_ref()) {
+            (Some(value), Some(previous_output)) => Some(value + previous_output.output.value),
+            _ => None,
+        };
+
+        let script: Script = input.script_sig.clone().into();
+        SignedTransactionInput {
+            txid: Some(H256::from(input.previous_output.hash.clone()).reversed()),
+            vout: Some(input.previous_output.index),
+            script_sig: Some(TransactionInputScript {
+                asm: format!("{}", script),
+                hex: input.script_sig.clone().into(),
+            }),
+            coinbase: None,
+            txinwitness: witness,
+            prevout: previous_output.map(|previous_output| SignedTransactionInputPrevout {
+                generated: previous_output.is_coinbase,
+                height: previous_output.height,
+                value: 0.00000001f64 * previous_output.output.value as f64,
+                script: transaction_output_script(&previous_output.output.script_pubkey, network),
+            }),
+            sequence: input.sequence,
+        }
+    }).collect();
+
+    let vout = transaction.outputs.iter().enumerate().map(|(index, output)| SignedTransactionOutput {
+        value: 0.00000001f64 * output.value as f64,
+        n: index as u32,
+        script: transaction_output_script(&output.script_pubkey, network),
+    }).collect();
+
+    let fee = match inputs_value {
+        Some(inputs_value) if !is_coinbase => {
+            let outputs_value = transaction.outputs.iter().fold(0u64, |sum, output| sum + output.value);
+            Some(0.00000001f64 * inputs_value.saturating_sub(outputs_value) as f64)
+        },
+        _ => None,
+    };
+
+    let best_block_number = storage.best_block().number;
+    let (in_active_chain, blockhash, confirmations, time) = match block {
+        Some(block) => {
+            let block_number = storage.block_number(&block.hash);
+            let confirmations = block_number
+                .map(|block_number| best_block_number - block_number + 1)
+                .unwrap_or(0);
+            let in_active_chain = if block.is_requested { Some(block_number.is_some()) } else { None };
+            (in_active_chain, Some(H256::from(block.hash).reversed()), Some(confirmations), Some(block.header.time))
+        },
+        None => (None, None, None, None),
+    };
+
+    let base_size = transaction.serialized_size();
+    let total_size = transaction.serialized_size_with_flags(SERIALIZE_TRANSACTION_WITNESS);
+    let weight = base_size * 3 + total_size;
+
+    Ok(GetRawTransactionResponse::Verbose(Transaction {
+        in_active_chain: in_active_chain,
+        hex: raw_transaction,
+        txid: H256::from(transaction.hash()).reversed(),
+        hash: H256::from(transaction.witness_hash()).reversed(),
+        size: total_size,
+        vsize: (weight + 3) / 4,
+        weight: weight,
+        version: transaction.version,
+        locktime: transaction.lock_time as i32,
+        vin: vin,
+        vout: vout,
+        fee: fee,
+        blockhash: blockhash,
+        confirmations: confirmations,
+        time: time,
+        blocktime: time,
+    }))
+}
+
+/// Finds output, spent by the transaction, in the memory pool or in the canon chain
+fn previous_output<F>(storage: &storage::CanonStore, memory_pool_transaction: &F, prevout: &OutPoint) -> Option<PreviousOutput>
+where
+    F: Fn(&GlobalH256) -> Option<GlobalTransaction>,
+{
+    if let Some(transaction) = memory_pool_transaction(&prevout.hash) {
+        return transaction.outputs.get(prevout.index as usize).cloned().map(|output| PreviousOutput {
+            output: output,
+            height: MEMORY_POOL_HEIGHT,
+            is_coinbase: false,
+        });
+    }
+
+    let meta = match storage.transaction_meta(&prevout.hash) {
+        Some(meta) => meta,
+        None => return None,
+    };
+    storage.transaction(&prevout.hash)
+        .and_then(|transaction| transaction.outputs.get(prevout.index as usize).cloned())
+        .map(|output| PreviousOutput {
+            output: output,
+            height: meta.height(),
+            is_coinbase: meta.is_coinbase(),
+        })
+}
+
+fn transaction_output_script(script_bytes: &GlobalBytes, network: keys::Network) -> TransactionOutputScript {
+    let script: Script = script_bytes.clone().into();
+    let script_addresses = script.extract_destinations().unwrap_or(vec![]);
+    TransactionOutputScript {
+        asm: format!("{}", script),
+        hex: script_bytes.clone().into(),
+        req_sigs: script.num_signatures_required() as u32,
+        script_type: script.script_type().into(),
+        addresses: script_addresses
+            .into_iter()
+            .map(|address| Address {
+                network: network,
+                hash: address.hash,
+                kind: address.kind,
+            })
+            .collect(),
+    }
+}
+
 pub struct SimpleClientCore {
     simple_node: Arc<sync::SimpleNode>,
 }
@@ -175,53 +397,48 @@ impl RawClientCoreApi for RawClientCore {
 
     fn get_raw_transaction(
         &self,
-        hash: H256,
-        verbose: Trailing<bool>,
+        hash: GlobalH256,
+        verbose: bool,
+        block_hash: Option<GlobalH256>,
     ) -> Result<GetRawTransactionResponse, Error> {
-        let ghash: GlobalH256 = hash.clone().into();
-        if let Some(tx) = self.local_sync_node.storage.transaction(&ghash) {
-            let raw_tx = Bytes::new(serialize(&tx).take());
-            if verbose.unwrap_or_default() {
-                let transaction = Transaction {
-                    hex: raw_tx,
-                    txid: hash.clone().reversed(), // segwit to do
-                    hash: hash.clone().reversed(),
-                    size: 0, // to do
-                    vsize: 0, // to do
-                    version: tx.version,
-                    locktime: tx.lock_time as i32,
-                    vin: tx.inputs.iter().map(|input| SignedTransactionInput{
-                                      txid: H256::from(input.clone().previous_output.hash.take()).reversed(),
-                                      vout: input.previous_output.index,
-                                      script_sig: TransactionInputScript{asm: String::new(), hex: Bytes::new(input.clone().script_sig.take()),},
-                                      sequence: input.sequence,
-                                      txinwitness: input.script_witness.iter().map(|bytes| String::from_utf8(bytes.clone().take()).unwrap()).collect::<_>(),}).collect::<_>(),
-                    vout: tx.outputs.iter().map(|output| {
-                                           let ref script_bytes = output.clone().script_pubkey;
-                                           let script: Script = script_bytes.clone().into();
-                                           let script_asm = format!("{}", script);
-                                           let script_addresses = script.extract_destinations().unwrap_or(vec![]);
-                                           SignedTransactionOutput{value: 0.00000001f64 * output.value as f64, n: 0,// to do
-                                             script: TransactionOutputScript{asm: script_asm, hex: script_bytes.clone().into(),
-                                             req_sigs: script.num_signatures_required() as u32, script_type: script.script_type().into(), addresses: script_addresses.into_iter().map(|a| Address {
-                                                       network: keys::Network::Testnet, /*match self.network {
-                                                            Network::Mainnet => keys::Network::Mainnet,
-                                                            _ => keys::Network::Testnet,
-                                                       },*/
-                                                       hash: a.hash,
-                                                       kind: a.kind,}).collect(),},
-                                           } }).collect::<_>(),
-                    blockhash: Default::default(),
-                    confirmations: 0, // to do:tx.is_final_in_block(height, block_time),
-                    time: 0, // to do: block_time,
-                    blocktime: 0, // to do: block_time,
-                };
-                return Ok(GetRawTransactionResponse::Verbose(transaction));
-            } else {
-                return Ok(GetRawTransactionResponse::Raw(raw_tx));
-            }
+        do_get_raw_transaction(
+            &*self.local_sync_node.storage,
+            |hash| self.local_sync_node.memory_pool_transaction(hash),
+            self.local_sync_node.consensus().address_prefixes,
+            hash,
+            verbose,
+            block_hash,
+        )
+    }
+
+    fn test_accept_transactions(&self, transactions: Vec<GlobalTransaction>) -> Vec<sync::MemoryPoolAcceptResult> {
+        self.local_sync_node.test_accept_transactions(transactions)
+    }
+
+    fn submit_package(&self, transactions: Vec<GlobalTransaction>) -> Result<sync::PackageAcceptResult, String> {
+        self.local_sync_node.submit_package(transactions)
+    }
+
+    fn spent_info(&self, prevout: OutPoint) -> Result<SpentInfoResponse, Error> {
+        if !self.local_sync_node.storage.is_spent_index_enabled() {
+            return Err(execution(storage::Error::SpentIndexDisabled));
+        }
+
+        if let Some((txid, index)) = self.local_sync_node.memory_pool_spending_input(&prevout) {
+            return Ok(SpentInfoResponse {
+                txid: txid.into(),
+                index: index,
+                height: None,
+            });
         }
-        Err(transaction_not_found(hash))
+
+        self.local_sync_node.storage.spent_info(&prevout)
+            .map(|info| SpentInfoResponse {
+                txid: info.txid.into(),
+                index: info.index,
+                height: Some(info.height),
+            })
+            .ok_or_else(|| spent_info_not_found(prevout))
     }
 }
 
@@ -250,21 +467,48 @@ impl RawClientCoreApi for SimpleClientCore {
 
     fn get_raw_transaction(
         &self,
-        hash: H256,
-        verbose: Trailing<bool>,
+        hash: GlobalH256,
+        verbose: bool,
+        block_hash: Option<GlobalH256>,
     ) -> Result<GetRawTransactionResponse, Error> {
-        let hash: GlobalH256 = hash.clone().into();
-        if verbose.unwrap_or_default() {
-            /* if let Some(tx) = self.simple_node.storage.transaction(&hash) {
-              Ok(GetRawTransactionResponse::Verbose(tx))
-           }*/
-        } else {
-            if let Some(tx) = self.simple_node.storage.transaction_bytes(&hash) {
-                let tx = Bytes::new(serialize(&tx).take());
-                return Ok(GetRawTransactionResponse::Raw(tx));
-            }
+        do_get_raw_transaction(
+            &*self.simple_node.storage,
+            |hash| self.simple_node.memory_pool_transaction(hash),
+            self.simple_node.consensus().address_prefixes,
+            hash,
+            verbose,
+            block_hash,
+        )
+    }
+
+    fn test_accept_transactions(&self, transactions: Vec<GlobalTransaction>) -> Vec<sync::MemoryPoolAcceptResult> {
+        self.simple_node.test_accept_transactions(transactions)
+    }
+
+    fn submit_package(&self, transactions: Vec<GlobalTransaction>) -> Result<sync::PackageAcceptResult, String> {
+        self.simple_node.submit_package(transactions)
+    }
+
+    fn spent_info(&self, prevout: OutPoint) -> Result<SpentInfoResponse, Error> {
+        if !self.simple_node.storage.is_spent_index_enabled() {
+            return Err(execution(storage::Error::SpentIndexDisabled));
         }
-        Err(transaction_not_found(hash))
+
+        if let Some((txid, index)) = self.simple_node.memory_pool_spending_input(&prevout) {
+            return Ok(SpentInfoResponse {
+                txid: txid.into(),
+                index: index,
+                height: None,
+            });
+        }
+
+        self.simple_node.storage.spent_info(&prevout)
+            .map(|info| SpentInfoResponse {
+                txid: info.txid.into(),
+                index: info.index,
+                height: Some(info.height),
+            })
+            .ok_or_else(|| spent_info_not_found(prevout))
     }
 }
 
@@ -318,19 +562,75 @@ where
         &self,
         hash: H256,
         verbose: Trailing<bool>,
+        block_hash: Trailing<H256>,
     ) -> Result<GetRawTransactionResponse, Error> {
-        self.core.get_raw_transaction(hash.reversed(), verbose)
+        let block_hash: Option<H256> = block_hash.into();
+        self.core.get_raw_transaction(
+            hash.reversed().into(),
+            verbose.unwrap_or_default(),
+            block_hash.map(|hash| hash.reversed().into()),
+        )
+    }
+
+    fn test_memory_pool_accept(
+        &self,
+        raw_transactions: Vec<RawTransaction>,
+    ) -> Result<Vec<TestMemoryPoolAcceptResult>, Error> {
+        let transactions = try!(deserialize_raw_transactions(raw_transactions));
+        Ok(self.core
+            .test_accept_transactions(transactions)
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    fn submit_package(&self, raw_transactions: Vec<RawTransaction>) -> Result<SubmitPackageResponse, Error> {
+        let transactions = try!(deserialize_raw_transactions(raw_transactions));
+        self.core
+            .submit_package(transactions)
+            .map(Into::into)
+            .map_err(|e| execution(e))
+    }
+
+    fn get_spent_info(&self, request: SpentInfoRequest) -> Result<SpentInfoResponse, Error> {
+        let prevout = OutPoint {
+            hash: request.txid.reversed().into(),
+            index: request.index,
+        };
+        self.core.spent_info(prevout).map(|mut info| {
+            info.txid = info.txid.reversed();
+            info
+        })
     }
 }
 
+fn deserialize_raw_transactions(raw_transactions: Vec<RawTransaction>) -> Result<Vec<GlobalTransaction>, Error> {
+    let mut transactions = Vec::with_capacity(raw_transactions.len());
+    for raw_transaction in raw_transactions {
+        let raw_transaction_data: Vec<u8> = raw_transaction.into();
+        let transaction = try!(deserialize(Reader::new(&raw_transaction_data)).map_err(
+            |e| {
+                invalid_params("rawtxs", e)
+            },
+        ));
+        transactions.push(transaction);
+    }
+    Ok(transactions)
+}
+
 #[cfg(test)]
 pub mod tests {
+    extern crate test_data;
+
     use jsonrpc_macros::Trailing;
     use jsonrpc_core::IoHandler;
     use chain::Transaction;
     use primitives::hash::H256 as GlobalH256;
     use v1::traits::Raw;
     use v1::types::{TransactionInput, TransactionOutputs};
+    use db::BlockChainDatabase;
+    use chain::IndexedBlock;
+    use network::AddressPrefixes;
     use super::*;
 
     #[derive(Default)]
@@ -351,8 +651,33 @@ pub mod tests {
         ) -> Result<Transaction, String> {
             Ok("0100000001ad9d38823d95f31dc6c0cb0724c11a3cf5a466ca4147254a10cd94aade6eb5b3230000006b483045022100b7683165c3ecd57b0c44bf6a0fb258dc08c328458321c8fadc2b9348d4e66bd502204fd164c58d1a949a4d39bb380f8f05c9f6b3e9417f06bf72e5c068428ca3578601210391c35ac5ee7cf82c5015229dcff89507f83f9b8c952b8fecfa469066c1cb44ccffffffff0170f30500000000001976a914801da3cb2ed9e44540f4b982bde07cd3fbae264288ac00000000".into())
         }
-        fn get_raw_transaction(&self, _hash: H256, _verbose: Trailing<bool>)
+        fn get_raw_transaction(&self, _hash: GlobalH256, _verbose: bool, _block_hash: Option<GlobalH256>)
            -> Result<GetRawTransactionResponse, Error> {unreachable!();}
+
+        fn test_accept_transactions(&self, transactions: Vec<Transaction>) -> Vec<sync::MemoryPoolAcceptResult> {
+            transactions.into_iter().map(|transaction| sync::MemoryPoolAcceptResult {
+                hash: transaction.hash(),
+                vsize: 60,
+                fee: Some(10000),
+                reject_reason: None,
+            }).collect()
+        }
+
+        fn submit_package(&self, transactions: Vec<Transaction>) -> Result<sync::PackageAcceptResult, String> {
+            Ok(sync::PackageAcceptResult {
+                transactions: self.test_accept_transactions(transactions),
+                fee_rate: Some(100000),
+                reject_reason: None,
+            })
+        }
+
+        fn spent_info(&self, _prevout: OutPoint) -> Result<SpentInfoResponse, Error> {
+            Ok(SpentInfoResponse {
+                txid: "3ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a".into(),
+                index: 1,
+                height: Some(100),
+            })
+        }
     }
 
     impl RawClientCoreApi for ErrorRawClientCore {
@@ -368,8 +693,25 @@ pub mod tests {
         ) -> Result<Transaction, String> {
             Err("error".to_owned())
         }
-        fn get_raw_transaction(&self, _hash: H256, _verbose: Trailing<bool>)
+        fn get_raw_transaction(&self, _hash: GlobalH256, _verbose: bool, _block_hash: Option<GlobalH256>)
            -> Result<GetRawTransactionResponse, Error> {unreachable!();}
+
+        fn test_accept_transactions(&self, transactions: Vec<Transaction>) -> Vec<sync::MemoryPoolAcceptResult> {
+            transactions.into_iter().map(|transaction| sync::MemoryPoolAcceptResult {
+                hash: transaction.hash(),
+                vsize: 60,
+                fee: None,
+                reject_reason: Some(sync::MemoryPoolRejectReason::AlreadyInMemoryPool),
+            }).collect()
+        }
+
+        fn submit_package(&self, _transactions: Vec<Transaction>) -> Result<sync::PackageAcceptResult, String> {
+            Err("error".to_owned())
+        }
+
+        fn spent_info(&self, _prevout: OutPoint) -> Result<SpentInfoResponse, Error> {
+            Err(execution(storage::Error::SpentIndexDisabled))
+        }
     }
 
     #[test]
@@ -445,4 +787,184 @@ pub mod tests {
 
         assert_eq!(r#"{"jsonrpc":"2.0","error":{"code":-32015,"message":"Execution error.","data":"\"error\""},"id":1}"#, &sample);
     }
+
+    #[test]
+    fn testmempoolaccept_accepted() {
+        let client = RawClient::new(SuccessRawClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler.handle_request_sync(&(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "testmempoolaccept",
+				"params": [["00000000013ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a0000000000000000000101000000000000000000000000"]],
+				"id": 1
+			}"#)
+		).unwrap();
+
+        assert_eq!(r#"{"jsonrpc":"2.0","result":[{"allowed":true,"fees":{"base":0.0001},"txid":"34acbc23f60064e0a6e45d9633f5efa5eb068188ff231050fec535d0ccef9107","vsize":60}],"id":1}"#, &sample);
+    }
+
+    #[test]
+    fn testmempoolaccept_rejected() {
+        let client = RawClient::new(ErrorRawClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler.handle_request_sync(&(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "testmempoolaccept",
+				"params": [["00000000013ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a0000000000000000000101000000000000000000000000"]],
+				"id": 1
+			}"#)
+		).unwrap();
+
+        assert_eq!(r#"{"jsonrpc":"2.0","result":[{"allowed":false,"reject-reason":"txn-already-in-mempool","txid":"34acbc23f60064e0a6e45d9633f5efa5eb068188ff231050fec535d0ccef9107"}],"id":1}"#, &sample);
+    }
+
+    #[test]
+    fn submitpackage_accepted() {
+        let client = RawClient::new(SuccessRawClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler.handle_request_sync(&(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "submitpackage",
+				"params": [["00000000013ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a0000000000000000000101000000000000000000000000"]],
+				"id": 1
+			}"#)
+		).unwrap();
+
+        assert_eq!(r#"{"jsonrpc":"2.0","result":{"package_feerate":0.001,"package_msg":"success","tx-results":{"34acbc23f60064e0a6e45d9633f5efa5eb068188ff231050fec535d0ccef9107":{"fees":{"base":0.0001},"txid":"34acbc23f60064e0a6e45d9633f5efa5eb068188ff231050fec535d0ccef9107","vsize":60}}},"id":1}"#, &sample);
+    }
+
+    #[test]
+    fn submitpackage_rejected() {
+        let client = RawClient::new(ErrorRawClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler.handle_request_sync(&(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "submitpackage",
+				"params": [["00000000013ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a0000000000000000000101000000000000000000000000"]],
+				"id": 1
+			}"#)
+		).unwrap();
+
+        assert_eq!(r#"{"jsonrpc":"2.0","error":{"code":-32015,"message":"Execution error.","data":"\"error\""},"id":1}"#, &sample);
+    }
+
+    #[test]
+    fn getspentinfo_success() {
+        let client = RawClient::new(SuccessRawClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler.handle_request_sync(&(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "getspentinfo",
+				"params": [{"txid":"4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b","index":0}],
+				"id": 1
+			}"#)
+		).unwrap();
+
+        assert_eq!(r#"{"jsonrpc":"2.0","result":{"height":100,"index":1,"txid":"4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"},"id":1}"#, &sample);
+    }
+
+    #[test]
+    fn getspentinfo_index_disabled() {
+        let client = RawClient::new(ErrorRawClientCore::default());
+        let mut handler = IoHandler::new();
+        handler.extend_with(client.to_delegate());
+
+        let sample = handler.handle_request_sync(&(r#"
+			{
+				"jsonrpc": "2.0",
+				"method": "getspentinfo",
+				"params": [{"txid":"4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b","index":0}],
+				"id": 1
+			}"#)
+		).unwrap();
+
+        assert_eq!(r#"{"jsonrpc":"2.0","error":{"code":-32015,"message":"Execution error.","data":"SpentIndexDisabled"},"id":1}"#, &sample);
+    }
+
+    #[test]
+    fn getrawtransaction_verbose_contents() {
+        let b0: IndexedBlock = test_data::block_builder()
+            .header().time(100).build()
+            .transaction()
+                .coinbase()
+                .output().value(50).build()
+                .build()
+            .build()
+            .into();
+        let b1: IndexedBlock = test_data::block_builder()
+            .header().parent(b0.hash().clone()).time(200).build()
+            .transaction()
+                .coinbase()
+                .output().value(10).build()
+                .build()
+            .transaction()
+                .input().hash(b0.transactions[0].hash.clone()).build()
+                .output().value(40).build()
+                .build()
+            .build()
+            .into();
+        let mempool_tx: Transaction = test_data::TransactionBuilder::with_output(5)
+            .add_input(&b1.transactions[1].raw, 0)
+            .into();
+        let storage = BlockChainDatabase::init_test_chain(vec![b0.clone(), b1.clone()]);
+        let memory_pool_transaction = |hash: &GlobalH256| if *hash == mempool_tx.hash() { Some(mempool_tx.clone()) } else { None };
+
+        // coinbase transaction, found using location index
+        let coinbase = match do_get_raw_transaction(&storage, &memory_pool_transaction, AddressPrefixes::Mainnet,
+            b1.transactions[0].hash.clone(), true, None).unwrap() {
+            GetRawTransactionResponse::Verbose(transaction) => transaction,
+            _ => panic!("expected verbose transaction"),
+        };
+        assert_eq!(coinbase.in_active_chain, None);
+        assert_eq!(coinbase.blockhash, Some(H256::from(b1.hash().clone()).reversed()));
+        assert_eq!(coinbase.confirmations, Some(1));
+        assert_eq!(coinbase.blocktime, Some(200));
+        assert!(coinbase.vin[0].coinbase.is_some());
+        assert_eq!(coinbase.vin[0].txid, None);
+        assert_eq!(coinbase.fee, None);
+
+        // regular transaction in the explicitly requested block
+        let transaction = match do_get_raw_transaction(&storage, &memory_pool_transaction, AddressPrefixes::Mainnet,
+            b1.transactions[1].hash.clone(), true, Some(b1.hash().clone())).unwrap() {
+            GetRawTransactionResponse::Verbose(transaction) => transaction,
+            _ => panic!("expected verbose transaction"),
+        };
+        assert_eq!(transaction.in_active_chain, Some(true));
+        assert_eq!(transaction.fee, Some(0.00000001f64 * 10.0));
+        assert_eq!(transaction.vout[0].n, 0);
+        let prevout = transaction.vin[0].prevout.as_ref().unwrap();
+        assert!(prevout.generated);
+        assert_eq!(prevout.height, 0);
+        assert_eq!(prevout.value, 0.00000001f64 * 50.0);
+
+        // memory pool transaction
+        let transaction = match do_get_raw_transaction(&storage, &memory_pool_transaction, AddressPrefixes::Mainnet,
+            mempool_tx.hash(), true, None).unwrap() {
+            GetRawTransactionResponse::Verbose(transaction) => transaction,
+            _ => panic!("expected verbose transaction"),
+        };
+        assert_eq!(transaction.blockhash, None);
+        assert_eq!(transaction.confirmations, None);
+        assert_eq!(transaction.fee, Some(0.00000001f64 * 35.0));
+        assert_eq!(transaction.vin[0].prevout.as_ref().unwrap().height, 1);
+
+        // transaction is not in the requested block
+        assert!(do_get_raw_transaction(&storage, &memory_pool_transaction, AddressPrefixes::Mainnet,
+            b1.transactions[1].hash.clone(), true, Some(b0.hash().clone())).is_err());
+    }
 }
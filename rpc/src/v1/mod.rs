@@ -8,7 +8,13 @@ pub use self::traits::Raw;
 pub use self::traits::Miner;
 pub use self::traits::BlockChain;
 pub use self::traits::Network;
+pub use self::traits::MemoryPool;
+pub use self::traits::Generate;
+pub use self::traits::PubSub;
 pub use self::impls::{RawClient, RawClientCore, SimpleClientCore};
 pub use self::impls::{MinerClient, MinerClientCore};
 pub use self::impls::{BlockChainClient, BlockChainClientCore};
 pub use self::impls::{NetworkClient, NetworkClientCore};
+pub use self::impls::{MemoryPoolClient, MemoryPoolClientCore};
+pub use self::impls::{GenerateClient, GenerateClientCore, SimpleGenerateClientCore};
+pub use self::impls::{PubSubClient, PubSubNotifier, Subscriptions};
@@ -2,15 +2,23 @@ use jsonrpc_macros::Trailing;
 use jsonrpc_core::Error;
 
 use v1::types::H256;
+use v1::types::BlockchainInfo;
 use v1::types::GetBlockResponse;
 use v1::types::GetTxOutResponse;
 use v1::types::GetTxOutSetInfoResponse;
 use v1::types::RawTransaction;
+use v1::types::{DumpTxOutSetResponse, ChainStates};
+use v1::types::ChainTip;
+use v1::types::VerifyChainResponse;
 
 
 build_rpc_trait! {
 	/// Parity-bitcoin blockchain data interface.
 	pub trait BlockChain {
+		/// Get state of the blockchain and status of known softforks.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "getblockchaininfo", "params": [], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "getblockchaininfo")]
+		fn blockchain_info(&self) -> Result<BlockchainInfo, Error>;
 		/// Get hash of best block.
 		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "getbestblockhash", "params": [], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
 		#[rpc(name = "getbestblockhash")]
@@ -39,6 +47,34 @@ build_rpc_trait! {
 		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "gettxoutsetinfo", "params": [], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
 		#[rpc(name = "gettxoutsetinfo")]
 		fn transaction_out_set_info(&self) -> Result<GetTxOutSetInfoResponse, Error>;
+		/// Prune blockchain up to given height. Returns height of the last pruned block.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "pruneblockchain", "params": [100000], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "pruneblockchain")]
+		fn prune_blockchain(&self, u32) -> Result<Option<u32>, Error>;
+		/// Write UTXO set snapshot at the best block to the file. Relative path is resolved against the data directory.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "dumptxoutset", "params": ["utxo.dat"], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "dumptxoutset")]
+		fn dump_tx_out_set(&self, String) -> Result<DumpTxOutSetResponse, Error>;
+		/// Get state of the chainstates: background chainstate (if it is validating UTXO set snapshot) and the active one.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "getchainstates", "params": [], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "getchainstates")]
+		fn chain_states(&self) -> Result<ChainStates, Error>;
+		/// Get information about all known tips of the block tree, including the best chain tip and side branches.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "getchaintips", "params": [], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "getchaintips")]
+		fn chain_tips(&self) -> Result<Vec<ChainTip>, Error>;
+		/// Mark block and all its descendants invalid and switch to the best valid branch.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "invalidateblock", "params": ["000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "invalidateblock")]
+		fn invalidate_block(&self, H256) -> Result<(), Error>;
+		/// Remove invalidity marks from the block, its ancestors and descendants and switch to the best valid branch.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "reconsiderblock", "params": ["000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "reconsiderblock")]
+		fn reconsider_block(&self, H256) -> Result<(), Error>;
+		/// Verify consistency of the stored best chain blocks. Check level (0-4, default 3) and number of the checked blocks (default 6, 0 - all blocks) are optional.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "verifychain", "params": [3, 6], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "verifychain")]
+		fn verify_chain(&self, Trailing<u32>, Trailing<u32>) -> Result<VerifyChainResponse, Error>;
 		#[rpc(name = "signrawtransaction")]
 		fn sign_raw_transaction(&self, RawTransaction, H256) -> Result<RawTransaction, Error>;
 	}
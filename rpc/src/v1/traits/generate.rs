@@ -0,0 +1,23 @@
+use jsonrpc_core::Error;
+use jsonrpc_macros::Trailing;
+
+use v1::types::{H256, GenerateBlockResponse};
+
+build_rpc_trait! {
+	/// Parity-bitcoin regtest blocks generation interface.
+	pub trait Generate {
+		/// Mine blocks immediately, paying coinbase to given address. Returns hashes of generated blocks.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "generatetoaddress", "params": [1, "mkmZxiEcEd8ZqjQWVZuC6so5dFMKEFpN2j"], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:18443/
+		#[rpc(name = "generatetoaddress")]
+		fn generate_to_address(&self, u32, String, Trailing<u64>) -> Result<Vec<H256>, Error>;
+		/// Mine blocks immediately, paying coinbase to given output descriptor. Returns hashes of generated blocks.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "generatetodescriptor", "params": [1, "raw(51)"], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:18443/
+		#[rpc(name = "generatetodescriptor")]
+		fn generate_to_descriptor(&self, u32, String, Trailing<u64>) -> Result<Vec<H256>, Error>;
+		/// Mine block with given transactions immediately, paying coinbase to given address or descriptor.
+		/// Transactions are given either as memory pool transaction ids, or as raw transactions.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "generateblock", "params": ["mkmZxiEcEd8ZqjQWVZuC6so5dFMKEFpN2j", []], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:18443/
+		#[rpc(name = "generateblock")]
+		fn generate_block(&self, String, Vec<String>) -> Result<GenerateBlockResponse, Error>;
+	}
+}
@@ -0,0 +1,31 @@
+use jsonrpc_macros::Trailing;
+use jsonrpc_core::Error;
+
+use v1::types::H256;
+use v1::types::{MemoryPoolInfo, MemoryPoolEntry, GetRawMemoryPoolResponse};
+
+build_rpc_trait! {
+	/// Parity-bitcoin memory pool data interface.
+	pub trait MemoryPool {
+		/// Get information on current memory pool state.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "getmempoolinfo", "params": [], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "getmempoolinfo")]
+		fn memory_pool_info(&self) -> Result<MemoryPoolInfo, Error>;
+		/// Get hashes (or verbose information) of all memory pool transactions.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "getrawmempool", "params": [true], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "getrawmempool")]
+		fn raw_memory_pool(&self, Trailing<bool>) -> Result<GetRawMemoryPoolResponse, Error>;
+		/// Get information on given memory pool transaction.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "getmempoolentry", "params": ["4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "getmempoolentry")]
+		fn memory_pool_entry(&self, H256) -> Result<MemoryPoolEntry, Error>;
+		/// Get hashes (or verbose information) of all in-pool ancestors of given memory pool transaction.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "getmempoolancestors", "params": ["4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "getmempoolancestors")]
+		fn memory_pool_ancestors(&self, H256, Trailing<bool>) -> Result<GetRawMemoryPoolResponse, Error>;
+		/// Get hashes (or verbose information) of all in-pool descendants of given memory pool transaction.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "getmempooldescendants", "params": ["4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "getmempooldescendants")]
+		fn memory_pool_descendants(&self, H256, Trailing<bool>) -> Result<GetRawMemoryPoolResponse, Error>;
+	}
+}
@@ -1,6 +1,8 @@
 use jsonrpc_core::Error;
+use jsonrpc_macros::Trailing;
 
-use v1::types::{BlockTemplate, BlockTemplateRequest};
+use v1::types::{H256, Bytes, RawBlock, BlockTemplate, BlockTemplateRequest, EstimateMode,
+	EstimateSmartFeeResponse, EstimateRawFeeResponse, MiningInfo};
 
 build_rpc_trait! {
 	/// Parity-bitcoin miner data interface.
@@ -9,5 +11,29 @@ build_rpc_trait! {
 		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "getblocktemplate", "params": [{"capabilities": ["coinbasetxn", "workid", "coinbase/append"]}], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
 		#[rpc(name = "getblocktemplate")]
 		fn get_block_template(&self, BlockTemplateRequest) -> Result<BlockTemplate, Error>;
+		/// Verify and then insert new block. Returns null on success or BIP22 reject reason otherwise.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "submitblock", "params": ["0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c0101000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000"], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "submitblock")]
+		fn submit_block(&self, RawBlock) -> Result<Option<String>, Error>;
+		/// Verify block header and then schedule downloading of this block.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "submitheader", "params": ["0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c"], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "submitheader")]
+		fn submit_header(&self, Bytes) -> Result<(), Error>;
+		/// Get mining-related information.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "getmininginfo", "params": [], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "getmininginfo")]
+		fn mining_info(&self) -> Result<MiningInfo, Error>;
+		/// Estimate fee rate, required for transaction to be confirmed within given number of blocks.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "estimatesmartfee", "params": [6, "CONSERVATIVE"], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "estimatesmartfee")]
+		fn estimate_smart_fee(&self, u32, Trailing<EstimateMode>) -> Result<EstimateSmartFeeResponse, Error>;
+		/// Get raw fee estimation data for every time horizon, which supports given target.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "estimaterawfee", "params": [6, 0.95], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "estimaterawfee")]
+		fn estimate_raw_fee(&self, u32, Trailing<f64>) -> Result<EstimateRawFeeResponse, Error>;
+		/// Change mining priority of memory pool transaction by adding fee delta (in satoshis) to its fee.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "prioritisetransaction", "params": ["4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b", 0, 10000], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "prioritisetransaction")]
+		fn prioritise_transaction(&self, H256, Option<f64>, i64) -> Result<bool, Error>;
 	}
 }
@@ -2,8 +2,14 @@ mod blockchain;
 mod miner;
 mod raw;
 mod network;
+mod memory_pool;
+mod generate;
+mod pubsub;
 
 pub use self::blockchain::BlockChain;
 pub use self::miner::Miner;
 pub use self::raw::Raw;
 pub use self::network::Network;
+pub use self::memory_pool::MemoryPool;
+pub use self::generate::Generate;
+pub use self::pubsub::PubSub;
@@ -0,0 +1,24 @@
+use jsonrpc_core::Error;
+use jsonrpc_macros::{pubsub, Trailing};
+use jsonrpc_pubsub::SubscriptionId;
+use v1::types::{SubscriptionKind, SubscriptionParams, PubSubNotification};
+
+build_rpc_trait! {
+	/// Parity-bitcoin publish-subscribe interface (WebSocket only)
+	pub trait PubSub {
+		type Metadata;
+
+		#[pubsub(name = "subscription")] {
+			/// Subscribe to notifications
+			/// @wscat-example: {"jsonrpc": "2.0", "method": "subscribe", "params": ["newHeads"], "id":1 }
+			/// @wscat-example: {"jsonrpc": "2.0", "method": "subscribe", "params": ["addressTransactions", {"addresses": ["1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa"]}], "id":1 }
+			#[rpc(name = "subscribe")]
+			fn subscribe(&self, Self::Metadata, pubsub::Subscriber<PubSubNotification>, SubscriptionKind, Trailing<SubscriptionParams>);
+
+			/// Unsubscribe from notifications
+			/// @wscat-example: {"jsonrpc": "2.0", "method": "unsubscribe", "params": [1], "id":1 }
+			#[rpc(name = "unsubscribe")]
+			fn unsubscribe(&self, SubscriptionId) -> Result<bool, Error>;
+		}
+	}
+}
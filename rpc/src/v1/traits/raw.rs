@@ -7,6 +7,9 @@ use v1::types::Transaction;
 use v1::types::TransactionInput;
 use v1::types::TransactionOutputs;
 use v1::types::GetRawTransactionResponse;
+use v1::types::TestMemoryPoolAcceptResult;
+use v1::types::SubmitPackageResponse;
+use v1::types::{SpentInfoRequest, SpentInfoResponse};
 
 build_rpc_trait! {
 	/// Parity-bitcoin raw data interface.
@@ -23,10 +26,26 @@ build_rpc_trait! {
 		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "decoderawtransaction", "params": ["01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000"], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
 		#[rpc(name = "decoderawtransaction")]
 		fn decode_raw_transaction(&self, RawTransaction) -> Result<Transaction, Error>;
-		/// Return the raw transaction data.
-		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "getrawtransaction", "params": ["4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		/// Return the raw transaction data. Transaction is searched in the canon chain and in the memory pool,
+		/// or in the given block (which may be a side chain block) if block hash is provided.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "getrawtransaction", "params": ["4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b", true, "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
 		#[rpc(name = "getrawtransaction")]
-		fn get_raw_transaction(&self, H256, Trailing<bool>) -> Result<GetRawTransactionResponse, Error>;
+		fn get_raw_transaction(&self, H256, Trailing<bool>, Trailing<H256>) -> Result<GetRawTransactionResponse, Error>;
+		/// Returns results of memory pool acceptance tests of raw transactions, without adding them to the memory pool.
+		/// Transactions may depend on previous transactions from the same list.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "testmempoolaccept", "params": [["01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000"]], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "testmempoolaccept")]
+		fn test_memory_pool_accept(&self, Vec<RawTransaction>) -> Result<Vec<TestMemoryPoolAcceptResult>, Error>;
+		/// Validates package of raw transactions (topologically sorted parents followed by the child) against
+		/// package fee rate and adds them to the memory pool.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "submitpackage", "params": [["01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000"]], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "submitpackage")]
+		fn submit_package(&self, Vec<RawTransaction>) -> Result<SubmitPackageResponse, Error>;
+		/// Returns transaction input, which spends given output. Requires spent outputs index to be enabled
+		/// (`--spentindex`). Memory pool spends are reported without height.
+		/// @curl-example: curl --data-binary '{"jsonrpc": "2.0", "method": "getspentinfo", "params": [{"txid":"4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b","index":0}], "id":1 }' -H 'content-type: application/json' http://127.0.0.1:8332/
+		#[rpc(name = "getspentinfo")]
+		fn get_spent_info(&self, SpentInfoRequest) -> Result<SpentInfoResponse, Error>;
 
 	}
 }
@@ -26,6 +26,8 @@ pub struct BlockTemplateRequest {
 	pub capabilities: Option<HashSet<String>>,
 	/// Softfork deployments, supported by client
 	pub rules: Option<HashSet<String>>,
+	/// Id of the previously received template: request is delayed until the template changes
+	pub longpollid: Option<String>,
 }
 
 #[cfg(test)]
@@ -47,12 +49,13 @@ mod tests {
 
 	#[test]
 	fn block_template_request_serialize() {
-		assert_eq!(serde_json::to_string(&BlockTemplateRequest::default()).unwrap(), r#"{"mode":null,"capabilities":null,"rules":null}"#);
+		assert_eq!(serde_json::to_string(&BlockTemplateRequest::default()).unwrap(), r#"{"mode":null,"capabilities":null,"rules":null,"longpollid":null}"#);
 		assert_eq!(serde_json::to_string(&BlockTemplateRequest {
 			mode: Some(BlockTemplateRequestMode::Template),
 			capabilities: Some(vec!["a".to_owned()].into_iter().collect()),
 			rules: Some(vec!["b".to_owned()].into_iter().collect()),
-		}).unwrap(), r#"{"mode":"template","capabilities":["a"],"rules":["b"]}"#);
+			longpollid: Some("c".to_owned()),
+		}).unwrap(), r#"{"mode":"template","capabilities":["a"],"rules":["b"],"longpollid":"c"}"#);
 	}
 
 	#[test]
@@ -63,13 +66,15 @@ mod tests {
 				mode: None,
 				capabilities: None,
 				rules: None,
+				longpollid: None,
 			});
 		assert_eq!(
-			serde_json::from_str::<BlockTemplateRequest>(r#"{"mode":"template","capabilities":["a"],"rules":["b"]}"#).unwrap(),
+			serde_json::from_str::<BlockTemplateRequest>(r#"{"mode":"template","capabilities":["a"],"rules":["b"],"longpollid":"c"}"#).unwrap(),
 			BlockTemplateRequest {
 				mode: Some(BlockTemplateRequestMode::Template),
 				capabilities: Some(vec!["a".to_owned()].into_iter().collect()),
 				rules: Some(vec!["b".to_owned()].into_iter().collect()),
+				longpollid: Some("c".to_owned()),
 			});
 	}
 }
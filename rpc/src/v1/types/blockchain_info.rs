@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+use super::hash::H256;
+use verification;
+
+/// getblockchaininfo response
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct BlockchainInfo {
+	/// Current network name (main, test, regtest)
+	pub chain: String,
+	/// Height of the best block
+	pub blocks: u32,
+	/// Height of the best known header
+	pub headers: u32,
+	/// Hash of the best block
+	pub bestblockhash: H256,
+	/// Proof-of-work difficulty of the best block as a multiple of the minimum difficulty
+	pub difficulty: f64,
+	/// Median time of the best block
+	pub mediantime: u32,
+	/// Estimate of the verification progress [0..1]
+	pub verificationprogress: f64,
+	/// Estimated size of the database on disk
+	pub size_on_disk: u64,
+	/// True if the blocks are subject to pruning
+	pub pruned: bool,
+	/// Height of the lowest unpruned block (only present if pruning is enabled)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub pruneheight: Option<u32>,
+	/// Status of known softforks
+	pub softforks: BTreeMap<String, Softfork>,
+}
+
+/// Softfork status
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Softfork {
+	/// Type of the softfork: buried or bip9
+	#[serde(rename = "type")]
+	pub softfork_type: SoftforkType,
+	/// Status of the BIP9 softfork
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub bip9: Option<Bip9Softfork>,
+	/// Activation height of the buried softfork
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub height: Option<u32>,
+	/// True if the rules are enforced for the next block
+	pub active: bool,
+}
+
+/// Softfork type
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SoftforkType {
+	/// Softfork with hardcoded activation height
+	Buried,
+	/// Softfork, deployed using BIP9 versionbits
+	Bip9,
+}
+
+/// Status of the BIP9 softfork
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Bip9Softfork {
+	/// Deployment state
+	pub status: Bip9SoftforkStatus,
+	/// Version bit, used to signal for this softfork
+	pub bit: u8,
+	/// Minimal median time, when signalling could start
+	pub start_time: u32,
+	/// Median time, when deployment is considered failed if it hasn't been locked in
+	pub timeout: u32,
+	/// Signalling statistics (only when deployment is started)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub statistics: Option<Bip9SoftforkStatistics>,
+}
+
+/// BIP9 deployment state
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Bip9SoftforkStatus {
+	Defined,
+	Started,
+	LockedIn,
+	Active,
+	Failed,
+}
+
+/// Signalling statistics of the current period
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Bip9SoftforkStatistics {
+	/// Length of the signalling period
+	pub period: u32,
+	/// Number of signalling blocks, required for lock in
+	pub threshold: u32,
+	/// Number of blocks, elapsed since the beginning of the current period
+	pub elapsed: u32,
+	/// Number of signalling blocks in the current period
+	pub count: u32,
+	/// False if there are not enough blocks left in the current period to lock in
+	pub possible: bool,
+}
+
+impl From<verification::ThresholdState> for Bip9SoftforkStatus {
+	fn from(state: verification::ThresholdState) -> Self {
+		match state {
+			verification::ThresholdState::Defined => Bip9SoftforkStatus::Defined,
+			verification::ThresholdState::Started => Bip9SoftforkStatus::Started,
+			verification::ThresholdState::LockedIn => Bip9SoftforkStatus::LockedIn,
+			verification::ThresholdState::Active => Bip9SoftforkStatus::Active,
+			verification::ThresholdState::Failed => Bip9SoftforkStatus::Failed,
+		}
+	}
+}
+
+impl From<verification::DeploymentStatistics> for Bip9SoftforkStatistics {
+	fn from(statistics: verification::DeploymentStatistics) -> Self {
+		Bip9SoftforkStatistics {
+			period: statistics.period,
+			threshold: statistics.threshold,
+			elapsed: statistics.elapsed,
+			count: statistics.count,
+			possible: statistics.possible,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::BTreeMap;
+	use serde_json;
+	use super::super::hash::H256;
+	use super::{BlockchainInfo, Softfork, SoftforkType, Bip9Softfork, Bip9SoftforkStatus, Bip9SoftforkStatistics};
+
+	#[test]
+	fn blockchain_info_serialize() {
+		let mut softforks = BTreeMap::new();
+		softforks.insert("csv".to_owned(), Softfork {
+			softfork_type: SoftforkType::Buried,
+			bip9: None,
+			height: Some(0),
+			active: true,
+		});
+		softforks.insert("segwit".to_owned(), Softfork {
+			softfork_type: SoftforkType::Bip9,
+			bip9: Some(Bip9Softfork {
+				status: Bip9SoftforkStatus::LockedIn,
+				bit: 1,
+				start_time: 0,
+				timeout: 4294967295,
+				statistics: None,
+			}),
+			height: None,
+			active: false,
+		});
+		softforks.insert("test".to_owned(), Softfork {
+			softfork_type: SoftforkType::Bip9,
+			bip9: Some(Bip9Softfork {
+				status: Bip9SoftforkStatus::Started,
+				bit: 2,
+				start_time: 0,
+				timeout: 4294967295,
+				statistics: Some(Bip9SoftforkStatistics {
+					period: 144,
+					threshold: 108,
+					elapsed: 10,
+					count: 5,
+					possible: true,
+				}),
+			}),
+			height: None,
+			active: false,
+		});
+		let info = BlockchainInfo {
+			chain: "regtest".into(),
+			blocks: 10,
+			headers: 10,
+			bestblockhash: H256::from(1),
+			difficulty: 1.0,
+			mediantime: 1000,
+			verificationprogress: 1.0,
+			size_on_disk: 4096,
+			pruned: false,
+			pruneheight: None,
+			softforks: softforks,
+		};
+		assert_eq!(serde_json::to_string(&info).unwrap(), r#"{"chain":"regtest","blocks":10,"headers":10,"bestblockhash":"0100000000000000000000000000000000000000000000000000000000000000","difficulty":1.0,"mediantime":1000,"verificationprogress":1.0,"size_on_disk":4096,"pruned":false,"softforks":{"csv":{"type":"buried","height":0,"active":true},"segwit":{"type":"bip9","bip9":{"status":"locked_in","bit":1,"start_time":0,"timeout":4294967295},"active":false},"test":{"type":"bip9","bip9":{"status":"started","bit":2,"start_time":0,"timeout":4294967295,"statistics":{"period":144,"threshold":108,"elapsed":10,"count":5,"possible":true}},"active":false}}}"#);
+	}
+}
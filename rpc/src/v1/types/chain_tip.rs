@@ -0,0 +1,48 @@
+use super::hash::H256;
+
+/// Status of the block tree tip.
+/// Blocks are fully verified before they're stored, so `valid-headers` status is never reported
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChainTipStatus {
+	/// Tip of the best chain
+	Active,
+	/// All blocks of the branch are stored and verified, but the branch is not the best one
+	ValidFork,
+	/// Headers of the branch are known, but not all of its blocks are stored
+	HeadersOnly,
+	/// Branch contains at least one invalid block
+	Invalid,
+}
+
+/// Tip of the block tree, reported by getchaintips
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ChainTip {
+	/// Height of the tip
+	pub height: u32,
+	/// Hash of the tip block
+	pub hash: H256,
+	/// Length of the branch, connecting the tip to the best chain (zero for the best chain tip)
+	pub branchlen: u32,
+	/// Status of the tip
+	pub status: ChainTipStatus,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::super::hash::H256;
+	use super::{ChainTip, ChainTipStatus};
+
+	#[test]
+	fn chain_tip_serialize() {
+		let tip = ChainTip {
+			height: 10,
+			hash: H256::from(1),
+			branchlen: 2,
+			status: ChainTipStatus::HeadersOnly,
+		};
+		assert_eq!(serde_json::to_string(&tip).unwrap(), r#"{"height":10,"hash":"0100000000000000000000000000000000000000000000000000000000000000","branchlen":2,"status":"headers-only"}"#);
+		assert_eq!(serde_json::to_string(&ChainTipStatus::ValidFork).unwrap(), r#""valid-fork""#);
+	}
+}
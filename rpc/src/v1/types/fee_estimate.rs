@@ -0,0 +1,203 @@
+use std::fmt;
+use serde::{Deserialize, Deserializer};
+use serde::de::Unexpected;
+use chain::constants::SATOSHIS_IN_COIN;
+use miner;
+
+/// Fee estimation mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EstimateMode {
+	/// Use default mode (conservative)
+	Unset,
+	/// Prefer responsiveness to short-term fee drops
+	Economical,
+	/// Prefer estimates that are less likely to be too low
+	Conservative,
+}
+
+impl Default for EstimateMode {
+	fn default() -> Self {
+		EstimateMode::Unset
+	}
+}
+
+impl From<EstimateMode> for miner::FeeEstimateMode {
+	fn from(mode: EstimateMode) -> Self {
+		match mode {
+			EstimateMode::Economical => miner::FeeEstimateMode::Economical,
+			EstimateMode::Unset | EstimateMode::Conservative => miner::FeeEstimateMode::Conservative,
+		}
+	}
+}
+
+impl<'a> Deserialize<'a> for EstimateMode {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'a> {
+		use serde::de::Visitor;
+
+		struct DummyVisitor;
+
+		impl<'b> Visitor<'b> for DummyVisitor {
+			type Value = EstimateMode;
+
+			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+				formatter.write_str("an estimate mode string")
+			}
+
+			fn visit_str<E>(self, value: &str) -> Result<EstimateMode, E> where E: ::serde::de::Error {
+				match &value.to_uppercase() as &str {
+					"UNSET" => Ok(EstimateMode::Unset),
+					"ECONOMICAL" => Ok(EstimateMode::Economical),
+					"CONSERVATIVE" => Ok(EstimateMode::Conservative),
+					_ => Err(E::invalid_value(Unexpected::Str(value), &self)),
+				}
+			}
+		}
+
+		deserializer.deserialize_identifier(DummyVisitor)
+	}
+}
+
+/// estimatesmartfee response
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct EstimateSmartFeeResponse {
+	/// Estimated fee rate in BTC/kB
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub feerate: Option<f64>,
+	/// Errors encountered during processing
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub errors: Option<Vec<String>>,
+	/// Block number where estimate was found
+	pub blocks: u32,
+}
+
+/// Fee rate range statistics, used in estimaterawfee response
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct FeeRateBucket {
+	/// Start of fee rate range (BTC/kB)
+	pub startrange: f64,
+	/// End of fee rate range (BTC/kB)
+	pub endrange: f64,
+	/// Number of transactions in fee rate range that were confirmed within target
+	pub withintarget: f64,
+	/// Number of transactions in fee rate range that were confirmed at any point
+	pub totalconfirmed: f64,
+	/// Current number of transactions in fee rate range that are in the memory pool for at least target blocks
+	pub inmempool: f64,
+	/// Number of transactions in fee rate range that left the memory pool unconfirmed after target
+	pub leftmempool: f64,
+}
+
+/// Single horizon estimate, used in estimaterawfee response
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct RawFeeEstimate {
+	/// Estimated fee rate in BTC/kB
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub feerate: Option<f64>,
+	/// Exponential decay (per block) for historical moving average of confirmation data
+	pub decay: f64,
+	/// The resolution of confirmation targets at this time horizon
+	pub scale: u32,
+	/// Information about the lowest range of fee rates to succeed in meeting the threshold
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub pass: Option<FeeRateBucket>,
+	/// Information about the highest range of fee rates to fail to meet the threshold
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub fail: Option<FeeRateBucket>,
+	/// Errors encountered during processing
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub errors: Option<Vec<String>>,
+}
+
+/// estimaterawfee response
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct EstimateRawFeeResponse {
+	/// Estimate for short time horizon
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub short: Option<RawFeeEstimate>,
+	/// Estimate for medium time horizon
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub medium: Option<RawFeeEstimate>,
+	/// Estimate for long time horizon
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub long: Option<RawFeeEstimate>,
+}
+
+/// Convert fee rate from satoshis per 1000 bytes to BTC per 1000 bytes
+pub fn fee_rate_to_btc(fee_rate: u64) -> f64 {
+	fee_rate as f64 / SATOSHIS_IN_COIN as f64
+}
+
+impl From<miner::FeeEstimate> for EstimateSmartFeeResponse {
+	fn from(estimate: miner::FeeEstimate) -> Self {
+		EstimateSmartFeeResponse {
+			feerate: estimate.fee_rate.map(fee_rate_to_btc),
+			errors: match estimate.fee_rate {
+				Some(_) => None,
+				None => Some(vec!["Insufficient data or no feerate found".into()]),
+			},
+			blocks: estimate.blocks,
+		}
+	}
+}
+
+impl From<miner::EstimatorBucket> for FeeRateBucket {
+	fn from(bucket: miner::EstimatorBucket) -> Self {
+		FeeRateBucket {
+			startrange: bucket.start / SATOSHIS_IN_COIN as f64,
+			endrange: bucket.end / SATOSHIS_IN_COIN as f64,
+			withintarget: bucket.within_target,
+			totalconfirmed: bucket.total_confirmed,
+			inmempool: bucket.in_mempool,
+			leftmempool: bucket.left_mempool,
+		}
+	}
+}
+
+impl From<miner::EstimationResult> for RawFeeEstimate {
+	fn from(result: miner::EstimationResult) -> Self {
+		let has_fail = result.fail.total_confirmed != 0f64 || result.fail.end != 0f64;
+		RawFeeEstimate {
+			feerate: result.fee_rate.map(fee_rate_to_btc),
+			decay: result.decay,
+			scale: result.scale,
+			errors: match result.fee_rate {
+				Some(_) => None,
+				None => Some(vec!["Insufficient data or no feerate found which meets threshold".into()]),
+			},
+			pass: match result.fee_rate {
+				Some(_) => Some(result.pass.into()),
+				None => None,
+			},
+			fail: if has_fail { Some(result.fail.into()) } else { None },
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::*;
+
+	#[test]
+	fn estimate_mode_deserialize() {
+		assert_eq!(serde_json::from_str::<EstimateMode>(r#""ECONOMICAL""#).unwrap(), EstimateMode::Economical);
+		assert_eq!(serde_json::from_str::<EstimateMode>(r#""conservative""#).unwrap(), EstimateMode::Conservative);
+		assert_eq!(serde_json::from_str::<EstimateMode>(r#""unset""#).unwrap(), EstimateMode::Unset);
+		assert!(serde_json::from_str::<EstimateMode>(r#""fast""#).is_err());
+	}
+
+	#[test]
+	fn estimate_smart_fee_response_serialize() {
+		let response: EstimateSmartFeeResponse = miner::FeeEstimate {
+			fee_rate: Some(12_345),
+			blocks: 2,
+		}.into();
+		assert_eq!(serde_json::to_string(&response).unwrap(), r#"{"feerate":0.00012345,"blocks":2}"#);
+
+		let response: EstimateSmartFeeResponse = miner::FeeEstimate {
+			fee_rate: None,
+			blocks: 1008,
+		}.into();
+		assert_eq!(serde_json::to_string(&response).unwrap(), r#"{"errors":["Insufficient data or no feerate found"],"blocks":1008}"#);
+	}
+}
@@ -0,0 +1,23 @@
+use super::hash::H256;
+
+/// generateblock response
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct GenerateBlockResponse {
+	/// Hash of the generated block
+	pub hash: H256,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::GenerateBlockResponse;
+	use v1::types::H256;
+
+	#[test]
+	fn generate_block_response_serialize() {
+		let response = GenerateBlockResponse {
+			hash: H256::from(1),
+		};
+		assert_eq!(serde_json::to_string(&response).unwrap(), r#"{"hash":"0100000000000000000000000000000000000000000000000000000000000000"}"#);
+	}
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use serde::{Serialize, Serializer};
+use chain::constants::SATOSHIS_IN_COIN;
+use miner;
+use super::hash::H256;
+
+/// getmempoolinfo response
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct MemoryPoolInfo {
+	/// Current number of transactions in the memory pool
+	pub size: usize,
+	/// Sum of all transactions sizes
+	pub bytes: usize,
+	/// Total memory usage of the memory pool
+	pub usage: usize,
+}
+
+/// Memory pool entry, used in getmempoolentry and verbose getrawmempool responses
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct MemoryPoolEntry {
+	/// Transaction size
+	pub size: usize,
+	/// Transaction fee in BTC
+	pub fee: f64,
+	/// Transaction fee with fee deltas used for mining priority in BTC
+	pub modifiedfee: f64,
+	/// Local time transaction entered pool in seconds since epoch (Jan 1 1970 GMT)
+	pub time: u32,
+	/// Number of in-pool descendant transactions (including this one)
+	pub descendantcount: usize,
+	/// Size of in-pool descendants (including this one)
+	pub descendantsize: usize,
+	/// Modified fees (see above) of in-pool descendants (including this one) in satoshis
+	pub descendantfees: i64,
+	/// Number of in-pool ancestor transactions (including this one)
+	pub ancestorcount: usize,
+	/// Size of in-pool ancestors (including this one)
+	pub ancestorsize: usize,
+	/// Modified fees (see above) of in-pool ancestors (including this one) in satoshis
+	pub ancestorfees: i64,
+	/// Unconfirmed transactions used as inputs for this transaction
+	pub depends: Vec<H256>,
+	/// Unconfirmed transactions spending outputs from this transaction
+	pub spentby: Vec<H256>,
+}
+
+/// Response to getrawmempool, getmempoolancestors and getmempooldescendants RPC requests
+#[derive(Debug)]
+pub enum GetRawMemoryPoolResponse {
+	/// When asking for short response
+	Hashes(Vec<H256>),
+	/// When asking for verbose response
+	Verbose(HashMap<H256, MemoryPoolEntry>),
+}
+
+impl Serialize for GetRawMemoryPoolResponse {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+		match *self {
+			GetRawMemoryPoolResponse::Hashes(ref hashes) => hashes.serialize(serializer),
+			GetRawMemoryPoolResponse::Verbose(ref entries) => entries.serialize(serializer),
+		}
+	}
+}
+
+impl From<miner::MemoryPoolInformation> for MemoryPoolInfo {
+	fn from(information: miner::MemoryPoolInformation) -> Self {
+		MemoryPoolInfo {
+			size: information.transactions_count,
+			bytes: information.transactions_size_in_bytes,
+			usage: information.memory_usage_in_bytes,
+		}
+	}
+}
+
+impl From<miner::MemoryPoolEntryInformation> for MemoryPoolEntry {
+	fn from(entry: miner::MemoryPoolEntryInformation) -> Self {
+		MemoryPoolEntry {
+			size: entry.size,
+			fee: entry.fee as f64 / SATOSHIS_IN_COIN as f64,
+			modifiedfee: entry.modified_fee as f64 / SATOSHIS_IN_COIN as f64,
+			time: entry.time,
+			descendantcount: entry.descendant_count,
+			descendantsize: entry.descendant_size,
+			descendantfees: entry.descendant_fees,
+			ancestorcount: entry.ancestor_count,
+			ancestorsize: entry.ancestor_size,
+			ancestorfees: entry.ancestor_fees,
+			depends: entry.depends.into_iter().map(|hash| hash.reversed().into()).collect(),
+			spentby: entry.spent_by.into_iter().map(|hash| hash.reversed().into()).collect(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::super::hash::H256;
+	use super::*;
+
+	#[test]
+	fn memory_pool_entry_serialize() {
+		let entry = MemoryPoolEntry {
+			size: 100,
+			fee: 0.0001,
+			modifiedfee: 0.0002,
+			time: 1000,
+			descendantcount: 2,
+			descendantsize: 300,
+			descendantfees: 30000,
+			ancestorcount: 1,
+			ancestorsize: 100,
+			ancestorfees: 20000,
+			depends: vec![],
+			spentby: vec![H256::from(1)],
+		};
+		assert_eq!(serde_json::to_string(&entry).unwrap(), r#"{"size":100,"fee":0.0001,"modifiedfee":0.0002,"time":1000,"descendantcount":2,"descendantsize":300,"descendantfees":30000,"ancestorcount":1,"ancestorsize":100,"ancestorfees":20000,"depends":[],"spentby":["0100000000000000000000000000000000000000000000000000000000000000"]}"#);
+	}
+
+	#[test]
+	fn get_raw_memory_pool_response_serialize() {
+		let response = GetRawMemoryPoolResponse::Hashes(vec![H256::from(1)]);
+		assert_eq!(serde_json::to_string(&response).unwrap(), r#"["0100000000000000000000000000000000000000000000000000000000000000"]"#);
+
+		let mut entries = HashMap::new();
+		entries.insert(H256::from(1), MemoryPoolEntry::default());
+		let response = GetRawMemoryPoolResponse::Verbose(entries);
+		assert_eq!(serde_json::to_string(&response).unwrap(), r#"{"0100000000000000000000000000000000000000000000000000000000000000":{"size":0,"fee":0.0,"modifiedfee":0.0,"time":0,"descendantcount":0,"descendantsize":0,"descendantfees":0,"ancestorcount":0,"ancestorsize":0,"ancestorfees":0,"depends":[],"spentby":[]}}"#);
+	}
+}
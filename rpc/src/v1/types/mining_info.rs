@@ -0,0 +1,29 @@
+/// getmininginfo response
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct MiningInfo {
+	/// Height of the best block
+	pub blocks: u32,
+	/// Proof-of-work difficulty of the best block as a multiple of the minimum difficulty
+	pub difficulty: f64,
+	/// Number of transactions in the memory pool
+	pub pooledtx: usize,
+	/// Number of block header hashes per second, checked by the local cpu miner
+	pub hashespersec: f64,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::MiningInfo;
+
+	#[test]
+	fn mining_info_serialize() {
+		let info = MiningInfo {
+			blocks: 100,
+			difficulty: 1.5,
+			pooledtx: 3,
+			hashespersec: 1000.0,
+		};
+		assert_eq!(serde_json::to_string(&info).unwrap(), r#"{"blocks":100,"difficulty":1.5,"pooledtx":3,"hashespersec":1000.0}"#);
+	}
+}
@@ -2,28 +2,55 @@ pub mod address;
 mod block;
 mod block_template;
 mod block_template_request;
+mod blockchain_info;
 mod bytes;
+mod chain_tip;
+mod fee_estimate;
+mod generate_block_response;
 mod get_block_response;
 mod get_tx_out_response;
 mod get_tx_out_set_info_response;
 mod hash;
+mod memory_pool;
+mod mining_info;
+mod pubsub;
 mod script;
+mod snapshot;
+mod spent_info;
+mod submit_package;
+mod test_memory_pool_accept;
 mod transaction;
 mod uint;
 mod nodes;
+mod verify_chain;
 
 pub use self::block::RawBlock;
 pub use self::block_template::{BlockTemplate, BlockTemplateTransaction};
 pub use self::block_template_request::{BlockTemplateRequest, BlockTemplateRequestMode};
+pub use self::blockchain_info::{BlockchainInfo, Softfork, SoftforkType, Bip9Softfork, Bip9SoftforkStatus,
+	Bip9SoftforkStatistics};
 pub use self::bytes::Bytes;
+pub use self::chain_tip::{ChainTip, ChainTipStatus};
+pub use self::fee_estimate::{EstimateMode, EstimateSmartFeeResponse, EstimateRawFeeResponse, RawFeeEstimate,
+	FeeRateBucket};
+pub use self::generate_block_response::GenerateBlockResponse;
 pub use self::get_block_response::{GetBlockResponse, VerboseBlock};
 pub use self::get_tx_out_response::GetTxOutResponse;
 pub use self::get_tx_out_set_info_response::GetTxOutSetInfoResponse;
 pub use self::hash::{H160, H256};
+pub use self::memory_pool::{MemoryPoolInfo, MemoryPoolEntry, GetRawMemoryPoolResponse};
+pub use self::mining_info::MiningInfo;
+pub use self::pubsub::{SubscriptionKind, SubscriptionParams, PubSubNotification, HeadNotification,
+	TransactionNotification, ReorgNotification};
 pub use self::script::ScriptType;
+pub use self::snapshot::{DumpTxOutSetResponse, ChainStates, ChainState};
+pub use self::spent_info::{SpentInfoRequest, SpentInfoResponse};
+pub use self::submit_package::{SubmitPackageResponse, SubmitPackageTransactionResult};
+pub use self::test_memory_pool_accept::{TestMemoryPoolAcceptResult, TestMemoryPoolAcceptFees};
 pub use self::transaction::{RawTransaction, Transaction, TransactionInput, TransactionOutput,
 	TransactionOutputWithAddress, TransactionOutputWithScriptData, TransactionInputScript,
-	TransactionOutputScript, SignedTransactionInput, GetRawTransactionResponse,
+	TransactionOutputScript, SignedTransactionInput, SignedTransactionInputPrevout, GetRawTransactionResponse,
 	SignedTransactionOutput, TransactionOutputs};
 pub use self::uint::U256;
 pub use self::nodes::{AddNodeOperation, NodeInfo};
+pub use self::verify_chain::{VerifyChainResponse, VerifyChainIssue};
@@ -0,0 +1,132 @@
+use keys::Address;
+use v1::types;
+use super::hash::H256;
+use super::transaction::RawTransaction;
+
+/// Kind of the subscription
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SubscriptionKind {
+	/// New best block headers
+	NewHeads,
+	/// Transactions, accepted to the memory pool
+	NewTransactions,
+	/// Reorganizations of the best chain
+	Reorgs,
+	/// Transactions (from the memory pool and from new best blocks), paying to given addresses
+	AddressTransactions,
+}
+
+/// Subscription parameters
+#[derive(Debug, Default, Clone, Deserialize, PartialEq)]
+pub struct SubscriptionParams {
+	/// Addresses to watch (`addressTransactions` subscription only)
+	#[serde(default, with = "types::address::vec")]
+	pub addresses: Vec<Address>,
+}
+
+/// Subscription notification
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(untagged)]
+pub enum PubSubNotification {
+	/// New best block header
+	Head(HeadNotification),
+	/// New transaction
+	Transaction(TransactionNotification),
+	/// Best chain reorganization
+	Reorg(ReorgNotification),
+}
+
+/// New best block header
+#[derive(Debug, Default, Clone, Serialize, PartialEq)]
+pub struct HeadNotification {
+	/// Block hash
+	pub hash: H256,
+	/// Block height
+	pub height: u32,
+	/// Block version
+	pub version: u32,
+	/// Merkle root of this block
+	pub merkleroot: H256,
+	/// Block time in seconds since epoch (Jan 1 1970 GMT)
+	pub time: u32,
+	/// Block nonce
+	pub nonce: u32,
+	/// Block nbits
+	pub bits: u32,
+	/// Hash of previous block
+	pub previousblockhash: H256,
+}
+
+/// New transaction
+#[derive(Debug, Default, Clone, Serialize, PartialEq)]
+pub struct TransactionNotification {
+	/// Transaction id
+	pub txid: H256,
+	/// Hex-encoded transaction
+	pub hex: RawTransaction,
+	/// Hash of the best block, which has included this transaction. None if transaction is in the memory pool
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub blockhash: Option<H256>,
+	/// Watched addresses, paid by this transaction (`addressTransactions` subscription only)
+	#[serde(skip_serializing_if = "Vec::is_empty", with = "types::address::vec")]
+	pub addresses: Vec<Address>,
+}
+
+/// Best chain reorganization
+#[derive(Debug, Default, Clone, Serialize, PartialEq)]
+pub struct ReorgNotification {
+	/// Blocks, disconnected from the best chain. Ordered from oldest to newest
+	pub disconnected: Vec<H256>,
+	/// Blocks, connected to the best chain. Ordered from oldest to newest
+	pub connected: Vec<H256>,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::super::bytes::Bytes;
+	use super::super::hash::H256;
+	use super::*;
+
+	#[test]
+	fn subscription_kind_deserialize() {
+		assert_eq!(serde_json::from_str::<SubscriptionKind>(r#""newHeads""#).unwrap(), SubscriptionKind::NewHeads);
+		assert_eq!(serde_json::from_str::<SubscriptionKind>(r#""newTransactions""#).unwrap(), SubscriptionKind::NewTransactions);
+		assert_eq!(serde_json::from_str::<SubscriptionKind>(r#""reorgs""#).unwrap(), SubscriptionKind::Reorgs);
+		assert_eq!(serde_json::from_str::<SubscriptionKind>(r#""addressTransactions""#).unwrap(), SubscriptionKind::AddressTransactions);
+		assert!(serde_json::from_str::<SubscriptionKind>(r#""logs""#).is_err());
+	}
+
+	#[test]
+	fn subscription_params_deserialize() {
+		assert_eq!(serde_json::from_str::<SubscriptionParams>(r#"{}"#).unwrap(), SubscriptionParams::default());
+		assert_eq!(serde_json::from_str::<SubscriptionParams>(r#"{"addresses":["1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa"]}"#).unwrap(),
+			SubscriptionParams {
+				addresses: vec!["1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa".into()],
+			});
+	}
+
+	#[test]
+	fn notification_serialize() {
+		let head = PubSubNotification::Head(HeadNotification {
+			hash: H256::from(1),
+			height: 2,
+			..Default::default()
+		});
+		assert_eq!(serde_json::to_string(&head).unwrap(), r#"{"hash":"0100000000000000000000000000000000000000000000000000000000000000","height":2,"version":0,"merkleroot":"0000000000000000000000000000000000000000000000000000000000000000","time":0,"nonce":0,"bits":0,"previousblockhash":"0000000000000000000000000000000000000000000000000000000000000000"}"#);
+
+		let transaction = PubSubNotification::Transaction(TransactionNotification {
+			txid: H256::from(1),
+			hex: Bytes::new(vec![1, 2]),
+			..Default::default()
+		});
+		assert_eq!(serde_json::to_string(&transaction).unwrap(), r#"{"txid":"0100000000000000000000000000000000000000000000000000000000000000","hex":"0102"}"#);
+
+		let reorg = PubSubNotification::Reorg(ReorgNotification {
+			disconnected: vec![H256::from(1)],
+			connected: vec![H256::from(2), H256::from(3)],
+		});
+		assert_eq!(serde_json::to_string(&reorg).unwrap(), r#"{"disconnected":["0100000000000000000000000000000000000000000000000000000000000000"],"connected":["0200000000000000000000000000000000000000000000000000000000000000","0300000000000000000000000000000000000000000000000000000000000000"]}"#);
+	}
+}
@@ -0,0 +1,60 @@
+use super::hash::H256;
+
+/// dumptxoutset response
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct DumpTxOutSetResponse {
+	/// Number of coins, written to the snapshot
+	pub coins_written: u64,
+	/// Hash of the block, at which snapshot has been made
+	pub base_hash: H256,
+	/// Height of the block, at which snapshot has been made
+	pub base_height: u32,
+	/// Absolute path to the snapshot file
+	pub path: String,
+	/// Hash of the snapshot coins
+	pub txoutset_hash: H256,
+}
+
+/// getchainstates response
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ChainStates {
+	/// Height of the best known header
+	pub headers: u32,
+	/// Chainstates, ordered by the height of the best block: background chainstate (if any) goes first
+	pub chainstates: Vec<ChainState>,
+}
+
+/// Chainstate description
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ChainState {
+	/// Height of the best block
+	pub blocks: u32,
+	/// Hash of the best block
+	pub bestblockhash: H256,
+	/// True if all blocks of the chainstate are fully validated
+	pub validated: bool,
+	/// Hash of the block, at which UTXO set snapshot of this chainstate has been made (only present for snapshot chainstate)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub snapshot_blockhash: Option<H256>,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::super::hash::H256;
+	use super::{ChainStates, ChainState};
+
+	#[test]
+	fn chain_states_serialize() {
+		let states = ChainStates {
+			headers: 10,
+			chainstates: vec![ChainState {
+				blocks: 10,
+				bestblockhash: H256::from(1),
+				validated: false,
+				snapshot_blockhash: Some(H256::from(2)),
+			}],
+		};
+		assert_eq!(serde_json::to_string(&states).unwrap(), r#"{"headers":10,"chainstates":[{"blocks":10,"bestblockhash":"0100000000000000000000000000000000000000000000000000000000000000","validated":false,"snapshot_blockhash":"0200000000000000000000000000000000000000000000000000000000000000"}]}"#);
+	}
+}
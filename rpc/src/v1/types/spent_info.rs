@@ -0,0 +1,49 @@
+use super::hash::H256;
+
+/// getspentinfo request
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct SpentInfoRequest {
+	/// Hash of the transaction, which has created the output
+	pub txid: H256,
+	/// Index of the output
+	pub index: u32,
+}
+
+/// getspentinfo response
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct SpentInfoResponse {
+	/// Hash of the spending transaction
+	pub txid: H256,
+	/// Index of the spending input
+	pub index: u32,
+	/// Height of the block, which contains spending transaction (not present for memory pool transactions)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub height: Option<u32>,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::super::hash::H256;
+	use super::{SpentInfoRequest, SpentInfoResponse};
+
+	#[test]
+	fn spent_info_request_deserialize() {
+		assert_eq!(
+			serde_json::from_str::<SpentInfoRequest>(r#"{"txid":"0100000000000000000000000000000000000000000000000000000000000000","index":2}"#).unwrap(),
+			SpentInfoRequest {
+				txid: H256::from(1),
+				index: 2,
+			});
+	}
+
+	#[test]
+	fn spent_info_response_serialize() {
+		let response = SpentInfoResponse {
+			txid: H256::from(1),
+			index: 2,
+			height: None,
+		};
+		assert_eq!(serde_json::to_string(&response).unwrap(), r#"{"txid":"0100000000000000000000000000000000000000000000000000000000000000","index":2}"#);
+	}
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use chain::constants::SATOSHIS_IN_COIN;
+use sync;
+use super::hash::H256;
+use super::test_memory_pool_accept::{TestMemoryPoolAcceptFees, reject_reason_to_string};
+
+/// Result of single package transaction submission
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct SubmitPackageTransactionResult {
+	/// Transaction hash in hex
+	pub txid: H256,
+	/// Virtual transaction size as defined in BIP141. Only present when transaction is valid
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub vsize: Option<usize>,
+	/// Transaction fees. Only present when transaction has been added to the memory pool by this call
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub fees: Option<TestMemoryPoolAcceptFees>,
+	/// Transaction rejection reason. Only present when transaction is not valid
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+}
+
+/// submitpackage response
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct SubmitPackageResponse {
+	/// Package result message: either 'success', or package rejection reason
+	pub package_msg: String,
+	/// Results of package transactions, by transaction hash
+	#[serde(rename = "tx-results")]
+	pub tx_results: HashMap<H256, SubmitPackageTransactionResult>,
+	/// Package fee rate in BTC/kvB. Only present when at least one transaction has been added to the memory pool
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub package_feerate: Option<f64>,
+}
+
+impl From<sync::PackageAcceptResult> for SubmitPackageResponse {
+	fn from(result: sync::PackageAcceptResult) -> Self {
+		let package_msg = match result.reject_reason {
+			None => "success",
+			Some(sync::PackageRejectReason::TooManyTransactions) => "package-too-many-transactions",
+			Some(sync::PackageRejectReason::TooLarge) => "package-too-large",
+			Some(sync::PackageRejectReason::NotSorted) => "package-not-sorted",
+			Some(sync::PackageRejectReason::ConflictingInputs) => "conflict-in-package",
+			Some(sync::PackageRejectReason::TransactionRejected) => "transaction failed",
+			Some(sync::PackageRejectReason::InsufficientFee) => "package-fee-too-low",
+		};
+
+		SubmitPackageResponse {
+			package_msg: package_msg.into(),
+			tx_results: result.transactions.into_iter()
+				.map(|transaction| {
+					let txid: H256 = transaction.hash.reversed().into();
+					let is_valid = transaction.reject_reason.is_none();
+					(txid.clone(), SubmitPackageTransactionResult {
+						txid: txid,
+						vsize: if is_valid { Some(transaction.vsize) } else { None },
+						fees: transaction.fee.map(|fee| TestMemoryPoolAcceptFees {
+							base: fee as f64 / SATOSHIS_IN_COIN as f64,
+						}),
+						error: transaction.reject_reason.map(reject_reason_to_string),
+					})
+				})
+				.collect(),
+			package_feerate: result.fee_rate.map(|fee_rate| fee_rate as f64 / SATOSHIS_IN_COIN as f64),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::super::hash::H256;
+	use super::*;
+
+	#[test]
+	fn submit_package_response_serialize() {
+		let mut tx_results = HashMap::new();
+		tx_results.insert(H256::from(1), SubmitPackageTransactionResult {
+			txid: H256::from(1),
+			vsize: Some(100),
+			fees: Some(TestMemoryPoolAcceptFees { base: 0.0001 }),
+			error: None,
+		});
+		let response = SubmitPackageResponse {
+			package_msg: "success".into(),
+			tx_results: tx_results,
+			package_feerate: Some(0.001),
+		};
+		assert_eq!(serde_json::to_string(&response).unwrap(), r#"{"package_msg":"success","tx-results":{"0100000000000000000000000000000000000000000000000000000000000000":{"txid":"0100000000000000000000000000000000000000000000000000000000000000","vsize":100,"fees":{"base":0.0001}}},"package_feerate":0.001}"#);
+	}
+}
@@ -0,0 +1,81 @@
+use chain::constants::SATOSHIS_IN_COIN;
+use sync;
+use super::hash::H256;
+
+/// Fees of the transaction, which has passed memory pool acceptance test
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct TestMemoryPoolAcceptFees {
+	/// Transaction fee in BTC
+	pub base: f64,
+}
+
+/// Result of memory pool acceptance test of single transaction (testmempoolaccept response item)
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct TestMemoryPoolAcceptResult {
+	/// Transaction hash in hex
+	pub txid: H256,
+	/// If the transaction would be accepted to the memory pool
+	pub allowed: bool,
+	/// Virtual transaction size as defined in BIP141. Only present when transaction is allowed
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub vsize: Option<usize>,
+	/// Transaction fees. Only present when transaction is allowed
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub fees: Option<TestMemoryPoolAcceptFees>,
+	/// Rejection reason. Only present when transaction is not allowed
+	#[serde(rename = "reject-reason")]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub reject_reason: Option<String>,
+}
+
+impl From<sync::MemoryPoolAcceptResult> for TestMemoryPoolAcceptResult {
+	fn from(result: sync::MemoryPoolAcceptResult) -> Self {
+		let allowed = result.is_allowed();
+		TestMemoryPoolAcceptResult {
+			txid: result.hash.reversed().into(),
+			allowed: allowed,
+			vsize: if allowed { Some(result.vsize) } else { None },
+			fees: result.fee.map(|fee| TestMemoryPoolAcceptFees {
+				base: fee as f64 / SATOSHIS_IN_COIN as f64,
+			}),
+			reject_reason: result.reject_reason.map(reject_reason_to_string),
+		}
+	}
+}
+
+/// Convert memory pool rejection reason to string
+pub fn reject_reason_to_string(reason: sync::MemoryPoolRejectReason) -> String {
+	match reason {
+		sync::MemoryPoolRejectReason::AlreadyInMemoryPool => "txn-already-in-mempool".into(),
+		sync::MemoryPoolRejectReason::DuplicateInPackage => "txn-duplicate-in-package".into(),
+		sync::MemoryPoolRejectReason::Verification(err) => format!("{:?}", err),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::super::hash::H256;
+	use super::*;
+
+	#[test]
+	fn test_memory_pool_accept_result_serialize() {
+		let result = TestMemoryPoolAcceptResult {
+			txid: H256::from(1),
+			allowed: true,
+			vsize: Some(100),
+			fees: Some(TestMemoryPoolAcceptFees { base: 0.0001 }),
+			reject_reason: None,
+		};
+		assert_eq!(serde_json::to_string(&result).unwrap(), r#"{"txid":"0100000000000000000000000000000000000000000000000000000000000000","allowed":true,"vsize":100,"fees":{"base":0.0001}}"#);
+
+		let result = TestMemoryPoolAcceptResult {
+			txid: H256::from(1),
+			allowed: false,
+			vsize: None,
+			fees: None,
+			reject_reason: Some("Overspend".into()),
+		};
+		assert_eq!(serde_json::to_string(&result).unwrap(), r#"{"txid":"0100000000000000000000000000000000000000000000000000000000000000","allowed":false,"reject-reason":"Overspend"}"#);
+	}
+}
@@ -87,19 +87,43 @@ pub struct TransactionOutputScript {
 	pub addresses: Vec<Address>,
 }
 
+/// Output, spent by the signed transaction input
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct SignedTransactionInputPrevout {
+	/// True if output has been created by coinbase transaction
+	pub generated: bool,
+	/// Height of the block, which has created the output (0x7FFFFFFF for memory pool outputs)
+	pub height: u32,
+	/// Output value in BTC
+	pub value: f64,
+	/// Output script
+	#[serde(rename = "scriptPubKey")]
+	pub script: TransactionOutputScript,
+}
+
 /// Signed transaction input
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct SignedTransactionInput {
-	/// Previous transaction id
-	pub txid: H256,
-	/// Previous transaction output index
-	pub vout: u32,
-	/// Input script
-	pub script_sig: TransactionInputScript,
+	/// Previous transaction id (not present for coinbase input)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub txid: Option<H256>,
+	/// Previous transaction output index (not present for coinbase input)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub vout: Option<u32>,
+	/// Input script (not present for coinbase input)
+	#[serde(rename = "scriptSig", skip_serializing_if = "Option::is_none")]
+	pub script_sig: Option<TransactionInputScript>,
+	/// Coinbase input script (only present for coinbase input)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub coinbase: Option<Bytes>,
+	/// Hex-encoded witness data (if any)
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub txinwitness: Vec<Bytes>,
+	/// Spent output (only present if it is available)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub prevout: Option<SignedTransactionInputPrevout>,
 	/// Sequence number
 	pub sequence: u32,
-	/// Hex-encoded witness data (if any)
-	pub txinwitness: Vec<String>,
 }
 
 /// Signed transaction output
@@ -117,6 +141,10 @@ pub struct SignedTransactionOutput {
 /// Transaction
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct Transaction {
+	/// True if the block this transaction is included in is in the main chain
+	/// (only present when block hash has been passed to the method)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub in_active_chain: Option<bool>,
 	/// Raw transaction
 	pub hex: RawTransaction,
 	/// The transaction id (same as provided)
@@ -127,6 +155,8 @@ pub struct Transaction {
 	pub size: usize,
 	/// The virtual transaction size (differs from size for witness transactions)
 	pub vsize: usize,
+	/// The transaction weight
+	pub weight: usize,
 	/// The version
 	pub version: i32,
 	/// The lock time
@@ -135,14 +165,21 @@ pub struct Transaction {
 	pub vin: Vec<SignedTransactionInput>,
 	/// Transaction outputs
 	pub vout: Vec<SignedTransactionOutput>,
-	/// Hash of the block this transaction is included in
-	pub blockhash: H256,
-	/// Number of confirmations of this transaction
-	pub confirmations: u32,
+	/// Transaction fee in BTC (only present if all spent outputs are available)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub fee: Option<f64>,
+	/// Hash of the block this transaction is included in (not present for memory pool transactions)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub blockhash: Option<H256>,
+	/// Number of confirmations of this transaction (not present for memory pool transactions)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub confirmations: Option<u32>,
 	/// The transaction time in seconds since epoch (Jan 1 1970 GMT)
-	pub time: u32,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub time: Option<u32>,
 	/// The block time in seconds since epoch (Jan 1 1970 GMT)
-	pub blocktime: u32,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub blocktime: Option<u32>,
 }
 
 /// Return value of `getrawtransaction` method
@@ -370,35 +407,81 @@ mod tests {
 	#[test]
 	fn signed_transaction_input_serialize() {
 		let txin = SignedTransactionInput {
-			txid: H256::from(77),
-			vout: 13,
-			script_sig: TransactionInputScript {
+			txid: Some(H256::from(77)),
+			vout: Some(13),
+			script_sig: Some(TransactionInputScript {
 				asm: "Hello, world!!!".to_owned(),
 				hex: Bytes::new(vec![1, 2, 3, 4]),
-			},
-			sequence: 123,
+			}),
+			coinbase: None,
 			txinwitness: vec![],
+			prevout: None,
+			sequence: 123,
 		};
-		assert_eq!(serde_json::to_string(&txin).unwrap(), r#"{"txid":"4d00000000000000000000000000000000000000000000000000000000000000","vout":13,"script_sig":{"asm":"Hello, world!!!","hex":"01020304"},"sequence":123,"txinwitness":[]}"#);
+		assert_eq!(serde_json::to_string(&txin).unwrap(), r#"{"txid":"4d00000000000000000000000000000000000000000000000000000000000000","vout":13,"scriptSig":{"asm":"Hello, world!!!","hex":"01020304"},"sequence":123}"#);
 	}
 
 	#[test]
 	fn signed_transaction_input_deserialize() {
 		let txin = SignedTransactionInput {
-			txid: H256::from(77),
-			vout: 13,
-			script_sig: TransactionInputScript {
+			txid: Some(H256::from(77)),
+			vout: Some(13),
+			script_sig: Some(TransactionInputScript {
 				asm: "Hello, world!!!".to_owned(),
 				hex: Bytes::new(vec![1, 2, 3, 4]),
-			},
-			sequence: 123,
+			}),
+			coinbase: None,
 			txinwitness: vec![],
+			prevout: None,
+			sequence: 123,
 		};
 		assert_eq!(
-			serde_json::from_str::<SignedTransactionInput>(r#"{"txid":"4d00000000000000000000000000000000000000000000000000000000000000","vout":13,"script_sig":{"asm":"Hello, world!!!","hex":"01020304"},"sequence":123,"txinwitness":[]}"#).unwrap(),
+			serde_json::from_str::<SignedTransactionInput>(r#"{"txid":"4d00000000000000000000000000000000000000000000000000000000000000","vout":13,"scriptSig":{"asm":"Hello, world!!!","hex":"01020304"},"sequence":123}"#).unwrap(),
 			txin);
 	}
 
+	#[test]
+	fn signed_transaction_input_coinbase_serialize() {
+		let txin = SignedTransactionInput {
+			txid: None,
+			vout: None,
+			script_sig: None,
+			coinbase: Some(Bytes::new(vec![1, 2])),
+			txinwitness: vec![Bytes::new(vec![3])],
+			prevout: None,
+			sequence: 123,
+		};
+		assert_eq!(serde_json::to_string(&txin).unwrap(), r#"{"coinbase":"0102","txinwitness":["03"],"sequence":123}"#);
+	}
+
+	#[test]
+	fn signed_transaction_input_prevout_serialize() {
+		let txin = SignedTransactionInput {
+			txid: Some(H256::from(77)),
+			vout: Some(13),
+			script_sig: Some(TransactionInputScript {
+				asm: "".to_owned(),
+				hex: Bytes::new(vec![]),
+			}),
+			coinbase: None,
+			txinwitness: vec![],
+			prevout: Some(SignedTransactionInputPrevout {
+				generated: true,
+				height: 100,
+				value: 50.0,
+				script: TransactionOutputScript {
+					asm: "".to_owned(),
+					hex: Bytes::new(vec![]),
+					req_sigs: 0,
+					script_type: ScriptType::NonStandard,
+					addresses: vec![],
+				},
+			}),
+			sequence: 123,
+		};
+		assert_eq!(serde_json::to_string(&txin).unwrap(), r#"{"txid":"4d00000000000000000000000000000000000000000000000000000000000000","vout":13,"scriptSig":{"asm":"","hex":""},"prevout":{"generated":true,"height":100,"value":50.0,"scriptPubKey":{"asm":"","hex":"","reqSigs":0,"type":"nonstandard","addresses":[]}},"sequence":123}"#);
+	}
+
 	#[test]
 	fn signed_transaction_output_serialize() {
 		let txout = SignedTransactionOutput {
@@ -436,42 +519,48 @@ mod tests {
 	#[test]
 	fn transaction_serialize() {
 		let tx = Transaction {
+			in_active_chain: None,
 			hex: "DEADBEEF".into(),
 			txid: H256::from(4),
 			hash: H256::from(5),
 			size: 33,
 			vsize: 44,
+			weight: 176,
 			version: 55,
 			locktime: 66,
 			vin: vec![],
 			vout: vec![],
-			blockhash: H256::from(6),
-			confirmations: 77,
-			time: 88,
-			blocktime: 99,
+			fee: Some(0.0001),
+			blockhash: Some(H256::from(6)),
+			confirmations: Some(77),
+			time: Some(88),
+			blocktime: Some(99),
 		};
-		assert_eq!(serde_json::to_string(&tx).unwrap(), r#"{"hex":"deadbeef","txid":"0400000000000000000000000000000000000000000000000000000000000000","hash":"0500000000000000000000000000000000000000000000000000000000000000","size":33,"vsize":44,"version":55,"locktime":66,"vin":[],"vout":[],"blockhash":"0600000000000000000000000000000000000000000000000000000000000000","confirmations":77,"time":88,"blocktime":99}"#);
+		assert_eq!(serde_json::to_string(&tx).unwrap(), r#"{"hex":"deadbeef","txid":"0400000000000000000000000000000000000000000000000000000000000000","hash":"0500000000000000000000000000000000000000000000000000000000000000","size":33,"vsize":44,"weight":176,"version":55,"locktime":66,"vin":[],"vout":[],"fee":0.0001,"blockhash":"0600000000000000000000000000000000000000000000000000000000000000","confirmations":77,"time":88,"blocktime":99}"#);
 	}
 
 	#[test]
 	fn transaction_deserialize() {
 		let tx = Transaction {
+			in_active_chain: None,
 			hex: "DEADBEEF".into(),
 			txid: H256::from(4),
 			hash: H256::from(5),
 			size: 33,
 			vsize: 44,
+			weight: 176,
 			version: 55,
 			locktime: 66,
 			vin: vec![],
 			vout: vec![],
-			blockhash: H256::from(6),
-			confirmations: 77,
-			time: 88,
-			blocktime: 99,
+			fee: Some(0.0001),
+			blockhash: Some(H256::from(6)),
+			confirmations: Some(77),
+			time: Some(88),
+			blocktime: Some(99),
 		};
 		assert_eq!(
-			serde_json::from_str::<Transaction>(r#"{"hex":"deadbeef","txid":"0400000000000000000000000000000000000000000000000000000000000000","hash":"0500000000000000000000000000000000000000000000000000000000000000","size":33,"vsize":44,"version":55,"locktime":66,"vin":[],"vout":[],"blockhash":"0600000000000000000000000000000000000000000000000000000000000000","confirmations":77,"time":88,"blocktime":99}"#).unwrap(),
+			serde_json::from_str::<Transaction>(r#"{"hex":"deadbeef","txid":"0400000000000000000000000000000000000000000000000000000000000000","hash":"0500000000000000000000000000000000000000000000000000000000000000","size":33,"vsize":44,"weight":176,"version":55,"locktime":66,"vin":[],"vout":[],"fee":0.0001,"blockhash":"0600000000000000000000000000000000000000000000000000000000000000","confirmations":77,"time":88,"blocktime":99}"#).unwrap(),
 			tx);
 	}
 }
@@ -0,0 +1,28 @@
+use super::hash::H256;
+
+/// Inconsistency of the stored block, found by verifychain
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct VerifyChainIssue {
+	/// Height of the inconsistent block
+	pub height: u32,
+	/// Hash of the inconsistent block (missing if block hash itself is not stored)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub hash: Option<H256>,
+	/// Description of the inconsistency
+	pub error: String,
+}
+
+/// verifychain response
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct VerifyChainResponse {
+	/// True if no inconsistencies were found
+	pub valid: bool,
+	/// Level of performed checks
+	pub checklevel: u32,
+	/// Height of the first checked block
+	pub firstblock: u32,
+	/// Height of the last checked block
+	pub lastblock: u32,
+	/// Found inconsistencies
+	pub errors: Vec<VerifyChainIssue>,
+}
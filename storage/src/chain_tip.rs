@@ -0,0 +1,132 @@
+//! Tips of the block tree
+
+use primitives::io;
+use hash::H256;
+use ser::{Serializable, Deserializable, Stream, Reader};
+use {Error, SideChainOrigin};
+
+/// Status of the chain tip
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChainTipStatus {
+	/// Tip of the canon chain
+	Active,
+	/// All blocks of the branch are stored and verified, but the branch is not canon
+	ValidFork,
+	/// Headers of the branch are known, but not all of its blocks are stored
+	HeadersOnly,
+	/// Branch contains at least one invalid block
+	Invalid,
+}
+
+impl Serializable for ChainTipStatus {
+	fn serialize(&self, stream: &mut Stream) {
+		let value: u8 = match *self {
+			ChainTipStatus::Active => 0,
+			ChainTipStatus::ValidFork => 1,
+			ChainTipStatus::HeadersOnly => 2,
+			ChainTipStatus::Invalid => 3,
+		};
+		stream.append(&value);
+	}
+}
+
+impl Deserializable for ChainTipStatus {
+	fn deserialize<T>(reader: &mut Reader<T>) -> Result<Self, io::Error> where T: io::Read {
+		match reader.read::<u8>()? {
+			0 => Ok(ChainTipStatus::Active),
+			1 => Ok(ChainTipStatus::ValidFork),
+			2 => Ok(ChainTipStatus::HeadersOnly),
+			3 => Ok(ChainTipStatus::Invalid),
+			_ => Err(io::ErrorKind::MalformedData),
+		}
+	}
+}
+
+/// Persisted state of the block tree tip
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainTipState {
+	/// Hash of the tip block
+	pub hash: H256,
+	/// Number of the tip block
+	pub number: u32,
+	/// Last stored block of the branch. Equals to the tip hash if tip block is stored
+	pub base: H256,
+	/// Status of the tip, as it was known when tip has been recorded
+	pub status: ChainTipStatus,
+}
+
+impl Serializable for ChainTipState {
+	fn serialize(&self, stream: &mut Stream) {
+		stream
+			.append(&self.hash)
+			.append(&self.number)
+			.append(&self.base)
+			.append(&self.status);
+	}
+}
+
+impl Deserializable for ChainTipState {
+	fn deserialize<T>(reader: &mut Reader<T>) -> Result<Self, io::Error> where T: io::Read {
+		let result = ChainTipState {
+			hash: reader.read()?,
+			number: reader.read()?,
+			base: reader.read()?,
+			status: reader.read()?,
+		};
+
+		Ok(result)
+	}
+}
+
+/// Tip of the block tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainTip {
+	/// Hash of the tip block
+	pub hash: H256,
+	/// Number of the tip block
+	pub number: u32,
+	/// Number of blocks in the branch, starting from the fork point with canon chain
+	pub branch_len: u32,
+	/// Status of the tip
+	pub status: ChainTipStatus,
+}
+
+/// Block tree tips interface
+pub trait ChainTipStore {
+	/// Returns all known tips of the block tree, including the canon chain tip
+	fn chain_tips(&self) -> Vec<ChainTip>;
+
+	/// Records tip of the branch, which block is not stored. `base` is the last stored block
+	/// of the branch and `distance` is the number of blocks between base and tip. Used to
+	/// remember headers-only branches and branches with blocks, that have failed verification
+	fn insert_chain_tip(&self, hash: &H256, base: &H256, distance: u32, status: ChainTipStatus) -> Result<(), Error>;
+
+	/// Returns true if block is invalid or descends from invalid block
+	fn is_invalid_block(&self, hash: &H256) -> bool;
+
+	/// Marks block and all its descendants invalid and switches to the best valid branch.
+	/// Returns route of the switch
+	fn invalidate_block(&self, hash: &H256) -> Result<SideChainOrigin, Error>;
+
+	/// Removes invalidity marks from the block, its ancestors and descendants and switches
+	/// to the best valid branch. Returns route of the switch
+	fn reconsider_block(&self, hash: &H256) -> Result<SideChainOrigin, Error>;
+}
+
+#[cfg(test)]
+mod tests {
+	use ser::{serialize, deserialize};
+	use super::{ChainTipState, ChainTipStatus};
+
+	#[test]
+	fn test_chain_tip_state_serialization() {
+		let state = ChainTipState {
+			hash: 2.into(),
+			number: 100,
+			base: 1.into(),
+			status: ChainTipStatus::HeadersOnly,
+		};
+
+		assert_eq!(deserialize::<_, ChainTipState>(serialize(&state).as_ref()).unwrap(), state);
+	}
+}
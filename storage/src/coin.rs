@@ -0,0 +1,139 @@
+//! Unspent transaction outputs and block undo data
+
+use primitives::io;
+use bytes::Bytes;
+use chain::{OutPoint, TransactionOutput};
+use ser::{Serializable, Deserializable, Stream, Reader};
+
+/// Unspent transaction output of the canon chain
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coin {
+	/// Output value
+	pub value: u64,
+	/// Output script
+	pub script_pubkey: Bytes,
+	/// Height of the block, which has created this output
+	pub height: u32,
+	/// True if output has been created by coinbase transaction
+	pub is_coinbase: bool,
+}
+
+impl Coin {
+	/// New coin for given transaction output
+	pub fn new(output: &TransactionOutput, height: u32, is_coinbase: bool) -> Self {
+		Coin {
+			value: output.value,
+			script_pubkey: output.script_pubkey.clone(),
+			height: height,
+			is_coinbase: is_coinbase,
+		}
+	}
+}
+
+impl From<Coin> for TransactionOutput {
+	fn from(coin: Coin) -> Self {
+		TransactionOutput {
+			value: coin.value,
+			script_pubkey: coin.script_pubkey,
+		}
+	}
+}
+
+impl Serializable for Coin {
+	fn serialize(&self, stream: &mut Stream) {
+		stream
+			.append(&self.height)
+			.append(&self.is_coinbase)
+			.append(&self.value)
+			.append(&self.script_pubkey);
+	}
+}
+
+impl Deserializable for Coin {
+	fn deserialize<T>(reader: &mut Reader<T>) -> Result<Self, io::Error> where T: io::Read {
+		let result = Coin {
+			height: reader.read()?,
+			is_coinbase: reader.read()?,
+			value: reader.read()?,
+			script_pubkey: reader.read()?,
+		};
+
+		Ok(result)
+	}
+}
+
+/// Coin, spent by the block
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpentCoin {
+	/// Spent output
+	pub outpoint: OutPoint,
+	/// Coin as it was before the block has been canonized
+	pub coin: Coin,
+}
+
+impl Serializable for SpentCoin {
+	fn serialize(&self, stream: &mut Stream) {
+		stream
+			.append(&self.outpoint)
+			.append(&self.coin);
+	}
+}
+
+impl Deserializable for SpentCoin {
+	fn deserialize<T>(reader: &mut Reader<T>) -> Result<Self, io::Error> where T: io::Read {
+		let result = SpentCoin {
+			outpoint: reader.read()?,
+			coin: reader.read()?,
+		};
+
+		Ok(result)
+	}
+}
+
+/// Block undo data: coins of the canon chain, spent by the block, in order of spending
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BlockUndo {
+	pub spent: Vec<SpentCoin>,
+}
+
+impl Serializable for BlockUndo {
+	fn serialize(&self, stream: &mut Stream) {
+		stream.append_list(&self.spent);
+	}
+}
+
+impl Deserializable for BlockUndo {
+	fn deserialize<T>(reader: &mut Reader<T>) -> Result<Self, io::Error> where T: io::Read {
+		let result = BlockUndo {
+			spent: reader.read_list()?,
+		};
+
+		Ok(result)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use chain::{OutPoint, TransactionOutput};
+	use ser::{serialize, deserialize};
+	use super::{Coin, SpentCoin, BlockUndo};
+
+	#[test]
+	fn test_coin_serialization() {
+		let output = TransactionOutput {
+			value: 5000000000,
+			script_pubkey: vec![0x51].into(),
+		};
+		let coin = Coin::new(&output, 100, true);
+		let undo = BlockUndo {
+			spent: vec![SpentCoin {
+				outpoint: OutPoint { hash: 1.into(), index: 2 },
+				coin: coin.clone(),
+			}],
+		};
+
+		assert_eq!(deserialize::<_, Coin>(serialize(&coin).as_ref()).unwrap(), coin);
+		assert_eq!(deserialize::<_, BlockUndo>(serialize(&undo).as_ref()).unwrap(), undo);
+		assert_eq!(TransactionOutput::from(coin), output);
+	}
+}
@@ -6,12 +6,33 @@ pub enum Error {
 	/// Invalid block
 	#[display(fmt = "Cannot canonize block")]
 	CannotCanonize,
+	/// Missing undo data
+	#[display(fmt = "Cannot decanonize block")]
+	CannotDecanonize,
 	/// Uknown parent
 	#[display(fmt = "Block parent is unknown")]
 	UnknownParent,
 	/// Ancient fork
 	#[display(fmt = "Fork is too long to proceed")]
 	AncientFork,
+	/// Pruning is disabled
+	#[display(fmt = "Cannot prune blocks because node is not in prune mode")]
+	PruningDisabled,
+	/// Invalid UTXO set snapshot
+	#[display(fmt = "Invalid UTXO snapshot: {}", _0)]
+	InvalidSnapshot(String),
+	/// Spent outputs index is disabled
+	#[display(fmt = "Spent outputs index is disabled")]
+	SpentIndexDisabled,
+	/// Block is not in the database
+	#[display(fmt = "Block not found")]
+	UnknownBlock,
+	/// Genesis block cannot be invalidated
+	#[display(fmt = "Cannot invalidate genesis block")]
+	CannotInvalidateGenesis,
+	/// Database has been created by newer version of the node
+	#[display(fmt = "Database schema version {} is not supported. Please upgrade the node", _0)]
+	UnsupportedSchemaVersion(u32),
 }
 
 impl From<Error> for String {
@@ -17,8 +17,14 @@ mod block_iterator;
 mod block_origin;
 mod block_provider;
 mod block_ref;
+mod chain_tip;
+mod coin;
 mod error;
+mod prune;
+mod snapshot;
+mod spent_info;
 mod store;
+mod transaction_location;
 mod transaction_meta;
 mod transaction_provider;
 
@@ -31,8 +37,14 @@ pub use block_iterator::BlockIterator;
 pub use block_origin::{BlockOrigin, SideChainOrigin};
 pub use block_provider::{BlockHeaderProvider, BlockProvider, IndexedBlockProvider};
 pub use block_ref::BlockRef;
+pub use chain_tip::{ChainTip, ChainTipState, ChainTipStatus, ChainTipStore};
+pub use coin::{Coin, SpentCoin, BlockUndo};
 pub use error::Error;
+pub use prune::{PruneMode, PruneStore, MIN_BLOCKS_TO_KEEP};
+pub use snapshot::{SnapshotBase, SnapshotInfo, SnapshotValidation, SnapshotStore};
+pub use spent_info::{SpentInfo, SpentInfoProvider};
 pub use store::{AsSubstore, Store, SharedStore, CanonStore, ConfigStore};
+pub use transaction_location::TransactionLocation;
 pub use transaction_meta::TransactionMeta;
-pub use transaction_provider::{TransactionProvider, TransactionOutputProvider, TransactionMetaProvider};
+pub use transaction_provider::{TransactionProvider, TransactionOutputProvider, TransactionMetaProvider, TransactionLocationProvider};
 
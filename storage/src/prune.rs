@@ -0,0 +1,46 @@
+use Error;
+
+/// Minimal number of the most recent blocks, which are never pruned (BIP159 NODE_NETWORK_LIMITED guarantee)
+pub const MIN_BLOCKS_TO_KEEP: u32 = 288;
+
+/// Block bodies pruning mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PruneMode {
+	/// Blocks are never pruned
+	Disabled,
+	/// Blocks are pruned on request only
+	Manual,
+	/// Oldest block files are removed when new best block is canonized, until total size of block
+	/// files fits the target. Files with any of `MIN_BLOCKS_TO_KEEP` most recent blocks are kept.
+	/// Stores without block files are only pruned on request
+	Automatic {
+		/// Target size (in bytes) of the block files
+		target_size: u64,
+	},
+}
+
+impl Default for PruneMode {
+	fn default() -> Self {
+		PruneMode::Disabled
+	}
+}
+
+impl PruneMode {
+	/// Returns true if pruning is enabled
+	pub fn is_enabled(&self) -> bool {
+		*self != PruneMode::Disabled
+	}
+}
+
+/// Block bodies pruning interface
+pub trait PruneStore {
+	/// Returns current pruning mode
+	fn prune_mode(&self) -> PruneMode;
+
+	/// Returns number of the highest canon block with pruned body. None if nothing has been pruned yet
+	fn pruned_height(&self) -> Option<u32>;
+
+	/// Prunes bodies of canon blocks up to given height (inclusive), keeping at least
+	/// `MIN_BLOCKS_TO_KEEP` most recent blocks. Returns number of the highest pruned block
+	fn prune(&self, height: u32) -> Result<Option<u32>, Error>;
+}
@@ -0,0 +1,118 @@
+//! UTXO set snapshots (assumeutxo)
+
+use std::io;
+use std::path::Path;
+use primitives::io as ser_io;
+use hash::H256;
+use ser::{Serializable, Deserializable, Stream, Reader};
+use Error;
+
+/// Block, at which UTXO set snapshot has been made
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotBase {
+	/// Hash of the block
+	pub block_hash: H256,
+	/// Number of the block
+	pub block_number: u32,
+	/// Hash of the snapshot coins
+	pub content_hash: H256,
+}
+
+impl Serializable for SnapshotBase {
+	fn serialize(&self, stream: &mut Stream) {
+		stream
+			.append(&self.block_hash)
+			.append(&self.block_number)
+			.append(&self.content_hash);
+	}
+}
+
+impl Deserializable for SnapshotBase {
+	fn deserialize<T>(reader: &mut Reader<T>) -> Result<Self, ser_io::Error> where T: ser_io::Read {
+		let result = SnapshotBase {
+			block_hash: reader.read()?,
+			block_number: reader.read()?,
+			content_hash: reader.read()?,
+		};
+
+		Ok(result)
+	}
+}
+
+/// UTXO set snapshot description
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotInfo {
+	/// Block, at which snapshot has been made
+	pub base: SnapshotBase,
+	/// Number of coins in the snapshot
+	pub coins_count: u64,
+}
+
+/// Validation state of the chainstate, seeded from UTXO set snapshot
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnapshotValidation {
+	/// Snapshot is not yet validated by the background chainstate
+	Pending,
+	/// Background chainstate has reached snapshot base and the UTXO sets match
+	Valid,
+	/// Background chainstate has reached snapshot base and the UTXO sets differ
+	Invalid,
+}
+
+impl Serializable for SnapshotValidation {
+	fn serialize(&self, stream: &mut Stream) {
+		let value: u8 = match *self {
+			SnapshotValidation::Pending => 0,
+			SnapshotValidation::Valid => 1,
+			SnapshotValidation::Invalid => 2,
+		};
+		stream.append(&value);
+	}
+}
+
+impl Deserializable for SnapshotValidation {
+	fn deserialize<T>(reader: &mut Reader<T>) -> Result<Self, ser_io::Error> where T: ser_io::Read {
+		match reader.read::<u8>()? {
+			0 => Ok(SnapshotValidation::Pending),
+			1 => Ok(SnapshotValidation::Valid),
+			2 => Ok(SnapshotValidation::Invalid),
+			_ => Err(ser_io::ErrorKind::MalformedData),
+		}
+	}
+}
+
+/// UTXO set snapshots interface
+pub trait SnapshotStore {
+	/// Writes snapshot of the UTXO set at the best block
+	fn dump_snapshot(&self, writer: &mut io::Write) -> Result<SnapshotInfo, Error>;
+
+	/// Seeds empty chainstate from the snapshot file. Snapshot base must be one of `trusted` blocks
+	fn load_snapshot(&self, path: &Path, trusted: &[SnapshotBase]) -> Result<SnapshotInfo, Error>;
+
+	/// Returns base of the snapshot, this chainstate has been seeded from
+	fn snapshot_base(&self) -> Option<SnapshotBase>;
+
+	/// Returns validation state of the snapshot, this chainstate has been seeded from
+	fn snapshot_validation(&self) -> Option<SnapshotValidation>;
+
+	/// Updates validation state of the snapshot, this chainstate has been seeded from
+	fn set_snapshot_validation(&self, validation: SnapshotValidation) -> Result<(), Error>;
+}
+
+#[cfg(test)]
+mod tests {
+	use ser::{serialize, deserialize};
+	use super::{SnapshotBase, SnapshotValidation};
+
+	#[test]
+	fn test_snapshot_base_serialization() {
+		let base = SnapshotBase {
+			block_hash: 1.into(),
+			block_number: 100,
+			content_hash: 2.into(),
+		};
+
+		assert_eq!(deserialize::<_, SnapshotBase>(serialize(&base).as_ref()).unwrap(), base);
+		assert_eq!(deserialize::<_, SnapshotValidation>(serialize(&SnapshotValidation::Valid).as_ref()).unwrap(), SnapshotValidation::Valid);
+	}
+}
@@ -0,0 +1,66 @@
+//! Spent outputs index
+
+use primitives::io;
+use hash::H256;
+use chain::OutPoint;
+use ser::{Serializable, Deserializable, Stream, Reader};
+
+/// Input of the canon chain transaction, which has spent the output
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpentInfo {
+	/// Hash of the spending transaction
+	pub txid: H256,
+	/// Index of the spending input
+	pub index: u32,
+	/// Number of the block, which contains spending transaction
+	pub height: u32,
+}
+
+impl Serializable for SpentInfo {
+	fn serialize(&self, stream: &mut Stream) {
+		stream
+			.append(&self.txid)
+			.append(&self.index)
+			.append(&self.height);
+	}
+}
+
+impl Deserializable for SpentInfo {
+	fn deserialize<T>(reader: &mut Reader<T>) -> Result<Self, io::Error> where T: io::Read {
+		let result = SpentInfo {
+			txid: reader.read()?,
+			index: reader.read()?,
+			height: reader.read()?,
+		};
+
+		Ok(result)
+	}
+}
+
+/// Spent outputs index interface
+pub trait SpentInfoProvider {
+	/// Returns true if spent outputs are indexed. Outputs are only indexed when spent
+	/// by blocks, canonized after the index has been enabled
+	fn is_spent_index_enabled(&self) -> bool;
+
+	/// Returns input of the canon chain transaction, which has spent given output.
+	/// Always returns None if index is disabled
+	fn spent_info(&self, outpoint: &OutPoint) -> Option<SpentInfo>;
+}
+
+#[cfg(test)]
+mod tests {
+	use ser::{serialize, deserialize};
+	use super::SpentInfo;
+
+	#[test]
+	fn test_spent_info_serialization() {
+		let info = SpentInfo {
+			txid: 1.into(),
+			index: 2,
+			height: 100,
+		};
+
+		assert_eq!(deserialize::<_, SpentInfo>(serialize(&info).as_ref()).unwrap(), info);
+	}
+}
@@ -2,10 +2,11 @@ use std::sync::Arc;
 use chain::BlockHeader;
 use {
 	BestBlock, BlockProvider, BlockHeaderProvider, TransactionProvider, TransactionMetaProvider,
-	TransactionOutputProvider, BlockChain, IndexedBlockProvider, Forkable, Error
+	TransactionOutputProvider, BlockChain, IndexedBlockProvider, Forkable, PruneStore, SnapshotStore, SpentInfoProvider,
+	TransactionLocationProvider, ChainTipStore, Error
 };
 
-pub trait CanonStore: Store + Forkable + ConfigStore {
+pub trait CanonStore: Store + Forkable + ConfigStore + PruneStore + SnapshotStore + SpentInfoProvider + TransactionLocationProvider + ChainTipStore {
 	fn as_store(&self) -> &Store;
 }
 
@@ -0,0 +1,49 @@
+//! Transaction location index
+
+use primitives::io;
+use hash::H256;
+use ser::{Serializable, Deserializable, Stream, Reader};
+
+/// Location of the canon chain transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionLocation {
+	/// Hash of the block, which contains transaction
+	pub block_hash: H256,
+	/// Position of the transaction in the block
+	pub index: u32,
+}
+
+impl Serializable for TransactionLocation {
+	fn serialize(&self, stream: &mut Stream) {
+		stream
+			.append(&self.block_hash)
+			.append(&self.index);
+	}
+}
+
+impl Deserializable for TransactionLocation {
+	fn deserialize<T>(reader: &mut Reader<T>) -> Result<Self, io::Error> where T: io::Read {
+		let result = TransactionLocation {
+			block_hash: reader.read()?,
+			index: reader.read()?,
+		};
+
+		Ok(result)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use ser::{serialize, deserialize};
+	use super::TransactionLocation;
+
+	#[test]
+	fn test_transaction_location_serialization() {
+		let location = TransactionLocation {
+			block_hash: 1.into(),
+			index: 2,
+		};
+
+		assert_eq!(deserialize::<_, TransactionLocation>(serialize(&location).as_ref()).unwrap(), location);
+	}
+}
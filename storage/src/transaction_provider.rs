@@ -1,7 +1,7 @@
 use hash::H256;
 use bytes::Bytes;
 use chain::{Transaction, OutPoint, TransactionOutput};
-use {TransactionMeta};
+use {TransactionMeta, TransactionLocation};
 
 /// Should be used to obtain all transactions from canon chain and forks.
 pub trait TransactionProvider {
@@ -32,3 +32,10 @@ pub trait TransactionMetaProvider: Send + Sync {
 	/// Otherwise returns transaction meta object
 	fn transaction_meta(&self, hash: &H256) -> Option<TransactionMeta>;
 }
+
+/// Transaction location provider stores location of canon chain transactions
+pub trait TransactionLocationProvider: Send + Sync {
+	/// Returns hash of the canon block, which contains given transaction, and position of
+	/// the transaction in this block. Returns None if transaction is not in the canon chain
+	fn transaction_location(&self, hash: &H256) -> Option<TransactionLocation>;
+}
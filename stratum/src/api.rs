@@ -0,0 +1,40 @@
+use chain::IndexedBlock;
+use miner::BlockTemplate;
+use primitives::hash::H256;
+use sync::LocalNodeRef;
+
+/// Node functionality, required by the stratum server
+pub trait StratumCoreApi: Send + Sync + 'static {
+	/// Hash of the current best block
+	fn best_block_hash(&self) -> H256;
+	/// Template of the next block
+	fn block_template(&self) -> BlockTemplate;
+	/// Submit mined block through the normal acceptance path
+	fn submit_block(&self, block: IndexedBlock) -> Result<(), String>;
+}
+
+pub struct StratumCore {
+	local_sync_node: LocalNodeRef,
+}
+
+impl StratumCore {
+	pub fn new(local_sync_node: LocalNodeRef) -> Self {
+		StratumCore {
+			local_sync_node: local_sync_node,
+		}
+	}
+}
+
+impl StratumCoreApi for StratumCore {
+	fn best_block_hash(&self) -> H256 {
+		self.local_sync_node.storage.best_block().hash
+	}
+
+	fn block_template(&self) -> BlockTemplate {
+		self.local_sync_node.get_block_template()
+	}
+
+	fn submit_block(&self, block: IndexedBlock) -> Result<(), String> {
+		self.local_sync_node.spawn_block(block)
+	}
+}
@@ -0,0 +1,280 @@
+use std::collections::HashSet;
+use parking_lot::Mutex;
+use hex::ToHex;
+use chain::{BlockHeader, IndexedBlock, IndexedBlockHeader, IndexedTransaction, Transaction, merkle_node_hash};
+use miner::{BlockTemplate, CoinbaseTransactionBuilder};
+use primitives::bytes::Bytes;
+use primitives::compact::Compact;
+use primitives::hash::H256;
+use primitives::U256;
+use ser::{serialize, deserialize};
+use verification::is_valid_proof_of_work_hash;
+
+/// Size of the extranonce part, assigned by server to every session
+pub const EXTRANONCE1_SIZE: usize = 4;
+/// Size of the extranonce part, rolled by the miner
+pub const EXTRANONCE2_SIZE: usize = 4;
+/// Compact bits of the difficulty-1 target
+const DIFFICULTY_1_BITS: u32 = 0x1d00ffff;
+
+/// Share submission error
+#[derive(Debug, PartialEq)]
+pub enum ShareError {
+	/// Extranonce2 has wrong size
+	InvalidExtranonce,
+	/// Share time is before the template time
+	InvalidTime,
+	/// Share has been already submitted
+	Duplicate,
+	/// Share hash doesn't meet the session target
+	LowDifficulty,
+}
+
+/// Mining job, built from the block template
+pub struct Job {
+	/// Job identifier
+	id: String,
+	/// Block template
+	template: BlockTemplate,
+	/// Serialized coinbase transaction before the extranonce
+	coinbase1: Bytes,
+	/// Serialized coinbase transaction after the extranonce
+	coinbase2: Bytes,
+	/// Merkle branch of the coinbase transaction
+	merkle_branch: Vec<H256>,
+	/// Already submitted shares
+	submitted: Mutex<HashSet<Vec<u8>>>,
+}
+
+impl Job {
+	pub fn new<T>(id: String, template: BlockTemplate, coinbase_builder: T) -> Self where T: CoinbaseTransactionBuilder + Clone {
+		let (coinbase1, coinbase2) = split_coinbase(coinbase_builder, EXTRANONCE1_SIZE + EXTRANONCE2_SIZE);
+		let merkle_branch = merkle_branch(&template.transactions.iter().map(|tx| tx.hash.clone()).collect::<Vec<_>>());
+		Job {
+			id: id,
+			template: template,
+			coinbase1: coinbase1,
+			coinbase2: coinbase2,
+			merkle_branch: merkle_branch,
+			submitted: Mutex::new(HashSet::new()),
+		}
+	}
+
+	pub fn id(&self) -> &str {
+		&self.id
+	}
+
+	pub fn template(&self) -> &BlockTemplate {
+		&self.template
+	}
+
+	/// Parameters of `mining.notify` notification
+	pub fn notify_params(&self, clean_jobs: bool) -> Vec<::serde_json::Value> {
+		vec![
+			self.id.clone().into(),
+			stratum_previous_hash(&self.template.previous_header_hash).into(),
+			self.coinbase1.to_hex::<String>().into(),
+			self.coinbase2.to_hex::<String>().into(),
+			self.merkle_branch.iter().map(|hash| hash.to_hex::<String>().into()).collect::<Vec<::serde_json::Value>>().into(),
+			format!("{:08x}", self.template.version).into(),
+			format!("{:08x}", u32::from(self.template.bits)).into(),
+			format!("{:08x}", self.template.time).into(),
+			clean_jobs.into(),
+		]
+	}
+
+	/// Verify share, submitted by the miner.
+	/// Returns the block if share also satisfies the network target.
+	pub fn submit(&self, extranonce1: &[u8], extranonce2: &[u8], time: u32, nonce: u32, share_target: &U256) -> Result<Option<IndexedBlock>, ShareError> {
+		if extranonce2.len() != EXTRANONCE2_SIZE {
+			return Err(ShareError::InvalidExtranonce);
+		}
+		if time < self.template.time {
+			return Err(ShareError::InvalidTime);
+		}
+
+		let mut coinbase = Vec::with_capacity(self.coinbase1.len() + EXTRANONCE1_SIZE + EXTRANONCE2_SIZE + self.coinbase2.len());
+		coinbase.extend_from_slice(&self.coinbase1);
+		coinbase.extend_from_slice(extranonce1);
+		coinbase.extend_from_slice(extranonce2);
+		coinbase.extend_from_slice(&self.coinbase2);
+		let coinbase: Transaction = deserialize(&coinbase as &[u8]).expect("coinbase is built from serialized transaction parts; qed");
+		let coinbase = IndexedTransaction::from(coinbase);
+
+		let header = BlockHeader {
+			version: self.template.version,
+			previous_header_hash: self.template.previous_header_hash.clone(),
+			merkle_root_hash: merkle_root_from_branch(&coinbase.hash, &self.merkle_branch),
+			time: time,
+			bits: self.template.bits,
+			nonce: nonce,
+		};
+		let hash = header.hash();
+
+		let is_block = is_valid_proof_of_work_hash(self.template.bits, &hash);
+		if !is_block && U256::from(&*hash.reversed() as &[u8]) > *share_target {
+			return Err(ShareError::LowDifficulty);
+		}
+		if !self.submitted.lock().insert(hash.to_vec()) {
+			return Err(ShareError::Duplicate);
+		}
+		if !is_block {
+			return Ok(None);
+		}
+
+		let mut transactions = vec![coinbase];
+		transactions.extend(self.template.transactions.iter().cloned());
+		Ok(Some(IndexedBlock::new(IndexedBlockHeader::new(hash, header), transactions)))
+	}
+}
+
+/// Split serialized coinbase transaction into parts before and after the extranonce
+pub fn split_coinbase<T>(coinbase_builder: T, extranonce_size: usize) -> (Bytes, Bytes) where T: CoinbaseTransactionBuilder + Clone {
+	let serialize_with_extranonce = |byte: u8| {
+		let mut coinbase_builder = coinbase_builder.clone();
+		coinbase_builder.set_extranonce(&vec![byte; extranonce_size]);
+		serialize(&coinbase_builder.finish())
+	};
+
+	// the only difference between these two transactions is the extranonce
+	let zeroes = serialize_with_extranonce(0x00);
+	let ones = serialize_with_extranonce(0xff);
+	let position = zeroes.iter().zip(ones.iter())
+		.position(|(zero, one)| zero != one)
+		.expect("extranonce is a part of the coinbase transaction; qed");
+
+	(zeroes[..position].to_vec().into(), zeroes[position + extranonce_size..].to_vec().into())
+}
+
+/// Compute merkle branch of the coinbase transaction, given hashes of all other block transactions
+pub fn merkle_branch(hashes: &[H256]) -> Vec<H256> {
+	let mut branch = Vec::new();
+	let mut row = hashes.to_vec();
+	while !row.is_empty() {
+		// first element is paired with the coinbase path, others are paired with each other
+		branch.push(row[0].clone());
+
+		let rest = &row[1..];
+		let mut next_row = Vec::with_capacity(rest.len() / 2 + 1);
+		let mut i = 0;
+		while i < rest.len() {
+			// duplicate the last element if len is not even
+			let right = if i + 1 < rest.len() { &rest[i + 1] } else { &rest[i] };
+			next_row.push(merkle_node_hash(&rest[i], right));
+			i += 2;
+		}
+		row = next_row;
+	}
+	branch
+}
+
+/// Compute merkle root from the coinbase transaction hash and its merkle branch
+pub fn merkle_root_from_branch(coinbase_hash: &H256, branch: &[H256]) -> H256 {
+	branch.iter().fold(coinbase_hash.clone(), |root, hash| merkle_node_hash(&root, hash))
+}
+
+/// Target of the share with given difficulty
+pub fn difficulty_target(difficulty: u64) -> U256 {
+	let difficulty_1_target = Compact::new(DIFFICULTY_1_BITS).to_u256()
+		.expect("DIFFICULTY_1_BITS is a valid compact target; qed");
+	difficulty_1_target / U256::from(::std::cmp::max(difficulty, 1))
+}
+
+/// Previous block hash in stratum encoding: every 4-byte word is byte-swapped
+fn stratum_previous_hash(hash: &H256) -> String {
+	hash.chunks(4)
+		.flat_map(|word| word.iter().rev().cloned())
+		.collect::<Vec<u8>>()
+		.to_hex()
+}
+
+#[cfg(test)]
+mod tests {
+	use chain::{merkle_root, IndexedTransaction, Transaction};
+	use miner::{BlockTemplate, CoinbaseTransactionBuilder, ScriptCoinbaseTransactionBuilder};
+	use primitives::bytes::Bytes;
+	use primitives::hash::H256;
+	use primitives::U256;
+	use ser::serialize;
+	use super::{Job, ShareError, split_coinbase, merkle_branch, merkle_root_from_branch, difficulty_target,
+		stratum_previous_hash, EXTRANONCE1_SIZE};
+
+	fn template(transactions: Vec<IndexedTransaction>) -> BlockTemplate {
+		BlockTemplate {
+			version: 0x20000000,
+			previous_header_hash: H256::from(1),
+			time: 1_500_000_000,
+			// regtest difficulty
+			bits: 0x207fffff.into(),
+			height: 100,
+			transactions: transactions,
+			coinbase_value: 50_0000_0000,
+			size_limit: 1_000_000,
+			sigop_limit: 20_000,
+		}
+	}
+
+	fn transactions(count: usize) -> Vec<IndexedTransaction> {
+		(0..count)
+			.map(|i| Transaction { lock_time: i as u32, ..Default::default() }.into())
+			.collect()
+	}
+
+	#[test]
+	fn test_merkle_branch() {
+		for count in 0..10 {
+			let coinbase_hash = H256::from(0xff);
+			let hashes: Vec<H256> = (0..count).map(|i| H256::from(i as u8)).collect();
+			let mut all_hashes = vec![coinbase_hash.clone()];
+			all_hashes.extend(hashes.iter().cloned());
+
+			let branch = merkle_branch(&hashes);
+			assert_eq!(merkle_root_from_branch(&coinbase_hash, &branch), merkle_root(&all_hashes));
+		}
+	}
+
+	#[test]
+	fn test_split_coinbase() {
+		let coinbase_builder = ScriptCoinbaseTransactionBuilder::new(100, "51".into(), 50);
+		let (coinbase1, coinbase2) = split_coinbase(coinbase_builder.clone(), 8);
+
+		let mut expected_builder = coinbase_builder;
+		expected_builder.set_extranonce(&[1, 2, 3, 4, 5, 6, 7, 8]);
+		let mut coinbase = coinbase1.to_vec();
+		coinbase.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+		coinbase.extend_from_slice(&coinbase2);
+		assert_eq!(Bytes::from(coinbase), serialize(&expected_builder.finish()));
+	}
+
+	#[test]
+	fn test_stratum_previous_hash() {
+		let hash: H256 = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f".into();
+		assert_eq!(stratum_previous_hash(&hash), "03020100070605040b0a09080f0e0d0c13121110171615141b1a19181f1e1d1c");
+	}
+
+	#[test]
+	fn test_job_submit() {
+		let template = template(transactions(3));
+		let coinbase_builder = ScriptCoinbaseTransactionBuilder::new(template.height, "51".into(), template.coinbase_value);
+		let job = Job::new("1".into(), template, coinbase_builder);
+		let extranonce1 = [0u8; EXTRANONCE1_SIZE];
+
+		// every share is a block on regtest
+		let block = (0..).filter_map(|nonce| job.submit(&extranonce1, &[0, 0, 0, 1], 1_500_000_000, nonce, &U256::zero()).ok())
+			.next()
+			.unwrap()
+			.unwrap();
+		assert_eq!(block.transactions.len(), 4);
+		assert_eq!(block.header.raw.merkle_root_hash, merkle_root(&block.transactions.iter().map(|tx| &tx.hash).collect::<Vec<_>>()));
+
+		assert_eq!(job.submit(&extranonce1, &[0, 0, 0, 1], 1_500_000_000, block.header.raw.nonce, &U256::max_value()), Err(ShareError::Duplicate));
+		assert_eq!(job.submit(&extranonce1, &[0, 0, 1], 1_500_000_000, 0, &U256::max_value()), Err(ShareError::InvalidExtranonce));
+		assert_eq!(job.submit(&extranonce1, &[0, 0, 0, 1], 1_400_000_000, 0, &U256::max_value()), Err(ShareError::InvalidTime));
+	}
+
+	#[test]
+	fn test_difficulty_target() {
+		assert_eq!(difficulty_target(1), difficulty_target(0));
+		assert_eq!(difficulty_target(2), difficulty_target(1) / U256::from(2));
+	}
+}
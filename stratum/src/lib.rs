@@ -0,0 +1,30 @@
+//! Stratum v1 mining server
+//!
+//! Miners subscribe to jobs, built from the `BlockAssembler` templates. Every job carries the coinbase
+//! transaction split around the extranonce (`coinbase1`/`coinbase2`) and the merkle branch of the
+//! coinbase, so that miners could roll the extranonce2 without asking for the new work. Submitted
+//! shares are validated against the per-session (variable) difficulty and shares, satisfying the
+//! network target, are submitted to the node as blocks.
+
+#[macro_use]
+extern crate log;
+extern crate parking_lot;
+extern crate rustc_hex as hex;
+extern crate serde_json;
+
+extern crate chain;
+extern crate miner;
+extern crate primitives;
+extern crate serialization as ser;
+extern crate sync;
+extern crate verification;
+
+mod api;
+mod job;
+mod server;
+mod vardiff;
+
+pub use api::{StratumCoreApi, StratumCore};
+pub use job::{Job, ShareError, split_coinbase, merkle_branch, merkle_root_from_branch, difficulty_target};
+pub use server::{start, StratumConfig};
+pub use vardiff::{VarDiff, VarDiffConfig};
@@ -0,0 +1,508 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use parking_lot::{Mutex, RwLock};
+use serde_json::Value;
+use hex::{FromHex, ToHex};
+use miner::ScriptCoinbaseTransactionBuilder;
+use primitives::bytes::Bytes;
+use api::StratumCoreApi;
+use job::{Job, ShareError, difficulty_target, EXTRANONCE2_SIZE};
+use vardiff::{VarDiff, VarDiffConfig};
+
+/// Interval between best block checks
+const BEST_BLOCK_POLL_INTERVAL_MS: u64 = 500;
+/// Interval between job updates when best block hasn't changed (to include new transactions)
+const JOB_REFRESH_INTERVAL_SECS: u64 = 30;
+/// Number of jobs, for which shares are still accepted
+const MAX_RECENT_JOBS: usize = 8;
+
+/// Stratum error codes
+const ERROR_OTHER: u32 = 20;
+const ERROR_JOB_NOT_FOUND: u32 = 21;
+const ERROR_DUPLICATE_SHARE: u32 = 22;
+const ERROR_LOW_DIFFICULTY: u32 = 23;
+const ERROR_UNAUTHORIZED: u32 = 24;
+const ERROR_NOT_SUBSCRIBED: u32 = 25;
+
+/// Stratum server configuration
+#[derive(Debug, Clone)]
+pub struct StratumConfig {
+	/// Address to listen on
+	pub address: SocketAddr,
+	/// Script, which receives the coinbase value of mined blocks
+	pub coinbase_script_pubkey: Bytes,
+	/// Variable difficulty parameters
+	pub vardiff: VarDiffConfig,
+}
+
+/// Start stratum server in background threads
+pub fn start<T>(config: StratumConfig, core: T) -> Result<(), String> where T: StratumCoreApi {
+	let listener = try!(TcpListener::bind(&config.address).map_err(|e| format!("Failed to start stratum server: {}", e)));
+	let server = Arc::new(Server::new(config, core));
+	server.update_job(true);
+
+	let jobs_server = server.clone();
+	try!(thread::Builder::new()
+		.name("Stratum jobs thread".to_owned())
+		.spawn(move || jobs_server.update_jobs())
+		.map_err(|e| format!("Failed to start stratum server: {}", e)));
+
+	try!(thread::Builder::new()
+		.name("Stratum listener thread".to_owned())
+		.spawn(move || for stream in listener.incoming() {
+			match stream {
+				Ok(stream) => {
+					let connection_server = server.clone();
+					let _ = thread::Builder::new()
+						.name("Stratum connection thread".to_owned())
+						.spawn(move || connection_server.handle_connection(stream));
+				},
+				Err(e) => warn!(target: "stratum", "Failed to accept stratum connection: {}", e),
+			}
+		})
+		.map_err(|e| format!("Failed to start stratum server: {}", e)));
+
+	Ok(())
+}
+
+/// Jobs, for which shares are accepted
+#[derive(Default)]
+struct Jobs {
+	/// Identifier of the next job
+	next_id: u64,
+	/// Recent jobs, the last one is the current job
+	recent: VecDeque<Arc<Job>>,
+}
+
+impl Jobs {
+	fn current(&self) -> Option<Arc<Job>> {
+		self.recent.back().cloned()
+	}
+
+	fn find(&self, id: &str) -> Option<Arc<Job>> {
+		self.recent.iter().find(|job| job.id() == id).cloned()
+	}
+}
+
+/// Miner connection state
+struct SessionState {
+	/// True if miner has subscribed to jobs notifications
+	subscribed: bool,
+	/// Authorized workers
+	workers: HashSet<String>,
+	/// Session difficulty
+	vardiff: VarDiff,
+	/// Difficulty before the last retarget (miner could still work on shares of this difficulty)
+	previous_difficulty: u64,
+}
+
+/// Miner connection
+struct Session {
+	/// Extranonce part, unique for every session
+	extranonce1: Bytes,
+	/// Writing half of the connection
+	writer: Mutex<Box<Write + Send>>,
+	/// Session state
+	state: Mutex<SessionState>,
+}
+
+impl Session {
+	fn new(id: u32, writer: Box<Write + Send>, vardiff: VarDiffConfig) -> Self {
+		let vardiff = VarDiff::new(vardiff, unix_time());
+		let difficulty = vardiff.difficulty();
+		Session {
+			extranonce1: vec![(id >> 24) as u8, (id >> 16) as u8, (id >> 8) as u8, id as u8].into(),
+			writer: Mutex::new(writer),
+			state: Mutex::new(SessionState {
+				subscribed: false,
+				workers: HashSet::new(),
+				vardiff: vardiff,
+				previous_difficulty: difficulty,
+			}),
+		}
+	}
+
+	/// Send messages to the miner
+	fn send(&self, messages: Vec<Value>) -> io::Result<()> {
+		let mut writer = self.writer.lock();
+		for message in messages {
+			try!(writer.write_all(message.to_string().as_bytes()));
+			try!(writer.write_all(b"\n"));
+		}
+		writer.flush()
+	}
+
+	/// Notifications, which should be sent to the miner when new job is available
+	fn job_notifications(&self, job: &Job, clean_jobs: bool) -> Vec<Value> {
+		let mut messages = Vec::new();
+		let mut state = self.state.lock();
+		if !state.subscribed {
+			return messages;
+		}
+
+		let difficulty = state.vardiff.difficulty();
+		if let Some(new_difficulty) = state.vardiff.retarget(unix_time()) {
+			state.previous_difficulty = difficulty;
+			messages.push(notification("mining.set_difficulty", vec![new_difficulty.into()]));
+		}
+		messages.push(notification("mining.notify", job.notify_params(clean_jobs)));
+		messages
+	}
+}
+
+struct Server<T> {
+	config: StratumConfig,
+	core: T,
+	jobs: RwLock<Jobs>,
+	sessions: Mutex<HashMap<u32, Arc<Session>>>,
+	next_session_id: AtomicUsize,
+}
+
+impl<T> Server<T> where T: StratumCoreApi {
+	fn new(config: StratumConfig, core: T) -> Self {
+		Server {
+			config: config,
+			core: core,
+			jobs: RwLock::new(Jobs::default()),
+			sessions: Mutex::new(HashMap::new()),
+			next_session_id: AtomicUsize::new(1),
+		}
+	}
+
+	/// Create new job and notify all subscribed miners
+	fn update_job(&self, clean_jobs: bool) {
+		let template = self.core.block_template();
+		let coinbase_builder = ScriptCoinbaseTransactionBuilder::new(template.height, self.config.coinbase_script_pubkey.clone(), template.coinbase_value);
+
+		let job = {
+			let mut jobs = self.jobs.write();
+			let job = Arc::new(Job::new(format!("{:x}", jobs.next_id), template, coinbase_builder));
+			jobs.next_id += 1;
+			if clean_jobs {
+				jobs.recent.clear();
+			}
+			if jobs.recent.len() == MAX_RECENT_JOBS {
+				jobs.recent.pop_front();
+			}
+			jobs.recent.push_back(job.clone());
+			job
+		};
+
+		trace!(target: "stratum", "New stratum job {} at height {}", job.id(), job.template().height);
+		let sessions: Vec<_> = self.sessions.lock().values().cloned().collect();
+		for session in sessions {
+			let messages = session.job_notifications(&job, clean_jobs);
+			if let Err(e) = session.send(messages) {
+				debug!(target: "stratum", "Failed to send stratum job: {}", e);
+			}
+		}
+	}
+
+	/// Keep jobs up to date with the best block and memory pool
+	fn update_jobs(&self) {
+		let mut best_block_hash = self.core.best_block_hash();
+		let mut last_update = Instant::now();
+		loop {
+			thread::sleep(Duration::from_millis(BEST_BLOCK_POLL_INTERVAL_MS));
+
+			let new_best_block_hash = self.core.best_block_hash();
+			let is_new_block = new_best_block_hash != best_block_hash;
+			if is_new_block || last_update.elapsed() >= Duration::from_secs(JOB_REFRESH_INTERVAL_SECS) {
+				best_block_hash = new_best_block_hash;
+				last_update = Instant::now();
+				self.update_job(is_new_block);
+			}
+		}
+	}
+
+	/// Read line-delimited requests from the miner until connection is closed
+	fn handle_connection(&self, stream: TcpStream) {
+		let peer_address = stream.peer_addr().ok();
+		let writer = match stream.try_clone() {
+			Ok(writer) => writer,
+			Err(e) => {
+				warn!(target: "stratum", "Failed to setup stratum connection: {}", e);
+				return;
+			},
+		};
+
+		let id = self.next_session_id.fetch_add(1, Ordering::SeqCst) as u32;
+		let session = Arc::new(Session::new(id, Box::new(writer), self.config.vardiff.clone()));
+		self.sessions.lock().insert(id, session.clone());
+		debug!(target: "stratum", "Stratum connection from {:?}", peer_address);
+
+		for line in BufReader::new(stream).lines() {
+			let line = match line {
+				Ok(ref line) if line.trim().is_empty() => continue,
+				Ok(line) => line,
+				Err(_) => break,
+			};
+			let request: Value = match ::serde_json::from_str(&line) {
+				Ok(request) => request,
+				Err(e) => {
+					debug!(target: "stratum", "Invalid stratum request from {:?}: {}", peer_address, e);
+					break;
+				},
+			};
+			if session.send(self.handle_request(&session, &request)).is_err() {
+				break;
+			}
+		}
+
+		self.sessions.lock().remove(&id);
+		debug!(target: "stratum", "Stratum connection from {:?} closed", peer_address);
+	}
+
+	/// Handle single request. Returns messages, which should be sent back to the miner.
+	fn handle_request(&self, session: &Session, request: &Value) -> Vec<Value> {
+		let id = request.get("id").cloned().unwrap_or(Value::Null);
+		let params = request.get("params").and_then(Value::as_array).cloned().unwrap_or_default();
+		match request.get("method").and_then(Value::as_str) {
+			Some("mining.subscribe") => self.subscribe(session, id),
+			Some("mining.authorize") => vec![response(id, self.authorize(session, &params))],
+			Some("mining.extranonce.subscribe") => vec![response(id, Ok(false.into()))],
+			Some("mining.submit") => self.submit(session, id, &params),
+			_ => vec![response(id, Err((ERROR_OTHER, "Unsupported method")))],
+		}
+	}
+
+	fn subscribe(&self, session: &Session, id: Value) -> Vec<Value> {
+		let subscription_id: String = session.extranonce1.to_hex();
+		let result = Value::Array(vec![
+			Value::Array(vec![
+				Value::Array(vec!["mining.set_difficulty".into(), subscription_id.clone().into()]),
+				Value::Array(vec!["mining.notify".into(), subscription_id.clone().into()]),
+			]),
+			subscription_id.into(),
+			EXTRANONCE2_SIZE.into(),
+		]);
+
+		let difficulty = {
+			let mut state = session.state.lock();
+			state.subscribed = true;
+			state.vardiff.difficulty()
+		};
+
+		let mut messages = vec![
+			response(id, Ok(result)),
+			notification("mining.set_difficulty", vec![difficulty.into()]),
+		];
+		if let Some(job) = self.jobs.read().current() {
+			messages.push(notification("mining.notify", job.notify_params(true)));
+		}
+		messages
+	}
+
+	fn authorize(&self, session: &Session, params: &[Value]) -> Result<Value, (u32, &'static str)> {
+		let worker = try!(params.get(0).and_then(Value::as_str).ok_or((ERROR_OTHER, "Invalid parameters")));
+		session.state.lock().workers.insert(worker.to_owned());
+		Ok(true.into())
+	}
+
+	fn submit(&self, session: &Session, id: Value, params: &[Value]) -> Vec<Value> {
+		let (result, new_difficulty) = self.check_share(session, params);
+		let mut messages = vec![response(id, result)];
+		if let Some(new_difficulty) = new_difficulty {
+			messages.push(notification("mining.set_difficulty", vec![new_difficulty.into()]));
+		}
+		messages
+	}
+
+	/// Verify submitted share and submit block if share satisfies the network target.
+	/// Returns submission result and new session difficulty, if it has been changed.
+	fn check_share(&self, session: &Session, params: &[Value]) -> (Result<Value, (u32, &'static str)>, Option<u64>) {
+		let param = |index: usize| params.get(index).and_then(Value::as_str);
+		let (worker, job_id, extranonce2, time, nonce) = match (param(0), param(1), param(2), param(3), param(4)) {
+			(Some(worker), Some(job_id), Some(extranonce2), Some(time), Some(nonce)) => (worker, job_id, extranonce2, time, nonce),
+			_ => return (Err((ERROR_OTHER, "Invalid parameters")), None),
+		};
+		let (extranonce2, time, nonce) = match (extranonce2.from_hex::<Vec<u8>>(), u32::from_str_radix(time, 16), u32::from_str_radix(nonce, 16)) {
+			(Ok(extranonce2), Ok(time), Ok(nonce)) => (extranonce2, time, nonce),
+			_ => return (Err((ERROR_OTHER, "Invalid parameters")), None),
+		};
+
+		let share_target = {
+			let state = session.state.lock();
+			if !state.subscribed {
+				return (Err((ERROR_NOT_SUBSCRIBED, "Not subscribed")), None);
+			}
+			if !state.workers.contains(worker) {
+				return (Err((ERROR_UNAUTHORIZED, "Unauthorized worker")), None);
+			}
+			difficulty_target(::std::cmp::min(state.vardiff.difficulty(), state.previous_difficulty))
+		};
+
+		let job = match self.jobs.read().find(job_id) {
+			Some(job) => job,
+			None => return (Err((ERROR_JOB_NOT_FOUND, "Job not found")), None),
+		};
+
+		match job.submit(&session.extranonce1, &extranonce2, time, nonce, &share_target) {
+			Ok(block) => {
+				if let Some(block) = block {
+					let block_hash = block.hash().clone();
+					match self.core.submit_block(block) {
+						Ok(_) => info!(target: "stratum", "Block {} is mined by {}", block_hash.to_reversed_str(), worker),
+						Err(e) => warn!(target: "stratum", "Block {}, mined by {}, is rejected: {}", block_hash.to_reversed_str(), worker, e),
+					}
+				}
+				let mut state = session.state.lock();
+				let difficulty = state.vardiff.difficulty();
+				let new_difficulty = state.vardiff.on_share(unix_time());
+				if new_difficulty.is_some() {
+					state.previous_difficulty = difficulty;
+				}
+				(Ok(true.into()), new_difficulty)
+			},
+			Err(ShareError::Duplicate) => (Err((ERROR_DUPLICATE_SHARE, "Duplicate share")), None),
+			Err(ShareError::LowDifficulty) => (Err((ERROR_LOW_DIFFICULTY, "Low difficulty share")), None),
+			Err(ShareError::InvalidExtranonce) => (Err((ERROR_OTHER, "Invalid extranonce2 size")), None),
+			Err(ShareError::InvalidTime) => (Err((ERROR_OTHER, "Invalid time")), None),
+		}
+	}
+}
+
+fn response(id: Value, result: Result<Value, (u32, &'static str)>) -> Value {
+	let (result, error) = match result {
+		Ok(result) => (result, Value::Null),
+		Err((code, message)) => (Value::Null, Value::Array(vec![code.into(), message.into(), Value::Null])),
+	};
+
+	let mut response = ::serde_json::Map::new();
+	response.insert("id".into(), id);
+	response.insert("result".into(), result);
+	response.insert("error".into(), error);
+	Value::Object(response)
+}
+
+fn notification(method: &str, params: Vec<Value>) -> Value {
+	let mut notification = ::serde_json::Map::new();
+	notification.insert("id".into(), Value::Null);
+	notification.insert("method".into(), method.into());
+	notification.insert("params".into(), Value::Array(params));
+	Value::Object(notification)
+}
+
+fn unix_time() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io;
+	use std::sync::Arc;
+	use parking_lot::Mutex;
+	use serde_json::{self, Value};
+	use chain::IndexedBlock;
+	use miner::BlockTemplate;
+	use primitives::hash::H256;
+	use api::StratumCoreApi;
+	use vardiff::VarDiffConfig;
+	use super::{Server, Session, StratumConfig};
+
+	#[derive(Default)]
+	struct TestCore {
+		submitted: Arc<Mutex<Vec<IndexedBlock>>>,
+	}
+
+	impl StratumCoreApi for TestCore {
+		fn best_block_hash(&self) -> H256 {
+			H256::from(1)
+		}
+
+		fn block_template(&self) -> BlockTemplate {
+			BlockTemplate {
+				version: 0x20000000,
+				previous_header_hash: H256::from(1),
+				time: 1_500_000_000,
+				// regtest difficulty
+				bits: 0x207fffff.into(),
+				height: 1,
+				transactions: vec![],
+				coinbase_value: 50_0000_0000,
+				size_limit: 1_000_000,
+				sigop_limit: 20_000,
+			}
+		}
+
+		fn submit_block(&self, block: IndexedBlock) -> Result<(), String> {
+			self.submitted.lock().push(block);
+			Ok(())
+		}
+	}
+
+	fn server(core: TestCore) -> Server<TestCore> {
+		let config = StratumConfig {
+			address: "127.0.0.1:3333".parse().unwrap(),
+			coinbase_script_pubkey: "51".into(),
+			vardiff: VarDiffConfig::default(),
+		};
+		let server = Server::new(config, core);
+		server.update_job(true);
+		server
+	}
+
+	fn request(server: &Server<TestCore>, session: &Session, request: &str) -> Vec<String> {
+		server.handle_request(session, &serde_json::from_str::<Value>(request).unwrap())
+			.into_iter()
+			.map(|message| message.to_string())
+			.collect()
+	}
+
+	#[test]
+	fn stratum_subscribe_and_authorize() {
+		let server = server(TestCore::default());
+		let session = Session::new(1, Box::new(io::sink()), VarDiffConfig::default());
+
+		let messages = request(&server, &session, r#"{"id":1,"method":"mining.subscribe","params":[]}"#);
+		assert_eq!(messages.len(), 3);
+		assert_eq!(&messages[0], r#"{"error":null,"id":1,"result":[[["mining.set_difficulty","00000001"],["mining.notify","00000001"]],"00000001",4]}"#);
+		assert_eq!(&messages[1], r#"{"id":null,"method":"mining.set_difficulty","params":[1024]}"#);
+		assert!(messages[2].starts_with(r#"{"id":null,"method":"mining.notify","params":["0","#));
+
+		let messages = request(&server, &session, r#"{"id":2,"method":"mining.authorize","params":["worker","x"]}"#);
+		assert_eq!(messages, vec![r#"{"error":null,"id":2,"result":true}"#.to_owned()]);
+	}
+
+	#[test]
+	fn stratum_submit_rejected() {
+		let server = server(TestCore::default());
+		let session = Session::new(1, Box::new(io::sink()), VarDiffConfig::default());
+
+		let messages = request(&server, &session, r#"{"id":1,"method":"mining.submit","params":["worker","0","00000000","59682f00","00000000"]}"#);
+		assert_eq!(messages, vec![r#"{"error":[25,"Not subscribed",null],"id":1,"result":null}"#.to_owned()]);
+
+		request(&server, &session, r#"{"id":2,"method":"mining.subscribe","params":[]}"#);
+		let messages = request(&server, &session, r#"{"id":3,"method":"mining.submit","params":["worker","0","00000000","59682f00","00000000"]}"#);
+		assert_eq!(messages, vec![r#"{"error":[24,"Unauthorized worker",null],"id":3,"result":null}"#.to_owned()]);
+
+		request(&server, &session, r#"{"id":4,"method":"mining.authorize","params":["worker","x"]}"#);
+		let messages = request(&server, &session, r#"{"id":5,"method":"mining.submit","params":["worker","ff","00000000","59682f00","00000000"]}"#);
+		assert_eq!(messages, vec![r#"{"error":[21,"Job not found",null],"id":5,"result":null}"#.to_owned()]);
+
+		let messages = request(&server, &session, r#"{"id":6,"method":"mining.unknown","params":[]}"#);
+		assert_eq!(messages, vec![r#"{"error":[20,"Unsupported method",null],"id":6,"result":null}"#.to_owned()]);
+	}
+
+	#[test]
+	fn stratum_submit_block() {
+		let submitted = Arc::new(Mutex::new(Vec::new()));
+		let server = server(TestCore { submitted: submitted.clone() });
+		let session = Session::new(1, Box::new(io::sink()), VarDiffConfig::default());
+		request(&server, &session, r#"{"id":1,"method":"mining.subscribe","params":[]}"#);
+		request(&server, &session, r#"{"id":2,"method":"mining.authorize","params":["worker","x"]}"#);
+
+		// session difficulty is too high for cpu, but every second share is a block on regtest
+		let accepted = (0u32..64)
+			.map(|nonce| request(&server, &session, &format!(r#"{{"id":3,"method":"mining.submit","params":["worker","0","00000000","59682f00","{:08x}"]}}"#, nonce)))
+			.filter(|messages| messages[0] == r#"{"error":null,"id":3,"result":true}"#)
+			.count();
+		assert!(accepted > 0);
+		assert_eq!(submitted.lock().len(), accepted);
+	}
+}
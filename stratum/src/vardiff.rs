@@ -0,0 +1,132 @@
+use std::cmp;
+
+/// Variable difficulty parameters
+#[derive(Debug, Clone)]
+pub struct VarDiffConfig {
+	/// Difficulty, assigned to the new session
+	pub initial_difficulty: u64,
+	/// Minimal session difficulty
+	pub min_difficulty: u64,
+	/// Maximal session difficulty
+	pub max_difficulty: u64,
+	/// Desired number of seconds between shares
+	pub target_share_interval: u64,
+	/// Number of seconds between difficulty adjustments
+	pub retarget_interval: u64,
+}
+
+impl Default for VarDiffConfig {
+	fn default() -> Self {
+		VarDiffConfig {
+			initial_difficulty: 1024,
+			min_difficulty: 1,
+			max_difficulty: 1 << 40,
+			target_share_interval: 10,
+			retarget_interval: 60,
+		}
+	}
+}
+
+/// Maximal factor, by which difficulty could change during single retarget
+const MAX_RETARGET_FACTOR: u64 = 4;
+
+/// Per-session difficulty, adjusted to keep the rate of shares close to the target
+pub struct VarDiff {
+	config: VarDiffConfig,
+	difficulty: u64,
+	shares: u64,
+	window_start: u64,
+}
+
+impl VarDiff {
+	pub fn new(config: VarDiffConfig, now: u64) -> Self {
+		let difficulty = cmp::min(cmp::max(config.initial_difficulty, config.min_difficulty), config.max_difficulty);
+		VarDiff {
+			config: config,
+			difficulty: difficulty,
+			shares: 0,
+			window_start: now,
+		}
+	}
+
+	/// Current difficulty
+	pub fn difficulty(&self) -> u64 {
+		self.difficulty
+	}
+
+	/// Register accepted share. Returns new difficulty if it has been changed.
+	pub fn on_share(&mut self, now: u64) -> Option<u64> {
+		self.shares += 1;
+		self.retarget(now)
+	}
+
+	/// Adjust difficulty if retarget interval has passed. Returns new difficulty if it has been changed.
+	pub fn retarget(&mut self, now: u64) -> Option<u64> {
+		let elapsed = now.saturating_sub(self.window_start);
+		if elapsed < self.config.retarget_interval {
+			return None;
+		}
+
+		let desired = (self.difficulty as f64 * self.shares as f64 * self.config.target_share_interval as f64 / elapsed as f64) as u64;
+		let desired = cmp::min(cmp::max(desired, self.difficulty / MAX_RETARGET_FACTOR), self.difficulty.saturating_mul(MAX_RETARGET_FACTOR));
+		let desired = cmp::min(cmp::max(desired, self.config.min_difficulty), self.config.max_difficulty);
+
+		self.shares = 0;
+		self.window_start = now;
+		if desired == self.difficulty {
+			return None;
+		}
+
+		self.difficulty = desired;
+		Some(desired)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{VarDiff, VarDiffConfig};
+
+	#[test]
+	fn test_vardiff_no_retarget_before_interval() {
+		let mut vardiff = VarDiff::new(VarDiffConfig::default(), 0);
+		for _ in 0..100 {
+			assert_eq!(vardiff.on_share(59), None);
+		}
+		assert_eq!(vardiff.difficulty(), 1024);
+	}
+
+	#[test]
+	fn test_vardiff_increases_difficulty() {
+		let mut vardiff = VarDiff::new(VarDiffConfig::default(), 0);
+		// 12 shares in 60 seconds, while 6 shares are expected
+		for _ in 0..11 {
+			vardiff.on_share(30);
+		}
+		assert_eq!(vardiff.on_share(60), Some(2048));
+	}
+
+	#[test]
+	fn test_vardiff_decreases_difficulty() {
+		let mut vardiff = VarDiff::new(VarDiffConfig::default(), 0);
+		// no shares at all => max decrease
+		assert_eq!(vardiff.retarget(60), Some(256));
+		assert_eq!(vardiff.retarget(120), Some(64));
+	}
+
+	#[test]
+	fn test_vardiff_respects_limits() {
+		let config = VarDiffConfig {
+			initial_difficulty: 2,
+			min_difficulty: 1,
+			max_difficulty: 4,
+			..Default::default()
+		};
+		let mut vardiff = VarDiff::new(config, 0);
+		assert_eq!(vardiff.retarget(60), Some(1));
+		assert_eq!(vardiff.retarget(120), None);
+		for _ in 0..1000 {
+			vardiff.on_share(130);
+		}
+		assert_eq!(vardiff.on_share(180), Some(4));
+	}
+}
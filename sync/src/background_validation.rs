@@ -0,0 +1,233 @@
+use std::io;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Sender, Receiver};
+use std::thread;
+use time::precise_time_s;
+use chain::IndexedBlock;
+use message::types;
+use message::common::{InventoryType, InventoryVector};
+use network::ConsensusParams;
+use primitives::hash::H256;
+use storage::{SnapshotBase, SnapshotValidation};
+use blocks_writer::BlocksWriter;
+use synchronization_executor::Task;
+use types::{PeerIndex, StorageRef};
+use {Error, VerificationParameters};
+
+/// Maximal number of blocks above the background chainstate best block, which are requested from peers
+const MAX_BACKGROUND_BLOCKS_WINDOW: u32 = 128;
+/// Maximal number of blocks to request from single peer
+const MAX_BACKGROUND_BLOCKS_IN_REQUEST: usize = 16;
+/// Time after which block is requested again
+const BACKGROUND_BLOCK_REQUEST_TIMEOUT_S: f64 = 60_f64;
+
+/// Background validation thread tasks
+enum BackgroundValidationTask {
+    /// Verify and insert block into the background chainstate
+    Block(IndexedBlock),
+    /// Stop background validation thread
+    Stop,
+}
+
+/// Background validation of the chainstate, seeded from UTXO set snapshot.
+/// Blocks from genesis to the snapshot base are downloaded, verified and inserted into the
+/// separate (background) chainstate. When background chainstate reaches the snapshot base,
+/// its UTXO set is compared to the snapshot and validation result is saved to the snapshot chainstate.
+pub struct BackgroundValidation {
+    /// Chainstate, seeded from the snapshot
+    snapshot_storage: StorageRef,
+    /// Chainstate, which is validating blocks from genesis to the snapshot base
+    storage: StorageRef,
+    /// Snapshot base block
+    base: SnapshotBase,
+    /// Number of the next block to request
+    next_block_number: u32,
+    /// Requested blocks with time of the request
+    requested: HashMap<H256, f64>,
+    /// True when validation thread has completed (or failed)
+    is_completed: Arc<AtomicBool>,
+    /// Validation work transmission channel
+    sender: Sender<BackgroundValidationTask>,
+    /// Validation thread
+    worker_thread: Option<thread::JoinHandle<()>>,
+}
+
+impl BackgroundValidation {
+    /// Starts background validation of the `snapshot_storage`, using `storage` as background chainstate.
+    /// `storage` must contain at least genesis block
+    pub fn new(
+        snapshot_storage: StorageRef,
+        storage: StorageRef,
+        consensus: ConsensusParams,
+        verification_params: VerificationParameters,
+    ) -> Result<Self, String> {
+        let base = match snapshot_storage.snapshot_base() {
+            Some(base) => base,
+            None => return Err("Chainstate is not seeded from UTXO set snapshot".into()),
+        };
+        if storage.block_hash(0) != snapshot_storage.block_hash(0) {
+            return Err("Background chainstate has different genesis block".into());
+        }
+
+        let next_block_number = storage.best_block().number + 1;
+        let is_completed = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = channel();
+        let worker_thread = {
+            let snapshot_storage = snapshot_storage.clone();
+            let storage = storage.clone();
+            let base = base.clone();
+            let is_completed = is_completed.clone();
+            thread::Builder::new()
+                .name("Sync background validation thread".to_string())
+                .spawn(move || {
+                    let mut writer = BlocksWriter::new(storage.clone(), consensus, verification_params);
+                    BackgroundValidation::worker_proc(&mut writer, snapshot_storage, storage, base, receiver);
+                    is_completed.store(true, Ordering::SeqCst);
+                })
+                .expect("Error creating background validation thread")
+        };
+
+        info!(target: "sync", "Starting background validation of UTXO set snapshot at block#{} from block#{}",
+            base.block_number, next_block_number);
+
+        Ok(BackgroundValidation {
+            snapshot_storage: snapshot_storage,
+            storage: storage,
+            base: base,
+            next_block_number: next_block_number,
+            requested: HashMap::new(),
+            is_completed: is_completed,
+            sender: sender,
+            worker_thread: Some(worker_thread),
+        })
+    }
+
+    /// Returns true if validation has completed
+    pub fn is_completed(&self) -> bool {
+        self.is_completed.load(Ordering::SeqCst)
+    }
+
+    /// Returns true if there are no requested blocks
+    pub fn is_idle(&self) -> bool {
+        self.requested.is_empty()
+    }
+
+    /// Passes block to the validation thread if it has been requested by the background validation.
+    /// Otherwise returns the block back
+    pub fn on_block(&mut self, block: IndexedBlock) -> Option<IndexedBlock> {
+        if self.requested.remove(block.hash()).is_none() {
+            return Some(block);
+        }
+
+        // if validation thread has stopped, block is just dropped
+        let _ = self.sender.send(BackgroundValidationTask::Block(block));
+        None
+    }
+
+    /// Prepare requests for blocks, required by the background validation
+    pub fn prepare_blocks_requests_tasks(&mut self, peers: &[PeerIndex], inv_type: InventoryType) -> Vec<Task> {
+        if peers.is_empty() || self.is_completed() {
+            return Vec::new();
+        }
+
+        // ask again for blocks, which have not been received in time
+        let now = precise_time_s();
+        let mut hashes: Vec<H256> = self.requested.iter()
+            .filter(|&(_, time)| now - *time > BACKGROUND_BLOCK_REQUEST_TIMEOUT_S)
+            .map(|(hash, _)| hash.clone())
+            .collect();
+
+        // ask for next blocks, keeping number of blocks in memory limited
+        let window_end = self.storage.best_block().number + MAX_BACKGROUND_BLOCKS_WINDOW;
+        let max_block_number = ::std::cmp::min(self.base.block_number, window_end);
+        while self.next_block_number <= max_block_number {
+            match self.snapshot_storage.block_hash(self.next_block_number) {
+                Some(hash) => hashes.push(hash),
+                None => break,
+            }
+            self.next_block_number += 1;
+        }
+
+        for hash in &hashes {
+            self.requested.insert(hash.clone(), now);
+        }
+
+        hashes
+            .chunks(MAX_BACKGROUND_BLOCKS_IN_REQUEST)
+            .zip(peers.iter().cycle())
+            .map(|(chunk, peer)| Task::GetData(*peer, types::GetData {
+                inventory: chunk.iter()
+                    .map(|hash| InventoryVector {
+                        inv_type: inv_type,
+                        hash: hash.clone(),
+                    })
+                    .collect(),
+            }))
+            .collect()
+    }
+
+    fn worker_proc(
+        writer: &mut BlocksWriter,
+        snapshot_storage: StorageRef,
+        storage: StorageRef,
+        base: SnapshotBase,
+        receiver: Receiver<BackgroundValidationTask>,
+    ) {
+        loop {
+            if storage.best_block().number >= base.block_number {
+                let validation = match storage.dump_snapshot(&mut io::sink()) {
+                    Ok(ref info) if info.base == base => SnapshotValidation::Valid,
+                    Ok(info) => {
+                        error!(target: "sync", "UTXO set snapshot does not match background chainstate: expected {:?}, actual {:?}", base, info.base);
+                        SnapshotValidation::Invalid
+                    },
+                    Err(err) => {
+                        error!(target: "sync", "Failed to compute UTXO set of background chainstate: {:?}", err);
+                        return;
+                    },
+                };
+                BackgroundValidation::complete(&snapshot_storage, validation);
+                return;
+            }
+
+            let block = match receiver.recv() {
+                Ok(BackgroundValidationTask::Block(block)) => block,
+                Ok(BackgroundValidationTask::Stop) | Err(_) => break,
+            };
+            let block_hash = block.hash().clone();
+            match writer.append_block(block) {
+                Ok(()) => (),
+                Err(Error::Verification(err)) => {
+                    // block of the snapshot chain is invalid => snapshot is invalid
+                    error!(target: "sync", "Background validation has failed at block {}: {}", block_hash.to_reversed_str(), err);
+                    BackgroundValidation::complete(&snapshot_storage, SnapshotValidation::Invalid);
+                    return;
+                },
+                Err(err) => {
+                    error!(target: "sync", "Background validation has stopped at block {}: {:?}", block_hash.to_reversed_str(), err);
+                    return;
+                },
+            }
+        }
+
+        trace!(target: "sync", "Stopping background validation thread");
+    }
+
+    fn complete(snapshot_storage: &StorageRef, validation: SnapshotValidation) {
+        match snapshot_storage.set_snapshot_validation(validation) {
+            Ok(()) => info!(target: "sync", "Background validation of UTXO set snapshot has completed: {:?}", validation),
+            Err(err) => error!(target: "sync", "Failed to save UTXO set snapshot validation result: {:?}", err),
+        }
+    }
+}
+
+impl Drop for BackgroundValidation {
+    fn drop(&mut self) {
+        if let Some(join_handle) = self.worker_thread.take() {
+            let _ = self.sender.send(BackgroundValidationTask::Stop);
+            join_handle.join().expect("Clean shutdown.");
+        }
+    }
+}
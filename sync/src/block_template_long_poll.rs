@@ -0,0 +1,155 @@
+use std::sync::Arc;
+use std::time::Duration;
+use parking_lot::{Mutex, Condvar};
+use chain::IndexedTransaction;
+use primitives::hash::H256;
+use super::SyncListener;
+
+/// Length of hex-encoded block hash part of long poll id
+const LONG_POLL_ID_HASH_LEN: usize = 64;
+
+/// Tracks changes of the data, used to build block templates, so that
+/// `getblocktemplate` long polling requests could be released when template changes.
+/// Long poll id is the hex-encoded best block hash, followed by the number of memory pool updates.
+pub struct BlockTemplateLongPoll {
+    /// Current state
+    state: Mutex<BlockTemplateLongPollState>,
+    /// Waiters of the state change
+    waiter: Condvar,
+}
+
+struct BlockTemplateLongPollState {
+    /// Best storage block hash
+    best_block_hash: H256,
+    /// Number of memory pool updates
+    transactions_updated: u64,
+}
+
+/// Synchronization listener, which updates block template long poll state
+pub struct BlockTemplateLongPollListener {
+    long_poll: Arc<BlockTemplateLongPoll>,
+}
+
+impl BlockTemplateLongPoll {
+    pub fn new(best_block_hash: H256) -> Self {
+        BlockTemplateLongPoll {
+            state: Mutex::new(BlockTemplateLongPollState {
+                best_block_hash: best_block_hash,
+                transactions_updated: 0,
+            }),
+            waiter: Condvar::new(),
+        }
+    }
+
+    /// Get id of the current block template state
+    pub fn long_poll_id(&self) -> String {
+        self.state.lock().long_poll_id()
+    }
+
+    /// Wait until the block template changes, comparing to the state with given id.
+    /// Returns immediately if new best block has been inserted since then.
+    /// Otherwise waits for new best block, checking for memory pool changes every `interval`.
+    pub fn wait(&self, long_poll_id: &str, interval: Duration) {
+        let mut state = self.state.lock();
+        loop {
+            if !state.is_same_best_block(long_poll_id) {
+                return;
+            }
+
+            let timed_out = self.waiter.wait_for(&mut state, interval).timed_out();
+            if timed_out && state.long_poll_id() != long_poll_id {
+                return;
+            }
+        }
+    }
+
+    fn on_best_block_inserted(&self, block_hash: &H256) {
+        {
+            let mut state = self.state.lock();
+            state.best_block_hash = block_hash.clone();
+            state.transactions_updated += 1;
+        }
+        self.waiter.notify_all();
+    }
+
+    fn on_transaction_accepted(&self) {
+        self.state.lock().transactions_updated += 1;
+    }
+}
+
+impl BlockTemplateLongPollState {
+    fn long_poll_id(&self) -> String {
+        format!("{}{}", self.best_block_hash.to_reversed_str(), self.transactions_updated)
+    }
+
+    fn is_same_best_block(&self, long_poll_id: &str) -> bool {
+        long_poll_id.len() >= LONG_POLL_ID_HASH_LEN &&
+            long_poll_id.is_char_boundary(LONG_POLL_ID_HASH_LEN) &&
+            long_poll_id[..LONG_POLL_ID_HASH_LEN] == self.best_block_hash.to_reversed_str()[..]
+    }
+}
+
+impl BlockTemplateLongPollListener {
+    pub fn new(long_poll: Arc<BlockTemplateLongPoll>) -> Self {
+        BlockTemplateLongPollListener { long_poll: long_poll }
+    }
+}
+
+impl SyncListener for BlockTemplateLongPollListener {
+    fn synchronization_state_switched(&self, _is_synchronizing: bool) {}
+
+    fn best_storage_block_inserted(&self, block_hash: &H256) {
+        self.long_poll.on_best_block_inserted(block_hash);
+    }
+
+    fn transaction_accepted(&self, _transaction: &IndexedTransaction, _fee: u64) {
+        self.long_poll.on_transaction_accepted();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+    use chain::IndexedTransaction;
+    use primitives::hash::H256;
+    use SyncListener;
+    use super::{BlockTemplateLongPoll, BlockTemplateLongPollListener};
+
+    #[test]
+    fn long_poll_id_changes_on_memory_pool_update() {
+        let long_poll = Arc::new(BlockTemplateLongPoll::new(H256::from(1)));
+        let listener = BlockTemplateLongPollListener::new(long_poll.clone());
+        let id = long_poll.long_poll_id();
+        assert_eq!(id, "00000000000000000000000000000000000000000000000000000000000000010");
+
+        listener.transaction_accepted(&IndexedTransaction::default(), 0);
+        assert_eq!(long_poll.long_poll_id(), "00000000000000000000000000000000000000000000000000000000000000011");
+
+        // memory pool changes are only reported after the interval has passed
+        long_poll.wait(&id, Duration::from_millis(10));
+    }
+
+    #[test]
+    fn long_poll_returns_immediately_when_best_block_has_changed() {
+        let long_poll = BlockTemplateLongPoll::new(H256::from(2));
+        long_poll.wait(&BlockTemplateLongPoll::new(H256::from(1)).long_poll_id(), Duration::from_secs(3600));
+        long_poll.wait("invalid", Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn long_poll_is_released_when_best_block_is_inserted() {
+        let long_poll = Arc::new(BlockTemplateLongPoll::new(H256::from(1)));
+        let listener = BlockTemplateLongPollListener::new(long_poll.clone());
+        let id = long_poll.long_poll_id();
+        let waiter = {
+            let long_poll = long_poll.clone();
+            thread::spawn(move || long_poll.wait(&id, Duration::from_secs(3600)))
+        };
+
+        // waiter either is notified, or sees new best block before starting to wait
+        listener.best_storage_block_inserted(&H256::from(2));
+        waiter.join().unwrap();
+    }
+}
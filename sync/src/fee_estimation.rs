@@ -0,0 +1,242 @@
+use std::cmp;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use chain::IndexedTransaction;
+use miner::{FeeEstimator, FeeEstimateHorizon, MemoryPoolListener, MemoryPoolRemovalReason};
+use primitives::hash::H256;
+use ser::{serialize, deserialize};
+use memory_pool_acceptance::transaction_vsize;
+use types::{StorageRef, FeeEstimatorRef};
+use super::SyncListener;
+
+/// Fee estimates are flushed to the disk every FLUSH_INTERVAL_BLOCKS blocks
+const FLUSH_INTERVAL_BLOCKS: usize = 6;
+
+/// Synchronization listener, which feeds fee estimator with memory pool and new blocks events
+pub struct FeeEstimationListener {
+    /// Storage reference
+    storage: StorageRef,
+    /// Fee estimator reference
+    estimator: FeeEstimatorRef,
+    /// Path to the file where estimates are persisted
+    path: Option<PathBuf>,
+    /// Number of blocks processed since last flush
+    blocks_since_flush: AtomicUsize,
+}
+
+impl FeeEstimationListener {
+    pub fn new(storage: StorageRef, estimator: FeeEstimatorRef, path: Option<PathBuf>) -> Self {
+        FeeEstimationListener {
+            storage: storage,
+            estimator: estimator,
+            path: path,
+            blocks_since_flush: AtomicUsize::new(0),
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(ref path) = self.path {
+            if let Err(err) = write_fee_estimates(&*self.estimator.read(), path) {
+                warn!(target: "sync", "Failed to write fee estimates to {:?}: {}", path, err);
+            }
+        }
+    }
+}
+
+impl SyncListener for FeeEstimationListener {
+    fn synchronization_state_switched(&self, _is_synchronizing: bool) {}
+
+    fn best_storage_block_inserted(&self, block_hash: &H256) {
+        let block_number = match self.storage.block_number(block_hash) {
+            Some(block_number) => block_number,
+            None => return,
+        };
+
+        // several blocks could be connected before notification (reorganization or batch canonization)
+        // => process every block above the last processed one. Transactions, waiting for longer than
+        // the longest estimated target, are not tracked anyway
+        let processed_blocks = {
+            let mut estimator = self.estimator.write();
+            let first_number = cmp::max(
+                estimator.best_seen_height() + 1,
+                block_number.saturating_sub(estimator.max_target(FeeEstimateHorizon::Long) - 1),
+            );
+            let mut processed_blocks = 0;
+            for number in first_number..block_number + 1 {
+                let hash = if number == block_number {
+                    block_hash.clone()
+                } else {
+                    match self.storage.block_hash(number) {
+                        Some(hash) => hash,
+                        None => continue,
+                    }
+                };
+                let transactions = self.storage.block_transaction_hashes(hash.into());
+                estimator.process_block(number, &transactions);
+                processed_blocks += 1;
+            }
+            processed_blocks
+        };
+
+        if self.blocks_since_flush.fetch_add(processed_blocks, Ordering::SeqCst) + processed_blocks >= FLUSH_INTERVAL_BLOCKS {
+            self.blocks_since_flush.store(0, Ordering::SeqCst);
+            self.flush();
+        }
+    }
+
+    fn transaction_accepted(&self, transaction: &IndexedTransaction, fee: u64) {
+        let best_block_number = self.storage.best_block().number;
+        self.estimator.write().process_transaction(
+            &transaction.hash,
+            best_block_number,
+            fee,
+            transaction_vsize(&transaction.raw),
+        );
+    }
+}
+
+/// Memory pool listener, which makes fee estimator forget transactions that have left memory pool unconfirmed
+pub struct FeeEstimationMemoryPoolListener {
+    /// Fee estimator reference
+    estimator: FeeEstimatorRef,
+}
+
+impl FeeEstimationMemoryPoolListener {
+    pub fn new(estimator: FeeEstimatorRef) -> Self {
+        FeeEstimationMemoryPoolListener {
+            estimator: estimator,
+        }
+    }
+}
+
+impl MemoryPoolListener for FeeEstimationMemoryPoolListener {
+    fn transaction_added(&self, _transaction: &IndexedTransaction, _sequence: u64) {}
+
+    fn transaction_removed(&self, hash: &H256, reason: MemoryPoolRemovalReason, _sequence: u64) {
+        // confirmed transactions are processed when block is inserted
+        if reason != MemoryPoolRemovalReason::Block {
+            self.estimator.write().remove_transaction(hash);
+        }
+    }
+}
+
+impl Drop for FeeEstimationListener {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Read fee estimates from the file
+pub fn read_fee_estimates(path: &Path) -> Result<FeeEstimator, String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data).map_err(|e| e.to_string())?;
+    deserialize(&data as &[u8]).map_err(|e| format!("{:?}", e))
+}
+
+/// Write fee estimates to the file
+pub fn write_fee_estimates(estimator: &FeeEstimator, path: &Path) -> Result<(), String> {
+    // write to temporary file first => estimates file is never left half-written
+    let tmp_path = path.with_extension("tmp");
+    {
+        let mut file = File::create(&tmp_path).map_err(|e| e.to_string())?;
+        file.write_all(&serialize(estimator)).map_err(|e| e.to_string())?;
+    }
+    fs::rename(&tmp_path, path).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test_data;
+
+    use std::sync::Arc;
+    use parking_lot::RwLock;
+    use chain::Transaction;
+    use db::BlockChainDatabase;
+    use miner::{FeeEstimator, MemoryPool};
+    use super::super::SyncListener;
+    use super::{FeeEstimationListener, FeeEstimationMemoryPoolListener};
+
+    #[test]
+    fn fee_estimation_listener_tracks_transactions_until_confirmed() {
+        let genesis = test_data::genesis();
+        let transaction: Transaction = test_data::TransactionBuilder::with_output(1)
+            .add_input(&genesis.transactions[0], 0)
+            .into();
+        let block = test_data::block_builder()
+            .header().parent(genesis.hash()).build()
+            .with_transaction(transaction.clone())
+            .build();
+
+        let storage = Arc::new(BlockChainDatabase::init_test_chain(vec![genesis.into()]));
+        let estimator = Arc::new(RwLock::new(FeeEstimator::new()));
+        let listener = FeeEstimationListener::new(storage.clone(), estimator.clone(), None);
+
+        listener.transaction_accepted(&transaction.into(), 10_000);
+        assert_eq!(estimator.read().tracked_transactions_count(), 1);
+
+        let block_hash = block.hash();
+        storage.insert(block.into()).unwrap();
+        storage.canonize(&block_hash).unwrap();
+        listener.best_storage_block_inserted(&block_hash);
+        assert_eq!(estimator.read().tracked_transactions_count(), 0);
+        assert_eq!(estimator.read().best_seen_height(), 1);
+    }
+
+    #[test]
+    fn fee_estimation_listener_processes_every_connected_block() {
+        let genesis = test_data::genesis();
+        let transaction: Transaction = test_data::TransactionBuilder::with_output(1)
+            .add_input(&genesis.transactions[0], 0)
+            .into();
+        let b1 = test_data::block_builder()
+            .header().parent(genesis.hash()).build()
+            .with_transaction(transaction.clone())
+            .build();
+        let b2 = test_data::block_builder()
+            .header().parent(b1.hash()).build()
+            .build();
+
+        let storage = Arc::new(BlockChainDatabase::init_test_chain(vec![genesis.into()]));
+        let estimator = Arc::new(RwLock::new(FeeEstimator::new()));
+        let listener = FeeEstimationListener::new(storage.clone(), estimator.clone(), None);
+        listener.transaction_accepted(&transaction.into(), 10_000);
+
+        // both blocks are canonized before the notification
+        let b2_hash = b2.hash();
+        for block in vec![b1, b2] {
+            let hash = block.hash();
+            storage.insert(block.into()).unwrap();
+            storage.canonize(&hash).unwrap();
+        }
+        listener.best_storage_block_inserted(&b2_hash);
+
+        // transaction is confirmed in the first block
+        assert_eq!(estimator.read().tracked_transactions_count(), 0);
+        assert_eq!(estimator.read().best_seen_height(), 2);
+    }
+
+    #[test]
+    fn fee_estimation_listener_forgets_transactions_removed_from_memory_pool() {
+        let genesis = test_data::genesis();
+        let transaction: Transaction = test_data::TransactionBuilder::with_output(1)
+            .add_input(&genesis.transactions[0], 0)
+            .into();
+
+        let storage = Arc::new(BlockChainDatabase::init_test_chain(vec![genesis.into()]));
+        let estimator = Arc::new(RwLock::new(FeeEstimator::new()));
+        let listener = FeeEstimationListener::new(storage, estimator.clone(), None);
+        let mut memory_pool = MemoryPool::new();
+        memory_pool.install_listener(Box::new(FeeEstimationMemoryPoolListener::new(estimator.clone())));
+
+        let transaction_hash = transaction.hash();
+        memory_pool.insert_verified(transaction.clone().into());
+        listener.transaction_accepted(&transaction.into(), 10_000);
+        assert_eq!(estimator.read().tracked_transactions_count(), 1);
+
+        memory_pool.remove_by_hash(&transaction_hash);
+        assert_eq!(estimator.read().tracked_transactions_count(), 0);
+    }
+}
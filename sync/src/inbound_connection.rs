@@ -207,6 +207,27 @@ impl InboundSyncConnection for InboundConnection {
     fn on_notfound(&self, message: types::NotFound) {
         self.node.on_notfound(self.peer_index, message);
     }
+
+    fn on_get_pkg_txns(&self, message: types::GetPkgTxns) {
+        // if request is empty - just ignore this message
+        if message.hashes.is_empty() {
+            return;
+        }
+
+        self.node.on_get_pkg_txns(self.peer_index, message);
+    }
+
+    fn on_pkg_txns(&self, message: types::PkgTxns) {
+        let transactions: Vec<IndexedTransaction> = message.transactions.into_iter().map(Into::into).collect();
+        for transaction in &transactions {
+            self.peers.hash_known_as(
+                self.peer_index,
+                transaction.hash.clone(),
+                KnownHashType::Transaction,
+            );
+        }
+        self.node.on_pkg_txns(self.peer_index, transactions);
+    }
 }
 
 #[cfg(test)]
@@ -356,6 +377,18 @@ pub mod tests {
                 .entry("notfound".to_owned())
                 .or_insert(0) += 1;
         }
+        fn send_get_pkg_txns(&self, _message: &types::GetPkgTxns) {
+            *self.messages
+                .lock()
+                .entry("getpkgtxns".to_owned())
+                .or_insert(0) += 1;
+        }
+        fn send_pkg_txns(&self, _message: &types::PkgTxns) {
+            *self.messages
+                .lock()
+                .entry("pkgtxns".to_owned())
+                .or_insert(0) += 1;
+        }
         fn ignored(&self, _id: RequestId) {}
         fn close(&self) {}
     }
@@ -20,11 +20,16 @@ extern crate script;
 extern crate serialization as ser;
 extern crate rand;
 extern crate network;
+extern crate zmq;
 
+mod background_validation;
+mod block_template_long_poll;
 mod blocks_writer;
+mod fee_estimation;
 mod inbound_connection;
 mod inbound_connection_factory;
 mod local_node;
+mod memory_pool_acceptance;
 mod synchronization_chain;
 mod synchronization_client;
 mod synchronization_client_core;
@@ -36,15 +41,23 @@ mod synchronization_server;
 mod synchronization_verifier;
 mod types;
 mod utils;
+mod zmq_publisher;
 
 pub use types::LocalNodeRef;
 pub use types::PeersRef;
 pub use local_node::SimpleNode;
+pub use fee_estimation::{FeeEstimationListener, FeeEstimationMemoryPoolListener, read_fee_estimates,
+    write_fee_estimates};
+pub use zmq_publisher::{ZmqConfig, ZmqPublisher, ZmqTopic};
+pub use memory_pool_acceptance::{MemoryPoolAcceptResult, MemoryPoolRejectReason, PackageAcceptResult,
+    PackageRejectReason};
 
 use std::sync::Arc;
+use std::path::PathBuf;
 use parking_lot::RwLock;
 use message::Services;
 use network::{Network, ConsensusParams};
+use chain::IndexedTransaction;
 use primitives::hash::H256;
 use verification::BackwardsCompatibleChainVerifier as ChainVerifier;
 
@@ -59,7 +72,7 @@ pub enum Error {
     Verification(String),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 /// Verification parameters.
 pub struct VerificationParameters {
     /// Blocks verification level.
@@ -75,6 +88,15 @@ pub trait SyncListener: Send + 'static {
     fn synchronization_state_switched(&self, is_synchronizing: bool);
     /// Called when new best storage block is inserted
     fn best_storage_block_inserted(&self, block_hash: &H256);
+    /// Called when best chain is reorganized: blocks are disconnected from the best chain and new blocks are connected.
+    /// Both routes are ordered from oldest to newest
+    fn best_chain_reorganized(&self, _decanonized: &[H256], _canonized: &[H256]) {}
+    /// Called when block is connected to the best chain
+    fn block_connected(&self, _block_hash: &H256) {}
+    /// Called when block is disconnected from the best chain. Blocks are disconnected from newest to oldest
+    fn block_disconnected(&self, _block_hash: &H256) {}
+    /// Called when verified transaction is inserted to the memory pool
+    fn transaction_accepted(&self, _transaction: &IndexedTransaction, _fee: u64) {}
 }
 
 /// Create blocks writer.
@@ -93,22 +115,25 @@ pub fn create_sync_peers() -> PeersRef {
     Arc::new(PeersImpl::default())
 }
 
-/// Creates local sync node for given `db`
+/// Creates local sync node for given `db`.
+/// If `fee_estimates_path` is given, fee estimates are loaded from (and periodically saved to) this file.
 pub fn create_local_sync_node(
     consensus: ConsensusParams,
     db: storage::SharedStore,
     peers: PeersRef,
     verification_params: VerificationParameters,
+    fee_estimates_path: Option<PathBuf>,
 ) -> LocalNodeRef {
-    use miner::MemoryPool;
+    use miner::{MemoryPool, FeeEstimator};
     use synchronization_chain::Chain as SyncChain;
     use synchronization_executor::LocalSynchronizationTaskExecutor as SyncExecutor;
     use local_node::LocalNode as SyncNode;
     use synchronization_server::ServerImpl;
-    use synchronization_client::SynchronizationClient;
+    use synchronization_client::{SynchronizationClient, Client};
     use synchronization_client_core::{SynchronizationClientCore, CoreVerificationSink,
                                       Config as SynchronizationConfig};
     use synchronization_verifier::AsyncVerifier;
+    use block_template_long_poll::{BlockTemplateLongPoll, BlockTemplateLongPollListener};
     use utils::SynchronizationState;
     use types::SynchronizationStateRef;
 
@@ -119,6 +144,20 @@ pub fn create_local_sync_node(
     };
 
     let memory_pool = Arc::new(RwLock::new(MemoryPool::new()));
+    let fee_estimator = match fee_estimates_path {
+        Some(ref path) if path.exists() => match read_fee_estimates(path) {
+            Ok(fee_estimator) => fee_estimator,
+            Err(err) => {
+                warn!(target: "sync", "Failed to read fee estimates from {:?}: {}", path, err);
+                FeeEstimator::new()
+            }
+        },
+        _ => FeeEstimator::new(),
+    };
+    let fee_estimator = Arc::new(RwLock::new(fee_estimator));
+    memory_pool.write().install_listener(Box::new(FeeEstimationMemoryPoolListener::new(
+        fee_estimator.clone(),
+    )));
     let sync_state = SynchronizationStateRef::new(SynchronizationState::with_storage(db.clone()));
     let sync_chain = SyncChain::new(db.clone(), consensus.clone(), memory_pool.clone());
     if sync_chain.is_segwit_active() {
@@ -143,17 +182,29 @@ pub fn create_local_sync_node(
     );
     let verifier_sink = Arc::new(CoreVerificationSink::new(sync_client_core.clone()));
     let verifier = AsyncVerifier::new(
-        chain_verifier,
+        chain_verifier.clone(),
         db.clone(),
         memory_pool.clone(),
         verifier_sink,
         verification_params,
     );
     let sync_client = SynchronizationClient::new(sync_state.clone(), sync_client_core, verifier);
+    sync_client.install_sync_listener(Box::new(FeeEstimationListener::new(
+        db.clone(),
+        fee_estimator.clone(),
+        fee_estimates_path,
+    )));
+    let block_template_long_poll = Arc::new(BlockTemplateLongPoll::new(db.best_block().hash));
+    sync_client.install_sync_listener(Box::new(BlockTemplateLongPollListener::new(
+        block_template_long_poll.clone(),
+    )));
     Arc::new(SyncNode::new(
         consensus,
         db,
         memory_pool,
+        fee_estimator,
+        chain_verifier,
+        block_template_long_poll,
         peers,
         sync_state,
         sync_executor,
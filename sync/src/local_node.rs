@@ -1,21 +1,33 @@
+use std::cmp;
 use std::sync::Arc;
+use std::time::Duration;
 use parking_lot::{Mutex, Condvar};
 use time;
 use futures::{lazy, finished};
-use chain::{Transaction, IndexedTransaction, IndexedBlock};
+use chain::{Transaction, IndexedTransaction, IndexedBlock, IndexedBlockHeader, OutPoint};
 use message::types;
-use miner::BlockAssembler;
+use miner::{BlockAssembler, MemoryPoolListener};
 use network::ConsensusParams;
 use synchronization_client::Client;
+use background_validation::BackgroundValidation;
 use synchronization_executor::{Task as SynchronizationTask, TaskExecutor};
 use synchronization_server::{Server, ServerTask};
-use synchronization_verifier::TransactionVerificationSink;
+use synchronization_verifier::{BlockVerificationSink, TransactionVerificationSink, VerificationTask};
 use primitives::hash::H256;
-use miner::BlockTemplate;
-use verification::median_timestamp_inclusive;
+use miner::{BlockTemplate, FeeEstimate, FeeEstimateMode, FeeEstimateHorizon, EstimationResult,
+            MemoryPoolInformation, MemoryPoolEntryInformation};
+use verification::{median_timestamp_inclusive, block_reward_satoshi, BackwardsCompatibleChainVerifier as ChainVerifier,
+                   Deployments, Verify, VerificationLevel};
 use synchronization_peers::{TransactionAnnouncementType, BlockAnnouncementType};
-use types::{PeerIndex, RequestId, StorageRef, MemoryPoolRef, PeersRef, ExecutorRef, ClientRef,
-            ServerRef, SynchronizationStateRef, SyncListenerRef};
+use block_template_long_poll::BlockTemplateLongPoll;
+use memory_pool_acceptance::{test_accept_transactions, test_accept_package, MemoryPoolAcceptResult,
+                             PackageAcceptResult};
+use types::{PeerIndex, RequestId, StorageRef, MemoryPoolRef, FeeEstimatorRef, PeersRef, ExecutorRef,
+            ClientRef, ServerRef, SynchronizationStateRef, SyncListenerRef};
+use VerificationParameters;
+
+/// Interval of checking memory pool changes when serving `getblocktemplate` long poll request
+const BLOCK_TEMPLATE_LONG_POLL_INTERVAL_S: u64 = 60;
 
 /// Local synchronization node
 pub struct LocalNode<T: TaskExecutor, U: Server, V: Client> {
@@ -25,6 +37,12 @@ pub struct LocalNode<T: TaskExecutor, U: Server, V: Client> {
     pub storage: StorageRef,
     /// Memory pool reference
     memory_pool: MemoryPoolRef,
+    /// Fee estimator reference
+    fee_estimator: FeeEstimatorRef,
+    /// Chain verifier
+    verifier: Arc<ChainVerifier>,
+    /// Block template changes tracker
+    block_template_long_poll: Arc<BlockTemplateLongPoll>,
     /// Synchronization peers
     peers: PeersRef,
     /// Shared synchronization state
@@ -41,15 +59,21 @@ pub struct SimpleNode {
     consensus: ConsensusParams,
     pub storage: StorageRef,
     memory_pool: MemoryPoolRef,
+    deployments: Deployments,
 }
 
 /// Transaction accept verification sink
 struct TransactionAcceptSink {
-    data: Arc<TransactionAcceptSinkData>,
+    data: Arc<AcceptSinkData>,
+}
+
+/// Block accept verification sink
+struct BlockAcceptSink {
+    data: Arc<AcceptSinkData>,
 }
 
 #[derive(Default)]
-struct TransactionAcceptSinkData {
+struct AcceptSinkData {
     result: Mutex<Option<Result<H256, String>>>,
     waiter: Condvar,
 }
@@ -64,6 +88,7 @@ impl SimpleNode {
             consensus: consensus,
             storage: storage,
             memory_pool: memory_pool,
+            deployments: Deployments::new(),
         }
     }
 
@@ -93,15 +118,105 @@ impl SimpleNode {
         block_assembler.create_new_block(
             &self.storage,
             memory_pool,
-            time::get_time().sec as u32,
+            cmp::max(time::get_time().sec as u32, median_timestamp + 1),
             &self.consensus,
+            &self.deployments,
+        )
+    }
+
+    /// Get block template for mining block with given transactions only
+    pub fn get_block_template_with_transactions(&self, transactions: Vec<Transaction>) -> Result<BlockTemplate, String> {
+        let verifier = ChainVerifier::new(self.storage.clone(), self.consensus.clone());
+        block_template_with_transactions(
+            self.get_block_template(),
+            &verifier,
+            &self.storage,
+            &self.memory_pool,
+            transactions,
         )
     }
 
+    /// Get consensus parameters of the network we are working on
+    pub fn consensus(&self) -> &ConsensusParams {
+        &self.consensus
+    }
+
+    /// Get memory pool transaction by hash
+    pub fn memory_pool_transaction(&self, hash: &H256) -> Option<Transaction> {
+        self.memory_pool.read().read_by_hash(hash).cloned()
+    }
+
+    /// Get hash of memory pool transaction and index of its input, which spends given output
+    pub fn memory_pool_spending_input(&self, prevout: &OutPoint) -> Option<(H256, u32)> {
+        self.memory_pool.read().spending_input(prevout)
+    }
+
     /// Verify and then schedule new transaction
     pub fn accept_transaction(&self, _transaction: Transaction) -> Result<H256, String> {
         unreachable!();
     }
+
+    /// Verify and then insert locally mined block.
+    /// Returns BIP22 reject reason if block has not became the best block.
+    pub fn spawn_block(&self, block: IndexedBlock) -> Result<(), String> {
+        if self.storage.contains_block(block.hash().clone().into()) {
+            return Err("duplicate".to_owned());
+        }
+        if block.header.raw.previous_header_hash != self.storage.best_block().hash {
+            return Err(if self.storage.contains_block(block.header.raw.previous_header_hash.clone().into()) {
+                "inconclusive".to_owned()
+            } else {
+                "prev-blk-not-found".to_owned()
+            });
+        }
+
+        let verifier = ChainVerifier::new(self.storage.clone(), self.consensus.clone());
+        try!(verifier.verify(VerificationLevel::Full, &block).map_err(|e| format!("{:?}", e)));
+        try!(self.storage.insert(block.clone()).map_err(|e| format!("{:?}", e)));
+        try!(self.storage.canonize(block.hash()).map_err(|e| format!("{:?}", e)));
+
+        let mut memory_pool = self.memory_pool.write();
+        for transaction in &block.transactions {
+            memory_pool.remove_included(&transaction.hash);
+        }
+        Ok(())
+    }
+
+    /// Check if transactions would be accepted to the memory pool, without inserting them
+    pub fn test_accept_transactions(&self, transactions: Vec<Transaction>) -> Vec<MemoryPoolAcceptResult> {
+        let verifier = ChainVerifier::new(self.storage.clone(), self.consensus.clone());
+        test_accept_transactions(
+            &verifier,
+            &self.storage,
+            &self.memory_pool,
+            time::get_time().sec as u32,
+            transactions,
+        )
+    }
+
+    /// Verify package of transactions and then insert package transactions to the memory pool
+    pub fn submit_package(&self, transactions: Vec<Transaction>) -> Result<PackageAcceptResult, String> {
+        let verifier = ChainVerifier::new(self.storage.clone(), self.consensus.clone());
+        let result = test_accept_package(
+            &verifier,
+            &self.storage,
+            &self.memory_pool,
+            time::get_time().sec as u32,
+            transactions.clone(),
+        );
+        if result.is_allowed() {
+            // there's no transactions verification queue => package transactions, verified
+            // against the memory pool above, are inserted directly
+            let mut memory_pool = self.memory_pool.write();
+            for transaction in transactions {
+                let transaction: IndexedTransaction = transaction.into();
+                if !memory_pool.contains(&transaction.hash) {
+                    memory_pool.insert_verified(transaction);
+                }
+            }
+        }
+        Ok(result)
+    }
 }
 
 impl<T, U, V> LocalNode<T, U, V>
@@ -116,6 +231,9 @@ where
         consensus: ConsensusParams,
         storage: StorageRef,
         memory_pool: MemoryPoolRef,
+        fee_estimator: FeeEstimatorRef,
+        verifier: Arc<ChainVerifier>,
+        block_template_long_poll: Arc<BlockTemplateLongPoll>,
         peers: PeersRef,
         state: SynchronizationStateRef,
         executor: ExecutorRef<T>,
@@ -126,6 +244,9 @@ where
             consensus: consensus,
             storage: storage,
             memory_pool: memory_pool,
+            fee_estimator: fee_estimator,
+            verifier: verifier,
+            block_template_long_poll: block_template_long_poll,
             peers: peers,
             state: state,
             executor: executor,
@@ -196,6 +317,30 @@ where
         self.client.on_notfound(peer_index, message);
     }
 
+    /// When peer is requesting for package transactions
+    pub fn on_get_pkg_txns(&self, peer_index: PeerIndex, message: types::GetPkgTxns) {
+        if self.state.synchronizing() {
+            trace!(target: "sync", "Ignored `getpkgtxns` message from peer#{}", peer_index);
+            return;
+        }
+
+        trace!(target: "sync", "Got `getpkgtxns` message from peer#{}. Transactions: {}", peer_index, message.hashes.len());
+        self.server.execute(
+            ServerTask::GetPkgTxns(peer_index, message),
+        );
+    }
+
+    /// When package transactions are received
+    pub fn on_pkg_txns(&self, peer_index: PeerIndex, transactions: Vec<IndexedTransaction>) {
+        if self.state.synchronizing() {
+            trace!(target: "sync", "Ignored `pkgtxns` message from peer#{}", peer_index);
+            return;
+        }
+
+        trace!(target: "sync", "Got `pkgtxns` message from peer#{}. Transactions: {}", peer_index, transactions.len());
+        self.client.on_package_transactions(peer_index, transactions);
+    }
+
     /// When peer is requesting for items
     pub fn on_getdata(&self, peer_index: PeerIndex, message: types::GetData) {
         if self.state.synchronizing() {
@@ -362,7 +507,7 @@ where
 
     /// Verify and then schedule new transaction
     pub fn accept_transaction(&self, transaction: Transaction) -> Result<H256, String> {
-        let sink_data = Arc::new(TransactionAcceptSinkData::default());
+        let sink_data = Arc::new(AcceptSinkData::default());
         let sink = TransactionAcceptSink::new(sink_data.clone()).boxed();
         {
             if let Err(err) = self.client.accept_transaction(transaction, sink) {
@@ -372,6 +517,48 @@ where
         sink_data.wait()
     }
 
+    /// Mark block and all its descendants invalid and switch to the best valid branch
+    pub fn invalidate_block(&self, hash: &H256) -> Result<(), String> {
+        self.client.invalidate_block(hash)
+    }
+
+    /// Remove invalidity marks from the block branch and switch to the best valid branch
+    pub fn reconsider_block(&self, hash: &H256) -> Result<(), String> {
+        self.client.reconsider_block(hash)
+    }
+
+    /// Check if transactions would be accepted to the memory pool, without inserting them.
+    /// Transactions may depend on previous transactions from the same list.
+    pub fn test_accept_transactions(&self, transactions: Vec<Transaction>) -> Vec<MemoryPoolAcceptResult> {
+        test_accept_transactions(
+            &self.verifier,
+            &self.storage,
+            &self.memory_pool,
+            time::get_time().sec as u32,
+            transactions,
+        )
+    }
+
+    /// Verify package of topologically sorted transactions against package fee rate and then
+    /// schedule package transactions, one by one.
+    pub fn submit_package(&self, transactions: Vec<Transaction>) -> Result<PackageAcceptResult, String> {
+        let result = test_accept_package(
+            &self.verifier,
+            &self.storage,
+            &self.memory_pool,
+            time::get_time().sec as u32,
+            transactions.clone(),
+        );
+        if result.is_allowed() {
+            for transaction in transactions {
+                if !self.memory_pool.read().contains(&transaction.hash()) {
+                    try!(self.accept_transaction(transaction));
+                }
+            }
+        }
+        Ok(result)
+    }
+
     /// Get block template for mining
     pub fn get_block_template(&self) -> BlockTemplate {
         let previous_block_height = self.storage.best_block().number;
@@ -398,23 +585,216 @@ where
         block_assembler.create_new_block(
             &self.storage,
             memory_pool,
-            time::get_time().sec as u32,
+            cmp::max(time::get_time().sec as u32, median_timestamp + 1),
             &self.consensus,
+            self.verifier.deployments(),
+        )
+    }
+
+    /// Get block template for mining block with given transactions only (instead of memory pool transactions).
+    /// Transactions, which are not in the memory pool, are verified as if they were added to the memory pool.
+    pub fn get_block_template_with_transactions(&self, transactions: Vec<Transaction>) -> Result<BlockTemplate, String> {
+        block_template_with_transactions(
+            self.get_block_template(),
+            &self.verifier,
+            &self.storage,
+            &self.memory_pool,
+            transactions,
         )
     }
 
+    /// Estimate fee rate (in satoshis per 1000 bytes), required for transaction to be confirmed within given number of blocks
+    pub fn estimate_smart_fee(&self, confirmation_target: u32, mode: FeeEstimateMode) -> FeeEstimate {
+        self.fee_estimator.read().estimate_smart_fee(confirmation_target, mode)
+    }
+
+    /// Estimate fee rate (in satoshis per 1000 bytes) using statistics of given horizon only
+    pub fn estimate_raw_fee(
+        &self,
+        confirmation_target: u32,
+        success_threshold: f64,
+        horizon: FeeEstimateHorizon,
+    ) -> EstimationResult {
+        self.fee_estimator.read().estimate_raw_fee(
+            confirmation_target,
+            success_threshold,
+            horizon,
+        )
+    }
+
+    /// Max confirmation target that could be estimated using statistics of given horizon
+    pub fn max_fee_estimation_target(&self, horizon: FeeEstimateHorizon) -> u32 {
+        self.fee_estimator.read().max_target(horizon)
+    }
+
+    /// Get information on current memory pool state
+    pub fn memory_pool_information(&self) -> MemoryPoolInformation {
+        self.memory_pool.read().information()
+    }
+
+    /// Get hashes of all memory pool transactions
+    pub fn memory_pool_transactions(&self) -> Vec<H256> {
+        self.memory_pool.read().get_transactions_ids()
+    }
+
+    /// Get information on all memory pool entries
+    pub fn memory_pool_entries(&self) -> Vec<MemoryPoolEntryInformation> {
+        let memory_pool = self.memory_pool.read();
+        memory_pool
+            .get_transactions_ids()
+            .iter()
+            .filter_map(|hash| memory_pool.entry_information(hash))
+            .collect()
+    }
+
+    /// Get consensus parameters of the network we are working on
+    pub fn consensus(&self) -> &ConsensusParams {
+        &self.consensus
+    }
+
+    /// Get memory pool transaction by hash
+    pub fn memory_pool_transaction(&self, hash: &H256) -> Option<Transaction> {
+        self.memory_pool.read().read_by_hash(hash).cloned()
+    }
+
+    /// Get hash of memory pool transaction and index of its input, which spends given output
+    pub fn memory_pool_spending_input(&self, prevout: &OutPoint) -> Option<(H256, u32)> {
+        self.memory_pool.read().spending_input(prevout)
+    }
+
+    /// Get information on single memory pool entry
+    pub fn memory_pool_entry(&self, hash: &H256) -> Option<MemoryPoolEntryInformation> {
+        self.memory_pool.read().entry_information(hash)
+    }
+
+    /// Get information on all in-pool ancestors of given memory pool transaction
+    pub fn memory_pool_ancestors(&self, hash: &H256) -> Option<Vec<MemoryPoolEntryInformation>> {
+        let memory_pool = self.memory_pool.read();
+        memory_pool.get_ancestors_ids(hash).map(|ancestors| {
+            ancestors
+                .iter()
+                .filter_map(|hash| memory_pool.entry_information(hash))
+                .collect()
+        })
+    }
+
+    /// Get information on all in-pool descendants of given memory pool transaction
+    pub fn memory_pool_descendants(&self, hash: &H256) -> Option<Vec<MemoryPoolEntryInformation>> {
+        let memory_pool = self.memory_pool.read();
+        memory_pool.get_descendants_ids(hash).map(|descendants| {
+            descendants
+                .iter()
+                .filter_map(|hash| memory_pool.entry_information(hash))
+                .collect()
+        })
+    }
+
+    /// Change mining priority of memory pool transaction by adding `fee_delta` to its virtual fee.
+    /// Returns false if transaction is not in the memory pool.
+    pub fn prioritise_transaction(&self, hash: &H256, fee_delta: i64) -> bool {
+        let mut memory_pool = self.memory_pool.write();
+        match memory_pool.virtual_fee(hash) {
+            Some(virtual_fee) => {
+                memory_pool.set_virtual_fee(hash, virtual_fee.saturating_add(fee_delta));
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Install synchronization events listener
     pub fn install_sync_listener(&self, listener: SyncListenerRef) {
         self.client.install_sync_listener(listener);
     }
 
-    pub fn spawn_block(&self, block: IndexedBlock) {
-        self.client.spawn_block(block);
+    /// Start background validation of the UTXO set snapshot, this node's storage has been seeded from.
+    /// Blocks from genesis to the snapshot base are verified and inserted into the `background_storage`
+    pub fn start_background_validation(
+        &self,
+        background_storage: StorageRef,
+        verification_params: VerificationParameters,
+    ) -> Result<(), String> {
+        let background_validation = BackgroundValidation::new(
+            self.storage.clone(),
+            background_storage,
+            self.consensus.clone(),
+            verification_params,
+        )?;
+        self.client.start_background_validation(background_validation);
+        Ok(())
+    }
+
+    /// Install memory pool events listener
+    pub fn install_memory_pool_listener(&self, listener: Box<MemoryPoolListener>) {
+        self.memory_pool.write().install_listener(listener);
+    }
+
+    /// Verify and then insert locally mined block.
+    /// Returns BIP22 reject reason if block has not became the best block.
+    pub fn spawn_block(&self, block: IndexedBlock) -> Result<(), String> {
+        let sink_data = Arc::new(AcceptSinkData::default());
+        let sink = BlockAcceptSink::new(sink_data.clone()).boxed();
+        try!(self.client.accept_block(block, sink));
+        sink_data.wait().map(|_| ())
+    }
+
+    /// Verify and then schedule downloading of the block with given header.
+    /// Returns BIP22 reject reason if header is rejected.
+    pub fn submit_header(&self, header: IndexedBlockHeader) -> Result<(), String> {
+        self.client.accept_header(header)
+    }
+
+    /// Get id of the current block template, used for `getblocktemplate` long polling
+    pub fn block_template_long_poll_id(&self) -> String {
+        self.block_template_long_poll.long_poll_id()
+    }
+
+    /// Wait until block template, identified by given long poll id, changes
+    pub fn wait_block_template_change(&self, long_poll_id: &str) {
+        self.block_template_long_poll.wait(
+            long_poll_id,
+            Duration::from_secs(BLOCK_TEMPLATE_LONG_POLL_INTERVAL_S),
+        )
     }
 }
 
+/// Replace transactions of the block template with given transactions, updating coinbase value.
+/// Returns error if some transaction, which is not in the memory pool, is invalid.
+fn block_template_with_transactions(
+    mut block_template: BlockTemplate,
+    verifier: &ChainVerifier,
+    storage: &StorageRef,
+    memory_pool: &MemoryPoolRef,
+    transactions: Vec<Transaction>,
+) -> Result<BlockTemplate, String> {
+    let mut fees = 0u64;
+    let mut new_transactions = Vec::new();
+    {
+        let memory_pool = memory_pool.read();
+        for transaction in &transactions {
+            if let Some(entry) = memory_pool.entry_information(&transaction.hash()) {
+                fees += entry.fee as u64;
+            } else {
+                new_transactions.push(transaction.clone());
+            }
+        }
+    }
+
+    let results = test_accept_transactions(verifier, storage, memory_pool, block_template.time, new_transactions);
+    for result in results {
+        match result.reject_reason {
+            Some(reason) => return Err(format!("transaction {} is rejected: {:?}", result.hash.to_reversed_str(), reason)),
+            None => fees += result.fee.unwrap_or_default(),
+        }
+    }
+
+    block_template.coinbase_value = block_reward_satoshi(block_template.height) + fees;
+    block_template.transactions = transactions.into_iter().map(Into::into).collect();
+    Ok(block_template)
+}
+
 impl TransactionAcceptSink {
-    pub fn new(data: Arc<TransactionAcceptSinkData>) -> Self {
+    pub fn new(data: Arc<AcceptSinkData>) -> Self {
         TransactionAcceptSink { data: data }
     }
 
@@ -423,7 +803,17 @@ impl TransactionAcceptSink {
     }
 }
 
-impl TransactionAcceptSinkData {
+impl BlockAcceptSink {
+    pub fn new(data: Arc<AcceptSinkData>) -> Self {
+        BlockAcceptSink { data: data }
+    }
+
+    pub fn boxed(self) -> Box<Self> {
+        Box::new(self)
+    }
+}
+
+impl AcceptSinkData {
     pub fn wait(&self) -> Result<H256, String> {
         let mut lock = self.result.lock();
         if lock.is_some() {
@@ -447,6 +837,19 @@ impl TransactionVerificationSink for TransactionAcceptSink {
     }
 }
 
+impl BlockVerificationSink for BlockAcceptSink {
+    fn on_block_verification_success(&self, block: IndexedBlock) -> Option<Vec<VerificationTask>> {
+        *self.data.result.lock() = Some(Ok(block.header.hash));
+        self.data.waiter.notify_all();
+        None
+    }
+
+    fn on_block_verification_error(&self, err: &str, _hash: &H256) {
+        *self.data.result.lock() = Some(Err(err.to_owned()));
+        self.data.waiter.notify_all();
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     extern crate test_data;
@@ -461,15 +864,17 @@ pub mod tests {
     use message::types;
     use message::common::{InventoryVector, InventoryType};
     use network::{ConsensusParams, ConsensusFork, Network};
-    use chain::Transaction;
+    use chain::{Block, Transaction, IndexedBlock};
     use db::BlockChainDatabase;
-    use miner::MemoryPool;
-    use super::LocalNode;
+    use miner::{MemoryPool, FeeEstimator};
+    use super::{LocalNode, SimpleNode};
     use synchronization_server::ServerTask;
     use synchronization_server::tests::DummyServer;
     use synchronization_verifier::tests::DummyVerifier;
     use primitives::bytes::Bytes;
-    use verification::BackwardsCompatibleChainVerifier as ChainVerifier;
+    use primitives::hash::H256;
+    use verification::{block_reward_satoshi, BackwardsCompatibleChainVerifier as ChainVerifier};
+    use block_template_long_poll::BlockTemplateLongPoll;
     use std::iter::repeat;
     use synchronization_peers::PeersImpl;
     use utils::SynchronizationState;
@@ -525,7 +930,7 @@ pub mod tests {
             sync_peers.clone(),
             executor.clone(),
             chain,
-            chain_verifier,
+            chain_verifier.clone(),
         );
         let mut verifier = match verifier {
             Some(verifier) => verifier,
@@ -537,6 +942,9 @@ pub mod tests {
             ConsensusParams::new(Network::Mainnet, ConsensusFork::BitcoinCore),
             storage,
             memory_pool,
+            Arc::new(RwLock::new(FeeEstimator::new())),
+            chain_verifier,
+            Arc::new(BlockTemplateLongPoll::new(test_data::genesis().hash())),
             sync_peers,
             sync_state,
             executor.clone(),
@@ -594,6 +1002,34 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn local_node_reports_memory_pool_entries() {
+        let (_, _, local_node) = create_local_node(None);
+
+        let genesis = test_data::genesis();
+        let transaction: Transaction = test_data::TransactionBuilder::with_output(1)
+            .add_input(&genesis.transactions[0], 0)
+            .into();
+        let transaction_hash = transaction.hash();
+        assert_eq!(local_node.accept_transaction(transaction), Ok(transaction_hash.clone()));
+
+        assert_eq!(local_node.memory_pool_transactions(), vec![transaction_hash.clone()]);
+        let entry = local_node.memory_pool_entry(&transaction_hash).unwrap();
+        assert_eq!(entry.fee, 4_999_999_999);
+        assert_eq!(entry.modified_fee, 4_999_999_999);
+        assert_eq!(entry.ancestor_count, 1);
+        assert_eq!(entry.descendant_count, 1);
+
+        assert!(local_node.prioritise_transaction(&transaction_hash, 1_000));
+        assert!(local_node.prioritise_transaction(&transaction_hash, 1_000));
+        let entry = local_node.memory_pool_entry(&transaction_hash).unwrap();
+        assert_eq!(entry.fee, 4_999_999_999);
+        assert_eq!(entry.modified_fee, 5_000_001_999);
+
+        assert!(!local_node.prioritise_transaction(&H256::default(), 1_000));
+        assert_eq!(local_node.memory_pool_ancestors(&H256::default()), None);
+    }
+
     #[test]
     fn local_node_discards_local_transaction() {
         let genesis = test_data::genesis();
@@ -617,4 +1053,102 @@ pub mod tests {
 
         assert_eq!(executor.take_tasks(), vec![]);
     }
+
+    #[test]
+    fn local_node_spawns_block() {
+        let (_, _, local_node) = create_local_node(None);
+
+        let block: IndexedBlock = test_data::block_h1().into();
+        assert_eq!(local_node.spawn_block(block.clone()), Ok(()));
+        assert_eq!(local_node.storage.best_block().hash, *block.hash());
+        assert_eq!(local_node.spawn_block(block), Err("duplicate".to_owned()));
+        assert_eq!(
+            local_node.spawn_block(test_data::block_h3().into()),
+            Err("prev-blk-not-found".to_owned())
+        );
+    }
+
+    #[test]
+    fn local_node_rejects_invalid_block() {
+        let block: IndexedBlock = test_data::block_h1().into();
+
+        // simulate block verification fail
+        let mut verifier = DummyVerifier::default();
+        verifier.error_when_verifying(block.hash().clone(), "simulated");
+
+        let (_, _, local_node) = create_local_node(Some(verifier));
+        assert_eq!(local_node.spawn_block(block.clone()), Err("simulated".to_owned()));
+        assert_eq!(local_node.spawn_block(block), Err("duplicate-invalid".to_owned()));
+    }
+
+    #[test]
+    fn local_node_accepts_block_header() {
+        let (_, _, local_node) = create_local_node(None);
+
+        let header1 = test_data::block_h1().block_header;
+        assert_eq!(local_node.submit_header(header1.clone().into()), Ok(()));
+        assert_eq!(local_node.submit_header(header1.into()), Ok(()));
+        assert_eq!(
+            local_node.submit_header(test_data::block_h3().block_header.into()),
+            Err("prev-blk-not-found".to_owned())
+        );
+    }
+
+    #[test]
+    fn local_node_builds_block_template_with_transactions() {
+        let (_, _, local_node) = create_local_node(None);
+
+        let block_template = local_node.get_block_template_with_transactions(vec![]).unwrap();
+        assert_eq!(block_template.height, 1);
+        assert_eq!(block_template.coinbase_value, block_reward_satoshi(1));
+        assert!(block_template.transactions.is_empty());
+
+        // invalid transaction is rejected
+        let transaction: Transaction = test_data::TransactionBuilder::with_output(10)
+            .add_input(&test_data::genesis().transactions[0], 0)
+            .into();
+        assert!(local_node.get_block_template_with_transactions(vec![transaction]).is_err());
+    }
+
+    #[test]
+    fn simple_node_submits_package() {
+        let genesis = test_data::genesis();
+        let b1: Block = test_data::block_builder()
+            .header().parent(genesis.hash()).build()
+            .transaction()
+                .coinbase()
+                .output().value(1_000).script_pubkey("51").build()
+                .build()
+            .build();
+        let spend = |hash: H256, value: u64| -> Transaction {
+            let block: Block = test_data::block_builder()
+                .header().build()
+                .transaction()
+                    .input().hash(hash).index(0).build()
+                    .output().value(value).script_pubkey("51").build()
+                    .build()
+                .build();
+            block.transactions[0].clone()
+        };
+        let parent = spend(b1.transactions[0].hash(), 1_000);
+        let child = spend(parent.hash(), 500);
+
+        let storage = Arc::new(BlockChainDatabase::init_test_chain(vec![genesis.into(), b1.into()]));
+        let memory_pool = Arc::new(RwLock::new(MemoryPool::new()));
+        let node = SimpleNode::new(
+            ConsensusParams::new(Network::Unitest, ConsensusFork::BitcoinCore),
+            storage,
+            memory_pool.clone(),
+        );
+
+        let result = node.submit_package(vec![parent.clone(), child.clone()]).unwrap();
+        assert!(result.is_allowed());
+        assert!(memory_pool.read().contains(&parent.hash()));
+        assert!(memory_pool.read().contains(&child.hash()));
+
+        // resubmitted package is accepted without duplicating transactions
+        let result = node.submit_package(vec![parent, child]).unwrap();
+        assert!(result.is_allowed());
+        assert_eq!(memory_pool.read().information().transactions_count, 2);
+    }
 }
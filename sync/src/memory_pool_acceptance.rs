@@ -0,0 +1,475 @@
+use std::collections::{HashMap, HashSet};
+use chain::{Transaction, IndexedTransaction, TransactionOutput, OutPoint};
+use miner::HashedOutPoint;
+use primitives::hash::H256;
+use ser::{Serializable, SERIALIZE_TRANSACTION_WITNESS};
+use storage::TransactionOutputProvider;
+use verification::{BackwardsCompatibleChainVerifier as ChainVerifier, TransactionError};
+use types::{StorageRef, MemoryPoolRef};
+use utils::MemoryPoolTransactionOutputProvider;
+
+/// Maximal number of transactions in the package
+pub const MAX_PACKAGE_TRANSACTIONS: usize = 25;
+/// Maximal virtual size of all package transactions
+pub const MAX_PACKAGE_VSIZE: usize = 101_000;
+/// Minimal fee rate (in satoshis per 1000 virtual bytes) of the package
+pub const DEFAULT_MIN_RELAY_FEE_RATE: u64 = 1_000;
+
+/// Reason of transaction rejection by the memory pool
+#[derive(Debug, PartialEq)]
+pub enum MemoryPoolRejectReason {
+    /// Transaction is already in the memory pool
+    AlreadyInMemoryPool,
+    /// Transaction is included in the tested package more than once
+    DuplicateInPackage,
+    /// Transaction verification has failed
+    Verification(TransactionError),
+}
+
+/// Result of memory pool acceptance test of single transaction
+#[derive(Debug, PartialEq)]
+pub struct MemoryPoolAcceptResult {
+    /// Transaction hash
+    pub hash: H256,
+    /// Virtual transaction size, as defined in BIP141
+    pub vsize: usize,
+    /// Transaction fee. Only known if transaction has passed the test
+    pub fee: Option<u64>,
+    /// Rejection reason. None if transaction would be accepted to the memory pool
+    pub reject_reason: Option<MemoryPoolRejectReason>,
+}
+
+impl MemoryPoolAcceptResult {
+    /// Would this transaction be accepted to the memory pool?
+    pub fn is_allowed(&self) -> bool {
+        self.reject_reason.is_none()
+    }
+}
+
+/// Reason of package rejection by the memory pool
+#[derive(Debug, PartialEq)]
+pub enum PackageRejectReason {
+    /// Package contains too many transactions
+    TooManyTransactions,
+    /// Package transactions are too large
+    TooLarge,
+    /// Package transactions are not topologically sorted
+    NotSorted,
+    /// Some output is spent by several package transactions
+    ConflictingInputs,
+    /// Some of package transactions has been rejected
+    TransactionRejected,
+    /// Package fee rate is below minimal relay fee rate
+    InsufficientFee,
+}
+
+/// Result of memory pool acceptance test of the package
+#[derive(Debug, PartialEq)]
+pub struct PackageAcceptResult {
+    /// Results of individual transactions tests. Empty if package has been rejected before checking transactions
+    pub transactions: Vec<MemoryPoolAcceptResult>,
+    /// Fee rate (in satoshis per 1000 virtual bytes) of package transactions, which are not yet in the memory pool
+    pub fee_rate: Option<u64>,
+    /// Rejection reason. None if package would be accepted to the memory pool
+    pub reject_reason: Option<PackageRejectReason>,
+}
+
+impl PackageAcceptResult {
+    /// Would this package be accepted to the memory pool?
+    pub fn is_allowed(&self) -> bool {
+        self.reject_reason.is_none()
+    }
+}
+
+/// Package transactions, which have already passed the test
+#[derive(Default)]
+struct PackageOutputs {
+    /// Transactions by hash
+    transactions: HashMap<H256, Transaction>,
+    /// Outputs, spent by these transactions
+    spent: HashSet<HashedOutPoint>,
+}
+
+/// Transaction output provider, which looks into package transactions, storage && memory pool.
+struct PackageTransactionOutputProvider<'a> {
+    /// Package transactions
+    package: &'a PackageOutputs,
+    /// Storage && memory pool provider
+    base: MemoryPoolTransactionOutputProvider,
+}
+
+impl<'a> TransactionOutputProvider for PackageTransactionOutputProvider<'a> {
+    fn transaction_output(
+        &self,
+        prevout: &OutPoint,
+        transaction_index: usize,
+    ) -> Option<TransactionOutput> {
+        if let Some(transaction) = self.package.transactions.get(&prevout.hash) {
+            return transaction.outputs.get(prevout.index as usize).cloned();
+        }
+
+        self.base.transaction_output(prevout, transaction_index)
+    }
+
+    fn is_spent(&self, prevout: &OutPoint) -> bool {
+        self.package.spent.contains(&prevout.clone().into()) || self.base.is_spent(prevout)
+    }
+}
+
+/// Run memory pool verification of given transactions, without inserting them to the memory pool.
+/// Transactions may depend on previous transactions from the same list.
+pub fn test_accept_transactions(
+    verifier: &ChainVerifier,
+    storage: &StorageRef,
+    memory_pool: &MemoryPoolRef,
+    time: u32,
+    transactions: Vec<Transaction>,
+) -> Vec<MemoryPoolAcceptResult> {
+    let height = storage.best_block().number + 1;
+    let mut package = PackageOutputs::default();
+    transactions
+        .into_iter()
+        .map(|transaction| {
+            if memory_pool.read().contains(&transaction.hash()) {
+                return MemoryPoolAcceptResult {
+                    hash: transaction.hash(),
+                    vsize: transaction_vsize(&transaction),
+                    fee: None,
+                    reject_reason: Some(MemoryPoolRejectReason::AlreadyInMemoryPool),
+                };
+            }
+
+            test_accept_transaction(verifier, storage, memory_pool, height, time, &mut package, transaction)
+        })
+        .collect()
+}
+
+/// Run memory pool verification of the package: topologically sorted list of transactions,
+/// which are validated together. Transactions, which are already in the memory pool, are skipped.
+/// Every package transaction must be valid, but only the package fee rate is checked against
+/// `DEFAULT_MIN_RELAY_FEE_RATE` => low-fee parent could be paid for by its child.
+pub fn test_accept_package(
+    verifier: &ChainVerifier,
+    storage: &StorageRef,
+    memory_pool: &MemoryPoolRef,
+    time: u32,
+    transactions: Vec<Transaction>,
+) -> PackageAcceptResult {
+    let reject = |reason| PackageAcceptResult {
+        transactions: Vec::new(),
+        fee_rate: None,
+        reject_reason: Some(reason),
+    };
+
+    if transactions.len() > MAX_PACKAGE_TRANSACTIONS {
+        return reject(PackageRejectReason::TooManyTransactions);
+    }
+    let total_vsize: usize = transactions.iter().map(transaction_vsize).sum();
+    if total_vsize > MAX_PACKAGE_VSIZE {
+        return reject(PackageRejectReason::TooLarge);
+    }
+
+    // every transaction can only spend outputs of previous package transactions
+    // && no output can be spent twice within the package
+    let positions: HashMap<H256, usize> = transactions.iter()
+        .enumerate()
+        .map(|(index, transaction)| (transaction.hash(), index))
+        .collect();
+    let mut spent: HashSet<HashedOutPoint> = HashSet::new();
+    for (index, transaction) in transactions.iter().enumerate() {
+        for input in &transaction.inputs {
+            if positions.get(&input.previous_output.hash).map(|parent_index| *parent_index >= index).unwrap_or(false) {
+                return reject(PackageRejectReason::NotSorted);
+            }
+            if !spent.insert(input.previous_output.clone().into()) {
+                return reject(PackageRejectReason::ConflictingInputs);
+            }
+        }
+    }
+
+    let height = storage.best_block().number + 1;
+    let mut package = PackageOutputs::default();
+    let mut package_fee = 0u64;
+    let mut package_vsize = 0usize;
+    let mut is_rejected = false;
+    let mut results = Vec::with_capacity(transactions.len());
+    for transaction in transactions {
+        // transaction is already in the memory pool => its outputs are provided by the memory pool itself
+        if memory_pool.read().contains(&transaction.hash()) {
+            results.push(MemoryPoolAcceptResult {
+                hash: transaction.hash(),
+                vsize: transaction_vsize(&transaction),
+                fee: None,
+                reject_reason: None,
+            });
+            continue;
+        }
+
+        let result = test_accept_transaction(verifier, storage, memory_pool, height, time, &mut package, transaction);
+        match result.fee {
+            Some(fee) => {
+                package_fee += fee;
+                package_vsize += result.vsize;
+            },
+            None => is_rejected = true,
+        }
+        results.push(result);
+    }
+
+    let fee_rate = if package_vsize != 0 { Some(package_fee * 1000 / package_vsize as u64) } else { None };
+    let reject_reason = if is_rejected {
+        Some(PackageRejectReason::TransactionRejected)
+    } else if fee_rate.map(|fee_rate| fee_rate < DEFAULT_MIN_RELAY_FEE_RATE).unwrap_or(false) {
+        Some(PackageRejectReason::InsufficientFee)
+    } else {
+        None
+    };
+
+    PackageAcceptResult {
+        transactions: results,
+        fee_rate: fee_rate,
+        reject_reason: reject_reason,
+    }
+}
+
+/// Sort package transactions so that parents are going before their children.
+/// Returns None if package transactions can not be sorted (i.e. there's a duplicate transaction).
+pub fn sort_package_transactions(transactions: Vec<IndexedTransaction>) -> Option<Vec<IndexedTransaction>> {
+    let hashes: HashSet<H256> = transactions.iter().map(|transaction| transaction.hash.clone()).collect();
+    if hashes.len() != transactions.len() {
+        return None;
+    }
+
+    let mut sorted = Vec::with_capacity(transactions.len());
+    let mut sorted_hashes = HashSet::new();
+    let mut unsorted = transactions;
+    while !unsorted.is_empty() {
+        let (ready, not_ready): (Vec<_>, Vec<_>) = unsorted.into_iter()
+            .partition(|transaction| transaction.raw.inputs.iter()
+                .map(|input| &input.previous_output.hash)
+                .all(|parent| !hashes.contains(parent) || sorted_hashes.contains(parent)));
+        if ready.is_empty() {
+            return None;
+        }
+
+        sorted_hashes.extend(ready.iter().map(|transaction| transaction.hash.clone()));
+        sorted.extend(ready);
+        unsorted = not_ready;
+    }
+
+    Some(sorted)
+}
+
+/// Run memory pool verification of single transaction, using given package outputs.
+/// If transaction is valid, it is added to the package.
+fn test_accept_transaction(
+    verifier: &ChainVerifier,
+    storage: &StorageRef,
+    memory_pool: &MemoryPoolRef,
+    height: u32,
+    time: u32,
+    package: &mut PackageOutputs,
+    transaction: Transaction,
+) -> MemoryPoolAcceptResult {
+    let hash = transaction.hash();
+    let vsize = transaction_vsize(&transaction);
+    let rejected_hash = hash.clone();
+    let reject = move |reason| MemoryPoolAcceptResult {
+        hash: rejected_hash.clone(),
+        vsize: vsize,
+        fee: None,
+        reject_reason: Some(reason),
+    };
+
+    if package.transactions.contains_key(&hash) {
+        return reject(MemoryPoolRejectReason::DuplicateInPackage);
+    }
+
+    let base = match MemoryPoolTransactionOutputProvider::for_transaction(
+        storage.clone(),
+        memory_pool,
+        &transaction,
+    ) {
+        Ok(base) => base,
+        Err(err) => return reject(MemoryPoolRejectReason::Verification(err)),
+    };
+    let fee = {
+        let output_provider = PackageTransactionOutputProvider {
+            package: &*package,
+            base: base,
+        };
+        if let Err(err) = verifier.verify_mempool_transaction(
+            storage.as_block_header_provider(),
+            &output_provider,
+            height,
+            time,
+            &transaction,
+        ) {
+            return reject(MemoryPoolRejectReason::Verification(err));
+        }
+
+        let inputs_sum: u64 = transaction.inputs.iter()
+            .filter_map(|input| output_provider.transaction_output(&input.previous_output, usize::max_value()))
+            .map(|output| output.value)
+            .sum();
+        let outputs_sum: u64 = transaction.outputs.iter().map(|output| output.value).sum();
+        inputs_sum.saturating_sub(outputs_sum)
+    };
+
+    // remember transaction => its descendants in the same package could be verified
+    package.spent.extend(transaction.inputs.iter().map(|input| input.previous_output.clone().into()));
+    package.transactions.insert(hash.clone(), transaction);
+
+    MemoryPoolAcceptResult {
+        hash: hash,
+        vsize: vsize,
+        fee: Some(fee),
+        reject_reason: None,
+    }
+}
+
+/// Compute virtual transaction size (weight / 4, rounded up)
+pub fn transaction_vsize(transaction: &Transaction) -> usize {
+    let base_size = transaction.serialized_size();
+    let total_size = transaction.serialized_size_with_flags(SERIALIZE_TRANSACTION_WITNESS);
+    (base_size * 3 + total_size + 3) / 4
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test_data;
+
+    use std::sync::Arc;
+    use parking_lot::RwLock;
+    use chain::{Block, Transaction};
+    use db::BlockChainDatabase;
+    use miner::MemoryPool;
+    use network::{ConsensusParams, ConsensusFork, Network};
+    use primitives::hash::H256;
+    use ser::Serializable;
+    use verification::{BackwardsCompatibleChainVerifier as ChainVerifier, TransactionError};
+    use types::{StorageRef, MemoryPoolRef};
+    use super::{test_accept_transactions, test_accept_package, sort_package_transactions,
+        MemoryPoolRejectReason, PackageRejectReason};
+
+    fn spend(hash: H256, value: u64) -> Transaction {
+        let block: Block = test_data::block_builder()
+            .header().build()
+            .transaction()
+                .input().hash(hash).index(0).build()
+                .output().value(value).script_pubkey("51").build()
+                .build()
+            .build();
+        block.transactions[0].clone()
+    }
+
+    fn prepare() -> (ChainVerifier, StorageRef, MemoryPoolRef, Transaction) {
+        let genesis = test_data::genesis();
+        let b1: Block = test_data::block_builder()
+            .header().parent(genesis.hash()).build()
+            .transaction()
+                .coinbase()
+                .output().value(1_000).script_pubkey("51").build()
+                .build()
+            .build();
+        let coinbase = b1.transactions[0].clone();
+        let storage: StorageRef = Arc::new(BlockChainDatabase::init_test_chain(vec![genesis.into(), b1.into()]));
+        let memory_pool: MemoryPoolRef = Arc::new(RwLock::new(MemoryPool::new()));
+        let verifier = ChainVerifier::new(storage.clone(), ConsensusParams::new(Network::Unitest, ConsensusFork::BitcoinCore));
+        (verifier, storage, memory_pool, coinbase)
+    }
+
+    #[test]
+    fn test_accept_single_transaction() {
+        let (verifier, storage, memory_pool, coinbase) = prepare();
+        let transaction = spend(coinbase.hash(), 900);
+
+        let results = test_accept_transactions(&verifier, &storage, &memory_pool, 0, vec![transaction.clone()]);
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_allowed());
+        assert_eq!(results[0].hash, transaction.hash());
+        assert_eq!(results[0].fee, Some(100));
+        assert_eq!(results[0].vsize, transaction.serialized_size());
+
+        // transaction is not inserted to the memory pool
+        assert!(!memory_pool.read().contains(&transaction.hash()));
+    }
+
+    #[test]
+    fn test_accept_dependent_package() {
+        let (verifier, storage, memory_pool, coinbase) = prepare();
+        let parent = spend(coinbase.hash(), 900);
+        let child = spend(parent.hash(), 850);
+        let double_spend = spend(coinbase.hash(), 800);
+
+        let results = test_accept_transactions(&verifier, &storage, &memory_pool, 0,
+            vec![parent.clone(), child.clone(), double_spend.clone()]);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].fee, Some(100));
+        assert_eq!(results[1].fee, Some(50));
+        assert!(results[0].is_allowed());
+        assert!(results[1].is_allowed());
+        assert!(!results[2].is_allowed());
+
+        // child alone is missing inputs
+        let results = test_accept_transactions(&verifier, &storage, &memory_pool, 0, vec![child]);
+        assert!(!results[0].is_allowed());
+        assert!(results[0].fee.is_none());
+    }
+
+    #[test]
+    fn test_accept_rejects_overspend_and_known_transactions() {
+        let (verifier, storage, memory_pool, coinbase) = prepare();
+        let overspend = spend(coinbase.hash(), 1_001);
+        let results = test_accept_transactions(&verifier, &storage, &memory_pool, 0, vec![overspend]);
+        assert_eq!(results[0].reject_reason, Some(MemoryPoolRejectReason::Verification(TransactionError::Overspend)));
+
+        let transaction = spend(coinbase.hash(), 900);
+        memory_pool.write().insert_verified(transaction.clone().into());
+        let results = test_accept_transactions(&verifier, &storage, &memory_pool, 0, vec![transaction]);
+        assert_eq!(results[0].reject_reason, Some(MemoryPoolRejectReason::AlreadyInMemoryPool));
+    }
+
+    #[test]
+    fn test_accept_package_with_low_fee_parent() {
+        let (verifier, storage, memory_pool, coinbase) = prepare();
+        let parent = spend(coinbase.hash(), 1_000);
+        let child = spend(parent.hash(), 500);
+
+        let result = test_accept_package(&verifier, &storage, &memory_pool, 0, vec![parent.clone(), child.clone()]);
+        assert!(result.is_allowed());
+        assert_eq!(result.transactions.len(), 2);
+        assert_eq!(result.transactions[0].fee, Some(0));
+        assert_eq!(result.transactions[1].fee, Some(500));
+        let package_vsize = (parent.serialized_size() + child.serialized_size()) as u64;
+        assert_eq!(result.fee_rate, Some(500 * 1000 / package_vsize));
+    }
+
+    #[test]
+    fn test_accept_package_rejects_low_package_fee_rate() {
+        let (verifier, storage, memory_pool, coinbase) = prepare();
+        let parent = spend(coinbase.hash(), 1_000);
+        let child = spend(parent.hash(), 990);
+
+        let result = test_accept_package(&verifier, &storage, &memory_pool, 0, vec![parent, child]);
+        assert_eq!(result.reject_reason, Some(PackageRejectReason::InsufficientFee));
+    }
+
+    #[test]
+    fn test_accept_package_rejects_unsorted_and_conflicting_packages() {
+        let (verifier, storage, memory_pool, coinbase) = prepare();
+        let parent = spend(coinbase.hash(), 1_000);
+        let child = spend(parent.hash(), 500);
+        let double_spend = spend(coinbase.hash(), 500);
+
+        let result = test_accept_package(&verifier, &storage, &memory_pool, 0, vec![child.clone(), parent.clone()]);
+        assert_eq!(result.reject_reason, Some(PackageRejectReason::NotSorted));
+
+        let result = test_accept_package(&verifier, &storage, &memory_pool, 0, vec![parent.clone(), double_spend]);
+        assert_eq!(result.reject_reason, Some(PackageRejectReason::ConflictingInputs));
+
+        let sorted = sort_package_transactions(vec![child.clone().into(), parent.clone().into()]).unwrap();
+        assert_eq!(sorted[0].hash, parent.hash());
+        assert_eq!(sorted[1].hash, child.hash());
+    }
+}
@@ -25,6 +25,8 @@ const NUMBER_OF_QUEUES: usize = 3;
 pub struct BlockInsertionResult {
     /// Hashes of blocks, which were canonized during this insertion procedure. Order matters
     pub canonized_blocks_hashes: Vec<H256>,
+    /// Hashes of blocks, which were decanonized during this insertion procedure. Order matters
+    pub decanonized_blocks_hashes: Vec<H256>,
     /// Transaction to 'reverify'. Order matters
     pub transactions_to_reverify: Vec<IndexedTransaction>,
 }
@@ -39,6 +41,13 @@ impl fmt::Debug for BlockInsertionResult {
                     .map(H256::reversed)
                     .collect::<Vec<_>>(),
             )
+            .field(
+                "decanonized_blocks_hashes",
+                &self.decanonized_blocks_hashes
+                    .iter()
+                    .map(H256::reversed)
+                    .collect::<Vec<_>>(),
+            )
             .field("transactions_to_reverify", &self.transactions_to_reverify)
             .finish()
     }
@@ -49,6 +58,7 @@ impl BlockInsertionResult {
     pub fn with_canonized_blocks(canonized_blocks_hashes: Vec<H256>) -> Self {
         BlockInsertionResult {
             canonized_blocks_hashes: canonized_blocks_hashes,
+            decanonized_blocks_hashes: Vec::new(),
             transactions_to_reverify: Vec::new(),
         }
     }
@@ -395,6 +405,115 @@ impl Chain {
         self.dead_end_blocks.insert(hash.clone());
     }
 
+    /// Remember tip of the branch, which headers are known, but blocks are not yet stored
+    pub fn insert_headers_tip(&self, base: &H256, headers: &[IndexedBlockHeader]) {
+        if let Some(tip) = headers.last() {
+            if let Err(err) = self.storage.insert_chain_tip(
+                &tip.hash,
+                base,
+                headers.len() as BlockHeight,
+                storage::ChainTipStatus::HeadersOnly,
+            )
+            {
+                warn!(target: "sync", "Failed to remember headers-only chain tip {}: {}", tip.hash.to_reversed_str(), err);
+            }
+        }
+    }
+
+    /// Remember tip of the branch, which block has failed verification
+    pub fn insert_invalid_tip(&self, hash: &H256) {
+        if let Some(header) = self.block_header_by_hash(hash) {
+            if let Err(err) = self.storage.insert_chain_tip(
+                hash,
+                &header.raw.previous_header_hash,
+                1,
+                storage::ChainTipStatus::Invalid,
+            )
+            {
+                warn!(target: "sync", "Failed to remember invalid chain tip {}: {}", hash.to_reversed_str(), err);
+            }
+        }
+    }
+
+    /// Mark block and all its descendants invalid and switch to the best valid branch
+    pub fn invalidate_block(&mut self, hash: &H256) -> Result<BlockInsertionResult, storage::Error> {
+        let origin = self.storage.invalidate_block(hash)?;
+        Ok(self.on_best_branch_switched(origin))
+    }
+
+    /// Remove invalidity marks from the block branch and switch to the best valid branch
+    pub fn reconsider_block(&mut self, hash: &H256) -> Result<BlockInsertionResult, storage::Error> {
+        let origin = self.storage.reconsider_block(hash)?;
+        Ok(self.on_best_branch_switched(origin))
+    }
+
+    /// Update in-memory state after storage has been switched to the other branch
+    fn on_best_branch_switched(&mut self, origin: storage::SideChainOrigin) -> BlockInsertionResult {
+        if origin.canonized_route.is_empty() && origin.decanonized_route.is_empty() {
+            return BlockInsertionResult::default();
+        }
+
+        // remember new best block hash
+        self.best_storage_block = self.storage.best_block();
+        self.is_segwit_active = self.deployments.segwit(
+            self.best_storage_block.number,
+            self.storage.as_block_header_provider(),
+            &self.consensus,
+        );
+
+        // in-memory blocks may belong to the old branch => forget them all
+        self.hash_chain = HashQueueChain::with_number_of_queues(NUMBER_OF_QUEUES);
+        self.headers_chain = BestHeadersChain::new(self.best_storage_block.hash.clone());
+
+        // transactions from the new branch blocks were accepted
+        let mut memory_pool = self.memory_pool.write();
+        let new_main_blocks_transactions_hashes = origin
+            .canonized_route
+            .iter()
+            .flat_map(|block_hash| {
+                self.storage.block_transaction_hashes(block_hash.clone().into())
+            })
+            .collect::<Vec<_>>();
+        for transaction_accepted in new_main_blocks_transactions_hashes {
+            memory_pool.remove_included(&transaction_accepted);
+            self.verifying_transactions.remove(&transaction_accepted);
+        }
+
+        // reverify all transactions from old branch blocks, memory pool and verification queue
+        let old_main_blocks_transactions = origin
+            .decanonized_route
+            .iter()
+            .flat_map(|block_hash| {
+                self.storage.indexed_block_transactions(block_hash.clone().into())
+            })
+            .collect::<Vec<_>>();
+        let memory_pool_transactions_count = memory_pool.information().transactions_count;
+        let memory_pool_transactions: Vec<IndexedTransaction> = memory_pool
+            .remove_n_with_strategy(
+                memory_pool_transactions_count,
+                MemoryPoolOrderingStrategy::ByTimestamp,
+            )
+            .into_iter()
+            .map(|t| t.into())
+            .collect();
+        let verifying_transactions: Vec<IndexedTransaction> = self.verifying_transactions
+            .iter()
+            .map(|(_, t)| t.clone())
+            .collect();
+        self.verifying_transactions.clear();
+
+        BlockInsertionResult {
+            canonized_blocks_hashes: origin.canonized_route,
+            decanonized_blocks_hashes: origin.decanonized_route,
+            // order matters: db transactions, then ordered mempool transactions, then ordered verifying transactions
+            transactions_to_reverify: old_main_blocks_transactions
+                .into_iter()
+                .chain(memory_pool_transactions.into_iter())
+                .chain(verifying_transactions.into_iter())
+                .collect(),
+        }
+    }
+
     /// Insert new best block to storage
     pub fn insert_best_block(
         &mut self,
@@ -439,7 +558,7 @@ impl Chain {
                 // + also remove transactions which spent outputs which have been spent by transactions from the block
                 let mut memory_pool = self.memory_pool.write();
                 for tx in &block.transactions {
-                    memory_pool.remove_by_hash(&tx.hash);
+                    memory_pool.remove_included(&tx.hash);
                     self.verifying_transactions.remove(&tx.hash);
                     for tx_input in &tx.raw.inputs {
                         memory_pool.remove_by_prevout(&tx_input.previous_output);
@@ -449,6 +568,7 @@ impl Chain {
 
                 Ok(BlockInsertionResult {
                     canonized_blocks_hashes: vec![block.hash().clone()],
+                    decanonized_blocks_hashes: Vec::new(),
                     transactions_to_reverify: Vec::new(),
                 })
             }
@@ -497,11 +617,12 @@ impl Chain {
                         new_main_blocks_transactions_hashes.into_iter(),
                     )
                 {
-                    memory_pool.remove_by_hash(&transaction_accepted);
+                    memory_pool.remove_included(&transaction_accepted);
                     self.verifying_transactions.remove(&transaction_accepted);
                 }
 
                 // reverify all transactions from old main branch' blocks
+                let decanonized_blocks_hashes = origin.decanonized_route.clone();
                 let old_main_blocks_transactions = origin
                     .decanonized_route
                     .into_iter()
@@ -535,6 +656,7 @@ impl Chain {
 
                 let result = BlockInsertionResult {
                     canonized_blocks_hashes: canonized_blocks_hashes,
+                    decanonized_blocks_hashes: decanonized_blocks_hashes,
                     // order matters: db transactions, then ordered mempool transactions, then ordered verifying transactions
                     transactions_to_reverify: old_main_blocks_transactions
                         .into_iter()
@@ -1508,6 +1630,35 @@ fn double_spend_transaction_is_removed_from_memory_pool_when_output_is_spent_in_
         assert_eq!(chain.information().transactions.transactions_count, 1);
     }
 
+    #[test]
+    fn chain_invalidate_and_reconsider_block() {
+        let db = Arc::new(BlockChainDatabase::init_test_chain(
+            vec![test_data::genesis().into()],
+        ));
+        let mut chain = Chain::new(
+            db,
+            ConsensusParams::new(Network::Unitest, ConsensusFork::BitcoinCore),
+            Arc::new(RwLock::new(MemoryPool::new())),
+        );
+        let genesis_hash = chain.best_block().hash;
+        let block1 = test_data::block_h1();
+        let block1_hash = block1.hash();
+        let block2 = test_data::block_h2();
+        let block2_hash = block2.hash();
+        chain.insert_best_block(block1.into()).expect("no error");
+        chain.insert_best_block(block2.into()).expect("no error");
+
+        let result = chain.invalidate_block(&block1_hash).expect("no error");
+        assert_eq!(result.decanonized_blocks_hashes, vec![block1_hash.clone(), block2_hash.clone()]);
+        assert_eq!(chain.best_storage_block().hash, genesis_hash);
+        assert!(chain.storage().is_invalid_block(&block2_hash));
+
+        let result = chain.reconsider_block(&block1_hash).expect("no error");
+        assert_eq!(result.canonized_blocks_hashes, vec![block1_hash.clone(), block2_hash.clone()]);
+        assert_eq!(chain.best_storage_block().hash, block2_hash);
+        assert!(!chain.storage().is_invalid_block(&block2_hash));
+    }
+
     #[test]
     fn update_memory_pool_transaction() {
         use self::test_data::{ChainBuilder, TransactionBuilder};
@@ -1,10 +1,13 @@
+use std::collections::VecDeque;
 use std::sync::Arc;
 use parking_lot::Mutex;
-use chain::{IndexedTransaction, Transaction, IndexedBlock};
+use chain::{IndexedTransaction, Transaction, IndexedBlock, IndexedBlockHeader};
 use message::types;
+use primitives::hash::H256;
 use synchronization_executor::TaskExecutor;
-use synchronization_verifier::{Verifier, TransactionVerificationSink};
+use synchronization_verifier::{Verifier, BlockVerificationSink, TransactionVerificationSink};
 use synchronization_client_core::{ClientCore, SynchronizationClientCore};
+use background_validation::BackgroundValidation;
 use types::{PeerIndex, ClientCoreRef, SynchronizationStateRef, EmptyBoxFuture, SyncListenerRef};
 
 #[cfg_attr(feature = "cargo-clippy", allow(doc_markdown))]
@@ -121,13 +124,13 @@ use types::{PeerIndex, ClientCoreRef, SynchronizationStateRef, EmptyBoxFuture, S
 
 /// Synchronization client trait
 pub trait Client: Send + Sync + 'static {
-    fn spawn_block(&self, block: IndexedBlock);
     fn on_connect(&self, peer_index: PeerIndex);
     fn on_disconnect(&self, peer_index: PeerIndex);
     fn on_inventory(&self, peer_index: PeerIndex, message: types::Inv);
     fn on_headers(&self, peer_index: PeerIndex, message: types::Headers);
     fn on_block(&self, peer_index: PeerIndex, block: IndexedBlock);
     fn on_transaction(&self, peer_index: PeerIndex, transaction: IndexedTransaction);
+    fn on_package_transactions(&self, peer_index: PeerIndex, transactions: Vec<IndexedTransaction>);
     fn on_notfound(&self, peer_index: PeerIndex, message: types::NotFound);
     fn after_peer_nearly_blocks_verified(&self, peer_index: PeerIndex, future: EmptyBoxFuture);
     fn accept_transaction(
@@ -135,7 +138,12 @@ pub trait Client: Send + Sync + 'static {
         transaction: Transaction,
         sink: Box<TransactionVerificationSink>,
     ) -> Result<(), String>;
+    fn accept_block(&self, block: IndexedBlock, sink: Box<BlockVerificationSink>) -> Result<(), String>;
+    fn accept_header(&self, header: IndexedBlockHeader) -> Result<(), String>;
+    fn invalidate_block(&self, hash: &H256) -> Result<(), String>;
+    fn reconsider_block(&self, hash: &H256) -> Result<(), String>;
     fn install_sync_listener(&self, listener: SyncListenerRef);
+    fn start_background_validation(&self, background_validation: BackgroundValidation);
 }
 
 /// Synchronization client facade
@@ -155,10 +163,6 @@ where
     T: TaskExecutor,
     U: Verifier,
 {
-    fn spawn_block(&self, block: IndexedBlock) {
-        self.core.lock().spawn_block(block);
-    }
-
     fn on_connect(&self, peer_index: PeerIndex) {
         self.core.lock().on_connect(peer_index);
     }
@@ -219,6 +223,18 @@ where
         }
     }
 
+    fn on_package_transactions(&self, peer_index: PeerIndex, transactions: Vec<IndexedTransaction>) {
+        // if package is accepted => package transactions + all dependent orphans should be verified
+        let transactions_to_verify = self.core.lock().on_package_transactions(peer_index, transactions);
+
+        if let Some(mut transactions_to_verify) = transactions_to_verify {
+            let next_block_height = self.shared_state.best_storage_block_height() + 1;
+            while let Some(tx) = transactions_to_verify.pop_front() {
+                self.verifier.verify_transaction(next_block_height, tx);
+            }
+        }
+    }
+
     fn on_notfound(&self, peer_index: PeerIndex, message: types::NotFound) {
         self.core.lock().on_notfound(peer_index, message);
     }
@@ -245,9 +261,44 @@ where
         Ok(())
     }
 
+    fn accept_block(&self, block: IndexedBlock, sink: Box<BlockVerificationSink>) -> Result<(), String> {
+        // verification tasks must be scheduled in the same order as they were built
+        let _verification_lock = self.verification_lock.lock();
+        let mut blocks_to_verify = try!(self.core.lock().accept_block(block, sink));
+
+        while let Some(block) = blocks_to_verify.pop_front() {
+            self.verifier.verify_block(block);
+        }
+        Ok(())
+    }
+
+    fn accept_header(&self, header: IndexedBlockHeader) -> Result<(), String> {
+        self.core.lock().accept_header(header)
+    }
+
+    fn invalidate_block(&self, hash: &H256) -> Result<(), String> {
+        let _verification_lock = self.verification_lock.lock();
+        let transactions_to_verify = try!(self.core.lock().invalidate_block(hash));
+        self.verify_transactions(transactions_to_verify);
+        Ok(())
+    }
+
+    fn reconsider_block(&self, hash: &H256) -> Result<(), String> {
+        let _verification_lock = self.verification_lock.lock();
+        let transactions_to_verify = try!(self.core.lock().reconsider_block(hash));
+        self.verify_transactions(transactions_to_verify);
+        Ok(())
+    }
+
     fn install_sync_listener(&self, listener: SyncListenerRef) {
         self.core.lock().install_sync_listener(listener);
     }
+
+    fn start_background_validation(&self, background_validation: BackgroundValidation) {
+        let mut core = self.core.lock();
+        core.set_background_validation(background_validation);
+        core.execute_background_validation_tasks();
+    }
 }
 
 impl<T, U> SynchronizationClient<T, U>
@@ -268,4 +319,11 @@ where
             verifier: verifier,
         })
     }
+
+    fn verify_transactions(&self, mut transactions_to_verify: VecDeque<IndexedTransaction>) {
+        let next_block_height = self.shared_state.best_storage_block_height() + 1;
+        while let Some(tx) = transactions_to_verify.pop_front() {
+            self.verifier.verify_transaction(next_block_height, tx);
+        }
+    }
 }
@@ -4,11 +4,13 @@ use std::collections::hash_map::Entry;
 use std::sync::Arc;
 use futures::Future;
 use parking_lot::Mutex;
-use time::precise_time_s;
+use time::{precise_time_s, get_time};
 use chain::{IndexedBlockHeader, IndexedTransaction, Transaction, IndexedBlock};
 use message::{types, Services};
 use message::common::{InventoryType, InventoryVector};
-use miner::transaction_fee_rate;
+use miner::{transaction_fee, transaction_fee_rate};
+use memory_pool_acceptance::{test_accept_package, sort_package_transactions};
+use background_validation::BackgroundValidation;
 use primitives::hash::H256;
 use verification::BackwardsCompatibleChainVerifier as ChainVerifier;
 use synchronization_chain::{Chain, BlockState, TransactionState, BlockInsertionResult};
@@ -83,6 +85,11 @@ pub trait ClientCore {
         peer_index: PeerIndex,
         transaction: IndexedTransaction,
     ) -> Option<VecDeque<IndexedTransaction>>;
+    fn on_package_transactions(
+        &mut self,
+        peer_index: PeerIndex,
+        transactions: Vec<IndexedTransaction>,
+    ) -> Option<VecDeque<IndexedTransaction>>;
     fn on_notfound(&mut self, peer_index: PeerIndex, message: types::NotFound);
     fn after_peer_nearly_blocks_verified(&mut self, peer_index: PeerIndex, future: EmptyBoxFuture);
     fn accept_transaction(
@@ -90,6 +97,14 @@ pub trait ClientCore {
         transaction: Transaction,
         sink: Box<TransactionVerificationSink>,
     ) -> Result<VecDeque<IndexedTransaction>, String>;
+    fn accept_block(
+        &mut self,
+        block: IndexedBlock,
+        sink: Box<BlockVerificationSink>,
+    ) -> Result<VecDeque<IndexedBlock>, String>;
+    fn accept_header(&mut self, header: IndexedBlockHeader) -> Result<(), String>;
+    fn invalidate_block(&mut self, hash: &H256) -> Result<VecDeque<IndexedTransaction>, String>;
+    fn reconsider_block(&mut self, hash: &H256) -> Result<VecDeque<IndexedTransaction>, String>;
     fn install_sync_listener(&mut self, listener: SyncListenerRef);
     fn execute_synchronization_tasks(
         &mut self,
@@ -136,6 +151,8 @@ pub struct SynchronizationClientCore<T: TaskExecutor> {
     verifying_blocks_futures: HashMap<PeerIndex, (HashSet<H256>, Vec<EmptyBoxFuture>)>,
     /// Verifying transactions futures
     verifying_transactions_sinks: HashMap<H256, Box<TransactionVerificationSink>>,
+    /// Verifying blocks futures (for locally submitted blocks)
+    verifying_blocks_sinks: HashMap<H256, Box<BlockVerificationSink>>,
     /// Hashes of items we do not want to relay after verification is completed
     do_not_relay: HashSet<H256>,
     /// Block processing speed meter
@@ -144,10 +161,12 @@ pub struct SynchronizationClientCore<T: TaskExecutor> {
     sync_speed_meter: AverageSpeedMeter,
     /// Configuration
     config: Config,
-    /// Synchronization events listener
-    listener: Option<SyncListenerRef>,
+    /// Synchronization events listeners
+    listeners: Vec<SyncListenerRef>,
     /// Time of last duplicated blocks request.
     last_dup_time: f64,
+    /// Background validation of the UTXO set snapshot
+    background_validation: Option<BackgroundValidation>,
 }
 
 /// Verification sink for synchronization client core
@@ -403,6 +422,14 @@ where
 					headers[num_headers - 1].hash.to_reversed_str()
 				);
 
+                // remember tip of the branch, which forks from the stored block
+                if self.chain.block_state(&last_known_hash) == BlockState::Stored {
+                    self.chain.insert_headers_tip(
+                        &last_known_hash,
+                        &headers[first_unknown_index..num_headers],
+                    );
+                }
+
                 // prepare new headers array
                 let new_headers = headers.split_off(first_unknown_index);
                 self.chain.schedule_blocks_headers(new_headers);
@@ -438,6 +465,19 @@ where
             &block.header.hash,
         );
 
+        // blocks, requested by the background validation, are processed separately
+        let block = match self.background_validation.as_mut().map(|validation| validation.on_block(block)) {
+            Some(Some(block)) => block,
+            Some(None) => {
+                self.peers_tasks.useful_peer(peer_index);
+                if self.background_validation.as_ref().map(|validation| validation.is_idle()).unwrap_or(false) {
+                    self.execute_background_validation_tasks();
+                }
+                return None;
+            },
+            None => block,
+        };
+
         // prepare list of blocks to verify + make all required changes to the chain
         let mut result: Option<VecDeque<IndexedBlock>> = None;
         let block_state = self.chain.block_state(&block.header.hash);
@@ -588,6 +628,63 @@ where
         self.process_peer_transaction(Some(peer_index), transaction, true)
     }
 
+    /// When peer has sent us package transactions, required to accept some orphan transactions
+    fn on_package_transactions(
+        &mut self,
+        peer_index: PeerIndex,
+        transactions: Vec<IndexedTransaction>,
+    ) -> Option<VecDeque<IndexedTransaction>> {
+        if self.state.is_synchronizing() {
+            return None;
+        }
+
+        // we only accept transactions, which are required by our orphans && which are not yet known
+        let transactions: Vec<_> = transactions
+            .into_iter()
+            .filter(|transaction| {
+                self.orphaned_transactions_pool.is_unknown_parent(&transaction.hash) &&
+                    self.chain.transaction_state(&transaction.hash) == TransactionState::Unknown
+            })
+            .collect();
+        if transactions.is_empty() {
+            return None;
+        }
+
+        // package consists of parents, sorted topologically, followed by orphans, which are waiting for these parents
+        let parents = match sort_package_transactions(transactions) {
+            Some(parents) => parents,
+            None => {
+                self.peers.misbehaving(peer_index, "Got 'pkgtxns' message with unsortable transactions");
+                return None;
+            }
+        };
+        let parents_hashes: HashSet<H256> = parents.iter().map(|tx| tx.hash.clone()).collect();
+        let children = self.orphaned_transactions_pool.transactions_for_parents(&parents_hashes);
+        let package: Vec<Transaction> = parents.iter().chain(children.iter()).map(|tx| tx.raw.clone()).collect();
+
+        // check package against package fee rate
+        let result = test_accept_package(
+            &self.chain_verifier,
+            &self.chain.storage(),
+            &self.chain.memory_pool(),
+            get_time().sec as u32,
+            package,
+        );
+        if let Some(reject_reason) = result.reject_reason {
+            trace!(target: "sync", "Package of {} transactions from peer#{} is rejected: {:?}", parents.len() + children.len(), peer_index, reject_reason);
+            return None;
+        }
+
+        // package is accepted => schedule verification of parents && all dependent orphans
+        let mut transactions_to_verify = VecDeque::new();
+        for parent in parents {
+            if let Some(transactions) = self.process_peer_transaction(Some(peer_index), parent, true) {
+                transactions_to_verify.extend(transactions);
+            }
+        }
+        Some(transactions_to_verify)
+    }
+
     /// When peer has no blocks
     fn on_notfound(&mut self, peer_index: PeerIndex, message: types::NotFound) {
         let notfound_blocks: HashSet<_> = message
@@ -671,10 +768,104 @@ where
         }
     }
 
+    /// Accept locally submitted block. Errors are BIP22 reject reasons.
+    fn accept_block(
+        &mut self,
+        block: IndexedBlock,
+        sink: Box<BlockVerificationSink>,
+    ) -> Result<VecDeque<IndexedBlock>, String> {
+        match self.chain.block_state(&block.header.hash) {
+            BlockState::Stored => return Err("duplicate".to_owned()),
+            BlockState::DeadEnd => return Err("duplicate-invalid".to_owned()),
+            BlockState::Verifying => return Err("duplicate-inconclusive".to_owned()),
+            BlockState::Unknown | BlockState::Scheduled | BlockState::Requested => (),
+        }
+        match self.chain.block_state(&block.header.raw.previous_header_hash) {
+            BlockState::Verifying | BlockState::Stored => (),
+            BlockState::DeadEnd => return Err("bad-prevblk".to_owned()),
+            BlockState::Unknown | BlockState::Scheduled | BlockState::Requested => {
+                return Err("prev-blk-not-found".to_owned())
+            }
+        }
+
+        // verify this block + all orphans, which were waiting for it
+        let block_hash = block.header.hash.clone();
+        let mut blocks_to_verify: VecDeque<IndexedBlock> = VecDeque::new();
+        blocks_to_verify.extend(self.orphaned_blocks_pool.remove_blocks_for_parent(
+            &block_hash,
+        ));
+        blocks_to_verify.push_front(block);
+        let blocks_hashes_to_forget: Vec<_> =
+            blocks_to_verify.iter().map(|b| b.hash().clone()).collect();
+        self.chain.forget_blocks_leave_header(&blocks_hashes_to_forget);
+        let blocks_headers_to_verify: Vec<_> =
+            blocks_to_verify.iter().map(|b| b.header.clone()).collect();
+        self.chain.verify_blocks(blocks_headers_to_verify);
+        self.verifying_blocks_sinks.insert(block_hash, sink);
+        Ok(blocks_to_verify)
+    }
+
+    /// Accept locally submitted block header. Errors are BIP22 reject reasons.
+    fn accept_header(&mut self, header: IndexedBlockHeader) -> Result<(), String> {
+        match self.chain.block_state(&header.hash) {
+            BlockState::DeadEnd => return Err("duplicate-invalid".to_owned()),
+            BlockState::Unknown => (),
+            _ => return Ok(()),
+        }
+        match self.chain.block_state(&header.raw.previous_header_hash) {
+            BlockState::DeadEnd => return Err("bad-prevblk".to_owned()),
+            BlockState::Unknown => return Err("prev-blk-not-found".to_owned()),
+            _ => (),
+        }
+
+        let verification_result = {
+            let headers_provider = MessageBlockHeadersProvider::new(
+                &self.chain,
+                self.chain.best_block_header().number,
+            );
+            self.chain_verifier.verify_block_header(
+                &headers_provider,
+                &header.hash,
+                &header.raw,
+            )
+        };
+        if let Err(error) = verification_result {
+            self.chain.mark_dead_end_block(&header.hash);
+            return Err(format!("{:?}", error));
+        }
+
+        // only headers, extending best headers chain, are scheduled for downloading
+        if header.raw.previous_header_hash != self.chain.best_block_header().hash {
+            trace!(target: "sync", "Submitted header {} is not on top of best headers chain", header.hash.to_reversed_str());
+            return Ok(());
+        }
+
+        self.chain.schedule_blocks_headers(vec![header]);
+        if !self.state.is_synchronizing() {
+            if self.chain.length_of_blocks_state(BlockState::Scheduled) +
+                self.chain.length_of_blocks_state(BlockState::Requested) == 1
+            {
+                self.switch_to_nearly_saturated_state();
+            } else {
+                self.switch_to_synchronization_state();
+            }
+        }
+        self.execute_synchronization_tasks(None, None);
+        Ok(())
+    }
+
+    fn invalidate_block(&mut self, hash: &H256) -> Result<VecDeque<IndexedTransaction>, String> {
+        let switch_result = self.chain.invalidate_block(hash).map_err(|err| err.to_string())?;
+        Ok(self.on_best_branch_switched(switch_result))
+    }
+
+    fn reconsider_block(&mut self, hash: &H256) -> Result<VecDeque<IndexedTransaction>, String> {
+        let switch_result = self.chain.reconsider_block(hash).map_err(|err| err.to_string())?;
+        Ok(self.on_best_branch_switched(switch_result))
+    }
+
     fn install_sync_listener(&mut self, listener: SyncListenerRef) {
-        // currently single, single-setup listener is supported
-        assert!(self.listener.is_none());
-        self.listener = Some(listener);
+        self.listeners.push(listener);
     }
 
     /// Schedule new synchronization tasks, if any.
@@ -984,12 +1175,14 @@ where
             verifying_blocks_by_peer: HashMap::new(),
             verifying_blocks_futures: HashMap::new(),
             verifying_transactions_sinks: HashMap::new(),
+            verifying_blocks_sinks: HashMap::new(),
             do_not_relay: HashSet::new(),
             block_speed_meter: AverageSpeedMeter::with_inspect_items(SYNC_SPEED_BLOCKS_TO_INSPECT),
             sync_speed_meter: AverageSpeedMeter::with_inspect_items(BLOCKS_SPEED_BLOCKS_TO_INSPECT),
             config: config,
-            listener: None,
+            listeners: Vec::new(),
             last_dup_time: 0f64,
+            background_validation: None,
         }));
 
         {
@@ -1001,6 +1194,42 @@ where
         sync
     }
 
+    /// Start background validation of the UTXO set snapshot
+    pub fn set_background_validation(&mut self, background_validation: BackgroundValidation) {
+        self.background_validation = Some(background_validation);
+    }
+
+    /// Request blocks, required by the background validation of the UTXO set snapshot.
+    /// Blocks are only requested when the node is saturated, so that the active chainstate is synchronized first
+    pub fn execute_background_validation_tasks(&mut self) {
+        if !self.state.is_saturated() {
+            return;
+        }
+
+        let is_completed = match self.background_validation {
+            Some(ref background_validation) => background_validation.is_completed(),
+            None => return,
+        };
+        if is_completed {
+            self.background_validation = None;
+            return;
+        }
+
+        let peers = self.peers_tasks.useful_peers();
+        let inv_type = if self.chain.is_segwit_active() {
+            InventoryType::MessageWitnessBlock
+        } else {
+            InventoryType::MessageBlock
+        };
+        let tasks = self.background_validation
+            .as_mut()
+            .expect("checked above; qed")
+            .prepare_blocks_requests_tasks(&peers, inv_type);
+        for task in tasks {
+            self.executor.execute(task);
+        }
+    }
+
     /// Get information on current synchronization state.
     #[cfg(test)]
     pub fn information(&self) -> Information {
@@ -1159,14 +1388,63 @@ where
     }
 
     /// Process new peer transaction
+    /// Process switch of the best storage branch, made on user request
+    fn on_best_branch_switched(&mut self, switch_result: BlockInsertionResult) -> VecDeque<IndexedTransaction> {
+        if switch_result.canonized_blocks_hashes.is_empty() && switch_result.decanonized_blocks_hashes.is_empty() {
+            return VecDeque::new();
+        }
+
+        // update shared state
+        self.shared_state.update_best_storage_block_height(
+            self.chain.best_storage_block().number,
+        );
+
+        // notify listeners
+        for listener in &self.listeners {
+            listener.best_chain_reorganized(
+                &switch_result.decanonized_blocks_hashes,
+                &switch_result.canonized_blocks_hashes,
+            );
+            for block_hash in switch_result.decanonized_blocks_hashes.iter().rev() {
+                listener.block_disconnected(block_hash);
+            }
+            for block_hash in &switch_result.canonized_blocks_hashes {
+                listener.block_connected(block_hash);
+            }
+            listener.best_storage_block_inserted(&self.chain.best_storage_block().hash);
+        }
+
+        // in-memory blocks have been forgotten => restart synchronization from the new best block
+        self.execute_synchronization_tasks(None, None);
+
+        // deal with transactions of decanonized blocks
+        let mut transactions_to_verify = VecDeque::new();
+        for tx in switch_result.transactions_to_reverify {
+            if let Some(tx_orphans) = self.process_peer_transaction(None, tx, false) {
+                transactions_to_verify.extend(tx_orphans);
+            }
+        }
+        transactions_to_verify
+    }
+
     fn process_peer_transaction(
         &mut self,
-        _peer_index: Option<PeerIndex>,
+        peer_index: Option<PeerIndex>,
         transaction: IndexedTransaction,
         relay: bool,
     ) -> Option<VecDeque<IndexedTransaction>> {
         match self.try_append_transaction(transaction.clone(), relay) {
             Err(AppendTransactionError::Orphan(unknown_parents)) => {
+                // ask peer, which has sent us this orphan, for unknown parents => it could be accepted as a package
+                if let Some(peer_index) = peer_index {
+                    if unknown_parents.len() <= types::GETPKGTXNS_MAX_TRANSACTIONS {
+                        self.executor.execute(Task::GetPackageTransactions(
+                            peer_index,
+                            types::GetPkgTxns::with_hashes(unknown_parents.iter().cloned().collect()),
+                        ));
+                    }
+                }
+
                 self.orphaned_transactions_pool.insert(
                     transaction,
                     unknown_parents,
@@ -1190,7 +1468,7 @@ where
 
         // else => verify transaction + it's orphans and then add to the memory pool
         // if any parent transaction is unknown => we have orphan transaction => remember in orphan pool
-        /*let unknown_parents: HashSet<H256> = transaction
+        let unknown_parents: HashSet<H256> = transaction
             .raw
             .inputs
             .iter()
@@ -1202,7 +1480,7 @@ where
             .collect();
         if !unknown_parents.is_empty() {
             return Err(AppendTransactionError::Orphan(unknown_parents));
-        }*/
+        }
 
         // else verify && insert this transaction && all dependent orphans
         let mut transactions: VecDeque<IndexedTransaction> = VecDeque::new();
@@ -1286,7 +1564,7 @@ where
             return;
         }
 
-        if let Some(ref listener) = self.listener {
+        for listener in &self.listeners {
             listener.synchronization_state_switched(true);
         }
 
@@ -1300,7 +1578,7 @@ where
             return;
         }
 
-        if let Some(ref listener) = self.listener {
+        for listener in &self.listeners {
             listener.synchronization_state_switched(false);
         }
 
@@ -1314,7 +1592,7 @@ where
             return;
         }
 
-        if let Some(ref listener) = self.listener {
+        for listener in &self.listeners {
             listener.synchronization_state_switched(false);
         }
 
@@ -1356,10 +1634,6 @@ where
         }
     }
 
-    pub fn spawn_block(&mut self, block: IndexedBlock) {
-        self.chain.insert_best_block(block).unwrap();
-    }
-
     fn on_block_verification_success(
         &mut self,
         block: IndexedBlock,
@@ -1374,6 +1648,9 @@ where
         let needs_relay = !self.do_not_relay.remove(block.hash());
 
         let block_hash = block.hash().clone();
+        // remember verification future, if any
+        let future_sink = self.verifying_blocks_sinks.remove(&block_hash);
+        let future_block = future_sink.as_ref().map(|_| block.clone());
         // insert block to the storage
         match {
             // remove block from verification queue
@@ -1405,9 +1682,25 @@ where
                     );
                 }
 
-                // notify listener
+                // notify listeners
+                if !insert_result.decanonized_blocks_hashes.is_empty() {
+                    for listener in &self.listeners {
+                        listener.best_chain_reorganized(
+                            &insert_result.decanonized_blocks_hashes,
+                            &insert_result.canonized_blocks_hashes,
+                        );
+                    }
+                }
+                for listener in &self.listeners {
+                    for block_hash in insert_result.decanonized_blocks_hashes.iter().rev() {
+                        listener.block_disconnected(block_hash);
+                    }
+                    for block_hash in &insert_result.canonized_blocks_hashes {
+                        listener.block_connected(block_hash);
+                    }
+                }
                 if let Some(best_block_hash) = insert_result.canonized_blocks_hashes.last() {
-                    if let Some(ref listener) = self.listener {
+                    for listener in &self.listeners {
                         listener.best_storage_block_inserted(best_block_hash);
                     }
                 }
@@ -1415,6 +1708,16 @@ where
                 // awake threads, waiting for this block insertion
                 self.awake_waiting_threads(&block_hash);
 
+                // call verification future, if any
+                // block, which has not became best, is reported as inconclusive (BIP22)
+                if let (Some(future_sink), Some(future_block)) = (future_sink, future_block) {
+                    if insert_result.canonized_blocks_hashes.contains(&block_hash) {
+                        future_sink.on_block_verification_success(future_block);
+                    } else {
+                        future_sink.on_block_verification_error("inconclusive", &block_hash);
+                    }
+                }
+
                 // continue with synchronization
                 self.execute_synchronization_tasks(None, None);
 
@@ -1476,6 +1779,9 @@ where
             }
         }
 
+        // remember tip of the invalid branch
+        self.chain.insert_invalid_tip(hash);
+
         // forget for this block and all its children
         // headers are also removed as they all are invalid
         self.chain.forget_block_with_children(hash);
@@ -1486,6 +1792,11 @@ where
         // awake threads, waiting for this block insertion
         self.awake_waiting_threads(hash);
 
+        // call verification future, if any
+        if let Some(future_sink) = self.verifying_blocks_sinks.remove(hash) {
+            future_sink.on_block_verification_error(err, hash);
+        }
+
         // start new tasks
         self.execute_synchronization_tasks(None, None);
     }
@@ -1504,8 +1815,15 @@ where
         // transaction was in verification queue => insert to memory pool
         self.chain.insert_verified_transaction(transaction.clone());
 
-        // calculate transaction fee rate
+        // calculate transaction fee && fee rate
+        let transaction_fee = transaction_fee(&self.chain, &transaction.raw);
         let transaction_fee_rate = transaction_fee_rate(&self.chain, &transaction.raw);
+        self.chain.memory_pool().write().set_fee(&transaction.hash, transaction_fee as i64);
+
+        // notify listeners
+        for listener in &self.listeners {
+            listener.transaction_accepted(&transaction, transaction_fee);
+        }
 
         // relay transaction to peers
         if needs_relay {
@@ -1607,6 +1925,9 @@ pub mod tests {
     struct DummySyncListenerData {
         pub is_synchronizing: bool,
         pub best_blocks: Vec<H256>,
+        pub reorganizations: Vec<(Vec<H256>, Vec<H256>)>,
+        pub connected_blocks: Vec<H256>,
+        pub disconnected_blocks: Vec<H256>,
     }
 
     struct DummySyncListener {
@@ -1627,6 +1948,18 @@ pub mod tests {
         fn best_storage_block_inserted(&self, block_hash: &H256) {
             self.data.lock().best_blocks.push(block_hash.clone());
         }
+
+        fn best_chain_reorganized(&self, decanonized: &[H256], canonized: &[H256]) {
+            self.data.lock().reorganizations.push((decanonized.to_vec(), canonized.to_vec()));
+        }
+
+        fn block_connected(&self, block_hash: &H256) {
+            self.data.lock().connected_blocks.push(block_hash.clone());
+        }
+
+        fn block_disconnected(&self, block_hash: &H256) {
+            self.data.lock().disconnected_blocks.push(block_hash.clone());
+        }
     }
 
     fn create_sync(
@@ -2685,6 +3018,51 @@ pub mod tests {
         assert_eq!(core.lock().information().orphaned_transactions, 0);
     }
 
+    #[test]
+    fn orphaned_transaction_is_verified_when_package_is_received() {
+        let genesis = test_data::genesis();
+        let b1: Block = test_data::block_builder()
+            .header().parent(genesis.hash()).build()
+            .transaction()
+                .coinbase()
+                .output().value(1_000).script_pubkey("51").build()
+                .build()
+            .build();
+        let spend = |hash: H256, value: u64| -> Transaction {
+            let block: Block = test_data::block_builder()
+                .header().build()
+                .transaction()
+                    .input().hash(hash).index(0).build()
+                    .output().value(value).script_pubkey("51").build()
+                    .build()
+                .build();
+            block.transactions[0].clone()
+        };
+        // zero-fee parent is paid by its child
+        let parent = spend(b1.transactions[0].hash(), 1_000);
+        let child = spend(parent.hash(), 500);
+        let storage = Arc::new(BlockChainDatabase::init_test_chain(vec![genesis.into(), b1.into()]));
+        let (executor, core, sync) = create_sync(Some(storage), None);
+
+        // orphan is received => package is requested from the same peer
+        sync.on_transaction(1, child.into());
+        assert_eq!(core.lock().information().orphaned_transactions, 1);
+        let tasks = executor.take_tasks();
+        assert_eq!(tasks, vec![Task::GetPackageTransactions(1, types::GetPkgTxns::with_hashes(vec![parent.hash()]))]);
+
+        // package is received => parent && orphan are verified
+        sync.on_package_transactions(1, vec![parent.into()]);
+        assert_eq!(core.lock().information().orphaned_transactions, 0);
+        assert_eq!(
+            core.lock()
+                .information()
+                .chain
+                .transactions
+                .transactions_count,
+            2
+        );
+    }
+
     #[test]
     // https://github.com/ethcore/parity-bitcoin/issues/121
     fn when_previous_block_verification_failed_fork_is_not_requested() {
@@ -3386,4 +3764,55 @@ pub mod tests {
         assert_eq!(data.lock().is_synchronizing, false);
         assert_eq!(data.lock().best_blocks.len(), 3);
     }
+
+    #[test]
+    fn sync_listener_reorganization_call() {
+        let genesis = test_data::genesis();
+        let fork1 = test_data::build_n_empty_blocks_from(2, 100, &genesis.block_header);
+        let fork2 = test_data::build_n_empty_blocks_from(3, 200, &genesis.block_header);
+
+        let (_, _, sync) = create_sync(None, None);
+        let data = Arc::new(Mutex::new(DummySyncListenerData::default()));
+        sync.install_sync_listener(Box::new(DummySyncListener::new(data.clone())));
+
+        // fork1 becomes the best chain => no reorganizations
+        sync.on_headers(
+            1,
+            types::Headers::with_headers(fork1.iter().map(|b| b.block_header.clone()).collect()),
+        );
+        sync.on_block(1, fork1[0].clone().into());
+        sync.on_block(1, fork1[1].clone().into());
+        assert_eq!(data.lock().best_blocks.len(), 2);
+        assert!(data.lock().reorganizations.is_empty());
+
+        // longer fork2 => fork1 is disconnected, fork2 is connected
+        sync.on_headers(
+            2,
+            types::Headers::with_headers(fork2.iter().map(|b| b.block_header.clone()).collect()),
+        );
+        sync.on_block(2, fork2[0].clone().into());
+        sync.on_block(2, fork2[1].clone().into());
+        sync.on_block(2, fork2[2].clone().into());
+        assert_eq!(
+            data.lock().reorganizations,
+            vec![
+                (
+                    vec![fork1[0].hash(), fork1[1].hash()],
+                    vec![fork2[0].hash(), fork2[1].hash(), fork2[2].hash()],
+                ),
+            ]
+        );
+        assert_eq!(data.lock().best_blocks.last(), Some(&fork2[2].hash()));
+        assert_eq!(data.lock().disconnected_blocks, vec![fork1[1].hash(), fork1[0].hash()]);
+        assert_eq!(
+            data.lock().connected_blocks,
+            vec![
+                fork1[0].hash(),
+                fork1[1].hash(),
+                fork2[0].hash(),
+                fork2[1].hash(),
+                fork2[2].hash(),
+            ]
+        );
+    }
 }
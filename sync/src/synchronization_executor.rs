@@ -40,6 +40,10 @@ pub enum Task {
     NotFound(PeerIndex, types::NotFound),
     /// Send inventory
     Inventory(PeerIndex, types::Inv),
+    /// Request package transactions from peer
+    GetPackageTransactions(PeerIndex, types::GetPkgTxns),
+    /// Send package transactions
+    PackageTransactions(PeerIndex, types::PkgTxns),
     /// Send headers
     Headers(PeerIndex, types::Headers, Option<RequestId>),
     /// Relay new block to peers
@@ -189,6 +193,27 @@ impl LocalSynchronizationTaskExecutor {
         }
     }
 
+    fn execute_get_package_transactions(&self, peer_index: PeerIndex, getpkgtxns: types::GetPkgTxns) {
+        if let Some(connection) = self.peers.connection(peer_index) {
+            trace!(target: "sync", "Querying {} package transactions from peer#{}", getpkgtxns.hashes.len(), peer_index);
+            connection.send_get_pkg_txns(&getpkgtxns);
+        }
+    }
+
+    fn execute_package_transactions(&self, peer_index: PeerIndex, pkgtxns: types::PkgTxns) {
+        if let Some(connection) = self.peers.connection(peer_index) {
+            trace!(target: "sync", "Sending pkgtxns with {} transactions to peer#{}", pkgtxns.transactions.len(), peer_index);
+            for transaction in &pkgtxns.transactions {
+                self.peers.hash_known_as(
+                    peer_index,
+                    transaction.hash(),
+                    KnownHashType::Transaction,
+                );
+            }
+            connection.send_pkg_txns(&pkgtxns);
+        }
+    }
+
     fn execute_headers(
         &self,
         peer_index: PeerIndex,
@@ -277,6 +302,12 @@ impl TaskExecutor for LocalSynchronizationTaskExecutor {
             Task::BlockTxn(peer_index, blocktxn) => self.execute_block_txn(peer_index, blocktxn),
             Task::NotFound(peer_index, notfound) => self.execute_notfound(peer_index, notfound),
             Task::Inventory(peer_index, inventory) => self.execute_inventory(peer_index, inventory),
+            Task::GetPackageTransactions(peer_index, getpkgtxns) => {
+                self.execute_get_package_transactions(peer_index, getpkgtxns)
+            }
+            Task::PackageTransactions(peer_index, pkgtxns) => {
+                self.execute_package_transactions(peer_index, pkgtxns)
+            }
             Task::Headers(peer_index, headers, request_id) => {
                 self.execute_headers(peer_index, headers, request_id)
             }
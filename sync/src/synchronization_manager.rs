@@ -131,6 +131,8 @@ impl ManagementWorker {
                         core.chain().forget_block(&orphan_to_remove);
                     }
                 }
+                // active chainstate is synchronized => continue background validation (if any)
+                core.execute_background_validation_tasks();
             }
         }
 
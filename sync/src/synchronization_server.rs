@@ -4,9 +4,10 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 use parking_lot::{Mutex, Condvar};
-use chain::IndexedTransaction;
+use chain::{Block, IndexedTransaction};
 use message::{types, common};
 use primitives::hash::H256;
+use storage::MIN_BLOCKS_TO_KEEP;
 use synchronization_executor::{Task, TaskExecutor};
 use types::{PeerIndex, RequestId, BlockHeight, StorageRef, ExecutorRef, MemoryPoolRef, PeersRef};
 use utils::KnownHashType;
@@ -26,6 +27,8 @@ pub enum ServerTask {
     Mempool(PeerIndex),
     /// Serve 'getblocktxn' request
     GetBlockTxn(PeerIndex, types::GetBlockTxn),
+    /// Serve 'getpkgtxns' request
+    GetPkgTxns(PeerIndex, types::GetPkgTxns),
 }
 
 /// Synchronization server
@@ -84,7 +87,8 @@ impl ServerTask {
             ServerTask::GetBlocks(peer_index, _) |
             ServerTask::GetHeaders(peer_index, _, _) |
             ServerTask::Mempool(peer_index) |
-            ServerTask::GetBlockTxn(peer_index, _) => peer_index,
+            ServerTask::GetBlockTxn(peer_index, _) |
+            ServerTask::GetPkgTxns(peer_index, _) => peer_index,
         }
     }
 }
@@ -264,6 +268,9 @@ where
             ServerTask::GetBlockTxn(peer_index, message) => {
                 self.serve_get_block_txn(peer_index, message)
             }
+            ServerTask::GetPkgTxns(peer_index, message) => {
+                self.serve_get_pkg_txns(peer_index, message)
+            }
         }
 
         None
@@ -327,7 +334,7 @@ where
                 }
             }
             common::InventoryType::MessageBlock => {
-                if let Some(block) = self.storage.block(next_item.hash.clone().into()) {
+                if let Some(block) = self.servable_block(&next_item.hash) {
                     trace!(target: "sync", "'getblocks' response to peer#{} is ready with block {}", peer_index, next_item.hash.to_reversed_str());
                     self.executor.execute(Task::Block(peer_index, block.into()));
                 } else {
@@ -335,7 +342,7 @@ where
                 }
             }
             common::InventoryType::MessageFilteredBlock => {
-                if let Some(block) = self.storage.block(next_item.hash.clone().into()) {
+                if let Some(block) = self.servable_block(&next_item.hash) {
                     let message_artefacts =
                         self.peers.build_merkle_block(peer_index, &block.into());
                     if let Some(message_artefacts) = message_artefacts {
@@ -362,7 +369,7 @@ where
                 }
             }
             common::InventoryType::MessageCompactBlock => {
-                if let Some(block) = self.storage.block(next_item.hash.clone().into()) {
+                if let Some(block) = self.servable_block(&next_item.hash) {
                     let message = self.peers.build_compact_block(peer_index, &block.into());
                     if let Some(message) = message {
                         trace!(target: "sync", "'getblocks' response to peer#{} is ready with compactblock {}", peer_index, next_item.hash.to_reversed_str());
@@ -375,7 +382,7 @@ where
                 }
             }
             common::InventoryType::MessageWitnessBlock => {
-                if let Some(block) = self.storage.block(next_item.hash.clone().into()) {
+                if let Some(block) = self.servable_block(&next_item.hash) {
                     trace!(target: "sync", "'getblocks' response to peer#{} is ready with witness-block {}", peer_index, next_item.hash.to_reversed_str());
                     self.executor.execute(
                         Task::WitnessBlock(peer_index, block.into()),
@@ -391,6 +398,20 @@ where
         Some(ServerTask::ReversedGetData(peer_index, message, notfound))
     }
 
+    /// Returns block that could be served to peer. When pruning is enabled, only the most
+    /// recent blocks are served (BIP159), even if older blocks are not yet pruned
+    fn servable_block(&self, hash: &H256) -> Option<Block> {
+        if self.storage.prune_mode().is_enabled() {
+            let best_block_number = self.storage.best_block().number;
+            match self.storage.block_number(hash) {
+                Some(number) if number + MIN_BLOCKS_TO_KEEP < best_block_number => return None,
+                _ => (),
+            }
+        }
+
+        self.storage.block(hash.clone().into())
+    }
+
     fn serve_get_blocks(&self, peer_index: PeerIndex, message: types::GetBlocks) {
         if let Some(block_height) =
             self.locate_best_common_block(&message.hash_stop, &message.block_locator_hashes)
@@ -557,6 +578,37 @@ where
         ));
     }
 
+    fn serve_get_pkg_txns(&self, peer_index: PeerIndex, message: types::GetPkgTxns) {
+        // package transactions are only served from the memory pool
+        let (transactions, notfound) = {
+            let memory_pool = self.memory_pool.read();
+            let mut transactions = Vec::with_capacity(message.hashes.len());
+            let mut notfound = Vec::new();
+            for hash in message.hashes {
+                match memory_pool.read_by_hash(&hash) {
+                    Some(transaction) => transactions.push(transaction.clone()),
+                    None => notfound.push(common::InventoryVector::tx(hash)),
+                }
+            }
+            (transactions, notfound)
+        };
+
+        if !transactions.is_empty() {
+            trace!(target: "sync", "'getpkgtxns' response to peer#{} is ready with {} transactions", peer_index, transactions.len());
+            self.executor.execute(Task::PackageTransactions(
+                peer_index,
+                types::PkgTxns::with_transactions(transactions),
+            ));
+        }
+        if !notfound.is_empty() {
+            trace!(target: "sync", "'getpkgtxns' request from peer#{} contains {} unknown transactions", peer_index, notfound.len());
+            self.executor.execute(Task::NotFound(
+                peer_index,
+                types::NotFound::with_inventory(notfound),
+            ));
+        }
+    }
+
     fn locate_best_common_block(&self, hash_stop: &H256, locator: &[H256]) -> Option<BlockHeight> {
         for block_hash in locator.iter().chain(&[hash_stop.clone()]) {
             if let Some(block_number) = self.storage.block_number(block_hash) {
@@ -600,7 +652,8 @@ pub mod tests {
     use message::types;
     use message::common::{self, Services, InventoryVector, InventoryType};
     use primitives::hash::H256;
-    use chain::Transaction;
+    use chain::{Transaction, IndexedBlock};
+    use storage::{PruneMode, MIN_BLOCKS_TO_KEEP};
     use inbound_connection::tests::DummyOutboundSyncConnection;
     use miner::MemoryPool;
     use local_node::tests::{default_filterload, make_filteradd};
@@ -694,6 +747,45 @@ pub mod tests {
         assert_eq!(tasks, vec![Task::Block(0, test_data::genesis().into())]);
     }
 
+    #[test]
+    fn server_getdata_responds_notfound_when_block_is_too_deep_for_pruned_node() {
+        let mut blocks: Vec<IndexedBlock> = vec![test_data::genesis().into()];
+        blocks.extend(test_data::build_n_empty_blocks_from_genesis(MIN_BLOCKS_TO_KEEP + 1, 0).into_iter().map(Into::into));
+        let recent_block = blocks[2].clone();
+        let storage = Arc::new(BlockChainDatabase::init_test_chain(blocks).with_prune_mode(PruneMode::Manual));
+        let executor = DummyTaskExecutor::new();
+        let server = ServerImpl::new(
+            Arc::new(PeersImpl::default()),
+            storage,
+            Arc::new(RwLock::new(MemoryPool::new())),
+            executor.clone(),
+        );
+        // when asking for genesis block and for one of the most recent blocks
+        let inventory = vec![
+            InventoryVector {
+                inv_type: InventoryType::MessageBlock,
+                hash: test_data::genesis().hash(),
+            },
+            InventoryVector {
+                inv_type: InventoryType::MessageBlock,
+                hash: recent_block.hash().clone(),
+            },
+        ];
+        server.execute(ServerTask::GetData(
+            0,
+            types::GetData::with_inventory(inventory.clone()),
+        ));
+        // => respond with recent block and notfound for genesis block
+        let tasks = DummyTaskExecutor::wait_tasks(executor);
+        assert_eq!(
+            tasks,
+            vec![
+                Task::Block(0, recent_block),
+                Task::NotFound(0, types::NotFound::with_inventory(vec![inventory[0].clone()])),
+            ]
+        );
+    }
+
     #[test]
     fn server_getblocks_do_not_responds_inventory_when_synchronized() {
         let (_, _, executor, _, server) = create_synchronization_server();
@@ -829,6 +921,45 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn server_get_pkg_txns_responds_with_memory_pool_transactions() {
+        let (_, memory_pool, executor, _, server) = create_synchronization_server();
+        let transaction = Transaction::default();
+        let transaction_hash = transaction.hash();
+        memory_pool.write().insert_verified(transaction.clone().into());
+
+        // when asking for package transaction
+        server.execute(ServerTask::GetPkgTxns(
+            0,
+            types::GetPkgTxns::with_hashes(vec![transaction_hash]),
+        ));
+
+        // => respond with transaction from the memory pool
+        let tasks = DummyTaskExecutor::wait_tasks(executor);
+        assert_eq!(
+            tasks,
+            vec![Task::PackageTransactions(0, types::PkgTxns::with_transactions(vec![transaction]))]
+        );
+    }
+
+    #[test]
+    fn server_get_pkg_txns_responds_notfound_when_transaction_is_unknown() {
+        let (_, _, executor, _, server) = create_synchronization_server();
+
+        // when asking for unknown package transaction
+        server.execute(ServerTask::GetPkgTxns(
+            0,
+            types::GetPkgTxns::with_hashes(vec![H256::from(1)]),
+        ));
+
+        // => respond with notfound
+        let tasks = DummyTaskExecutor::wait_tasks(executor);
+        assert_eq!(
+            tasks,
+            vec![Task::NotFound(0, types::NotFound::with_inventory(vec![InventoryVector::tx(H256::from(1))]))]
+        );
+    }
+
     #[test]
     fn server_get_block_txn_responds_when_good_request() {
         let (_, _, executor, peers, server) = create_synchronization_server();
@@ -3,7 +3,7 @@ use futures::Future;
 use parking_lot::{Mutex, RwLock};
 use storage;
 use local_node::LocalNode;
-use miner::MemoryPool;
+use miner::{MemoryPool, FeeEstimator};
 use super::SyncListener;
 use synchronization_client::SynchronizationClient;
 use synchronization_executor::LocalSynchronizationTaskExecutor;
@@ -29,6 +29,9 @@ pub type StorageRef = storage::SharedStore;
 /// Reference to memory pool
 pub type MemoryPoolRef = Arc<RwLock<MemoryPool>>;
 
+/// Reference to fee estimator
+pub type FeeEstimatorRef = Arc<RwLock<FeeEstimator>>;
+
 /// Shared synchronization state reference
 pub type SynchronizationStateRef = Arc<SynchronizationState>;
 
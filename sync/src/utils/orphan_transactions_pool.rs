@@ -51,6 +51,25 @@ impl OrphanTransactionsPool {
         self.by_hash.contains_key(hash)
     }
 
+    /// Check if some orphan transaction is waiting for this parent transaction
+    pub fn is_unknown_parent(&self, hash: &H256) -> bool {
+        self.by_parent.contains_key(hash)
+    }
+
+    /// Get all orphan transactions, which have no unknown parents except given (and their orphan children).
+    /// Transactions are returned in the insertion order.
+    pub fn transactions_for_parents(&self, parents: &HashSet<H256>) -> Vec<IndexedTransaction> {
+        let mut known = parents.clone();
+        let mut transactions = Vec::new();
+        for (hash, orphan) in self.by_hash.iter() {
+            if orphan.unknown_parents.is_subset(&known) {
+                known.insert(hash.clone());
+                transactions.push(orphan.transaction.clone());
+            }
+        }
+        transactions
+    }
+
     /// Insert orphan transaction
     pub fn insert(&mut self, transaction: IndexedTransaction, unknown_parents: HashSet<H256>) {
         assert!(!self.by_hash.contains_key(&transaction.hash));
@@ -286,4 +305,30 @@ mod tests {
 
         pool.remove_transactions(&[chain.at(2).hash(), chain.at(1).hash()]);
     }
+
+    #[test]
+    fn orphan_transaction_pool_transactions_for_parents() {
+        let chain = &mut ChainBuilder::new();
+        TransactionBuilder::with_output(100).store(chain)			// t1
+			.into_input(0).add_output(200).store(chain)				// t1 -> t2
+			.into_input(0).add_output(300).store(chain)				// t1 -> t2 -> t3
+			.set_default_input(0).set_output(400).store(chain)		// t4
+			.into_input(0).set_output(500).store(chain); // t4 -> t5
+        let unknown = |index: usize| -> HashSet<H256> {
+            chain.at(index).inputs.iter().map(|i| i.previous_output.hash.clone()).collect()
+        };
+
+        let mut pool = OrphanTransactionsPool::new();
+        pool.insert(chain.at(1).into(), unknown(1)); // t2
+        pool.insert(chain.at(2).into(), unknown(2)); // t3
+        pool.insert(chain.at(4).into(), unknown(4)); // t5
+        assert!(pool.is_unknown_parent(&chain.at(0).hash()));
+        assert!(!pool.is_unknown_parent(&chain.at(4).hash()));
+
+        let parents = vec![chain.at(0).hash()].into_iter().collect();
+        let transactions: Vec<H256> = pool.transactions_for_parents(&parents).into_iter().map(|tx| tx.hash).collect();
+        assert_eq!(transactions, vec![chain.at(1).hash(), chain.at(2).hash()]);
+        // transactions are not removed from the pool
+        assert_eq!(pool.len(), 3);
+    }
 }
@@ -0,0 +1,442 @@
+//! ZeroMQ notifications, compatible with Bitcoin Core `-zmqpub*` notifications.
+//!
+//! Every notification is a multipart message: topic, body and 4-byte little-endian sequence number,
+//! which is incremented (per topic and endpoint) after every message. Bodies are:
+//! - `hashblock`, `hashtx`: block/transaction hash (in RPC byte order);
+//! - `rawblock`, `rawtx`: serialized block/transaction (including witness);
+//! - `sequence`: block hash + `C` (connected) or `D` (disconnected), or transaction hash + `A` (added to the
+//!   memory pool) or `R` (removed from the memory pool not because of block inclusion), followed by 8-byte
+//!   little-endian memory pool sequence number.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Sender, Receiver};
+use std::thread;
+use byteorder::{LittleEndian, WriteBytesExt};
+use zmq;
+use chain::{IndexedBlock, IndexedTransaction};
+use miner::{MemoryPoolListener, MemoryPoolRemovalReason};
+use primitives::hash::H256;
+use ser::{serialize_with_flags, SERIALIZE_TRANSACTION_WITNESS};
+use types::StorageRef;
+use super::SyncListener;
+
+/// ZeroMQ notification topic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ZmqTopic {
+    /// Hash of the new best block
+    HashBlock,
+    /// Hash of the transaction, added to the memory pool, or connected/disconnected with the block
+    HashTx,
+    /// Serialized new best block
+    RawBlock,
+    /// Serialized transaction, added to the memory pool, or connected/disconnected with the block
+    RawTx,
+    /// Blocks connections/disconnections and memory pool additions/removals
+    Sequence,
+}
+
+impl ZmqTopic {
+    /// Topic name (also used as the name of `--zmqpub<topic>` command line option)
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ZmqTopic::HashBlock => "hashblock",
+            ZmqTopic::HashTx => "hashtx",
+            ZmqTopic::RawBlock => "rawblock",
+            ZmqTopic::RawTx => "rawtx",
+            ZmqTopic::Sequence => "sequence",
+        }
+    }
+
+    /// All topics, in the order of notification
+    pub fn all() -> [ZmqTopic; 5] {
+        [ZmqTopic::HashBlock, ZmqTopic::HashTx, ZmqTopic::RawBlock, ZmqTopic::RawTx, ZmqTopic::Sequence]
+    }
+}
+
+/// ZeroMQ notifications configuration
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ZmqConfig {
+    /// Topics, published to the endpoints (e.g. `tcp://127.0.0.1:28332`)
+    pub endpoints: Vec<(ZmqTopic, String)>,
+}
+
+impl ZmqConfig {
+    pub fn is_empty(&self) -> bool {
+        self.endpoints.is_empty()
+    }
+}
+
+/// ZeroMQ notifications publisher. Notifications are published from the separate thread.
+/// Publisher should be installed both as synchronization and memory pool listener.
+/// New best blocks are not published while the node is synchronizing.
+#[derive(Clone)]
+pub struct ZmqPublisher {
+    inner: Arc<ZmqPublisherInner>,
+}
+
+struct ZmqPublisherInner {
+    endpoints: Vec<(ZmqTopic, String)>,
+    tx: Mutex<Sender<ZmqTask>>,
+    is_synchronizing: AtomicBool,
+    worker_thread: Mutex<Option<thread::JoinHandle<()>>>,
+}
+
+enum ZmqTask {
+    BestBlock(H256),
+    BlockConnected(H256),
+    BlockDisconnected(H256),
+    TransactionAdded(IndexedTransaction, u64),
+    TransactionRemoved(H256, u64),
+    Stop,
+}
+
+/// Topic, published to the socket
+struct Notifier {
+    topic: ZmqTopic,
+    socket: usize,
+    sequence: u32,
+}
+
+struct ZmqWorker {
+    storage: StorageRef,
+    // context must outlive sockets
+    _context: zmq::Context,
+    sockets: Vec<zmq::Socket>,
+    notifiers: Vec<Notifier>,
+}
+
+impl ZmqPublisher {
+    /// Binds sockets to the configured endpoints and starts publishing thread
+    pub fn new(config: &ZmqConfig, storage: StorageRef) -> Result<Self, String> {
+        let context = zmq::Context::new();
+        let mut sockets = Vec::new();
+        let mut socket_by_endpoint = HashMap::new();
+        let mut notifiers = Vec::new();
+        let mut bound_endpoints = Vec::new();
+        let mut endpoints = Vec::new();
+        for topic in ZmqTopic::all().iter() {
+            for &(_, ref endpoint) in config.endpoints.iter().filter(|&&(ref t, _)| t == topic) {
+                let socket = match socket_by_endpoint.get(endpoint).cloned() {
+                    Some(socket) => socket,
+                    None => {
+                        let socket = context.socket(zmq::PUB)
+                            .and_then(|socket| socket.bind(endpoint).map(|_| socket))
+                            .map_err(|err| format!("Failed to bind ZMQ socket to {}: {}", endpoint, err))?;
+                        // wildcard endpoints (e.g. `tcp://127.0.0.1:*`) are resolved when bound
+                        let bound_endpoint = match socket.get_last_endpoint() {
+                            Ok(Ok(bound_endpoint)) => bound_endpoint,
+                            _ => endpoint.clone(),
+                        };
+                        info!(target: "sync", "Publishing ZMQ notifications to {}", bound_endpoint);
+                        sockets.push(socket);
+                        bound_endpoints.push(bound_endpoint);
+                        socket_by_endpoint.insert(endpoint.clone(), sockets.len() - 1);
+                        sockets.len() - 1
+                    }
+                };
+
+                notifiers.push(Notifier {
+                    topic: *topic,
+                    socket: socket,
+                    sequence: 0,
+                });
+                endpoints.push((*topic, bound_endpoints[socket].clone()));
+            }
+        }
+
+        let worker = ZmqWorker {
+            storage: storage,
+            _context: context,
+            sockets: sockets,
+            notifiers: notifiers,
+        };
+
+        let (tx, rx) = channel();
+        let worker_thread = thread::Builder::new()
+            .name("ZMQ notification thread".to_owned())
+            .spawn(move || worker.run(rx))
+            .map_err(|err| format!("Error creating ZMQ notification thread: {}", err))?;
+
+        Ok(ZmqPublisher {
+            inner: Arc::new(ZmqPublisherInner {
+                endpoints: endpoints,
+                tx: Mutex::new(tx),
+                is_synchronizing: AtomicBool::default(),
+                worker_thread: Mutex::new(Some(worker_thread)),
+            }),
+        })
+    }
+
+    /// Topics and endpoints, the sockets are bound to
+    pub fn endpoints(&self) -> &[(ZmqTopic, String)] {
+        &self.inner.endpoints
+    }
+
+    fn send(&self, task: ZmqTask) {
+        self.inner.tx.lock().expect("lock is never poisoned; qed")
+            .send(task)
+            .expect("ZMQ notification thread have the same lifetime as `ZmqPublisher`")
+    }
+}
+
+impl Drop for ZmqPublisherInner {
+    fn drop(&mut self) {
+        if let Some(join_handle) = self.worker_thread.lock().expect("lock is never poisoned; qed").take() {
+            let _ = self.tx.lock().expect("lock is never poisoned; qed").send(ZmqTask::Stop);
+            join_handle.join().expect("Clean shutdown.");
+        }
+    }
+}
+
+impl SyncListener for ZmqPublisher {
+    fn synchronization_state_switched(&self, is_synchronizing: bool) {
+        self.inner.is_synchronizing.store(is_synchronizing, Ordering::SeqCst);
+    }
+
+    fn best_storage_block_inserted(&self, block_hash: &H256) {
+        if !self.inner.is_synchronizing.load(Ordering::SeqCst) {
+            self.send(ZmqTask::BestBlock(block_hash.clone()));
+        }
+    }
+
+    fn block_connected(&self, block_hash: &H256) {
+        self.send(ZmqTask::BlockConnected(block_hash.clone()));
+    }
+
+    fn block_disconnected(&self, block_hash: &H256) {
+        self.send(ZmqTask::BlockDisconnected(block_hash.clone()));
+    }
+}
+
+impl MemoryPoolListener for ZmqPublisher {
+    fn transaction_added(&self, transaction: &IndexedTransaction, sequence: u64) {
+        self.send(ZmqTask::TransactionAdded(transaction.clone(), sequence));
+    }
+
+    fn transaction_removed(&self, hash: &H256, reason: MemoryPoolRemovalReason, sequence: u64) {
+        // removal of transactions, included in the block, is implied by the block connection
+        if reason != MemoryPoolRemovalReason::Block {
+            self.send(ZmqTask::TransactionRemoved(hash.clone(), sequence));
+        }
+    }
+}
+
+impl ZmqWorker {
+    fn run(mut self, rx: Receiver<ZmqTask>) {
+        for task in rx {
+            match task {
+                ZmqTask::BestBlock(hash) => {
+                    self.publish(ZmqTopic::HashBlock, &*hash.reversed());
+                    if let Some(block) = self.block(&hash, &[ZmqTopic::RawBlock]) {
+                        self.publish(ZmqTopic::RawBlock, &serialize_with_flags(&block.to_raw_block(), SERIALIZE_TRANSACTION_WITNESS));
+                    }
+                }
+                ZmqTask::BlockConnected(hash) => self.block_event(&hash, b'C'),
+                ZmqTask::BlockDisconnected(hash) => self.block_event(&hash, b'D'),
+                ZmqTask::TransactionAdded(transaction, sequence) => {
+                    self.transaction(&transaction);
+                    self.publish(ZmqTopic::Sequence, &transaction_sequence_body(&transaction.hash, b'A', sequence));
+                }
+                ZmqTask::TransactionRemoved(hash, sequence) => {
+                    self.publish(ZmqTopic::Sequence, &transaction_sequence_body(&hash, b'R', sequence));
+                }
+                ZmqTask::Stop => break,
+            }
+        }
+        trace!(target: "sync", "ZMQ notification thread stopped");
+    }
+
+    fn block_event(&mut self, hash: &H256, label: u8) {
+        if let Some(block) = self.block(hash, &[ZmqTopic::HashTx, ZmqTopic::RawTx]) {
+            for transaction in &block.transactions {
+                self.transaction(transaction);
+            }
+        }
+
+        let mut body = hash.reversed().to_vec();
+        body.push(label);
+        self.publish(ZmqTopic::Sequence, &body);
+    }
+
+    fn transaction(&mut self, transaction: &IndexedTransaction) {
+        self.publish(ZmqTopic::HashTx, &*transaction.hash.reversed());
+        if self.has_topic(ZmqTopic::RawTx) {
+            self.publish(ZmqTopic::RawTx, &serialize_with_flags(&transaction.raw, SERIALIZE_TRANSACTION_WITNESS));
+        }
+    }
+
+    /// Reads block from the storage if any of `topics` is published
+    fn block(&self, hash: &H256, topics: &[ZmqTopic]) -> Option<IndexedBlock> {
+        if !topics.iter().any(|topic| self.has_topic(*topic)) {
+            return None;
+        }
+
+        self.storage.indexed_block(hash.clone().into())
+    }
+
+    fn has_topic(&self, topic: ZmqTopic) -> bool {
+        self.notifiers.iter().any(|notifier| notifier.topic == topic)
+    }
+
+    fn publish(&mut self, topic: ZmqTopic, body: &[u8]) {
+        let sockets = &self.sockets;
+        for notifier in self.notifiers.iter_mut().filter(|notifier| notifier.topic == topic) {
+            let mut sequence = Vec::with_capacity(4);
+            sequence.write_u32::<LittleEndian>(notifier.sequence).expect("writing to Vec never fails; qed");
+
+            let socket = &sockets[notifier.socket];
+            let result = socket.send(topic.name().as_bytes(), zmq::SNDMORE)
+                .and_then(|_| socket.send(body, zmq::SNDMORE))
+                .and_then(|_| socket.send(&sequence[..], 0));
+            if let Err(err) = result {
+                warn!(target: "sync", "Failed to publish ZMQ {} notification: {}", topic.name(), err);
+            }
+
+            notifier.sequence = notifier.sequence.wrapping_add(1);
+        }
+    }
+}
+
+fn transaction_sequence_body(hash: &H256, label: u8, sequence: u64) -> Vec<u8> {
+    let mut body = hash.reversed().to_vec();
+    body.push(label);
+    body.write_u64::<LittleEndian>(sequence).expect("writing to Vec never fails; qed");
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test_data;
+
+    use std::sync::Arc;
+    use zmq;
+    use byteorder::{LittleEndian, WriteBytesExt};
+    use chain::IndexedTransaction;
+    use db::BlockChainDatabase;
+    use primitives::hash::H256;
+    use miner::{MemoryPoolListener, MemoryPoolRemovalReason};
+    use ser::{serialize_with_flags, SERIALIZE_TRANSACTION_WITNESS};
+    use super::super::SyncListener;
+    use super::{ZmqConfig, ZmqPublisher, ZmqTopic};
+
+    /// Connects subscriber to the publisher. Messages are dropped until the subscription reaches
+    /// the publisher => best block is published until it is received by the subscriber.
+    /// Returns subscriber and the number of published best blocks.
+    fn subscriber(
+        context: &zmq::Context,
+        publisher: &ZmqPublisher,
+        block_hash: &H256,
+        last_topic: &str,
+    ) -> (zmq::Socket, u32) {
+        let subscriber = context.socket(zmq::SUB).unwrap();
+        subscriber.set_rcvtimeo(5000).unwrap();
+        subscriber.set_subscribe(b"").unwrap();
+        subscriber.connect(&publisher.endpoints()[0].1).unwrap();
+
+        let mut published = 0;
+        loop {
+            publisher.best_storage_block_inserted(block_hash);
+            published += 1;
+            if subscriber.poll(zmq::POLLIN, 100).unwrap() != 0 {
+                break;
+            }
+        }
+
+        // skip messages, published while waiting for the subscription
+        loop {
+            let message = subscriber.recv_multipart(0).unwrap();
+            if message[0] == last_topic.as_bytes() && message[2] == sequence(published - 1) {
+                break;
+            }
+        }
+
+        (subscriber, published)
+    }
+
+    fn sequence(sequence: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.write_u32::<LittleEndian>(sequence).unwrap();
+        bytes
+    }
+
+    fn message(topic: &str, body: &[u8], sequence: u32) -> Vec<Vec<u8>> {
+        vec![topic.as_bytes().to_vec(), body.to_vec(), self::sequence(sequence)]
+    }
+
+    #[test]
+    fn zmq_publisher_publishes_block_and_memory_pool_events() {
+        let endpoint = "tcp://127.0.0.1:*";
+        let genesis = test_data::genesis();
+        let storage = Arc::new(BlockChainDatabase::init_test_chain(vec![genesis.clone().into()]));
+        let config = ZmqConfig {
+            endpoints: ZmqTopic::all().iter().map(|topic| (*topic, endpoint.to_owned())).collect(),
+        };
+        let publisher = ZmqPublisher::new(&config, storage).unwrap();
+        let context = zmq::Context::new();
+        let (subscriber, best_blocks) = subscriber(&context, &publisher, &genesis.hash(), "rawblock");
+
+        let genesis_hash = genesis.hash().reversed();
+        let coinbase_hash = genesis.transactions[0].hash().reversed();
+        let coinbase = serialize_with_flags(&genesis.transactions[0], SERIALIZE_TRANSACTION_WITNESS);
+        let raw_genesis = serialize_with_flags(&genesis, SERIALIZE_TRANSACTION_WITNESS);
+
+        // block connection: transactions, then sequence
+        publisher.block_connected(&genesis.hash());
+        assert_eq!(subscriber.recv_multipart(0).unwrap(), message("hashtx", &*coinbase_hash, 0));
+        assert_eq!(subscriber.recv_multipart(0).unwrap(), message("rawtx", &coinbase, 0));
+        let mut body = genesis_hash.to_vec();
+        body.push(b'C');
+        assert_eq!(subscriber.recv_multipart(0).unwrap(), message("sequence", &body, 0));
+
+        // new best block
+        publisher.best_storage_block_inserted(&genesis.hash());
+        assert_eq!(subscriber.recv_multipart(0).unwrap(), message("hashblock", &*genesis_hash, best_blocks));
+        assert_eq!(subscriber.recv_multipart(0).unwrap(), message("rawblock", &raw_genesis, best_blocks));
+
+        // memory pool events
+        let transaction: IndexedTransaction = test_data::TransactionBuilder::with_output(10).into();
+        let transaction_hash = transaction.hash.reversed();
+        publisher.transaction_added(&transaction, 7);
+        assert_eq!(subscriber.recv_multipart(0).unwrap(), message("hashtx", &*transaction_hash, 1));
+        assert_eq!(subscriber.recv_multipart(0).unwrap(),
+            message("rawtx", &serialize_with_flags(&transaction.raw, SERIALIZE_TRANSACTION_WITNESS), 1));
+        let mut body = transaction_hash.to_vec();
+        body.push(b'A');
+        body.extend_from_slice(&[7, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(subscriber.recv_multipart(0).unwrap(), message("sequence", &body, 1));
+
+        // removal because of block inclusion is not published
+        publisher.transaction_removed(&transaction.hash, MemoryPoolRemovalReason::Block, 8);
+        publisher.transaction_removed(&transaction.hash, MemoryPoolRemovalReason::Conflict, 9);
+        let mut body = transaction_hash.to_vec();
+        body.push(b'R');
+        body.extend_from_slice(&[9, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(subscriber.recv_multipart(0).unwrap(), message("sequence", &body, 2));
+    }
+
+    #[test]
+    fn zmq_publisher_skips_best_blocks_while_synchronizing() {
+        let endpoint = "tcp://127.0.0.1:*";
+        let genesis = test_data::genesis();
+        let storage = Arc::new(BlockChainDatabase::init_test_chain(vec![genesis.clone().into()]));
+        let config = ZmqConfig {
+            endpoints: vec![(ZmqTopic::HashBlock, endpoint.to_owned()), (ZmqTopic::Sequence, endpoint.to_owned())],
+        };
+        let publisher = ZmqPublisher::new(&config, storage).unwrap();
+        let context = zmq::Context::new();
+        let (subscriber, best_blocks) = subscriber(&context, &publisher, &genesis.hash(), "hashblock");
+
+        publisher.synchronization_state_switched(true);
+        publisher.best_storage_block_inserted(&genesis.hash());
+        publisher.block_disconnected(&genesis.hash());
+        let mut body = genesis.hash().reversed().to_vec();
+        body.push(b'D');
+        assert_eq!(subscriber.recv_multipart(0).unwrap(), message("sequence", &body, 0));
+
+        publisher.synchronization_state_switched(false);
+        publisher.best_storage_block_inserted(&genesis.hash());
+        assert_eq!(subscriber.recv_multipart(0).unwrap(), message("hashblock", &*genesis.hash().reversed(), best_blocks));
+    }
+}
@@ -9,6 +9,8 @@ use duplex_store::DuplexTransactionOutputProvider;
 use deployments::BlockDeployments;
 use canon::CanonBlock;
 use error::{Error, TransactionError};
+use primitives::bytes::Bytes;
+use signet::SignetTxs;
 use timestamp::median_timestamp;
 
 /// Flexible verification of ordered block
@@ -19,6 +21,7 @@ pub struct BlockAcceptor<'a> {
 	pub coinbase_claim: BlockCoinbaseClaim<'a>,
 	pub coinbase_script: BlockCoinbaseScript<'a>,
 	pub witness: BlockWitness<'a>,
+	pub signet: BlockSignetSolution<'a>,
 }
 
 impl<'a> BlockAcceptor<'a> {
@@ -38,6 +41,7 @@ impl<'a> BlockAcceptor<'a> {
 			coinbase_claim: BlockCoinbaseClaim::new(block, store, height),
 			sigops: BlockSigops::new(block, store, consensus, height),
 			witness: BlockWitness::new(block, deployments),
+			signet: BlockSignetSolution::new(block, consensus),
 		}
 	}
 
@@ -48,6 +52,7 @@ impl<'a> BlockAcceptor<'a> {
 		self.coinbase_claim.check()?;
 		self.coinbase_script.check()?;
 		self.witness.check()?;
+		self.signet.check()?;
 		Ok(())
 	}
 }
@@ -339,6 +344,31 @@ impl<'a> BlockWitness<'a> {
 	}
 }
 
+pub struct BlockSignetSolution<'a> {
+	block: CanonBlock<'a>,
+	challenge: Option<&'a Bytes>,
+}
+
+impl<'a> BlockSignetSolution<'a> {
+	fn new(block: CanonBlock<'a>, consensus: &'a ConsensusParams) -> Self {
+		BlockSignetSolution {
+			block: block,
+			challenge: consensus.signet_challenge.as_ref(),
+		}
+	}
+
+	fn check(&self) -> Result<(), Error> {
+		let challenge = match self.challenge {
+			Some(challenge) => challenge,
+			None => return Ok(()),
+		};
+
+		let signet_txs = SignetTxs::new(&self.block.header.raw, &self.block.transactions, challenge)
+			.ok_or(Error::SignetSolution)?;
+		signet_txs.verify().map_err(|_| Error::SignetSolution)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	extern crate test_data;
@@ -2,7 +2,7 @@
 
 use hash::H256;
 use chain::{IndexedBlock, IndexedBlockHeader, BlockHeader, Transaction};
-use storage::{SharedStore, TransactionOutputProvider, BlockHeaderProvider, BlockOrigin};
+use storage::{SharedStore, TransactionOutputProvider, BlockHeaderProvider, BlockOrigin, SideChainOrigin, Error as DBError};
 use network::ConsensusParams;
 use error::{Error, TransactionError};
 use canon::{CanonBlock, CanonTransaction};
@@ -31,6 +31,11 @@ impl BackwardsCompatibleChainVerifier {
 		}
 	}
 
+	/// Deployments states cache, shared with the block verification
+	pub fn deployments(&self) -> &Deployments {
+		&self.deployments
+	}
+
 	fn verify_block(&self, verification_level: VerificationLevel, block: &IndexedBlock) -> Result<(), Error> {
 		if verification_level == VerificationLevel::NoVerification {
 			return Ok(());
@@ -38,7 +43,7 @@ impl BackwardsCompatibleChainVerifier {
 
 		let current_time = ::time::get_time().sec as u32;
 		// first run pre-verification
-		let chain_verifier = ChainVerifier::new(block, self.consensus.network, current_time);
+		let chain_verifier = ChainVerifier::new(block, &self.consensus, current_time);
 		chain_verifier.check()?;
 
 		assert_eq!(Some(self.store.best_block().hash), self.store.block_hash(self.store.best_block().number));
@@ -85,6 +90,57 @@ impl BackwardsCompatibleChainVerifier {
 		Ok(())
 	}
 
+	/// Verifies canon chain blocks, starting at the given number, as if they were new blocks.
+	/// Blocks are disconnected in the fork of the store and then connected back one by one,
+	/// so the store itself is not modified. On failure, returns number of the failed block
+	pub fn replay_canon_blocks(&self, verification_level: VerificationLevel, from: u32) -> Result<(), (u32, Error)> {
+		let best_block = self.store.best_block();
+		if from == 0 || from > best_block.number {
+			return Ok(());
+		}
+
+		let mut route = Vec::new();
+		for block_number in from..best_block.number + 1 {
+			match self.store.block_hash(block_number) {
+				Some(hash) => route.push(hash),
+				None => return Err((block_number, Error::Database(DBError::UnknownBlock))),
+			}
+		}
+
+		let origin = SideChainOrigin {
+			ancestor: from - 1,
+			canonized_route: Vec::new(),
+			decanonized_route: route.clone(),
+			block_number: from,
+		};
+		// fork is never flushed, so replayed blocks are only connected in memory
+		let fork = self.store.fork(origin).map_err(|err| (from, Error::Database(err)))?;
+
+		let current_time = ::time::get_time().sec as u32;
+		for (block_number, hash) in (from..).zip(route) {
+			let block = match self.store.indexed_block(hash.into()) {
+				Some(block) => block,
+				None => return Err((block_number, Error::Database(DBError::UnknownBlock))),
+			};
+			trace!(target: "verification", "replay_canon_blocks: {:?} block_number: {}", block.hash().reversed(), block_number);
+
+			let chain_verifier = ChainVerifier::new(&block, &self.consensus, current_time);
+			chain_verifier.check().map_err(|err| (block_number, err))?;
+
+			let median_time_past = median_timestamp_inclusive(block.header.raw.previous_header_hash.clone(), self.store.as_block_header_provider());
+			let header_provider = self.store.as_store().as_block_header_provider();
+			let deployments = BlockDeployments::new(&self.deployments, block_number, header_provider, &self.consensus);
+			let canon_block = CanonBlock::new(&block);
+			let chain_acceptor = ChainAcceptor::new(fork.store(), &self.consensus, verification_level, canon_block,
+				block_number, median_time_past, &deployments);
+			chain_acceptor.check().map_err(|err| (block_number, err))?;
+
+			fork.store().canonize(block.hash()).map_err(|err| (block_number, Error::Database(err)))?;
+		}
+
+		Ok(())
+	}
+
 	pub fn verify_block_header(
 		&self,
 		_block_header_provider: &BlockHeaderProvider,
@@ -95,7 +151,7 @@ impl BackwardsCompatibleChainVerifier {
 		// TODO: full verification
 		let current_time = ::time::get_time().sec as u32;
 		let header = IndexedBlockHeader::new(hash.clone(), header.clone());
-		let header_verifier = HeaderVerifier::new(&header, self.consensus.network, current_time);
+		let header_verifier = HeaderVerifier::new(&header, &self.consensus, current_time);
 		header_verifier.check()
 	}
 
@@ -1,17 +1,22 @@
 use std::collections::HashMap;
-use std::collections::hash_map::Entry;
 use parking_lot::Mutex;
-use network::{ConsensusParams, Deployment};
+use network::{ConsensusParams, Deployment, VERSIONBITS_TOP_BITS};
 use hash::H256;
-use storage::{BlockHeaderProvider, BlockRef, BlockAncestors, BlockIterator};
-use timestamp::median_timestamp;
+use storage::{BlockHeaderProvider, BlockRef, BlockAncestors};
+use timestamp::median_timestamp_inclusive;
 
+/// BIP9 deployment state
 #[derive(Debug, PartialEq, Clone, Copy)]
-enum ThresholdState {
+pub enum ThresholdState {
+	/// Deployment start time hasn't been reached yet
 	Defined,
+	/// Miners are signalling for the deployment
 	Started,
+	/// Deployment has been accepted, rules will be enforced from the next period
 	LockedIn,
+	/// Deployment rules are enforced
 	Active,
+	/// Deployment has timed out
 	Failed,
 }
 
@@ -22,34 +27,39 @@ impl Default for ThresholdState {
 }
 
 impl ThresholdState {
-	fn is_final(&self) -> bool {
+	pub fn is_active(&self) -> bool {
 		match *self {
-			ThresholdState::Active | ThresholdState::Failed => true,
-			ThresholdState::Defined | ThresholdState::Started | ThresholdState::LockedIn => false,
+			ThresholdState::Active => true,
+			_ => false,
 		}
 	}
 
-	fn is_active(&self) -> bool {
+	/// Returns true if miners should signal for deployment in this state
+	pub fn is_signalling(&self) -> bool {
 		match *self {
-			ThresholdState::Active => true,
-			_ => false,
+			ThresholdState::Started | ThresholdState::LockedIn => true,
+			ThresholdState::Defined | ThresholdState::Active | ThresholdState::Failed => false,
 		}
 	}
 }
 
-/// Threshold state at given point of time
-#[derive(Debug, Clone, Default)]
-struct DeploymentState {
-	/// Block number
-	block_number: u32,
-	/// Block hash
-	block_hash: H256,
-	/// Threshold state for given block
-	state: ThresholdState,
+/// Signalling statistics of the current period of started deployment
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct DeploymentStatistics {
+	/// Length of the signalling period
+	pub period: u32,
+	/// Number of signalling blocks, required for lock in
+	pub threshold: u32,
+	/// Number of blocks, elapsed since the beginning of the current period
+	pub elapsed: u32,
+	/// Number of signalling blocks in the current period
+	pub count: u32,
+	/// False if there are not enough blocks left in the current period to lock in
+	pub possible: bool,
 }
 
-/// Last known deployment states
-type DeploymentStateCache = HashMap<&'static str, DeploymentState>;
+/// Deployment states, by hash of the last block of the period
+type DeploymentStateCache = HashMap<&'static str, HashMap<H256, ThresholdState>>;
 
 #[derive(Default, Debug)]
 pub struct Deployments {
@@ -68,13 +78,16 @@ impl Deployments {
 		Deployments::default()
 	}
 
+	/// Returns state of the deployment for the block with given number
+	pub fn threshold_state(&self, deployment: Deployment, number: u32, headers: &BlockHeaderProvider, consensus: &ConsensusParams) -> ThresholdState {
+		let mut cache = self.cache.lock();
+		threshold_state(&mut cache, deployment, number, headers, consensus.miner_confirmation_window, consensus.rule_change_activation_threshold)
+	}
+
 	/// Returns true if csv deployment is active
 	pub fn csv(&self, number: u32, headers: &BlockHeaderProvider, consensus: &ConsensusParams) -> bool {
 		match consensus.csv_deployment {
-			Some(csv) => {
-				let mut cache = self.cache.lock();
-				threshold_state(&mut cache, csv, number, headers, consensus.miner_confirmation_window, consensus.rule_change_activation_threshold).is_active()
-			},
+			Some(csv) => self.threshold_state(csv, number, headers, consensus).is_active(),
 			None => false
 		}
 	}
@@ -82,13 +95,17 @@ impl Deployments {
 	/// Returns true if SegWit deployment is active
 	pub fn segwit(&self, number: u32, headers: &BlockHeaderProvider, consensus: &ConsensusParams) -> bool {
 		match consensus.segwit_deployment {
-			Some(segwit) => {
-				let mut cache = self.cache.lock();
-				threshold_state(&mut cache, segwit, number, headers, consensus.miner_confirmation_window, consensus.rule_change_activation_threshold).is_active()
-			},
+			Some(segwit) => self.threshold_state(segwit, number, headers, consensus).is_active(),
 			None => false
 		}
 	}
+
+	/// Returns version of the block with given number, signalling for all started and locked in deployments
+	pub fn block_version(&self, number: u32, headers: &BlockHeaderProvider, consensus: &ConsensusParams) -> u32 {
+		consensus.deployments().into_iter()
+			.filter(|deployment| self.threshold_state(*deployment, number, headers, consensus).is_signalling())
+			.fold(VERSIONBITS_TOP_BITS, |version, deployment| version | deployment.mask())
+	}
 }
 
 impl<'a> BlockDeployments<'a> {
@@ -122,6 +139,25 @@ impl<'a> AsRef<Deployments> for BlockDeployments<'a> {
 	} 
 }
 
+/// Returns signalling statistics of the period, which contains block with given number
+pub fn deployment_statistics(deployment: Deployment, number: u32, headers: &BlockHeaderProvider, consensus: &ConsensusParams) -> DeploymentStatistics {
+	let period = consensus.miner_confirmation_window;
+	let threshold = consensus.rule_change_activation_threshold;
+	let elapsed = number % period;
+	let count = match elapsed {
+		0 => 0,
+		_ => count_deployment_matches(number - 1, headers, deployment, elapsed) as u32,
+	};
+
+	DeploymentStatistics {
+		period: period,
+		threshold: threshold,
+		elapsed: elapsed,
+		count: count,
+		possible: period - threshold >= elapsed - count,
+	}
+}
+
 /// Calculates threshold state of given deployment
 fn threshold_state(cache: &mut DeploymentStateCache, deployment: Deployment, number: u32, headers: &BlockHeaderProvider, miner_confirmation_window: u32, rule_change_activation_threshold: u32) -> ThresholdState {
 	// deployments are checked using previous block index
@@ -133,49 +169,62 @@ fn threshold_state(cache: &mut DeploymentStateCache, deployment: Deployment, num
 		}
 	}
 
-	// number is number of block which is currently validating
-	// => it is not in database
-	// we need to make all checks for previous blocks
-	let number = number.saturating_sub(1);
+	// state is the same for all blocks of the period
+	// => it is computed using the last block of the previous period
+	// blocks of the first period are always in defined state
+	if number < miner_confirmation_window {
+		return ThresholdState::Defined;
+	}
+	let mut period_end = first_of_the_period(number - 1, miner_confirmation_window);
 
-	// get number of the first block in the period
-	let number = first_of_the_period(number, miner_confirmation_window);
+	// walk back until state of some period is known
+	let deployment_cache = cache.entry(deployment.name).or_insert_with(HashMap::new);
+	let mut to_compute = Vec::new();
+	let mut state = loop {
+		let header = match headers.block_header(BlockRef::Number(period_end)) {
+			Some(header) => header,
+			None => return ThresholdState::Defined,
+		};
+		// by checking hash, we make sure we are on the same branch
+		let hash = header.hash();
+		if let Some(state) = deployment_cache.get(&hash) {
+			break *state;
+		}
+		// optimization: state is defined for all periods before the start time
+		if median_timestamp_inclusive(hash.clone(), headers) < deployment.start_time {
+			deployment_cache.insert(hash, ThresholdState::Defined);
+			break ThresholdState::Defined;
+		}
 
-	let hash = match headers.block_header(BlockRef::Number(number)) {
-		Some(header) => header.hash(),
-		None => return ThresholdState::Defined,
+		to_compute.push((period_end, hash));
+		if period_end < miner_confirmation_window {
+			break ThresholdState::Defined;
+		}
+		period_end -= miner_confirmation_window;
 	};
 
-	match cache.entry(deployment.name) {
-		// by checking hash, we make sure we are on the same branch
-		Entry::Occupied(ref entry) if entry.get().block_number == number && entry.get().block_hash == hash => {
-			entry.get().state
-		},
-		// otherwise we need to recalculate threshold state
-		Entry::Occupied(mut entry) => {
-			let deployment_state = entry.get().clone();
-			if deployment_state.state.is_final() {
-				return deployment_state.state
-			}
-			let threshold_state = deployment_state.state;
-			let deployment_iter = ThresholdIterator::new(deployment, headers, number, miner_confirmation_window, rule_change_activation_threshold, threshold_state);
-			let state = deployment_iter.last().expect("iter must have at least one item");
-			let result = state.state;
-			entry.insert(state);
-			result
-		},
-		Entry::Vacant(entry) => {
-			let deployment_iter = ThresholdIterator::new(deployment, headers, miner_confirmation_window - 1, miner_confirmation_window, rule_change_activation_threshold, ThresholdState::Defined);
-			let state = deployment_iter.last().unwrap_or_else(|| DeploymentState {
-				block_number: number,
-				block_hash: hash,
-				state: ThresholdState::Defined,
-			});
-			let result = state.state;
-			entry.insert(state);
-			result
-		},
+	// and then compute states of all following periods
+	while let Some((period_end, hash)) = to_compute.pop() {
+		let median = median_timestamp_inclusive(hash.clone(), headers);
+		state = match state {
+			ThresholdState::Defined if median >= deployment.timeout => ThresholdState::Failed,
+			ThresholdState::Defined if median >= deployment.start_time => ThresholdState::Started,
+			ThresholdState::Started if median >= deployment.timeout => ThresholdState::Failed,
+			ThresholdState::Started => {
+				let count = count_deployment_matches(period_end, headers, deployment, miner_confirmation_window);
+				if count >= rule_change_activation_threshold as usize {
+					ThresholdState::LockedIn
+				} else {
+					ThresholdState::Started
+				}
+			},
+			ThresholdState::LockedIn => ThresholdState::Active,
+			state => state,
+		};
+		deployment_cache.insert(hash, state);
 	}
+
+	state
 }
 
 fn first_of_the_period(block: u32, miner_confirmation_window: u32) -> u32 {
@@ -193,85 +242,17 @@ fn count_deployment_matches(block_number: u32, blocks: &BlockHeaderProvider, dep
 		.count()
 }
 
-struct ThresholdIterator<'a> {
-	deployment: Deployment,
-	block_iterator: BlockIterator<'a>,
-	headers: &'a BlockHeaderProvider,
-	miner_confirmation_window: u32,
-	rule_change_activation_threshold: u32,
-	last_state: ThresholdState,
-}
-
-impl<'a> ThresholdIterator<'a> {
-	fn new(deployment: Deployment, headers: &'a BlockHeaderProvider, to_check: u32, miner_confirmation_window: u32, rule_change_activation_threshold: u32, state: ThresholdState) -> Self {
-		ThresholdIterator {
-			deployment: deployment,
-			block_iterator: BlockIterator::new(to_check, miner_confirmation_window, headers),
-			headers: headers,
-			miner_confirmation_window: miner_confirmation_window,
-			rule_change_activation_threshold: rule_change_activation_threshold,
-			last_state: state,
-		}
-	}
-}
-
-impl<'a> Iterator for ThresholdIterator<'a> {
-	type Item = DeploymentState;
-
-	fn next(&mut self) -> Option<Self::Item> {
-		let (block_number, header) = match self.block_iterator.next() {
-			Some(header) => header,
-			None => return None,
-		};
-
-		let median = median_timestamp(&header, self.headers);
-
-		match self.last_state {
-			ThresholdState::Defined => {
-				if median >= self.deployment.timeout {
-					self.last_state = ThresholdState::Failed;
-				} else if median >= self.deployment.start_time {
-					self.last_state = ThresholdState::Started;
-				}
-			},
-			ThresholdState::Started => {
-				if median >= self.deployment.timeout {
-					self.last_state = ThresholdState::Failed;
-				} else {
-					let count = count_deployment_matches(block_number, self.headers, self.deployment, self.miner_confirmation_window);
-					if count >= self.rule_change_activation_threshold as usize {
-						self.last_state = ThresholdState::LockedIn;
-					}
-				}
-			},
-			ThresholdState::LockedIn => {
-				self.last_state = ThresholdState::Active;
-			},
-			ThresholdState::Failed | ThresholdState::Active => {
-				return None
-			}
-		}
-
-		let result = DeploymentState {
-			block_number: block_number,
-			block_hash: header.hash(),
-			state: self.last_state,
-		};
-
-		Some(result)
-	}
-}
-
 #[cfg(test)]
 mod tests {
 	use std::sync::atomic::{AtomicUsize, Ordering};
 	use std::collections::HashMap;
 	use chain::BlockHeader;
 	use storage::{BlockHeaderProvider, BlockRef};
-	use network::Deployment;
+	use network::{Deployment, ConsensusParams, ConsensusFork, Network};
 	use hash::H256;
 	use primitives::bytes::Bytes;
-	use super::{DeploymentStateCache, ThresholdState, first_of_the_period, threshold_state};
+	use super::{Deployments, DeploymentStateCache, DeploymentStatistics, ThresholdState, first_of_the_period,
+		threshold_state, deployment_statistics};
 
 	const MINER_CONFIRMATION_WINDOW: u32 = 1000;
 	const RULE_CHANGE_ACTIVATION_THRESHOLD: u32 = 900;
@@ -483,4 +464,26 @@ mod tests {
 			assert_eq!(threshold_state(&mut DeploymentStateCache::default(), deployment, height, &headers, MINER_CONFIRMATION_WINDOW, RULE_CHANGE_ACTIVATION_THRESHOLD), state);
 		}
 	}
+
+	#[test]
+	fn test_block_version_signals_started_deployments() {
+		let consensus = ConsensusParams::new(Network::Regtest, ConsensusFork::BitcoinCore);
+		let segwit = consensus.segwit_deployment.unwrap();
+		let mut headers = DeploymentHeaderProvider::default();
+		headers.mine(150, make_test_time(1), 0x20000002);
+
+		let deployments = Deployments::new();
+		assert_eq!(deployments.threshold_state(segwit, 100, &headers, &consensus), ThresholdState::Defined);
+		assert_eq!(deployments.block_version(100, &headers, &consensus), 0x20000000);
+		assert_eq!(deployments.threshold_state(segwit, 150, &headers, &consensus), ThresholdState::Started);
+		assert_eq!(deployments.block_version(150, &headers, &consensus), 0x20000002);
+
+		assert_eq!(deployment_statistics(segwit, 150, &headers, &consensus), DeploymentStatistics {
+			period: 144,
+			threshold: 108,
+			elapsed: 6,
+			count: 6,
+			possible: true,
+		});
+	}
 }
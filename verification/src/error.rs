@@ -57,6 +57,8 @@ pub enum Error {
 	WitnessMerkleCommitmentMismatch,
 	/// SegWit: unexpected witness
 	UnexpectedWitness,
+	/// Signet: block solution is missing or doesn't satisfy the challenge
+	SignetSolution,
 	/// Database error
 	Database(DBError),
 }
@@ -75,6 +75,7 @@ mod deployments;
 mod duplex_store;
 mod error;
 mod sigops;
+mod signet;
 mod timestamp;
 mod work;
 mod work_bch;
@@ -94,6 +95,9 @@ mod accept_transaction;
 // backwards compatibility
 mod chain_verifier;
 
+// database consistency
+mod verify_database;
+
 pub use primitives::{hash, compact};
 
 pub use canon::{CanonBlock, CanonHeader, CanonTransaction};
@@ -108,11 +112,14 @@ pub use verify_header::HeaderVerifier;
 pub use verify_transaction::{TransactionVerifier, MemoryPoolTransactionVerifier};
 
 pub use chain_verifier::BackwardsCompatibleChainVerifier;
+pub use verify_database::{DatabaseVerifier, DatabaseVerificationReport, IntegrityIssue, IntegrityError,
+	DEFAULT_CHECK_LEVEL, DEFAULT_CHECK_BLOCKS, MAX_CHECK_LEVEL};
 pub use error::{Error, TransactionError};
 pub use sigops::transaction_sigops;
+pub use signet::{SignetTxs, SIGNET_HEADER, signet_solution};
 pub use timestamp::{median_timestamp, median_timestamp_inclusive};
 pub use work::{work_required, is_valid_proof_of_work, is_valid_proof_of_work_hash, block_reward_satoshi};
-pub use deployments::Deployments;
+pub use deployments::{Deployments, ThresholdState, DeploymentStatistics, deployment_statistics};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 /// Blocks verification level.
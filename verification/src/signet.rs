@@ -0,0 +1,264 @@
+//! Signet block solution (BIP325)
+//! https://github.com/bitcoin/bips/blob/master/bip-0325.mediawiki
+
+use chain::{merkle_root, BlockHeader, IndexedTransaction, OutPoint, Transaction, TransactionInput, TransactionOutput};
+use primitives::bytes::Bytes;
+use ser::{Reader, Stream};
+use script::{self, verify_script, is_witness_commitment_script, Builder, Opcode, Script, SignatureVersion,
+	TransactionSignatureChecker, VerificationFlags};
+
+/// Prefix of the witness commitment push, which holds the signet solution
+pub const SIGNET_HEADER: [u8; 4] = [0xec, 0xc7, 0xda, 0xa2];
+
+/// Virtual transactions, used to check that the block solution satisfies the signet challenge
+pub struct SignetTxs {
+	/// Transaction, paying to the challenge script. Commits to the block data.
+	pub to_spend: Transaction,
+	/// Transaction, spending `to_spend` with the block solution
+	pub to_sign: Transaction,
+}
+
+impl SignetTxs {
+	/// Build virtual transactions of the block.
+	/// Returns None if block has no witness commitment or if the solution is malformed.
+	pub fn new(header: &BlockHeader, transactions: &[IndexedTransaction], challenge: &Bytes) -> Option<Self> {
+		let coinbase = match transactions.first() {
+			Some(coinbase) => coinbase,
+			None => return None,
+		};
+		let commitment_index = match coinbase.raw.outputs.iter().rposition(|output| is_witness_commitment_script(&output.script_pubkey)) {
+			Some(commitment_index) => commitment_index,
+			None => return None,
+		};
+
+		// the solution itself is not signed => it is removed from the coinbase before computing merkle root
+		let mut modified_coinbase = coinbase.raw.clone();
+		let solution = fetch_and_clear_solution(&mut modified_coinbase.outputs[commitment_index].script_pubkey);
+		let (script_sig, script_witness) = match solution {
+			Some(solution) => {
+				let mut reader = Reader::new(&solution);
+				let script_sig: Bytes = match reader.read() {
+					Ok(script_sig) => script_sig,
+					Err(_) => return None,
+				};
+				let script_witness: Vec<Bytes> = match reader.read_list() {
+					Ok(script_witness) => script_witness,
+					Err(_) => return None,
+				};
+				if !reader.is_finished() {
+					return None;
+				}
+				(script_sig, script_witness)
+			},
+			None => (Bytes::default(), Vec::new()),
+		};
+
+		let mut hashes = vec![modified_coinbase.hash()];
+		hashes.extend(transactions[1..].iter().map(|tx| tx.hash.clone()));
+		let signet_merkle_root = merkle_root(&hashes);
+
+		let mut block_data = Stream::default();
+		block_data
+			.append(&header.version)
+			.append(&header.previous_header_hash)
+			.append(&signet_merkle_root)
+			.append(&header.time);
+
+		let to_spend = Transaction {
+			version: 0,
+			inputs: vec![TransactionInput {
+				previous_output: OutPoint::null(),
+				script_sig: Builder::default()
+					.push_opcode(Opcode::OP_0)
+					.push_data(&block_data.out())
+					.into_bytes(),
+				sequence: 0,
+				script_witness: Vec::new(),
+			}],
+			outputs: vec![TransactionOutput {
+				value: 0,
+				script_pubkey: challenge.clone(),
+			}],
+			lock_time: 0,
+		};
+
+		let to_sign = Transaction {
+			version: 0,
+			inputs: vec![TransactionInput {
+				previous_output: OutPoint {
+					hash: to_spend.hash(),
+					index: 0,
+				},
+				script_sig: script_sig,
+				sequence: 0,
+				script_witness: script_witness,
+			}],
+			outputs: vec![TransactionOutput {
+				value: 0,
+				script_pubkey: Builder::default().push_opcode(Opcode::OP_RETURN).into_bytes(),
+			}],
+			lock_time: 0,
+		};
+
+		Some(SignetTxs {
+			to_spend: to_spend,
+			to_sign: to_sign,
+		})
+	}
+
+	/// Verify that the block solution satisfies the challenge
+	pub fn verify(&self) -> Result<(), script::Error> {
+		let input = &self.to_sign.inputs[0];
+		let script_sig: Script = input.script_sig.clone().into();
+		let challenge: Script = self.to_spend.outputs[0].script_pubkey.clone().into();
+		let flags = VerificationFlags::default()
+			.verify_p2sh(true)
+			.verify_witness(true)
+			.verify_dersig(true)
+			.verify_nulldummy(true);
+		let checker = TransactionSignatureChecker {
+			signer: self.to_sign.clone().into(),
+			input_index: 0,
+			input_amount: 0,
+		};
+
+		verify_script(&script_sig, &challenge, &input.script_witness, &flags, &checker, SignatureVersion::Base)
+	}
+}
+
+/// Serialize block solution: script sig, followed by the witness stack
+pub fn signet_solution(script_sig: &Bytes, script_witness: &[Bytes]) -> Bytes {
+	let mut stream = Stream::default();
+	stream
+		.append(script_sig)
+		.append_list::<Bytes, Bytes>(script_witness);
+	stream.out()
+}
+
+/// Find the first push, starting with `SIGNET_HEADER`, in the witness commitment script.
+/// Returns data after the header and leaves only the header in the script.
+fn fetch_and_clear_solution(commitment_script: &mut Bytes) -> Option<Bytes> {
+	let script: Script = commitment_script.clone().into();
+	let mut replacement = Builder::default();
+	let mut solution = None;
+	for instruction in script.iter() {
+		let instruction = match instruction {
+			Ok(instruction) => instruction,
+			Err(_) => break,
+		};
+
+		replacement = match instruction.data {
+			Some(data) if !data.is_empty() => {
+				// push only counts if it has both the header and the solution
+				if solution.is_none() && data.len() > SIGNET_HEADER.len() && data[..SIGNET_HEADER.len()] == SIGNET_HEADER {
+					solution = Some(data[SIGNET_HEADER.len()..].to_vec().into());
+					replacement.push_data(&SIGNET_HEADER)
+				} else {
+					replacement.push_data(data)
+				}
+			},
+			_ => replacement.push_opcode(instruction.opcode),
+		};
+	}
+
+	if solution.is_some() {
+		*commitment_script = replacement.into_bytes();
+	}
+	solution
+}
+
+#[cfg(test)]
+mod tests {
+	use chain::{BlockHeader, IndexedTransaction, Transaction, TransactionInput, TransactionOutput};
+	use primitives::bytes::Bytes;
+	use super::{SignetTxs, SIGNET_HEADER, signet_solution, fetch_and_clear_solution};
+
+	const COMMITMENT: &'static str = "6a24aa21a9ede2f61c3f71d1defd3fa999dfa36953755c690689799962b48bebd836974e8cf9";
+
+	fn header() -> BlockHeader {
+		BlockHeader {
+			version: 0x20000000,
+			previous_header_hash: 1u8.into(),
+			merkle_root_hash: Default::default(),
+			time: 1598918400,
+			bits: 0x1e0377ae.into(),
+			nonce: 0,
+		}
+	}
+
+	fn coinbase(commitment: Option<Bytes>) -> IndexedTransaction {
+		let mut outputs = vec![TransactionOutput {
+			value: 50_0000_0000,
+			script_pubkey: "51".into(),
+		}];
+		outputs.extend(commitment.map(|commitment| TransactionOutput {
+			value: 0,
+			script_pubkey: commitment,
+		}));
+
+		Transaction {
+			version: 1,
+			inputs: vec![TransactionInput::coinbase("0101".into())],
+			outputs: outputs,
+			lock_time: 0,
+		}.into()
+	}
+
+	fn commitment_with_solution(solution: &Bytes) -> Bytes {
+		let mut data = SIGNET_HEADER.to_vec();
+		data.extend_from_slice(solution);
+		let mut script: Vec<u8> = Bytes::from(COMMITMENT).into();
+		script.push(data.len() as u8);
+		script.extend(data);
+		script.into()
+	}
+
+	#[test]
+	fn test_fetch_and_clear_solution() {
+		let solution = signet_solution(&"0151".into(), &[]);
+		let mut script = commitment_with_solution(&solution);
+		assert_eq!(fetch_and_clear_solution(&mut script), Some(solution));
+		let mut expected: Vec<u8> = Bytes::from(COMMITMENT).into();
+		expected.push(SIGNET_HEADER.len() as u8);
+		expected.extend_from_slice(&SIGNET_HEADER);
+		assert_eq!(script, expected.into());
+
+		let mut script: Bytes = COMMITMENT.into();
+		assert_eq!(fetch_and_clear_solution(&mut script), None);
+		assert_eq!(script, COMMITMENT.into());
+	}
+
+	#[test]
+	fn test_signet_txs_require_witness_commitment() {
+		assert!(SignetTxs::new(&header(), &[coinbase(None)], &"51".into()).is_none());
+	}
+
+	#[test]
+	fn test_signet_txs_reject_malformed_solution() {
+		let coinbase = coinbase(Some(commitment_with_solution(&"ff".into())));
+		assert!(SignetTxs::new(&header(), &[coinbase], &"51".into()).is_none());
+	}
+
+	#[test]
+	fn test_signet_trivial_challenge() {
+		let transactions = vec![coinbase(Some(COMMITMENT.into()))];
+		// OP_TRUE challenge is satisfied by empty solution
+		assert!(SignetTxs::new(&header(), &transactions, &"51".into()).unwrap().verify().is_ok());
+		// OP_FALSE challenge is never satisfied
+		assert!(SignetTxs::new(&header(), &transactions, &"00".into()).unwrap().verify().is_err());
+		// OP_EQUAL challenge is satisfied by solution, pushing two equal items
+		let transactions = vec![coinbase(Some(commitment_with_solution(&signet_solution(&"01020102".into(), &[]))))];
+		assert!(SignetTxs::new(&header(), &transactions, &"87".into()).unwrap().verify().is_ok());
+	}
+
+	#[test]
+	fn test_signet_solution_is_not_signed() {
+		let challenge: Bytes = "87".into();
+		let first = vec![coinbase(Some(commitment_with_solution(&signet_solution(&"01020102".into(), &[]))))];
+		let second = vec![coinbase(Some(commitment_with_solution(&signet_solution(&"01030103".into(), &[]))))];
+		let first = SignetTxs::new(&header(), &first, &challenge).unwrap();
+		let second = SignetTxs::new(&header(), &second, &challenge).unwrap();
+		assert_eq!(first.to_spend, second.to_spend);
+		assert!(first.to_sign.inputs[0].script_sig != second.to_sign.inputs[0].script_sig);
+	}
+}
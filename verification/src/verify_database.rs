@@ -0,0 +1,404 @@
+//! Consistency checks of the stored chain, similar to the `verifychain` of Bitcoin Core.
+//!
+//! Check levels (every level includes all checks of the lower levels):
+//! 0. headers of the canon chain are linked and have valid proof of work
+//! 1. block hashes and block numbers of the canon chain are the same mapping in both directions
+//! 2. stored transactions of the canon blocks match merkle roots of their headers
+//! 3. spent bits of the transactions meta match the unspent outputs and the spends of the checked blocks
+//! 4. checked blocks are disconnected and then connected back through the chain verifier
+
+use std::{cmp, fmt};
+use hash::H256;
+use chain::{IndexedBlock, OutPoint};
+use storage::SharedStore;
+use network::ConsensusParams;
+use error::Error;
+use chain_verifier::BackwardsCompatibleChainVerifier;
+use work::is_valid_proof_of_work;
+use VerificationLevel;
+
+/// Check level, used when level is not specified
+pub const DEFAULT_CHECK_LEVEL: u32 = 3;
+/// Number of the checked best blocks, used when number is not specified
+pub const DEFAULT_CHECK_BLOCKS: u32 = 6;
+/// Highest check level. Higher levels are treated as this one
+pub const MAX_CHECK_LEVEL: u32 = 4;
+
+/// Inconsistency, found in the database
+#[derive(Debug, PartialEq)]
+pub enum IntegrityError {
+	/// There's no canon block with this number
+	MissingBlockHash,
+	/// Header of the canon block is not stored
+	MissingHeader,
+	/// Hash of the stored header differs from the hash it is stored by
+	HeaderHashMismatch,
+	/// Header doesn't reference previous canon block as parent
+	BrokenHeaderLink,
+	/// Header hash doesn't satisfy its nBits
+	InvalidProofOfWork,
+	/// Number, stored for the canon block hash, differs from the block number
+	BlockNumberMismatch(Option<u32>),
+	/// Number is stored for the block, which is not in the canon chain
+	StrayBlockNumber(H256),
+	/// Transactions of the canon block are not stored, though the block is neither pruned, nor below UTXO snapshot
+	MissingBlockTransactions,
+	/// Merkle root of the stored transactions differs from the one in header
+	MerkleRootMismatch,
+	/// Meta of the canon transaction is not stored
+	MissingTransactionMeta(H256),
+	/// Meta of the canon transaction doesn't match the transaction
+	InvalidTransactionMeta(H256),
+	/// Output is marked spent, but is in the unspent outputs set, or vice versa
+	SpentOutputMismatch(OutPoint),
+	/// Output, spent by the canon block, is not marked spent
+	UnspentInput(OutPoint),
+	/// Block has failed verification, when connected to the chain again
+	Replay(Error),
+}
+
+impl fmt::Display for IntegrityError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			IntegrityError::MissingBlockHash => f.write_str("canon block hash is missing"),
+			IntegrityError::MissingHeader => f.write_str("block header is missing"),
+			IntegrityError::HeaderHashMismatch => f.write_str("hash of the stored header doesn't match the block hash"),
+			IntegrityError::BrokenHeaderLink => f.write_str("header doesn't reference the previous canon block"),
+			IntegrityError::InvalidProofOfWork => f.write_str("invalid proof of work"),
+			IntegrityError::BlockNumberMismatch(Some(number)) => write!(f, "block hash is mapped to block number {}", number),
+			IntegrityError::BlockNumberMismatch(None) => f.write_str("block hash is not mapped to block number"),
+			IntegrityError::StrayBlockNumber(ref hash) => write!(f, "side chain block {} is mapped to this number", hash.to_reversed_str()),
+			IntegrityError::MissingBlockTransactions => f.write_str("block transactions are missing"),
+			IntegrityError::MerkleRootMismatch => f.write_str("block transactions don't match merkle root"),
+			IntegrityError::MissingTransactionMeta(ref hash) => write!(f, "meta of transaction {} is missing", hash.to_reversed_str()),
+			IntegrityError::InvalidTransactionMeta(ref hash) => write!(f, "meta of transaction {} doesn't match the transaction", hash.to_reversed_str()),
+			IntegrityError::SpentOutputMismatch(ref outpoint) => write!(f, "spent bit of output {}:{} doesn't match unspent outputs set", outpoint.hash.to_reversed_str(), outpoint.index),
+			IntegrityError::UnspentInput(ref outpoint) => write!(f, "spent output {}:{} is not marked spent", outpoint.hash.to_reversed_str(), outpoint.index),
+			IntegrityError::Replay(ref err) => write!(f, "block verification has failed: {:?}", err),
+		}
+	}
+}
+
+/// Inconsistency of the single block
+#[derive(Debug, PartialEq)]
+pub struct IntegrityIssue {
+	/// Number of the canon block
+	pub block_number: u32,
+	/// Inconsistency
+	pub error: IntegrityError,
+}
+
+/// Result of the database verification
+#[derive(Debug, PartialEq)]
+pub struct DatabaseVerificationReport {
+	/// Level of performed checks
+	pub check_level: u32,
+	/// Number of the first checked block
+	pub first_block: u32,
+	/// Number of the last checked block (best block at the moment of check)
+	pub last_block: u32,
+	/// Found inconsistencies, ordered by block number within every check level
+	pub issues: Vec<IntegrityIssue>,
+}
+
+impl DatabaseVerificationReport {
+	/// Returns true if no inconsistencies were found
+	pub fn is_ok(&self) -> bool {
+		self.issues.is_empty()
+	}
+
+	/// Number of the first inconsistent block. The database is repaired by rolling back to its parent
+	pub fn first_invalid_block(&self) -> Option<u32> {
+		self.issues.iter().map(|issue| issue.block_number).min()
+	}
+}
+
+/// Checks consistency of the canon chain, stored in the database
+pub struct DatabaseVerifier {
+	store: SharedStore,
+	consensus: ConsensusParams,
+}
+
+impl DatabaseVerifier {
+	pub fn new(store: SharedStore, consensus: ConsensusParams) -> Self {
+		DatabaseVerifier {
+			store: store,
+			consensus: consensus,
+		}
+	}
+
+	/// Checks `blocks` best blocks (or all blocks, if zero) at given level
+	pub fn verify(&self, check_level: u32, blocks: u32) -> DatabaseVerificationReport {
+		let check_level = cmp::min(check_level, MAX_CHECK_LEVEL);
+		let last_block = self.store.best_block().number;
+		let first_block = match blocks {
+			0 => 0,
+			blocks => last_block.saturating_sub(blocks - 1),
+		};
+		info!(target: "verification", "Verifying blocks {}..{} at check level {}", first_block, last_block, check_level);
+
+		let mut report = DatabaseVerificationReport {
+			check_level: check_level,
+			first_block: first_block,
+			last_block: last_block,
+			issues: Vec::new(),
+		};
+
+		let mut previous_hash = match first_block {
+			0 => None,
+			first_block => self.store.block_hash(first_block - 1),
+		};
+		for block_number in first_block..last_block + 1 {
+			let errors = self.verify_block(check_level, block_number, previous_hash.as_ref());
+			report.issues.extend(errors.into_iter().map(|error| IntegrityIssue {
+				block_number: block_number,
+				error: error,
+			}));
+			previous_hash = self.store.block_hash(block_number);
+		}
+
+		if check_level >= 1 {
+			self.verify_side_chains(first_block, &mut report);
+		}
+
+		// blocks are only replayed over the consistent database
+		if check_level >= 4 && report.is_ok() {
+			let replay_from = cmp::max(first_block, self.first_block_with_transactions());
+			info!(target: "verification", "Replaying blocks {}..{}", replay_from, last_block);
+			let verifier = BackwardsCompatibleChainVerifier::new(self.store.clone(), self.consensus.clone());
+			if let Err((block_number, err)) = verifier.replay_canon_blocks(VerificationLevel::Full, replay_from) {
+				report.issues.push(IntegrityIssue {
+					block_number: block_number,
+					error: IntegrityError::Replay(err),
+				});
+			}
+		}
+
+		info!(target: "verification", "Database verification has finished with {} issues", report.issues.len());
+		report
+	}
+
+	/// Number of the first block, which transactions must be stored
+	fn first_block_with_transactions(&self) -> u32 {
+		let pruned = self.store.pruned_height().map(|number| number + 1).unwrap_or(0);
+		let snapshot = self.store.snapshot_base().map(|base| base.block_number + 1).unwrap_or(0);
+		cmp::max(pruned, snapshot)
+	}
+
+	fn verify_block(&self, check_level: u32, block_number: u32, previous_hash: Option<&H256>) -> Vec<IntegrityError> {
+		let mut errors = Vec::new();
+		let hash = match self.store.block_hash(block_number) {
+			Some(hash) => hash,
+			None => {
+				errors.push(IntegrityError::MissingBlockHash);
+				return errors;
+			},
+		};
+
+		let header = match self.store.block_header(hash.clone().into()) {
+			Some(header) => header,
+			None => {
+				errors.push(IntegrityError::MissingHeader);
+				return errors;
+			},
+		};
+
+		if header.hash() != hash {
+			errors.push(IntegrityError::HeaderHashMismatch);
+		}
+		let is_linked = match previous_hash {
+			Some(previous_hash) => header.previous_header_hash == *previous_hash,
+			None => block_number == 0 && header.previous_header_hash.is_zero(),
+		};
+		if !is_linked {
+			errors.push(IntegrityError::BrokenHeaderLink);
+		}
+		if !is_valid_proof_of_work(self.consensus.pow_limit.into(), header.bits, &hash) {
+			errors.push(IntegrityError::InvalidProofOfWork);
+		}
+
+		if check_level < 1 {
+			return errors;
+		}
+
+		match self.store.block_number(&hash) {
+			Some(number) if number == block_number => (),
+			number => errors.push(IntegrityError::BlockNumberMismatch(number)),
+		}
+
+		if check_level < 2 {
+			return errors;
+		}
+
+		let block = match self.store.indexed_block(hash.into()) {
+			Some(block) => block,
+			None => {
+				if block_number >= self.first_block_with_transactions() {
+					errors.push(IntegrityError::MissingBlockTransactions);
+				}
+				return errors;
+			},
+		};
+
+		if block.merkle_root() != block.header.raw.merkle_root_hash {
+			errors.push(IntegrityError::MerkleRootMismatch);
+			return errors;
+		}
+
+		if check_level < 3 {
+			return errors;
+		}
+
+		self.verify_spends(&block, block_number, &mut errors);
+		errors
+	}
+
+	fn verify_spends(&self, block: &IndexedBlock, block_number: u32, errors: &mut Vec<IntegrityError>) {
+		for (tx_index, tx) in block.transactions.iter().enumerate() {
+			let meta = match self.store.transaction_meta(&tx.hash) {
+				Some(meta) => meta,
+				None => {
+					errors.push(IntegrityError::MissingTransactionMeta(tx.hash.clone()));
+					continue;
+				},
+			};
+
+			// meta of the duplicate transaction (bip30) is overwritten by the later one
+			if meta.height() != block_number && self.store.block_transaction_hashes(meta.height().into()).contains(&tx.hash) {
+				continue;
+			}
+
+			if meta.height() != block_number || meta.is_coinbase() != (tx_index == 0) {
+				errors.push(IntegrityError::InvalidTransactionMeta(tx.hash.clone()));
+				continue;
+			}
+
+			for output_index in 0..tx.raw.outputs.len() {
+				let outpoint = OutPoint {
+					hash: tx.hash.clone(),
+					index: output_index as u32,
+				};
+				let is_unspent = self.store.transaction_output(&outpoint, usize::max_value()).is_some();
+				if meta.is_spent(output_index) != Some(!is_unspent) {
+					errors.push(IntegrityError::SpentOutputMismatch(outpoint));
+				}
+			}
+
+			if tx_index == 0 {
+				continue;
+			}
+
+			for input in &tx.raw.inputs {
+				let outpoint = &input.previous_output;
+				let is_marked_spent = self.store.transaction_meta(&outpoint.hash)
+					.and_then(|meta| meta.is_spent(outpoint.index as usize))
+					.unwrap_or(false);
+				let is_unspent = self.store.transaction_output(outpoint, usize::max_value()).is_some();
+				if !is_marked_spent || is_unspent {
+					errors.push(IntegrityError::UnspentInput(outpoint.clone()));
+				}
+			}
+		}
+	}
+
+	/// Checks that side chain blocks of the known branches aren't mapped to block numbers
+	fn verify_side_chains(&self, first_block: u32, report: &mut DatabaseVerificationReport) {
+		for tip in self.store.chain_tips() {
+			if tip.number < first_block {
+				continue;
+			}
+
+			let mut hash = tip.hash;
+			for _ in 0..tip.branch_len {
+				let header = match self.store.block_header(hash.clone().into()) {
+					Some(header) => header,
+					// headers-only branch
+					None => break,
+				};
+
+				match self.store.block_number(&hash) {
+					Some(number) if self.store.block_hash(number).as_ref() == Some(&hash) => break,
+					Some(number) => report.issues.push(IntegrityIssue {
+						block_number: number,
+						error: IntegrityError::StrayBlockNumber(hash.clone()),
+					}),
+					None => (),
+				}
+
+				hash = header.previous_header_hash;
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	extern crate test_data;
+
+	use std::sync::Arc;
+	use chain::IndexedBlock;
+	use db::BlockChainDatabase;
+	use db::kv::{SharedMemoryDatabase, KeyValueDatabase, Transaction as DBTransaction, Key};
+	use network::{Network, ConsensusParams, ConsensusFork};
+	use super::{DatabaseVerifier, IntegrityError, IntegrityIssue, MAX_CHECK_LEVEL};
+
+	fn test_database() -> (SharedMemoryDatabase, Vec<IndexedBlock>) {
+		let shared_database = SharedMemoryDatabase::default();
+		let blocks: Vec<IndexedBlock> = vec![
+			test_data::block_h0().into(),
+			test_data::block_h1().into(),
+			test_data::block_h2().into(),
+		];
+
+		let store = BlockChainDatabase::open(shared_database.clone()).unwrap();
+		for block in &blocks {
+			store.insert(block.clone()).unwrap();
+			store.canonize(block.hash()).unwrap();
+		}
+
+		(shared_database, blocks)
+	}
+
+	fn verifier(shared_database: SharedMemoryDatabase) -> DatabaseVerifier {
+		let store = Arc::new(BlockChainDatabase::open(shared_database).unwrap());
+		DatabaseVerifier::new(store, ConsensusParams::new(Network::Unitest, ConsensusFork::BitcoinCore))
+	}
+
+	#[test]
+	fn consistent_database_is_verified() {
+		let (shared_database, _) = test_database();
+		let report = verifier(shared_database).verify(MAX_CHECK_LEVEL + 1, 0);
+		assert_eq!(report.check_level, MAX_CHECK_LEVEL);
+		assert_eq!(report.first_block, 0);
+		assert_eq!(report.last_block, 2);
+		assert!(report.is_ok());
+		assert_eq!(report.first_invalid_block(), None);
+	}
+
+	#[test]
+	fn inconsistent_database_is_reported() {
+		let (shared_database, blocks) = test_database();
+		let mut update = DBTransaction::new();
+		update.delete(Key::BlockNumber(blocks[1].hash().clone()));
+		update.delete(Key::TransactionMeta(blocks[2].transactions[0].hash.clone()));
+		shared_database.write(update).unwrap();
+
+		let verifier = verifier(shared_database);
+		// block numbers are not checked at level 0
+		assert!(verifier.verify(0, 0).is_ok());
+
+		let report = verifier.verify(3, 2);
+		assert_eq!(report.first_block, 1);
+		assert_eq!(report.issues, vec![
+			IntegrityIssue {
+				block_number: 1,
+				error: IntegrityError::BlockNumberMismatch(None),
+			},
+			IntegrityIssue {
+				block_number: 2,
+				error: IntegrityError::MissingTransactionMeta(blocks[2].transactions[0].hash.clone()),
+			},
+		]);
+		assert_eq!(report.first_invalid_block(), Some(1));
+	}
+}
@@ -3,18 +3,11 @@ use primitives::compact::Compact;
 use primitives::hash::H256;
 use primitives::U256;
 use chain::{IndexedBlockHeader, BlockHeader};
-use network::{Network, ConsensusParams, ConsensusFork};
+use network::{ConsensusParams, ConsensusFork};
 use storage::{BlockHeaderProvider, BlockRef};
 use work_bch::work_required_bitcoin_cash;
 
-use constants::{
-	DOUBLE_SPACING_SECONDS, TARGET_TIMESPAN_SECONDS,
-	MIN_TIMESPAN, MAX_TIMESPAN, RETARGETING_INTERVAL
-};
-
-pub fn is_retarget_height(height: u32) -> bool {
-	height % RETARGETING_INTERVAL == 0
-}
+use constants::{DOUBLE_SPACING_SECONDS, TARGET_SPACING_SECONDS, RETARGETING_FACTOR};
 
 fn range_constrain(value: i64, min: i64, max: i64) -> i64 {
 	cmp::min(cmp::max(value, min), max)
@@ -49,16 +42,18 @@ pub fn is_valid_proof_of_work(max_work_bits: Compact, bits: Compact, hash: &H256
 }
 
 /// Returns constrained number of seconds since last retarget
-pub fn retarget_timespan(retarget_timestamp: u32, last_timestamp: u32) -> u32 {
+pub fn retarget_timespan(retarget_timestamp: u32, last_timestamp: u32, target_timespan: u32) -> u32 {
 	// subtract unsigned 32 bit numbers in signed 64 bit space in
 	// order to prevent underflow before applying the range constraint.
 	let timespan = last_timestamp as i64 - retarget_timestamp as i64;
-	range_constrain(timespan, MIN_TIMESPAN as i64, MAX_TIMESPAN as i64) as u32
+	let min_timespan = target_timespan / RETARGETING_FACTOR;
+	let max_timespan = target_timespan as i64 * RETARGETING_FACTOR as i64;
+	range_constrain(timespan, min_timespan as i64, max_timespan) as u32
 }
 
 /// Returns work required for given header
 pub fn work_required(parent_hash: H256, time: u32, height: u32, store: &BlockHeaderProvider, consensus: &ConsensusParams) -> Compact {
-	let max_bits = consensus.network.max_bits().into();
+	let max_bits = consensus.pow_limit.into();
 	if height == 0 {
 		return max_bits;
 	}
@@ -74,32 +69,37 @@ pub fn work_required(parent_hash: H256, time: u32, height: u32, store: &BlockHea
 		_ => (),
 	}
 
-	if is_retarget_height(height) {
-		return work_required_retarget(parent_header, height, store, max_bits);
+	if consensus.is_retarget_height(height) {
+		return work_required_retarget(parent_header, height, store, consensus, max_bits);
 	}
 
-	if consensus.network == Network::Testnet {
-		return work_required_testnet(parent_hash, time, height, store, Network::Testnet)
+	if consensus.pow_allow_min_difficulty_blocks {
+		return work_required_testnet(parent_hash, time, height, store, consensus)
 	}
 
 	parent_header.bits
 }
 
-pub fn work_required_testnet(parent_hash: H256, time: u32, height: u32, store: &BlockHeaderProvider, network: Network) -> Compact {
+/// Number of seconds without blocks, after which min-difficulty block is allowed
+fn min_difficulty_time_gap(consensus: &ConsensusParams) -> u32 {
+	(consensus.pow_target_spacing as u64 * DOUBLE_SPACING_SECONDS as u64 / TARGET_SPACING_SECONDS as u64) as u32
+}
+
+pub fn work_required_testnet(parent_hash: H256, time: u32, height: u32, store: &BlockHeaderProvider, consensus: &ConsensusParams) -> Compact {
 	assert!(height != 0, "cannot calculate required work for genesis block");
 
 	let mut bits = Vec::new();
 	let mut block_ref: BlockRef = parent_hash.into();
 
 	let parent_header = store.block_header(block_ref.clone()).expect("height != 0; qed");
-	let max_time_gap = parent_header.time + DOUBLE_SPACING_SECONDS;
-	let max_bits = network.max_bits().into();
+	let max_time_gap = parent_header.time + min_difficulty_time_gap(consensus);
+	let max_bits = consensus.pow_limit.into();
 	if time > max_time_gap {
 		return max_bits;
 	}
 
 	// TODO: optimize it, so it does not make 2016!!! redundant queries each time
-	for _ in 0..RETARGETING_INTERVAL {
+	for _ in 0..consensus.retargeting_interval() {
 		let previous_header = match store.block_header(block_ref) {
 			Some(h) => h,
 			None => { break; }
@@ -109,7 +109,7 @@ pub fn work_required_testnet(parent_hash: H256, time: u32, height: u32, store: &
 	}
 
 	for (index, bit) in bits.into_iter().enumerate() {
-		if bit != max_bits || is_retarget_height(height - index as u32 - 1) {
+		if bit != max_bits || consensus.is_retarget_height(height - index as u32 - 1) {
 			return bit;
 		}
 	}
@@ -118,8 +118,12 @@ pub fn work_required_testnet(parent_hash: H256, time: u32, height: u32, store: &
 }
 
 /// Algorithm used for retargeting work every 2 weeks
-pub fn work_required_retarget(parent_header: BlockHeader, height: u32, store: &BlockHeaderProvider, max_work_bits: Compact) -> Compact {
-	let retarget_ref = (height - RETARGETING_INTERVAL).into();
+pub fn work_required_retarget(parent_header: BlockHeader, height: u32, store: &BlockHeaderProvider, consensus: &ConsensusParams, max_work_bits: Compact) -> Compact {
+	if consensus.pow_no_retargeting {
+		return parent_header.bits;
+	}
+
+	let retarget_ref = (height - consensus.retargeting_interval()).into();
 	let retarget_header = store.block_header(retarget_ref).expect("self.height != 0 && self.height % RETARGETING_INTERVAL == 0; qed");
 
 	// timestamp of block(height - RETARGETING_INTERVAL)
@@ -132,8 +136,8 @@ pub fn work_required_retarget(parent_header: BlockHeader, height: u32, store: &B
 	let mut retarget: U256 = last_bits.into();
 	let maximum: U256 = max_work_bits.into();
 
-	retarget = retarget * U256::from(retarget_timespan(retarget_timestamp, last_timestamp));
-	retarget = retarget / U256::from(TARGET_TIMESPAN_SECONDS);
+	retarget = retarget * U256::from(retarget_timespan(retarget_timestamp, last_timestamp, consensus.pow_target_timespan));
+	retarget = retarget / U256::from(consensus.pow_target_timespan);
 
 	if retarget > maximum {
 		max_work_bits
@@ -2,10 +2,10 @@ use primitives::compact::Compact;
 use primitives::hash::H256;
 use primitives::U256;
 use chain::{IndexedBlockHeader, BlockHeader};
-use network::{Network, ConsensusParams, BitcoinCashConsensusParams};
+use network::{ConsensusParams, BitcoinCashConsensusParams};
 use storage::BlockHeaderProvider;
 use timestamp::median_timestamp_inclusive;
-use work::{is_retarget_height, work_required_testnet, work_required_retarget};
+use work::{work_required_testnet, work_required_retarget};
 
 use constants::{
 	DOUBLE_SPACING_SECONDS, TARGET_SPACING_SECONDS, RETARGETING_INTERVAL
@@ -19,12 +19,12 @@ pub fn work_required_bitcoin_cash(parent_header: IndexedBlockHeader, time: u32,
 		return work_required_bitcoin_cash_adjusted(parent_header, time, height, store, consensus);
 	}
 
-	if is_retarget_height(height) {
-		return work_required_retarget(parent_header.raw, height, store, max_bits);
+	if consensus.is_retarget_height(height) {
+		return work_required_retarget(parent_header.raw, height, store, consensus, max_bits);
 	}
 
-	if consensus.network == Network::Testnet {
-		return work_required_testnet(parent_header.hash, time, height, store, Network::Testnet)
+	if consensus.pow_allow_min_difficulty_blocks {
+		return work_required_testnet(parent_header.hash, time, height, store, consensus)
 	}
 
 	if parent_header.raw.bits == max_bits {
@@ -140,8 +140,8 @@ fn work_required_bitcoin_cash_adjusted(parent_header: IndexedBlockHeader, time:
 	// Special difficulty rule for testnet:
 	// If the new block's timestamp is more than 2 * 10 minutes then allow
 	// mining of a min-difficulty block.
-	let max_bits = consensus.network.max_bits();
-	if consensus.network == Network::Testnet {
+	let max_bits = consensus.pow_limit;
+	if consensus.pow_allow_min_difficulty_blocks {
 		let max_time_gap = parent_header.raw.time + DOUBLE_SPACING_SECONDS;
 		if time > max_time_gap {
 			return max_bits.into();
@@ -163,7 +163,7 @@ fn work_required_bitcoin_cash_adjusted(parent_header: IndexedBlockHeader, time:
 
 	// Compute the target based on time and work done during the interval.
 	let next_target = compute_target(first_header, last_header, store);
-	let max_bits = consensus.network.max_bits();
+	let max_bits = consensus.pow_limit;
 	if next_target > max_bits {
 		return max_bits.into();
 	}